@@ -32,6 +32,11 @@
 //! autonomy = 60
 //! security_keywords = ["password", "token", "secret"]
 //! log_resolutions = true
+//!
+//! [[gates.steps]]
+//! name = "quick"
+//! kind = "moon"
+//! run = ":quick"
 //! ```
 
 use std::{collections::HashMap, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
@@ -49,6 +54,10 @@ pub use conflict_resolution::{
     ConflictMode, ConflictResolutionConfig, PartialConflictResolutionConfig,
 };
 
+// Quality gate pipeline configuration
+pub mod gates;
+pub use gates::{GateDefinition, GateKind, GatesConfig, PartialGatesConfig};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum RecoveryPolicy {
@@ -206,6 +215,7 @@ pub struct Config {
     pub session: SessionConfig,
     pub recovery: RecoveryConfig,
     pub conflict_resolution: ConflictResolutionConfig,
+    pub gates: GatesConfig,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -276,6 +286,7 @@ impl Default for Config {
             session: SessionConfig::default(),
             recovery: RecoveryConfig::default(),
             conflict_resolution: ConflictResolutionConfig::default(),
+            gates: GatesConfig::default(),
         }
     }
 }
@@ -360,6 +371,8 @@ pub struct PartialConfig {
     pub recovery: Option<PartialRecoveryConfig>,
     #[serde(default)]
     pub conflict_resolution: Option<PartialConflictResolutionConfig>,
+    #[serde(default)]
+    pub gates: Option<PartialGatesConfig>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -591,6 +604,7 @@ const VALID_KEYS: &[&str] = &[
     "session",
     "recovery",
     "conflict_resolution",
+    "gates",
     "watch.enabled",
     "watch.debounce_ms",
     "watch.paths",
@@ -609,6 +623,9 @@ const VALID_KEYS: &[&str] = &[
     "conflict_resolution.autonomy",
     "conflict_resolution.security_keywords",
     "conflict_resolution.log_resolutions",
+    "gates.fail_fast",
+    "gates.timeout_secs",
+    "gates.steps",
 ];
 
 /// Validate a configuration key
@@ -653,6 +670,7 @@ pub fn validate_key(key: &str) -> Result<()> {
         error_msg.push_str("  session.auto_commit, session.commit_prefix, session.max_sessions\n");
         error_msg.push_str("  recovery.policy, recovery.log_recovered, recovery.auto_recover_corrupted_wal, recovery.delete_corrupted_database\n");
         error_msg.push_str("  conflict_resolution.mode, conflict_resolution.autonomy, conflict_resolution.security_keywords, conflict_resolution.log_resolutions\n");
+        error_msg.push_str("  gates.fail_fast, gates.timeout_secs, gates.steps\n");
         error_msg.push_str("\nUse 'isolate config' to see current configuration.");
 
         Err(Error::ValidationError {
@@ -1087,6 +1105,9 @@ impl Config {
         if let Some(conflict_resolution) = partial.conflict_resolution {
             self.conflict_resolution.merge_partial(conflict_resolution);
         }
+        if let Some(gates) = partial.gates {
+            self.gates.merge_partial(gates);
+        }
     }
 
     /// Apply environment variable overrides
@@ -1196,6 +1217,9 @@ impl Config {
         // Validate conflict resolution config
         self.conflict_resolution.validate()?;
 
+        // Validate the gate pipeline
+        self.gates.validate()?;
+
         Ok(())
     }
 
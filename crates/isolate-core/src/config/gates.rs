@@ -0,0 +1,299 @@
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![forbid(unsafe_code)]
+
+//! Quality gate configuration
+//!
+//! The `[gates]` section lists the checks `isolate done`, `isolate submit`
+//! and the merge queue run before anything lands, in order:
+//!
+//! ```toml
+//! [gates]
+//! fail_fast = true
+//! timeout_secs = 1800
+//!
+//! [[gates.steps]]
+//! name = "quick"
+//! kind = "moon"
+//! run = ":quick"
+//!
+//! [[gates.steps]]
+//! name = "unit"
+//! kind = "command"
+//! run = "cargo test --workspace"
+//! timeout_secs = 600
+//! ```
+//!
+//! A `moon` gate runs `moon run <task>`; a `command` gate runs its command
+//! with `sh -c`. Each gate runs in the session's workspace and is killed once
+//! its timeout (or the section-wide `timeout_secs`) passes.
+
+use std::{collections::HashSet, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{moon_gates::MoonGate, Error, Result};
+
+// ═══════════════════════════════════════════════════════════════════════════
+// GATE DEFINITIONS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// How a gate is run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GateKind {
+    /// `moon run <task>`, with moon's output parsed for the summary
+    Moon,
+    /// A shell command run with `sh -c`
+    Command,
+}
+
+/// One gate of the pipeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GateDefinition {
+    /// Name results are reported under
+    pub name: String,
+    /// How `run` is executed
+    pub kind: GateKind,
+    /// Moon task or shell command
+    pub run: String,
+    /// Overrides the section-wide timeout for this gate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+impl GateDefinition {
+    /// A gate running one of the standard moon tasks
+    #[must_use]
+    pub fn moon(gate: MoonGate) -> Self {
+        Self {
+            name: gate.name().to_string(),
+            kind: GateKind::Moon,
+            run: gate.as_task().to_string(),
+            timeout_secs: None,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// GATES CONFIG
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Configuration of the quality gate pipeline
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GatesConfig {
+    /// Stop at the first failing gate instead of running the rest
+    pub fail_fast: bool,
+
+    /// Timeout for gates that do not set their own
+    pub timeout_secs: u64,
+
+    /// Gates to run, in order. Empty means `done` and `submit` land ungated.
+    pub steps: Vec<GateDefinition>,
+}
+
+impl GatesConfig {
+    /// The `:quick` then `:test` moon pipeline
+    ///
+    /// The merge queue gates with this when no steps are configured.
+    #[must_use]
+    pub fn moon() -> Self {
+        Self {
+            steps: vec![
+                GateDefinition::moon(MoonGate::Quick),
+                GateDefinition::moon(MoonGate::Test),
+            ],
+            ..Self::default()
+        }
+    }
+
+    /// This pipeline, or the moon pipeline if no steps are configured
+    #[must_use]
+    pub fn or_moon(self) -> Self {
+        if self.steps.is_empty() {
+            Self {
+                steps: Self::moon().steps,
+                ..self
+            }
+        } else {
+            self
+        }
+    }
+
+    /// How long `gate` may run before it is killed
+    #[must_use]
+    pub fn timeout_for(&self, gate: &GateDefinition) -> Duration {
+        Duration::from_secs(gate.timeout_secs.unwrap_or(self.timeout_secs))
+    }
+
+    /// Validate configuration values
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::ValidationError` if:
+    /// - a timeout is zero
+    /// - a gate has an empty name or `run`
+    /// - two gates share a name
+    pub fn validate(&self) -> Result<()> {
+        if self.timeout_secs == 0 {
+            return Err(invalid("gates.timeout_secs", "0", "timeout_secs > 0"));
+        }
+
+        let mut names = HashSet::new();
+        for gate in &self.steps {
+            if gate.name.trim().is_empty() {
+                return Err(invalid("gates.steps.name", "", "name is not empty"));
+            }
+            if !names.insert(gate.name.as_str()) {
+                return Err(invalid("gates.steps.name", &gate.name, "names are unique"));
+            }
+            if gate.run.trim().is_empty() {
+                return Err(invalid("gates.steps.run", "", "run is not empty"));
+            }
+            if gate.timeout_secs == Some(0) {
+                return Err(invalid("gates.steps.timeout_secs", "0", "timeout_secs > 0"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for GatesConfig {
+    fn default() -> Self {
+        Self {
+            fail_fast: true,
+            timeout_secs: 1800,
+            steps: Vec::new(),
+        }
+    }
+}
+
+fn invalid(field: &str, value: &str, constraint: &str) -> Error {
+    Error::ValidationError {
+        message: format!("Invalid gate configuration: {field} must satisfy {constraint}"),
+        field: Some(field.to_string()),
+        value: Some(value.to_string()),
+        constraints: vec![constraint.to_string()],
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// PARTIAL CONFIG STRUCTURES (explicit-key merge semantics)
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Partial configuration with Option<T> fields for explicit-key merge semantics
+///
+/// `steps` replaces the whole pipeline: a project listing its own gates does
+/// not inherit gates from the global config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PartialGatesConfig {
+    #[serde(default)]
+    pub fail_fast: Option<bool>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub steps: Option<Vec<GateDefinition>>,
+}
+
+impl GatesConfig {
+    /// Merge partial config, only updating fields that are Some(value)
+    pub fn merge_partial(&mut self, partial: PartialGatesConfig) {
+        if let Some(fail_fast) = partial.fail_fast {
+            self.fail_fast = fail_fast;
+        }
+        if let Some(timeout_secs) = partial.timeout_secs {
+            self.timeout_secs = timeout_secs;
+        }
+        if let Some(steps) = partial.steps {
+            self.steps = steps;
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// TESTS
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gate(name: &str, run: &str) -> GateDefinition {
+        GateDefinition {
+            name: name.to_string(),
+            kind: GateKind::Command,
+            run: run.to_string(),
+            timeout_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_default_pipeline_is_empty_and_fails_fast() {
+        let config = GatesConfig::default();
+
+        assert!(config.steps.is_empty());
+        assert!(config.fail_fast);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_steps_parse_in_order() -> std::result::Result<(), toml::de::Error> {
+        let partial: PartialGatesConfig = toml::from_str(
+            r#"
+            fail_fast = false
+
+            [[steps]]
+            name = "quick"
+            kind = "moon"
+            run = ":quick"
+
+            [[steps]]
+            name = "unit"
+            kind = "command"
+            run = "cargo test"
+            timeout_secs = 60
+            "#,
+        )?;
+        let mut config = GatesConfig::default();
+        config.merge_partial(partial);
+
+        assert!(!config.fail_fast);
+        assert_eq!(config.timeout_secs, 1800);
+        assert_eq!(config.steps[0], GateDefinition::moon(MoonGate::Quick));
+        assert_eq!(config.steps[1].kind, GateKind::Command);
+        assert_eq!(config.timeout_for(&config.steps[0]).as_secs(), 1800);
+        assert_eq!(config.timeout_for(&config.steps[1]).as_secs(), 60);
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_or_empty_gates_are_rejected() {
+        let duplicate = GatesConfig {
+            steps: vec![gate("lint", "true"), gate("lint", "false")],
+            ..GatesConfig::default()
+        };
+        let empty_run = GatesConfig {
+            steps: vec![gate("lint", " ")],
+            ..GatesConfig::default()
+        };
+        let zero_timeout = GatesConfig {
+            timeout_secs: 0,
+            ..GatesConfig::default()
+        };
+
+        assert!(duplicate.validate().is_err());
+        assert!(empty_run.validate().is_err());
+        assert!(zero_timeout.validate().is_err());
+    }
+
+    #[test]
+    fn test_or_moon_keeps_configured_steps() {
+        let configured = GatesConfig {
+            steps: vec![gate("lint", "true")],
+            ..GatesConfig::default()
+        };
+
+        assert_eq!(configured.clone().or_moon(), configured);
+        assert_eq!(GatesConfig::default().or_moon(), GatesConfig::moon());
+    }
+}
@@ -0,0 +1,187 @@
+//! Quality gate results cached in `SQLite`.
+//!
+//! Rerunning `done` after an unrelated failure should not rerun a test suite
+//! that already passed for the same files. `GateCache` records every passing
+//! [`GateResult`] in the `gate_results` table of `state.db`, keyed by the id
+//! of the tree it ran against and by the gate's definition. Changing the files
+//! or the gate (its name, kind or command) misses the cache; the timeout is
+//! not part of the key since it does not change what a pass means.
+//!
+//! Failures are never cached: a flaky gate gets another chance on every run.
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+
+use chrono::Utc;
+use sqlx::{Row, SqlitePool};
+
+use crate::{config::GateDefinition, moon_gates::GateResult, Error, Result};
+
+/// Persists passing gate results in the `gate_results` table of `state.db`.
+#[derive(Debug, Clone)]
+pub struct GateCache {
+    db: SqlitePool,
+}
+
+impl GateCache {
+    /// Create a new `GateCache` over an existing pool.
+    #[must_use]
+    pub const fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+
+    /// Initialize the gate results table.
+    pub async fn init(&self) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS gate_results (
+                tree_id TEXT NOT NULL,
+                definition TEXT NOT NULL,
+                gate TEXT NOT NULL,
+                exit_code INTEGER NOT NULL,
+                summary TEXT NOT NULL,
+                recorded_at TEXT NOT NULL,
+                PRIMARY KEY (tree_id, definition)
+            )",
+        )
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// The pass recorded for `gate` on `tree_id`, marked as cached.
+    pub async fn lookup(&self, tree_id: &str, gate: &GateDefinition) -> Result<Option<GateResult>> {
+        let row = sqlx::query(
+            "SELECT exit_code, summary FROM gate_results
+             WHERE tree_id = ? AND definition = ?",
+        )
+        .bind(tree_id)
+        .bind(definition_key(gate)?)
+        .fetch_optional(&self.db)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+        row.map(|row| {
+            let exit_code: i32 = row
+                .try_get("exit_code")
+                .map_err(|e| Error::DatabaseError(e.to_string()))?;
+            let summary: String = row
+                .try_get("summary")
+                .map_err(|e| Error::DatabaseError(e.to_string()))?;
+            Ok(GateResult::new(
+                &gate.name,
+                true,
+                exit_code,
+                String::new(),
+                String::new(),
+                summary,
+            )
+            .into_cached())
+        })
+        .transpose()
+    }
+
+    /// Remember that `gate` passed on `tree_id`.
+    ///
+    /// Failed and cached results are ignored; returns whether `result` was
+    /// recorded.
+    pub async fn record(
+        &self,
+        tree_id: &str,
+        gate: &GateDefinition,
+        result: &GateResult,
+    ) -> Result<bool> {
+        if !result.passed || result.cached {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO gate_results
+             (tree_id, definition, gate, exit_code, summary, recorded_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(tree_id)
+        .bind(definition_key(gate)?)
+        .bind(&gate.name)
+        .bind(result.exit_code)
+        .bind(&result.summary)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+        Ok(true)
+    }
+}
+
+/// Cache key of a gate: everything that decides what it checks
+fn definition_key(gate: &GateDefinition) -> Result<String> {
+    Ok(serde_json::to_string(&(&gate.name, gate.kind, &gate.run))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::config::GateKind;
+
+    async fn cache() -> Result<GateCache> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .map_err(|e| Error::DatabaseError(e.to_string()))?;
+        let cache = GateCache::new(pool);
+        cache.init().await?;
+        Ok(cache)
+    }
+
+    fn gate(run: &str) -> GateDefinition {
+        GateDefinition {
+            name: "unit".to_string(),
+            kind: GateKind::Command,
+            run: run.to_string(),
+            timeout_secs: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pass_is_reused_for_the_same_tree_and_gate() -> Result<()> {
+        let cache = cache().await?;
+        let passed = GateResult::new("unit", true, 0, String::new(), String::new(), "ok".into());
+
+        assert!(cache.record("tree-1", &gate("cargo test"), &passed).await?);
+
+        let hit = cache.lookup("tree-1", &gate("cargo test")).await?;
+        assert_eq!(hit, Some(passed.into_cached()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_other_tree_or_definition_misses() -> Result<()> {
+        let cache = cache().await?;
+        let passed = GateResult::new("unit", true, 0, String::new(), String::new(), "ok".into());
+        cache.record("tree-1", &gate("cargo test"), &passed).await?;
+
+        let mut slower = gate("cargo test");
+        slower.timeout_secs = Some(60);
+
+        assert_eq!(cache.lookup("tree-2", &gate("cargo test")).await?, None);
+        assert_eq!(cache.lookup("tree-1", &gate("cargo nextest")).await?, None);
+        assert!(cache.lookup("tree-1", &slower).await?.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_not_cached() -> Result<()> {
+        let cache = cache().await?;
+        let failed = GateResult::new("unit", false, 1, String::new(), String::new(), "x".into());
+
+        assert!(!cache.record("tree-1", &gate("cargo test"), &failed).await?);
+        assert_eq!(cache.lookup("tree-1", &gate("cargo test")).await?, None);
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@
 //! The coordination layer handles:
 //! - **Conflict resolution** - Detecting and resolving merge conflicts
 //! - **Locking** - Distributed locking for critical sections
+//! - **Merge queue** - Persisting the queue of sessions waiting to land
 //!
 //! ## Module Structure
 //!
@@ -36,6 +37,25 @@
 //! - Automatic expiration on failure
 //! - Safe cleanup on release
 //!
+//! ### Merge Queue
+//!
+//! **Queue persistence:**
+//! - [`queue_store`] - Merge queue storage in `state.db`
+//! - [`QueueStore`] - Load the queue, apply use cases and log status transitions atomically
+//!
+//! ### Gate Results
+//!
+//! **Gate caching:**
+//! - [`gate_cache`] - Passing quality gate results in `state.db`
+//! - [`GateCache`] - Reuse a pass for an identical tree and gate definition
+//!
+//! ### Session Stacks
+//!
+//! **Stack persistence:**
+//! - [`stack_store`] - Parent/child session relationships in `state.db`
+//! - [`StackStore`] - Record, query and unwind stacked sessions
+//! - [`StateDbMetadataBackend`] - `MetadataBackend` over the stored stack
+//!
 //! ## Domain Types
 //!
 //! This module re-exports domain types from [`domain_types`]:
@@ -53,7 +73,10 @@
 pub mod conflict_resolutions;
 pub mod conflict_resolutions_entities;
 pub mod domain_types;
+pub mod gate_cache;
 pub mod locks;
+pub mod queue_store;
+pub mod stack_store;
 
 pub use conflict_resolutions::{
     get_conflict_resolutions, get_resolutions_by_decider, get_resolutions_by_time_range,
@@ -61,4 +84,7 @@ pub use conflict_resolutions::{
 };
 pub use conflict_resolutions_entities::{ConflictResolution, ConflictResolutionError};
 pub use domain_types::{AgentId, BeadId, DomainError, WorkspaceName};
+pub use gate_cache::GateCache;
 pub use locks::{LockInfo, LockManager, LockResponse};
+pub use queue_store::{QueueStore, QueueTransition};
+pub use stack_store::{StackStore, StateDbMetadataBackend};
@@ -0,0 +1,635 @@
+//! Merge queue persistence backed by `SQLite`.
+//!
+//! The queue domain types in [`crate::queue`] and the pure use cases in
+//! [`crate::use_cases`] never touch storage. `QueueStore` is the infrastructure
+//! side: it loads the persisted [`Queue`], hands it to a use case, and writes
+//! the result back inside a single `BEGIN IMMEDIATE` transaction so concurrent
+//! agents enqueueing at the same time cannot lose each other's entries.
+//!
+//! Status changes made by the queue processor go through [`QueueStore::transition`],
+//! which also appends to `merge_queue_transitions` in the same transaction. The
+//! stored status is therefore always the last step that completed, which is
+//! what lets a restarted processor pick up where a crashed one stopped.
+//! Entries claimed together by [`QueueStore::claim_batch`] carry a shared
+//! `batch_id`, which is kept on the entry and on every transition it logs.
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{pool::PoolConnection, Row, Sqlite, SqlitePool};
+
+use crate::{
+    queue::{Queue, QueueEntry, QueueEntryId, QueueStatus, SessionName},
+    use_cases::{claim_batch, transition_entry, DomainError},
+    Error, Result,
+};
+
+/// A persisted status change of a queue entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueTransition {
+    /// Entry that changed
+    pub entry_id: String,
+    /// Session the entry belongs to
+    pub session: String,
+    /// Status before the change
+    pub from: QueueStatus,
+    /// Status after the change
+    pub to: QueueStatus,
+    /// Why the change happened (failure reason, gate summary, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Speculative batch the entry belonged to when it changed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// When the change was recorded
+    pub at: DateTime<Utc>,
+}
+
+/// Persists the merge queue in the `merge_queue` table of `state.db`.
+#[derive(Debug, Clone)]
+pub struct QueueStore {
+    db: SqlitePool,
+}
+
+impl QueueStore {
+    /// Create a new `QueueStore` over an existing pool.
+    #[must_use]
+    pub const fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+
+    /// Get the database pool
+    #[must_use]
+    pub const fn pool(&self) -> &SqlitePool {
+        &self.db
+    }
+
+    /// Initialize the merge queue table.
+    pub async fn init(&self) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS merge_queue (
+                id TEXT PRIMARY KEY,
+                session TEXT NOT NULL UNIQUE,
+                priority INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                enqueued_at TEXT NOT NULL,
+                batch_id TEXT
+            )",
+        )
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_merge_queue_position ON merge_queue(position)")
+            .execute(&self.db)
+            .await
+            .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS merge_queue_transitions (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                entry_id TEXT NOT NULL,
+                session TEXT NOT NULL,
+                from_status TEXT NOT NULL,
+                to_status TEXT NOT NULL,
+                detail TEXT,
+                at TEXT NOT NULL,
+                batch_id TEXT
+            )",
+        )
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_merge_queue_transitions_session
+             ON merge_queue_transitions(session)",
+        )
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+        self.ensure_batch_columns().await
+    }
+
+    /// Add the `batch_id` columns to tables created before batching existed.
+    async fn ensure_batch_columns(&self) -> Result<()> {
+        for table in ["merge_queue", "merge_queue_transitions"] {
+            let columns = sqlx::query(&format!("PRAGMA table_info({table})"))
+                .fetch_all(&self.db)
+                .await
+                .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+            let has_batch_id = columns.iter().any(|row| {
+                row.try_get::<String, _>("name")
+                    .is_ok_and(|n| n == "batch_id")
+            });
+
+            if !has_batch_id {
+                sqlx::query(&format!("ALTER TABLE {table} ADD COLUMN batch_id TEXT"))
+                    .execute(&self.db)
+                    .await
+                    .map_err(|e| Error::DatabaseError(e.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load the queue in processing order.
+    pub async fn load(&self) -> Result<Queue> {
+        let mut conn = self.acquire().await?;
+        load_queue(&mut conn).await
+    }
+
+    /// Find the queue entry for a session, if any.
+    pub async fn find_by_session(&self, session: &str) -> Result<Option<QueueEntry>> {
+        let queue = self.load().await?;
+        let name = SessionName::new(session)?;
+        Ok(queue.find_by_session(&name).cloned())
+    }
+
+    /// Apply a pure queue use case and persist the resulting queue atomically.
+    ///
+    /// The closure receives the queue as currently stored and returns the
+    /// queue that should replace it. Nothing is written if it returns an error.
+    pub async fn modify<F>(&self, update: F) -> Result<Queue>
+    where
+        F: FnOnce(&Queue) -> std::result::Result<Queue, DomainError> + Send,
+    {
+        self.apply(|current| {
+            update(current)
+                .map(|queue| (queue, Vec::new()))
+                .map_err(domain_error)
+        })
+        .await
+        .map(|(queue, _)| queue)
+    }
+
+    /// Move an entry to `status` and record the change in the transition log.
+    ///
+    /// The move is validated by `QueueStatus::transition_to`.
+    pub async fn transition(
+        &self,
+        id: &QueueEntryId,
+        status: QueueStatus,
+        detail: Option<&str>,
+    ) -> Result<QueueTransition> {
+        let id = id.clone();
+        let detail = detail.map(String::from);
+        let (_, transitions) = self
+            .apply(move |current| {
+                let (queue, transition) = plan_transition(current, &id, status, detail)?;
+                Ok((queue, vec![transition]))
+            })
+            .await?;
+
+        transitions
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::QueueError("Queue transition was not recorded".into()))
+    }
+
+    /// Atomically claim the next pending entry, if there is one.
+    pub async fn claim_next(&self) -> Result<Option<QueueTransition>> {
+        self.apply(|current| match current.next_pending() {
+            Some(entry) => {
+                let id = entry.id.clone();
+                let (queue, transition) =
+                    plan_transition(current, &id, QueueStatus::Claimed, None)?;
+                Ok((queue, vec![transition]))
+            }
+            None => Ok((current.clone(), Vec::new())),
+        })
+        .await
+        .map(|(_, transitions)| transitions.into_iter().next())
+    }
+
+    /// Atomically claim up to `limit` pending entries as one speculative batch.
+    ///
+    /// Every claimed entry is tagged with `batch_id`. Returns the claim
+    /// transitions in queue order; empty when nothing is pending.
+    pub async fn claim_batch(&self, limit: usize, batch_id: &str) -> Result<Vec<QueueTransition>> {
+        self.apply(|current| {
+            let (queue, ids) = claim_batch(current, limit, batch_id).map_err(domain_error)?;
+            let transitions = ids
+                .iter()
+                .filter_map(|id| current.find(id).zip(queue.find(id)))
+                .map(|(before, after)| QueueTransition {
+                    entry_id: after.id.to_string(),
+                    session: after.session.to_string(),
+                    from: before.status,
+                    to: after.status,
+                    detail: None,
+                    batch_id: after.batch_id.clone(),
+                    at: Utc::now(),
+                })
+                .collect();
+            Ok((queue, transitions))
+        })
+        .await
+        .map(|(_, transitions)| transitions)
+    }
+
+    /// Status history of a session's entries, oldest first.
+    pub async fn history(&self, session: &str) -> Result<Vec<QueueTransition>> {
+        let rows = sqlx::query(
+            "SELECT entry_id, session, from_status, to_status, detail, at, batch_id
+             FROM merge_queue_transitions
+             WHERE session = ?
+             ORDER BY seq ASC",
+        )
+        .bind(session)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+        rows.into_iter().map(parse_transition_row).collect()
+    }
+
+    /// Run `update` against the stored queue inside `BEGIN IMMEDIATE`, then
+    /// save the new queue and log the transitions it reports.
+    async fn apply<F>(&self, update: F) -> Result<(Queue, Vec<QueueTransition>)>
+    where
+        F: FnOnce(&Queue) -> Result<(Queue, Vec<QueueTransition>)> + Send,
+    {
+        let mut conn = self.acquire().await?;
+
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| Error::DatabaseError(format!("Failed to begin queue transaction: {e}")))?;
+
+        let outcome = async {
+            let current = load_queue(&mut conn).await?;
+            let (updated, transitions) = update(&current)?;
+            save_queue(&mut conn, &updated).await?;
+            for transition in &transitions {
+                insert_transition(&mut conn, transition).await?;
+            }
+            Ok::<_, Error>((updated, transitions))
+        }
+        .await;
+
+        match outcome {
+            Ok(result) => {
+                sqlx::query("COMMIT")
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(|e| {
+                        Error::DatabaseError(format!("Failed to commit queue transaction: {e}"))
+                    })?;
+                Ok(result)
+            }
+            Err(e) => {
+                let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn acquire(&self) -> Result<PoolConnection<Sqlite>> {
+        self.db
+            .acquire()
+            .await
+            .map_err(|e| Error::DatabaseError(e.to_string()))
+    }
+}
+
+/// Map a use-case failure onto the crate error type.
+fn domain_error(err: DomainError) -> Error {
+    match err {
+        DomainError::NotFound(what) => Error::NotFound(format!("Queue entry not found: {what}")),
+        DomainError::InvalidPriority(_) => Error::InvalidInput(err.to_string()),
+        DomainError::AlreadyExists(_) | DomainError::InvalidStateTransition { .. } => {
+            Error::QueueError(err.to_string())
+        }
+    }
+}
+
+/// Apply `transition_entry` and describe the change it made.
+fn plan_transition(
+    queue: &Queue,
+    id: &QueueEntryId,
+    status: QueueStatus,
+    detail: Option<String>,
+) -> Result<(Queue, QueueTransition)> {
+    let entry = queue
+        .find(id)
+        .ok_or_else(|| Error::NotFound(format!("Queue entry not found: {id}")))?;
+    let updated = transition_entry(queue, id, status).map_err(domain_error)?;
+
+    let transition = QueueTransition {
+        entry_id: id.to_string(),
+        session: entry.session.to_string(),
+        from: entry.status,
+        to: status,
+        detail,
+        batch_id: entry.batch_id.clone(),
+        at: Utc::now(),
+    };
+    Ok((updated, transition))
+}
+
+async fn load_queue(conn: &mut PoolConnection<Sqlite>) -> Result<Queue> {
+    let rows = sqlx::query(
+        "SELECT id, session, priority, status, enqueued_at, batch_id
+         FROM merge_queue
+         ORDER BY position ASC",
+    )
+    .fetch_all(&mut **conn)
+    .await
+    .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+    rows.into_iter()
+        .map(parse_entry_row)
+        .collect::<Result<Vec<_>>>()
+        .map(Queue::from_entries)
+}
+
+async fn save_queue(conn: &mut PoolConnection<Sqlite>, queue: &Queue) -> Result<()> {
+    sqlx::query("DELETE FROM merge_queue")
+        .execute(&mut **conn)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+    for (position, entry) in queue.entries().iter().enumerate() {
+        let position = i64::try_from(position)
+            .map_err(|e| Error::DatabaseError(format!("Queue position overflow: {e}")))?;
+        sqlx::query(
+            "INSERT INTO merge_queue
+                 (id, session, priority, status, position, enqueued_at, batch_id)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(entry.id.as_str())
+        .bind(entry.session.as_str())
+        .bind(i64::from(entry.priority))
+        .bind(entry.status.as_str())
+        .bind(position)
+        .bind(entry.enqueued_at.to_rfc3339())
+        .bind(entry.batch_id.as_deref())
+        .execute(&mut **conn)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+    }
+
+    Ok(())
+}
+
+async fn insert_transition(
+    conn: &mut PoolConnection<Sqlite>,
+    transition: &QueueTransition,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO merge_queue_transitions
+             (entry_id, session, from_status, to_status, detail, at, batch_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&transition.entry_id)
+    .bind(&transition.session)
+    .bind(transition.from.as_str())
+    .bind(transition.to.as_str())
+    .bind(transition.detail.as_deref())
+    .bind(transition.at.to_rfc3339())
+    .bind(transition.batch_id.as_deref())
+    .execute(&mut **conn)
+    .await
+    .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+    Ok(())
+}
+
+fn parse_transition_row(row: sqlx::sqlite::SqliteRow) -> Result<QueueTransition> {
+    let get = |column: &str| -> Result<String> {
+        row.try_get(column)
+            .map_err(|e| Error::DatabaseError(e.to_string()))
+    };
+    let detail: Option<String> = row
+        .try_get("detail")
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+    let batch_id: Option<String> = row
+        .try_get("batch_id")
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+    let at = get("at")?;
+
+    Ok(QueueTransition {
+        entry_id: get("entry_id")?,
+        session: get("session")?,
+        from: get("from_status")?.parse()?,
+        to: get("to_status")?.parse()?,
+        detail,
+        batch_id,
+        at: DateTime::parse_from_rfc3339(&at)
+            .map_err(|e| Error::ParseError(e.to_string()))?
+            .with_timezone(&Utc),
+    })
+}
+
+fn parse_entry_row(row: sqlx::sqlite::SqliteRow) -> Result<QueueEntry> {
+    let id: String = row
+        .try_get("id")
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+    let session: String = row
+        .try_get("session")
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+    let priority: i64 = row
+        .try_get("priority")
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+    let status: String = row
+        .try_get("status")
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+    let enqueued_at: String = row
+        .try_get("enqueued_at")
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+    let batch_id: Option<String> = row
+        .try_get("batch_id")
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+    Ok(QueueEntry {
+        id: QueueEntryId::new(id)?,
+        session: SessionName::new(session)?,
+        priority: u32::try_from(priority)
+            .map_err(|e| Error::ParseError(format!("Invalid queue priority {priority}: {e}")))?,
+        status: status.parse::<QueueStatus>()?,
+        enqueued_at: DateTime::parse_from_rfc3339(&enqueued_at)
+            .map_err(|e| Error::ParseError(e.to_string()))?
+            .with_timezone(&Utc),
+        batch_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+    use crate::use_cases::{dequeue_session, enqueue_session, reprioritize_session};
+
+    async fn setup() -> Result<QueueStore> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .map_err(|e| Error::DatabaseError(e.to_string()))?;
+        let store = QueueStore::new(pool);
+        store.init().await?;
+        Ok(store)
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_is_persisted_in_priority_order() -> Result<()> {
+        let store = setup().await?;
+        store
+            .modify(|q| enqueue_session(q, "low".to_string(), 80))
+            .await?;
+        store
+            .modify(|q| enqueue_session(q, "high".to_string(), 10))
+            .await?;
+
+        let queue = store.load().await?;
+        let order: Vec<&str> = queue.entries().iter().map(|e| e.session.as_str()).collect();
+        assert_eq!(order, vec!["high", "low"]);
+        assert!(queue
+            .entries()
+            .iter()
+            .all(|e| e.status == QueueStatus::Pending));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_use_case_leaves_queue_untouched() -> Result<()> {
+        let store = setup().await?;
+        store
+            .modify(|q| enqueue_session(q, "feature".to_string(), 50))
+            .await?;
+
+        let result = store
+            .modify(|q| enqueue_session(q, "feature".to_string(), 10))
+            .await;
+        assert!(matches!(result, Err(Error::QueueError(_))));
+
+        let entry = store.find_by_session("feature").await?;
+        assert_eq!(entry.map(|e| e.priority), Some(50));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_failed_entry_can_be_queued_again() -> Result<()> {
+        let store = setup().await?;
+        store
+            .modify(|q| enqueue_session(q, "feature".to_string(), 50))
+            .await?;
+        let claimed = store
+            .claim_next()
+            .await?
+            .ok_or_else(|| Error::QueueError("nothing claimed".into()))?;
+        let id = QueueEntryId::new(claimed.entry_id)?;
+        store
+            .transition(&id, QueueStatus::FailedRetryable, Some("conflict"))
+            .await?;
+
+        store
+            .modify(|q| enqueue_session(q, "feature".to_string(), 50))
+            .await?;
+
+        let entry = store.find_by_session("feature").await?;
+        assert_eq!(entry.map(|e| e.status), Some(QueueStatus::Pending));
+        assert!(store.claim_next().await?.is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reprioritize_and_remove_round_trip() -> Result<()> {
+        let store = setup().await?;
+        store
+            .modify(|q| enqueue_session(q, "a".to_string(), 10))
+            .await?;
+        store
+            .modify(|q| enqueue_session(q, "b".to_string(), 20))
+            .await?;
+        store.modify(|q| reprioritize_session(q, "b", 1)).await?;
+
+        let queue = store.load().await?;
+        assert_eq!(
+            queue.entries().first().map(|e| e.session.as_str()),
+            Some("b")
+        );
+
+        store.modify(|q| dequeue_session(q, "a")).await?;
+        assert_eq!(store.load().await?.len(), 1);
+
+        let missing = store.modify(|q| dequeue_session(q, "a")).await;
+        assert!(matches!(missing, Err(Error::NotFound(_))));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_claim_and_transition_are_logged() -> Result<()> {
+        let store = setup().await?;
+        store
+            .modify(|q| enqueue_session(q, "feature".to_string(), 50))
+            .await?;
+
+        let claimed = store.claim_next().await?;
+        let id = match claimed {
+            Some(ref t) => QueueEntryId::new(t.entry_id.clone())?,
+            None => return Err(Error::QueueError("nothing claimed".into())),
+        };
+        assert_eq!(store.claim_next().await?, None);
+
+        store.transition(&id, QueueStatus::Rebasing, None).await?;
+        store
+            .transition(&id, QueueStatus::FailedRetryable, Some("conflict in a.rs"))
+            .await?;
+
+        let invalid = store.transition(&id, QueueStatus::Merged, None).await;
+        assert!(matches!(invalid, Err(Error::QueueError(_))));
+
+        let history = store.history("feature").await?;
+        let steps: Vec<(QueueStatus, QueueStatus)> =
+            history.iter().map(|t| (t.from, t.to)).collect();
+        assert_eq!(
+            steps,
+            vec![
+                (QueueStatus::Pending, QueueStatus::Claimed),
+                (QueueStatus::Claimed, QueueStatus::Rebasing),
+                (QueueStatus::Rebasing, QueueStatus::FailedRetryable),
+            ]
+        );
+        assert_eq!(
+            history.last().and_then(|t| t.detail.as_deref()),
+            Some("conflict in a.rs")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_claim_batch_tags_entries_and_transitions() -> Result<()> {
+        let store = setup().await?;
+        for (session, priority) in [("a", 10), ("b", 20), ("c", 30)] {
+            store
+                .modify(|q| enqueue_session(q, session.to_string(), priority))
+                .await?;
+        }
+
+        let claimed = store.claim_batch(2, "b-1").await?;
+        let sessions: Vec<&str> = claimed.iter().map(|t| t.session.as_str()).collect();
+        assert_eq!(sessions, vec!["a", "b"]);
+
+        let b = store.find_by_session("b").await?;
+        assert_eq!(b.and_then(|e| e.batch_id), Some("b-1".to_string()));
+        let c = store.find_by_session("c").await?;
+        assert_eq!(c.and_then(|e| e.batch_id), None);
+
+        let history = store.history("a").await?;
+        assert_eq!(
+            history.first().and_then(|t| t.batch_id.as_deref()),
+            Some("b-1")
+        );
+        Ok(())
+    }
+}
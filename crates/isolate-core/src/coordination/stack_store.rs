@@ -0,0 +1,463 @@
+//! Stacked session relationships persisted in `SQLite`.
+//!
+//! [`StackMetadata`] records which session is stacked on which and saves
+//! itself through the synchronous [`MetadataBackend`] trait. `StackStore`
+//! connects that to `state.db`: each call loads the stored metadata into a
+//! [`StateDbMetadataBackend`], runs against it, and writes back whatever the
+//! metadata saved, all inside one `BEGIN IMMEDIATE` transaction so concurrent
+//! `add --on` calls cannot drop each other's links.
+//!
+//! Sessions are branch ids. A session stacked on nothing hangs off the
+//! `trunk` root, which stands for the main branch.
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+
+use std::{cell::RefCell, rc::Rc};
+
+use sqlx::{pool::PoolConnection, Row, Sqlite, SqlitePool};
+
+use crate::{
+    dag::{BranchDag, BranchId},
+    metadata::{MetadataBackend, StackMetadata},
+    Error, Result,
+};
+
+/// Branch id of the main branch in the stack
+pub const TRUNK: &str = "trunk";
+
+/// `MetadataBackend` over a snapshot of the `session_stack` row.
+///
+/// Saves only replace the in-memory snapshot; [`StackStore`] writes it back
+/// to `state.db` once the metadata is done with it.
+#[derive(Debug, Default)]
+pub struct StateDbMetadataBackend {
+    data: RefCell<Vec<u8>>,
+}
+
+impl StateDbMetadataBackend {
+    /// Start from `data` as loaded from `state.db`
+    #[must_use]
+    pub const fn new(data: Vec<u8>) -> Self {
+        Self {
+            data: RefCell::new(data),
+        }
+    }
+
+    /// The metadata as last saved
+    #[must_use]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.borrow().clone()
+    }
+}
+
+impl MetadataBackend for StateDbMetadataBackend {
+    fn load(&self) -> Result<Vec<u8>> {
+        Ok(self.data())
+    }
+
+    fn save(&self, data: &[u8]) -> Result<()> {
+        *self.data.borrow_mut() = data.to_vec();
+        Ok(())
+    }
+}
+
+/// Persists stacked session relationships in the `session_stack` table of `state.db`.
+#[derive(Debug, Clone)]
+pub struct StackStore {
+    db: SqlitePool,
+}
+
+impl StackStore {
+    /// Create a new `StackStore` over an existing pool.
+    #[must_use]
+    pub const fn new(db: SqlitePool) -> Self {
+        Self { db }
+    }
+
+    /// Initialize the session stack table.
+    pub async fn init(&self) -> Result<()> {
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS session_stack (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                data BLOB NOT NULL
+            )",
+        )
+        .execute(&self.db)
+        .await
+        .map_err(|e| Error::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Session `session` is stacked on, or `None` if it is based on main or
+    /// not tracked at all.
+    pub async fn parent_of(&self, session: &str) -> Result<Option<String>> {
+        let id = BranchId::new(session)?;
+        self.read(|metadata| {
+            if !metadata.has_branch(&id) {
+                return Ok(None);
+            }
+            Ok(metadata
+                .get_parent(id)?
+                .filter(|parent| parent.as_str() != TRUNK)
+                .map(|parent| parent.as_str().to_string()))
+        })
+        .await
+    }
+
+    /// Sessions stacked directly on `session`, in name order.
+    pub async fn children_of(&self, session: &str) -> Result<Vec<String>> {
+        let id = BranchId::new(session)?;
+        self.read(|metadata| {
+            if !metadata.has_branch(&id) {
+                return Ok(Vec::new());
+            }
+            let mut children: Vec<String> = metadata
+                .get_children(id)?
+                .iter()
+                .map(|child| child.as_str().to_string())
+                .collect();
+            children.sort();
+            Ok(children)
+        })
+        .await
+    }
+
+    /// `session` and the sessions it is stacked on, nearest to main first.
+    pub async fn stack_of(&self, session: &str) -> Result<Vec<String>> {
+        let id = BranchId::new(session)?;
+        self.read(|metadata| {
+            let mut stack = Vec::new();
+            let mut current = Some(id);
+            while let Some(branch) = current.filter(|b| b.as_str() != TRUNK) {
+                if stack.contains(&branch) {
+                    break;
+                }
+                current = if metadata.has_branch(&branch) {
+                    metadata.get_parent(branch.clone())?
+                } else {
+                    None
+                };
+                stack.push(branch);
+            }
+            Ok(stack
+                .into_iter()
+                .rev()
+                .map(|branch| branch.as_str().to_string())
+                .collect())
+        })
+        .await
+    }
+
+    /// Every tracked session as a [`BranchDag`]; sessions on main are its roots.
+    pub async fn dag(&self) -> Result<BranchDag> {
+        self.read(|metadata| {
+            let mut dag = BranchDag::new();
+            let mut pending: Vec<(BranchId, Option<BranchId>)> = Vec::new();
+            for branch in metadata.branch_ids() {
+                if branch.as_str() == TRUNK {
+                    continue;
+                }
+                match metadata.get_parent(branch.clone())? {
+                    Some(parent) if parent.as_str() != TRUNK => {}
+                    _ => pending.push((branch, None)),
+                }
+            }
+            while let Some((branch, parent)) = pending.pop() {
+                for child in metadata.get_children(branch.clone())? {
+                    pending.push((child, Some(branch.clone())));
+                }
+                dag.add_branch(branch, parent)?;
+            }
+            Ok(dag)
+        })
+        .await
+    }
+
+    /// Record `child` as stacked on `parent`.
+    ///
+    /// A parent that is not tracked yet is added on top of main.
+    pub async fn add_child(&self, child: &str, parent: &str) -> Result<()> {
+        let child = BranchId::new(child)?;
+        let parent = BranchId::new(parent)?;
+        self.update(move |metadata| {
+            let trunk = BranchId::new(TRUNK)?;
+            if !metadata.has_branch(&parent) {
+                metadata.add_branch(parent.clone(), Some(&trunk))?;
+            }
+            if metadata.has_branch(&child) {
+                metadata.set_parent(child, parent)
+            } else {
+                metadata.add_branch(child, Some(&parent))
+            }
+        })
+        .await
+    }
+
+    /// Forget `session`, restacking its children on its own parent.
+    ///
+    /// Returns the children that moved. Untracked sessions are a no-op.
+    pub async fn remove(&self, session: &str) -> Result<Vec<String>> {
+        let id = BranchId::new(session)?;
+        self.update(move |metadata| {
+            if !metadata.has_branch(&id) {
+                return Ok(Vec::new());
+            }
+            let parent = match metadata.get_parent(id.clone())? {
+                Some(parent) => parent,
+                None => BranchId::new(TRUNK)?,
+            };
+            let children = metadata.get_children(id.clone())?;
+            for child in &children {
+                metadata.set_parent(child.clone(), parent.clone())?;
+            }
+            metadata.remove_branch(id)?;
+            Ok(children
+                .iter()
+                .map(|child| child.as_str().to_string())
+                .collect())
+        })
+        .await
+    }
+
+    /// Carry `old_name`'s place in the stack over to `new_name`.
+    pub async fn rename(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let old = BranchId::new(old_name)?;
+        let new = BranchId::new(new_name)?;
+        self.update(move |metadata| {
+            if !metadata.has_branch(&old) {
+                return Ok(());
+            }
+            let parent = metadata.get_parent(old.clone())?;
+            metadata.add_branch(new.clone(), parent.as_ref())?;
+            for child in metadata.get_children(old.clone())? {
+                metadata.set_parent(child, new.clone())?;
+            }
+            metadata.remove_branch(old)
+        })
+        .await
+    }
+
+    /// Run `query` against the stored metadata without writing anything.
+    async fn read<T, F>(&self, query: F) -> Result<T>
+    where
+        F: FnOnce(&StackMetadata) -> Result<T> + Send,
+    {
+        let mut conn = self.acquire().await?;
+        let data = load_data(&mut conn).await?;
+        with_metadata(data, |metadata| query(metadata)).map(|(value, _)| value)
+    }
+
+    /// Run `update` against the stored metadata inside `BEGIN IMMEDIATE` and
+    /// save what it leaves behind.
+    async fn update<T, F>(&self, update: F) -> Result<T>
+    where
+        F: FnOnce(&mut StackMetadata) -> Result<T> + Send,
+    {
+        let mut conn = self.acquire().await?;
+
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| Error::DatabaseError(format!("Failed to begin stack transaction: {e}")))?;
+
+        let outcome = async {
+            let data = load_data(&mut conn).await?;
+            let (value, saved) = with_metadata(data, update)?;
+            save_data(&mut conn, &saved).await?;
+            Ok::<_, Error>(value)
+        }
+        .await;
+
+        match outcome {
+            Ok(value) => {
+                sqlx::query("COMMIT")
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(|e| {
+                        Error::DatabaseError(format!("Failed to commit stack transaction: {e}"))
+                    })?;
+                Ok(value)
+            }
+            Err(e) => {
+                let _ = sqlx::query("ROLLBACK").execute(&mut *conn).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn acquire(&self) -> Result<PoolConnection<Sqlite>> {
+        self.db
+            .acquire()
+            .await
+            .map_err(|e| Error::DatabaseError(e.to_string()))
+    }
+}
+
+/// Load `data` into `StackMetadata`, apply `f` and return what it saved.
+///
+/// The metadata holds its backend in an `Rc`, so it never outlives this call.
+fn with_metadata<T>(
+    data: Vec<u8>,
+    f: impl FnOnce(&mut StackMetadata) -> Result<T>,
+) -> Result<(T, Vec<u8>)> {
+    let backend = Rc::new(StateDbMetadataBackend::new(data));
+    let mut metadata = StackMetadata::load(backend.clone())?;
+    let value = f(&mut metadata)?;
+    drop(metadata);
+    Ok((value, backend.data()))
+}
+
+async fn load_data(conn: &mut PoolConnection<Sqlite>) -> Result<Vec<u8>> {
+    let row = sqlx::query("SELECT data FROM session_stack WHERE id = 1")
+        .fetch_optional(&mut **conn)
+        .await
+        .map_err(|e| Error::DatabaseError(format!("Failed to load session stack: {e}")))?;
+
+    row.map_or_else(
+        || Ok(Vec::new()),
+        |row| {
+            row.try_get::<Vec<u8>, _>("data")
+                .map_err(|e| Error::DatabaseError(format!("Failed to read session stack: {e}")))
+        },
+    )
+}
+
+async fn save_data(conn: &mut PoolConnection<Sqlite>, data: &[u8]) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO session_stack (id, data) VALUES (1, ?)
+         ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+    )
+    .bind(data)
+    .execute(&mut **conn)
+    .await
+    .map_err(|e| Error::DatabaseError(format!("Failed to save session stack: {e}")))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use super::*;
+
+    async fn store() -> Result<StackStore> {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .map_err(|e| Error::DatabaseError(e.to_string()))?;
+        let store = StackStore::new(pool);
+        store.init().await?;
+        Ok(store)
+    }
+
+    #[tokio::test]
+    async fn test_untracked_session_has_no_parent_or_children() -> Result<()> {
+        let store = store().await?;
+
+        assert_eq!(store.parent_of("feature").await?, None);
+        assert!(store.children_of("feature").await?.is_empty());
+        assert_eq!(
+            store.stack_of("feature").await?,
+            vec!["feature".to_string()]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_add_child_records_parent_on_trunk() -> Result<()> {
+        let store = store().await?;
+
+        store.add_child("api", "schema").await?;
+        store.add_child("ui", "api").await?;
+
+        assert_eq!(store.parent_of("api").await?, Some("schema".to_string()));
+        assert_eq!(store.parent_of("schema").await?, None);
+        assert_eq!(store.children_of("schema").await?, vec!["api".to_string()]);
+        assert_eq!(
+            store.stack_of("ui").await?,
+            vec!["schema".to_string(), "api".to_string(), "ui".to_string()]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_dag_roots_are_sessions_on_main() -> Result<()> {
+        let store = store().await?;
+        store.add_child("api", "schema").await?;
+        store.add_child("ui", "api").await?;
+
+        let dag = store.dag().await?;
+
+        let order: Vec<String> = dag
+            .topological_sort()?
+            .iter()
+            .map(|branch| branch.as_str().to_string())
+            .collect();
+        assert_eq!(order, vec!["schema", "api", "ui"]);
+        assert_eq!(dag.get_roots().len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cycles_are_rejected() -> Result<()> {
+        let store = store().await?;
+        store.add_child("api", "schema").await?;
+
+        assert!(store.add_child("schema", "api").await.is_err());
+        assert_eq!(store.parent_of("schema").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_remove_restacks_children_on_grandparent() -> Result<()> {
+        let store = store().await?;
+        store.add_child("api", "schema").await?;
+        store.add_child("ui", "api").await?;
+        store.add_child("cli", "api").await?;
+
+        let moved = store.remove("api").await?;
+
+        assert_eq!(moved.len(), 2);
+        assert_eq!(store.parent_of("ui").await?, Some("schema".to_string()));
+        assert_eq!(store.parent_of("cli").await?, Some("schema".to_string()));
+        assert_eq!(
+            store.children_of("schema").await?,
+            vec!["cli".to_string(), "ui".to_string()]
+        );
+        assert!(store.remove("api").await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rename_keeps_place_in_stack() -> Result<()> {
+        let store = store().await?;
+        store.add_child("api", "schema").await?;
+        store.add_child("ui", "api").await?;
+
+        store.rename("api", "backend").await?;
+
+        assert_eq!(
+            store.parent_of("backend").await?,
+            Some("schema".to_string())
+        );
+        assert_eq!(store.parent_of("ui").await?, Some("backend".to_string()));
+        assert_eq!(store.parent_of("api").await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_relationships_survive_a_new_store() -> Result<()> {
+        let store = store().await?;
+        store.add_child("api", "schema").await?;
+
+        let reopened = StackStore::new(store.db.clone());
+
+        assert_eq!(reopened.parent_of("api").await?, Some("schema".to_string()));
+        Ok(())
+    }
+}
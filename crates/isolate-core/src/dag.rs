@@ -216,32 +216,29 @@ impl BranchDag {
         ancestors.iter().any(|a| a == new_id)
     }
 
+    /// Every branch, parents before their children
+    ///
+    /// Siblings come out in name order so the result is stable.
     pub fn topological_sort(&self) -> Result<Vec<BranchId>, Error> {
         let mut in_degree: HashMap<BranchId, usize> = HashMap::new();
         let mut result = vec![];
         let mut queue: Vec<BranchId> = self.roots.iter().cloned().collect();
+        queue.sort_by(|a, b| b.cmp(a));
 
-        // Initialize in-degrees
-        for id in self.nodes.keys() {
-            in_degree.insert(id.clone(), 0);
-        }
-
-        // Calculate in-degrees from parents
+        // Each branch waits on its parent
         for node in self.nodes.values() {
-            if let Some(parent_id) = &node.parent {
-                if let Some(degree) = in_degree.get_mut(parent_id) {
-                    *degree += 1;
-                }
-            }
+            in_degree.insert(node.id.clone(), usize::from(node.parent.is_some()));
         }
 
         // Process queue
         while let Some(current) = queue.pop() {
             result.push(current.clone());
 
-            for child in self.get_children(&current) {
+            let mut children = self.get_children(&current);
+            children.sort_by(|a, b| b.cmp(a));
+            for child in children {
                 if let Some(degree) = in_degree.get_mut(child) {
-                    *degree -= 1;
+                    *degree = degree.saturating_sub(1);
                     if *degree == 0 {
                         queue.push(child.clone());
                     }
@@ -267,3 +264,39 @@ impl From<DagError> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(name: &str) -> BranchId {
+        BranchId::new(name).expect("valid branch id")
+    }
+
+    fn stack() -> BranchDag {
+        let mut dag = BranchDag::new();
+        dag.add_branch(id("schema"), None).expect("add schema");
+        dag.add_branch(id("api"), Some(id("schema"))).expect("add api");
+        dag.add_branch(id("ui"), Some(id("api"))).expect("add ui");
+        dag.add_branch(id("cli"), Some(id("api"))).expect("add cli");
+        dag.add_branch(id("docs"), None).expect("add docs");
+        dag
+    }
+
+    #[test]
+    fn test_topological_sort_puts_parents_first() {
+        let order = stack().topological_sort().expect("sort");
+
+        let names: Vec<&str> = order.iter().map(BranchId::as_str).collect();
+        assert_eq!(names, vec!["docs", "schema", "api", "cli", "ui"]);
+    }
+
+    #[test]
+    fn test_get_descendants_walks_the_whole_subtree() {
+        let mut descendants = stack().get_descendants(&id("schema"));
+        descendants.sort();
+
+        assert_eq!(descendants, vec![id("api"), id("cli"), id("ui")]);
+        assert!(stack().get_descendants(&id("ui")).is_empty());
+    }
+}
@@ -245,6 +245,18 @@ impl Status {
     pub const fn change_count(&self) -> usize {
         self.modified.len() + self.added.len() + self.deleted.len() + self.renamed.len()
     }
+
+    /// Paths of all changed files; renames are reported by their new path
+    #[must_use]
+    pub fn changed_files(&self) -> Vec<String> {
+        self.modified
+            .iter()
+            .chain(&self.added)
+            .chain(&self.deleted)
+            .chain(self.renamed.iter().map(|(_, new)| new))
+            .map(|path| path.display().to_string())
+            .collect()
+    }
 }
 
 /// Detect JJ workspace conflict type from error output
@@ -251,6 +251,25 @@ pub async fn get_current_operation(root: &Path) -> Result<RepoOperationInfo> {
 /// # }
 /// ```
 pub async fn create_workspace_synced(name: &str, path: &Path, repo_root: &Path) -> Result<()> {
+    create_workspace_synced_at(name, path, repo_root, None).await
+}
+
+/// Create a JJ workspace whose working copy starts on top of `base`
+///
+/// Same as [`create_workspace_synced`], but passes `-r <base>` to
+/// `jj workspace add` so the new workspace builds on an existing revision
+/// (for example another workspace's `<name>@`) instead of the default parent.
+///
+/// # Errors
+///
+/// Returns the same errors as [`create_workspace_synced`], plus a JJ command
+/// error if `base` does not resolve.
+pub async fn create_workspace_synced_at(
+    name: &str,
+    path: &Path,
+    repo_root: &Path,
+    base: Option<&str>,
+) -> Result<()> {
     // Validate inputs
     if name.is_empty() {
         return Err(Error::InvalidConfig(
@@ -280,9 +299,13 @@ pub async fn create_workspace_synced(name: &str, path: &Path, repo_root: &Path)
     // Step 2: Verify repository is accessible before mutation.
     let _ = get_current_operation(repo_root).await?;
 
-    // Step 3: Execute jj workspace add --name <name> <path>
-    let output = get_jj_command()
-        .args(["workspace", "add", "--name", name])
+    // Step 3: Execute jj workspace add --name <name> [-r <base>] <path>
+    let mut command = get_jj_command();
+    command.args(["workspace", "add", "--name", name]);
+    if let Some(base) = base {
+        command.args(["-r", base]);
+    }
+    let output = command
         .arg(path)
         .current_dir(repo_root)
         .output()
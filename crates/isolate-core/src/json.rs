@@ -70,6 +70,14 @@ pub mod schemas {
     pub const QUERY_PENDING_MERGES: &str = "query-pending-merges";
     pub const QUERY_LOCATION: &str = "query-location";
 
+    // Merge queue schemas
+    pub const QUEUE_ADD_RESPONSE: &str = "queue-add-response";
+    pub const QUEUE_LIST_RESPONSE: &str = "queue-list-response";
+    pub const QUEUE_REMOVE_RESPONSE: &str = "queue-remove-response";
+    pub const QUEUE_REPRIORITIZE_RESPONSE: &str = "queue-reprioritize-response";
+    pub const QUEUE_STATUS_RESPONSE: &str = "queue-status-response";
+    pub const QUEUE_PROCESS_RESPONSE: &str = "queue-process-response";
+
     // Error schema
     pub const ERROR_RESPONSE: &str = "error-response";
 
@@ -123,6 +131,12 @@ pub mod schemas {
             QUERY_CAN_SPAWN,
             QUERY_PENDING_MERGES,
             QUERY_LOCATION,
+            QUEUE_ADD_RESPONSE,
+            QUEUE_LIST_RESPONSE,
+            QUEUE_REMOVE_RESPONSE,
+            QUEUE_REPRIORITIZE_RESPONSE,
+            QUEUE_STATUS_RESPONSE,
+            QUEUE_PROCESS_RESPONSE,
             ERROR_RESPONSE,
         ]
     }
@@ -119,14 +119,16 @@ pub use dag::{BranchDag, BranchId, DagError};
 pub use events::{Event, EventType};
 pub use lock::{HolderId, Lock, LockManager, ResourceId, TtlSeconds};
 pub use metadata::{MetadataBackend, StackMetadata};
-pub use queue::{Queue, QueueEntry, QueueEntryId, QueueStatus, SessionName, MAX_PRIORITY};
+pub use queue::{
+    Queue, QueueEntry, QueueEntryId, QueueStatus, SessionName, DEFAULT_PRIORITY, MAX_PRIORITY,
+};
 pub use use_cases::{
-    dequeue_session, enqueue_session, insert_at_position, list_queue, remove_at_position,
-    DomainError, QueueEntryView,
+    claim_batch, dequeue_session, enqueue_session, insert_at_position, list_queue,
+    remove_at_position, reprioritize_session, transition_entry, DomainError, QueueEntryView,
 };
 pub use vcs::{
-    detect_backend, BackendType, BranchName, Change, ChangeId, CommitId, RepoStatus, VcsBackend,
-    VcsError,
+    backend_for, detect_backend, discover_backend, BackendType, Branch, BranchName, Change,
+    ChangeId, CommitId, GitBackend, JjBackend, RepoStatus, VcsBackend, VcsError,
 };
 
 pub use config::{ConfigManager, RecoveryPolicy};
@@ -139,8 +141,8 @@ pub use json::{
     ErrorCode, HateoasLink, RelatedResources, ResponseMeta, SchemaEnvelope, SchemaEnvelopeArray,
 };
 pub use moon_gates::{
-    classify_exit_code, combine_results, format_failure_message, parse_summary, GateError,
-    GateResult, GatesOutcome, GatesStatus, MoonGate,
+    classify_exit_code, collect_results, combine_results, format_failure_message, parse_summary,
+    GateError, GateResult, GatesOutcome, GatesStatus, MoonGate,
 };
 pub use output_format::OutputFormat;
 pub use recovery::{
@@ -1,26 +1,28 @@
-//! Moon gate execution for CI quality gates.
+//! Quality gate results for CI gates.
 //!
-//! This module implements the gate execution logic for running moon CI tasks
-//! and parsing their output. It follows the functional core pattern with:
+//! This module implements the gate result model and the parsing of gate
+//! output. Gates are the ordered checks configured under `[gates]`; a moon
+//! task is one kind of gate, a shell command another. It follows the
+//! functional core pattern with:
 //! - Pure functions for output parsing
 //! - No I/O in the parsing layer
 //! - Railway-oriented error handling
 //!
-//! # Gate Execution Order
+//! # Default Moon Gates
 //! 1. `:quick` - Fast lint check (format + clippy)
 //! 2. `:test` - Full test suite (only if quick passes)
 //!
 //! # State Transitions
-//! - On quick pass + test pass -> `ready_to_merge`
-//! - On quick fail -> `failed_retryable` (skip test - fail fast)
-//! - On test fail -> `failed_retryable`
+//! - On every gate passing -> `ready_to_merge`
+//! - On a gate failing -> `failed_retryable` (later gates skipped when failing fast)
 
 #![warn(clippy::pedantic)]
 #![warn(clippy::nursery)]
 #![forbid(unsafe_code)]
 
-use std::fmt;
+use std::{fmt, time::Duration};
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 // ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━
@@ -37,6 +39,15 @@ pub enum MoonGate {
 }
 
 impl MoonGate {
+    /// Returns the gate name results for this gate are reported under.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Quick => "quick",
+            Self::Test => "test",
+        }
+    }
+
     /// Returns the moon task name for this gate.
     #[must_use]
     pub const fn as_task(&self) -> &'static str {
@@ -62,29 +73,46 @@ impl fmt::Display for MoonGate {
     }
 }
 
-/// Result of running a single moon gate.
-#[derive(Debug, Clone, PartialEq, Eq)]
+impl From<MoonGate> for String {
+    fn from(gate: MoonGate) -> Self {
+        gate.name().to_string()
+    }
+}
+
+/// Exit code recorded for a gate that was killed at its timeout.
+pub const TIMED_OUT_EXIT_CODE: i32 = -1;
+
+/// Result of running a single gate.
+///
+/// Raw output is kept for debugging but left out of serialized results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GateResult {
-    /// Which gate was run
-    pub gate: MoonGate,
+    /// Name of the gate that was run
+    pub gate: String,
     /// Whether the gate passed
     pub passed: bool,
-    /// Exit code from moon
+    /// Exit code of the gate command
     pub exit_code: i32,
     /// Raw stdout (for debugging)
+    #[serde(skip)]
     pub stdout: String,
     /// Raw stderr (for debugging)
+    #[serde(skip)]
     pub stderr: String,
     /// Parsed summary message
     pub summary: String,
+    /// Whether this is a pass recorded for the same tree earlier rather
+    /// than a fresh run
+    #[serde(default)]
+    pub cached: bool,
 }
 
 impl GateResult {
     /// Create a new gate result.
     #[allow(clippy::too_many_arguments)]
     #[must_use]
-    pub const fn new(
-        gate: MoonGate,
+    pub fn new(
+        gate: impl Into<String>,
         passed: bool,
         exit_code: i32,
         stdout: String,
@@ -92,50 +120,79 @@ impl GateResult {
         summary: String,
     ) -> Self {
         Self {
-            gate,
+            gate: gate.into(),
             passed,
             exit_code,
             stdout,
             stderr,
             summary,
+            cached: false,
+        }
+    }
+
+    /// Mark this result as reused from an earlier run.
+    #[must_use]
+    pub fn into_cached(self) -> Self {
+        Self {
+            cached: true,
+            ..self
         }
     }
 
     /// Create a passing result.
     #[must_use]
-    pub fn passed(gate: MoonGate, stdout: String, stderr: String) -> Self {
+    pub fn passed(gate: impl Into<String>, stdout: String, stderr: String) -> Self {
         let summary = parse_summary(&stdout, &stderr);
         Self::new(gate, true, 0, stdout, stderr, summary)
     }
 
     /// Create a failing result.
     #[must_use]
-    pub fn failed(gate: MoonGate, exit_code: i32, stdout: String, stderr: String) -> Self {
+    pub fn failed(gate: impl Into<String>, exit_code: i32, stdout: String, stderr: String) -> Self {
         let summary = parse_summary(&stdout, &stderr);
         Self::new(gate, false, exit_code, stdout, stderr, summary)
     }
+
+    /// Create a failing result for a gate killed after `timeout`.
+    #[must_use]
+    pub fn timed_out(gate: impl Into<String>, timeout: Duration) -> Self {
+        let summary = format!("Timed out after {}s", timeout.as_secs());
+        Self::new(
+            gate,
+            false,
+            TIMED_OUT_EXIT_CODE,
+            String::new(),
+            String::new(),
+            summary,
+        )
+    }
 }
 
-/// Combined result of running all required gates.
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// Combined result of running a gate pipeline.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GatesOutcome {
-    /// Result of the quick gate
-    pub quick: GateResult,
-    /// Result of the test gate (None if quick failed - fail fast)
-    pub test: Option<GateResult>,
+    /// Results of the gates that ran, in pipeline order. Gates after a
+    /// failure are skipped when failing fast and have no result.
+    pub results: Vec<GateResult>,
     /// Overall outcome
     pub status: GatesStatus,
 }
 
+impl GatesOutcome {
+    /// Results of the gates that failed, in pipeline order.
+    pub fn failures(&self) -> impl Iterator<Item = &GateResult> {
+        self.results.iter().filter(|result| !result.passed)
+    }
+}
+
 /// Overall status of gate execution.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum GatesStatus {
     /// All gates passed
     AllPassed,
-    /// Quick gate failed (test skipped)
-    QuickFailed,
-    /// Test gate failed
-    TestFailed,
+    /// At least one gate failed
+    Failed,
 }
 
 impl GatesStatus {
@@ -156,8 +213,7 @@ impl fmt::Display for GatesStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::AllPassed => write!(f, "all gates passed"),
-            Self::QuickFailed => write!(f, "quick gate failed"),
-            Self::TestFailed => write!(f, "test gate failed"),
+            Self::Failed => write!(f, "gates failed"),
         }
     }
 }
@@ -169,9 +225,9 @@ impl fmt::Display for GatesStatus {
 /// Errors that can occur during gate execution.
 #[derive(Debug, Clone, Error, PartialEq, Eq)]
 pub enum GateError {
-    /// Failed to execute the moon command
-    #[error("failed to execute moon {gate}: {reason}")]
-    ExecutionFailed { gate: MoonGate, reason: String },
+    /// Failed to execute the gate command
+    #[error("failed to run gate '{gate}': {reason}")]
+    ExecutionFailed { gate: String, reason: String },
 
     /// Moon binary not found
     #[error("moon binary not found in PATH")]
@@ -278,12 +334,25 @@ fn extract_failure_summary(stdout_lines: &[&str], stderr_lines: &[&str]) -> Stri
     )
 }
 
-/// Determine the overall gates status from individual results.
+/// Determine the overall gates status from the results of a pipeline run.
 ///
-/// This implements the fail-fast logic:
-/// - If quick failed, test is None and status is `QuickFailed`
-/// - If quick passed but test failed, status is `TestFailed`
-/// - If both passed, status is `AllPassed`
+/// The outcome fails when any gate failed. Gates skipped after a failure
+/// simply have no result.
+#[must_use]
+pub fn collect_results(results: Vec<GateResult>) -> GatesOutcome {
+    let status = if results.iter().all(|result| result.passed) {
+        GatesStatus::AllPassed
+    } else {
+        GatesStatus::Failed
+    };
+
+    GatesOutcome { results, status }
+}
+
+/// Combine the results of the default moon gates.
+///
+/// This implements the fail-fast logic of the `:quick` -> `:test` pipeline:
+/// `test_result` is `None` when quick failed and the test gate was skipped.
 ///
 /// # Arguments
 /// * `quick_result` - Result of the quick gate
@@ -292,57 +361,40 @@ fn extract_failure_summary(stdout_lines: &[&str], stderr_lines: &[&str]) -> Stri
 /// # Returns
 /// The combined gates outcome
 #[must_use]
-pub const fn combine_results(
-    quick_result: GateResult,
-    test_result: Option<GateResult>,
-) -> GatesOutcome {
-    let status = match (&quick_result, &test_result) {
-        (quick, None) if !quick.passed => GatesStatus::QuickFailed,
-        (_quick, None) => {
-            // Quick passed but test was not run (shouldn't happen in normal flow)
-            GatesStatus::AllPassed
-        }
-        (quick, Some(_test)) if !quick.passed => {
-            // Quick failed but test was run (shouldn't happen with fail-fast)
-            GatesStatus::QuickFailed
-        }
-        (_quick, Some(test)) if !test.passed => GatesStatus::TestFailed,
-        _ => GatesStatus::AllPassed,
-    };
-
-    GatesOutcome {
-        quick: quick_result,
-        test: test_result,
-        status,
-    }
+pub fn combine_results(quick_result: GateResult, test_result: Option<GateResult>) -> GatesOutcome {
+    collect_results(std::iter::once(quick_result).chain(test_result).collect())
 }
 
-/// Create an error message for a failed gate.
+/// Create an error message for a failed gate pipeline.
+///
+/// Every failed gate is listed in pipeline order, e.g.
+/// `Quick gate failed (exit code 1): error: formatting check failed`.
 #[must_use]
 pub fn format_failure_message(outcome: &GatesOutcome) -> String {
     match outcome.status {
-        GatesStatus::QuickFailed => {
-            format!(
-                "Quick gate failed (exit code {}): {}",
-                outcome.quick.exit_code, outcome.quick.summary
-            )
-        }
-        GatesStatus::TestFailed => {
-            let test_result = outcome.test.as_ref();
-            test_result.map_or_else(
-                || "Test gate failed".to_string(),
-                |test| {
-                    format!(
-                        "Test gate failed (exit code {}): {}",
-                        test.exit_code, test.summary
-                    )
-                },
-            )
-        }
         GatesStatus::AllPassed => "All gates passed".to_string(),
+        GatesStatus::Failed => outcome
+            .failures()
+            .map(|result| {
+                format!(
+                    "{} gate failed (exit code {}): {}",
+                    capitalize(&result.gate),
+                    result.exit_code,
+                    result.summary
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; "),
     }
 }
 
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -398,7 +450,7 @@ mod tests {
         let result = GateResult::passed(MoonGate::Quick, "passed".to_string(), String::new());
         assert!(result.passed);
         assert_eq!(result.exit_code, 0);
-        assert_eq!(result.gate, MoonGate::Quick);
+        assert_eq!(result.gate, "quick");
     }
 
     #[test]
@@ -424,9 +476,9 @@ mod tests {
         let quick = GateResult::failed(MoonGate::Quick, 1, String::new(), String::new());
         let outcome = combine_results(quick, None);
 
-        assert_eq!(outcome.status, GatesStatus::QuickFailed);
+        assert_eq!(outcome.status, GatesStatus::Failed);
         assert!(outcome.status.is_failure());
-        assert!(outcome.test.is_none());
+        assert_eq!(outcome.results.len(), 1);
     }
 
     #[test]
@@ -435,9 +487,10 @@ mod tests {
         let test = GateResult::failed(MoonGate::Test, 1, String::new(), String::new());
         let outcome = combine_results(quick, Some(test));
 
-        assert_eq!(outcome.status, GatesStatus::TestFailed);
+        assert_eq!(outcome.status, GatesStatus::Failed);
         assert!(outcome.status.is_failure());
-        assert!(outcome.test.is_some());
+        assert_eq!(outcome.results.len(), 2);
+        assert_eq!(outcome.failures().count(), 1);
     }
 
     #[test]
@@ -475,6 +528,32 @@ mod tests {
         assert_eq!(msg, "All gates passed");
     }
 
+    #[test]
+    fn test_format_failure_message_lists_every_failure() {
+        let outcome = collect_results(vec![
+            GateResult::failed("lint", 2, String::new(), "error: unused import".to_string()),
+            GateResult::passed("docs", String::new(), String::new()),
+            GateResult::timed_out("unit", Duration::from_secs(30)),
+        ]);
+
+        assert_eq!(
+            format_failure_message(&outcome),
+            "Lint gate failed (exit code 2): error: unused import; \
+             Unit gate failed (exit code -1): Timed out after 30s"
+        );
+    }
+
+    #[test]
+    fn test_gate_result_serializes_without_raw_output() {
+        let result = GateResult::failed("lint", 1, "noisy".to_string(), "error: x".to_string());
+        let json = serde_json::to_value(&result).unwrap_or_default();
+
+        assert_eq!(json["gate"], "lint");
+        assert_eq!(json["summary"], "error: x");
+        assert!(json.get("stdout").is_none());
+        assert_eq!(json["cached"], false);
+    }
+
     #[test]
     fn test_extract_failure_summary() {
         let stdout = vec!["Running tests...", "error: test case failed"];
@@ -497,18 +576,17 @@ mod tests {
     #[test]
     fn test_gates_status_display() {
         assert_eq!(format!("{}", GatesStatus::AllPassed), "all gates passed");
-        assert_eq!(format!("{}", GatesStatus::QuickFailed), "quick gate failed");
-        assert_eq!(format!("{}", GatesStatus::TestFailed), "test gate failed");
+        assert_eq!(format!("{}", GatesStatus::Failed), "gates failed");
     }
 
     #[test]
     fn test_gate_error_display() {
         let err = GateError::ExecutionFailed {
-            gate: MoonGate::Quick,
+            gate: MoonGate::Quick.into(),
             reason: "timeout".to_string(),
         };
         let msg = format!("{err}");
-        assert!(msg.contains(":quick"));
+        assert!(msg.contains("'quick'"));
         assert!(msg.contains("timeout"));
     }
 }
@@ -111,9 +111,9 @@ pub use domain_types::{
 pub use test_utils::{OutputEmitter, StdoutEmitter, VecEmitter};
 pub use types::{
     Action, ActionStatus, Assessment, ConflictAnalysis, ConflictDetail, ConflictType, Context,
-    ErrorSeverity, Issue, IssueKind, IssueSeverity, OutputLine, OutputLineError, Plan, PlanStep,
-    Recovery, RecoveryAction, ResolutionOption, ResolutionRisk, ResolutionStrategy, ResultKind,
-    ResultOutput, Session, SessionOutput, SessionState, Summary, SummaryType, Warning,
+    ErrorSeverity, Issue, IssueKind, IssueSeverity, OutputLine, OutputLineError, PipelineOutput,
+    Plan, PlanStep, Recovery, RecoveryAction, ResolutionOption, ResolutionRisk, ResolutionStrategy,
+    ResultKind, ResultOutput, Session, SessionOutput, SessionState, Summary, SummaryType, Warning,
 };
 pub use writer::{emit, emit_all_stdout, emit_stdout, JsonlConfig, JsonlWriter};
 
@@ -282,6 +282,39 @@ fn test_output_line_kind() {
     assert_eq!(issue.kind(), "issue");
 }
 
+#[test]
+fn test_pipeline_output_validates_empty_id() {
+    let result = PipelineOutput::new(
+        " ".to_string(),
+        "specs/login.yaml".to_string(),
+        "pending".to_string(),
+        0,
+        10,
+    );
+    assert!(matches!(result, Err(OutputLineError::EmptyPipelineId)));
+}
+
+#[test]
+fn test_pipeline_output_line_is_wrapped_by_kind() {
+    let pipeline = PipelineOutput::new(
+        "3f2a9c1e".to_string(),
+        "specs/login.yaml".to_string(),
+        "escalated".to_string(),
+        2,
+        10,
+    )
+    .expect("valid")
+    .with_session(Some("pipeline-3f2a9c1e".to_string()));
+    let line = OutputLine::Pipeline(pipeline);
+
+    let json: serde_json::Value = serde_json::to_value(&line).expect("serialize");
+
+    assert_eq!(line.kind(), "pipeline");
+    assert_eq!(json["pipeline"]["state"], "escalated");
+    assert_eq!(json["pipeline"]["session"], "pipeline-3f2a9c1e");
+    assert!(json["pipeline"].get("last_error").is_none());
+}
+
 #[test]
 fn test_recovery_with_action() {
     let assessment = Assessment {
@@ -37,6 +37,8 @@ pub enum OutputLineError {
     EmptyDescription,
     #[error("session name is required but was empty")]
     EmptySessionName,
+    #[error("pipeline id is required but was empty")]
+    EmptyPipelineId,
     #[error("at least one action is required")]
     NoActions,
     #[error("plan step count exceeds u32::MAX")]
@@ -65,6 +67,7 @@ pub enum OutputLine {
     Result(ResultOutput),
     ConflictDetail(ConflictAnalysis),
     ConflictAnalysis(ConflictAnalysis),
+    Pipeline(PipelineOutput),
 }
 
 impl OutputLine {
@@ -80,6 +83,7 @@ impl OutputLine {
             Self::Result(_) => "result",
             Self::ConflictDetail(_) => "conflictdetail",
             Self::ConflictAnalysis(_) => "conflict_analysis",
+            Self::Pipeline(_) => "pipeline",
         }
     }
 }
@@ -192,6 +196,76 @@ impl SessionOutput {
     }
 }
 
+/// An orchestrator pipeline taking a spec through agent development and
+/// scenario validation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PipelineOutput {
+    pub id: String,
+    pub spec_path: String,
+    /// Pipeline state, e.g. `agent_development` or `accepted`
+    pub state: String,
+    pub iteration: u32,
+    pub max_iterations: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub created_at: DateTime<Utc>,
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PipelineOutput {
+    /// Create a new pipeline output line.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OutputLineError::EmptyPipelineId` if `id` is blank.
+    pub fn new(
+        id: String,
+        spec_path: String,
+        state: String,
+        iteration: u32,
+        max_iterations: u32,
+    ) -> Result<Self, OutputLineError> {
+        if id.trim().is_empty() {
+            return Err(OutputLineError::EmptyPipelineId);
+        }
+        let now = Utc::now();
+        Ok(Self {
+            id,
+            spec_path,
+            state,
+            iteration,
+            max_iterations,
+            session: None,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    #[must_use]
+    pub fn with_session(self, session: Option<String>) -> Self {
+        Self { session, ..self }
+    }
+
+    #[must_use]
+    pub fn with_last_error(self, last_error: Option<String>) -> Self {
+        Self { last_error, ..self }
+    }
+
+    #[must_use]
+    pub fn with_timestamps(self, created_at: DateTime<Utc>, updated_at: DateTime<Utc>) -> Self {
+        Self {
+            created_at,
+            updated_at,
+            ..self
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Issue {
     pub id: IssueId,
@@ -9,6 +9,7 @@ use crate::error::Error;
 
 /// Queue entry status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum QueueStatus {
     Pending,
     Claimed,
@@ -31,6 +32,30 @@ impl QueueStatus {
         matches!(self, Self::FailedRetryable | Self::FailedTerminal)
     }
 
+    /// Entry is being worked on by a queue processor and must not be edited
+    pub fn is_in_flight(&self) -> bool {
+        matches!(
+            self,
+            Self::Claimed | Self::Rebasing | Self::Testing | Self::ReadyToMerge | Self::Merging
+        )
+    }
+
+    /// Stable snake_case name used for persistence and display
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Claimed => "claimed",
+            Self::Rebasing => "rebasing",
+            Self::Testing => "testing",
+            Self::ReadyToMerge => "ready_to_merge",
+            Self::Merging => "merging",
+            Self::Merged => "merged",
+            Self::FailedRetryable => "failed_retryable",
+            Self::FailedTerminal => "failed_terminal",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
     pub fn transition_to(&self, new: Self) -> Result<Self, Error> {
         match (self, new) {
             // Valid transitions
@@ -44,7 +69,14 @@ impl QueueStatus {
             (Self::Testing, Self::FailedRetryable) => Ok(new),
             (Self::Testing, Self::FailedTerminal) => Ok(new),
             (Self::Claimed, Self::FailedRetryable) => Ok(new),
+            // Sync conflicts and landing failures send the entry back to its author
+            (Self::Rebasing, Self::FailedRetryable) => Ok(new),
+            (Self::Merging, Self::FailedRetryable) => Ok(new),
+            // Entries behind the culprit of a failed speculative batch go back in line
+            (Self::Testing, Self::Pending) => Ok(new),
             (Self::Pending, Self::FailedRetryable) => Ok(new),
+            // A fixed session is queued again
+            (Self::FailedRetryable, Self::Pending) => Ok(new),
             // Invalid transitions
             _ => Err(Error::InvalidState(format!(
                 "Invalid transition from {:?} to {:?}",
@@ -54,6 +86,32 @@ impl QueueStatus {
     }
 }
 
+impl std::fmt::Display for QueueStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl std::str::FromStr for QueueStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "claimed" => Ok(Self::Claimed),
+            "rebasing" => Ok(Self::Rebasing),
+            "testing" => Ok(Self::Testing),
+            "ready_to_merge" => Ok(Self::ReadyToMerge),
+            "merging" => Ok(Self::Merging),
+            "merged" => Ok(Self::Merged),
+            "failed_retryable" => Ok(Self::FailedRetryable),
+            "failed_terminal" => Ok(Self::FailedTerminal),
+            "cancelled" => Ok(Self::Cancelled),
+            other => Err(Error::ParseError(format!("Unknown queue status: {other}"))),
+        }
+    }
+}
+
 /// Queue entry identifier
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct QueueEntryId(String);
@@ -107,6 +165,9 @@ impl std::fmt::Display for SessionName {
 /// Maximum priority value
 pub const MAX_PRIORITY: u32 = 100;
 
+/// Priority used when none is given (lower values are processed first)
+pub const DEFAULT_PRIORITY: u32 = 50;
+
 /// Queue entry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueueEntry {
@@ -115,6 +176,9 @@ pub struct QueueEntry {
     pub priority: u32,
     pub enqueued_at: DateTime<Utc>,
     pub status: QueueStatus,
+    /// Speculative batch the entry is being (or was) landed in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
 }
 
 impl QueueEntry {
@@ -131,6 +195,7 @@ impl QueueEntry {
             priority,
             enqueued_at: Utc::now(),
             status: QueueStatus::Pending,
+            batch_id: None,
         })
     }
 }
@@ -146,6 +211,11 @@ impl Queue {
         Self::default()
     }
 
+    /// Rebuild a queue from entries that are already in processing order
+    pub fn from_entries(entries: Vec<QueueEntry>) -> Self {
+        Self { entries }
+    }
+
     pub fn len(&self) -> usize {
         self.entries.len()
     }
@@ -6,7 +6,7 @@
 //! - Railway-Oriented Programming for error chaining
 //! - Each use case takes domain types and returns Results
 
-use crate::queue::{Queue, QueueEntry, QueueEntryId, QueueStatus, SessionName};
+use crate::queue::{Queue, QueueEntry, QueueEntryId, QueueStatus, SessionName, MAX_PRIORITY};
 use crate::Error;
 
 /// Domain errors for queue operations
@@ -61,8 +61,11 @@ impl From<&QueueEntry> for QueueEntryView {
 
 /// Enqueue a session into the queue
 ///
+/// A session whose entry has finished (merged, cancelled or failed) is queued
+/// again in place of that entry; a retryable failure keeps its entry id.
+///
 /// # Errors
-/// Returns `DomainError::AlreadyExists` if session is already in queue.
+/// Returns `DomainError::AlreadyExists` if session is pending or in flight.
 /// Returns `DomainError::InvalidPriority` if priority is invalid.
 pub fn enqueue_session(
     queue: &Queue,
@@ -76,24 +79,47 @@ pub fn enqueue_session(
             to: "session".to_string(),
         })?;
 
-    // Check if session already exists
-    if queue.find_by_session(&session_name).is_some() {
-        return Err(DomainError::AlreadyExists(session));
-    }
+    // A finished entry is replaced; a live one keeps its place
+    let previous = queue.find_by_session(&session_name);
+    let retried_id = match previous {
+        Some(entry) if entry.status == QueueStatus::FailedRetryable => {
+            entry
+                .status
+                .transition_to(QueueStatus::Pending)
+                .map_err(|_| DomainError::InvalidStateTransition {
+                    from: entry.status.to_string(),
+                    to: QueueStatus::Pending.to_string(),
+                })?;
+            Some(entry.id.clone())
+        }
+        Some(entry) if !entry.status.is_terminal() => {
+            return Err(DomainError::AlreadyExists(session));
+        }
+        _ => None,
+    };
 
     // Create entry
-    let id = QueueEntryId::new(format!("q-{}", chrono::Utc::now().timestamp_millis())).map_err(
-        |_| DomainError::InvalidStateTransition {
+    let id = match retried_id {
+        Some(id) => id,
+        None => QueueEntryId::new(format!(
+            "q-{}-{}",
+            chrono::Utc::now().timestamp_millis(),
+            session_name
+        ))
+        .map_err(|_| DomainError::InvalidStateTransition {
             from: "timestamp".to_string(),
             to: "id".to_string(),
-        },
-    )?;
+        })?,
+    };
 
     let entry = QueueEntry::new(id, session_name, priority)
         .map_err(|_| DomainError::InvalidPriority(priority))?;
 
     // Add to queue
     let mut new_queue = queue.clone();
+    if let Some(previous) = previous {
+        new_queue.dequeue(&previous.id);
+    }
     new_queue.enqueue(entry);
     Ok(new_queue)
 }
@@ -110,11 +136,144 @@ pub fn dequeue_session(queue: &Queue, session: &str) -> Result<Queue, DomainErro
         .find_by_session(&session_name)
         .ok_or_else(|| DomainError::NotFound(session.to_string()))?;
 
+    if entry.status.is_in_flight() {
+        return Err(DomainError::InvalidStateTransition {
+            from: entry.status.to_string(),
+            to: "removed".to_string(),
+        });
+    }
+
     let mut new_queue = queue.clone();
     new_queue.dequeue(&entry.id);
     Ok(new_queue)
 }
 
+/// Change the priority of a pending session, moving it to its new place in line
+///
+/// # Errors
+/// Returns `DomainError::NotFound` if session is not in queue.
+/// Returns `DomainError::InvalidPriority` if priority is invalid.
+/// Returns `DomainError::InvalidStateTransition` if the entry is no longer pending.
+pub fn reprioritize_session(
+    queue: &Queue,
+    session: &str,
+    priority: u32,
+) -> Result<Queue, DomainError> {
+    if priority > MAX_PRIORITY {
+        return Err(DomainError::InvalidPriority(priority));
+    }
+
+    let session_name =
+        SessionName::new(session).map_err(|_| DomainError::NotFound(session.to_string()))?;
+
+    let entry = queue
+        .find_by_session(&session_name)
+        .ok_or_else(|| DomainError::NotFound(session.to_string()))?;
+
+    if entry.status != QueueStatus::Pending {
+        return Err(DomainError::InvalidStateTransition {
+            from: entry.status.to_string(),
+            to: "reprioritized".to_string(),
+        });
+    }
+
+    let mut new_queue = queue.clone();
+    let entry = new_queue
+        .dequeue(&entry.id)
+        .map(|e| QueueEntry { priority, ..e })
+        .ok_or_else(|| DomainError::NotFound(session.to_string()))?;
+    new_queue.enqueue(entry);
+    Ok(new_queue)
+}
+
+/// Move an entry to a new status, enforcing `QueueStatus::transition_to`
+///
+/// # Errors
+/// Returns `DomainError::NotFound` if the entry is not in queue.
+/// Returns `DomainError::InvalidStateTransition` if the status change is not allowed.
+pub fn transition_entry(
+    queue: &Queue,
+    id: &QueueEntryId,
+    status: QueueStatus,
+) -> Result<Queue, DomainError> {
+    let entry = queue
+        .find(id)
+        .ok_or_else(|| DomainError::NotFound(id.to_string()))?;
+
+    let next = entry
+        .status
+        .transition_to(status)
+        .map_err(|_| DomainError::InvalidStateTransition {
+            from: entry.status.to_string(),
+            to: status.to_string(),
+        })?;
+
+    let entries = queue
+        .entries()
+        .iter()
+        .cloned()
+        .map(|e| {
+            if e.id == *id {
+                // Going back in line detaches the entry from its batch
+                let batch_id = if next == QueueStatus::Pending {
+                    None
+                } else {
+                    e.batch_id.clone()
+                };
+                QueueEntry {
+                    status: next,
+                    batch_id,
+                    ..e
+                }
+            } else {
+                e
+            }
+        })
+        .collect();
+    Ok(Queue::from_entries(entries))
+}
+
+/// Claim up to `limit` pending entries, in queue order, for one batch
+///
+/// Returns the updated queue and the claimed entry ids in queue order.
+///
+/// # Errors
+/// Returns `DomainError::InvalidStateTransition` if a claim is not allowed.
+pub fn claim_batch(
+    queue: &Queue,
+    limit: usize,
+    batch_id: &str,
+) -> Result<(Queue, Vec<QueueEntryId>), DomainError> {
+    let ids: Vec<QueueEntryId> = queue
+        .entries()
+        .iter()
+        .filter(|e| e.status == QueueStatus::Pending)
+        .take(limit)
+        .map(|e| e.id.clone())
+        .collect();
+
+    let claimed = ids.iter().try_fold(queue.clone(), |q, id| {
+        transition_entry(&q, id, QueueStatus::Claimed)
+    })?;
+
+    let entries = claimed
+        .entries()
+        .iter()
+        .cloned()
+        .map(|e| {
+            if ids.contains(&e.id) {
+                QueueEntry {
+                    batch_id: Some(batch_id.to_string()),
+                    ..e
+                }
+            } else {
+                e
+            }
+        })
+        .collect();
+    Ok((Queue::from_entries(entries), ids))
+}
+
 /// List all queue entries
 #[must_use]
 pub fn list_queue(queue: &Queue) -> Vec<QueueEntryView> {
@@ -164,7 +323,12 @@ pub fn insert_at_position(
     }
 
     // Create entry
-    let id = QueueEntryId::new(format!("q-{}", chrono::Utc::now().timestamp_millis())).map_err(
+    let id = QueueEntryId::new(format!(
+        "q-{}-{}",
+        chrono::Utc::now().timestamp_millis(),
+        session_name
+    ))
+    .map_err(
         |_| DomainError::InvalidStateTransition {
             from: "timestamp".to_string(),
             to: "id".to_string(),
@@ -203,6 +367,59 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn with_status(queue: &Queue, status: QueueStatus) -> Queue {
+        Queue::from_entries(
+            queue
+                .entries()
+                .iter()
+                .cloned()
+                .map(|e| QueueEntry { status, ..e })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_enqueue_after_retryable_failure_requeues_entry() {
+        let queue = enqueue_session(&Queue::new(), "test-session".to_string(), 10).unwrap();
+        let id = queue.entries()[0].id.clone();
+        let failed = with_status(&queue, QueueStatus::FailedRetryable);
+
+        let requeued = enqueue_session(&failed, "test-session".to_string(), 30).unwrap();
+
+        assert_eq!(requeued.len(), 1);
+        let entry = &requeued.entries()[0];
+        assert_eq!(entry.id, id);
+        assert_eq!(entry.status, QueueStatus::Pending);
+        assert_eq!(entry.priority, 30);
+    }
+
+    #[test]
+    fn test_enqueue_replaces_finished_entry() {
+        let queue = enqueue_session(&Queue::new(), "test-session".to_string(), 10).unwrap();
+        for status in [
+            QueueStatus::Merged,
+            QueueStatus::FailedTerminal,
+            QueueStatus::Cancelled,
+        ] {
+            let finished = with_status(&queue, status);
+
+            let requeued = enqueue_session(&finished, "test-session".to_string(), 10).unwrap();
+
+            assert_eq!(requeued.len(), 1);
+            assert_eq!(requeued.entries()[0].status, QueueStatus::Pending);
+        }
+    }
+
+    #[test]
+    fn test_enqueue_in_flight_session_fails() {
+        let queue = enqueue_session(&Queue::new(), "test-session".to_string(), 10).unwrap();
+        let claimed = with_status(&queue, QueueStatus::Testing);
+
+        let result = enqueue_session(&claimed, "test-session".to_string(), 10);
+
+        assert!(matches!(result, Err(DomainError::AlreadyExists(_))));
+    }
+
     #[test]
     fn test_dequeue_session() {
         let queue = Queue::new();
@@ -213,6 +430,93 @@ mod tests {
         assert!(new_queue.is_empty());
     }
 
+    #[test]
+    fn test_dequeue_in_flight_session_fails() {
+        let queue = enqueue_session(&Queue::new(), "test-session".to_string(), 10).unwrap();
+        let claimed: Vec<QueueEntry> = queue
+            .entries()
+            .iter()
+            .cloned()
+            .map(|e| QueueEntry {
+                status: QueueStatus::Claimed,
+                ..e
+            })
+            .collect();
+        let queue = Queue::from_entries(claimed);
+        let result = dequeue_session(&queue, "test-session");
+        assert!(matches!(
+            result,
+            Err(DomainError::InvalidStateTransition { .. })
+        ));
+    }
+
+    #[test]
+    fn test_reprioritize_session_moves_entry() {
+        let queue = enqueue_session(&Queue::new(), "first".to_string(), 10).unwrap();
+        let queue = enqueue_session(&queue, "second".to_string(), 20).unwrap();
+        let queue = reprioritize_session(&queue, "second", 5).unwrap();
+        let order: Vec<&str> = queue.entries().iter().map(|e| e.session.as_str()).collect();
+        assert_eq!(order, vec!["second", "first"]);
+        assert_eq!(queue.entries()[0].priority, 5);
+    }
+
+    #[test]
+    fn test_reprioritize_rejects_invalid_priority() {
+        let queue = enqueue_session(&Queue::new(), "first".to_string(), 10).unwrap();
+        let result = reprioritize_session(&queue, "first", MAX_PRIORITY + 1);
+        assert!(matches!(result, Err(DomainError::InvalidPriority(_))));
+    }
+
+    #[test]
+    fn test_transition_entry_follows_state_machine() {
+        let queue = enqueue_session(&Queue::new(), "first".to_string(), 10).unwrap();
+        let id = queue.entries()[0].id.clone();
+
+        let queue = transition_entry(&queue, &id, QueueStatus::Claimed).unwrap();
+        assert_eq!(queue.entries()[0].status, QueueStatus::Claimed);
+
+        let result = transition_entry(&queue, &id, QueueStatus::Merged);
+        assert!(matches!(
+            result,
+            Err(DomainError::InvalidStateTransition { .. })
+        ));
+    }
+
+    #[test]
+    fn test_claim_batch_takes_pending_in_order() {
+        let queue = enqueue_session(&Queue::new(), "a".to_string(), 10).unwrap();
+        let queue = enqueue_session(&queue, "b".to_string(), 20).unwrap();
+        let queue = enqueue_session(&queue, "c".to_string(), 30).unwrap();
+
+        let (queue, ids) = claim_batch(&queue, 2, "b-1").unwrap();
+        assert_eq!(ids.len(), 2);
+        let claimed: Vec<(&str, QueueStatus, Option<&str>)> = queue
+            .entries()
+            .iter()
+            .map(|e| (e.session.as_str(), e.status, e.batch_id.as_deref()))
+            .collect();
+        assert_eq!(
+            claimed,
+            vec![
+                ("a", QueueStatus::Claimed, Some("b-1")),
+                ("b", QueueStatus::Claimed, Some("b-1")),
+                ("c", QueueStatus::Pending, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_requeue_clears_batch() {
+        let queue = enqueue_session(&Queue::new(), "a".to_string(), 10).unwrap();
+        let (queue, ids) = claim_batch(&queue, 1, "b-1").unwrap();
+        let queue = [QueueStatus::Rebasing, QueueStatus::Testing, QueueStatus::Pending]
+            .into_iter()
+            .try_fold(queue, |q, status| transition_entry(&q, &ids[0], status))
+            .unwrap();
+        assert_eq!(queue.entries()[0].status, QueueStatus::Pending);
+        assert_eq!(queue.entries()[0].batch_id, None);
+    }
+
     #[test]
     fn test_list_queue() {
         let queue = Queue::new();
@@ -0,0 +1,1993 @@
+//! In-memory implementation of [`VcsBackend`] for tests
+//!
+//! [`FakeBackend`] models a JJ repository without a `jj` binary. Commits hold
+//! complete file trees, every workspace has a working-copy commit, bookmarks
+//! point at commits and each mutation is recorded in an operation log that can
+//! be restored. Rewritten commits keep their change id and get a new commit id.
+//!
+//! Rebasing replays each commit's changes onto the destination. A path changed
+//! differently on both sides is marked conflicted in the rebased commit, as JJ
+//! does; the [`VcsBackend::rebase`] implementation then undoes the rebase and
+//! returns [`VcsError::Conflict`], like [`JjBackend`](super::JjBackend).
+//!
+//! Failures are scripted per [`FakeOp`]: a forced conflict, a rejected push or
+//! any other error, for the next call or the next few. A workspace whose
+//! working-copy commit is rewritten from another workspace becomes stale and
+//! refuses every command until [`FakeBackend::update_stale`], which is also how
+//! tests simulate a stale working copy with [`FakeBackend::mark_stale`].
+//!
+//! Clones share the same repository, so a test can keep one handle for
+//! inspection and hand another to the code under test.
+//!
+//! Only built for this crate's tests and with the `test-utils` feature, which
+//! dependents enable from their dev-dependencies.
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use super::{
+    BackendType, Branch, BranchName, Change, ChangeId, CommitId, RepoStatus, VcsBackend, VcsError,
+    VcsResult,
+};
+use crate::jj::DiffSummary;
+
+/// Commit id of the root commit every history starts from
+pub const ROOT_COMMIT_ID: &str = "000000000000";
+
+/// Change id of the root commit
+const ROOT_CHANGE_ID: &str = "zzzzzzzzzzzz";
+
+/// Workspace created with the repository
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+const AUTHOR: &str = "fake@example.com";
+
+/// Commit timestamps count up from here, one second per allocated id
+const EPOCH: i64 = 1_700_000_000;
+
+/// Operations that scripted failures can target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FakeOp {
+    Status,
+    Log,
+    Diff,
+    Commit,
+    Rebase,
+    Squash,
+    Merge,
+    New,
+    Edit,
+    Bookmark,
+    Push,
+    Fetch,
+    Workspace,
+    Restore,
+}
+
+/// A scripted failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FakeFailure {
+    /// The operation conflicts in these paths. A rebase still succeeds and
+    /// leaves the paths conflicted; other operations fail.
+    Conflict(Vec<String>),
+    /// The remote refuses the push
+    PushRejected(String),
+    /// Any other failure, reported as [`VcsError::OperationFailed`]
+    Error(String),
+}
+
+/// A commit in the fake repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeCommit {
+    pub change_id: String,
+    pub commit_id: String,
+    pub parents: Vec<String>,
+    pub description: String,
+    /// Every file in the commit, path to contents
+    pub tree: BTreeMap<String, String>,
+    /// Paths left conflicted by a rebase
+    pub conflicts: BTreeSet<String>,
+    pub timestamp: i64,
+    /// Allocation order, used to sort logs newest first
+    seq: u64,
+}
+
+/// An entry in the operation log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeOperation {
+    pub id: String,
+    pub description: String,
+    pub timestamp: i64,
+    repo: Repo,
+}
+
+/// Working-copy changes and conflicts of a workspace
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FakeStatus {
+    /// `(kind, path)` with kind `A`, `M` or `D`
+    pub changes: Vec<(char, String)>,
+    pub conflicts: Vec<String>,
+}
+
+/// The part of the state the operation log snapshots
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Repo {
+    commits: BTreeMap<String, FakeCommit>,
+    workspaces: BTreeMap<String, Workspace>,
+    bookmarks: BTreeMap<String, String>,
+    remote_bookmarks: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Workspace {
+    path: String,
+    working_copy: String,
+}
+
+#[derive(Debug)]
+struct Scripted {
+    op: FakeOp,
+    failure: FakeFailure,
+    remaining: usize,
+}
+
+#[derive(Debug)]
+struct State {
+    repo: Repo,
+    stale: BTreeSet<String>,
+    operations: Vec<FakeOperation>,
+    failures: Vec<Scripted>,
+    next_id: u64,
+}
+
+/// In-memory JJ repository; see the module docs
+#[derive(Debug, Clone)]
+pub struct FakeBackend {
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeBackend {
+    /// Create a repository whose default workspace lives at `root`
+    ///
+    /// The default workspace starts with an empty working-copy commit on top
+    /// of the root commit, and `main` points at the root commit.
+    pub fn new(root: impl Into<String>) -> Self {
+        let root_commit = FakeCommit {
+            change_id: ROOT_CHANGE_ID.to_string(),
+            commit_id: ROOT_COMMIT_ID.to_string(),
+            parents: Vec::new(),
+            description: String::new(),
+            tree: BTreeMap::new(),
+            conflicts: BTreeSet::new(),
+            timestamp: EPOCH,
+            seq: 0,
+        };
+        let mut state = State {
+            repo: Repo::default(),
+            stale: BTreeSet::new(),
+            operations: Vec::new(),
+            failures: Vec::new(),
+            next_id: 0,
+        };
+        state
+            .repo
+            .commits
+            .insert(ROOT_COMMIT_ID.to_string(), root_commit);
+        state
+            .repo
+            .bookmarks
+            .insert("main".to_string(), ROOT_COMMIT_ID.to_string());
+        let working_copy = state.new_commit(
+            None,
+            vec![ROOT_COMMIT_ID.to_string()],
+            String::new(),
+            BTreeMap::new(),
+            BTreeSet::new(),
+        );
+        state.repo.workspaces.insert(
+            DEFAULT_WORKSPACE.to_string(),
+            Workspace {
+                path: root.into(),
+                working_copy,
+            },
+        );
+        state.record("initialize repo");
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // ───────────────────────────────────────────────────────────────────────
+    // Scripting
+    // ───────────────────────────────────────────────────────────────────────
+
+    /// Fail the next `op` with `failure`
+    pub fn fail_next(&self, op: FakeOp, failure: FakeFailure) {
+        self.fail_times(op, 1, failure);
+    }
+
+    /// Fail the next `times` calls of `op` with `failure`
+    pub fn fail_times(&self, op: FakeOp, times: usize, failure: FakeFailure) {
+        self.lock().failures.push(Scripted {
+            op,
+            failure,
+            remaining: times,
+        });
+    }
+
+    /// Make `workspace` refuse commands until [`Self::update_stale`]
+    pub fn mark_stale(&self, workspace: &str) {
+        self.lock().stale.insert(workspace.to_string());
+    }
+
+    /// Bring a stale workspace up to date, like `jj workspace update-stale`
+    pub fn update_stale(&self, workspace: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.workspace(workspace)?;
+        if state.stale.remove(workspace) {
+            state.record(&format!("update stale working copy of {workspace}"));
+        }
+        Ok(())
+    }
+
+    // ───────────────────────────────────────────────────────────────────────
+    // Editing
+    // ───────────────────────────────────────────────────────────────────────
+
+    /// Write a file in the working copy of `workspace`
+    pub fn write_file(&self, workspace: &str, path: &str, contents: &str) -> VcsResult<()> {
+        self.edit_working_copy(workspace, |tree| {
+            tree.insert(path.to_string(), contents.to_string());
+        })
+    }
+
+    /// Delete a file from the working copy of `workspace`
+    pub fn remove_file(&self, workspace: &str, path: &str) -> VcsResult<()> {
+        self.edit_working_copy(workspace, |tree| {
+            tree.remove(path);
+        })
+    }
+
+    fn edit_working_copy(
+        &self,
+        workspace: &str,
+        edit: impl FnOnce(&mut BTreeMap<String, String>),
+    ) -> VcsResult<()> {
+        let mut state = self.lock();
+        let working_copy = state.working_copy_id(workspace)?;
+        let mut commit = state.commit(&working_copy)?.clone();
+        edit(&mut commit.tree);
+        let rewritten = state.rewrite(Some(workspace), commit);
+        state.record(&format!("snapshot working copy {rewritten}"));
+        Ok(())
+    }
+
+    /// Land a commit with `files` directly on `bookmark`, as if another
+    /// session had merged it. Returns the new commit id.
+    pub fn commit_on(
+        &self,
+        bookmark: &str,
+        message: &str,
+        files: &[(&str, &str)],
+    ) -> VcsResult<String> {
+        let mut state = self.lock();
+        let parent = state
+            .repo
+            .bookmarks
+            .get(bookmark)
+            .cloned()
+            .ok_or_else(|| VcsError::BranchNotFound(bookmark.to_string()))?;
+        let mut tree = state.commit(&parent)?.tree.clone();
+        for (path, contents) in files {
+            tree.insert((*path).to_string(), (*contents).to_string());
+        }
+        let id = state.new_commit(
+            None,
+            vec![parent],
+            message.to_string(),
+            tree,
+            BTreeSet::new(),
+        );
+        state
+            .repo
+            .bookmarks
+            .insert(bookmark.to_string(), id.clone());
+        state.record(&format!("commit {id} on {bookmark}"));
+        Ok(id)
+    }
+
+    // ───────────────────────────────────────────────────────────────────────
+    // Commands (scripted failures and stale workspaces apply)
+    // ───────────────────────────────────────────────────────────────────────
+
+    /// Working-copy changes of `workspace`, like `jj status`
+    pub fn status_of(&self, workspace: &str) -> VcsResult<FakeStatus> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Status)?;
+        let working_copy = state.commit(&state.working_copy_id(workspace)?)?.clone();
+        let parent = state.first_parent_tree(&working_copy)?;
+        Ok(FakeStatus {
+            changes: diff_trees(&parent, &working_copy.tree),
+            conflicts: working_copy.conflicts.iter().cloned().collect(),
+        })
+    }
+
+    /// Commits in `revset`, newest first, like `jj log -r`
+    pub fn log_revset(&self, workspace: &str, revset: &str) -> VcsResult<Vec<FakeCommit>> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Log)?;
+        state.revset(workspace, revset)
+    }
+
+    /// Changed paths between two revisions, like `jj diff --summary`
+    pub fn diff_revisions(
+        &self,
+        workspace: &str,
+        from: &str,
+        to: &str,
+    ) -> VcsResult<Vec<(char, String)>> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Diff)?;
+        let from = state.resolve(workspace, from)?;
+        let to = state.resolve(workspace, to)?;
+        Ok(diff_trees(&from.tree, &to.tree))
+    }
+
+    /// Describe the working copy and start a new empty one on top, like
+    /// `jj commit -m`. Returns the id of the committed change.
+    pub fn commit_workspace(&self, workspace: &str, message: &str) -> VcsResult<String> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Commit)?;
+        let mut commit = state.commit(&state.working_copy_id(workspace)?)?.clone();
+        commit.description = message.to_string();
+        let committed = state.rewrite(Some(workspace), commit);
+        let tree = state.commit(&committed)?.tree.clone();
+        let conflicts = state.commit(&committed)?.conflicts.clone();
+        let working_copy = state.new_commit(
+            None,
+            vec![committed.clone()],
+            String::new(),
+            tree,
+            conflicts,
+        );
+        state.set_working_copy(workspace, working_copy)?;
+        state.record(&format!("commit {committed}"));
+        Ok(committed)
+    }
+
+    /// Rebase `source` and every descendant not already on `destination`,
+    /// like `jj rebase -b source -d destination`
+    ///
+    /// Conflicts do not fail the rebase; the conflicted paths are recorded on
+    /// the rebased commits and returned.
+    pub fn rebase_onto(
+        &self,
+        workspace: &str,
+        source: &str,
+        destination: &str,
+    ) -> VcsResult<Vec<String>> {
+        let mut state = self.lock();
+        let forced = state.gate(workspace, FakeOp::Rebase)?;
+        state.rebase(workspace, source, destination, &forced)
+    }
+
+    /// Move the changes in `from` into `into`, like
+    /// `jj squash --from from --into into -m message`
+    ///
+    /// The squashed commits are abandoned. A workspace left on the result
+    /// gets a new empty working-copy commit on top of it.
+    pub fn squash(&self, workspace: &str, from: &str, into: &str, message: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        let forced = state.gate(workspace, FakeOp::Squash)?;
+        let sources: Vec<FakeCommit> = state
+            .revset(workspace, from)?
+            .into_iter()
+            .filter(|c| c.commit_id != ROOT_COMMIT_ID)
+            .collect();
+        let (Some(tip), Some(oldest)) = (sources.first().cloned(), sources.last().cloned()) else {
+            return Ok(());
+        };
+        let target = state.resolve(workspace, into)?;
+        let base = state.first_parent_tree(&oldest)?;
+        let (tree, mut conflicts) = merge_trees(&base, &tip.tree, &target.tree);
+        conflicts.extend(forced);
+        if !conflicts.is_empty() {
+            return Err(conflict_error(
+                &format!("squashing into {into}"),
+                &conflicts,
+            ));
+        }
+
+        let mut rewritten = target.clone();
+        rewritten.tree = tree;
+        rewritten.description = message.to_string();
+        let new_target = state.rewrite(Some(workspace), rewritten);
+        let abandoned: BTreeMap<String, String> = sources
+            .iter()
+            .map(|c| (c.commit_id.clone(), new_target.clone()))
+            .collect();
+        state.replace(Some(workspace), &abandoned);
+        state.refresh_working_copies_on(&new_target)?;
+        state.record(&format!("squash {} commits into {into}", sources.len()));
+        Ok(())
+    }
+
+    /// Create a new working-copy commit on top of `parents`, like `jj new`
+    pub fn new_change(&self, workspace: &str, parents: &[&str]) -> VcsResult<String> {
+        let mut state = self.lock();
+        let forced = state.gate(workspace, FakeOp::New)?;
+        let parents = if parents.is_empty() {
+            vec![state.working_copy_id(workspace)?]
+        } else {
+            parents
+                .iter()
+                .map(|rev| state.resolve(workspace, rev).map(|c| c.commit_id))
+                .collect::<VcsResult<Vec<_>>>()?
+        };
+        let (tree, mut conflicts) = state.merged_tree(&parents)?;
+        conflicts.extend(forced);
+        let id = state.new_commit(None, parents, String::new(), tree, conflicts);
+        state.set_working_copy(workspace, id.clone())?;
+        state.record(&format!("new empty commit {id}"));
+        Ok(id)
+    }
+
+    /// Make `revision` the working-copy commit, like `jj edit`
+    pub fn edit(&self, workspace: &str, revision: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Edit)?;
+        let target = state.resolve(workspace, revision)?;
+        state.set_working_copy(workspace, target.commit_id.clone())?;
+        state.record(&format!("edit commit {}", target.commit_id));
+        Ok(())
+    }
+
+    /// Create a bookmark, like `jj bookmark create`
+    pub fn create_bookmark(&self, workspace: &str, name: &str, revision: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Bookmark)?;
+        if state.repo.bookmarks.contains_key(name) {
+            return Err(VcsError::InvalidOperation(format!(
+                "Bookmark already exists: {name}"
+            )));
+        }
+        let target = state.resolve(workspace, revision)?.commit_id;
+        state.repo.bookmarks.insert(name.to_string(), target);
+        state.record(&format!("create bookmark {name}"));
+        Ok(())
+    }
+
+    /// Point a bookmark at `revision`, like `jj bookmark set`
+    ///
+    /// Without `allow_backwards`, moving an existing bookmark anywhere but
+    /// forward is refused.
+    pub fn set_bookmark(
+        &self,
+        workspace: &str,
+        name: &str,
+        revision: &str,
+        allow_backwards: bool,
+    ) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Bookmark)?;
+        let target = state.resolve(workspace, revision)?.commit_id;
+        if let Some(current) = state.repo.bookmarks.get(name) {
+            if !allow_backwards && !state.ancestors(&target).contains(current) {
+                return Err(VcsError::InvalidOperation(format!(
+                    "Refusing to move bookmark backwards or sideways: {name}"
+                )));
+            }
+        }
+        state.repo.bookmarks.insert(name.to_string(), target);
+        state.record(&format!("point bookmark {name} to {revision}"));
+        Ok(())
+    }
+
+    /// Delete a bookmark, like `jj bookmark delete`
+    pub fn delete_bookmark(&self, workspace: &str, name: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Bookmark)?;
+        state
+            .repo
+            .bookmarks
+            .remove(name)
+            .ok_or_else(|| VcsError::BranchNotFound(name.to_string()))?;
+        state.record(&format!("delete bookmark {name}"));
+        Ok(())
+    }
+
+    /// Push one bookmark to the remote, like `jj git push --bookmark`
+    pub fn push_bookmark(&self, workspace: &str, name: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Push)?;
+        state.push(name)?;
+        state.record(&format!("push bookmark {name} to git remote origin"));
+        Ok(())
+    }
+
+    /// Push every bookmark to the remote, like `jj git push --all`
+    pub fn push_all(&self, workspace: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Push)?;
+        let names: Vec<String> = state.repo.bookmarks.keys().cloned().collect();
+        for name in &names {
+            state.push(name)?;
+        }
+        state.record("push all bookmarks to git remote origin");
+        Ok(())
+    }
+
+    /// Create local bookmarks for remote ones, like `jj git fetch`
+    pub fn fetch(&self, workspace: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Fetch)?;
+        let remote = state.repo.remote_bookmarks.clone();
+        for (name, target) in remote {
+            state.repo.bookmarks.entry(name).or_insert(target);
+        }
+        state.record("fetch from git remote origin");
+        Ok(())
+    }
+
+    /// Add workspace `name` at `path`, starting on `base` or on the parent of
+    /// the acting workspace's working copy
+    pub fn add_workspace_at(
+        &self,
+        workspace: &str,
+        name: &str,
+        path: &str,
+        base: Option<&str>,
+    ) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Workspace)?;
+        if state.repo.workspaces.contains_key(name) {
+            return Err(VcsError::InvalidOperation(format!(
+                "Workspace named '{name}' already exists"
+            )));
+        }
+        let base = match base {
+            Some(rev) => state.resolve(workspace, rev)?.commit_id,
+            None => state
+                .commit(&state.working_copy_id(workspace)?)?
+                .parents
+                .first()
+                .cloned()
+                .unwrap_or_else(|| ROOT_COMMIT_ID.to_string()),
+        };
+        let tree = state.commit(&base)?.tree.clone();
+        let working_copy = state.new_commit(None, vec![base], String::new(), tree, BTreeSet::new());
+        state.repo.workspaces.insert(
+            name.to_string(),
+            Workspace {
+                path: path.to_string(),
+                working_copy,
+            },
+        );
+        state.record(&format!(
+            "create initial working-copy commit in workspace {name}"
+        ));
+        Ok(())
+    }
+
+    /// Stop tracking workspace `name`, like `jj workspace forget`
+    pub fn forget(&self, workspace: &str, name: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Workspace)?;
+        state
+            .repo
+            .workspaces
+            .remove(name)
+            .ok_or_else(|| VcsError::InvalidOperation(format!("No such workspace: {name}")))?;
+        state.stale.remove(name);
+        state.record(&format!("forget workspace {name}"));
+        Ok(())
+    }
+
+    /// Restore the repository to operation `id` (or a unique prefix of it),
+    /// like `jj op restore`
+    pub fn restore_operation(&self, workspace: &str, id: &str) -> VcsResult<()> {
+        let mut state = self.lock();
+        state.gate(workspace, FakeOp::Restore)?;
+        let matching: Vec<&FakeOperation> = state
+            .operations
+            .iter()
+            .filter(|op| op.id.starts_with(id))
+            .collect();
+        let repo = match matching.as_slice() {
+            [op] => op.repo.clone(),
+            [] => {
+                return Err(VcsError::InvalidOperation(format!(
+                    "No operation ID matching \"{id}\""
+                )))
+            }
+            _ => {
+                return Err(VcsError::InvalidOperation(format!(
+                    "Operation ID prefix \"{id}\" is ambiguous"
+                )))
+            }
+        };
+        state.repo = repo;
+        state.record(&format!("restore to operation {id}"));
+        Ok(())
+    }
+
+    /// Restore the repository to the operation before the last, like `jj undo`
+    pub fn undo(&self, workspace: &str) -> VcsResult<()> {
+        let previous = self
+            .lock()
+            .operations
+            .iter()
+            .rev()
+            .nth(1)
+            .map(|op| op.id.clone())
+            .ok_or_else(|| {
+                VcsError::InvalidOperation("Cannot undo repo initialization".to_string())
+            })?;
+        self.restore_operation(workspace, &previous)
+    }
+
+    // ───────────────────────────────────────────────────────────────────────
+    // Inspection (never fails on scripts or stale workspaces)
+    // ───────────────────────────────────────────────────────────────────────
+
+    /// The working-copy commit of `workspace`
+    pub fn working_copy(&self, workspace: &str) -> VcsResult<FakeCommit> {
+        let state = self.lock();
+        state.commit(&state.working_copy_id(workspace)?).cloned()
+    }
+
+    /// Contents of `path` in the working copy of `workspace`
+    pub fn file(&self, workspace: &str, path: &str) -> Option<String> {
+        self.working_copy(workspace)
+            .ok()
+            .and_then(|c| c.tree.get(path).cloned())
+    }
+
+    /// Resolve a single revision as seen from `workspace`
+    pub fn resolve(&self, workspace: &str, revision: &str) -> VcsResult<FakeCommit> {
+        self.lock().resolve(workspace, revision)
+    }
+
+    /// Commit a local bookmark points at
+    pub fn bookmark(&self, name: &str) -> Option<String> {
+        self.lock().repo.bookmarks.get(name).cloned()
+    }
+
+    /// Every local bookmark and the commit it points at
+    pub fn bookmarks(&self) -> BTreeMap<String, String> {
+        self.lock().repo.bookmarks.clone()
+    }
+
+    /// Every bookmark on the remote and the commit it points at
+    pub fn remote_bookmarks(&self) -> BTreeMap<String, String> {
+        self.lock().repo.remote_bookmarks.clone()
+    }
+
+    /// Commit the remote's copy of a bookmark points at
+    pub fn remote_bookmark(&self, name: &str) -> Option<String> {
+        self.lock().repo.remote_bookmarks.get(name).cloned()
+    }
+
+    /// Names of the workspaces in the repository
+    pub fn workspaces(&self) -> Vec<String> {
+        self.lock().repo.workspaces.keys().cloned().collect()
+    }
+
+    /// Directory of `workspace`
+    pub fn workspace_path(&self, workspace: &str) -> VcsResult<String> {
+        self.lock().workspace(workspace).map(|w| w.path.clone())
+    }
+
+    /// Workspace whose directory contains `path`
+    pub fn workspace_at(&self, path: &str) -> Option<String> {
+        self.lock().workspace_at(path)
+    }
+
+    /// Whether `workspace` is stale
+    pub fn is_stale(&self, workspace: &str) -> bool {
+        self.lock().stale.contains(workspace)
+    }
+
+    /// The operation log, newest first
+    pub fn operations(&self) -> Vec<FakeOperation> {
+        self.lock().operations.iter().rev().cloned().collect()
+    }
+
+    /// Whether `ancestor` is reachable from `descendant`
+    pub fn is_ancestor(&self, ancestor: &str, descendant: &str) -> bool {
+        self.lock().ancestors(descendant).contains(ancestor)
+    }
+
+    fn name_at(&self, path: &str) -> VcsResult<String> {
+        self.workspace_at(path)
+            .ok_or_else(|| VcsError::RepoNotFound(path.to_string()))
+    }
+
+    fn to_change(&self, commit: &FakeCommit) -> Change {
+        let state = self.lock();
+        Change {
+            id: ChangeId::new(commit.change_id.clone()),
+            commit_id: CommitId::new(commit.commit_id.clone()),
+            branch: state
+                .bookmarks_at(&commit.commit_id)
+                .into_iter()
+                .next()
+                .and_then(|name| BranchName::new(name).ok()),
+            description: commit.description.clone(),
+            author: AUTHOR.to_string(),
+            timestamp: commit.timestamp,
+        }
+    }
+}
+
+impl State {
+    fn alloc(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn new_commit(
+        &mut self,
+        change_id: Option<String>,
+        parents: Vec<String>,
+        description: String,
+        tree: BTreeMap<String, String>,
+        conflicts: BTreeSet<String>,
+    ) -> String {
+        let seq = self.alloc();
+        let commit_id = format!("{:012x}", mix(seq));
+        let commit = FakeCommit {
+            change_id: change_id.unwrap_or_else(|| reverse_hex(mix(seq ^ 0x5555))),
+            commit_id: commit_id.clone(),
+            parents,
+            description,
+            tree,
+            conflicts,
+            timestamp: EPOCH + i64::try_from(seq).unwrap_or(i64::MAX - EPOCH),
+            seq,
+        };
+        self.repo.commits.insert(commit_id.clone(), commit);
+        commit_id
+    }
+
+    /// Replace `commit` (matched by commit id) with a rewritten copy and
+    /// return the new commit id
+    fn rewrite(&mut self, acting: Option<&str>, commit: FakeCommit) -> String {
+        let old = commit.commit_id.clone();
+        let new = self.new_commit(
+            Some(commit.change_id),
+            commit.parents,
+            commit.description,
+            commit.tree,
+            commit.conflicts,
+        );
+        self.replace(acting, &BTreeMap::from([(old, new.clone())]));
+        new
+    }
+
+    /// Drop the commits keyed in `mapping` and point every reference to them
+    /// at their replacement. Workspaces other than `acting` whose working copy
+    /// moves become stale.
+    fn replace(&mut self, acting: Option<&str>, mapping: &BTreeMap<String, String>) {
+        for old in mapping.keys() {
+            self.repo.commits.remove(old);
+        }
+        for commit in self.repo.commits.values_mut() {
+            for parent in &mut commit.parents {
+                if let Some(new) = mapping.get(parent) {
+                    parent.clone_from(new);
+                }
+            }
+        }
+        for target in self.repo.bookmarks.values_mut() {
+            if let Some(new) = mapping.get(target) {
+                target.clone_from(new);
+            }
+        }
+        let mut stale = Vec::new();
+        for (name, workspace) in &mut self.repo.workspaces {
+            if let Some(new) = mapping.get(&workspace.working_copy) {
+                workspace.working_copy.clone_from(new);
+                if acting != Some(name.as_str()) {
+                    stale.push(name.clone());
+                }
+            }
+        }
+        self.stale.extend(stale);
+    }
+
+    /// Give every workspace sitting on `commit` a new empty working copy
+    fn refresh_working_copies_on(&mut self, commit: &str) -> VcsResult<()> {
+        let names: Vec<String> = self
+            .repo
+            .workspaces
+            .iter()
+            .filter(|(_, w)| w.working_copy == commit)
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in names {
+            let tree = self.commit(commit)?.tree.clone();
+            let id = self.new_commit(
+                None,
+                vec![commit.to_string()],
+                String::new(),
+                tree,
+                BTreeSet::new(),
+            );
+            self.set_working_copy(&name, id)?;
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, description: &str) {
+        let seq = self.alloc();
+        self.operations.push(FakeOperation {
+            id: format!("{:012x}", mix(seq ^ 0xaaaa)),
+            description: description.to_string(),
+            timestamp: EPOCH + i64::try_from(seq).unwrap_or(i64::MAX - EPOCH),
+            repo: self.repo.clone(),
+        });
+    }
+
+    /// Refuse commands in stale workspaces and apply scripted failures.
+    /// Returns the paths of a scripted conflict for operations that create
+    /// conflicted commits instead of failing.
+    fn gate(&mut self, workspace: &str, op: FakeOp) -> VcsResult<Vec<String>> {
+        self.workspace(workspace)?;
+        if self.stale.contains(workspace) {
+            let current = self.operations.last().map_or("", |o| o.id.as_str());
+            return Err(VcsError::OperationFailed(format!(
+                "The working copy is stale (not updated since operation {current}). \
+                 Run `jj workspace update-stale` to update it."
+            )));
+        }
+        let Some(index) = self.failures.iter().position(|s| s.op == op) else {
+            return Ok(Vec::new());
+        };
+        let failure = self.failures[index].failure.clone();
+        self.failures[index].remaining -= 1;
+        if self.failures[index].remaining == 0 {
+            self.failures.remove(index);
+        }
+        match failure {
+            FakeFailure::Conflict(paths)
+                if matches!(op, FakeOp::Rebase | FakeOp::Squash | FakeOp::New) =>
+            {
+                Ok(paths)
+            }
+            FakeFailure::Conflict(paths) => Err(conflict_error(
+                &format!("{op:?}").to_lowercase(),
+                &paths.into_iter().collect(),
+            )),
+            FakeFailure::PushRejected(reason) => Err(VcsError::OperationFailed(format!(
+                "push rejected: {reason}"
+            ))),
+            FakeFailure::Error(message) => Err(VcsError::OperationFailed(message)),
+        }
+    }
+
+    fn workspace(&self, name: &str) -> VcsResult<&Workspace> {
+        self.repo
+            .workspaces
+            .get(name)
+            .ok_or_else(|| VcsError::RepoNotFound(format!("no workspace named {name}")))
+    }
+
+    fn workspace_at(&self, path: &str) -> Option<String> {
+        let path = path.trim_end_matches('/');
+        self.repo
+            .workspaces
+            .iter()
+            .filter(|(_, w)| {
+                let root = w.path.trim_end_matches('/');
+                path == root || path.starts_with(&format!("{root}/"))
+            })
+            .max_by_key(|(_, w)| w.path.len())
+            .map(|(name, _)| name.clone())
+    }
+
+    fn working_copy_id(&self, workspace: &str) -> VcsResult<String> {
+        self.workspace(workspace).map(|w| w.working_copy.clone())
+    }
+
+    fn set_working_copy(&mut self, workspace: &str, commit: String) -> VcsResult<()> {
+        self.repo
+            .workspaces
+            .get_mut(workspace)
+            .map(|w| w.working_copy = commit)
+            .ok_or_else(|| VcsError::RepoNotFound(format!("no workspace named {workspace}")))
+    }
+
+    fn commit(&self, id: &str) -> VcsResult<&FakeCommit> {
+        self.repo
+            .commits
+            .get(id)
+            .ok_or_else(|| VcsError::CommitNotFound(format!("Revision `{id}` doesn't exist")))
+    }
+
+    fn first_parent_tree(&self, commit: &FakeCommit) -> VcsResult<BTreeMap<String, String>> {
+        commit.parents.first().map_or_else(
+            || Ok(BTreeMap::new()),
+            |p| self.commit(p).map(|c| c.tree.clone()),
+        )
+    }
+
+    fn bookmarks_at(&self, commit: &str) -> Vec<String> {
+        self.repo
+            .bookmarks
+            .iter()
+            .filter(|(_, target)| target.as_str() == commit)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    fn ancestors(&self, commit: &str) -> BTreeSet<String> {
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![commit.to_string()];
+        while let Some(id) = pending.pop() {
+            if let Some(c) = self.repo.commits.get(&id) {
+                if seen.insert(id) {
+                    pending.extend(c.parents.iter().cloned());
+                }
+            }
+        }
+        seen
+    }
+
+    /// Tree of the merge of `parents`, with the paths that conflict
+    fn merged_tree(
+        &self,
+        parents: &[String],
+    ) -> VcsResult<(BTreeMap<String, String>, BTreeSet<String>)> {
+        let Some((first, rest)) = parents.split_first() else {
+            return Ok((BTreeMap::new(), BTreeSet::new()));
+        };
+        let first_commit = self.commit(first)?;
+        let mut tree = first_commit.tree.clone();
+        let mut conflicts = first_commit.conflicts.clone();
+        let mut merged = self.ancestors(first);
+        for parent in rest {
+            let theirs = self.ancestors(parent);
+            let base = merged
+                .intersection(&theirs)
+                .filter_map(|id| self.repo.commits.get(id))
+                .max_by_key(|c| c.seq)
+                .map(|c| c.tree.clone())
+                .unwrap_or_default();
+            let (next, new_conflicts) = merge_trees(&base, &self.commit(parent)?.tree, &tree);
+            tree = next;
+            conflicts.extend(new_conflicts);
+            merged.extend(theirs);
+        }
+        Ok((tree, conflicts))
+    }
+
+    fn rebase(
+        &mut self,
+        workspace: &str,
+        source: &str,
+        destination: &str,
+        forced: &[String],
+    ) -> VcsResult<Vec<String>> {
+        let source = self.resolve(workspace, source)?.commit_id;
+        let destination = self.resolve(workspace, destination)?.commit_id;
+        let on_destination = self.ancestors(&destination);
+        let mut moving: BTreeSet<String> = self
+            .ancestors(&source)
+            .difference(&on_destination)
+            .cloned()
+            .collect();
+        // Descendants of moved commits move with them
+        let commits: Vec<FakeCommit> = self.topo_order().into_iter().cloned().collect();
+        for commit in &commits {
+            if commit.parents.iter().any(|p| moving.contains(p))
+                && !on_destination.contains(&commit.commit_id)
+            {
+                moving.insert(commit.commit_id.clone());
+            }
+        }
+        if moving.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut mapping = BTreeMap::new();
+        let mut conflicted = BTreeSet::new();
+        for commit in commits.iter().filter(|c| moving.contains(&c.commit_id)) {
+            let parents: Vec<String> = commit
+                .parents
+                .iter()
+                .map(|p| {
+                    mapping.get(p).cloned().unwrap_or_else(|| {
+                        if moving.contains(p) {
+                            p.clone()
+                        } else {
+                            destination.clone()
+                        }
+                    })
+                })
+                .collect();
+            let base = self.first_parent_tree(commit)?;
+            let new_parent = parents.first().map_or_else(
+                || Ok(BTreeMap::new()),
+                |p| self.commit(p).map(|c| c.tree.clone()),
+            )?;
+            let parent_conflicts = parents
+                .first()
+                .and_then(|p| self.repo.commits.get(p))
+                .map(|c| c.conflicts.clone())
+                .unwrap_or_default();
+            let (tree, mut conflicts) = merge_trees(&base, &commit.tree, &new_parent);
+            conflicts.extend(commit.conflicts.iter().cloned());
+            conflicts.extend(
+                parent_conflicts
+                    .into_iter()
+                    .filter(|path| base.get(path) == commit.tree.get(path)),
+            );
+            if mapping.is_empty() {
+                conflicts.extend(forced.iter().cloned());
+            }
+            conflicted.extend(conflicts.iter().cloned());
+            let id = self.new_commit(
+                Some(commit.change_id.clone()),
+                parents,
+                commit.description.clone(),
+                tree,
+                conflicts,
+            );
+            mapping.insert(commit.commit_id.clone(), id);
+        }
+        let moved = mapping.len();
+        self.replace(Some(workspace), &mapping);
+        self.record(&format!("rebase {moved} commits onto {destination}"));
+        Ok(conflicted.into_iter().collect())
+    }
+
+    fn push(&mut self, name: &str) -> VcsResult<()> {
+        let target = self
+            .repo
+            .bookmarks
+            .get(name)
+            .cloned()
+            .ok_or_else(|| VcsError::BranchNotFound(name.to_string()))?;
+        if !self.commit(&target)?.conflicts.is_empty() {
+            return Err(VcsError::InvalidOperation(format!(
+                "Won't push commit {target} since it has conflicts"
+            )));
+        }
+        self.repo.remote_bookmarks.insert(name.to_string(), target);
+        Ok(())
+    }
+
+    fn resolve(&self, workspace: &str, revision: &str) -> VcsResult<FakeCommit> {
+        let commits = self.revset(workspace, revision)?;
+        match commits.as_slice() {
+            [commit] => Ok(commit.clone()),
+            [] => Err(VcsError::CommitNotFound(format!(
+                "Revision `{revision}` doesn't exist"
+            ))),
+            _ => Err(VcsError::InvalidOperation(format!(
+                "Revset `{revision}` resolved to more than one revision"
+            ))),
+        }
+    }
+
+    fn revset(&self, workspace: &str, expression: &str) -> VcsResult<Vec<FakeCommit>> {
+        let tokens = tokenize(expression)?;
+        let mut parser = RevsetParser {
+            state: self,
+            workspace,
+            tokens: &tokens,
+            position: 0,
+        };
+        let ids = parser.union()?;
+        if parser.position != tokens.len() {
+            return Err(VcsError::InvalidOperation(format!(
+                "Failed to parse revset: {expression}"
+            )));
+        }
+        Ok(self
+            .topo_order()
+            .into_iter()
+            .rev()
+            .filter(|c| ids.contains(&c.commit_id))
+            .cloned()
+            .collect())
+    }
+
+    /// Every commit, parents before children, otherwise oldest first
+    fn topo_order(&self) -> Vec<&FakeCommit> {
+        let mut pending: Vec<&FakeCommit> = self.repo.commits.values().collect();
+        pending.sort_by_key(|c| c.seq);
+        let mut placed = BTreeSet::new();
+        let mut order = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let before = pending.len();
+            pending.retain(|c| {
+                let ready = c
+                    .parents
+                    .iter()
+                    .all(|p| placed.contains(p) || !self.repo.commits.contains_key(p));
+                if ready {
+                    placed.insert(c.commit_id.clone());
+                    order.push(*c);
+                }
+                !ready
+            });
+            if pending.len() == before {
+                order.append(&mut pending);
+            }
+        }
+        order
+    }
+
+    /// Resolve a symbol: `@`, `name@`, a bookmark, a change id or commit id
+    /// (prefix), `trunk()`-style names and `x-` for the parents of `x`
+    fn symbol(&self, workspace: &str, symbol: &str) -> VcsResult<BTreeSet<String>> {
+        if symbol == "@" {
+            return Ok(BTreeSet::from([self.working_copy_id(workspace)?]));
+        }
+        if let Some(name) = symbol.strip_suffix('@') {
+            if let Some(w) = self.repo.workspaces.get(name) {
+                return Ok(BTreeSet::from([w.working_copy.clone()]));
+            }
+        }
+        if let Some(target) = self.repo.bookmarks.get(symbol) {
+            return Ok(BTreeSet::from([target.clone()]));
+        }
+        let by_change: BTreeSet<String> = self
+            .repo
+            .commits
+            .values()
+            .filter(|c| c.change_id.starts_with(symbol))
+            .map(|c| c.commit_id.clone())
+            .collect();
+        if by_change.len() == 1 {
+            return Ok(by_change);
+        }
+        let by_commit: BTreeSet<String> = self
+            .repo
+            .commits
+            .keys()
+            .filter(|id| id.starts_with(symbol))
+            .cloned()
+            .collect();
+        if by_commit.len() == 1 {
+            return Ok(by_commit);
+        }
+        if let Some(child) = symbol.strip_suffix('-') {
+            let children = self.symbol(workspace, child)?;
+            return Ok(children
+                .iter()
+                .filter_map(|id| self.repo.commits.get(id))
+                .flat_map(|c| c.parents.iter().cloned())
+                .collect());
+        }
+        Err(VcsError::CommitNotFound(format!(
+            "Revision `{symbol}` doesn't exist"
+        )))
+    }
+
+    fn trunk(&self) -> BTreeSet<String> {
+        ["main", "master", "trunk"]
+            .iter()
+            .find_map(|name| self.repo.bookmarks.get(*name))
+            .map_or_else(
+                || BTreeSet::from([ROOT_COMMIT_ID.to_string()]),
+                |target| BTreeSet::from([target.clone()]),
+            )
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// REVSETS
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Symbol(String),
+    Open,
+    Close,
+    Union,
+    Intersection,
+    Not,
+    Ancestors,
+    Range,
+}
+
+fn tokenize(expression: &str) -> VcsResult<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' | '\t' | '\n' => {
+                chars.next();
+            }
+            '(' | ')' | '|' | '&' | '~' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    '|' => Token::Union,
+                    '&' => Token::Intersection,
+                    _ => Token::Not,
+                });
+            }
+            ':' | '.' => {
+                chars.next();
+                if chars.next() != Some(c) {
+                    return Err(VcsError::InvalidOperation(format!(
+                        "Failed to parse revset: {expression}"
+                    )));
+                }
+                tokens.push(if c == ':' {
+                    Token::Ancestors
+                } else {
+                    Token::Range
+                });
+            }
+            c if c.is_alphanumeric() || matches!(c, '@' | '-' | '_' | '/') => {
+                let mut symbol = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || matches!(c, '@' | '-' | '_' | '/') {
+                        symbol.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Symbol(symbol));
+            }
+            _ => {
+                return Err(VcsError::InvalidOperation(format!(
+                    "Failed to parse revset: {expression}"
+                )))
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent evaluator for the revsets the commands use:
+/// `x | y`, `x & y`, `~x`, `::x`, `x..y`, `heads(x)`, `ancestors(x)`,
+/// `conflicts()`, `trunk()`, `root()`, `all()` and symbols
+struct RevsetParser<'a> {
+    state: &'a State,
+    workspace: &'a str,
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl RevsetParser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self) -> VcsError {
+        VcsError::InvalidOperation(format!(
+            "Failed to parse revset near token {}",
+            self.position
+        ))
+    }
+
+    fn union(&mut self) -> VcsResult<BTreeSet<String>> {
+        let mut set = self.intersection()?;
+        while self.eat(&Token::Union) {
+            set.extend(self.intersection()?);
+        }
+        Ok(set)
+    }
+
+    fn intersection(&mut self) -> VcsResult<BTreeSet<String>> {
+        let mut set = self.negation()?;
+        while self.eat(&Token::Intersection) {
+            let other = self.negation()?;
+            set.retain(|id| other.contains(id));
+        }
+        Ok(set)
+    }
+
+    fn negation(&mut self) -> VcsResult<BTreeSet<String>> {
+        if self.eat(&Token::Not) {
+            let excluded = self.negation()?;
+            return Ok(self
+                .state
+                .repo
+                .commits
+                .keys()
+                .filter(|id| !excluded.contains(*id))
+                .cloned()
+                .collect());
+        }
+        self.range()
+    }
+
+    fn range(&mut self) -> VcsResult<BTreeSet<String>> {
+        if self.eat(&Token::Ancestors) {
+            let heads = self.primary()?;
+            return Ok(self.ancestors_of(&heads));
+        }
+        if self.eat(&Token::Range) {
+            let heads = self.primary()?;
+            let root = BTreeSet::from([ROOT_COMMIT_ID.to_string()]);
+            return Ok(self.between(&root, &heads));
+        }
+        let set = self.primary()?;
+        if self.eat(&Token::Range) {
+            let heads = self.primary()?;
+            return Ok(self.between(&set, &heads));
+        }
+        Ok(set)
+    }
+
+    fn primary(&mut self) -> VcsResult<BTreeSet<String>> {
+        match self.peek().cloned() {
+            Some(Token::Open) => {
+                self.position += 1;
+                let set = self.union()?;
+                if !self.eat(&Token::Close) {
+                    return Err(self.error());
+                }
+                Ok(set)
+            }
+            Some(Token::Symbol(name)) => {
+                self.position += 1;
+                if self.eat(&Token::Open) {
+                    return self.function(&name);
+                }
+                self.state.symbol(self.workspace, &name)
+            }
+            _ => Err(self.error()),
+        }
+    }
+
+    fn function(&mut self, name: &str) -> VcsResult<BTreeSet<String>> {
+        let argument = if self.peek() == Some(&Token::Close) {
+            None
+        } else {
+            Some(self.union()?)
+        };
+        if !self.eat(&Token::Close) {
+            return Err(self.error());
+        }
+        let commits = &self.state.repo.commits;
+        match (name, argument) {
+            ("heads", Some(set)) => Ok(set
+                .iter()
+                .filter(|id| {
+                    !set.iter()
+                        .any(|other| other != *id && self.state.ancestors(other).contains(*id))
+                })
+                .cloned()
+                .collect()),
+            ("ancestors", Some(set)) => Ok(self.ancestors_of(&set)),
+            ("conflicts", None) => Ok(commits
+                .values()
+                .filter(|c| !c.conflicts.is_empty())
+                .map(|c| c.commit_id.clone())
+                .collect()),
+            ("trunk", None) => Ok(self.state.trunk()),
+            ("root", None) => Ok(BTreeSet::from([ROOT_COMMIT_ID.to_string()])),
+            ("all", None) => Ok(commits.keys().cloned().collect()),
+            _ => Err(VcsError::InvalidOperation(format!(
+                "Revset function `{name}` is not supported by the fake backend"
+            ))),
+        }
+    }
+
+    fn ancestors_of(&self, heads: &BTreeSet<String>) -> BTreeSet<String> {
+        heads
+            .iter()
+            .flat_map(|id| self.state.ancestors(id))
+            .collect()
+    }
+
+    fn between(&self, roots: &BTreeSet<String>, heads: &BTreeSet<String>) -> BTreeSet<String> {
+        let excluded = self.ancestors_of(roots);
+        self.ancestors_of(heads)
+            .into_iter()
+            .filter(|id| !excluded.contains(id))
+            .collect()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// TREES AND IDS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Three-way merge: apply the change from `base` to `ours` on top of
+/// `theirs`. Paths changed differently on both sides keep `ours` and are
+/// reported as conflicts.
+fn merge_trees(
+    base: &BTreeMap<String, String>,
+    ours: &BTreeMap<String, String>,
+    theirs: &BTreeMap<String, String>,
+) -> (BTreeMap<String, String>, BTreeSet<String>) {
+    let paths: BTreeSet<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    let mut tree = BTreeMap::new();
+    let mut conflicts = BTreeSet::new();
+    for path in paths {
+        let (b, o, t) = (base.get(path), ours.get(path), theirs.get(path));
+        let merged = if o == b || o == t {
+            t
+        } else if t == b {
+            o
+        } else {
+            conflicts.insert(path.clone());
+            o
+        };
+        if let Some(contents) = merged {
+            tree.insert(path.clone(), contents.clone());
+        }
+    }
+    (tree, conflicts)
+}
+
+/// Paths that differ between two trees, tagged `A`, `M` or `D`
+fn diff_trees(
+    from: &BTreeMap<String, String>,
+    to: &BTreeMap<String, String>,
+) -> Vec<(char, String)> {
+    let paths: BTreeSet<&String> = from.keys().chain(to.keys()).collect();
+    paths
+        .into_iter()
+        .filter_map(|path| match (from.get(path), to.get(path)) {
+            (None, Some(_)) => Some(('A', path.clone())),
+            (Some(_), None) => Some(('D', path.clone())),
+            (Some(a), Some(b)) if a != b => Some(('M', path.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn conflict_error(what: &str, paths: &BTreeSet<String>) -> VcsError {
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+    VcsError::Conflict(format!("{what}: {}", paths.join(", ")))
+}
+
+/// Spread sequential ids over 48 bits so prefixes are unambiguous
+const fn mix(seq: u64) -> u64 {
+    (seq.wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 16) & 0xFFFF_FFFF_FFFF
+}
+
+/// JJ writes change ids in "reverse hex": `0`-`f` become `z`-`k`
+fn reverse_hex(value: u64) -> String {
+    format!("{value:012x}")
+        .chars()
+        .map(|c| {
+            let digit = c.to_digit(16).unwrap_or(0);
+            char::from(b'z' - u8::try_from(digit).unwrap_or(0))
+        })
+        .collect()
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// VCS BACKEND
+// ═══════════════════════════════════════════════════════════════════════════
+
+impl VcsBackend for FakeBackend {
+    fn backend_type(&self) -> BackendType {
+        BackendType::Jj
+    }
+
+    fn repo_exists(&self, path: &str) -> bool {
+        self.workspace_at(path).is_some()
+    }
+
+    fn status(&self, path: &str) -> VcsResult<RepoStatus> {
+        let workspace = self.name_at(path)?;
+        let status = self.status_of(&workspace)?;
+        Ok(RepoStatus {
+            clean: status.changes.is_empty(),
+            branch: self.current_branch(path).ok(),
+            commit_id: Some(CommitId::new(self.working_copy(&workspace)?.commit_id)),
+            has_conflicts: !status.conflicts.is_empty(),
+            uncommitted_files: status.changes.into_iter().map(|(_, p)| p).collect(),
+        })
+    }
+
+    fn current_branch(&self, path: &str) -> VcsResult<BranchName> {
+        let workspace = self.name_at(path)?;
+        let commits = self.log_revset(&workspace, "@ | @-")?;
+        let state = self.lock();
+        commits
+            .iter()
+            .flat_map(|c| state.bookmarks_at(&c.commit_id))
+            .next()
+            .map(BranchName::new)
+            .unwrap_or_else(|| {
+                Err(VcsError::BranchNotFound(
+                    "no bookmark on @ or @-".to_string(),
+                ))
+            })
+    }
+
+    fn log(&self, path: &str, limit: usize) -> VcsResult<Vec<Change>> {
+        let workspace = self.name_at(path)?;
+        Ok(self
+            .log_revset(&workspace, "::@")?
+            .iter()
+            .take(limit)
+            .map(|c| self.to_change(c))
+            .collect())
+    }
+
+    fn create_branch(
+        &self,
+        path: &str,
+        name: &BranchName,
+        base: Option<&CommitId>,
+    ) -> VcsResult<()> {
+        let workspace = self.name_at(path)?;
+        self.create_bookmark(
+            &workspace,
+            name.as_str(),
+            base.map_or("@", CommitId::as_str),
+        )
+    }
+
+    fn delete_branch(&self, path: &str, name: &BranchName) -> VcsResult<()> {
+        self.delete_bookmark(&self.name_at(path)?, name.as_str())
+    }
+
+    fn checkout(&self, path: &str, target: &str) -> VcsResult<()> {
+        self.new_change(&self.name_at(path)?, &[target]).map(|_| ())
+    }
+
+    fn checkout_merge(&self, path: &str, parents: &[&str]) -> VcsResult<()> {
+        let workspace = self.name_at(path)?;
+        let before = self.lock().repo.clone();
+        self.new_change(&workspace, parents)?;
+        let conflicts = self.working_copy(&workspace)?.conflicts;
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        let mut state = self.lock();
+        state.repo = before;
+        state.record("undo new");
+        Err(conflict_error(
+            &format!("merging {}", parents.join(", ")),
+            &conflicts,
+        ))
+    }
+
+    fn commit(&self, path: &str, message: &str) -> VcsResult<CommitId> {
+        self.commit_workspace(&self.name_at(path)?, message)
+            .map(CommitId::new)
+    }
+
+    fn pull(&self, path: &str) -> VcsResult<()> {
+        self.fetch(&self.name_at(path)?)
+    }
+
+    fn push(&self, path: &str) -> VcsResult<()> {
+        self.push_all(&self.name_at(path)?)
+    }
+
+    fn resolve(&self, path: &str, revision: &str) -> VcsResult<CommitId> {
+        let workspace = self.name_at(path)?;
+        Self::resolve(self, &workspace, revision).map(|c| CommitId::new(c.commit_id))
+    }
+
+    fn diff(&self, path: &str, from: &CommitId, to: &CommitId) -> VcsResult<String> {
+        let changes = self.diff_revisions(&self.name_at(path)?, from.as_str(), to.as_str())?;
+        Ok(changes
+            .iter()
+            .map(|(kind, path)| format!("{kind} {path}\n"))
+            .collect())
+    }
+
+    fn merge(&self, path: &str, source: &BranchName, target: &BranchName) -> VcsResult<CommitId> {
+        let workspace = self.name_at(path)?;
+        self.lock().gate(&workspace, FakeOp::Merge)?;
+        let before = self.lock().repo.clone();
+        let merged = self.new_change(&workspace, &[target.as_str(), source.as_str()])?;
+        let conflicts = self.working_copy(&workspace)?.conflicts;
+        if !conflicts.is_empty() {
+            let mut state = self.lock();
+            state.repo = before;
+            state.record("undo merge");
+            return Err(conflict_error(
+                &format!("merging {source} into {target}"),
+                &conflicts,
+            ));
+        }
+        self.set_bookmark(&workspace, target.as_str(), &merged, true)?;
+        self.new_change(&workspace, &[])?;
+        Ok(CommitId::new(merged))
+    }
+
+    fn rebase(&self, path: &str, branch: &BranchName, onto: &BranchName) -> VcsResult<()> {
+        let workspace = self.name_at(path)?;
+        let before = self.lock().repo.clone();
+        let conflicts = self.rebase_onto(&workspace, branch.as_str(), onto.as_str())?;
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        let mut state = self.lock();
+        state.repo = before;
+        state.record("undo rebase");
+        Err(conflict_error(
+            &format!("rebasing {branch} onto {onto}"),
+            &conflicts.into_iter().collect(),
+        ))
+    }
+
+    fn diff_summary(&self, path: &str) -> VcsResult<DiffSummary> {
+        let workspace = self.name_at(path)?;
+        let changes = self.diff_revisions(&workspace, "@-", "@")?;
+        let before = self.resolve(&workspace, "@-")?.tree;
+        let after = self.working_copy(&workspace)?.tree;
+        let lines = |tree: &BTreeMap<String, String>, path: &str| {
+            tree.get(path)
+                .map_or(0, |contents| contents.lines().count())
+        };
+        Ok(changes
+            .iter()
+            .fold(DiffSummary::default(), |mut summary, (_, path)| {
+                summary.insertions += lines(&after, path);
+                summary.deletions += lines(&before, path);
+                summary
+            }))
+    }
+
+    fn list_branches(&self, path: &str, all: bool) -> VcsResult<Vec<Branch>> {
+        let workspace = self.name_at(path)?;
+        let mut state = self.lock();
+        state.gate(&workspace, FakeOp::Bookmark)?;
+        let local =
+            state.repo.bookmarks.iter().map(|(name, target)| {
+                (name, target, state.repo.remote_bookmarks.contains_key(name))
+            });
+        let remote_only = state
+            .repo
+            .remote_bookmarks
+            .iter()
+            .filter(|(name, _)| all && !state.repo.bookmarks.contains_key(*name))
+            .map(|(name, target)| (name, target, true));
+        local
+            .chain(remote_only)
+            .map(|(name, target, remote)| {
+                Ok(Branch {
+                    name: BranchName::new(name.clone())?,
+                    commit_id: Some(CommitId::new(target.clone())),
+                    remote,
+                })
+            })
+            .collect()
+    }
+
+    fn move_branch(&self, path: &str, name: &BranchName, target: &str) -> VcsResult<()> {
+        self.set_bookmark(&self.name_at(path)?, name.as_str(), target, true)
+    }
+
+    fn push_branch(&self, path: &str, name: &BranchName) -> VcsResult<()> {
+        self.push_bookmark(&self.name_at(path)?, name.as_str())
+    }
+
+    fn list_workspaces(&self, path: &str) -> VcsResult<Vec<String>> {
+        self.name_at(path)?;
+        Ok(self.workspaces())
+    }
+
+    fn forget_workspace(&self, path: &str, name: &str) -> VcsResult<()> {
+        self.forget(&self.name_at(path)?, name)
+    }
+
+    fn add_workspace(
+        &self,
+        path: &str,
+        name: &str,
+        workspace_path: &str,
+        base: Option<&str>,
+    ) -> VcsResult<()> {
+        self.add_workspace_at(&self.name_at(path)?, name, workspace_path, base)
+    }
+
+    fn workspace_root(&self, path: &str) -> VcsResult<String> {
+        self.workspace_path(&self.name_at(path)?)
+    }
+
+    fn tree_id(&self, path: &str) -> VcsResult<Option<String>> {
+        let workspace = self.name_at(path)?;
+        let commit = self.working_copy(&workspace)?;
+        let mut hasher = DefaultHasher::new();
+        (&commit.tree, &commit.conflicts).hash(&mut hasher);
+        Ok(Some(format!("tree-{:016x}", hasher.finish())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROOT: &str = "/repo";
+    const WS: &str = "/repo/.isolate/workspaces/feature";
+
+    fn repo_with_workspace() -> VcsResult<FakeBackend> {
+        let repo = FakeBackend::new(ROOT);
+        repo.commit_on("main", "initial", &[("a.txt", "one\n")])?;
+        repo.add_workspace(ROOT, "feature", WS, Some("main"))?;
+        Ok(repo)
+    }
+
+    #[test]
+    fn test_commit_moves_working_copy_on_top() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.write_file("feature", "b.txt", "new\n")?;
+        assert_eq!(repo.status(WS)?.uncommitted_files, vec!["b.txt"]);
+
+        let committed = repo.commit(WS, "add b")?;
+        assert!(repo.status(WS)?.clean);
+        let parent = repo.resolve("feature", "@-")?;
+        assert_eq!(parent.commit_id, committed.as_str());
+        assert_eq!(parent.description, "add b");
+        assert_eq!(repo.file("feature", "b.txt").as_deref(), Some("new\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tree_id_changes_with_working_copy() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        let before = repo.tree_id(WS)?;
+        assert_eq!(repo.tree_id(WS)?, before);
+
+        repo.write_file("feature", "b.txt", "new\n")?;
+
+        assert!(before.is_some());
+        assert_ne!(repo.tree_id(WS)?, before);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebase_replays_changes_onto_new_main() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.write_file("feature", "b.txt", "feature\n")?;
+        repo.commit(WS, "add b")?;
+        let change = repo.resolve("feature", "@-")?.change_id;
+        let main = repo.commit_on("main", "main moves", &[("c.txt", "main\n")])?;
+
+        repo.rebase(WS, &BranchName::new("@")?, &BranchName::new("main")?)?;
+
+        let rebased = repo.resolve("feature", "@-")?;
+        assert_eq!(rebased.change_id, change);
+        assert_eq!(rebased.parents, vec![main]);
+        assert_eq!(repo.file("feature", "c.txt").as_deref(), Some("main\n"));
+        assert_eq!(repo.file("feature", "b.txt").as_deref(), Some("feature\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflicting_rebase_is_undone() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.write_file("feature", "a.txt", "feature\n")?;
+        repo.commit(WS, "edit a")?;
+        repo.commit_on("main", "main edits a", &[("a.txt", "main\n")])?;
+        let before = repo.working_copy("feature")?;
+
+        let result = repo.rebase(WS, &BranchName::new("@")?, &BranchName::new("main")?);
+
+        assert!(matches!(result, Err(VcsError::Conflict(ref m)) if m.contains("a.txt")));
+        assert_eq!(repo.working_copy("feature")?, before);
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkout_merge_stacks_commits() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.write_file("feature", "b.txt", "feature\n")?;
+        let tip = repo.commit(WS, "add b")?;
+        let main = repo.commit_on("main", "main moves", &[("c.txt", "main\n")])?;
+
+        repo.checkout_merge(WS, &[tip.as_str(), &main])?;
+
+        assert_eq!(
+            repo.working_copy("feature")?.parents,
+            vec![tip.to_string(), main]
+        );
+        assert_eq!(repo.file("feature", "b.txt").as_deref(), Some("feature\n"));
+        assert_eq!(repo.file("feature", "c.txt").as_deref(), Some("main\n"));
+        assert_eq!(
+            VcsBackend::resolve(&repo, WS, "@")?.as_str(),
+            repo.working_copy("feature")?.commit_id
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflicting_checkout_merge_is_undone() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.write_file("feature", "a.txt", "feature\n")?;
+        let tip = repo.commit(WS, "edit a")?;
+        let main = repo.commit_on("main", "main edits a", &[("a.txt", "main\n")])?;
+        let before = repo.working_copy("feature")?;
+
+        let result = repo.checkout_merge(WS, &[tip.as_str(), &main]);
+
+        assert!(matches!(result, Err(VcsError::Conflict(ref m)) if m.contains("a.txt")));
+        assert_eq!(repo.working_copy("feature")?, before);
+        Ok(())
+    }
+
+    #[test]
+    fn test_jj_style_rebase_keeps_conflicts() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.write_file("feature", "a.txt", "feature\n")?;
+        repo.commit(WS, "edit a")?;
+        repo.commit_on("main", "main edits a", &[("a.txt", "main\n")])?;
+
+        let conflicts = repo.rebase_onto("feature", "@", "main")?;
+
+        assert_eq!(conflicts, vec!["a.txt"]);
+        assert_eq!(repo.status_of("feature")?.conflicts, vec!["a.txt"]);
+        assert_eq!(
+            repo.log_revset("feature", "(main..@) & conflicts()")?.len(),
+            2
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_scripted_rebase_conflict() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.write_file("feature", "b.txt", "feature\n")?;
+        repo.commit(WS, "add b")?;
+        repo.commit_on("main", "main moves", &[("c.txt", "main\n")])?;
+        repo.fail_next(FakeOp::Rebase, FakeFailure::Conflict(vec!["b.txt".into()]));
+
+        let result = repo.rebase(WS, &BranchName::new("@")?, &BranchName::new("main")?);
+        assert!(matches!(result, Err(VcsError::Conflict(_))));
+
+        // The script is used up; the retry is clean
+        repo.rebase(WS, &BranchName::new("@")?, &BranchName::new("main")?)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_rejection_and_success() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        let feature = BranchName::new("feature")?;
+        repo.write_file("feature", "b.txt", "feature\n")?;
+        let head = repo.commit(WS, "add b")?;
+        repo.create_branch(WS, &feature, Some(&head))?;
+        repo.fail_next(
+            FakeOp::Push,
+            FakeFailure::PushRejected("non-fast-forward".into()),
+        );
+
+        let rejected = repo.push_branch(WS, &feature);
+        assert!(
+            matches!(rejected, Err(VcsError::OperationFailed(ref m)) if m.contains("rejected"))
+        );
+        assert_eq!(repo.remote_bookmark("feature"), None);
+
+        repo.push_branch(WS, &feature)?;
+        assert_eq!(repo.remote_bookmark("feature"), Some(head.to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_working_copy_blocks_commands() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.mark_stale("feature");
+
+        let result = repo.status(WS);
+        assert!(matches!(result, Err(VcsError::OperationFailed(ref m)) if m.contains("stale")));
+        assert!(repo.status(ROOT).is_ok());
+
+        repo.update_stale("feature")?;
+        assert!(repo.status(WS).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewriting_another_workspace_makes_it_stale() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        let other = repo.working_copy("feature")?.commit_id;
+
+        repo.edit("default", &other)?;
+        repo.write_file("default", "x.txt", "x\n")?;
+
+        assert!(repo.is_stale("feature"));
+        assert!(!repo.is_stale("default"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_operation_rolls_back() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        let before = repo.operations()[0].id.clone();
+        repo.write_file("feature", "b.txt", "feature\n")?;
+        repo.commit(WS, "add b")?;
+
+        repo.restore_operation("feature", &before)?;
+
+        assert!(repo.status(WS)?.clean);
+        assert_eq!(repo.file("feature", "b.txt"), None);
+        assert_eq!(
+            repo.operations()[0].description,
+            format!("restore to operation {before}")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_revsets() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.write_file("feature", "b.txt", "1\n")?;
+        repo.commit(WS, "one")?;
+        repo.write_file("feature", "b.txt", "2\n")?;
+        repo.commit(WS, "two")?;
+
+        let branch = repo.log_revset("feature", "main..@-")?;
+        let descriptions: Vec<&str> = branch.iter().map(|c| c.description.as_str()).collect();
+        assert_eq!(descriptions, vec!["two", "one"]);
+
+        let base = repo.log_revset("feature", "heads(::@ & ::trunk())")?;
+        assert_eq!(Some(base[0].commit_id.clone()), repo.bookmark("main"));
+
+        assert!(repo.log_revset("feature", "@..@-")?.is_empty());
+        assert_eq!(repo.log_revset("feature", "feature@")?.len(), 1);
+        assert!(repo.log_revset("feature", "nope").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_squash_moves_changes_into_main() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        repo.write_file("feature", "b.txt", "feature\n")?;
+        repo.commit(WS, "add b")?;
+
+        repo.squash(
+            "feature",
+            "ancestors(feature@) & ~ancestors(main)",
+            "main",
+            "land feature",
+        )?;
+
+        let main = repo.resolve("feature", "main")?;
+        assert_eq!(main.description, "land feature");
+        assert_eq!(
+            main.tree.get("b.txt").map(String::as_str),
+            Some("feature\n")
+        );
+        assert_eq!(repo.resolve("feature", "@-")?.commit_id, main.commit_id);
+        Ok(())
+    }
+
+    #[test]
+    fn test_workspaces() -> VcsResult<()> {
+        let repo = repo_with_workspace()?;
+        assert_eq!(repo.list_workspaces(ROOT)?, vec!["default", "feature"]);
+        assert_eq!(repo.workspace_root(&format!("{WS}/src"))?, WS);
+        assert!(repo.add_workspace(ROOT, "feature", WS, None).is_err());
+
+        repo.forget_workspace(ROOT, "feature")?;
+        assert_eq!(repo.list_workspaces(ROOT)?, vec!["default"]);
+        assert_eq!(repo.workspace_at(WS).as_deref(), Some("default"));
+        Ok(())
+    }
+}
@@ -0,0 +1,726 @@
+//! Git implementation of [`VcsBackend`]
+//!
+//! For repositories that have not adopted JJ yet. Workspaces are git
+//! worktrees: each one checks out its own branch, named after the workspace,
+//! in its own directory. The main worktree is reported as `default`, matching
+//! the default JJ workspace.
+//!
+//! Git has no change ids, so [`Change::id`] carries the commit hash. Pulling
+//! only fetches, like `jj git fetch`. Features built on the JJ operation log
+//! have no git equivalent; callers report [`VcsError::RequiresJj`] for them.
+//!
+//! Merges and rebases that stop on conflicts are aborted before returning
+//! [`VcsError::Conflict`], so the worktree is left as it was.
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+
+use std::{path::Path, process::Command};
+
+use super::{
+    BackendType, Branch, BranchName, Change, ChangeId, CommitId, RepoStatus, VcsBackend, VcsError,
+    VcsResult,
+};
+use crate::jj::{parse_diff_stat, DiffSummary};
+
+/// Format for [`parse_git_log`]: hash, decorations, author, time, subject
+const LOG_FORMAT: &str = "--format=%H%x09%D%x09%ae%x09%at%x09%s";
+
+/// Name reported for the main worktree
+const MAIN_WORKTREE: &str = "default";
+
+/// A git worktree as listed by `git worktree list --porcelain`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worktree {
+    pub path: String,
+    pub head: Option<CommitId>,
+    /// Checked-out branch; `None` when HEAD is detached
+    pub branch: Option<BranchName>,
+}
+
+/// `VcsBackend` that drives the `git` CLI
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GitBackend;
+
+impl GitBackend {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+
+    /// Run `git` in `path` and return stdout.
+    fn run(&self, path: &str, args: &[&str]) -> VcsResult<String> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(path)
+            .output()
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    VcsError::OperationFailed("git command not found in PATH".into())
+                } else {
+                    VcsError::OperationFailed(format!("failed to run git: {e}"))
+                }
+            })?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            // Conflict reports go to stdout, everything else to stderr
+            let message = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stderr),
+                String::from_utf8_lossy(&output.stdout)
+            );
+            Err(classify_failure(path, args, &message))
+        }
+    }
+
+    /// Commit id of a single revision.
+    fn rev_parse(&self, path: &str, revision: &str) -> VcsResult<CommitId> {
+        let spec = format!("{revision}^{{commit}}");
+        self.run(path, &["rev-parse", "--verify", "--quiet", &spec])
+            .map(|id| CommitId::new(id.trim()))
+            .map_err(|_| VcsError::CommitNotFound(revision.to_string()))
+    }
+
+    /// Fail unless `branch` is checked out in the worktree at `path`.
+    fn ensure_on_branch(&self, path: &str, branch: &BranchName) -> VcsResult<()> {
+        let current = self.current_branch(path)?;
+        if current == *branch {
+            Ok(())
+        } else {
+            Err(VcsError::InvalidOperation(format!(
+                "'{branch}' is not checked out at {path} (found '{current}')"
+            )))
+        }
+    }
+
+    /// Undo a merge or rebase that stopped on conflicts.
+    fn abort_conflict(&self, path: &str, abort: &[&str], err: VcsError, what: &str) -> VcsError {
+        match err {
+            VcsError::Conflict(files) => match self.run(path, abort) {
+                Ok(_) => VcsError::Conflict(format!("{what}: {files}")),
+                Err(e) => VcsError::Conflict(format!("{what}: {files} (abort failed: {e})")),
+            },
+            other => other,
+        }
+    }
+
+    /// Root of the main worktree, the one that owns the `.git` directory
+    ///
+    /// From a linked worktree this is the repository the worktree was added to.
+    pub fn main_worktree_root(&self, path: &str) -> VcsResult<String> {
+        let common_dir = self.run(
+            path,
+            &["rev-parse", "--path-format=absolute", "--git-common-dir"],
+        )?;
+        Path::new(common_dir.trim())
+            .parent()
+            .map(|root| root.to_string_lossy().to_string())
+            .ok_or_else(|| VcsError::RepoNotFound(path.to_string()))
+    }
+
+    /// Branch sessions land on
+    ///
+    /// Prefers the default branch of `origin`, then a local `main` or `master`.
+    #[must_use]
+    pub fn main_branch(&self, path: &str) -> String {
+        self.run(
+            path,
+            &["symbolic-ref", "--short", "refs/remotes/origin/HEAD"],
+        )
+        .ok()
+        .and_then(|head| {
+            head.trim()
+                .strip_prefix("origin/")
+                .filter(|name| !name.is_empty())
+                .map(String::from)
+        })
+        .or_else(|| {
+            ["main", "master"]
+                .into_iter()
+                .find(|name| self.rev_parse(path, &format!("refs/heads/{name}")).is_ok())
+                .map(String::from)
+        })
+        .unwrap_or_else(|| "main".to_string())
+    }
+
+    /// Commits reachable from `tip` but not from `base`, newest first
+    pub fn commits_between(&self, path: &str, base: &str, tip: &str) -> VcsResult<Vec<Change>> {
+        let range = format!("{base}..{tip}");
+        self.run(path, &["log", &range, LOG_FORMAT])
+            .map(|output| parse_git_log(&output))
+    }
+
+    /// Diff of the worktree at `path` against its merge base with `base`
+    ///
+    /// Uncommitted changes are included, like `jj diff --from base --to @`.
+    pub fn diff_against(&self, path: &str, base: &str, stat: bool) -> VcsResult<String> {
+        let merge_base = self.run(path, &["merge-base", base, "HEAD"])?;
+        let merge_base = merge_base.trim();
+        if stat {
+            self.run(path, &["diff", "--stat", merge_base])
+        } else {
+            self.run(path, &["diff", merge_base])
+        }
+    }
+
+    /// Squash `source` into a single commit on the branch checked out at `path`
+    pub fn squash_merge(
+        &self,
+        path: &str,
+        source: &BranchName,
+        message: &str,
+    ) -> VcsResult<CommitId> {
+        self.run(path, &["merge", "--squash", source.as_str()])
+            .map_err(|e| {
+                self.abort_conflict(
+                    path,
+                    &["reset", "--merge"],
+                    e,
+                    &format!("squashing {source}"),
+                )
+            })?;
+        self.run(path, &["commit", "-m", message])?;
+        self.rev_parse(path, "HEAD")
+    }
+}
+
+impl VcsBackend for GitBackend {
+    fn backend_type(&self) -> BackendType {
+        BackendType::Git
+    }
+
+    fn repo_exists(&self, path: &str) -> bool {
+        Path::new(path).join(".git").exists()
+    }
+
+    fn status(&self, path: &str) -> VcsResult<RepoStatus> {
+        let output = self.run(path, &["status", "--porcelain=v1"])?;
+        let (uncommitted_files, has_conflicts) = parse_porcelain_status(&output);
+        Ok(RepoStatus {
+            clean: uncommitted_files.is_empty(),
+            branch: self.current_branch(path).ok(),
+            commit_id: self.rev_parse(path, "HEAD").ok(),
+            has_conflicts,
+            uncommitted_files,
+        })
+    }
+
+    fn current_branch(&self, path: &str) -> VcsResult<BranchName> {
+        match self.run(path, &["symbolic-ref", "--short", "HEAD"]) {
+            Ok(name) if !name.trim().is_empty() => BranchName::new(name.trim()),
+            Ok(_) | Err(VcsError::OperationFailed(_)) => {
+                Err(VcsError::BranchNotFound("HEAD is detached".to_string()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn log(&self, path: &str, limit: usize) -> VcsResult<Vec<Change>> {
+        let limit = limit.to_string();
+        self.run(path, &["log", "-n", &limit, LOG_FORMAT])
+            .map(|output| parse_git_log(&output))
+    }
+
+    fn create_branch(
+        &self,
+        path: &str,
+        name: &BranchName,
+        base: Option<&CommitId>,
+    ) -> VcsResult<()> {
+        let base = base.map_or("HEAD", CommitId::as_str);
+        self.run(path, &["branch", name.as_str(), base]).map(|_| ())
+    }
+
+    fn delete_branch(&self, path: &str, name: &BranchName) -> VcsResult<()> {
+        self.run(path, &["branch", "-D", name.as_str()]).map(|_| ())
+    }
+
+    fn checkout(&self, path: &str, target: &str) -> VcsResult<()> {
+        self.run(path, &["checkout", target]).map(|_| ())
+    }
+
+    fn checkout_merge(&self, path: &str, parents: &[&str]) -> VcsResult<()> {
+        let Some((first, rest)) = parents.split_first() else {
+            return Err(VcsError::InvalidOperation(
+                "No commits to merge".to_string(),
+            ));
+        };
+        self.run(path, &["checkout", "--detach", first])?;
+        if rest.is_empty() {
+            return Ok(());
+        }
+        let mut args = vec!["merge", "--no-edit"];
+        args.extend_from_slice(rest);
+        self.run(path, &args).map(|_| ()).map_err(|e| {
+            self.abort_conflict(
+                path,
+                &["merge", "--abort"],
+                e,
+                &format!("merging {}", parents.join(", ")),
+            )
+        })
+    }
+
+    fn commit(&self, path: &str, message: &str) -> VcsResult<CommitId> {
+        self.run(path, &["add", "--all"])?;
+        self.run(path, &["commit", "-m", message])?;
+        self.rev_parse(path, "HEAD")
+    }
+
+    fn pull(&self, path: &str) -> VcsResult<()> {
+        self.run(path, &["fetch"]).map(|_| ())
+    }
+
+    fn push(&self, path: &str) -> VcsResult<()> {
+        self.run(path, &["push"]).map(|_| ())
+    }
+
+    fn resolve(&self, path: &str, revision: &str) -> VcsResult<CommitId> {
+        self.rev_parse(path, revision)
+    }
+
+    fn diff(&self, path: &str, from: &CommitId, to: &CommitId) -> VcsResult<String> {
+        self.run(path, &["diff", from.as_str(), to.as_str()])
+    }
+
+    fn merge(&self, path: &str, source: &BranchName, target: &BranchName) -> VcsResult<CommitId> {
+        self.ensure_on_branch(path, target)?;
+        let message = format!("Merge {source} into {target}");
+        self.run(
+            path,
+            &["merge", "--no-edit", "-m", &message, source.as_str()],
+        )
+        .map_err(|e| {
+            self.abort_conflict(
+                path,
+                &["merge", "--abort"],
+                e,
+                &format!("merging {source} into {target}"),
+            )
+        })?;
+        self.rev_parse(path, "HEAD")
+    }
+
+    fn rebase(&self, path: &str, branch: &BranchName, onto: &BranchName) -> VcsResult<()> {
+        self.ensure_on_branch(path, branch)?;
+        self.run(path, &["rebase", onto.as_str()])
+            .map(|_| ())
+            .map_err(|e| {
+                self.abort_conflict(
+                    path,
+                    &["rebase", "--abort"],
+                    e,
+                    &format!("rebasing {branch} onto {onto}"),
+                )
+            })
+    }
+
+    fn diff_summary(&self, path: &str) -> VcsResult<DiffSummary> {
+        self.run(path, &["diff", "HEAD", "--stat"])
+            .map(|output| parse_diff_stat(&output))
+    }
+
+    fn list_branches(&self, path: &str, all: bool) -> VcsResult<Vec<Branch>> {
+        let format = "--format=%(refname)%09%(objectname)";
+        let args: &[&str] = if all {
+            &["for-each-ref", format, "refs/heads", "refs/remotes"]
+        } else {
+            &["for-each-ref", format, "refs/heads"]
+        };
+        let branches = self.run(path, args).map(|output| parse_ref_list(&output))?;
+
+        // Like JJ, a remote branch is only listed when there is no local one
+        let local: Vec<BranchName> = branches
+            .iter()
+            .filter(|b| !b.remote)
+            .map(|b| b.name.clone())
+            .collect();
+        Ok(branches
+            .into_iter()
+            .filter(|b| !b.remote || !local.contains(&b.name))
+            .collect())
+    }
+
+    fn move_branch(&self, path: &str, name: &BranchName, target: &str) -> VcsResult<()> {
+        self.run(path, &["branch", "-f", name.as_str(), target])
+            .map(|_| ())
+    }
+
+    fn push_branch(&self, path: &str, name: &BranchName) -> VcsResult<()> {
+        self.run(path, &["push", "origin", name.as_str()])
+            .map(|_| ())
+    }
+
+    fn list_workspaces(&self, path: &str) -> VcsResult<Vec<String>> {
+        let worktrees = self
+            .run(path, &["worktree", "list", "--porcelain"])
+            .map(|output| parse_worktree_list(&output))?;
+        Ok(worktrees
+            .iter()
+            .enumerate()
+            .map(|(index, worktree)| worktree_name(index, worktree))
+            .collect())
+    }
+
+    fn forget_workspace(&self, path: &str, name: &str) -> VcsResult<()> {
+        let worktrees = self
+            .run(path, &["worktree", "list", "--porcelain"])
+            .map(|output| parse_worktree_list(&output))?;
+        let worktree = worktrees
+            .iter()
+            .enumerate()
+            .skip(1)
+            .find(|(index, worktree)| worktree_name(*index, worktree) == name)
+            .map(|(_, worktree)| worktree)
+            .ok_or_else(|| VcsError::InvalidOperation(format!("No worktree named '{name}'")))?;
+
+        if Path::new(&worktree.path).exists() {
+            self.run(path, &["worktree", "remove", "--force", &worktree.path])
+                .map(|_| ())
+        } else {
+            // Directory already gone: drop the stale administrative entry
+            self.run(path, &["worktree", "prune"]).map(|_| ())
+        }
+    }
+
+    fn add_workspace(
+        &self,
+        path: &str,
+        name: &str,
+        workspace_path: &str,
+        base: Option<&str>,
+    ) -> VcsResult<()> {
+        let base = base.unwrap_or("HEAD");
+        self.run(path, &["worktree", "add", "-b", name, workspace_path, base])
+            .map(|_| ())
+    }
+
+    fn workspace_root(&self, path: &str) -> VcsResult<String> {
+        self.run(path, &["rev-parse", "--show-toplevel"])
+            .map(|output| output.trim().to_string())
+    }
+
+    fn tree_id(&self, path: &str) -> VcsResult<Option<String>> {
+        if !self.status(path)?.clean {
+            return Ok(None);
+        }
+        self.run(path, &["rev-parse", "--verify", "--quiet", "HEAD^{tree}"])
+            .map(|id| Some(id.trim().to_string()))
+            .map_err(|_| VcsError::CommitNotFound("HEAD".to_string()))
+    }
+}
+
+/// Workspace name of the `index`-th entry of `git worktree list`
+///
+/// The main worktree is always listed first.
+fn worktree_name(index: usize, worktree: &Worktree) -> String {
+    if index == 0 {
+        MAIN_WORKTREE.to_string()
+    } else {
+        Path::new(&worktree.path).file_name().map_or_else(
+            || worktree.path.clone(),
+            |n| n.to_string_lossy().to_string(),
+        )
+    }
+}
+
+/// Map a failed `git` invocation onto a [`VcsError`].
+fn classify_failure(path: &str, args: &[&str], output: &str) -> VcsError {
+    let output = output.trim();
+    let lower = output.to_lowercase();
+    let conflicts = conflicted_files(output);
+    let branch = match args {
+        ["branch", "-D" | "-f", name, ..] | ["push", "origin", name] => Some(*name),
+        _ => None,
+    };
+
+    if lower.contains("not a git repository") {
+        VcsError::RepoNotFound(path.to_string())
+    } else if !conflicts.is_empty() {
+        VcsError::Conflict(conflicts.join(", "))
+    } else if lower.contains("branch '") && lower.contains("not found") {
+        VcsError::BranchNotFound(branch.map_or_else(|| output.to_string(), String::from))
+    } else if lower.contains("unknown revision")
+        || lower.contains("not a valid object name")
+        || lower.contains("invalid reference")
+    {
+        VcsError::CommitNotFound(output.to_string())
+    } else if lower.contains("already exists") {
+        VcsError::InvalidOperation(output.to_string())
+    } else {
+        VcsError::OperationFailed(format!("git {}: {output}", args.join(" ")))
+    }
+}
+
+/// Files named in `CONFLICT (...): Merge conflict in <file>` lines
+fn conflicted_files(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| line.starts_with("CONFLICT"))
+        .map(|line| {
+            line.rsplit_once(" in ")
+                .map_or(line, |(_, file)| file)
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// PURE PARSERS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Parse output produced with [`LOG_FORMAT`]
+///
+/// The branch is the one HEAD points at, otherwise the first decoration that
+/// is not a tag.
+#[must_use]
+pub fn parse_git_log(output: &str) -> Vec<Change> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(5, '\t');
+            let hash = fields.next().map(str::trim).filter(|s| !s.is_empty())?;
+            let branch = fields.next().and_then(decorated_branch);
+            let author = fields
+                .next()
+                .map_or_else(String::new, |a| a.trim().to_string());
+            let timestamp = fields
+                .next()
+                .and_then(|t| t.trim().parse().ok())
+                .unwrap_or(0);
+            let description = fields
+                .next()
+                .map_or_else(String::new, |d| d.trim().to_string());
+
+            Some(Change {
+                id: ChangeId::new(hash),
+                commit_id: CommitId::new(hash),
+                branch,
+                description,
+                author,
+                timestamp,
+            })
+        })
+        .collect()
+}
+
+/// Pick the branch out of a `%D` decoration list ("HEAD -> main, origin/main")
+fn decorated_branch(decorations: &str) -> Option<BranchName> {
+    let refs: Vec<&str> = decorations
+        .split(", ")
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .collect();
+    refs.iter()
+        .find_map(|r| r.strip_prefix("HEAD -> "))
+        .or_else(|| {
+            refs.iter()
+                .copied()
+                .find(|r| *r != "HEAD" && !r.starts_with("tag: "))
+        })
+        .and_then(|name| BranchName::new(name).ok())
+}
+
+/// Parse `git status --porcelain=v1` output
+///
+/// Returns the changed paths (the new path for renames) and whether any of
+/// them has unresolved conflicts.
+#[must_use]
+pub fn parse_porcelain_status(output: &str) -> (Vec<String>, bool) {
+    const CONFLICT_CODES: [&str; 7] = ["DD", "AU", "UD", "UA", "DU", "AA", "UU"];
+
+    output.lines().filter(|line| line.len() > 3).fold(
+        (Vec::new(), false),
+        |(mut files, conflicts), line| {
+            let (code, path) = line.split_at(2);
+            let path = path.trim();
+            let path = path.rsplit_once(" -> ").map_or(path, |(_, new)| new);
+            files.push(path.to_string());
+            (files, conflicts || CONFLICT_CODES.contains(&code))
+        },
+    )
+}
+
+/// Parse `git for-each-ref --format=%(refname)%09%(objectname)` output
+///
+/// Remote branches are named without their remote prefix and flagged as
+/// remote. Symbolic `HEAD` refs of remotes are skipped.
+#[must_use]
+pub fn parse_ref_list(output: &str) -> Vec<Branch> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (refname, object) = line.split_once('\t')?;
+            let (name, remote) = if let Some(local) = refname.strip_prefix("refs/heads/") {
+                (local, false)
+            } else {
+                let (_remote, name) = refname.strip_prefix("refs/remotes/")?.split_once('/')?;
+                (name, true)
+            };
+            if remote && name == "HEAD" {
+                return None;
+            }
+
+            Some(Branch {
+                name: BranchName::new(name).ok()?,
+                commit_id: Some(object.trim())
+                    .filter(|id| !id.is_empty())
+                    .map(CommitId::new),
+                remote,
+            })
+        })
+        .collect()
+}
+
+/// Parse `git worktree list --porcelain` output
+///
+/// Entries are separated by blank lines; the main worktree comes first.
+#[must_use]
+pub fn parse_worktree_list(output: &str) -> Vec<Worktree> {
+    output
+        .split("\n\n")
+        .filter_map(|block| {
+            let mut lines = block.lines();
+            let path = lines.next()?.strip_prefix("worktree ")?.to_string();
+            let worktree = Worktree {
+                path,
+                head: None,
+                branch: None,
+            };
+            Some(lines.fold(worktree, |mut worktree, line| {
+                if let Some(head) = line.strip_prefix("HEAD ") {
+                    worktree.head = Some(CommitId::new(head));
+                } else if let Some(branch) = line.strip_prefix("branch ") {
+                    let name = branch.strip_prefix("refs/heads/").unwrap_or(branch);
+                    worktree.branch = BranchName::new(name).ok();
+                }
+                worktree
+            }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_git_log_reads_decorations() {
+        let output =
+            "abc123\tHEAD -> feature, origin/feature\tdev@example.com\t1700000000\tAdd parser\n\
+                      def456\ttag: v1, main\t\t0\tInit\n\
+                      999999\t\t\t\t\n";
+        let changes = parse_git_log(output);
+
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].id.as_str(), "abc123");
+        assert_eq!(changes[0].commit_id.as_str(), "abc123");
+        assert_eq!(
+            changes[0].branch.as_ref().map(BranchName::as_str),
+            Some("feature")
+        );
+        assert_eq!(changes[0].author, "dev@example.com");
+        assert_eq!(changes[0].timestamp, 1_700_000_000);
+        assert_eq!(changes[0].description, "Add parser");
+        assert_eq!(
+            changes[1].branch.as_ref().map(BranchName::as_str),
+            Some("main")
+        );
+        assert_eq!(changes[2].branch, None);
+    }
+
+    #[test]
+    fn test_parse_porcelain_status_renames_and_conflicts() {
+        let output = " M src/lib.rs\n\
+                      R  old.rs -> new.rs\n\
+                      ?? notes.txt\n";
+        let (files, conflicts) = parse_porcelain_status(output);
+        assert_eq!(files, vec!["src/lib.rs", "new.rs", "notes.txt"]);
+        assert!(!conflicts);
+
+        let (files, conflicts) = parse_porcelain_status("UU src/main.rs\n");
+        assert_eq!(files, vec!["src/main.rs"]);
+        assert!(conflicts);
+    }
+
+    #[test]
+    fn test_parse_ref_list_local_and_remote() {
+        let output = "refs/heads/main\tabc123\n\
+                      refs/heads/feature/x\tdef456\n\
+                      refs/remotes/origin/HEAD\tabc123\n\
+                      refs/remotes/origin/main\tabc123\n";
+        let branches = parse_ref_list(output);
+
+        let names: Vec<(&str, bool)> = branches
+            .iter()
+            .map(|b| (b.name.as_str(), b.remote))
+            .collect();
+        assert_eq!(
+            names,
+            vec![("main", false), ("feature/x", false), ("main", true)]
+        );
+        assert_eq!(
+            branches[1].commit_id.as_ref().map(CommitId::as_str),
+            Some("def456")
+        );
+    }
+
+    #[test]
+    fn test_parse_worktree_list() {
+        let output = "worktree /repo\nHEAD abc123\nbranch refs/heads/main\n\n\
+                      worktree /work/bd-42\nHEAD def456\nbranch refs/heads/bd-42\n\n\
+                      worktree /work/detached\nHEAD 999999\ndetached\n\n";
+        let worktrees = parse_worktree_list(output);
+
+        assert_eq!(worktrees.len(), 3);
+        assert_eq!(worktrees[0].path, "/repo");
+        assert_eq!(
+            worktrees[1].branch.as_ref().map(BranchName::as_str),
+            Some("bd-42")
+        );
+        assert_eq!(worktrees[2].branch, None);
+
+        let names: Vec<String> = worktrees
+            .iter()
+            .enumerate()
+            .map(|(i, w)| worktree_name(i, w))
+            .collect();
+        assert_eq!(names, vec!["default", "bd-42", "detached"]);
+    }
+
+    #[test]
+    fn test_classify_failure() {
+        assert!(matches!(
+            classify_failure(
+                "/tmp",
+                &["status"],
+                "fatal: not a git repository (or any of the parent directories): .git"
+            ),
+            VcsError::RepoNotFound(_)
+        ));
+        assert!(matches!(
+            classify_failure(
+                "/tmp",
+                &["merge", "--no-edit", "feature"],
+                "Auto-merging a.txt\nCONFLICT (content): Merge conflict in a.txt\nAutomatic merge failed"
+            ),
+            VcsError::Conflict(files) if files == "a.txt"
+        ));
+        assert!(matches!(
+            classify_failure(
+                "/tmp",
+                &["branch", "-D", "gone"],
+                "error: branch 'gone' not found."
+            ),
+            VcsError::BranchNotFound(name) if name == "gone"
+        ));
+        assert!(matches!(
+            classify_failure("/tmp", &["push"], "rejected"),
+            VcsError::OperationFailed(_)
+        ));
+    }
+}
@@ -0,0 +1,576 @@
+//! Jujutsu implementation of [`VcsBackend`]
+//!
+//! Every operation runs the `jj` binary resolved by [`crate::jj::get_jj_command_sync`]
+//! in the given workspace directory. Output is parsed by the pure functions at
+//! the bottom of this file and by the existing [`parse_status`] and
+//! [`parse_diff_stat`] parsers, so the parsing is testable without `jj`.
+//!
+//! [`VcsBackend::log`] walks back from the working-copy commit, so the first
+//! change it returns is `@` itself.
+//!
+//! JJ has no "current branch". [`VcsBackend::current_branch`] reports the
+//! bookmark on the working-copy commit, or on its parent when `@` is the usual
+//! empty commit on top of a bookmarked change.
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::{
+    BackendType, Branch, BranchName, Change, ChangeId, CommitId, RepoStatus, VcsBackend, VcsError,
+    VcsResult,
+};
+use crate::jj::{get_jj_command_sync, parse_diff_stat, parse_status, DiffSummary};
+
+/// Template for [`parse_log`]: one tab-separated line per change
+const LOG_TEMPLATE: &str = r#"change_id ++ "\t" ++ commit_id ++ "\t" ++ local_bookmarks.map(|b| b.name()).join(" ") ++ "\t" ++ author.email() ++ "\t" ++ author.timestamp().format("%s") ++ "\t" ++ description.first_line() ++ "\n""#;
+
+/// `VcsBackend` that drives the `jj` CLI
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JjBackend;
+
+impl JjBackend {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self
+    }
+
+    /// Run `jj` in `path` and return stdout.
+    fn run(&self, path: &str, args: &[&str]) -> VcsResult<String> {
+        let output = get_jj_command_sync()
+            .args(args)
+            .current_dir(path)
+            .output()
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    VcsError::OperationFailed("jj command not found in PATH".into())
+                } else {
+                    VcsError::OperationFailed(format!("failed to run jj: {e}"))
+                }
+            })?;
+
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(classify_failure(
+                path,
+                args,
+                &String::from_utf8_lossy(&output.stderr),
+            ))
+        }
+    }
+
+    /// Commit id of a single revision.
+    fn commit_id_of(&self, path: &str, revision: &str) -> VcsResult<CommitId> {
+        let id = self.run(
+            path,
+            &["log", "-r", revision, "--no-graph", "-T", "commit_id"],
+        )?;
+        let id = id.trim();
+        if id.is_empty() {
+            Err(VcsError::CommitNotFound(revision.to_string()))
+        } else {
+            Ok(CommitId::new(id))
+        }
+    }
+
+    /// Whether any revision in `revset` has unresolved conflicts.
+    fn has_conflicts(&self, path: &str, revset: &str) -> VcsResult<bool> {
+        let revset = format!("({revset}) & conflicts()");
+        let out = self.run(
+            path,
+            &[
+                "log",
+                "-r",
+                &revset,
+                "--no-graph",
+                "-T",
+                r#"commit_id ++ "\n""#,
+            ],
+        )?;
+        Ok(!out.trim().is_empty())
+    }
+
+    /// Undo the last operation after it produced conflicts.
+    fn undo_conflict(&self, path: &str, what: String) -> VcsError {
+        match self.run(path, &["undo"]) {
+            Ok(_) => VcsError::Conflict(what),
+            Err(e) => VcsError::Conflict(format!("{what} (undo failed: {e})")),
+        }
+    }
+}
+
+impl VcsBackend for JjBackend {
+    fn backend_type(&self) -> BackendType {
+        BackendType::Jj
+    }
+
+    fn repo_exists(&self, path: &str) -> bool {
+        std::path::Path::new(path).join(".jj").is_dir()
+    }
+
+    fn status(&self, path: &str) -> VcsResult<RepoStatus> {
+        let output = self.run(path, &["status", "--no-pager"])?;
+        let status = parse_status(&output);
+        Ok(RepoStatus {
+            clean: status.is_clean(),
+            branch: self.current_branch(path).ok(),
+            commit_id: self.commit_id_of(path, "@").ok(),
+            has_conflicts: output.contains("unresolved conflicts"),
+            uncommitted_files: status.changed_files(),
+        })
+    }
+
+    fn current_branch(&self, path: &str) -> VcsResult<BranchName> {
+        let output = self.run(
+            path,
+            &[
+                "log",
+                "-r",
+                "@ | @-",
+                "--no-graph",
+                "-T",
+                r#"local_bookmarks.map(|b| b.name()).join(" ") ++ "\n""#,
+            ],
+        )?;
+
+        output
+            .lines()
+            .find_map(|line| line.split_whitespace().next())
+            .map(BranchName::new)
+            .unwrap_or_else(|| {
+                Err(VcsError::BranchNotFound(
+                    "no bookmark on @ or @-".to_string(),
+                ))
+            })
+    }
+
+    fn log(&self, path: &str, limit: usize) -> VcsResult<Vec<Change>> {
+        let limit = limit.to_string();
+        let output = self.run(
+            path,
+            &[
+                "log",
+                "-r",
+                "::@",
+                "--no-graph",
+                "-n",
+                &limit,
+                "-T",
+                LOG_TEMPLATE,
+            ],
+        )?;
+        Ok(parse_log(&output))
+    }
+
+    fn create_branch(
+        &self,
+        path: &str,
+        name: &BranchName,
+        base: Option<&CommitId>,
+    ) -> VcsResult<()> {
+        let revision = base.map_or("@", CommitId::as_str);
+        self.run(path, &["bookmark", "create", name.as_str(), "-r", revision])
+            .map(|_| ())
+    }
+
+    fn delete_branch(&self, path: &str, name: &BranchName) -> VcsResult<()> {
+        self.run(path, &["bookmark", "delete", name.as_str()])
+            .map(|_| ())
+    }
+
+    fn checkout(&self, path: &str, target: &str) -> VcsResult<()> {
+        self.run(path, &["new", target]).map(|_| ())
+    }
+
+    fn checkout_merge(&self, path: &str, parents: &[&str]) -> VcsResult<()> {
+        let mut args = vec!["new"];
+        args.extend_from_slice(parents);
+        self.run(path, &args)?;
+        if self.has_conflicts(path, "@")? {
+            return Err(self.undo_conflict(path, format!("merging {}", parents.join(", "))));
+        }
+        Ok(())
+    }
+
+    fn commit(&self, path: &str, message: &str) -> VcsResult<CommitId> {
+        self.run(path, &["commit", "-m", message])?;
+        self.commit_id_of(path, "@-")
+    }
+
+    fn pull(&self, path: &str) -> VcsResult<()> {
+        self.run(path, &["git", "fetch"]).map(|_| ())
+    }
+
+    fn push(&self, path: &str) -> VcsResult<()> {
+        self.run(path, &["git", "push"]).map(|_| ())
+    }
+
+    fn resolve(&self, path: &str, revision: &str) -> VcsResult<CommitId> {
+        self.commit_id_of(path, revision)
+    }
+
+    fn diff(&self, path: &str, from: &CommitId, to: &CommitId) -> VcsResult<String> {
+        self.run(
+            path,
+            &[
+                "diff",
+                "--git",
+                "--from",
+                from.as_str(),
+                "--to",
+                to.as_str(),
+            ],
+        )
+    }
+
+    fn merge(&self, path: &str, source: &BranchName, target: &BranchName) -> VcsResult<CommitId> {
+        let message = format!("Merge {source} into {target}");
+        self.run(
+            path,
+            &["new", target.as_str(), source.as_str(), "-m", &message],
+        )?;
+        if self.has_conflicts(path, "@")? {
+            return Err(self.undo_conflict(path, format!("merging {source} into {target}")));
+        }
+
+        self.run(path, &["bookmark", "set", target.as_str(), "-r", "@"])?;
+        let merged = self.commit_id_of(path, "@")?;
+        self.run(path, &["new"])?;
+        Ok(merged)
+    }
+
+    fn rebase(&self, path: &str, branch: &BranchName, onto: &BranchName) -> VcsResult<()> {
+        self.run(
+            path,
+            &["rebase", "-b", branch.as_str(), "-d", onto.as_str()],
+        )?;
+        if self.has_conflicts(path, &format!("{onto}..{branch}"))? {
+            return Err(self.undo_conflict(path, format!("rebasing {branch} onto {onto}")));
+        }
+        Ok(())
+    }
+
+    fn diff_summary(&self, path: &str) -> VcsResult<DiffSummary> {
+        self.run(path, &["diff", "--stat"])
+            .map(|output| parse_diff_stat(&output))
+    }
+
+    fn list_branches(&self, path: &str, all: bool) -> VcsResult<Vec<Branch>> {
+        let args: &[&str] = if all {
+            &["bookmark", "list", "--all"]
+        } else {
+            &["bookmark", "list"]
+        };
+        self.run(path, args)
+            .map(|output| parse_bookmark_list(&output))
+    }
+
+    fn move_branch(&self, path: &str, name: &BranchName, target: &str) -> VcsResult<()> {
+        self.run(path, &["bookmark", "move", name.as_str(), "--to", target])
+            .map(|_| ())
+    }
+
+    fn push_branch(&self, path: &str, name: &BranchName) -> VcsResult<()> {
+        self.run(path, &["git", "push", "--bookmark", name.as_str()])
+            .map(|_| ())
+    }
+
+    fn list_workspaces(&self, path: &str) -> VcsResult<Vec<String>> {
+        self.run(path, &["workspace", "list"])
+            .map(|output| parse_workspace_list(&output))
+    }
+
+    fn forget_workspace(&self, path: &str, name: &str) -> VcsResult<()> {
+        self.run(path, &["workspace", "forget", name]).map(|_| ())
+    }
+
+    fn add_workspace(
+        &self,
+        path: &str,
+        name: &str,
+        workspace_path: &str,
+        base: Option<&str>,
+    ) -> VcsResult<()> {
+        let mut args = vec!["workspace", "add", "--name", name];
+        if let Some(base) = base {
+            args.extend(["-r", base]);
+        }
+        args.push(workspace_path);
+        self.run(path, &args).map(|_| ())
+    }
+
+    fn workspace_root(&self, path: &str) -> VcsResult<String> {
+        self.run(path, &["workspace", "root"])
+            .map(|output| output.trim().to_string())
+    }
+
+    fn tree_id(&self, path: &str) -> VcsResult<Option<String>> {
+        // Snapshots the working copy first, so the commit covers uncommitted edits
+        let commit = self.commit_id_of(path, "@")?;
+        let root = self.workspace_root(path)?;
+        let Some(git_dir) = git_store_dir(Path::new(&root)).map_err(|e| {
+            VcsError::OperationFailed(format!("failed to find jj's git store: {e}"))
+        })?
+        else {
+            return Ok(None);
+        };
+
+        let tree = format!("{commit}^{{tree}}");
+        let output = Command::new("git")
+            .arg("--git-dir")
+            .arg(&git_dir)
+            .args(["rev-parse", "--verify", "--quiet", &tree])
+            .output()
+            .map_err(|e| VcsError::OperationFailed(format!("failed to run git: {e}")))?;
+        if output.status.success() {
+            Ok(Some(
+                String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            ))
+        } else {
+            Err(VcsError::CommitNotFound(commit.to_string()))
+        }
+    }
+}
+
+/// Git repository a JJ repository keeps its commits in, `None` when its
+/// store is not git-backed
+///
+/// `.jj/repo` is a directory in the workspace the repository was created in
+/// and a file naming that directory in every other workspace. The store's
+/// `git_target` names the git directory: the workspace's own `.git` when
+/// colocated, otherwise one inside the store.
+fn git_store_dir(workspace_root: &Path) -> std::io::Result<Option<PathBuf>> {
+    let dot_jj = workspace_root.join(".jj");
+    let mut repo = dot_jj.join("repo");
+    if repo.is_file() {
+        repo = dot_jj.join(fs::read_to_string(&repo)?.trim());
+    }
+    let store = repo.join("store");
+    match fs::read_to_string(store.join("git_target")) {
+        Ok(target) => Ok(Some(store.join(target.trim()))),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Map a failed `jj` invocation onto a [`VcsError`].
+fn classify_failure(path: &str, args: &[&str], stderr: &str) -> VcsError {
+    let stderr = stderr.trim();
+    let lower = stderr.to_lowercase();
+    let bookmark = match args {
+        ["bookmark", _, name, ..] | ["git", "push", "--bookmark", name] => Some(*name),
+        _ => None,
+    };
+
+    if lower.contains("there is no jj repo") {
+        VcsError::RepoNotFound(path.to_string())
+    } else if lower.contains("no such bookmark") || lower.contains("no matching bookmarks") {
+        VcsError::BranchNotFound(bookmark.map_or_else(|| stderr.to_string(), String::from))
+    } else if lower.contains("revision") && lower.contains("doesn't exist") {
+        VcsError::CommitNotFound(stderr.to_string())
+    } else if lower.contains("already exists") {
+        VcsError::InvalidOperation(stderr.to_string())
+    } else {
+        VcsError::OperationFailed(format!("jj {}: {stderr}", args.join(" ")))
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// PURE PARSERS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Parse output produced with [`LOG_TEMPLATE`]
+///
+/// Lines that do not carry a change id and commit id are skipped. The first
+/// bookmark on a change becomes its branch.
+#[must_use]
+pub fn parse_log(output: &str) -> Vec<Change> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(6, '\t');
+            let change_id = fields.next().map(str::trim).filter(|s| !s.is_empty())?;
+            let commit_id = fields.next().map(str::trim).filter(|s| !s.is_empty())?;
+            let branch = fields
+                .next()
+                .and_then(|b| b.split_whitespace().next())
+                .and_then(|b| BranchName::new(b).ok());
+            let author = fields
+                .next()
+                .map_or_else(String::new, |a| a.trim().to_string());
+            let timestamp = fields
+                .next()
+                .and_then(|t| t.trim().parse().ok())
+                .unwrap_or(0);
+            let description = fields
+                .next()
+                .map_or_else(String::new, |d| d.trim().to_string());
+
+            Some(Change {
+                id: ChangeId::new(change_id),
+                commit_id: CommitId::new(commit_id),
+                branch,
+                description,
+                author,
+                timestamp,
+            })
+        })
+        .collect()
+}
+
+/// Parse `jj bookmark list` output
+///
+/// Handles multi-line format:
+/// - Main bookmarks: "name: `change_id` `commit_id` description"
+/// - Remote bookmarks (indented): "  @remote: `change_id` `commit_id` description"
+/// - Deleted bookmarks: "name: ... (deleted)" on following line
+/// - Legacy format: "name: `revision_hash`"
+///
+/// Only returns non-deleted local bookmarks (skips indented remote lines).
+#[must_use]
+pub fn parse_bookmark_list(output: &str) -> Vec<Branch> {
+    output
+        .lines()
+        .filter(|line| {
+            let trimmed = line.trim();
+            !trimmed.is_empty() && !line.starts_with("  @") && !trimmed.contains("(deleted)")
+        })
+        .filter_map(|line| {
+            let (name, rest) = line.split_once(':')?;
+            let name = BranchName::new(name.trim()).ok()?;
+
+            // New format: "change_id commit_id description" -> commit_id
+            // Legacy format: "revision_hash" -> first token
+            let tokens: Vec<&str> = rest.split_whitespace().collect();
+            let revision = if tokens.len() >= 2 {
+                tokens.get(1)
+            } else {
+                tokens.first()
+            };
+
+            Some(Branch {
+                name,
+                commit_id: revision.map(|r| CommitId::new(*r)),
+                remote: rest.contains("@origin"),
+            })
+        })
+        .collect()
+}
+
+/// Parse `jj workspace list` output ("name: `change_id` `commit_id` description")
+#[must_use]
+pub fn parse_workspace_list(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, _)| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_store_dir_follows_workspace_pointers() -> std::io::Result<()> {
+        let temp = tempfile::TempDir::new()?;
+        let main = temp.path().join("repo");
+        let store = main.join(".jj/repo/store");
+        fs::create_dir_all(&store)?;
+        fs::write(store.join("git_target"), "../../../.git")?;
+        let workspace = temp.path().join("feature");
+        fs::create_dir_all(workspace.join(".jj"))?;
+        fs::write(
+            workspace.join(".jj/repo"),
+            main.join(".jj/repo").to_string_lossy().as_bytes(),
+        )?;
+
+        let expected = Some(store.join("../../../.git"));
+        assert_eq!(git_store_dir(&main)?, expected);
+        assert_eq!(git_store_dir(&workspace)?, expected);
+
+        fs::remove_file(store.join("git_target"))?;
+        assert_eq!(git_store_dir(&main)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_log_reads_tab_separated_changes() {
+        let output = "kxqpmv\tabc123\tfeature main\tdev@example.com\t1700000000\tAdd parser\n\
+                      zzzzzz\t000000\t\t\t0\t\n";
+        let changes = parse_log(output);
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].id.as_str(), "kxqpmv");
+        assert_eq!(changes[0].commit_id.as_str(), "abc123");
+        assert_eq!(
+            changes[0].branch.as_ref().map(BranchName::as_str),
+            Some("feature")
+        );
+        assert_eq!(changes[0].author, "dev@example.com");
+        assert_eq!(changes[0].timestamp, 1_700_000_000);
+        assert_eq!(changes[0].description, "Add parser");
+        assert_eq!(changes[1].branch, None);
+    }
+
+    #[test]
+    fn test_parse_log_skips_malformed_lines() {
+        assert!(parse_log("\nnot a log line\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_bookmark_list_local_and_remote() {
+        let output = "feature: kxqpmv abc123 Add parser\n\
+                      \x20 @origin: kxqpmv abc123 Add parser\n\
+                      main: zzzzzz def456 (empty) init\n\
+                      old: qqqqqq 111111 (deleted)\n\
+                      legacy: 9f8e7d\n";
+        let branches = parse_bookmark_list(output);
+
+        let names: Vec<&str> = branches.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, vec!["feature", "main", "legacy"]);
+        assert_eq!(
+            branches[0].commit_id.as_ref().map(CommitId::as_str),
+            Some("abc123")
+        );
+        assert_eq!(
+            branches[2].commit_id.as_ref().map(CommitId::as_str),
+            Some("9f8e7d")
+        );
+    }
+
+    #[test]
+    fn test_parse_workspace_list() {
+        let output = "default: sqpuoqvx 3a2f1e (no description set)\n\
+                      bd-42: mzvwutvl 9c8b7a Fix parser\n";
+        assert_eq!(parse_workspace_list(output), vec!["default", "bd-42"]);
+    }
+
+    #[test]
+    fn test_classify_failure() {
+        assert!(matches!(
+            classify_failure("/tmp", &["status"], "Error: There is no jj repo in \".\""),
+            VcsError::RepoNotFound(_)
+        ));
+        assert!(matches!(
+            classify_failure(
+                "/tmp",
+                &["bookmark", "delete", "gone"],
+                "Error: No such bookmark: gone"
+            ),
+            VcsError::BranchNotFound(name) if name == "gone"
+        ));
+        assert!(matches!(
+            classify_failure("/tmp", &["git", "push"], "rejected"),
+            VcsError::OperationFailed(_)
+        ));
+    }
+}
@@ -1,10 +1,28 @@
 //! VCS abstraction - Git and JJ backend support
 //!
 //! This module provides VCS abstraction for Git and Jujutsu operations.
+//! Commands take a `&dyn VcsBackend` instead of shelling out, so the backend
+//! can be swapped per repository and replaced in tests.
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod fake;
+mod git_backend;
+mod jj_backend;
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+#[cfg(any(test, feature = "test-utils"))]
+pub use self::fake::{FakeBackend, FakeFailure, FakeOp};
+pub use self::{
+    git_backend::{
+        parse_git_log, parse_porcelain_status, parse_ref_list, parse_worktree_list, GitBackend,
+        Worktree,
+    },
+    jj_backend::{parse_bookmark_list, parse_log, parse_workspace_list, JjBackend},
+};
+use crate::jj::DiffSummary;
+
 #[derive(Debug, Error)]
 pub enum VcsError {
     #[error("Repository not found: {0}")]
@@ -19,6 +37,14 @@ pub enum VcsError {
     OperationFailed(String),
     #[error("Invalid operation: {0}")]
     InvalidOperation(String),
+    #[error("{0} requires a JJ repository. Run 'jj git init --colocate' to add JJ to this git repository.")]
+    RequiresJj(String),
+}
+
+impl From<VcsError> for crate::Error {
+    fn from(err: VcsError) -> Self {
+        Self::VcsError(err.to_string())
+    }
 }
 
 pub type VcsResult<T> = Result<T, VcsError>;
@@ -118,6 +144,15 @@ pub struct Change {
     pub timestamp: i64,
 }
 
+/// A branch (JJ bookmark) and where it points
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Branch {
+    pub name: BranchName,
+    pub commit_id: Option<CommitId>,
+    /// Whether the branch is tracked on a remote
+    pub remote: bool,
+}
+
 /// VCS Backend trait
 pub trait VcsBackend: Send + Sync {
     /// Get the backend type
@@ -149,6 +184,11 @@ pub trait VcsBackend: Send + Sync {
     /// Checkout a branch/commit
     fn checkout(&self, path: &str, target: &str) -> VcsResult<()>;
 
+    /// Check out a new change on top of every commit in `parents`, merging
+    /// them. Fails with [`VcsError::Conflict`] if the parents conflict, with
+    /// no conflicted change left checked out.
+    fn checkout_merge(&self, path: &str, parents: &[&str]) -> VcsResult<()>;
+
     /// Commit changes
     fn commit(&self, path: &str, message: &str) -> VcsResult<CommitId>;
 
@@ -158,6 +198,9 @@ pub trait VcsBackend: Send + Sync {
     /// Push changes
     fn push(&self, path: &str) -> VcsResult<()>;
 
+    /// Commit id of a single revision
+    fn resolve(&self, path: &str, revision: &str) -> VcsResult<CommitId>;
+
     /// Get diff
     fn diff(&self, path: &str, from: &CommitId, to: &CommitId) -> VcsResult<String>;
 
@@ -166,6 +209,48 @@ pub trait VcsBackend: Send + Sync {
 
     /// Rebase branch
     fn rebase(&self, path: &str, branch: &BranchName, onto: &BranchName) -> VcsResult<()>;
+
+    /// Summary of uncommitted changes in the working copy
+    fn diff_summary(&self, path: &str) -> VcsResult<DiffSummary>;
+
+    /// List local branches; with `all`, include remote-only branches too
+    fn list_branches(&self, path: &str, all: bool) -> VcsResult<Vec<Branch>>;
+
+    /// Move an existing branch to `target`
+    fn move_branch(&self, path: &str, name: &BranchName, target: &str) -> VcsResult<()>;
+
+    /// Push a single branch to the default remote
+    fn push_branch(&self, path: &str, name: &BranchName) -> VcsResult<()>;
+
+    /// Names of the workspaces registered in the repository
+    fn list_workspaces(&self, path: &str) -> VcsResult<Vec<String>>;
+
+    /// Stop tracking a workspace. JJ leaves its directory on disk; git removes
+    /// the worktree directory along with it.
+    fn forget_workspace(&self, path: &str, name: &str) -> VcsResult<()>;
+
+    /// Create workspace `name` at `workspace_path`, starting from `base` (or the
+    /// current commit)
+    fn add_workspace(
+        &self,
+        path: &str,
+        name: &str,
+        workspace_path: &str,
+        base: Option<&str>,
+    ) -> VcsResult<()>;
+
+    /// Root directory of the workspace containing `path`
+    fn workspace_root(&self, path: &str) -> VcsResult<String>;
+
+    /// Id of the files checked out at `path`: the same files give the same
+    /// id, whatever commit they are in
+    ///
+    /// JJ's working-copy tree, which covers uncommitted edits since JJ
+    /// snapshots them first and is kept when the working copy is committed,
+    /// or `None` when the repository's store is not git. Git's `HEAD` tree,
+    /// or `None` while the worktree has uncommitted changes the tree does not
+    /// cover.
+    fn tree_id(&self, path: &str) -> VcsResult<Option<String>>;
 }
 
 /// Detect which VCS backend to use
@@ -181,3 +266,28 @@ pub fn detect_backend(path: &str) -> BackendType {
         BackendType::Git // Default to git
     }
 }
+
+/// Find the backend of the nearest repository enclosing `path`
+///
+/// Walks up from `path` and stops at the first directory holding `.jj` or
+/// `.git`. Colocated repositories have both and use JJ. A linked git worktree
+/// has a `.git` file rather than a directory, which also counts.
+pub fn discover_backend(path: &std::path::Path) -> Option<BackendType> {
+    path.ancestors().find_map(|dir| {
+        if dir.join(".jj").is_dir() {
+            Some(BackendType::Jj)
+        } else if dir.join(".git").exists() {
+            Some(BackendType::Git)
+        } else {
+            None
+        }
+    })
+}
+
+/// Backend implementation for `backend_type`
+pub fn backend_for(backend_type: BackendType) -> Box<dyn VcsBackend> {
+    match backend_type {
+        BackendType::Jj => Box::new(JjBackend::new()),
+        BackendType::Git => Box::new(GitBackend::new()),
+    }
+}
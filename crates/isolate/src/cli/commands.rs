@@ -78,6 +78,7 @@ pub fn cmd_add() -> ClapCommand {
                 "isolate add bugfix-123 --no-open       Create without opening terminal",
                 "isolate add quick-test --no-hooks      Skip post-create hooks",
                 "isolate add work --bead isolate-abc123     Associate with bead isolate-abc123",
+                "isolate add api-client --on api        Stack on session 'api' before it lands",
                 "isolate add --example-json            Show example JSON output",
             ],
             Some(json_docs::add()),
@@ -95,6 +96,12 @@ pub fn cmd_add() -> ClapCommand {
                 .value_name("BEAD_ID")
                 .help("Associate this session with a bead/issue ID"),
         )
+        .arg(
+            Arg::new("on")
+                .long("on")
+                .value_name("PARENT")
+                .help("Stack on another session, starting from its current changes"),
+        )
         .arg(
             Arg::new("no-hooks")
                 .long("no-hooks")
@@ -1317,6 +1324,7 @@ pub fn cmd_checkpoint() -> ClapCommand {
         )
 }
 
+#[allow(clippy::too_many_lines)]
 pub fn cmd_done() -> ClapCommand {
     ClapCommand::new("done")
         .about("Complete work and merge workspace to main")
@@ -1328,6 +1336,8 @@ pub fn cmd_done() -> ClapCommand {
                 "isolate done --dry-run                  Preview without executing",
                 "isolate done --keep-workspace           Keep workspace after merge",
                 "isolate done --detect-conflicts         Check for conflicts before merging",
+                "isolate done --queue                    Enqueue for the merge queue instead",
+                "isolate done --queue --priority 10      Enqueue ahead of default-priority work",
                 "isolate done --json                     Get JSON output",
             ],
             Some(json_docs::done()),
@@ -1384,6 +1394,24 @@ pub fn cmd_done() -> ClapCommand {
                 .action(clap::ArgAction::SetTrue)
                 .help("Skip workspace retention (cleanup immediately)"),
         )
+        .arg(
+            Arg::new("queue")
+                .long("queue")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with_all(["dry-run", "squash", "detect-conflicts"])
+                .help("Enqueue for merging instead of merging now"),
+        )
+        .arg(
+            Arg::new("priority")
+                .long("priority")
+                .short('p')
+                .value_name("N")
+                .requires("queue")
+                .value_parser(
+                    clap::value_parser!(u32).range(0..=i64::from(isolate_core::MAX_PRIORITY)),
+                )
+                .help("Queue priority (0 = first in line, default 50)"),
+        )
         .arg(
             Arg::new("json")
                 .long("json")
@@ -2314,10 +2342,11 @@ pub fn cmd_wait() -> ClapCommand {
                     "session-unlocked",
                     "healthy",
                     "session-status",
+                    "queue-landed",
                 ])
                 .help("Condition to wait for"),
         )
-        .arg(Arg::new("name").help("Session name (for session conditions)"))
+        .arg(Arg::new("name").help("Session name (for session and queue conditions)"))
         .arg(
             Arg::new("status")
                 .long("status")
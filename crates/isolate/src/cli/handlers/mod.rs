@@ -2,11 +2,13 @@
 //!
 //! This module is organized into logical submodules:
 //! - `workspace`: Session/workspace management (init, add, remove, switch, etc.)
-//! - `sync`: Sync, diff, submit, done, abort
+//! - `sync`: Sync, restack, diff, submit, done, abort
 //! - `bookmark`: Bookmark operations
 //! - `integrity`: Integrity, doctor, clean, prune
 //! - `checkpoint`: Checkpoint, undo, revert, recover, retry, rollback
 //! - `coordination`: Coordination commands
+//! - `queue`: Merge queue (add, list, remove, reprioritize, status)
+//! - `pipeline`: Orchestrator pipelines (create, run, status, list, recover, metrics)
 //! - `introspection`: AI, introspect, context, whereami, whoami, etc.
 //! - `batch`: Batch and events operations
 //! - `backup`: Backup, export, import
@@ -36,6 +38,8 @@ pub mod domain;
 pub mod integrity;
 pub mod introspection;
 pub mod json_format;
+pub mod pipeline;
+pub mod queue;
 pub mod session;
 pub mod sync;
 pub mod utility;
@@ -54,8 +58,10 @@ pub use self::{
         handle_ai, handle_can_i, handle_context, handle_contract, handle_examples, handle_help,
         handle_introspect, handle_validate, handle_whatif, handle_whereami, handle_whoami,
     },
+    pipeline::handle_pipeline,
+    queue::handle_queue,
     session::handle_session,
-    sync::{handle_abort, handle_diff, handle_done, handle_submit, handle_sync},
+    sync::{handle_abort, handle_diff, handle_done, handle_restack, handle_submit, handle_sync},
     utility::{handle_completions, handle_config, handle_query, handle_schema, handle_wait},
     workspace::{
         handle_add, handle_clone, handle_init, handle_list, handle_pause, handle_remove,
@@ -187,6 +193,7 @@ pub async fn run_cli() -> Result<()> {
                 alias_handler::ALIAS_SYNC.warn();
                 handle_sync(sub_m).await
             }
+            Some(("restack", sub_m)) => handle_restack(sub_m).await,
             Some(("diff", sub_m)) => handle_diff(sub_m).await,
             Some(("config", sub_m)) => handle_config(sub_m).await,
             Some(("clean", sub_m)) => handle_clean(sub_m).await,
@@ -235,6 +242,8 @@ pub async fn run_cli() -> Result<()> {
             Some(("rollback", sub_m)) => handle_rollback(sub_m).await,
             Some(("task", sub_m)) => handle_task(sub_m).await,
             Some(("session", sub_m)) => handle_session(sub_m).await,
+            Some(("queue", sub_m)) => handle_queue(sub_m).await,
+            Some(("pipeline", sub_m)) => handle_pipeline(sub_m).await,
             _ => {
                 build_cli().print_help()?;
                 Ok(())
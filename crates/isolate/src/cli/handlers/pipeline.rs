@@ -0,0 +1,485 @@
+//! Pipeline handlers: pipeline create, run, status, list, recover, metrics,
+//! inbox, resume, accept, fail
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::ArgMatches;
+use isolate_core::{
+    output::{
+        emit_stdout, Action, ActionStatus, ActionTarget, ActionVerb, Message, OutputLine,
+        ResultKind, ResultOutput, Summary, SummaryType,
+    },
+    OutputFormat,
+};
+use orchestrator::{Escalation, HumanAction, Pipeline, PipelineId, PipelineState, StateStore};
+
+use super::{json_format::get_format, CommandExit};
+use crate::commands::{
+    pipeline::{
+        self,
+        types::{
+            decision_name, pipeline_output, state_name, MetricsExport, PipelineCreateArgs,
+            PipelineMetricsOutput, PipelineRunOptions, PipelineRunOutcome, Settlement,
+        },
+    },
+    spawn::SessionAgent,
+};
+
+pub async fn handle_pipeline(sub_m: &ArgMatches) -> Result<()> {
+    match sub_m.subcommand() {
+        Some(("create", args)) => handle_pipeline_create(args).await,
+        Some(("run", args)) => handle_pipeline_run(args).await,
+        Some(("status", args)) => handle_pipeline_status(args).await,
+        Some(("list", args)) => handle_pipeline_list(args).await,
+        Some(("recover", args)) => handle_pipeline_recover(args).await,
+        Some(("metrics", args)) => handle_pipeline_metrics(args).await,
+        Some(("inbox", args)) => handle_pipeline_inbox(args).await,
+        Some(("resume" | "accept" | "fail", args)) => handle_pipeline_settle(sub_m, args).await,
+        _ => anyhow::bail!("Unknown pipeline subcommand. Run 'isolate pipeline --help'"),
+    }
+}
+
+fn run_options(sub_m: &ArgMatches) -> PipelineRunOptions {
+    let agent = (!sub_m.get_flag("no-agent")).then(|| {
+        SessionAgent::new(
+            sub_m
+                .get_one::<String>("agent-command")
+                .cloned()
+                .unwrap_or_else(|| "claude".to_string()),
+            sub_m
+                .get_many::<String>("agent-args")
+                .map(|vals| vals.cloned().collect())
+                .unwrap_or_default(),
+            sub_m.get_one::<u64>("timeout").copied().unwrap_or(14400),
+        )
+    });
+
+    PipelineRunOptions {
+        scenarios: sub_m
+            .get_one::<String>("scenarios")
+            .map_or_else(|| PathBuf::from("scenarios"), PathBuf::from),
+        specs: sub_m.get_one::<String>("specs").map(PathBuf::from),
+        agent,
+        concurrency: sub_m.get_one::<usize>("concurrency").copied().unwrap_or(8),
+        policy: sub_m.get_one::<String>("policy").map(PathBuf::from),
+    }
+}
+
+async fn open() -> Result<StateStore> {
+    let state_dir = pipeline::state_dir().await?;
+    pipeline::open_store(&state_dir)
+}
+
+fn required_id(sub_m: &ArgMatches) -> Result<&str> {
+    sub_m
+        .get_one::<String>("id")
+        .map(String::as_str)
+        .ok_or_else(|| anyhow::anyhow!("Pipeline id is required"))
+}
+
+/// Run `ids`, plus every unfinished pipeline with `resume`
+async fn run_scheduled(
+    sub_m: &ArgMatches,
+    ids: &[&str],
+    resume: bool,
+) -> Result<Vec<PipelineRunOutcome>> {
+    let state_dir = pipeline::state_dir().await?;
+    let scheduler = pipeline::start_scheduler(&state_dir, &run_options(sub_m), resume)?;
+    let ids = {
+        let store = scheduler.store();
+        ids.iter()
+            .map(|id| pipeline::resolve_id(&store, id))
+            .collect::<Result<Vec<PipelineId>>>()?
+    };
+    tokio::task::block_in_place(|| pipeline::run_scheduled(&scheduler, &ids))
+}
+
+fn emit_pipeline(pipeline: &Pipeline) -> Result<()> {
+    let line = pipeline_output(pipeline).map_err(|e| anyhow::anyhow!("{e}"))?;
+    emit_stdout(&OutputLine::Pipeline(line)).map_err(|e| anyhow::anyhow!("{e}"))
+}
+
+fn emit_result(success: bool, message: &str) -> Result<()> {
+    let message = Message::new(message).map_err(|e| anyhow::anyhow!("{e}"))?;
+    let result = if success {
+        ResultOutput::success(ResultKind::Command, message)
+    } else {
+        ResultOutput::failure(ResultKind::Command, message)
+    }
+    .map_err(|e| anyhow::anyhow!("{e}"))?;
+    emit_stdout(&OutputLine::Result(result)).map_err(|e| anyhow::anyhow!("{e}"))
+}
+
+fn print_pipeline(pipeline: &Pipeline) {
+    println!(
+        "  {} {} (iteration {}/{}) {}",
+        pipeline.id.0,
+        state_name(pipeline.state),
+        pipeline.iteration,
+        pipeline.max_iterations,
+        pipeline.spec_path
+    );
+    if let Some(ref session) = pipeline.session {
+        println!("    Session: {session}");
+    }
+    if let Some(ref error) = pipeline.last_error {
+        println!("    Error: {error}");
+    }
+}
+
+fn print_decisions(pipeline: &Pipeline) {
+    if pipeline.decisions.is_empty() {
+        return;
+    }
+    println!("    Decisions:");
+    for record in &pipeline.decisions {
+        let class = record
+            .error_class
+            .map(|class| format!(" [{}]", class.as_str()))
+            .unwrap_or_default();
+        println!(
+            "      {} {}{class} after iteration {}: {}",
+            record.at.format("%Y-%m-%d %H:%M:%S"),
+            decision_name(record.decision),
+            record.iteration,
+            record.reason
+        );
+    }
+}
+
+fn print_reviews(pipeline: &Pipeline) {
+    if pipeline.reviews.is_empty() {
+        return;
+    }
+    println!("    Reviews:");
+    for review in &pipeline.reviews {
+        let action = match review.action {
+            HumanAction::Resume => "resume",
+            HumanAction::Accept => "accept",
+            HumanAction::Fail => "fail",
+        };
+        println!(
+            "      {} {action} by {}{}",
+            review.at.format("%Y-%m-%d %H:%M:%S"),
+            review.author,
+            review
+                .note
+                .as_ref()
+                .map(|note| format!(": {note}"))
+                .unwrap_or_default()
+        );
+    }
+}
+
+async fn handle_pipeline_create(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let args = PipelineCreateArgs {
+        spec: sub_m
+            .get_one::<String>("spec")
+            .map(PathBuf::from)
+            .ok_or_else(|| anyhow::anyhow!("Spec is required"))?,
+        max_iterations: sub_m
+            .get_one::<u32>("max-iterations")
+            .copied()
+            .unwrap_or(10),
+        quality_threshold: sub_m
+            .get_one::<u32>("quality-threshold")
+            .copied()
+            .unwrap_or(80),
+    };
+
+    let mut store = open().await?;
+    let created = pipeline::run_create(&args, &mut store)?;
+    if format.is_json() {
+        emit_pipeline(&created)?;
+        emit_result(true, &format!("Created pipeline {}", created.id.0))
+    } else {
+        println!("✓ Created pipeline {}", created.id.0);
+        print_pipeline(&created);
+        println!();
+        println!("NEXT: isolate pipeline run {}", created.id.0);
+        Ok(())
+    }
+}
+
+/// Report each run: a `run` action and the pipeline in JSON mode
+fn output_outcomes(
+    outcomes: &[PipelineRunOutcome],
+    verb: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    for outcome in outcomes {
+        let decision = decision_name(outcome.decision);
+        if format.is_json() {
+            let status = if outcome.accepted() {
+                ActionStatus::Completed
+            } else {
+                ActionStatus::Failed
+            };
+            let action = Action::new(
+                ActionVerb::new(verb).map_err(|e| anyhow::anyhow!("{e}"))?,
+                ActionTarget::new(&outcome.pipeline.id.0).map_err(|e| anyhow::anyhow!("{e}"))?,
+                status,
+            )
+            .with_result(format!("Decision: {decision}"));
+            emit_stdout(&OutputLine::Action(action)).map_err(|e| anyhow::anyhow!("{e}"))?;
+            emit_pipeline(&outcome.pipeline)?;
+        } else {
+            let mark = if outcome.accepted() { "✓" } else { "✗" };
+            println!(
+                "{mark} Pipeline {} decision: {decision}",
+                outcome.pipeline.id.0
+            );
+            print_pipeline(&outcome.pipeline);
+        }
+    }
+    Ok(())
+}
+
+/// Exit non-zero unless every pipeline was accepted
+fn exit_for(outcomes: &[PipelineRunOutcome]) -> Result<()> {
+    if outcomes.iter().all(PipelineRunOutcome::accepted) {
+        Ok(())
+    } else {
+        Err(anyhow::Error::new(CommandExit::new(1)))
+    }
+}
+
+async fn handle_pipeline_run(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let ids: Vec<&str> = sub_m
+        .get_many::<String>("id")
+        .map(|ids| ids.map(String::as_str).collect())
+        .unwrap_or_default();
+
+    let outcomes = run_scheduled(sub_m, &ids, false).await?;
+    output_outcomes(&outcomes, "run", format)?;
+    if format.is_json() {
+        let accepted = outcomes.iter().filter(|o| o.accepted()).count();
+        let message = match outcomes.as_slice() {
+            [outcome] => format!(
+                "Pipeline {} decision: {}",
+                outcome.pipeline.id.0,
+                decision_name(outcome.decision)
+            ),
+            _ => format!("Ran {} pipeline(s), {accepted} accepted", outcomes.len()),
+        };
+        emit_result(accepted == outcomes.len(), &message)?;
+    }
+    exit_for(&outcomes)
+}
+
+async fn handle_pipeline_status(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let store = open().await?;
+    let id = pipeline::resolve_id(&store, required_id(sub_m)?)?;
+    let found = store.get(&id)?;
+
+    if format.is_json() {
+        emit_pipeline(found)
+    } else {
+        println!("Pipeline {}: {}", found.id.0, found.state);
+        print_pipeline(found);
+        print_decisions(found);
+        print_reviews(found);
+        Ok(())
+    }
+}
+
+async fn handle_pipeline_list(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let state = sub_m
+        .get_one::<String>("state")
+        .map(|s| serde_json::from_value::<PipelineState>(serde_json::Value::String(s.clone())))
+        .transpose()?;
+    let store = open().await?;
+
+    let pipelines = pipeline::run_list(&store, state);
+    if format.is_json() {
+        for found in &pipelines {
+            emit_pipeline(found)?;
+        }
+        let message = Message::new(format!("{} pipeline(s)", pipelines.len()))
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        let summary =
+            Summary::new(SummaryType::Count, message).map_err(|e| anyhow::anyhow!("{e}"))?;
+        emit_stdout(&OutputLine::Summary(summary)).map_err(|e| anyhow::anyhow!("{e}"))
+    } else if pipelines.is_empty() {
+        println!("No pipelines.");
+        Ok(())
+    } else {
+        println!("Pipelines ({}):", pipelines.len());
+        pipelines.iter().for_each(print_pipeline);
+        Ok(())
+    }
+}
+
+async fn handle_pipeline_recover(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let outcomes = match sub_m.get_one::<String>("id") {
+        Some(id) => run_scheduled(sub_m, &[id.as_str()], false).await?,
+        None => run_scheduled(sub_m, &[], true).await?,
+    };
+    output_outcomes(&outcomes, "recover", format)?;
+
+    let accepted = outcomes.iter().filter(|o| o.accepted()).count();
+    let message = match outcomes.len() {
+        0 => "Nothing to recover".to_string(),
+        n => format!("Recovered {n} pipeline(s), {accepted} accepted"),
+    };
+    if format.is_json() {
+        emit_result(accepted == outcomes.len(), &message)?;
+    } else {
+        println!("{message}");
+    }
+    exit_for(&outcomes)
+}
+
+async fn handle_pipeline_metrics(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let store = open().await?;
+
+    if let Some(export) = sub_m.get_one::<String>("export") {
+        let export = match export.as_str() {
+            "csv" => MetricsExport::Csv,
+            _ => MetricsExport::Prometheus,
+        };
+        let weeks = sub_m.get_one::<u32>("weeks").copied().unwrap_or(4);
+        print!(
+            "{}",
+            pipeline::run_export(&store, export, weeks, chrono::Utc::now())?
+        );
+        return Ok(());
+    }
+
+    let metrics = pipeline::run_metrics(&store);
+
+    if format.is_json() {
+        let message = Message::new(format!("{} pipeline(s)", metrics.total))
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        let result = ResultOutput::success(ResultKind::Assessment, message)
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .with_data(serde_json::to_value(&metrics)?);
+        emit_stdout(&OutputLine::Result(result)).map_err(|e| anyhow::anyhow!("{e}"))
+    } else {
+        print_metrics(&metrics);
+        Ok(())
+    }
+}
+
+fn print_metrics(metrics: &PipelineMetricsOutput) {
+    println!("Pipelines: {}", metrics.total);
+    for (state, count) in &metrics.by_state {
+        println!("  {state:<18} {count}");
+    }
+    println!("Average iterations: {:.1}", metrics.average_iterations);
+    println!(
+        "Acceptance rate:    {:.0}%",
+        metrics.acceptance_rate * 100.0
+    );
+}
+
+async fn handle_pipeline_inbox(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let store = open().await?;
+
+    let escalations = pipeline::run_inbox(&store);
+    if format.is_json() {
+        let message = Message::new(format!("{} escalation(s)", escalations.len()))
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        let result = ResultOutput::success(ResultKind::Assessment, message)
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .with_data(serde_json::to_value(&escalations)?);
+        emit_stdout(&OutputLine::Result(result)).map_err(|e| anyhow::anyhow!("{e}"))
+    } else if escalations.is_empty() {
+        println!("No escalated pipelines.");
+        Ok(())
+    } else {
+        println!("Escalations ({}):", escalations.len());
+        escalations.iter().for_each(print_escalation);
+        println!();
+        println!("NEXT: isolate pipeline resume <id> --guidance TEXT, accept <id> or fail <id>");
+        Ok(())
+    }
+}
+
+fn print_escalation(escalation: &Escalation) {
+    println!(
+        "  {} (iteration {}/{}) {}",
+        escalation.pipeline_id.0,
+        escalation.iteration,
+        escalation.max_iterations,
+        escalation.spec_path
+    );
+    println!(
+        "    Escalated: {}",
+        escalation.escalated_at.format("%Y-%m-%d %H:%M:%S")
+    );
+    if let Some(ref session) = escalation.session {
+        println!("    Session: {session}");
+    }
+    if let Some(ref reason) = escalation.reason {
+        println!("    Reason: {reason}");
+    }
+    if let Some(ref feedback) = escalation.feedback {
+        println!("    Feedback:");
+        for line in feedback.lines() {
+            println!("      {line}");
+        }
+    }
+}
+
+/// `resume`, `accept` or `fail` an escalated pipeline
+async fn handle_pipeline_settle(sub_m: &ArgMatches, args: &ArgMatches) -> Result<()> {
+    let format = get_format(args);
+    let (verb, settlement) = match sub_m.subcommand_name() {
+        Some("resume") => (
+            "resume",
+            Settlement::Resume {
+                guidance: args
+                    .get_one::<String>("guidance")
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Guidance is required"))?,
+                iterations: args.get_one::<u32>("iterations").copied().unwrap_or(3),
+            },
+        ),
+        Some("accept") => (
+            "accept",
+            Settlement::Accept {
+                note: args.get_one::<String>("note").cloned(),
+            },
+        ),
+        _ => (
+            "fail",
+            Settlement::Fail {
+                reason: args.get_one::<String>("reason").cloned(),
+            },
+        ),
+    };
+    let author = pipeline::resolve_author(args.get_one::<String>("author").map(String::as_str))?;
+
+    let mut store = open().await?;
+    let settled = pipeline::run_settle(&mut store, required_id(args)?, &author, &settlement)?;
+    if format.is_json() {
+        let action = Action::new(
+            ActionVerb::new(verb).map_err(|e| anyhow::anyhow!("{e}"))?,
+            ActionTarget::new(&settled.id.0).map_err(|e| anyhow::anyhow!("{e}"))?,
+            ActionStatus::Completed,
+        )
+        .with_result(format!("By {author}"));
+        emit_stdout(&OutputLine::Action(action)).map_err(|e| anyhow::anyhow!("{e}"))?;
+        emit_pipeline(&settled)
+    } else {
+        println!(
+            "✓ Pipeline {} is now {} ({author})",
+            settled.id.0, settled.state
+        );
+        print_pipeline(&settled);
+        if settled.state == PipelineState::AgentDevelopment {
+            println!();
+            println!("NEXT: isolate pipeline run {}", settled.id.0);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,222 @@
+//! Merge queue handlers: queue add, list, remove, reprioritize, status, process
+
+use anyhow::Result;
+use clap::ArgMatches;
+use isolate_core::{coordination::QueueStore, json::schemas, SchemaEnvelope, DEFAULT_PRIORITY};
+
+use super::json_format::get_format;
+use crate::commands::{
+    get_session_db,
+    lock::types::ProductionSessionValidator,
+    queue::{
+        self,
+        processor::{acquire_processor_lock, EventLogSink, JjLandingOps, QueueProcessor},
+        types::{QueueAddArgs, QueueEntryOutput, QueueProcessOutput, QueueReprioritizeArgs},
+    },
+};
+
+pub async fn handle_queue(sub_m: &ArgMatches) -> Result<()> {
+    match sub_m.subcommand() {
+        Some(("add", args)) => handle_queue_add(args).await,
+        Some(("list", args)) => handle_queue_list(args).await,
+        Some(("remove", args)) => handle_queue_remove(args).await,
+        Some(("reprioritize", args)) => handle_queue_reprioritize(args).await,
+        Some(("status", args)) => handle_queue_status(args).await,
+        Some(("process", args)) => handle_queue_process(args).await,
+        _ => anyhow::bail!("Unknown queue subcommand. Run 'isolate queue --help'"),
+    }
+}
+
+fn required_session(sub_m: &ArgMatches) -> Result<String> {
+    sub_m
+        .get_one::<String>("session")
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("Session is required"))
+}
+
+fn print_entry(entry: &QueueEntryOutput) {
+    println!(
+        "  #{} {} (priority {}, {})",
+        entry.position + 1,
+        entry.session,
+        entry.priority,
+        entry.status
+    );
+}
+
+async fn handle_queue_add(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let args = QueueAddArgs {
+        session: required_session(sub_m)?,
+        priority: sub_m
+            .get_one::<u32>("priority")
+            .copied()
+            .unwrap_or(DEFAULT_PRIORITY),
+    };
+
+    let db = get_session_db().await?;
+    let store = QueueStore::new(db.pool().clone());
+    let validator = ProductionSessionValidator::new(db);
+
+    let output = queue::run_add(&args, &store, &validator).await?;
+    if format.is_json() {
+        let envelope = SchemaEnvelope::new(schemas::QUEUE_ADD_RESPONSE, "single", &output);
+        println!("{}", serde_json::to_string_pretty(&envelope)?);
+    } else {
+        println!("✓ Queued session '{}'", output.session);
+        print_entry(&output);
+    }
+    Ok(())
+}
+
+async fn handle_queue_list(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let db = get_session_db().await?;
+    let store = QueueStore::new(db.pool().clone());
+
+    let output = queue::run_list(&store).await?;
+    if format.is_json() {
+        let envelope = SchemaEnvelope::new(schemas::QUEUE_LIST_RESPONSE, "array", &output);
+        println!("{}", serde_json::to_string_pretty(&envelope)?);
+    } else if output.entries.is_empty() {
+        println!("Merge queue is empty.");
+    } else {
+        println!("Merge queue ({} entries):", output.total);
+        output.entries.iter().for_each(print_entry);
+    }
+    Ok(())
+}
+
+async fn handle_queue_remove(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let session = required_session(sub_m)?;
+    let db = get_session_db().await?;
+    let store = QueueStore::new(db.pool().clone());
+
+    let output = queue::run_remove(&session, &store).await?;
+    if format.is_json() {
+        let envelope = SchemaEnvelope::new(schemas::QUEUE_REMOVE_RESPONSE, "single", &output);
+        println!("{}", serde_json::to_string_pretty(&envelope)?);
+    } else {
+        println!(
+            "✓ Removed '{}' from merge queue ({} remaining)",
+            output.session, output.remaining
+        );
+    }
+    Ok(())
+}
+
+async fn handle_queue_reprioritize(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let args = QueueReprioritizeArgs {
+        session: required_session(sub_m)?,
+        priority: sub_m
+            .get_one::<u32>("priority")
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Priority is required"))?,
+    };
+    let db = get_session_db().await?;
+    let store = QueueStore::new(db.pool().clone());
+
+    let output = queue::run_reprioritize(&args, &store).await?;
+    if format.is_json() {
+        let envelope =
+            SchemaEnvelope::new(schemas::QUEUE_REPRIORITIZE_RESPONSE, "single", &output);
+        println!("{}", serde_json::to_string_pretty(&envelope)?);
+    } else {
+        println!("✓ Reprioritized '{}'", output.session);
+        print_entry(&output);
+    }
+    Ok(())
+}
+
+async fn handle_queue_status(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let session = sub_m.get_one::<String>("session").map(String::as_str);
+    let db = get_session_db().await?;
+    let store = QueueStore::new(db.pool().clone());
+
+    let output = queue::run_status(session, &store).await?;
+    if format.is_json() {
+        let envelope = SchemaEnvelope::new(schemas::QUEUE_STATUS_RESPONSE, "single", &output);
+        println!("{}", serde_json::to_string_pretty(&envelope)?);
+    } else if let Some(ref entry) = output.entry {
+        println!("Session '{}' in merge queue:", entry.session);
+        print_entry(entry);
+    } else {
+        let counts = &output.counts;
+        println!("Merge queue: {} entries", counts.total);
+        println!("  Pending:   {}", counts.pending);
+        println!("  In flight: {}", counts.in_flight);
+        println!("  Merged:    {}", counts.merged);
+        println!("  Failed:    {}", counts.failed);
+        println!("  Cancelled: {}", counts.cancelled);
+    }
+    Ok(())
+}
+
+async fn handle_queue_process(sub_m: &ArgMatches) -> Result<()> {
+    let format = get_format(sub_m);
+    let limit = sub_m.get_one::<usize>("limit").copied();
+    let watch = sub_m.get_flag("watch");
+    let interval = sub_m.get_one::<u64>("interval").copied().unwrap_or(5);
+    let batch_size = sub_m
+        .get_one::<u64>("batch-size")
+        .copied()
+        .and_then(|n| usize::try_from(n).ok())
+        .unwrap_or(1);
+
+    // Landing rebases and pushes with jj
+    crate::commands::require_jj("The merge queue")?;
+    let _lock = acquire_processor_lock().await?;
+    let db = get_session_db().await?;
+    let store = QueueStore::new(db.pool().clone());
+    let config = isolate_core::config::load_config().await?;
+    let main_branch = if config.main_branch.is_empty() {
+        "main".to_string()
+    } else {
+        config.main_branch
+    };
+    let ops = JjLandingOps::new(db, main_branch, config.gates.or_moon());
+    let events = EventLogSink;
+    let processor = QueueProcessor::new(&store, &ops, &events).with_batch_size(batch_size);
+
+    loop {
+        let output = processor.run(limit).await?;
+        if !watch || !output.processed.is_empty() {
+            print_process_output(&output, format.is_json())?;
+        }
+        if !watch {
+            return Ok(());
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
+
+fn print_process_output(output: &QueueProcessOutput, json: bool) -> Result<()> {
+    if json {
+        let envelope = SchemaEnvelope::new(schemas::QUEUE_PROCESS_RESPONSE, "single", output);
+        println!("{}", serde_json::to_string_pretty(&envelope)?);
+        return Ok(());
+    }
+
+    if output.processed.is_empty() {
+        println!("Nothing to process.");
+    }
+    for entry in &output.processed {
+        match entry.detail {
+            Some(ref reason) if entry.requeued() => {
+                println!("↺ {} {}: {reason}", entry.session, entry.status);
+            }
+            Some(ref reason) if !entry.landed() => {
+                println!("✗ {} {}: {reason}", entry.session, entry.status);
+            }
+            _ => println!("✓ {} {}", entry.session, entry.status),
+        }
+    }
+    println!(
+        "Merged {}, failed {}, requeued {} in {} batch(es), {} still pending",
+        output.merged, output.failed, output.requeued, output.batches, output.remaining
+    );
+    Ok(())
+}
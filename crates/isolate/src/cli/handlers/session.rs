@@ -45,6 +45,7 @@ async fn handle_session_add(args: &ArgMatches) -> Result<()> {
         format,
         idempotent: false,
         dry_run,
+        parent: args.get_one::<String>("on").cloned(),
     };
 
     add::run_with_options(&options).await
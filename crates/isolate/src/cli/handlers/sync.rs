@@ -1,10 +1,10 @@
-//! Sync, diff, submit, done, and abort handlers
+//! Sync, restack, diff, submit, done, and abort handlers
 
 use anyhow::Result;
 use clap::ArgMatches;
 
 use super::json_format::get_format;
-use crate::commands::{abort, diff, done, submit, sync};
+use crate::commands::{abort, diff, done, restack, submit, sync};
 
 pub async fn handle_sync(sub_m: &ArgMatches) -> Result<()> {
     // Handle --contract flag first
@@ -31,6 +31,15 @@ pub async fn handle_sync(sub_m: &ArgMatches) -> Result<()> {
     sync::run_with_options(name, options).await
 }
 
+pub async fn handle_restack(sub_m: &ArgMatches) -> Result<()> {
+    let options = restack::RestackOptions {
+        name: sub_m.get_one::<String>("name").cloned(),
+        dry_run: sub_m.get_flag("dry-run"),
+        format: get_format(sub_m),
+    };
+    restack::run_with_options(&options).await
+}
+
 pub async fn handle_submit(sub_m: &ArgMatches) -> Result<()> {
     let name = sub_m.get_one::<String>("name").cloned();
     let format = get_format(sub_m);
@@ -87,10 +96,15 @@ pub async fn handle_done(sub_m: &ArgMatches) -> Result<()> {
         dry_run: sub_m.get_flag("dry-run"),
         detect_conflicts: sub_m.get_flag("detect-conflicts"),
         no_bead_update: sub_m.get_flag("no-bead-update"),
+        queue: sub_m.get_flag("queue"),
+        priority: sub_m.get_one::<u32>("priority").copied(),
         format,
     };
     let options = args.to_options();
-    done::run_with_options(&options).await?;
+    let exit_code = done::run_with_options(&options).await?;
+    if exit_code != 0 {
+        std::process::exit(exit_code);
+    }
     Ok(())
 }
 
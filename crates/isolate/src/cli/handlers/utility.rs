@@ -216,6 +216,9 @@ fn build_wait_options_from_values(
             name: name.ok_or_else(|| anyhow::anyhow!("Session name required"))?,
             status: status.ok_or_else(|| anyhow::anyhow!("--status required"))?,
         },
+        "queue-landed" => wait::WaitCondition::QueueLanded(
+            name.ok_or_else(|| anyhow::anyhow!("Session name required"))?,
+        ),
         _ => anyhow::bail!("Unknown condition: {condition_str}"),
     };
 
@@ -64,6 +64,7 @@ pub async fn handle_add(sub_m: &ArgMatches) -> Result<()> {
     let no_open = sub_m.get_flag("no-open");
     let idempotent = sub_m.get_flag("idempotent");
     let dry_run = sub_m.get_flag("dry-run");
+    let parent = sub_m.get_one::<String>("on").cloned();
 
     let options = add::AddOptions {
         name: name.clone(),
@@ -73,6 +74,7 @@ pub async fn handle_add(sub_m: &ArgMatches) -> Result<()> {
         format: get_format(sub_m),
         idempotent,
         dry_run,
+        parent,
     };
 
     add::run_with_options(&options).await
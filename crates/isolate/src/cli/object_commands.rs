@@ -28,6 +28,10 @@ pub enum ZjjObject {
     Config,
     /// Diagnostics and health checks
     Doctor,
+    /// Merge queue for landing sessions
+    Queue,
+    /// Orchestrator pipelines taking specs through agent development
+    Pipeline,
 }
 
 impl ZjjObject {
@@ -39,6 +43,8 @@ impl ZjjObject {
             Self::Status,
             Self::Config,
             Self::Doctor,
+            Self::Queue,
+            Self::Pipeline,
         ]
     }
 
@@ -50,6 +56,8 @@ impl ZjjObject {
             Self::Status => "status",
             Self::Config => "config",
             Self::Doctor => "doctor",
+            Self::Queue => "queue",
+            Self::Pipeline => "pipeline",
         }
     }
 
@@ -61,6 +69,8 @@ impl ZjjObject {
             Self::Status => "Query system and session status",
             Self::Config => "Manage isolate configuration",
             Self::Doctor => "Run diagnostics and health checks",
+            Self::Queue => "Queue sessions for serialized merging",
+            Self::Pipeline => "Run specs through agent development and validation",
         }
     }
 }
@@ -150,6 +160,48 @@ pub enum DoctorAction {
     Clean,
 }
 
+/// Subcommands for the Queue object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueObjectAction {
+    /// Enqueue a session
+    Add,
+    /// List queue entries
+    List,
+    /// Remove a session from the queue
+    Remove,
+    /// Change a session's priority
+    Reprioritize,
+    /// Show queue status
+    Status,
+    /// Land queued sessions
+    Process,
+}
+
+/// Subcommands for the Pipeline object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineAction {
+    /// Register a spec as a pipeline
+    Create,
+    /// Run a pipeline to a decision
+    Run,
+    /// Show one pipeline
+    Status,
+    /// List pipelines
+    List,
+    /// Resume interrupted pipelines
+    Recover,
+    /// Show pipeline metrics
+    Metrics,
+    /// List escalated pipelines
+    Inbox,
+    /// Send an escalated pipeline back to the agent
+    Resume,
+    /// Accept an escalated pipeline
+    Accept,
+    /// Fail an escalated pipeline
+    Fail,
+}
+
 /// Global flags available on all commands
 #[derive(Debug, Clone, Default)]
 pub struct GlobalFlags {
@@ -324,6 +376,12 @@ pub fn cmd_session() -> ClapCommand {
                         .value_name("BEAD_ID")
                         .help("Associate with a bead ID"),
                 )
+                .arg(
+                    Arg::new("on")
+                        .long("on")
+                        .value_name("PARENT")
+                        .help("Stack on another session's current changes"),
+                )
                 .arg(
                     Arg::new("no-open")
                         .long("no-open")
@@ -650,6 +708,308 @@ pub fn cmd_doctor() -> ClapCommand {
         )
 }
 
+/// Create the queue priority argument
+fn priority_arg() -> Arg {
+    Arg::new("priority")
+        .long("priority")
+        .short('p')
+        .value_name("N")
+        .value_parser(clap::value_parser!(u32).range(0..=i64::from(isolate_core::MAX_PRIORITY)))
+        .help("Queue priority (0 = first in line, default 50)")
+}
+
+/// Build the Queue object command with all subcommands
+pub fn cmd_queue() -> ClapCommand {
+    ClapCommand::new("queue")
+        .about("Queue sessions for serialized merging into main")
+        .subcommand_required(true)
+        .arg(json_arg())
+        .arg(verbose_arg())
+        .subcommand(
+            ClapCommand::new("add")
+                .about("Add a session to the merge queue")
+                .arg(json_arg())
+                .arg(Arg::new("session").required(true).help("Session to enqueue"))
+                .arg(priority_arg()),
+        )
+        .subcommand(
+            ClapCommand::new("list")
+                .about("List queued sessions in processing order")
+                .arg(json_arg()),
+        )
+        .subcommand(
+            ClapCommand::new("remove")
+                .about("Remove a session from the merge queue")
+                .arg(json_arg())
+                .arg(Arg::new("session").required(true).help("Session to remove")),
+        )
+        .subcommand(
+            ClapCommand::new("reprioritize")
+                .about("Change the priority of a pending session")
+                .arg(json_arg())
+                .arg(Arg::new("session").required(true).help("Session to move"))
+                .arg(priority_arg().required(true)),
+        )
+        .subcommand(
+            ClapCommand::new("status")
+                .about("Show queue counts, or one session's entry")
+                .arg(json_arg())
+                .arg(Arg::new("session").help("Session to inspect (whole queue if omitted)")),
+        )
+        .subcommand(
+            ClapCommand::new("process")
+                .about("Rebase, gate and land queued sessions in order")
+                .arg(json_arg())
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Stop after handling N entries"),
+                )
+                .arg(
+                    Arg::new("batch-size")
+                        .long("batch-size")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .default_value("1")
+                        .help("Gate up to N entries together, bisecting on failure"),
+                )
+                .arg(
+                    Arg::new("watch")
+                        .long("watch")
+                        .action(clap::ArgAction::SetTrue)
+                        .help("Keep running and process entries as they are queued"),
+                )
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .value_name("SECONDS")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .default_value("5")
+                        .requires("watch")
+                        .help("Polling interval in watch mode"),
+                ),
+        )
+}
+
+/// Arguments shared by the pipeline commands that run pipelines
+fn pipeline_run_args() -> [Arg; 8] {
+    [
+        Arg::new("scenarios")
+            .long("scenarios")
+            .value_name("DIR")
+            .default_value("scenarios")
+            .help("Directory of scenario YAML files to validate against"),
+        Arg::new("specs")
+            .long("specs")
+            .value_name("DIR")
+            .help("Specs directory with linter rules and schema (bundled if omitted)"),
+        Arg::new("agent-command")
+            .long("agent-command")
+            .value_name("CMD")
+            .default_value("claude")
+            .help("Agent command to run in the pipeline's session"),
+        Arg::new("agent-args")
+            .long("agent-args")
+            .value_name("ARGS")
+            .num_args(1..)
+            .allow_hyphen_values(true)
+            .help("Arguments passed to the agent"),
+        Arg::new("no-agent")
+            .long("no-agent")
+            .action(clap::ArgAction::SetTrue)
+            .conflicts_with_all(["agent-command", "agent-args"])
+            .help("Skip agent development and only validate"),
+        Arg::new("timeout")
+            .long("timeout")
+            .value_name("SECONDS")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .default_value("14400")
+            .help("Timeout for each agent iteration"),
+        Arg::new("concurrency")
+            .long("concurrency")
+            .short('j')
+            .value_name("N")
+            .value_parser(clap::value_parser!(usize))
+            .default_value("8")
+            .help("Pipelines to run at once"),
+        Arg::new("policy")
+            .long("policy")
+            .value_name("FILE")
+            .help("Retry and escalation policy YAML (default policy if omitted)"),
+    ]
+}
+
+/// Build the Pipeline object command with all subcommands
+pub fn cmd_pipeline() -> ClapCommand {
+    ClapCommand::new("pipeline")
+        .about("Run specs through agent development and scenario validation")
+        .subcommand_required(true)
+        .arg(json_arg())
+        .arg(verbose_arg())
+        .subcommand(
+            ClapCommand::new("create")
+                .about("Register a spec as a new pipeline")
+                .arg(json_arg())
+                .arg(Arg::new("spec").required(true).help("Spec file to develop"))
+                .arg(
+                    Arg::new("max-iterations")
+                        .long("max-iterations")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .default_value("10")
+                        .help("Agent iterations before escalating"),
+                )
+                .arg(
+                    Arg::new("quality-threshold")
+                        .long("quality-threshold")
+                        .value_name("SCORE")
+                        .value_parser(clap::value_parser!(u32).range(0..=100))
+                        .default_value("80")
+                        .help("Minimum spec quality score (0-100)"),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("run")
+                .about("Run pipelines until each is accepted, escalated or failed")
+                .arg(json_arg())
+                .arg(
+                    Arg::new("id")
+                        .required(true)
+                        .num_args(1..)
+                        .help("Pipeline ids or unique prefixes"),
+                )
+                .args(pipeline_run_args()),
+        )
+        .subcommand(
+            ClapCommand::new("status")
+                .about("Show a pipeline's state")
+                .arg(json_arg())
+                .arg(Arg::new("id").required(true).help("Pipeline id or unique prefix")),
+        )
+        .subcommand(
+            ClapCommand::new("list")
+                .about("List pipelines, oldest first")
+                .arg(json_arg())
+                .arg(
+                    Arg::new("state")
+                        .long("state")
+                        .value_name("STATE")
+                        .value_parser([
+                            "pending",
+                            "spec_review",
+                            "universe_setup",
+                            "agent_development",
+                            "validation",
+                            "accepted",
+                            "escalated",
+                            "failed",
+                        ])
+                        .help("Only pipelines in this state"),
+                ),
+        )
+        .subcommand(
+            ClapCommand::new("recover")
+                .about("Resume pipelines left mid-flight by a crash")
+                .arg(json_arg())
+                .arg(Arg::new("id").help("Pipeline to resume (all unfinished if omitted)"))
+                .args(pipeline_run_args()),
+        )
+        .subcommand(
+            ClapCommand::new("metrics")
+                .about("Show pipeline counts, iterations and acceptance rate")
+                .arg(json_arg())
+                .arg(
+                    Arg::new("export")
+                        .long("export")
+                        .value_name("FORMAT")
+                        .value_parser(["prometheus", "csv"])
+                        .help("Write metrics as Prometheus text or weekly CSV rows instead"),
+                )
+                .arg(
+                    Arg::new("weeks")
+                        .long("weeks")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .default_value("4")
+                        .requires("export")
+                        .help("Export pipelines created in the last N weeks"),
+                ),
+        )
+        .subcommands(escalation_commands())
+}
+
+/// Subcommands for settling escalated pipelines
+fn escalation_commands() -> [ClapCommand; 4] {
+    [
+        ClapCommand::new("inbox")
+            .about("Show escalated pipelines with their reason and last feedback")
+            .arg(json_arg()),
+        ClapCommand::new("resume")
+            .about("Send an escalated pipeline back to the agent with guidance")
+            .arg(json_arg())
+            .arg(Arg::new("id").required(true).help("Pipeline id or unique prefix"))
+            .arg(
+                Arg::new("guidance")
+                    .long("guidance")
+                    .value_name("TEXT")
+                    .required(true)
+                    .help("Guidance the agent sees alongside the last feedback"),
+            )
+            .arg(
+                Arg::new("iterations")
+                    .long("iterations")
+                    .value_name("N")
+                    .value_parser(clap::value_parser!(u32).range(1..))
+                    .default_value("3")
+                    .help("Further agent iterations to allow"),
+            )
+            .arg(author_arg()),
+        ClapCommand::new("accept")
+            .about("Accept an escalated pipeline's work as it stands")
+            .arg(json_arg())
+            .arg(Arg::new("id").required(true).help("Pipeline id or unique prefix"))
+            .arg(
+                Arg::new("note")
+                    .long("note")
+                    .value_name("TEXT")
+                    .help("Why it was accepted"),
+            )
+            .arg(author_arg()),
+        ClapCommand::new("fail")
+            .about("Fail an escalated pipeline for good")
+            .arg(json_arg())
+            .arg(Arg::new("id").required(true).help("Pipeline id or unique prefix"))
+            .arg(
+                Arg::new("reason")
+                    .long("reason")
+                    .value_name("TEXT")
+                    .help("Why it failed"),
+            )
+            .arg(author_arg()),
+    ]
+}
+
+/// Who settles an escalated pipeline, recorded with the decision
+fn author_arg() -> Arg {
+    Arg::new("author")
+        .long("author")
+        .value_name("NAME")
+        .help("Who is deciding (default: $USER)")
+}
+
+pub fn cmd_restack() -> ClapCommand {
+    ClapCommand::new("restack")
+        .about("Rebase stacked sessions onto their parent sessions")
+        .arg(json_arg())
+        .arg(dry_run_arg())
+        .arg(
+            Arg::new("name")
+                .help("Restack only the sessions built on this one (all stacks if omitted)"),
+        )
+}
+
 /// Build the complete object-based CLI
 ///
 /// This creates the new `isolate <object> <action>` command structure
@@ -672,7 +1032,11 @@ pub fn build_object_cli() -> ClapCommand {
              \n\
   isolate config <action>   Manage configuration\n\
              \n\
-  isolate doctor <action>   Run diagnostics\n",
+  isolate doctor <action>   Run diagnostics\n\
+             \n\
+  isolate queue <action>    Manage the merge queue\n\
+             \n\
+  isolate pipeline <action> Run specs through agents and scenarios\n",
         )
         .subcommand_required(true)
         .arg(json_arg().global(true))
@@ -704,6 +1068,9 @@ pub fn build_object_cli() -> ClapCommand {
         .subcommand(cmd_status())
         .subcommand(cmd_config())
         .subcommand(cmd_doctor())
+        .subcommand(cmd_queue())
+        .subcommand(cmd_pipeline())
+        .subcommand(cmd_restack())
         // Legacy commands - route to same handlers
         .subcommand(
             ClapCommand::new("init")
@@ -722,6 +1089,7 @@ pub fn build_object_cli() -> ClapCommand {
                 .arg(contract_arg())
                 .arg(ai_hints_arg())
                 .arg(Arg::new("bead").long("bead").short('b').value_name("BEAD_ID"))
+                .arg(Arg::new("on").long("on").value_name("PARENT"))
                 .arg(Arg::new("no-open").long("no-open").action(clap::ArgAction::SetTrue).default_value("false"))
                 .arg(Arg::new("no-hooks").long("no-hooks").action(clap::ArgAction::SetTrue).default_value("false"))
                 .arg(Arg::new("idempotent").long("idempotent").action(clap::ArgAction::SetTrue).default_value("false"))
@@ -847,7 +1215,9 @@ pub fn build_object_cli() -> ClapCommand {
                 .arg(Arg::new("squash").long("squash").action(clap::ArgAction::SetTrue))
                 .arg(Arg::new("dry-run").long("dry-run").action(clap::ArgAction::SetTrue))
                 .arg(Arg::new("detect-conflicts").long("detect-conflicts").action(clap::ArgAction::SetTrue))
-                .arg(Arg::new("no-bead-update").long("no-bead-update").action(clap::ArgAction::SetTrue)),
+                .arg(Arg::new("no-bead-update").long("no-bead-update").action(clap::ArgAction::SetTrue))
+                .arg(Arg::new("queue").long("queue").action(clap::ArgAction::SetTrue).conflicts_with_all(["dry-run", "squash", "detect-conflicts"]).help("Enqueue for merging instead of merging now"))
+                .arg(priority_arg().requires("queue")),
         )
         .subcommand(
             ClapCommand::new("work")
@@ -896,6 +1266,7 @@ pub fn build_object_cli() -> ClapCommand {
                 .arg(Arg::new("dry-run").long("dry-run").action(clap::ArgAction::SetTrue))
                 .arg(json_arg()),
         )
+        .subcommand(super::commands::cmd_wait())
 }
 
 #[cfg(test)]
@@ -909,11 +1280,13 @@ mod tests {
         assert_eq!(ZjjObject::Status.name(), "status");
         assert_eq!(ZjjObject::Config.name(), "config");
         assert_eq!(ZjjObject::Doctor.name(), "doctor");
+        assert_eq!(ZjjObject::Queue.name(), "queue");
+        assert_eq!(ZjjObject::Pipeline.name(), "pipeline");
     }
 
     #[test]
     fn test_isolate_object_all_count() {
-        assert_eq!(ZjjObject::all().len(), 5);
+        assert_eq!(ZjjObject::all().len(), 7);
     }
 
     #[test]
@@ -926,6 +1299,8 @@ mod tests {
         assert!(subcommands.contains(&"status"));
         assert!(subcommands.contains(&"config"));
         assert!(subcommands.contains(&"doctor"));
+        assert!(subcommands.contains(&"queue"));
+        assert!(subcommands.contains(&"pipeline"));
     }
 
     #[test]
@@ -985,4 +1360,139 @@ mod tests {
         assert!(subcommands.contains(&"sync"));
         assert!(subcommands.contains(&"init"));
     }
+
+    #[test]
+    fn test_queue_subcommands() {
+        let cmd = cmd_queue();
+        let subcommands: Vec<&str> = cmd.get_subcommands().map(clap::Command::get_name).collect();
+
+        assert_eq!(
+            subcommands,
+            vec!["add", "list", "remove", "reprioritize", "status", "process"]
+        );
+    }
+
+    #[test]
+    fn test_pipeline_subcommands() {
+        let cmd = cmd_pipeline();
+        let subcommands: Vec<&str> = cmd.get_subcommands().map(clap::Command::get_name).collect();
+
+        assert_eq!(
+            subcommands,
+            vec![
+                "create", "run", "status", "list", "recover", "metrics", "inbox", "resume",
+                "accept", "fail"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pipeline_no_agent_conflicts_with_agent_command() {
+        assert!(cmd_pipeline()
+            .try_get_matches_from(["pipeline", "run", "3f2a", "--no-agent"])
+            .is_ok());
+        assert!(cmd_pipeline()
+            .try_get_matches_from(["pipeline", "run", "3f2a", "--no-agent", "--agent-command", "codex"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_pipeline_run_takes_several_ids() {
+        let matches = cmd_pipeline()
+            .try_get_matches_from(["pipeline", "run", "3f2a", "9c1e", "-j", "12"])
+            .expect("run should parse");
+        let (_, run) = matches.subcommand().expect("subcommand");
+
+        assert_eq!(run.get_many::<String>("id").map(Iterator::count), Some(2));
+        assert_eq!(run.get_one::<usize>("concurrency"), Some(&12));
+    }
+
+    #[test]
+    fn test_add_accepts_parent_session() {
+        for args in [
+            vec!["isolate", "add", "ui", "--on", "api"],
+            vec!["isolate", "session", "add", "ui", "--on", "api"],
+        ] {
+            let matches = build_object_cli().try_get_matches_from(&args);
+            let matches = matches.expect("add --on should parse");
+            let (_, add) = matches.subcommand().expect("subcommand");
+            let add = add.subcommand().map_or(add, |(_, nested)| nested);
+            assert_eq!(add.get_one::<String>("on").map(String::as_str), Some("api"));
+        }
+    }
+
+    #[test]
+    fn test_restack_name_is_optional() {
+        let matches = build_object_cli()
+            .try_get_matches_from(["isolate", "restack", "api", "--dry-run"])
+            .expect("restack should parse");
+        let (_, restack) = matches.subcommand().expect("subcommand");
+        assert_eq!(
+            restack.get_one::<String>("name").map(String::as_str),
+            Some("api")
+        );
+        assert!(restack.get_flag("dry-run"));
+        assert!(build_object_cli()
+            .try_get_matches_from(["isolate", "restack"])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_queue_process_interval_requires_watch() {
+        assert!(cmd_queue()
+            .try_get_matches_from(["queue", "process", "--interval", "2"])
+            .is_err());
+        assert!(cmd_queue()
+            .try_get_matches_from(["queue", "process", "--watch", "--interval", "2"])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_queue_process_batch_size_must_be_positive() {
+        let matches = cmd_queue().try_get_matches_from(["queue", "process", "--batch-size", "4"]);
+        let batch_size = matches.ok().and_then(|m| {
+            m.subcommand_matches("process")
+                .and_then(|p| p.get_one::<u64>("batch-size").copied())
+        });
+        assert_eq!(batch_size, Some(4));
+        assert!(cmd_queue()
+            .try_get_matches_from(["queue", "process", "--batch-size", "0"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_wait_for_queue_landing_parses() {
+        let result = build_object_cli().try_get_matches_from([
+            "isolate",
+            "wait",
+            "queue-landed",
+            "feature",
+            "--timeout",
+            "600",
+        ]);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_queue_priority_is_bounded() {
+        let too_high = isolate_core::MAX_PRIORITY + 1;
+        let result = cmd_queue().try_get_matches_from([
+            "queue",
+            "add",
+            "feature",
+            "--priority",
+            &too_high.to_string(),
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_done_queue_flag_parses_priority() {
+        let matches = build_object_cli()
+            .try_get_matches_from(["isolate", "done", "--queue", "--priority", "5"])
+            .unwrap();
+        let (_, done) = matches.subcommand().unwrap();
+        assert!(done.get_flag("queue"));
+        assert_eq!(done.get_one::<u32>("priority"), Some(&5));
+    }
 }
@@ -11,7 +11,7 @@ use anyhow::{Context, Result};
 use isolate_core::{json::SchemaEnvelope, OutputFormat};
 use serde::Serialize;
 
-use super::{context, get_session_db};
+use super::{context, get_session_db, run_vcs};
 use crate::session::{SessionStatus, SessionUpdate};
 
 /// Output for abort command
@@ -51,7 +51,12 @@ pub struct AbortOptions {
 /// - Workspace not found
 /// - Cleanup fails
 pub async fn run(options: &AbortOptions) -> Result<()> {
-    let root = super::check_in_jj_repo().await?;
+    let git_only = super::in_git_only_repo();
+    let root = if git_only {
+        super::check_prerequisites().await?
+    } else {
+        super::check_in_jj_repo().await?
+    };
     let location = context::detect_location(&root)?;
 
     // Determine which workspace to abort
@@ -80,12 +85,18 @@ pub async fn run(options: &AbortOptions) -> Result<()> {
 
     let workspace_path = std::path::Path::new(&session.workspace_path);
 
-    // Forget the JJ workspace
-    let _ = tokio::process::Command::new("jj")
-        .args(["workspace", "forget", &workspace_name])
-        .current_dir(&root)
-        .output()
-        .await;
+    // Forget the workspace. Removing a git worktree also deletes its
+    // directory, so a kept git workspace stays registered instead.
+    let worktree_removed = if git_only && options.keep_workspace {
+        false
+    } else {
+        let repo = root.to_string_lossy().into_owned();
+        let name = workspace_name.clone();
+        let forgotten = run_vcs(move |vcs| vcs.forget_workspace(&repo, &name))
+            .await
+            .is_ok();
+        git_only && forgotten
+    };
 
     // Remove workspace files unless --keep-workspace
     let workspace_removed = if options.keep_workspace {
@@ -100,7 +111,7 @@ pub async fn run(options: &AbortOptions) -> Result<()> {
                     })?;
                 true
             }
-            _ => false,
+            _ => worktree_removed,
         }
     };
 
@@ -11,6 +11,7 @@ use std::path::PathBuf;
 use anyhow::{Context, Result};
 use isolate_core::{
     config,
+    coordination::StackStore,
     domain::SessionName,
     output::{
         emit_stdout, Action, ActionStatus, ActionTarget, ActionVerb, Issue, IssueId, IssueKind,
@@ -57,13 +58,17 @@ fn emit_add_json_envelope(
     status: &str,
     created: bool,
     success: bool,
+    parent: Option<&str>,
 ) -> Result<()> {
-    let data = json!({
+    let mut data = json!({
         "name": name,
         "workspace_path": workspace_path,
         "status": status,
         "created": created,
     });
+    if let Some(parent) = parent {
+        data["parent"] = json!(parent);
+    }
 
     let output = json!({
         "$schema": "isolate://add-response/v1",
@@ -207,20 +212,24 @@ fn output_human_result(
     mode: &str,
     created: bool,
     format: OutputFormat,
+    parent: Option<&str>,
 ) {
     // Only output human-readable text in non-JSON mode
     if format.is_json() {
         return;
     }
 
-    match (created, mode) {
-        (false, "idempotent" | "command replay") => {
+    match (created, mode, parent) {
+        (false, "idempotent" | "command replay", _) => {
             println!("Session '{name}' already exists (idempotent)");
         }
-        (false, _) => {
+        (false, _, _) => {
             println!("Session '{name}' already exists");
         }
-        (true, _) => {
+        (true, _, Some(parent)) => {
+            println!("Created session '{name}' on '{parent}' (workspace at {workspace_path})");
+        }
+        (true, _, None) => {
             println!("Created session '{name}' (workspace at {workspace_path})");
         }
     }
@@ -235,6 +244,7 @@ fn output_result(
     created: bool,
     format: OutputFormat,
     session: Option<&crate::session::Session>,
+    parent: Option<&str>,
 ) -> Result<()> {
     if json_envelope_mode() {
         let status = if created {
@@ -242,7 +252,7 @@ fn output_result(
         } else {
             format!("Session '{name}' already exists ({mode})")
         };
-        return emit_add_json_envelope(name, workspace_path, &status, created, true);
+        return emit_add_json_envelope(name, workspace_path, &status, created, true, parent);
     }
 
     if format.is_json() {
@@ -275,24 +285,26 @@ fn output_result(
         }
 
         // Emit result
-        let result_message = if created {
-            format!("Created session '{name}' ({mode})")
-        } else {
-            format!("Session '{name}' already exists ({mode})")
+        let result_message = match (created, parent) {
+            (true, Some(parent)) => format!("Created session '{name}' on '{parent}' ({mode})"),
+            (true, None) => format!("Created session '{name}' ({mode})"),
+            (false, _) => format!("Session '{name}' already exists ({mode})"),
         };
         emit_result_success(&result_message)?;
     } else {
-        output_human_result(name, workspace_path, mode, created, format);
+        output_human_result(name, workspace_path, mode, created, format, parent);
     }
 
     Ok(())
 }
 
-async fn handle_post_create_hook_failure(
+/// Roll back a created session after `step` failed and mark it failed
+async fn handle_post_create_failure(
     name: &str,
     workspace_path: &std::path::Path,
     db: &SessionDb,
-    hook_error: anyhow::Error,
+    step: &str,
+    step_error: anyhow::Error,
 ) -> Result<()> {
     let rollback_result = rollback_partial_state(name, workspace_path).await;
     let failed_status_result = db
@@ -307,14 +319,15 @@ async fn handle_post_create_hook_failure(
         .context("Failed to mark session as failed");
 
     match (rollback_result, failed_status_result) {
-        (Ok(()), Ok(())) => Err(hook_error).context("post_create hook failed"),
-        (Err(rollback_error), Ok(())) => Err(hook_error)
-            .context(format!("post_create hook failed and rollback failed: {rollback_error}")),
-        (Ok(()), Err(status_error)) => Err(hook_error).context(format!(
-            "post_create hook failed and failed status update failed: {status_error}"
+        (Ok(()), Ok(())) => Err(step_error).context(format!("{step} failed")),
+        (Err(rollback_error), Ok(())) => Err(step_error).context(format!(
+            "{step} failed and rollback failed: {rollback_error}"
+        )),
+        (Ok(()), Err(status_error)) => Err(step_error).context(format!(
+            "{step} failed and failed status update failed: {status_error}"
         )),
-        (Err(rollback_error), Err(status_error)) => Err(hook_error).context(format!(
-            "post_create hook failed, rollback failed: {rollback_error}, status update failed: {status_error}"
+        (Err(rollback_error), Err(status_error)) => Err(step_error).context(format!(
+            "{step} failed, rollback failed: {rollback_error}, status update failed: {status_error}"
         )),
     }
 }
@@ -377,6 +390,8 @@ pub async fn run_internal(options: &AddOptions) -> Result<()> {
         ));
     }
 
+    let base = resolve_stack_base(options, &db, &root).await?;
+
     // Construct workspace path from config's workspace_dir
     let workspace_base = root.join(&cfg.workspace_dir);
     let workspace_path = workspace_base.join(&options.name);
@@ -390,13 +405,22 @@ pub async fn run_internal(options: &AddOptions) -> Result<()> {
         &db,
         bead_metadata,
         create_command_id.as_deref(),
+        base.as_deref(),
     )
     .await?;
+    link_stack_parent(options, &workspace_path, &db).await?;
 
     // Execute post_create hooks unless --no-hooks
     if !options.no_hooks {
         if let Err(e) = execute_post_create_hooks(&workspace_path_str).await {
-            return handle_post_create_hook_failure(&options.name, &workspace_path, &db, e).await;
+            return handle_post_create_failure(
+                &options.name,
+                &workspace_path,
+                &db,
+                "post_create hook",
+                e,
+            )
+            .await;
         }
     }
 
@@ -444,15 +468,19 @@ pub async fn run_with_options(options: &AddOptions) -> Result<()> {
         return handle_existing_session(options, &db, existing).await;
     }
 
-    // Phase 3: Handle dry run for new session
+    // Phase 3: Resolve the session to stack on, if any
+    let base = resolve_stack_base(options, &db, &root).await?;
+
+    // Phase 4: Handle dry run for new session
     if options.dry_run {
         return handle_new_session_dry_run(options, &workspace_path_str);
     }
 
-    // Phase 4: Perform the actual creation sequence
-    let session = perform_creation_sequence(options, &root, &workspace_path, &db).await?;
+    // Phase 5: Perform the actual creation sequence
+    let session =
+        perform_creation_sequence(options, &root, &workspace_path, &db, base.as_deref()).await?;
 
-    // Phase 5: Output result
+    // Phase 6: Output result
     output_result(
         &options.name,
         &workspace_path_str,
@@ -460,9 +488,90 @@ pub async fn run_with_options(options: &AddOptions) -> Result<()> {
         true,
         options.format,
         Some(&session),
+        options.parent.as_deref(),
     )
 }
 
+/// Resolve the revision a stacked session's workspace starts from
+///
+/// Returns `None` when `--on` was not given. The parent must be a live
+/// session; the child starts from its working copy (`<parent>@`) in JJ
+/// repositories and from its branch in git-only ones.
+async fn resolve_stack_base(
+    options: &AddOptions,
+    db: &SessionDb,
+    root: &std::path::Path,
+) -> Result<Option<String>> {
+    let Some(parent) = options.parent.as_deref() else {
+        return Ok(None);
+    };
+
+    if parent == options.name {
+        return Err(anyhow::Error::new(isolate_core::Error::ValidationError {
+            message: format!("Session '{parent}' cannot be stacked on itself"),
+            field: Some("on".to_string()),
+            value: Some(parent.to_string()),
+            constraints: vec!["parent must be a different session".to_string()],
+        }));
+    }
+
+    let session = db.get(parent).await?.ok_or_else(|| {
+        isolate_core::Error::NotFound(format!("Parent session '{parent}' not found"))
+    })?;
+
+    if matches!(
+        session.status,
+        SessionStatus::Completed | SessionStatus::Failed
+    ) {
+        return Err(anyhow::Error::new(isolate_core::Error::ValidationError {
+            message: format!(
+                "Parent session '{parent}' is {} and cannot be stacked on",
+                session.status
+            ),
+            field: Some("on".to_string()),
+            value: Some(parent.to_string()),
+            constraints: vec!["parent must be an active or paused session".to_string()],
+        }));
+    }
+
+    if isolate_core::discover_backend(root) == Some(isolate_core::BackendType::Git) {
+        Ok(Some(session.name))
+    } else {
+        Ok(Some(format!("{}@", session.name)))
+    }
+}
+
+/// Record a freshly created stacked session under its parent in `state.db`
+async fn record_stack_parent(options: &AddOptions, db: &SessionDb) -> Result<()> {
+    if let Some(parent) = options.parent.as_deref() {
+        StackStore::new(db.pool().clone())
+            .add_child(&options.name, parent)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to record '{}' as stacked on '{parent}'",
+                    options.name
+                )
+            })?;
+    }
+    Ok(())
+}
+
+/// Record a freshly created session's stack parent, rolling the session back
+/// if that fails so `--on` is never silently dropped
+async fn link_stack_parent(
+    options: &AddOptions,
+    workspace_path: &std::path::Path,
+    db: &SessionDb,
+) -> Result<()> {
+    match record_stack_parent(options, db).await {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            handle_post_create_failure(&options.name, workspace_path, db, "Stacking", e).await
+        }
+    }
+}
+
 /// Handle logic for when a session already exists
 async fn handle_existing_session(
     options: &AddOptions,
@@ -480,6 +589,7 @@ async fn handle_existing_session(
                 false,
                 options.format,
                 Some(&existing),
+                None,
             )?;
             return Ok(());
         }
@@ -499,6 +609,7 @@ async fn handle_existing_session(
             false,
             options.format,
             Some(&existing),
+            None,
         )?;
         return Ok(());
     }
@@ -512,6 +623,7 @@ async fn handle_existing_session(
                 &format!("Session '{}' already exists", options.name),
                 false,
                 false,
+                None,
             )?;
         } else {
             emit_issue(
@@ -550,6 +662,7 @@ fn handle_existing_session_dry_run(
             "[DRY RUN] Session already exists (idempotent)",
             false,
             true,
+            None,
         );
     }
 
@@ -584,6 +697,7 @@ fn handle_new_session_dry_run(options: &AddOptions, workspace_path_str: &str) ->
             "[DRY RUN] Would create session",
             true,
             true,
+            options.parent.as_deref(),
         );
     }
 
@@ -614,6 +728,9 @@ fn handle_new_session_dry_run(options: &AddOptions, workspace_path_str: &str) ->
     } else {
         println!("[DRY RUN] Would create session '{}'", options.name);
         println!("  Workspace: {workspace_path_str}");
+        if let Some(parent) = &options.parent {
+            println!("  Stacked on: {parent}");
+        }
     }
     Ok(())
 }
@@ -624,6 +741,7 @@ async fn perform_creation_sequence(
     root: &std::path::Path,
     workspace_path: &std::path::Path,
     db: &SessionDb,
+    base: Option<&str>,
 ) -> Result<crate::session::Session> {
     // Query bead metadata if bead_id provided
     let bead_metadata = if let Some(bead_id) = &options.bead_id {
@@ -647,8 +765,10 @@ async fn perform_creation_sequence(
         db,
         bead_metadata,
         create_command_id.as_deref(),
+        base,
     )
     .await?;
+    link_stack_parent(options, workspace_path, db).await?;
 
     // Emit action: workspace created
     if options.format.is_json() {
@@ -675,7 +795,8 @@ async fn perform_creation_sequence(
     // Execute post_create hooks unless --no-hooks
     if !options.no_hooks {
         if let Err(e) = execute_post_create_hooks(&workspace_path.to_string_lossy()).await {
-            handle_post_create_hook_failure(&options.name, workspace_path, db, e).await?;
+            handle_post_create_failure(&options.name, workspace_path, db, "post_create hook", e)
+                .await?;
         }
     }
 
@@ -1133,6 +1254,7 @@ mod tests {
             format: OutputFormat::Json,
             idempotent: false,
             dry_run: false,
+            parent: None,
         };
 
         assert!(opts.format.is_json());
@@ -1151,6 +1273,7 @@ mod tests {
             format: OutputFormat::Json,
             idempotent: false,
             dry_run: false,
+            parent: None,
         };
 
         assert!(opts.format.is_json());
@@ -1172,6 +1295,7 @@ mod tests {
             format,
             idempotent: false,
             dry_run: false,
+            parent: None,
         };
 
         // Verify round-trip
@@ -1195,6 +1319,7 @@ mod tests {
             format: OutputFormat::Json,
             idempotent: false,
             dry_run: false,
+            parent: None,
         };
 
         // When run_with_options is called:
@@ -1367,4 +1492,103 @@ mod tests {
 
         format!("{header}Flags:{flags_section}")
     }
+
+    mod stacking {
+        use tempfile::TempDir;
+
+        use super::*;
+
+        async fn setup(parent: &str) -> Result<(SessionDb, TempDir)> {
+            let dir = TempDir::new()?;
+            let db = SessionDb::create_or_open(&dir.path().join("state.db")).await?;
+            let workspace = dir.path().join("workspaces").join(parent);
+            db.create(parent, &workspace.to_string_lossy()).await?;
+            Ok((db, dir))
+        }
+
+        fn stacked(name: &str, parent: &str) -> AddOptions {
+            let mut options = AddOptions::new(name.to_string());
+            options.parent = Some(parent.to_string());
+            options
+        }
+
+        #[tokio::test]
+        async fn test_unstacked_session_has_no_base() -> Result<()> {
+            let (db, dir) = setup("api").await?;
+
+            let options = AddOptions::new("ui".to_string());
+            let base = resolve_stack_base(&options, &db, dir.path()).await?;
+
+            assert_eq!(base, None);
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn test_stacked_session_starts_from_parent_working_copy() -> Result<()> {
+            let (db, dir) = setup("api").await?;
+
+            let base = resolve_stack_base(&stacked("ui", "api"), &db, dir.path()).await?;
+
+            assert_eq!(base.as_deref(), Some("api@"));
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn test_missing_or_finished_parent_is_rejected() -> Result<()> {
+            let (db, dir) = setup("api").await?;
+
+            let missing = resolve_stack_base(&stacked("ui", "schema"), &db, dir.path()).await;
+            assert!(missing
+                .unwrap_err()
+                .to_string()
+                .contains("Parent session 'schema' not found"));
+
+            let own = resolve_stack_base(&stacked("api", "api"), &db, dir.path()).await;
+            assert!(own.is_err());
+
+            db.update(
+                "api",
+                SessionUpdate {
+                    status: Some(SessionStatus::Completed),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            let finished = resolve_stack_base(&stacked("ui", "api"), &db, dir.path()).await;
+            assert!(finished.unwrap_err().to_string().contains("completed"));
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn test_removing_parent_unlinks_child_from_stack() -> Result<()> {
+            let (db, dir) = setup("api").await?;
+            let workspace = dir.path().join("workspaces").join("ui");
+            db.create("ui", &workspace.to_string_lossy()).await?;
+            record_stack_parent(&stacked("ui", "api"), &db).await?;
+            let stacks = StackStore::new(db.pool().clone());
+            assert_eq!(stacks.parent_of("ui").await?.as_deref(), Some("api"));
+
+            db.delete("api").await?;
+
+            assert_eq!(stacks.parent_of("ui").await?, None);
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn test_failed_stack_link_rolls_back_session() -> Result<()> {
+            let (db, dir) = setup("api").await?;
+            let name = "stack-link-rollback";
+            let workspace = dir.path().join("workspaces").join(name);
+            std::fs::create_dir_all(&workspace)?;
+            db.create(name, &workspace.to_string_lossy()).await?;
+
+            let result = link_stack_parent(&stacked(name, ""), &workspace, &db).await;
+
+            assert!(result.unwrap_err().to_string().contains("Stacking failed"));
+            assert!(!workspace.exists());
+            let session = db.get(name).await?.ok_or_else(|| anyhow::anyhow!("gone"))?;
+            assert_eq!(session.status, SessionStatus::Failed);
+            Ok(())
+        }
+    }
 }
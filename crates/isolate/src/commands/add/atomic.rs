@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use futures::StreamExt;
+use isolate_core::{BackendType, GitBackend, VcsBackend};
 
 use crate::{
     db::{AddOperationRecord, SessionDb},
@@ -96,6 +97,7 @@ pub(super) async fn atomic_create_session(
     db: &SessionDb,
     bead_metadata: Option<serde_json::Value>,
     create_command_id: Option<&str>,
+    base: Option<&str>,
 ) -> Result<()> {
     let workspace_path_str = workspace_path.display().to_string();
     let operation_id = add_operation_id(name, create_command_id);
@@ -196,13 +198,23 @@ pub(super) async fn atomic_create_session(
 
     // STEP 2: Create JJ workspace (can be interrupted by SIGKILL)
     // If this fails or is interrupted, rollback will clean up
+    let git_only = isolate_core::discover_backend(repo_root) == Some(BackendType::Git);
     log_add_state(
         name,
         AddAtomicState::WorkspaceCreateStarted,
         false,
-        "creating jj workspace",
+        if git_only {
+            "creating git worktree"
+        } else {
+            "creating jj workspace"
+        },
     );
-    let workspace_result = match create_jj_workspace(name, workspace_path, repo_root).await {
+    let created = if git_only {
+        create_git_worktree(name, workspace_path, repo_root, base).await
+    } else {
+        create_jj_workspace(name, workspace_path, repo_root, base).await
+    };
+    let workspace_result = match created {
         Ok(()) => {
             let exists = tokio::fs::try_exists(workspace_path)
                 .await
@@ -516,7 +528,16 @@ pub(super) async fn rollback_partial_state(
         "starting workspace rollback",
     );
 
-    let forget_result = isolate_core::jj::workspace_forget(name).await;
+    let forget_result = if crate::commands::in_git_only_repo() {
+        let name = name.to_string();
+        crate::commands::run_vcs(move |vcs| vcs.forget_workspace(".", &name))
+            .await
+            .map_err(anyhow::Error::new)
+    } else {
+        isolate_core::jj::workspace_forget(name)
+            .await
+            .map_err(anyhow::Error::new)
+    };
     if let Err(forget_error) = forget_result {
         tracing::warn!(
             "Failed to forget workspace '{}' during rollback: {}",
@@ -613,11 +634,14 @@ pub(super) async fn rollback_partial_state(
 /// Create a JJ workspace for the session with operation graph synchronization
 ///
 /// This uses the synchronized workspace creation to prevent operation graph
-/// corruption when multiple workspaces are created concurrently.
+/// corruption when multiple workspaces are created concurrently. With a
+/// `base` revision the working copy starts on top of it instead of the
+/// default parent.
 async fn create_jj_workspace(
     name: &str,
     workspace_path: &std::path::Path,
     repo_root: &std::path::Path,
+    base: Option<&str>,
 ) -> Result<()> {
     // Use the synchronized workspace creation to prevent operation graph corruption
     // This ensures:
@@ -625,9 +649,35 @@ async fn create_jj_workspace(
     // 2. All workspaces are based on the same repository operation
     // 3. Operation graph consistency is verified after creation
     // CRITICAL-004 fix: Pass repo_root explicitly to support sibling workspace directories
-    isolate_core::jj_operation_sync::create_workspace_synced(name, workspace_path, repo_root)
-        .await
-        .map_err(anyhow::Error::new)?;
+    isolate_core::jj_operation_sync::create_workspace_synced_at(
+        name,
+        workspace_path,
+        repo_root,
+        base,
+    )
+    .await
+    .map_err(anyhow::Error::new)?;
 
     Ok(())
 }
+
+/// Create a git worktree for the session on a new branch named after it
+///
+/// Used in repositories without JJ. The branch starts at `base`, or at the
+/// commit checked out in `repo_root` when there is none.
+async fn create_git_worktree(
+    name: &str,
+    workspace_path: &std::path::Path,
+    repo_root: &std::path::Path,
+    base: Option<&str>,
+) -> Result<()> {
+    let name = name.to_string();
+    let workspace_path = workspace_path.to_string_lossy().to_string();
+    let repo_root = repo_root.to_string_lossy().to_string();
+    let base = base.map(str::to_string);
+    tokio::task::spawn_blocking(move || {
+        GitBackend::new().add_workspace(&repo_root, &name, &workspace_path, base.as_deref())
+    })
+    .await?
+    .map_err(anyhow::Error::new)
+}
@@ -17,6 +17,8 @@ pub struct AddOptions {
     pub idempotent: bool,
     /// Preview without creating
     pub dry_run: bool,
+    /// Session to stack on; the workspace starts from its current changes
+    pub parent: Option<String>,
 }
 
 impl AddOptions {
@@ -31,6 +33,7 @@ impl AddOptions {
             format: OutputFormat::Json,
             idempotent: false,
             dry_run: false,
+            parent: None,
         }
     }
 }
@@ -9,12 +9,11 @@
 #![warn(clippy::pedantic)]
 
 use anyhow::{Context, Result};
-use isolate_core::{json::SchemaEnvelope, OutputFormat};
+use isolate_core::{json::SchemaEnvelope, Branch, BranchName, OutputFormat, VcsError};
 use serde::Serialize;
 use thiserror::Error;
-use tokio::process::Command;
 
-use crate::commands::get_session_db;
+use crate::commands::{get_session_db, run_vcs};
 
 /// Bookmark-specific errors
 #[derive(Debug, Error, Clone, PartialEq, Eq)]
@@ -62,6 +61,18 @@ pub struct BookmarkInfo {
     pub remote: bool,
 }
 
+impl From<Branch> for BookmarkInfo {
+    fn from(branch: Branch) -> Self {
+        Self {
+            name: branch.name.to_string(),
+            revision: branch
+                .commit_id
+                .map_or_else(|| "unknown".to_string(), |id| id.to_string()),
+            remote: branch.remote,
+        }
+    }
+}
+
 /// Options for bookmark list operation
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ListOptions {
@@ -107,30 +118,12 @@ pub struct MoveOptions {
 pub async fn list(options: &ListOptions) -> Result<Vec<BookmarkInfo>> {
     let workspace_path = resolve_workspace_path(options.session.as_deref()).await?;
 
-    // Build JJ command
-    let mut cmd = Command::new("jj");
-    cmd.args(["bookmark", "list"]).current_dir(&workspace_path);
-
-    if options.show_all {
-        cmd.arg("--all");
-    }
-
-    // Execute command
-    let output = cmd
-        .output()
-        .await
-        .context("failed to execute jj bookmark list")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        if options.session.is_none() && stderr.contains("There is no jj repo") {
-            return Ok(Vec::new());
-        }
-        return Err(BookmarkError::JjCommandFailed(stderr.to_string()).into());
+    let show_all = options.show_all;
+    match run_vcs(move |vcs| vcs.list_branches(&workspace_path, show_all)).await {
+        Ok(branches) => Ok(branches.into_iter().map(BookmarkInfo::from).collect()),
+        Err(VcsError::RepoNotFound(_)) if options.session.is_none() => Ok(Vec::new()),
+        Err(e) => Err(bookmark_error(e).into()),
     }
-
-    // Parse output
-    parse_bookmark_list(&output.stdout)
 }
 
 /// Create a new bookmark at current revision
@@ -158,29 +151,25 @@ pub async fn create(options: &CreateOptions) -> Result<BookmarkInfo> {
         return Err(BookmarkError::AlreadyExists(options.name.clone()).into());
     }
 
-    // Build JJ command
-    let mut cmd = Command::new("jj");
-    cmd.args(["bookmark", "create", &options.name])
-        .current_dir(&workspace_path);
-
-    // Execute command
-    let output = cmd
-        .output()
-        .await
-        .context("failed to execute jj bookmark create")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(BookmarkError::JjCommandFailed(stderr.to_string()).into());
-    }
+    let name = branch_name(&options.name)?;
+    let push = options.push;
+    let revision = run_vcs(move |vcs| {
+        vcs.create_branch(&workspace_path, &name, None)?;
 
-    // Get current revision
-    let revision = get_current_revision(&workspace_path).await?;
+        // Get current revision
+        let revision = vcs
+            .status(&workspace_path)?
+            .commit_id
+            .map_or_else(String::new, |id| id.to_string());
 
-    // Push to remote if requested
-    if options.push {
-        push_bookmark(&options.name, &workspace_path).await?;
-    }
+        // Push to remote if requested
+        if push {
+            vcs.push_branch(&workspace_path, &name)?;
+        }
+        Ok(revision)
+    })
+    .await
+    .map_err(bookmark_error)?;
 
     Ok(BookmarkInfo {
         name: options.name.clone(),
@@ -214,21 +203,10 @@ pub async fn delete(options: &DeleteOptions) -> Result<()> {
         return Err(BookmarkError::NotFound(options.name.clone()).into());
     }
 
-    // Build JJ command
-    let mut cmd = Command::new("jj");
-    cmd.args(["bookmark", "delete", &options.name])
-        .current_dir(&workspace_path);
-
-    // Execute command
-    let output = cmd
-        .output()
+    let name = branch_name(&options.name)?;
+    run_vcs(move |vcs| vcs.delete_branch(&workspace_path, &name))
         .await
-        .context("failed to execute jj bookmark delete")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(BookmarkError::JjCommandFailed(stderr.to_string()).into());
-    }
+        .map_err(bookmark_error)?;
 
     Ok(())
 }
@@ -259,27 +237,11 @@ pub async fn move_bookmark(options: &MoveOptions) -> Result<BookmarkInfo> {
         return Err(BookmarkError::NotFound(options.name.clone()).into());
     }
 
-    // Build JJ command
-    let mut cmd = Command::new("jj");
-    cmd.args([
-        "bookmark",
-        "move",
-        &options.name,
-        "--to",
-        &options.to_revision,
-    ])
-    .current_dir(&workspace_path);
-
-    // Execute command
-    let output = cmd
-        .output()
+    let name = branch_name(&options.name)?;
+    let to_revision = options.to_revision.clone();
+    run_vcs(move |vcs| vcs.move_branch(&workspace_path, &name, &to_revision))
         .await
-        .context("failed to execute jj bookmark move")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(BookmarkError::JjCommandFailed(stderr.to_string()).into());
-    }
+        .map_err(bookmark_error)?;
 
     Ok(BookmarkInfo {
         name: options.name.clone(),
@@ -435,109 +397,17 @@ async fn resolve_workspace_path(session: Option<&str>) -> Result<String> {
     }
 }
 
-/// Parse JJ bookmark list output
-///
-/// Handles multi-line format:
-/// - Main bookmarks: "name: `change_id` `commit_id` description"
-/// - Remote bookmarks (indented): "  @remote: `change_id` `commit_id` description"
-/// - Deleted bookmarks: "name: ... (deleted)" on following line
-/// - Legacy format: "name: `revision_hash`"
-///
-/// Only returns non-deleted local bookmarks (skips indented remote lines).
-fn parse_bookmark_list(output: &[u8]) -> Result<Vec<BookmarkInfo>> {
-    let stdout = String::from_utf8_lossy(output);
-
-    // Use functional parsing with zero unwrap
-    let bookmarks: std::result::Result<Vec<BookmarkInfo>, anyhow::Error> = stdout
-        .lines()
-        .filter(|line| {
-            // Skip empty lines
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                return false;
-            }
-
-            // Skip indented remote bookmark lines (start with "  @")
-            if line.starts_with("  @") {
-                return false;
-            }
-
-            // Filter out lines marked as deleted
-            !trimmed.contains("(deleted)")
-        })
-        .map(|line| {
-            // Parse format: "bookmark_name: change_id commit_id description"
-            // or legacy: "bookmark_name: revision_hash"
-            let parts: Vec<&str> = line.splitn(2, ':').collect();
-
-            match parts.as_slice() {
-                [name_part, rest] => {
-                    let name = name_part.trim().to_string();
-
-                    // Extract revision (handle both formats)
-                    // New format: "change_id commit_id description" -> get commit_id
-                    // Legacy format: "revision_hash" -> get first token
-                    let tokens: Vec<&str> = rest.split_whitespace().collect();
-                    let revision = if tokens.len() >= 2 {
-                        // New format: skip change_id, get commit_id
-                        tokens
-                            .get(1)
-                            .map_or_else(|| "unknown".to_string(), ToString::to_string)
-                    } else {
-                        // Legacy format: use first token
-                        tokens
-                            .first()
-                            .map_or_else(|| "unknown".to_string(), ToString::to_string)
-                    };
-
-                    let remote = rest.contains("@origin");
-
-                    Ok(BookmarkInfo {
-                        name,
-                        revision,
-                        remote,
-                    })
-                }
-                _ => Err(anyhow::anyhow!("invalid bookmark list output: {line}")),
-            }
-        })
-        .collect();
-
-    bookmarks
-}
-
-/// Get current revision hash
-async fn get_current_revision(workspace_path: &str) -> Result<String> {
-    let output = Command::new("jj")
-        .args(["log", "-r", "@", "--no-graph", "-T", "commit_id"])
-        .current_dir(workspace_path)
-        .output()
-        .await
-        .context("failed to get current revision")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(BookmarkError::JjCommandFailed(stderr.to_string()).into());
-    }
-
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+fn branch_name(name: &str) -> Result<BranchName> {
+    BranchName::new(name)
+        .map_err(|e| BookmarkError::InvalidName(name.to_string(), e.to_string()).into())
 }
 
-/// Push bookmark to remote
-async fn push_bookmark(bookmark_name: &str, workspace_path: &str) -> Result<()> {
-    let output = Command::new("jj")
-        .args(["git", "push", "--bookmark", bookmark_name])
-        .current_dir(workspace_path)
-        .output()
-        .await
-        .context("failed to push bookmark")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(BookmarkError::JjCommandFailed(stderr.to_string()).into());
+/// Map a backend failure onto the bookmark error users see
+fn bookmark_error(err: VcsError) -> BookmarkError {
+    match err {
+        VcsError::BranchNotFound(name) => BookmarkError::NotFound(name),
+        other => BookmarkError::JjCommandFailed(other.to_string()),
     }
-
-    Ok(())
 }
 
 // ═══════════════════════════════════════════════════════════════════════════
@@ -548,6 +418,15 @@ async fn push_bookmark(bookmark_name: &str, workspace_path: &str) -> Result<()>
 mod tests {
     use super::*;
 
+    #[allow(clippy::unnecessary_wraps)]
+    fn parse_bookmark_list(output: &[u8]) -> Result<Vec<BookmarkInfo>> {
+        let output = String::from_utf8_lossy(output);
+        Ok(isolate_core::vcs::parse_bookmark_list(&output)
+            .into_iter()
+            .map(BookmarkInfo::from)
+            .collect())
+    }
+
     #[test]
     fn test_bookmark_error_messages() {
         let err = BookmarkError::AlreadyExists("feature-v1".to_string());
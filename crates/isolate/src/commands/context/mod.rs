@@ -87,6 +87,20 @@ pub fn detect_location(root: &PathBuf) -> Result<Location> {
         });
     }
 
+    // Git worktrees work the same way: a linked worktree has a `.git` FILE
+    // pointing at the main repository, the main worktree a `.git` directory.
+    if root.join(".git").is_file() {
+        let name = root
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| anyhow::anyhow!("Workspace directory name is not valid UTF-8"))?
+            .to_string();
+        return Ok(Location::Workspace {
+            name,
+            path: current_dir.to_string_lossy().to_string(),
+        });
+    }
+
     if current_dir == *root {
         return Ok(Location::Main);
     }
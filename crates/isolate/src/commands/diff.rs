@@ -9,7 +9,7 @@ use std::{io, path::Path, process::Stdio};
 use anyhow::Result;
 use isolate_core::{
     output::{emit_stdout, Message, OutputLine, ResultKind, ResultOutput, SessionOutput},
-    OutputFormat,
+    BackendType, GitBackend, OutputFormat, VcsBackend,
 };
 use tokio::{io::AsyncWriteExt, process::Command};
 
@@ -64,6 +64,10 @@ async fn detect_session_from_workspace(db: &crate::db::SessionDb) -> Result<Opti
     // Get current directory
     let current_dir = std::env::current_dir().context("Failed to get current directory")?;
 
+    if crate::commands::in_git_only_repo() {
+        return Ok(detect_session_from_worktree(db, &current_dir).await);
+    }
+
     // Try to get JJ workspace root
     let output = tokio::process::Command::new("jj")
         .args(["workspace", "root"])
@@ -116,6 +120,29 @@ async fn detect_session_from_workspace(db: &crate::db::SessionDb) -> Result<Opti
     Ok(None)
 }
 
+/// Find the session whose git worktree contains `current_dir`
+///
+/// Returns `None` in the main worktree, which has a `.git` directory rather
+/// than the `.git` file of a linked worktree.
+async fn detect_session_from_worktree(
+    db: &crate::db::SessionDb,
+    current_dir: &Path,
+) -> Option<String> {
+    let root = GitBackend::new()
+        .workspace_root(&current_dir.to_string_lossy())
+        .ok()?;
+    if !Path::new(&root).join(".git").is_file() {
+        return None;
+    }
+
+    db.list(None)
+        .await
+        .ok()?
+        .into_iter()
+        .find(|session| Path::new(&root) == Path::new(&session.workspace_path))
+        .map(|session| session.name)
+}
+
 /// Handle diff output using JSONL output format.
 ///
 /// For JSON format: emit Result line with diff content in data field.
@@ -241,6 +268,15 @@ pub async fn run(name: Option<&str>, stat: bool, format: OutputFormat) -> Result
     }
 
     let main_branch = determine_main_branch(workspace_path).await;
+
+    if isolate_core::discover_backend(workspace_path) == Some(BackendType::Git) {
+        let stdout = GitBackend::new()
+            .diff_against(&session.workspace_path, &main_branch, stat)
+            .map_err(|e| anyhow::Error::new(isolate_core::Error::from(e)))?;
+        handle_diff_output(&stdout, &session_name, stat, format, Some(&session)).await?;
+        return Ok(());
+    }
+
     let args = build_diff_args(stat, &main_branch);
 
     let output = Command::new("jj")
@@ -487,6 +487,17 @@ pub async fn run_conflict_detection<E: JjExecutor + ?Sized>(
     detector.detect_conflicts().await
 }
 
+/// Files with unresolved JJ conflicts in the working copy
+///
+/// Used after a rebase, where only conflicts jj recorded matter and trunk
+/// overlap does not.
+pub async fn existing_conflicts<E: JjExecutor + ?Sized>(
+    executor: &E,
+) -> Result<Vec<String>, ConflictError> {
+    let detector = JjConflictDetector::new(executor);
+    detector.check_existing_conflicts().await
+}
+
 /// Quick check for existing conflicts only
 #[allow(dead_code)] // Reserved for future use in quick conflict checks
 pub async fn has_conflicts<E: JjExecutor + ?Sized>(executor: &E) -> Result<bool, ConflictError> {
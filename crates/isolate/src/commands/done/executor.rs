@@ -1,6 +1,12 @@
 //! JJ command executor trait for dependency injection
 //!
 //! This module provides a trait for executing JJ commands.
+//!
+//! `done`, `restack` and `recover` drive `jj` through this trait rather than
+//! [`isolate_core::VcsBackend`]: they rely on JJ behaviour the backend hides,
+//! such as rebases that keep conflicts in the rebased commits and restoring
+//! from the operation log. Tests swap in `FakeJjExecutor`, which runs the same
+//! command lines against the in-memory repository behind `FakeBackend`.
 
 #![cfg_attr(not(test), deny(clippy::unwrap_used))]
 #![cfg_attr(not(test), deny(clippy::expect_used))]
@@ -9,7 +15,6 @@
 use std::{future::Future, pin::Pin};
 
 use thiserror::Error;
-use tokio::process::Command;
 
 use super::newtypes::JjOutput;
 
@@ -68,7 +73,7 @@ impl JjExecutor for RealJjExecutor {
         env: &'a [(&'a str, &'a str)],
     ) -> BoxFuture<'a, Result<JjOutput, ExecutorError>> {
         Box::pin(async move {
-            let mut cmd = Command::new("jj");
+            let mut cmd = isolate_core::jj::get_jj_command();
 
             if let Some(ref dir) = self.working_dir {
                 cmd.current_dir(dir);
@@ -0,0 +1,451 @@
+//! `JjExecutor` that answers from an in-memory repository
+//!
+//! [`FakeJjExecutor`] interprets the `jj` command lines the commands send and
+//! runs them against an [`isolate_core::vcs::FakeBackend`], printing what
+//! `jj` would print for the templates and flags in use. `-R`/`--repository`
+//! selects the workspace by path, so it composes with `WorkspaceExecutor`.
+//!
+//! Scripted failures and stale workspaces on the backend surface as failed
+//! commands with the error on stderr. Command lines it does not understand
+//! fail loudly rather than pretending to succeed.
+
+use std::collections::BTreeSet;
+
+use isolate_core::vcs::{
+    fake::{FakeCommit, DEFAULT_WORKSPACE},
+    FakeBackend, VcsError,
+};
+
+use super::{
+    executor::{BoxFuture, ExecutorError, JjExecutor},
+    newtypes::JjOutput,
+};
+
+/// Author of every commit and operation
+const USER: &str = "fake@example.com";
+
+/// In-memory stand-in for `jj`; see the module docs
+#[derive(Debug, Clone)]
+pub struct FakeJjExecutor {
+    repo: FakeBackend,
+    workspace: String,
+}
+
+impl FakeJjExecutor {
+    /// Run commands in the default workspace unless `-R` says otherwise
+    pub fn new(repo: FakeBackend) -> Self {
+        Self::in_workspace(repo, DEFAULT_WORKSPACE)
+    }
+
+    /// Run commands in `workspace` unless `-R` says otherwise
+    pub fn in_workspace(repo: FakeBackend, workspace: &str) -> Self {
+        Self {
+            repo,
+            workspace: workspace.to_string(),
+        }
+    }
+
+    fn execute(&self, args: &[&str]) -> Result<String, ExecutorError> {
+        let mut workspace = self.workspace.clone();
+        let mut rest = Vec::with_capacity(args.len());
+        let mut iter = args.iter().copied();
+        while let Some(arg) = iter.next() {
+            match arg {
+                "-R" | "--repository" => {
+                    let path = iter.next().unwrap_or_default();
+                    workspace = self
+                        .repo
+                        .workspace_at(path)
+                        .ok_or_else(|| failed(1, format!("There is no jj repo in \"{path}\"")))?;
+                }
+                "--no-pager" => {}
+                other => rest.push(other),
+            }
+        }
+        self.dispatch(&workspace, &rest)
+    }
+
+    fn dispatch(&self, ws: &str, args: &[&str]) -> Result<String, ExecutorError> {
+        let repo = &self.repo;
+        match args {
+            ["status" | "st"] => self.status(ws),
+            ["commit", "-m", message] => quiet(repo.commit_workspace(ws, message)),
+            ["log", options @ ..] => self.log(ws, options),
+            ["resolve", "--list"] => {
+                let conflicts = repo.working_copy(ws).map_err(vcs_failed)?.conflicts;
+                if conflicts.is_empty() {
+                    return Err(failed(2, "No conflicts found at this revision"));
+                }
+                Ok(lines(
+                    conflicts
+                        .iter()
+                        .map(|path| format!("{path}    2-sided conflict")),
+                ))
+            }
+            ["diff", options @ ..] => self.diff(ws, options),
+            ["rebase", options @ ..] => {
+                let source = option(options, &["-b", "--branch"]).unwrap_or("@");
+                let destination = option(options, &["-d", "--destination", "-o", "--onto"])
+                    .ok_or_else(|| unsupported(args))?;
+                quiet(repo.rebase_onto(ws, source, destination))
+            }
+            ["squash", options @ ..] => {
+                let from = option(options, &["--from"]).unwrap_or("@");
+                let into = option(options, &["--into", "--to"]).unwrap_or("@-");
+                let message = option(options, &["-m", "--message"]).unwrap_or_default();
+                quiet(repo.squash(ws, from, into, message))
+            }
+            ["new", revisions @ ..] if !revisions.iter().any(|r| r.starts_with('-')) => {
+                quiet(repo.new_change(ws, revisions))
+            }
+            ["edit", revision] => quiet(repo.edit(ws, revision)),
+            ["undo"] => quiet(repo.undo(ws)),
+            ["bookmark", "create", name, "-r", revision] => {
+                quiet(repo.create_bookmark(ws, name, revision))
+            }
+            ["bookmark", "set", name, options @ ..] => {
+                let revision = option(options, &["-r", "--revision"]).unwrap_or("@");
+                let backwards = options.contains(&"--allow-backwards");
+                quiet(repo.set_bookmark(ws, name, revision, backwards))
+            }
+            ["bookmark", "delete", name] => quiet(repo.delete_bookmark(ws, name)),
+            ["bookmark", "list", rest @ ..] => self.bookmark_list(ws, rest.contains(&"--all")),
+            ["git", "push", "--bookmark" | "-b", name] => quiet(repo.push_bookmark(ws, name)),
+            ["git", "push"] | ["git", "push", "--all"] => quiet(repo.push_all(ws)),
+            ["git", "fetch"] => quiet(repo.fetch(ws)),
+            ["workspace", "forget", name] => quiet(repo.forget(ws, name)),
+            ["workspace", "update-stale"] => quiet(repo.update_stale(ws)),
+            ["workspace", "root"] => repo
+                .workspace_path(ws)
+                .map(|path| format!("{path}\n"))
+                .map_err(vcs_failed),
+            ["workspace", "list"] => Ok(lines(repo.workspaces().iter().filter_map(|name| {
+                repo.working_copy(name)
+                    .ok()
+                    .map(|c| format!("{name}: {}", summary_line(&c)))
+            }))),
+            ["workspace", "add", "--name", name, options @ ..] => {
+                let (path, base) = match options {
+                    [path] => (*path, None),
+                    ["-r", base, path] => (*path, Some(*base)),
+                    _ => return Err(unsupported(args)),
+                };
+                quiet(repo.add_workspace_at(ws, name, path, base))
+            }
+            ["op", "log", options @ ..] => self.op_log(options),
+            ["op", "restore", "--operation", id] | ["op", "restore", id] => {
+                quiet(repo.restore_operation(ws, id))
+            }
+            _ => Err(unsupported(args)),
+        }
+    }
+
+    fn status(&self, ws: &str) -> Result<String, ExecutorError> {
+        let status = self.repo.status_of(ws).map_err(vcs_failed)?;
+        let working_copy = self.repo.working_copy(ws).map_err(vcs_failed)?;
+        let parent = self.repo.resolve(ws, "@-").map_err(vcs_failed)?;
+
+        let mut out = Vec::new();
+        if status.changes.is_empty() {
+            out.push("The working copy has no changes.".to_string());
+        } else {
+            out.push("Working copy changes:".to_string());
+            out.extend(
+                status
+                    .changes
+                    .iter()
+                    .map(|(kind, path)| format!("{kind} {path}")),
+            );
+        }
+        if !status.conflicts.is_empty() {
+            out.push("There are unresolved conflicts at these paths:".to_string());
+            out.extend(
+                status
+                    .conflicts
+                    .iter()
+                    .map(|path| format!("{path}    2-sided conflict")),
+            );
+        }
+        out.push(format!("Working copy : {}", summary_line(&working_copy)));
+        out.push(format!("Parent commit: {}", summary_line(&parent)));
+        Ok(lines(out))
+    }
+
+    fn log(&self, ws: &str, options: &[&str]) -> Result<String, ExecutorError> {
+        let revset = option(options, &["-r", "--revisions"]).unwrap_or("::@");
+        let limit = option(options, &["-n", "--limit"])
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(usize::MAX);
+        let template = option(options, &["-T", "--template"]);
+        let commits = self.repo.log_revset(ws, revset).map_err(vcs_failed)?;
+        commits
+            .iter()
+            .take(limit)
+            .map(|commit| match template {
+                Some(template) => self.render_commit(template, commit),
+                None => Ok(format!("{}\n", summary_line(commit))),
+            })
+            .collect()
+    }
+
+    fn diff(&self, ws: &str, options: &[&str]) -> Result<String, ExecutorError> {
+        let (from, to) = match option(options, &["-r", "--revision"]) {
+            Some(revision) => (format!("{revision}-"), revision.to_string()),
+            None => (
+                option(options, &["--from"]).unwrap_or("@-").to_string(),
+                option(options, &["--to"]).unwrap_or("@").to_string(),
+            ),
+        };
+        if !options.contains(&"--summary") {
+            return Err(unsupported(options));
+        }
+        let changes = self
+            .repo
+            .diff_revisions(ws, &from, &to)
+            .map_err(vcs_failed)?;
+        Ok(lines(
+            changes.iter().map(|(kind, path)| format!("{kind} {path}")),
+        ))
+    }
+
+    fn bookmark_list(&self, ws: &str, all: bool) -> Result<String, ExecutorError> {
+        let local = self.repo.bookmarks();
+        let remote = self.repo.remote_bookmarks();
+        let describe = |target: &str| {
+            self.repo
+                .resolve(ws, target)
+                .map(|c| summary_line(&c))
+                .map_err(vcs_failed)
+        };
+        let names: BTreeSet<&String> = local.keys().chain(remote.keys()).collect();
+        let mut out = Vec::new();
+        for name in names {
+            match (local.get(name), remote.get(name)) {
+                (Some(target), tracked) => {
+                    out.push(format!("{name}: {}", describe(target)?));
+                    if let Some(tracked) = tracked {
+                        out.push(format!("  @origin: {}", describe(tracked)?));
+                    }
+                }
+                (None, Some(target)) if all => {
+                    out.push(format!("{name}@origin: {}", describe(target)?));
+                }
+                _ => {}
+            }
+        }
+        Ok(lines(out))
+    }
+
+    fn op_log(&self, options: &[&str]) -> Result<String, ExecutorError> {
+        let limit = option(options, &["-n", "--limit"])
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(usize::MAX);
+        let template = option(options, &["-T", "--template"]);
+        self.repo
+            .operations()
+            .iter()
+            .take(limit)
+            .map(|op| {
+                let Some(template) = template else {
+                    return Ok(format!("{} {}\n", op.id, op.description));
+                };
+                render(template, |keyword| match keyword {
+                    "id" | "id.short()" | "self.id()" => Some(op.id.clone()),
+                    "description" => Some(op.description.clone()),
+                    "description.first_line()" => Some(first_line(&op.description)),
+                    "time" | "time.start()" | "time.end()" => Some(op.timestamp.to_string()),
+                    "user" => Some(USER.to_string()),
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+
+    fn render_commit(&self, template: &str, commit: &FakeCommit) -> Result<String, ExecutorError> {
+        let bookmarks = self.bookmarks_at(&commit.commit_id);
+        render(template, |keyword| match keyword {
+            "change_id" | "change_id.short()" => Some(commit.change_id.clone()),
+            "commit_id" | "commit_id.short()" => Some(commit.commit_id.clone()),
+            "description" => Some(commit.description.clone()),
+            "description.first_line()" => Some(first_line(&commit.description)),
+            "committer.timestamp()" | "author.timestamp()" => Some(commit.timestamp.to_string()),
+            "author.email()" | "committer.email()" => Some(USER.to_string()),
+            "empty" => Some(commit.tree.is_empty().to_string()),
+            k if k.starts_with("if(conflict") => Some(conditional(k, !commit.conflicts.is_empty())),
+            k if k.starts_with("local_bookmarks") || k.starts_with("bookmarks") => {
+                Some(bookmarks.join(" "))
+            }
+            _ => None,
+        })
+    }
+
+    fn bookmarks_at(&self, commit_id: &str) -> Vec<String> {
+        self.repo
+            .bookmarks()
+            .into_iter()
+            .filter(|(_, target)| target == commit_id)
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+impl JjExecutor for FakeJjExecutor {
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> BoxFuture<'a, Result<JjOutput, ExecutorError>> {
+        Box::pin(async move { self.run_with_env(args, &[]).await })
+    }
+
+    fn run_with_env<'a>(
+        &'a self,
+        args: &'a [&'a str],
+        _env: &'a [(&'a str, &'a str)],
+    ) -> BoxFuture<'a, Result<JjOutput, ExecutorError>> {
+        Box::pin(async move {
+            let stdout = self.execute(args)?;
+            JjOutput::new(stdout).map_err(|e| ExecutorError::InvalidUtf8(e.to_string()))
+        })
+    }
+}
+
+/// Render a template: `++`-joined string literals and keywords
+fn render(
+    template: &str,
+    keyword: impl Fn(&str) -> Option<String>,
+) -> Result<String, ExecutorError> {
+    split_concat(template)
+        .iter()
+        .map(|item| {
+            if let Some(literal) = string_literal(item) {
+                return Ok(literal);
+            }
+            keyword(item).ok_or_else(|| {
+                failed(
+                    1,
+                    format!("Failed to parse template: fake jj does not know `{item}`"),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Split on top-level `++`, ignoring any inside quotes or parentheses
+fn split_concat(template: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if quoted => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+                continue;
+            }
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth = depth.saturating_sub(1),
+            '+' if !quoted && depth == 0 && chars.peek() == Some(&'+') => {
+                chars.next();
+                items.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    items.push(current.trim().to_string());
+    items
+}
+
+/// The value of a `"..."` literal, with `\n`, `\t`, `\"` and `\\` unescaped
+fn string_literal(item: &str) -> Option<String> {
+    let inner = item.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => {}
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    Some(out)
+}
+
+/// Evaluate `if(conflict, "then"[, "else"])`
+fn conditional(item: &str, condition: bool) -> String {
+    let literals: Vec<String> = item
+        .split('"')
+        .skip(1)
+        .step_by(2)
+        .filter_map(|s| string_literal(&format!("\"{s}\"")))
+        .collect();
+    literals
+        .get(usize::from(!condition))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Value following any of `names` in `options`
+fn option<'a>(options: &[&'a str], names: &[&str]) -> Option<&'a str> {
+    options
+        .iter()
+        .position(|o| names.contains(o))
+        .and_then(|i| options.get(i + 1))
+        .copied()
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or_default().to_string()
+}
+
+/// `change_id commit_id description`, as `jj` prints a commit on one line
+fn summary_line(commit: &FakeCommit) -> String {
+    let description = if commit.description.is_empty() {
+        "(no description set)"
+    } else {
+        commit.description.as_str()
+    };
+    format!("{} {} {description}", commit.change_id, commit.commit_id)
+}
+
+/// Each item on its own line
+fn lines(items: impl IntoIterator<Item = String>) -> String {
+    items.into_iter().fold(String::new(), |mut out, line| {
+        out.push_str(&line);
+        out.push('\n');
+        out
+    })
+}
+
+/// Output of a command that prints nothing on success
+fn quiet<T>(result: Result<T, VcsError>) -> Result<String, ExecutorError> {
+    result.map(|_| String::new()).map_err(vcs_failed)
+}
+
+fn failed(code: i32, stderr: impl Into<String>) -> ExecutorError {
+    ExecutorError::CommandFailed {
+        code,
+        stderr: stderr.into(),
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // used as a `map_err` adapter
+fn vcs_failed(err: VcsError) -> ExecutorError {
+    failed(1, format!("Error: {err}"))
+}
+
+fn unsupported(args: &[&str]) -> ExecutorError {
+    failed(
+        2,
+        format!("fake jj does not support: jj {}", args.join(" ")),
+    )
+}
@@ -0,0 +1,424 @@
+//! Done for plain git repositories
+//!
+//! A session lives in a git worktree on a branch named after it. Landing it
+//! commits pending work, rebases the branch onto main inside the worktree and
+//! then merges it in the main worktree, where main is checked out. After the
+//! rebase the merge is a fast-forward. Squashing lands one commit instead.
+//!
+//! Git has no operation log to restore, so nothing is written to the undo log.
+//! The merge queue and conflict detection are built on JJ and are refused.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use isolate_core::{BranchName, Change, GitBackend, VcsBackend, VcsError};
+
+use super::{bead, filesystem, types, DoneError, DoneOptions, DoneOutput};
+
+/// Run the done command in a git repository, returning the exit code
+pub async fn run_with_options(options: &DoneOptions) -> Result<i32> {
+    if options.detect_conflicts {
+        crate::commands::require_jj("Conflict detection")?;
+    }
+    if options.queue {
+        crate::commands::require_jj("The merge queue")?;
+    }
+
+    let cwd = std::env::current_dir()?.to_string_lossy().to_string();
+    let git = GitBackend::new();
+    let main_root = blocking(move || {
+        git.main_worktree_root(&cwd)
+            .map_err(|e| invalid_state("Failed to find the main worktree", &e))
+    })
+    .await?;
+    let mut bead_repo = bead::RealBeadRepository::new(PathBuf::from(&main_root));
+    let filesystem = filesystem::RealFileSystem::new();
+
+    let output = match execute_done(options, &git, &mut bead_repo, &filesystem).await {
+        Ok(output) => output,
+        Err(error @ DoneError::GatesFailed { .. }) => {
+            return super::output_gates_failure(&error, options.format)
+        }
+        Err(error) => return Err(error.into()),
+    };
+    super::output_result(&output, options.format)?;
+    super::restack_after_landing(
+        &output,
+        &super::executor::RealJjExecutor::new(),
+        options.format,
+    )
+    .await?;
+    Ok(0)
+}
+
+/// Core done logic for a session in a git worktree
+pub async fn execute_done(
+    options: &DoneOptions,
+    git: &GitBackend,
+    bead_repo: &mut dyn bead::BeadRepository,
+    filesystem: &dyn filesystem::FileSystem,
+) -> Result<DoneOutput, DoneError> {
+    let git = *git;
+
+    // Phase 1: Resolve the worktree and the main worktree it lands in
+    let cwd = std::env::current_dir()
+        .map_err(|e| invalid_state("Failed to get current directory", &e))?
+        .to_string_lossy()
+        .to_string();
+    let (root, main_root) = blocking(move || {
+        let root = git
+            .workspace_root(&cwd)
+            .map_err(|e| invalid_state("Not in a git repository", &e))?;
+        let main_root = git
+            .main_worktree_root(&cwd)
+            .map_err(|e| invalid_state("Failed to find the main worktree", &e))?;
+        Ok((root, main_root))
+    })
+    .await?;
+    let workspace_name = match &options.workspace {
+        Some(name) => name.clone(),
+        None => super::get_workspace_name(&root)?,
+    };
+    let session = super::get_session_info(&workspace_name).await?;
+    let workspace_path = session.workspace_path.clone();
+
+    let main = {
+        let main_root = main_root.clone();
+        blocking(move || {
+            BranchName::new(git.main_branch(&main_root))
+                .map_err(|e| invalid_state("Invalid main branch", &e))
+        })
+        .await?
+    };
+
+    // Phase 2: Handle dry-run early
+    if options.dry_run {
+        let preview =
+            build_preview(git, &workspace_name, &workspace_path, &main, bead_repo).await?;
+        return Ok(DoneOutput {
+            workspace_name,
+            dry_run: true,
+            preview: Some(preview),
+            session_updated: false,
+            ..Default::default()
+        });
+    }
+
+    // Phase 2.5: Nothing lands unless the quality gates pass
+    let gates = super::run_quality_gates(&workspace_path).await?;
+
+    // Phase 3-7: Commit pending work, replay it on main and land it there
+    let message = options
+        .message
+        .clone()
+        .unwrap_or_else(|| format!("Complete work on {workspace_name}"));
+    let landing = Landing {
+        workspace_path: workspace_path.clone(),
+        main_root: main_root.clone(),
+        main,
+        message,
+        squash: options.squash,
+    };
+    let landed = blocking(move || landing.land(git)).await?;
+
+    // Phase 8-9: Update statuses and remove the worktree directory. This runs
+    // before the worktree is dropped, while the current directory still exists.
+    let output = super::finalize_done_status(
+        &workspace_name,
+        &workspace_path,
+        options,
+        bead_repo,
+        filesystem,
+        landed.files_committed,
+        landed.commits_merged,
+        false,
+    )
+    .await?;
+
+    // Phase 9 (git): unregister the worktree and drop its branch, now in main
+    if !options.keep_workspace {
+        let name = workspace_name.clone();
+        let branch = landed.branch;
+        blocking(move || {
+            git.forget_workspace(&main_root, &name)
+                .map_err(|e| DoneError::CleanupFailed {
+                    reason: format!("Failed to remove worktree {name}: {e}"),
+                })?;
+            if let Err(e) = git.delete_branch(&main_root, &branch) {
+                tracing::warn!("Failed to delete branch '{branch}' after landing: {e}");
+            }
+            Ok(())
+        })
+        .await?;
+    }
+
+    Ok(DoneOutput { gates, ..output })
+}
+
+/// Run git work off the async runtime
+async fn blocking<T, F>(op: F) -> Result<T, DoneError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, DoneError> + Send + 'static,
+{
+    tokio::task::spawn_blocking(op)
+        .await
+        .map_err(|e| invalid_state("git task failed", &e))?
+}
+
+/// A session worktree to land on main
+struct Landing {
+    workspace_path: String,
+    main_root: String,
+    main: BranchName,
+    /// Message for pending work, and for the squashed commit
+    message: String,
+    squash: bool,
+}
+
+/// What landing a worktree did
+#[derive(Debug)]
+struct Landed {
+    /// Branch the worktree had checked out
+    branch: BranchName,
+    files_committed: usize,
+    commits_merged: usize,
+}
+
+impl Landing {
+    /// Commit pending work, rebase the branch onto main inside the worktree
+    /// and merge it in the main worktree
+    ///
+    /// A conflicting rebase is aborted, leaving the worktree as it was.
+    fn land(&self, git: GitBackend) -> Result<Landed, DoneError> {
+        let path = self.workspace_path.as_str();
+        let branch = git
+            .current_branch(path)
+            .map_err(|e| invalid_state("Session worktree has no branch", &e))?;
+
+        let uncommitted = git
+            .status(path)
+            .map_err(|e| invalid_state("git status failed", &e))?
+            .uncommitted_files;
+        if !uncommitted.is_empty() {
+            git.commit(path, &self.message)
+                .map_err(|e| DoneError::CommitFailed {
+                    reason: e.to_string(),
+                })?;
+        }
+
+        // Replay the branch on main; conflicts abort and leave it as it was
+        git.rebase(path, &branch, &self.main).map_err(merge_error)?;
+        let commits_merged = git
+            .commits_between(path, self.main.as_str(), branch.as_str())
+            .map_err(|e| invalid_state("git log failed", &e))?
+            .len();
+
+        // Land on main in the main worktree
+        if self.squash {
+            git.squash_merge(&self.main_root, &branch, &self.message)
+        } else {
+            git.merge(&self.main_root, &branch, &self.main)
+        }
+        .map_err(merge_error)?;
+
+        Ok(Landed {
+            branch,
+            files_committed: uncommitted.len(),
+            commits_merged,
+        })
+    }
+}
+
+/// Build preview for dry-run mode
+async fn build_preview(
+    git: GitBackend,
+    workspace_name: &str,
+    workspace_path: &str,
+    main: &BranchName,
+    bead_repo: &dyn bead::BeadRepository,
+) -> Result<types::DonePreview, DoneError> {
+    let (uncommitted_files, commits_to_merge) = {
+        let path = workspace_path.to_string();
+        let main = main.clone();
+        blocking(move || {
+            let uncommitted_files = git
+                .status(&path)
+                .map_err(|e| invalid_state("git status failed", &e))?
+                .uncommitted_files;
+            let commits_to_merge = git
+                .commits_between(&path, main.as_str(), "HEAD")
+                .map_err(|e| invalid_state("git log failed", &e))?
+                .into_iter()
+                .map(commit_info)
+                .collect();
+            Ok((uncommitted_files, commits_to_merge))
+        })
+        .await?
+    };
+    let bead_to_close = super::get_bead_id_for_workspace(workspace_name, bead_repo).await?;
+
+    Ok(types::DonePreview {
+        uncommitted_files,
+        commits_to_merge,
+        potential_conflicts: Vec::new(),
+        bead_to_close,
+        workspace_path: workspace_path.to_string(),
+        conflict_detection: None,
+    })
+}
+
+fn commit_info(change: Change) -> types::CommitInfo {
+    types::CommitInfo {
+        change_id: change.id.to_string(),
+        commit_id: change.commit_id.to_string(),
+        description: change.description,
+        timestamp: change.timestamp.to_string(),
+    }
+}
+
+fn merge_error(err: VcsError) -> DoneError {
+    match err {
+        VcsError::Conflict(conflicts) => DoneError::MergeConflict {
+            conflicts: vec![conflicts],
+        },
+        other => DoneError::MergeFailed {
+            reason: other.to_string(),
+        },
+    }
+}
+
+fn invalid_state(context: &str, err: &dyn std::fmt::Display) -> DoneError {
+    DoneError::InvalidState {
+        reason: format!("{context}: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path, process::Command};
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Run `git` in `dir`, returning stdout
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .expect("Should run git");
+        assert!(
+            output.status.success(),
+            "git {args:?} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    fn commit_file(dir: &Path, file: &str, contents: &str) {
+        fs::write(dir.join(file), contents).expect("Should write file");
+        git(dir, &["add", "."]);
+        git(dir, &["commit", "-q", "-m", &format!("Edit {file}")]);
+    }
+
+    /// A git repository on `main` at `<temp>/repo` with a session worktree
+    /// on branch `feature` at `<temp>/feature`
+    fn repo_with_worktree() -> (TempDir, String, String) {
+        let temp = TempDir::new().expect("Should create temp dir");
+        let repo = temp.path().join("repo");
+        fs::create_dir(&repo).expect("Should create repo dir");
+        git(&repo, &["init", "-q", "-b", "main"]);
+        git(&repo, &["config", "user.email", "dev@example.com"]);
+        git(&repo, &["config", "user.name", "Dev"]);
+        git(&repo, &["config", "commit.gpgsign", "false"]);
+        commit_file(&repo, "README", "hello\n");
+
+        let repo = repo.to_string_lossy().to_string();
+        let worktree = temp.path().join("feature").to_string_lossy().to_string();
+        GitBackend::new()
+            .add_workspace(&repo, "feature", &worktree, None)
+            .expect("Should add worktree");
+        (temp, repo, worktree)
+    }
+
+    fn landing(repo: &str, worktree: &str) -> Landing {
+        Landing {
+            workspace_path: worktree.to_string(),
+            main_root: repo.to_string(),
+            main: BranchName::new("main").expect("Should name main"),
+            message: "Complete work on feature".to_string(),
+            squash: false,
+        }
+    }
+
+    #[test]
+    fn test_worktree_add_and_forget() {
+        let (_temp, repo, worktree) = repo_with_worktree();
+        let git = GitBackend::new();
+
+        assert_eq!(
+            git.list_workspaces(&repo).expect("Should list"),
+            vec!["default".to_string(), "feature".to_string()]
+        );
+        assert_eq!(
+            git.current_branch(&worktree).expect("Should have branch"),
+            BranchName::new("feature").expect("Should name branch")
+        );
+
+        git.forget_workspace(&repo, "feature")
+            .expect("Should forget worktree");
+
+        assert!(!Path::new(&worktree).exists());
+        assert_eq!(
+            git.list_workspaces(&repo).expect("Should list"),
+            vec!["default".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_done_lands_worktree_on_main() {
+        let (_temp, repo, worktree) = repo_with_worktree();
+        commit_file(Path::new(&repo), "CHANGELOG", "main moved on\n");
+        commit_file(Path::new(&worktree), "feature.rs", "fn feature() {}\n");
+        fs::write(Path::new(&worktree).join("pending.rs"), "// wip\n")
+            .expect("Should write pending work");
+
+        let landed = landing(&repo, &worktree)
+            .land(GitBackend::new())
+            .expect("Should land");
+
+        assert_eq!(landed.branch.as_str(), "feature");
+        assert_eq!(landed.files_committed, 1);
+        assert_eq!(landed.commits_merged, 2);
+        let main_tree = git(Path::new(&repo), &["ls-tree", "--name-only", "main"]);
+        assert_eq!(
+            main_tree.lines().collect::<Vec<_>>(),
+            vec!["CHANGELOG", "README", "feature.rs", "pending.rs"]
+        );
+    }
+
+    #[test]
+    fn test_conflicting_done_aborts_rebase() {
+        let (_temp, repo, worktree) = repo_with_worktree();
+        commit_file(Path::new(&repo), "README", "edited on main\n");
+        commit_file(Path::new(&worktree), "README", "edited in session\n");
+        let main_before = git(Path::new(&repo), &["rev-parse", "main"]);
+        let feature_before = git(Path::new(&worktree), &["rev-parse", "HEAD"]);
+
+        let result = landing(&repo, &worktree).land(GitBackend::new());
+
+        assert!(matches!(result, Err(DoneError::MergeConflict { .. })));
+        assert_eq!(git(Path::new(&repo), &["rev-parse", "main"]), main_before);
+        assert_eq!(
+            git(Path::new(&worktree), &["rev-parse", "HEAD"]),
+            feature_before
+        );
+        assert_eq!(git(Path::new(&worktree), &["status", "--porcelain"]), "");
+        assert_eq!(
+            fs::read_to_string(Path::new(&worktree).join("README")).expect("Should read README"),
+            "edited in session\n"
+        );
+    }
+}
@@ -1,7 +1,8 @@
 //! Done command - Complete work and merge to main
 //!
 //! This command:
-//! 1. Validates we're in a workspace (not main)
+//! 1. Validates we're in a workspace (not main) and runs the quality gates configured under
+//!    `[gates]`, refusing to land on failure
 //! 2. Checks for uncommitted changes
 //! 3. Commits any uncommitted changes
 //! 4. Checks for merge conflicts
@@ -10,6 +11,11 @@
 //! 7. Updates linked bead status to completed
 //! 8. Keeps workspace for 24h (unless --no-keep specified)
 //! 9. Switches back to main
+//!
+//! With `--queue`, the gates and steps 4-9 are skipped: changes are committed
+//! and the session is added to the merge queue, which gates and lands it.
+//!
+//! Plain git repositories take the worktree path in [`git`].
 
 #![cfg_attr(not(test), deny(clippy::unwrap_used))]
 #![cfg_attr(not(test), deny(clippy::expect_used))]
@@ -18,17 +24,29 @@
 pub mod bead;
 pub mod conflict;
 pub mod executor;
+#[cfg(test)]
+pub mod fake_executor;
 pub mod filesystem;
+pub mod git;
 pub mod newtypes;
 pub mod types;
 
+#[cfg(test)]
+mod tests;
+
 use std::{
     path::{Path, PathBuf},
     time::SystemTime,
 };
 
 use anyhow::Result;
-use isolate_core::{json::SchemaEnvelope, WorkspaceState};
+use isolate_core::{
+    coordination::QueueStore,
+    enqueue_session, format_failure_message,
+    json::{ErrorDetail, JsonError, SchemaEnvelope},
+    GateResult, WorkspaceState, DEFAULT_PRIORITY,
+};
+use serde::Serialize;
 pub use types::{DoneError, DoneOptions, DoneOutput, UndoEntry};
 
 use self::conflict::ConflictDetector;
@@ -36,13 +54,22 @@ use crate::{
     cli::jj_root,
     commands::{
         context::{detect_location, Location},
-        get_session_db,
+        gates, get_session_db,
+        queue::types::QueueEntryOutput,
+        restack,
     },
     session::{SessionStatus, SessionUpdate},
 };
 
 /// Run the done command with options
-pub async fn run_with_options(options: &DoneOptions) -> Result<()> {
+///
+/// Returns the process exit code: 0, or 3 when the quality gates refused the
+/// landing. Other failures are errors.
+pub async fn run_with_options(options: &DoneOptions) -> Result<i32> {
+    if crate::commands::in_git_only_repo() {
+        return git::run_with_options(options).await;
+    }
+
     // Create real dependencies
     let executor = executor::RealJjExecutor::new();
     let root_path = jj_root()
@@ -92,12 +119,104 @@ pub async fn run_with_options(options: &DoneOptions) -> Result<()> {
         if result.has_conflicts() {
             anyhow::bail!("Merge conflicts detected");
         }
-        return Ok(());
+        return Ok(0);
+    }
+
+    if options.queue {
+        let output = enqueue_done(options, &executor).await?;
+        output_result(&output, options.format)?;
+        return Ok(0);
     }
 
-    let output = execute_done(options, &executor, &mut bead_repo, &filesystem).await?;
+    let output = match execute_done(options, &executor, &mut bead_repo, &filesystem).await {
+        Ok(output) => output,
+        Err(error @ DoneError::GatesFailed { .. }) => {
+            return output_gates_failure(&error, options.format)
+        }
+        Err(error) => return Err(error.into()),
+    };
     output_result(&output, options.format)?;
-    Ok(())
+    restack_after_landing(&output, &executor, options.format).await?;
+    Ok(0)
+}
+
+/// Restack the sessions built on a session that just landed on main
+///
+/// The landed session leaves the stack, so its children move onto its own
+/// parent, or main. Their conflicts are reported per child.
+async fn restack_after_landing(
+    output: &DoneOutput,
+    executor: &dyn executor::JjExecutor,
+    format: isolate_core::OutputFormat,
+) -> Result<()> {
+    if !output.merged || output.dry_run {
+        return Ok(());
+    }
+
+    let db = get_session_db().await?;
+    let restacked =
+        restack::restack_descendants(&db, executor, &output.workspace_name, true).await?;
+    restack::emit_restacked(&restacked, format)
+}
+
+/// Commit pending work and hand the session to the merge queue instead of merging
+///
+/// The queue processor lands it later; the workspace is left in place.
+pub async fn enqueue_done(
+    options: &DoneOptions,
+    executor: &dyn executor::JjExecutor,
+) -> Result<DoneOutput, DoneError> {
+    let root = validate_location(options).await?;
+    let workspace_name = match &options.workspace {
+        Some(name) => name.clone(),
+        None => get_workspace_name(&root)?,
+    };
+    let session = get_session_info(&workspace_name).await?;
+
+    let workspace_executor =
+        executor::WorkspaceExecutor::new(executor, PathBuf::from(&session.workspace_path));
+    let files_committed =
+        prepare_workspace_for_merge(&root, &workspace_name, options, &workspace_executor).await?;
+
+    let db = get_session_db()
+        .await
+        .map_err(|e| DoneError::InvalidState {
+            reason: format!("Failed to open session database: {e}"),
+        })?;
+
+    let priority = options.priority.unwrap_or(DEFAULT_PRIORITY);
+    let name = workspace_name.clone();
+    let queue = QueueStore::new(db.pool().clone())
+        .modify(move |queue| enqueue_session(queue, name, priority))
+        .await
+        .map_err(|e| DoneError::InvalidState {
+            reason: format!("Failed to enqueue session: {e}"),
+        })?;
+    let queued = QueueEntryOutput::find_in(&queue, &workspace_name);
+
+    // Mark the session ready so status views show it is waiting to land
+    let session_updated = if session.state.can_transition_to(WorkspaceState::Ready) {
+        let update = SessionUpdate {
+            state: Some(WorkspaceState::Ready),
+            ..SessionUpdate::default()
+        };
+        db.update(&workspace_name, update)
+            .await
+            .map_err(|e| DoneError::InvalidState {
+                reason: format!("Failed to update session state: {e}"),
+            })?;
+        true
+    } else {
+        false
+    };
+
+    Ok(DoneOutput {
+        workspace_name,
+        files_committed,
+        session_updated,
+        queued,
+        ..Default::default()
+    })
 }
 
 /// Core done logic using Railway-Oriented Programming
@@ -142,43 +261,146 @@ pub async fn execute_done(
         });
     }
 
+    // Phase 2.5: Nothing lands unless the quality gates pass
+    let gates = run_quality_gates(&session.workspace_path).await?;
+
+    // Phase 3-7: Commit, check for conflicts, merge and log undo history
+    let merged = merge_workspace(
+        &root,
+        &workspace_name,
+        options,
+        &workspace_executor,
+        filesystem,
+    )
+    .await?;
+
+    // Phase 8-9: Update statuses and cleanup
+    let output = finalize_done_status(
+        &workspace_name,
+        &session.workspace_path,
+        options,
+        bead_repo,
+        filesystem,
+        merged.files_committed,
+        merged.commits_merged,
+        merged.pushed_to_remote,
+    )
+    .await?;
+
+    Ok(DoneOutput { gates, ..output })
+}
+
+/// Phase 2.5: run the configured quality gates in the session's workspace
+///
+/// A failing gate refuses the landing with the gates' failure summary and
+/// results. Gates that could not be run at all are reported separately.
+async fn run_quality_gates(workspace_path: &str) -> Result<Vec<GateResult>, DoneError> {
+    let outcome = gates::run_configured(Path::new(workspace_path))
+        .await
+        .map_err(|e| DoneError::GatesUnavailable {
+            reason: format!("{e:#}"),
+        })?;
+
+    if outcome.status.is_failure() {
+        return Err(DoneError::GatesFailed {
+            summary: format_failure_message(&outcome),
+            gates: outcome.results,
+        });
+    }
+    Ok(outcome.results)
+}
+
+/// Exit code of a landing the quality gates refused
+const GATES_FAILED_EXIT_CODE: i32 = 3;
+
+/// Report a landing the quality gates refused and return the exit code
+///
+/// JSON mode prints a `done-response` error envelope carrying the summary and
+/// every gate result; otherwise the error goes to stderr.
+fn output_gates_failure(error: &DoneError, format: isolate_core::OutputFormat) -> Result<i32> {
+    if format.is_json() {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&gates_failure_envelope(error))?
+        );
+    } else {
+        eprintln!("Error: {error}");
+    }
+    Ok(GATES_FAILED_EXIT_CODE)
+}
+
+#[derive(Debug, Serialize)]
+struct FailurePayload {
+    error: ErrorDetail,
+}
+
+/// `done-response` error envelope for a refused landing
+fn gates_failure_envelope(error: &DoneError) -> SchemaEnvelope<FailurePayload> {
+    let mut json_error = JsonError::new(error.error_code(), error.to_string())
+        .with_exit_code(GATES_FAILED_EXIT_CODE);
+    if let DoneError::GatesFailed { summary, gates } = error {
+        json_error = json_error.with_details(serde_json::json!({
+            "phase": error.phase().name(),
+            "summary": summary,
+            "gates": gates,
+        }));
+    }
+    SchemaEnvelope::new(
+        "done-response",
+        "single",
+        FailurePayload {
+            error: json_error.error,
+        },
+    )
+    .as_error()
+}
+
+/// What [`merge_workspace`] did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MergeSummary {
+    files_committed: usize,
+    commits_merged: usize,
+    pushed_to_remote: bool,
+}
+
+/// Phases 3-7: commit pending work, refuse on conflicts, merge to main and
+/// record the undo entry under `root`
+async fn merge_workspace(
+    root: &str,
+    workspace_name: &str,
+    options: &DoneOptions,
+    executor: &dyn executor::JjExecutor,
+    filesystem: &dyn filesystem::FileSystem,
+) -> Result<MergeSummary, DoneError> {
     // Phase 3-4: Prep workspace (commit uncommitted changes)
     let files_committed =
-        prepare_workspace_for_merge(&root, &workspace_name, options, &workspace_executor).await?;
+        prepare_workspace_for_merge(root, workspace_name, options, executor).await?;
 
     // Phase 5: Check for conflicts
-    check_conflicts(&root, &workspace_executor).await?;
+    check_conflicts(root, executor).await?;
 
     // Phase 5.5-6: Gather merge metadata
-    let pre_merge_commit_id = get_current_commit_id(&root, &workspace_executor).await?;
-    let pushed_to_remote = is_pushed_to_remote(&root, &workspace_executor).await?;
-    let commits_to_merge = get_commits_to_merge(&root, &workspace_executor).await?;
+    let pre_merge_commit_id = get_current_commit_id(root, executor).await?;
+    let pushed_to_remote = is_pushed_to_remote(root, executor).await?;
+    let commits_to_merge = get_commits_to_merge(root, executor).await?;
 
     // Phase 7: Perform merge and log undo history
     perform_merge_and_undo_log(
-        &root,
-        &workspace_name,
+        root,
+        workspace_name,
         &pre_merge_commit_id,
         pushed_to_remote,
         options,
-        &workspace_executor,
+        executor,
         filesystem,
-        bead_repo,
     )
     .await?;
 
-    // Phase 8-9: Update statuses and cleanup
-    finalize_done_status(
-        &workspace_name,
-        &session.workspace_path,
-        options,
-        bead_repo,
-        filesystem,
+    Ok(MergeSummary {
         files_committed,
-        commits_to_merge.len(),
+        commits_merged: commits_to_merge.len(),
         pushed_to_remote,
-    )
-    .await
+    })
 }
 
 /// Get session info from database
@@ -225,7 +447,6 @@ async fn perform_merge_and_undo_log(
     options: &DoneOptions,
     executor: &dyn executor::JjExecutor,
     filesystem: &dyn filesystem::FileSystem,
-    _bead_repo: &dyn bead::BeadRepository,
 ) -> Result<(), DoneError> {
     // Phase 7: Merge to main
     merge_to_main(
@@ -308,6 +529,8 @@ async fn finalize_done_status(
         pushed_to_remote,
         dry_run: false,
         preview: None,
+        queued: None,
+        gates: Vec::new(),
         error: None,
     })
 }
@@ -405,21 +628,7 @@ async fn get_uncommitted_files(
                 reason: e.to_string(),
             })?;
 
-    let stdout = output.as_str();
-    let files = stdout
-        .lines()
-        .map(str::trim)
-        .filter(|line| {
-            line.starts_with("A ")
-                || line.starts_with("M ")
-                || line.starts_with("D ")
-                || line.starts_with("R ")
-        })
-        .filter_map(|line| line.split_ascii_whitespace().nth(1))
-        .map(String::from)
-        .collect();
-
-    Ok(files)
+    Ok(isolate_core::jj::parse_status(output.as_str()).changed_files())
 }
 
 /// Commit uncommitted changes
@@ -688,8 +897,24 @@ fn output_result(result: &DoneOutput, format: isolate_core::OutputFormat) -> Res
                 print!("{}", conflict_detection.to_text_output());
             }
         }
+    } else if let Some(ref entry) = result.queued {
+        println!(
+            "📥 Workspace '{}' queued for merge (position {}, priority {})",
+            result.workspace_name,
+            entry.position + 1,
+            entry.priority
+        );
+        if result.files_committed > 0 {
+            println!("  Committed {} files", result.files_committed);
+        }
+        println!();
+        println!("NEXT: Track it with:");
+        println!("  isolate queue status {}", result.workspace_name);
     } else {
         println!("✅ Workspace '{}' completed", result.workspace_name);
+        if !result.gates.is_empty() {
+            println!("  Gates passed: {}", gates::describe_passed(&result.gates));
+        }
         if result.merged {
             println!("  Merged {} commits to main", result.commits_merged);
         }
@@ -0,0 +1,268 @@
+//! End-to-end tests of the jj done flow against the in-memory repository
+
+use std::path::PathBuf;
+
+use isolate_core::{
+    config::{GateDefinition, GateKind, GatesConfig},
+    format_failure_message,
+    vcs::{FakeBackend, FakeFailure, FakeOp},
+    GateResult, GatesOutcome, OutputFormat,
+};
+
+use super::{
+    executor::WorkspaceExecutor, fake_executor::FakeJjExecutor, filesystem::RealFileSystem,
+    gates_failure_envelope, merge_workspace, DoneError, DoneOptions, MergeSummary,
+};
+use crate::{
+    commands::gates::{run_pipeline, TreeCache},
+    db::SessionDb,
+};
+
+const WORKSPACE: &str = "feature";
+const WORKSPACE_PATH: &str = "/repo/.isolate/workspaces/feature";
+
+fn options(squash: bool) -> DoneOptions {
+    DoneOptions {
+        workspace: Some(WORKSPACE.to_string()),
+        message: Some("Add feature".to_string()),
+        keep_workspace: false,
+        no_keep: false,
+        squash,
+        dry_run: false,
+        detect_conflicts: false,
+        no_bead_update: true,
+        queue: false,
+        priority: None,
+        format: OutputFormat::Json,
+    }
+}
+
+/// A repository with `lib.rs` on main and a session workspace started there
+fn repo_with_session() -> FakeBackend {
+    let repo = FakeBackend::new("/repo");
+    repo.commit_on("main", "Initial commit", &[("lib.rs", "fn lib() {}\n")])
+        .expect("commit on main");
+    repo.add_workspace_at("default", WORKSPACE, WORKSPACE_PATH, Some("main"))
+        .expect("add workspace");
+    repo
+}
+
+async fn run_done(
+    repo: &FakeBackend,
+    options: &DoneOptions,
+) -> (tempfile::TempDir, Result<MergeSummary, DoneError>) {
+    let root = tempfile::tempdir().expect("tempdir");
+    std::fs::create_dir_all(root.path().join(".isolate")).expect("create .isolate");
+    let fake = FakeJjExecutor::new(repo.clone());
+    let executor = WorkspaceExecutor::new(&fake, PathBuf::from(WORKSPACE_PATH));
+    let result = merge_workspace(
+        &root.path().to_string_lossy(),
+        WORKSPACE,
+        options,
+        &executor,
+        &RealFileSystem::new(),
+    )
+    .await;
+    (root, result)
+}
+
+#[tokio::test]
+async fn test_squash_lands_work_on_main_and_forgets_workspace() {
+    let repo = repo_with_session();
+    repo.write_file(WORKSPACE, "feature.rs", "fn feature() {}\n")
+        .expect("write");
+
+    let (root, result) = run_done(&repo, &options(true)).await;
+
+    let summary = result.expect("done should land the session");
+    assert_eq!(summary.files_committed, 1);
+    assert!(!summary.pushed_to_remote);
+    let main = repo.resolve("default", "main").expect("main");
+    assert_eq!(
+        main.tree.get("feature.rs").map(String::as_str),
+        Some("fn feature() {}\n")
+    );
+    assert_eq!(main.description, "Add feature");
+    assert!(!repo.workspaces().contains(&WORKSPACE.to_string()));
+    assert!(root.path().join(".isolate/undo.log").exists());
+}
+
+#[tokio::test]
+async fn test_overlapping_edits_on_main_are_refused() {
+    let repo = repo_with_session();
+    repo.write_file(WORKSPACE, "lib.rs", "fn lib() { feature() }\n")
+        .expect("write");
+    repo.commit_on("main", "Concurrent change", &[("lib.rs", "fn lib2() {}\n")])
+        .expect("commit on main");
+    let main_before = repo.bookmark("main");
+
+    let (_root, result) = run_done(&repo, &options(true)).await;
+
+    match result {
+        Err(DoneError::MergeConflict { conflicts }) => {
+            assert_eq!(conflicts, vec!["lib.rs".to_string()]);
+        }
+        other => panic!("expected MergeConflict, got {other:?}"),
+    }
+    assert_eq!(repo.bookmark("main"), main_before);
+    assert!(repo.workspaces().contains(&WORKSPACE.to_string()));
+}
+
+#[tokio::test]
+async fn test_existing_conflict_in_workspace_is_refused() {
+    let repo = repo_with_session();
+    repo.write_file(WORKSPACE, "lib.rs", "fn lib() { feature() }\n")
+        .expect("write");
+    repo.commit_workspace(WORKSPACE, "Change lib")
+        .expect("commit");
+    repo.commit_on("main", "Concurrent change", &[("lib.rs", "fn lib2() {}\n")])
+        .expect("commit on main");
+    let conflicted = repo
+        .rebase_onto(WORKSPACE, "@-", "main")
+        .expect("jj records conflicts instead of failing");
+    assert_eq!(conflicted, vec!["lib.rs".to_string()]);
+
+    let (_root, result) = run_done(&repo, &options(false)).await;
+
+    assert!(
+        matches!(&result, Err(DoneError::MergeConflict { conflicts }) if conflicts.contains(&"lib.rs".to_string())),
+        "expected MergeConflict, got {result:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_stale_working_copy_fails_before_committing() {
+    let repo = repo_with_session();
+    repo.write_file(WORKSPACE, "feature.rs", "fn feature() {}\n")
+        .expect("write");
+    repo.mark_stale(WORKSPACE);
+
+    let (_root, result) = run_done(&repo, &options(true)).await;
+
+    match result {
+        Err(DoneError::JjCommandFailed { command, reason }) => {
+            assert_eq!(command, "jj status");
+            assert!(reason.contains("stale"), "unexpected reason: {reason}");
+        }
+        other => panic!("expected JjCommandFailed, got {other:?}"),
+    }
+    let main = repo.resolve("default", "main").expect("main");
+    assert!(!main.tree.contains_key("feature.rs"));
+}
+
+#[tokio::test]
+async fn test_failed_squash_reports_merge_failure_and_keeps_workspace() {
+    let repo = repo_with_session();
+    repo.write_file(WORKSPACE, "feature.rs", "fn feature() {}\n")
+        .expect("write");
+    repo.fail_next(
+        FakeOp::Squash,
+        FakeFailure::Error("concurrent modification".to_string()),
+    );
+    let main_before = repo.bookmark("main");
+
+    let (_root, result) = run_done(&repo, &options(true)).await;
+
+    match result {
+        Err(DoneError::MergeFailed { reason }) => {
+            assert!(
+                reason.contains("Squash failed"),
+                "unexpected reason: {reason}"
+            );
+            assert!(reason.contains("concurrent modification"));
+        }
+        other => panic!("expected MergeFailed, got {other:?}"),
+    }
+    assert_eq!(repo.bookmark("main"), main_before);
+    assert!(repo.workspaces().contains(&WORKSPACE.to_string()));
+}
+
+/// Run a gate logging to `runs.txt` in `dir`, cached on the session's tree
+async fn run_gates(repo: &FakeBackend, db: &SessionDb, dir: &std::path::Path) -> GatesOutcome {
+    let config = GatesConfig {
+        steps: vec![GateDefinition {
+            name: "unit".to_string(),
+            kind: GateKind::Command,
+            run: "echo unit >> runs.txt".to_string(),
+            timeout_secs: None,
+        }],
+        ..GatesConfig::default()
+    };
+    let cache = TreeCache::for_tree(db.pool().clone(), repo, WORKSPACE_PATH);
+    run_pipeline(&config, dir, cache.as_ref())
+        .await
+        .expect("gates should run")
+}
+
+#[tokio::test]
+async fn test_rerun_after_failed_merge_reuses_cached_gates() {
+    let repo = repo_with_session();
+    repo.write_file(WORKSPACE, "feature.rs", "fn feature() {}\n")
+        .expect("write");
+    repo.fail_next(
+        FakeOp::Squash,
+        FakeFailure::Error("concurrent modification".to_string()),
+    );
+    let state = tempfile::tempdir().expect("tempdir");
+    let db = SessionDb::create_or_open(&state.path().join("state.db"))
+        .await
+        .expect("open state.db");
+    let working_copy = repo.working_copy(WORKSPACE).expect("working copy");
+
+    let first = run_gates(&repo, &db, state.path()).await;
+    let (_root, result) = run_done(&repo, &options(true)).await;
+    let rerun = run_gates(&repo, &db, state.path()).await;
+
+    assert!(matches!(result, Err(DoneError::MergeFailed { .. })));
+    assert_ne!(
+        repo.working_copy(WORKSPACE)
+            .expect("working copy")
+            .commit_id,
+        working_copy.commit_id,
+        "done should have committed the working copy before failing"
+    );
+    assert!(!first.results[0].cached);
+    assert!(rerun.results[0].cached);
+    assert_eq!(
+        std::fs::read_to_string(state.path().join("runs.txt")).expect("read runs"),
+        "unit\n"
+    );
+}
+
+#[test]
+fn test_failed_gates_report_summary_and_results_as_json() {
+    let outcome = isolate_core::collect_results(vec![
+        GateResult::passed("lint", String::new(), String::new()),
+        GateResult::failed("test", 1, String::new(), "2 tests failed".to_string()),
+    ]);
+    let error = DoneError::GatesFailed {
+        summary: format_failure_message(&outcome),
+        gates: outcome.results,
+    };
+
+    let json = serde_json::to_value(gates_failure_envelope(&error)).expect("serialize envelope");
+
+    assert_eq!(json["success"], false);
+    assert_eq!(json["error"]["code"], "GATES_FAILED");
+    assert_eq!(json["error"]["exit_code"], 3);
+    assert_eq!(
+        json["error"]["details"]["summary"],
+        "Test gate failed (exit code 1): 2 tests failed"
+    );
+    let gates = json["error"]["details"]["gates"]
+        .as_array()
+        .expect("gate results");
+    assert_eq!(gates.len(), 2);
+    assert_eq!(gates[1]["gate"], "test");
+    assert_eq!(gates[1]["passed"], false);
+}
+
+#[test]
+fn test_gates_that_cannot_run_are_not_a_red_gate() {
+    let error = DoneError::GatesUnavailable {
+        reason: "moon: command not found".to_string(),
+    };
+
+    assert_eq!(error.error_code(), "GATES_UNAVAILABLE");
+    assert!(!error.is_recoverable());
+}
@@ -4,14 +4,16 @@
 
 use std::fmt;
 
-use isolate_core::OutputFormat;
+use isolate_core::{GateResult, OutputFormat};
 use serde::{Deserialize, Serialize};
 
 use super::conflict::ConflictDetectionResult;
+use crate::commands::queue::types::QueueEntryOutput;
 
 /// CLI arguments for done command (parsed in main.rs)
 #[derive(Debug, Clone)]
-#[expect(clippy::struct_excessive_bools)] // CLI flags: >3 bools is appropriate for independent options
+#[expect(clippy::struct_excessive_bools)] // CLI flags: >3 bools is appropriate for independent
+                                          // options
 pub struct DoneArgs {
     /// Workspace to complete
     pub workspace: Option<String>,
@@ -37,6 +39,12 @@ pub struct DoneArgs {
     /// Skip bead status update
     pub no_bead_update: bool,
 
+    /// Enqueue for the merge queue instead of merging directly
+    pub queue: bool,
+
+    /// Merge queue priority (only with `queue`)
+    pub priority: Option<u32>,
+
     /// Output format
     pub format: OutputFormat,
 }
@@ -53,6 +61,8 @@ impl DoneArgs {
             dry_run: self.dry_run,
             detect_conflicts: self.detect_conflicts,
             no_bead_update: self.no_bead_update,
+            queue: self.queue,
+            priority: self.priority,
             format: self.format,
         }
     }
@@ -70,6 +80,8 @@ pub struct DoneOptions {
     pub dry_run: bool,
     pub detect_conflicts: bool,
     pub no_bead_update: bool,
+    pub queue: bool,
+    pub priority: Option<u32>,
     pub format: OutputFormat,
 }
 
@@ -90,6 +102,12 @@ pub struct DoneOutput {
     pub pushed_to_remote: bool,
     pub dry_run: bool,
     pub preview: Option<DonePreview>,
+    /// Merge queue entry when `--queue` was used instead of merging
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queued: Option<QueueEntryOutput>,
+    /// Quality gates the session passed before landing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gates: Vec<GateResult>,
     pub error: Option<String>,
 }
 
@@ -139,6 +157,13 @@ pub enum DoneError {
     CommitFailed {
         reason: String,
     },
+    GatesFailed {
+        summary: String,
+        gates: Vec<GateResult>,
+    },
+    GatesUnavailable {
+        reason: String,
+    },
     MergeConflict {
         conflicts: Vec<String>,
     },
@@ -172,6 +197,12 @@ impl fmt::Display for DoneError {
                 write!(f, "Workspace '{workspace_name}' not found")
             }
             Self::CommitFailed { reason } => write!(f, "Failed to commit changes: {reason}"),
+            Self::GatesFailed { summary, .. } => {
+                write!(f, "Quality gates failed, refusing to land: {summary}")
+            }
+            Self::GatesUnavailable { reason } => {
+                write!(f, "Could not run the quality gates: {reason}")
+            }
             Self::MergeConflict { conflicts } => {
                 write!(f, "Merge conflicts detected: {}", conflicts.join(", "))
             }
@@ -224,6 +255,8 @@ impl DoneError {
             Self::NotAJjRepo => "NOT_A_JJ_REPO",
             Self::WorkspaceNotFound { .. } => "WORKSPACE_NOT_FOUND",
             Self::CommitFailed { .. } => "COMMIT_FAILED",
+            Self::GatesFailed { .. } => "GATES_FAILED",
+            Self::GatesUnavailable { .. } => "GATES_UNAVAILABLE",
             Self::MergeConflict { .. } => "MERGE_CONFLICT",
             Self::MergeFailed { .. } => "MERGE_FAILED",
             Self::CleanupFailed { .. } => "CLEANUP_FAILED",
@@ -235,7 +268,7 @@ impl DoneError {
 
     #[allow(dead_code)]
     pub const fn is_recoverable(&self) -> bool {
-        matches!(self, Self::MergeConflict { .. })
+        matches!(self, Self::MergeConflict { .. } | Self::GatesFailed { .. })
     }
 
     #[allow(dead_code)] // Public API method, tested but not used internally
@@ -244,6 +277,7 @@ impl DoneError {
             Self::NotInWorkspace { .. } | Self::NotAJjRepo | Self::WorkspaceNotFound { .. } => {
                 DonePhase::ValidatingLocation
             }
+            Self::GatesFailed { .. } | Self::GatesUnavailable { .. } => DonePhase::RunningGates,
             Self::CommitFailed { .. } => DonePhase::CommittingChanges,
             Self::MergeConflict { .. }
             | Self::MergeFailed { .. }
@@ -260,6 +294,8 @@ impl DoneError {
 pub enum DonePhase {
     /// Initial validation phase (checking workspace location)
     ValidatingLocation,
+    /// Quality gate phase (running the configured gates)
+    RunningGates,
     /// Commit phase (committing uncommitted changes)
     CommittingChanges,
     /// Merge and cleanup phase (merging to main, cleanup, bead update)
@@ -273,6 +309,7 @@ impl DonePhase {
     pub const fn name(&self) -> &'static str {
         match self {
             Self::ValidatingLocation => "validating_location",
+            Self::RunningGates => "running_gates",
             Self::CommittingChanges => "committing_changes",
             Self::MergingToMain => "merging_to_main",
         }
@@ -294,6 +331,8 @@ mod tests {
             dry_run: false,
             detect_conflicts: false,
             no_bead_update: false,
+            queue: true,
+            priority: Some(10),
             format: OutputFormat::Json,
         };
 
@@ -305,12 +344,15 @@ mod tests {
         assert!(!opts.no_keep);
         assert!(!opts.squash);
         assert!(!opts.dry_run);
+        assert!(opts.queue);
+        assert_eq!(opts.priority, Some(10));
         assert!(matches!(opts.format, OutputFormat::Json));
     }
 
     #[test]
     fn test_done_phase_names() {
         assert_eq!(DonePhase::ValidatingLocation.name(), "validating_location");
+        assert_eq!(DonePhase::RunningGates.name(), "running_gates");
         assert_eq!(DonePhase::CommittingChanges.name(), "committing_changes");
         assert_eq!(DonePhase::MergingToMain.name(), "merging_to_main");
     }
@@ -329,6 +371,14 @@ mod tests {
         };
         assert_eq!(err2.error_code(), "WORKSPACE_NOT_FOUND");
         assert_eq!(err2.phase(), DonePhase::ValidatingLocation);
+
+        let err3 = DoneError::GatesFailed {
+            summary: "Test gate failed (exit code 1): 2 tests failed".to_string(),
+            gates: Vec::new(),
+        };
+        assert_eq!(err3.error_code(), "GATES_FAILED");
+        assert_eq!(err3.phase(), DonePhase::RunningGates);
+        assert!(err3.to_string().contains("Test gate failed (exit code 1)"));
     }
 
     #[test]
@@ -359,6 +409,8 @@ mod tests {
             pushed_to_remote: false,
             dry_run: false,
             preview: None,
+            queued: None,
+            gates: Vec::new(),
             error: None,
         };
 
@@ -41,6 +41,9 @@ pub enum EventType {
     CheckpointCreated,
     CheckpointRestored,
     BeadStatusChanged,
+    QueueEntryAdded,
+    QueueEntryRemoved,
+    QueueEntryStatusChanged,
 }
 
 impl std::fmt::Display for EventType {
@@ -285,7 +288,7 @@ fn event_type_matches(filter: Option<&str>, event_type: &EventType) -> bool {
 
     matches!(
         normalized_filter.as_str(),
-        "session" | "agent" | "lock" | "checkpoint" | "bead"
+        "session" | "agent" | "lock" | "checkpoint" | "bead" | "queue"
     ) && canonical.starts_with(&format!("{normalized_filter}_"))
 }
 
@@ -317,8 +320,6 @@ pub async fn _log_event(
     agent_id: Option<&str>,
     message: &str,
 ) -> Result<()> {
-    use tokio::io::AsyncWriteExt;
-
     let event = Event {
         id: _generate_event_id(),
         event_type,
@@ -329,11 +330,22 @@ pub async fn _log_event(
         message: message.to_string(),
     };
 
+    append_event(&event).await
+}
+
+/// Append any serializable event record to the events log
+///
+/// Used for events built elsewhere, such as the `isolate_core::Event`s the
+/// merge queue processor emits. Records must serialize with the same field
+/// names as [`Event`] to be readable by `isolate events`.
+pub async fn append_event<T: Serialize + Sync>(event: &T) -> Result<()> {
+    use tokio::io::AsyncWriteExt;
+
     // Get events file path
     let events_file = get_events_file_path().await?;
 
     // Serialize event
-    let event_json = serde_json::to_string(&event)
+    let event_json = serde_json::to_string(event)
         .map_err(|e| anyhow::anyhow!("Failed to serialize event: {e}"))?;
 
     // Open file for appending
@@ -409,6 +421,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_core_queue_event_is_readable() -> Result<(), Box<dyn std::error::Error>> {
+        let core = isolate_core::Event::new(
+            isolate_core::EventType::QueueEntryStatusChanged,
+            "feature: testing -> ready_to_merge".to_string(),
+        )
+        .with_session("feature");
+
+        let line = serde_json::to_string(&core)?;
+        let event: Event = serde_json::from_str(&line)?;
+        assert!(matches!(
+            event.event_type,
+            EventType::QueueEntryStatusChanged
+        ));
+        assert_eq!(event.session.as_deref(), Some("feature"));
+        assert!(event_type_matches(Some("queue"), &event.event_type));
+        Ok(())
+    }
+
     #[test]
     fn test_event_types_are_snake_case() -> Result<(), Box<dyn std::error::Error>> {
         let types = vec![
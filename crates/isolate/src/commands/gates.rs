@@ -0,0 +1,357 @@
+//! Quality gates - run the configured gate pipeline in a workspace
+//!
+//! `isolate done`, `isolate submit` and the merge queue run the gates listed
+//! under `[gates]` before anything lands. Gates run in order in the session's
+//! workspace; each is a moon task or a shell command and is killed, along
+//! with every process it started, when its timeout passes. With `fail_fast`
+//! (the default) the first failing gate ends the run, otherwise every gate
+//! runs and all failures are reported.
+//!
+//! Passes are cached in `state.db` by the workspace's tree id and the gate
+//! definition, so rerunning `done` on an unchanged session skips gates that
+//! already passed and reports them as cached. The tree id depends only on
+//! the files, so committing the working copy, as a `done` that later failed
+//! to merge did, keeps the cache.
+
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![forbid(unsafe_code)]
+
+use std::{path::Path, process::Stdio, time::Duration};
+
+use anyhow::Result;
+use isolate_core::{
+    collect_results,
+    config::{GateDefinition, GateKind, GatesConfig},
+    coordination::GateCache,
+    GateError, GateResult, GatesOutcome, VcsBackend,
+};
+use sqlx::SqlitePool;
+use tokio::process::Command;
+
+use crate::commands::{get_session_db, in_own_process_group, kill_process_group};
+
+/// Passing gate results recorded for the tree a pipeline runs against
+#[derive(Debug, Clone)]
+pub struct TreeCache {
+    cache: GateCache,
+    tree_id: String,
+}
+
+impl TreeCache {
+    /// Cache for the tree checked out in `dir`
+    ///
+    /// `None` when the tree has no id to key on, such as a git worktree with
+    /// uncommitted changes; the gates then simply run.
+    ///
+    /// The backend runs `jj` or `git`, so it is asked off the async runtime.
+    pub async fn for_workspace(pool: SqlitePool, dir: &Path) -> Option<Self> {
+        let backend = isolate_core::backend_for(isolate_core::discover_backend(dir)?);
+        let path = dir.to_string_lossy().to_string();
+        tokio::task::spawn_blocking(move || Self::for_tree(pool, backend.as_ref(), &path))
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Not caching gate results for {}: {e}", dir.display());
+                None
+            })
+    }
+
+    /// Cache keyed on the tree `backend` reports at `path`
+    pub fn for_tree(pool: SqlitePool, backend: &dyn VcsBackend, path: &str) -> Option<Self> {
+        match backend.tree_id(path) {
+            Ok(tree_id) => tree_id.map(|tree_id| Self {
+                cache: GateCache::new(pool),
+                tree_id,
+            }),
+            Err(e) => {
+                tracing::warn!("Not caching gate results for {path}: {e}");
+                None
+            }
+        }
+    }
+
+    async fn lookup(&self, gate: &GateDefinition) -> Option<GateResult> {
+        self.cache
+            .lookup(&self.tree_id, gate)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!("Failed to read cached result of gate '{}': {e}", gate.name);
+                None
+            })
+    }
+
+    async fn record(&self, gate: &GateDefinition, result: &GateResult) {
+        if let Err(e) = self.cache.record(&self.tree_id, gate, result).await {
+            tracing::warn!("Failed to cache result of gate '{}': {e}", gate.name);
+        }
+    }
+}
+
+/// Run the gates configured for this repository in `dir`, reusing passes
+/// cached for its tree
+pub async fn run_configured(dir: &Path) -> Result<GatesOutcome> {
+    let config = isolate_core::config::load_config().await?;
+    let cache = match get_session_db().await {
+        Ok(db) => TreeCache::for_workspace(db.pool().clone(), dir).await,
+        Err(_) => None,
+    };
+
+    run_pipeline(&config.gates, dir, cache.as_ref())
+        .await
+        .map_err(anyhow::Error::new)
+}
+
+/// Run every gate of `config` in `dir`, in order
+///
+/// A gate that fails or times out is a failed result, not an error; errors
+/// mean a gate could not be started at all. Gates `cache` holds a pass for
+/// are skipped and reported with their cached result; fresh passes are added
+/// to the cache.
+pub async fn run_pipeline(
+    config: &GatesConfig,
+    dir: &Path,
+    cache: Option<&TreeCache>,
+) -> Result<GatesOutcome, GateError> {
+    if !config.steps.is_empty() && !tokio::fs::try_exists(dir).await.unwrap_or(false) {
+        return Err(GateError::WorkingDirectoryNotFound(
+            dir.display().to_string(),
+        ));
+    }
+
+    let mut results = Vec::with_capacity(config.steps.len());
+    for gate in &config.steps {
+        let result = if let Some(cached) = cached_pass(cache, gate).await {
+            cached
+        } else {
+            let result = run_gate(gate, config.timeout_for(gate), dir).await?;
+            if let Some(cache) = cache {
+                cache.record(gate, &result).await;
+            }
+            result
+        };
+        let failed = !result.passed;
+        results.push(result);
+        if failed && config.fail_fast {
+            break;
+        }
+    }
+
+    Ok(collect_results(results))
+}
+
+async fn cached_pass(cache: Option<&TreeCache>, gate: &GateDefinition) -> Option<GateResult> {
+    let cache = cache?;
+    cache.lookup(gate).await
+}
+
+/// Names of passed gates for human output, marking the ones reused from
+/// the cache
+pub fn describe_passed(results: &[GateResult]) -> String {
+    results
+        .iter()
+        .map(|result| {
+            if result.cached {
+                format!("{} (cached)", result.gate)
+            } else {
+                result.gate.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Run one gate in `dir`, killing it and everything it started after
+/// `timeout`
+async fn run_gate(
+    gate: &GateDefinition,
+    timeout: Duration,
+    dir: &Path,
+) -> Result<GateResult, GateError> {
+    let mut command = match gate.kind {
+        GateKind::Moon => {
+            let mut command = Command::new("moon");
+            command.args(["run", &gate.run]);
+            command
+        }
+        GateKind::Command => {
+            let mut command = Command::new("sh");
+            command.args(["-c", &gate.run]);
+            command
+        }
+    };
+    command
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    in_own_process_group(&mut command);
+
+    let execution_failed = |e: std::io::Error| {
+        if gate.kind == GateKind::Moon && e.kind() == std::io::ErrorKind::NotFound {
+            GateError::MoonNotFound
+        } else {
+            GateError::ExecutionFailed {
+                gate: gate.name.clone(),
+                reason: e.to_string(),
+            }
+        }
+    };
+    let child = command.spawn().map_err(execution_failed)?;
+    let pid = child.id();
+
+    let Ok(output) = tokio::time::timeout(timeout, child.wait_with_output()).await else {
+        // The build or test processes a gate starts would outlive `sh`
+        if let Some(pid) = pid {
+            kill_process_group(pid).await;
+        }
+        return Ok(GateResult::timed_out(&gate.name, timeout));
+    };
+    let output = output.map_err(execution_failed)?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    Ok(if output.status.success() {
+        GateResult::passed(&gate.name, stdout, stderr)
+    } else {
+        GateResult::failed(
+            &gate.name,
+            output.status.code().unwrap_or(-1),
+            stdout,
+            stderr,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use isolate_core::{format_failure_message, GatesStatus};
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::db::SessionDb;
+
+    fn gate(name: &str, run: &str) -> GateDefinition {
+        GateDefinition {
+            name: name.to_string(),
+            kind: GateKind::Command,
+            run: run.to_string(),
+            timeout_secs: None,
+        }
+    }
+
+    fn pipeline(fail_fast: bool, steps: Vec<GateDefinition>) -> GatesConfig {
+        GatesConfig {
+            fail_fast,
+            steps,
+            ..GatesConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gates_run_in_order_in_the_workspace() -> anyhow::Result<()> {
+        let dir = TempDir::new()?;
+        let config = pipeline(
+            true,
+            vec![
+                gate("first", "echo first > order.txt"),
+                gate("second", "echo second >> order.txt"),
+            ],
+        );
+
+        let outcome = run_pipeline(&config, dir.path(), None).await?;
+
+        assert_eq!(outcome.status, GatesStatus::AllPassed);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("order.txt"))?,
+            "first\nsecond\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_skips_later_gates() -> anyhow::Result<()> {
+        let dir = TempDir::new()?;
+        let steps = vec![
+            gate("lint", "echo 'error: unused import' >&2; exit 3"),
+            gate("unit", "touch ran.txt"),
+        ];
+
+        let outcome = run_pipeline(&pipeline(true, steps.clone()), dir.path(), None).await?;
+
+        assert_eq!(outcome.results.len(), 1);
+        assert!(!dir.path().join("ran.txt").exists());
+        assert_eq!(
+            format_failure_message(&outcome),
+            "Lint gate failed (exit code 3): error: unused import"
+        );
+
+        let outcome = run_pipeline(&pipeline(false, steps), dir.path(), None).await?;
+
+        assert_eq!(outcome.status, GatesStatus::Failed);
+        assert_eq!(outcome.results.len(), 2);
+        assert!(dir.path().join("ran.txt").exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gate_is_killed_at_its_timeout() -> anyhow::Result<()> {
+        let dir = TempDir::new()?;
+        let mut slow = gate("slow", "sleep 30 & echo $! > child.pid; wait");
+        slow.timeout_secs = Some(1);
+
+        let outcome = run_pipeline(&pipeline(true, vec![slow]), dir.path(), None).await?;
+
+        assert_eq!(outcome.status, GatesStatus::Failed);
+        assert_eq!(outcome.results[0].summary, "Timed out after 1s");
+        let child = std::fs::read_to_string(dir.path().join("child.pid"))?;
+        assert!(
+            crate::commands::process_exited(child.trim()).await,
+            "gate process {} still running",
+            child.trim()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_passes_are_reused_for_the_same_tree() -> anyhow::Result<()> {
+        let dir = TempDir::new()?;
+        let db = SessionDb::create_or_open(&dir.path().join("state.db")).await?;
+        let cache = TreeCache {
+            cache: GateCache::new(db.pool().clone()),
+            tree_id: "tree-1".to_string(),
+        };
+        let config = pipeline(
+            false,
+            vec![
+                gate("unit", "echo unit >> runs.txt"),
+                gate("flaky", "echo flaky >> runs.txt; exit 1"),
+            ],
+        );
+
+        let first = run_pipeline(&config, dir.path(), Some(&cache)).await?;
+        let second = run_pipeline(&config, dir.path(), Some(&cache)).await?;
+
+        assert!(!first.results[0].cached);
+        assert!(second.results[0].cached);
+        assert!(second.results[0].passed);
+        assert!(!second.results[1].cached);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("runs.txt"))?,
+            "unit\nflaky\nflaky\n"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_missing_workspace_is_an_error() {
+        let config = pipeline(true, vec![gate("lint", "true")]);
+
+        let result = run_pipeline(&config, Path::new("/nonexistent/workspace"), None).await;
+
+        assert!(matches!(
+            result,
+            Err(GateError::WorkingDirectoryNotFound(_))
+        ));
+    }
+}
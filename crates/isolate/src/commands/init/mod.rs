@@ -10,7 +10,7 @@ use std::{
 
 use anyhow::{bail, Context, Result};
 use fs4::fs_std::FileExt;
-use isolate_core::{json::SchemaEnvelope, OutputFormat};
+use isolate_core::{json::SchemaEnvelope, BackendType, GitBackend, OutputFormat};
 
 use crate::db::SessionDb;
 
@@ -102,8 +102,8 @@ impl Drop for InitLock {
 
 use deps::{check_dependencies, ensure_jj_repo_with_cwd, jj_root_with_cwd};
 use setup::{
-    create_agents_md, create_claude_md, create_docs, create_jj_hooks, create_jjignore,
-    create_moon_pipeline, create_repo_ai_instructions, DEFAULT_CONFIG,
+    create_agents_md, create_claude_md, create_docs, create_git_exclude, create_jj_hooks,
+    create_jjignore, create_moon_pipeline, create_repo_ai_instructions, DEFAULT_CONFIG,
 };
 use types::{build_init_response, InitPaths, InitResponse};
 
@@ -141,8 +141,13 @@ pub async fn run_with_cwd_and_options(cwd: Option<&Path>, options: InitOptions)
         None => std::env::current_dir().context("Failed to get current directory")?,
     };
 
+    // Plain git repositories are not converted; sessions use git worktrees
+    let git_only = isolate_core::discover_backend(&cwd) == Some(BackendType::Git);
+
     // Check required dependencies
-    check_dependencies().await?;
+    if !git_only {
+        check_dependencies().await?;
+    }
 
     if options.dry_run {
         println!("Would initialize Isolate in {}", cwd.display());
@@ -156,11 +161,17 @@ pub async fn run_with_cwd_and_options(cwd: Option<&Path>, options: InitOptions)
         return Ok(());
     }
 
-    // Initialize JJ repo if needed
-    ensure_jj_repo_with_cwd(&cwd, options.format.is_json()).await?;
-
     // Get the repo root using the provided cwd
-    let root = jj_root_with_cwd(&cwd).await?;
+    let root = if git_only {
+        GitBackend::new()
+            .main_worktree_root(&cwd.to_string_lossy())
+            .map(PathBuf::from)
+            .context("Failed to find git repository root")?
+    } else {
+        // Initialize JJ repo if needed
+        ensure_jj_repo_with_cwd(&cwd, options.format.is_json()).await?;
+        jj_root_with_cwd(&cwd).await?
+    };
     let isolate_dir = root.join(".isolate");
 
     // Create .isolate directory early so lock file can be created
@@ -195,7 +206,7 @@ pub async fn run_with_cwd_and_options(cwd: Option<&Path>, options: InitOptions)
                 state_db: ".isolate/state.db".to_string(),
                 layouts: ".isolate/layouts/".to_string(),
             },
-            jj_initialized: true,
+            jj_initialized: !git_only,
             already_initialized: true,
         };
 
@@ -223,11 +234,16 @@ pub async fn run_with_cwd_and_options(cwd: Option<&Path>, options: InitOptions)
             .context("Failed to create layouts directory")?;
     }
 
-    // Create .jjignore to prevent .isolate tracking (avoids nested .jj conflicts)
-    create_jjignore(&root).await?;
+    if git_only {
+        // Keep .isolate out of git status without touching tracked files
+        create_git_exclude(&root).await?;
+    } else {
+        // Create .jjignore to prevent .isolate tracking (avoids nested .jj conflicts)
+        create_jjignore(&root).await?;
 
-    // Create JJ hooks to enforce isolate workflow (agents can't bypass with --no-verify)
-    create_jj_hooks(&root).await?;
+        // Create JJ hooks to enforce isolate workflow (agents can't bypass with --no-verify)
+        create_jj_hooks(&root).await?;
+    }
 
     // Create repo-level AI discoverability file
     create_repo_ai_instructions(&root).await?;
@@ -256,7 +272,10 @@ pub async fn run_with_cwd_and_options(cwd: Option<&Path>, options: InitOptions)
     _lock.release()?;
 
     if options.format.is_json() {
-        let response = build_init_response(&root, false);
+        let response = InitResponse {
+            jj_initialized: !git_only,
+            ..build_init_response(&root, false)
+        };
         let envelope = SchemaEnvelope::new("init-response", "single", response);
         println!("{}", serde_json::to_string(&envelope)?);
     } else {
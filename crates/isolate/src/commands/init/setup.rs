@@ -153,6 +153,40 @@ pub(super) async fn create_jjignore(repo_root: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Add `.isolate/` to `.git/info/exclude` in a plain git repository
+///
+/// The exclude file is local to the clone, so nothing tracked changes.
+pub(super) async fn create_git_exclude(repo_root: &Path) -> Result<()> {
+    let info_dir = repo_root.join(".git/info");
+    let exclude_path = info_dir.join("exclude");
+    let isolate_pattern = ".isolate/";
+
+    tokio::fs::create_dir_all(&info_dir)
+        .await
+        .context("Failed to create .git/info directory")?;
+
+    let content = match tokio::fs::read_to_string(&exclude_path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).context("Failed to read .git/info/exclude"),
+    };
+
+    if content.lines().any(|line| line.trim() == isolate_pattern) {
+        return Ok(());
+    }
+
+    let mut new_content = content;
+    if !new_content.is_empty() && !new_content.ends_with('\n') {
+        new_content.push('\n');
+    }
+    new_content.push_str(isolate_pattern);
+    new_content.push('\n');
+
+    tokio::fs::write(&exclude_path, new_content)
+        .await
+        .context("Failed to update .git/info/exclude")
+}
+
 /// Create JJ hooks to enforce isolate workflow
 ///
 /// Unlike git hooks, JJ hooks CANNOT be bypassed with --no-verify.
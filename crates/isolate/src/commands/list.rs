@@ -20,8 +20,7 @@ use isolate_core::{
 
 use crate::{
     beads::{BeadRepository, BeadStatus},
-    cli::jj_root,
-    commands::get_session_db,
+    commands::{check_prerequisites, get_session_db},
     session::{Session, SessionStatus},
 };
 
@@ -257,7 +256,7 @@ pub async fn run(
 
 /// Get beads count from the repository's beads database
 async fn get_beads_count() -> Result<BeadCounts> {
-    let root = jj_root().await.ok();
+    let root = check_prerequisites().await.ok();
     let Some(root) = root else {
         return Ok(BeadCounts::default());
     };
@@ -21,16 +21,20 @@ pub mod done;
 pub mod events;
 pub mod examples;
 pub mod export_import;
+pub mod gates;
 pub mod init;
 pub mod integrity;
 pub mod introspect;
 pub mod list;
 pub mod lock;
+pub mod pipeline;
 pub mod prune_invalid;
 pub mod query;
+pub mod queue;
 pub mod recover;
 pub mod remove;
 pub mod rename;
+pub mod restack;
 pub mod revert;
 pub mod schema;
 pub mod session_command;
@@ -57,6 +61,7 @@ pub mod workspace_utils;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
+use isolate_core::{vcs::VcsResult, BackendType, GitBackend, VcsBackend, VcsError};
 use tokio::process::Command;
 
 use crate::{
@@ -95,16 +100,142 @@ pub async fn check_in_jj_repo() -> Result<PathBuf> {
     })
 }
 
+/// VCS backend commands use to read and change the repository
+///
+/// All repository access from commands goes through this, so tests can pass
+/// their own `VcsBackend` instead. The backend follows the repository around
+/// the current directory: plain git repositories get git worktrees.
+#[must_use]
+pub fn vcs_backend() -> Box<dyn VcsBackend> {
+    isolate_core::backend_for(current_backend_type())
+}
+
+/// Run `op` against [`vcs_backend`] on the blocking thread pool
+///
+/// Backends wait on `jj` and `git` processes, which would stall a runtime
+/// worker if called from async code directly.
+///
+/// # Errors
+///
+/// Returns the operation's error, or `VcsError::OperationFailed` if the
+/// blocking task panicked
+pub async fn run_vcs<T, F>(op: F) -> VcsResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn VcsBackend) -> VcsResult<T> + Send + 'static,
+{
+    let backend = vcs_backend();
+    tokio::task::spawn_blocking(move || op(backend.as_ref()))
+        .await
+        .map_err(|e| VcsError::OperationFailed(format!("VCS task failed: {e}")))?
+}
+
+/// Start `command` as the leader of a new process group
+///
+/// Everything the command starts joins that group, so
+/// [`kill_process_group`] can stop the whole tree rather than the one
+/// process `kill_on_drop` reaches.
+pub fn in_own_process_group(command: &mut Command) -> &mut Command {
+    #[cfg(unix)]
+    command.process_group(0);
+    command
+}
+
+/// Kill every process in the group led by `pid`
+///
+/// Groups whose processes have all exited are ignored.
+pub async fn kill_process_group(pid: u32) {
+    let _ = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{pid}")])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .await;
+}
+
+/// Wait up to five seconds for `pid` to exit
+///
+/// A killed process whose parent never reaps it stays a zombie, so zombies
+/// count as exited where `/proc` shows them.
+#[cfg(test)]
+pub(crate) async fn process_exited(pid: &str) -> bool {
+    for _ in 0..50 {
+        let running = match std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+            Ok(stat) => !stat
+                .rsplit(')')
+                .next()
+                .is_some_and(|fields| fields.trim_start().starts_with('Z')),
+            Err(_) if Path::new("/proc/self").exists() => false,
+            Err(_) => Command::new("kill")
+                .args(["-0", pid])
+                .stderr(std::process::Stdio::null())
+                .status()
+                .await
+                .is_ok_and(|status| status.success()),
+        };
+        if !running {
+            return true;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    false
+}
+
+/// Backend of the repository around the current directory, JJ if there is none
+#[must_use]
+pub fn current_backend_type() -> BackendType {
+    std::env::current_dir()
+        .ok()
+        .and_then(|dir| isolate_core::discover_backend(&dir))
+        .unwrap_or(BackendType::Jj)
+}
+
+/// Whether the current directory is in a git repository that has no JJ repo
+#[must_use]
+pub fn in_git_only_repo() -> bool {
+    current_backend_type() == BackendType::Git
+}
+
+/// Refuse a JJ-only feature in a plain git repository
+///
+/// # Errors
+///
+/// Returns a VCS error naming `feature` when the repository has no JJ repo
+pub fn require_jj(feature: &str) -> Result<()> {
+    if in_git_only_repo() {
+        Err(anyhow::Error::new(isolate_core::Error::from(
+            VcsError::RequiresJj(feature.to_string()),
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+/// Run a git backend query against the current directory
+fn git_query(query: impl FnOnce(&GitBackend, &str) -> VcsResult<String>) -> Result<PathBuf> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    query(&GitBackend::new(), &cwd.to_string_lossy())
+        .map(PathBuf::from)
+        .map_err(|e| anyhow::Error::new(isolate_core::Error::from(e)))
+}
+
 /// Check prerequisites before executing JJ commands
 ///
 /// This ensures:
 /// 1. JJ binary is installed
 /// 2. We're inside a JJ repository
 ///
+/// Plain git repositories need neither; the root of the current worktree is
+/// returned instead.
+///
 /// # Errors
 ///
 /// Returns an error with helpful messages if prerequisites are not met
 pub async fn check_prerequisites() -> Result<PathBuf> {
+    if in_git_only_repo() {
+        return git_query(VcsBackend::workspace_root);
+    }
+
     // First check if JJ is installed
     check_jj_installed().await?;
 
@@ -113,6 +244,11 @@ pub async fn check_prerequisites() -> Result<PathBuf> {
 }
 
 async fn isolate_project_root() -> Result<PathBuf> {
+    if in_git_only_repo() {
+        // Linked worktrees share the state of the main worktree
+        return git_query(GitBackend::main_worktree_root);
+    }
+
     let root = check_prerequisites().await?;
     let workspace_repo_pointer = root.join(".jj").join("repo");
 
@@ -183,6 +319,10 @@ pub async fn get_session_db() -> Result<SessionDb> {
 /// Falls back to "main" if unable to detect.
 #[allow(dead_code)] // Used in sync.rs via re-export
 pub async fn determine_main_branch(workspace_path: &Path) -> String {
+    if isolate_core::discover_backend(workspace_path) == Some(BackendType::Git) {
+        return GitBackend::new().main_branch(&workspace_path.to_string_lossy());
+    }
+
     let output = Command::new("jj")
         .args(["log", "-r", "trunk()", "--no-graph", "-T", "commit_id"])
         .current_dir(workspace_path)
@@ -0,0 +1,258 @@
+//! Pipeline commands - drive specs through the orchestrator from the CLI
+//!
+//! A pipeline takes one spec through spec review, a twin universe, agent
+//! development in an isolate session and scenario validation, retrying the
+//! agent with sanitized feedback until the scenarios pass or it runs out of
+//! iterations. State lives in `.isolate/pipelines/state.db`, so an
+//! interrupted run can be picked up with `recover`. Runs go through the
+//! orchestrator's scheduler, so several pipelines develop at once.
+//!
+//! # Subcommands
+//!
+//! - `create` - Register a spec as a new pipeline
+//! - `run` - Run pipelines to a decision
+//! - `status` - Show one pipeline
+//! - `list` - Show every pipeline, optionally by state
+//! - `recover` - Resume pipelines a crash left mid-flight
+//! - `metrics` - Pipeline counts and averages, or Prometheus/CSV exports
+//! - `inbox` - Show escalated pipelines waiting on a human
+//! - `resume` / `accept` / `fail` - Settle an escalated pipeline
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+
+pub mod types;
+
+#[cfg(test)]
+mod tests;
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use orchestrator::{
+    escalation::{self, EscalationError},
+    metrics::export,
+    AgentDriver, Escalation, Pipeline, PipelineConfig, PipelineId, PipelineScheduler,
+    PipelineState, PolicyConfig, SchedulerConfig, StateStore,
+};
+
+use self::types::{
+    state_name, MetricsExport, PipelineCreateArgs, PipelineMetricsOutput, PipelineRunOptions,
+    PipelineRunOutcome, Settlement,
+};
+use crate::commands::isolate_data_dir;
+
+/// Directory pipeline state is kept in
+pub async fn state_dir() -> Result<PathBuf> {
+    Ok(isolate_data_dir().await?.join("pipelines"))
+}
+
+/// Open the pipeline store in `state_dir`
+pub fn open_store(state_dir: &Path) -> Result<StateStore> {
+    StateStore::new(state_dir.to_path_buf()).context("Failed to open pipeline state")
+}
+
+/// Start a scheduler on the store in `state_dir`, running with `options`
+///
+/// With `resume` it picks up every unfinished pipeline as it starts.
+pub fn start_scheduler(
+    state_dir: &Path,
+    options: &PipelineRunOptions,
+    resume: bool,
+) -> Result<PipelineScheduler> {
+    let mut config = SchedulerConfig::new(state_dir.to_path_buf(), options.scenarios.clone())
+        .with_concurrency(options.concurrency)
+        .with_resume(resume);
+    if let Some(specs) = &options.specs {
+        config = config.with_linter_path(specs.clone());
+    }
+    if let Some(agent) = options.agent.clone() {
+        config = config.with_agents(move || Box::new(agent.clone()) as Box<dyn AgentDriver>);
+    }
+    if let Some(policy) = &options.policy {
+        let policies = PolicyConfig::from_file(policy)
+            .with_context(|| format!("INVALID_POLICY: {}", policy.display()))?;
+        config = config.with_policies(policies);
+    }
+    PipelineScheduler::start(config)
+}
+
+/// Register the spec as a new pending pipeline
+pub fn run_create(args: &PipelineCreateArgs, store: &mut StateStore) -> Result<Pipeline> {
+    let spec = args.spec.canonicalize().with_context(|| {
+        format!(
+            "SPEC_NOT_FOUND: Spec '{}' does not exist",
+            args.spec.display()
+        )
+    })?;
+
+    let config = PipelineConfig {
+        max_iterations: args.max_iterations,
+        quality_threshold: args.quality_threshold,
+        ..PipelineConfig::default()
+    };
+    let pipeline = Pipeline::with_config(spec.to_string_lossy().into_owned(), &config);
+    Ok(store.create(pipeline)?)
+}
+
+/// Resolve a full pipeline id or a unique prefix of one
+pub fn resolve_id(store: &StateStore, id: &str) -> Result<PipelineId> {
+    let mut matches: Vec<&Pipeline> = store
+        .list()
+        .into_iter()
+        .filter(|p| p.id.0.starts_with(id))
+        .collect();
+
+    if let Some(exact) = matches.iter().find(|p| p.id.0 == id) {
+        return Ok(exact.id.clone());
+    }
+    match matches.len() {
+        0 => anyhow::bail!("PIPELINE_NOT_FOUND: Pipeline '{id}' does not exist"),
+        1 => Ok(matches.remove(0).id.clone()),
+        n => anyhow::bail!("AMBIGUOUS_PIPELINE_ID: '{id}' matches {n} pipelines"),
+    }
+}
+
+/// Run `ids` alongside anything the scheduler resumed, until each is
+/// accepted, escalated or failed
+///
+/// Outcomes come back oldest pipeline first. This blocks while agents and
+/// scenarios run, so callers on an async runtime should run it via
+/// `block_in_place`.
+pub fn run_scheduled(
+    scheduler: &PipelineScheduler,
+    ids: &[PipelineId],
+) -> Result<Vec<PipelineRunOutcome>> {
+    for id in ids {
+        scheduler.submit(id)?;
+    }
+
+    let runs = scheduler.wait_idle();
+    let store = scheduler.store();
+    let mut outcomes = runs
+        .into_iter()
+        .map(|run| {
+            let decision = run
+                .result
+                .with_context(|| format!("Pipeline {} did not finish", run.pipeline_id.0))?;
+            let pipeline = store.get(&run.pipeline_id)?.clone();
+            Ok(PipelineRunOutcome { decision, pipeline })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    outcomes.sort_by(|a, b| {
+        a.pipeline
+            .created_at
+            .cmp(&b.pipeline.created_at)
+            .then_with(|| a.pipeline.id.0.cmp(&b.pipeline.id.0))
+    });
+    Ok(outcomes)
+}
+
+/// Every pipeline, oldest first, optionally only those in `state`
+pub fn run_list(store: &StateStore, state: Option<PipelineState>) -> Vec<Pipeline> {
+    let mut pipelines: Vec<Pipeline> = store
+        .list()
+        .into_iter()
+        .filter(|p| state.is_none() || state == Some(p.state))
+        .cloned()
+        .collect();
+    pipelines.sort_by(|a, b| {
+        a.created_at
+            .cmp(&b.created_at)
+            .then_with(|| a.id.0.cmp(&b.id.0))
+    });
+    pipelines
+}
+
+/// Counts and averages over every stored pipeline
+#[allow(clippy::cast_precision_loss)]
+pub fn run_metrics(store: &StateStore) -> PipelineMetricsOutput {
+    let pipelines = store.list();
+    if pipelines.is_empty() {
+        return PipelineMetricsOutput::default();
+    }
+
+    let mut output = PipelineMetricsOutput {
+        total: pipelines.len(),
+        ..PipelineMetricsOutput::default()
+    };
+    for pipeline in &pipelines {
+        *output
+            .by_state
+            .entry(state_name(pipeline.state))
+            .or_default() += 1;
+    }
+
+    let iterations: u64 = pipelines.iter().map(|p| u64::from(p.iteration)).sum();
+    output.average_iterations = iterations as f64 / pipelines.len() as f64;
+
+    let finished = pipelines.iter().filter(|p| p.state.is_terminal()).count();
+    let accepted = pipelines
+        .iter()
+        .filter(|p| p.state == PipelineState::Accepted)
+        .count();
+    if finished > 0 {
+        output.acceptance_rate = accepted as f64 / finished as f64;
+    }
+    output
+}
+
+/// Metrics for pipelines created in the `weeks` weeks up to `now`, rendered
+/// as `export`
+pub fn run_export(
+    store: &StateStore,
+    export: MetricsExport,
+    weeks: u32,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let start = now - Duration::weeks(i64::from(weeks));
+    let rendered = match export {
+        MetricsExport::Prometheus => {
+            export::prometheus(&store.metrics_between(start, now)?.aggregated())
+        }
+        MetricsExport::Csv => {
+            export::csv(&store.aggregate_windows(start, now, Duration::weeks(1))?)
+        }
+    };
+    Ok(rendered)
+}
+
+/// Escalated pipelines waiting on a human, longest waiting first
+pub fn run_inbox(store: &StateStore) -> Vec<Escalation> {
+    escalation::inbox(store)
+}
+
+/// Who is settling an escalation: `author` if given, otherwise `$USER`
+pub fn resolve_author(author: Option<&str>) -> Result<String> {
+    author
+        .map(str::to_string)
+        .or_else(|| std::env::var("USER").ok())
+        .filter(|author| !author.trim().is_empty())
+        .ok_or_else(|| anyhow::anyhow!("NO_AUTHOR: No author provided. Set USER or use --author"))
+}
+
+/// Settle the escalated pipeline `id` as `author`, recording the decision
+pub fn run_settle(
+    store: &mut StateStore,
+    id: &str,
+    author: &str,
+    settlement: &Settlement,
+) -> Result<Pipeline> {
+    let id = resolve_id(store, id)?;
+    let settled = match settlement {
+        Settlement::Resume {
+            guidance,
+            iterations,
+        } => escalation::resume(store, &id, author, guidance, *iterations),
+        Settlement::Accept { note } => escalation::accept(store, &id, author, note.as_deref()),
+        Settlement::Fail { reason } => escalation::fail(store, &id, author, reason.as_deref()),
+    };
+    settled.map_err(|e| match e {
+        EscalationError::NotEscalated { .. } => anyhow::anyhow!("PIPELINE_NOT_ESCALATED: {e}"),
+        other => other.into(),
+    })
+}
@@ -0,0 +1,237 @@
+//! Tests for pipeline commands
+
+use std::path::PathBuf;
+
+use orchestrator::{Decision, PipelineId, PipelineState, StateStore};
+use tempfile::TempDir;
+
+use super::{
+    open_store, resolve_author, resolve_id, run_create, run_export, run_inbox, run_list,
+    run_metrics, run_scheduled, run_settle, start_scheduler,
+    types::{
+        MetricsExport, PipelineCreateArgs, PipelineRunOptions, PipelineRunOutcome, Settlement,
+    },
+};
+
+fn setup() -> (StateStore, TempDir) {
+    let temp = TempDir::new().unwrap();
+    let store = open_store(&temp.path().join("pipelines")).unwrap();
+    (store, temp)
+}
+
+/// Run `ids`, plus every unfinished pipeline with `resume`, then reopen the
+/// store to see what the runs left
+fn run(temp: &TempDir, ids: &[PipelineId], resume: bool) -> (Vec<PipelineRunOutcome>, StateStore) {
+    let state_dir = temp.path().join("pipelines");
+    let options = PipelineRunOptions {
+        scenarios: temp.path().join("scenarios"),
+        specs: None,
+        agent: None,
+        concurrency: 2,
+        policy: None,
+    };
+    let scheduler = start_scheduler(&state_dir, &options, resume).unwrap();
+    let outcomes = run_scheduled(&scheduler, ids).unwrap();
+    drop(scheduler);
+    (outcomes, open_store(&state_dir).unwrap())
+}
+
+/// A spec too thin to pass review, so runs end at the first phase
+fn create(store: &mut StateStore, temp: &TempDir, name: &str) -> String {
+    let spec = temp.path().join(format!("{name}.yaml"));
+    std::fs::write(&spec, format!("identity:\n  id: spec-{name}\n")).unwrap();
+    let args = PipelineCreateArgs {
+        spec,
+        max_iterations: 3,
+        quality_threshold: 80,
+    };
+    run_create(&args, store).unwrap().id.0
+}
+
+#[test]
+fn test_create_records_config_and_absolute_spec() {
+    let (mut store, temp) = setup();
+
+    let id = create(&mut store, &temp, "login");
+
+    let pipelines = run_list(&store, None);
+    assert_eq!(pipelines.len(), 1);
+    assert_eq!(pipelines[0].id.0, id);
+    assert_eq!(pipelines[0].max_iterations, 3);
+    assert!(PathBuf::from(&pipelines[0].spec_path).is_absolute());
+}
+
+#[test]
+fn test_create_rejects_missing_spec() {
+    let (mut store, temp) = setup();
+    let args = PipelineCreateArgs {
+        spec: temp.path().join("missing.yaml"),
+        max_iterations: 10,
+        quality_threshold: 80,
+    };
+
+    let result = run_create(&args, &mut store);
+
+    assert!(result.is_err_and(|e| e.to_string().starts_with("SPEC_NOT_FOUND")));
+}
+
+#[test]
+fn test_resolve_id_accepts_unique_prefix() {
+    let (mut store, temp) = setup();
+    let id = create(&mut store, &temp, "login");
+
+    assert_eq!(resolve_id(&store, &id).unwrap().0, id);
+    assert_eq!(resolve_id(&store, &id[..8]).unwrap().0, id);
+    assert!(
+        resolve_id(&store, "zzzz").is_err_and(|e| e.to_string().starts_with("PIPELINE_NOT_FOUND"))
+    );
+}
+
+#[test]
+fn test_resolve_id_rejects_ambiguous_prefix() {
+    let (mut store, temp) = setup();
+    create(&mut store, &temp, "login");
+    create(&mut store, &temp, "signup");
+
+    let result = resolve_id(&store, "");
+
+    assert!(result.is_err_and(|e| e.to_string().starts_with("AMBIGUOUS_PIPELINE_ID")));
+}
+
+#[test]
+fn test_resume_runs_unfinished_pipelines_oldest_first() {
+    let (mut store, temp) = setup();
+    let first = create(&mut store, &temp, "login");
+    let second = create(&mut store, &temp, "signup");
+    drop(store);
+
+    let (outcomes, _store) = run(&temp, &[], true);
+
+    let ids: Vec<&str> = outcomes.iter().map(|o| o.pipeline.id.0.as_str()).collect();
+    assert_eq!(ids, vec![first.as_str(), second.as_str()]);
+    assert!(outcomes.iter().all(|o| o.decision == Decision::Fail));
+    assert_eq!(run(&temp, &[], true).0.len(), 0);
+}
+
+#[test]
+fn test_list_filters_by_state() {
+    let (mut store, temp) = setup();
+    create(&mut store, &temp, "login");
+    let pending = create(&mut store, &temp, "signup");
+    let logout = create(&mut store, &temp, "logout");
+    let failed = resolve_id(&store, &logout).unwrap();
+    drop(store);
+
+    let (outcomes, store) = run(&temp, &[failed], false);
+    let ids: Vec<String> = run_list(&store, Some(PipelineState::Pending))
+        .into_iter()
+        .map(|p| p.id.0)
+        .collect();
+
+    assert_eq!(outcomes.len(), 1);
+    assert_eq!(ids.len(), 2);
+    assert!(ids.contains(&pending));
+    assert_eq!(run_list(&store, Some(PipelineState::Failed)).len(), 1);
+}
+
+#[test]
+fn test_metrics_count_states_and_acceptance() {
+    let (mut store, temp) = setup();
+    assert_eq!(run_metrics(&store).total, 0);
+    create(&mut store, &temp, "login");
+    let signup = create(&mut store, &temp, "signup");
+    let failed = resolve_id(&store, &signup).unwrap();
+    drop(store);
+
+    let (_outcomes, store) = run(&temp, &[failed], false);
+    let metrics = run_metrics(&store);
+
+    assert_eq!(metrics.total, 2);
+    assert_eq!(metrics.by_state.get("pending"), Some(&1));
+    assert_eq!(metrics.by_state.get("failed"), Some(&1));
+    assert!(metrics.acceptance_rate.abs() < f64::EPSILON);
+}
+
+#[test]
+fn test_export_includes_persisted_phases() {
+    let (mut store, temp) = setup();
+    let login = create(&mut store, &temp, "login");
+    let failed = resolve_id(&store, &login).unwrap();
+    drop(store);
+
+    let (_outcomes, store) = run(&temp, &[failed], false);
+    let now = chrono::Utc::now();
+    let prometheus = run_export(&store, MetricsExport::Prometheus, 4, now).unwrap();
+    let csv = run_export(&store, MetricsExport::Csv, 4, now).unwrap();
+
+    assert!(prometheus.contains("orchestrator_pipelines{outcome=\"failed\"} 1\n"));
+    assert!(prometheus.contains("orchestrator_phase_duration_seconds_avg{phase=\"spec_review\"}"));
+    assert_eq!(csv.lines().count(), 5);
+    assert!(csv.lines().last().unwrap().contains(",1,0,1,0,0,"));
+}
+
+/// A pipeline escalated after running out of iterations
+fn escalate(store: &mut StateStore, temp: &TempDir, name: &str) -> String {
+    let id = create(store, temp, name);
+    let mut pipeline = store.get(&PipelineId(id.clone())).unwrap().clone();
+    pipeline.state = PipelineState::Validation;
+    pipeline.iteration = 3;
+    pipeline.feedback = Some("Scenario 1: FAIL".to_string());
+    pipeline.transition_to(PipelineState::Escalated).unwrap();
+    pipeline.set_error("Max iterations reached".to_string());
+    store.update(pipeline).unwrap();
+    id
+}
+
+#[test]
+fn test_inbox_lists_escalations_with_feedback() {
+    let (mut store, temp) = setup();
+    create(&mut store, &temp, "pending");
+    let login = escalate(&mut store, &temp, "login");
+
+    let inbox = run_inbox(&store);
+
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox[0].pipeline_id.0, login);
+    assert_eq!(inbox[0].reason.as_deref(), Some("Max iterations reached"));
+    assert_eq!(inbox[0].feedback.as_deref(), Some("Scenario 1: FAIL"));
+}
+
+#[test]
+fn test_settle_resumes_by_prefix_and_records_author() {
+    let (mut store, temp) = setup();
+    let login = escalate(&mut store, &temp, "login");
+    let resume = Settlement::Resume {
+        guidance: "Hash passwords with argon2".to_string(),
+        iterations: 2,
+    };
+
+    let resumed = run_settle(&mut store, &login[..8], "alice", &resume).unwrap();
+
+    assert_eq!(resumed.state, PipelineState::AgentDevelopment);
+    assert_eq!(resumed.max_iterations, 5);
+    assert_eq!(resumed.reviews[0].author, "alice");
+    assert_eq!(run_inbox(&store).len(), 0);
+}
+
+#[test]
+fn test_settle_rejects_pipelines_not_escalated() {
+    let (mut store, temp) = setup();
+    let pending = create(&mut store, &temp, "pending");
+
+    let err = run_settle(
+        &mut store,
+        &pending,
+        "alice",
+        &Settlement::Accept { note: None },
+    )
+    .unwrap_err();
+
+    assert!(err.to_string().starts_with("PIPELINE_NOT_ESCALATED"));
+}
+
+#[test]
+fn test_resolve_author_prefers_explicit_author() {
+    assert_eq!(resolve_author(Some("alice")).unwrap(), "alice");
+    assert!(resolve_author(Some("  ")).is_err());
+}
@@ -0,0 +1,111 @@
+//! Types for the pipeline commands
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use isolate_core::output::{OutputLineError, PipelineOutput};
+use orchestrator::{Decision, Pipeline, PipelineState};
+use serde::Serialize;
+
+use crate::commands::spawn::SessionAgent;
+
+/// Arguments for `pipeline create`
+#[derive(Debug, Clone)]
+pub struct PipelineCreateArgs {
+    /// Spec file the pipeline develops
+    pub spec: PathBuf,
+    /// Agent iterations before escalating
+    pub max_iterations: u32,
+    /// Minimum spec quality score (0-100)
+    pub quality_threshold: u32,
+}
+
+/// How `pipeline run` and `pipeline recover` execute pipelines
+#[derive(Debug, Clone)]
+pub struct PipelineRunOptions {
+    /// Directory of scenario YAML files validating the agent's work
+    pub scenarios: PathBuf,
+    /// Specs directory with linter rules and schema (bundled if unset)
+    pub specs: Option<PathBuf>,
+    /// Agent to develop with; development is skipped without one
+    pub agent: Option<SessionAgent>,
+    /// Pipelines to run at once
+    pub concurrency: usize,
+    /// Retry and escalation policy file (default policy if unset)
+    pub policy: Option<PathBuf>,
+}
+
+/// How a human settles an escalated pipeline
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Settlement {
+    /// Back to agent development with guidance and more iterations
+    Resume { guidance: String, iterations: u32 },
+    /// Accept the work as it stands
+    Accept { note: Option<String> },
+    /// Fail the pipeline for good
+    Fail { reason: Option<String> },
+}
+
+/// Where a run left a pipeline
+#[derive(Debug, Clone)]
+pub struct PipelineRunOutcome {
+    pub decision: Decision,
+    pub pipeline: Pipeline,
+}
+
+impl PipelineRunOutcome {
+    /// Whether the pipeline's work was accepted
+    pub fn accepted(&self) -> bool {
+        self.decision == Decision::Accept
+    }
+}
+
+/// Pipeline counts and averages across the state store
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PipelineMetricsOutput {
+    pub total: usize,
+    /// Pipelines per state; states with none are omitted
+    pub by_state: BTreeMap<String, usize>,
+    /// Mean agent iterations over all pipelines
+    pub average_iterations: f64,
+    /// Accepted share of finished pipelines (0.0-1.0)
+    pub acceptance_rate: f64,
+}
+
+/// Format `pipeline metrics --export` writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsExport {
+    /// Prometheus text format, aggregated over the whole period
+    Prometheus,
+    /// CSV, one row per week of the period
+    Csv,
+}
+
+/// Snake-case state name as stored, e.g. `agent_development`
+pub fn state_name(state: PipelineState) -> String {
+    serde_json::to_value(state)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("{state:?}"))
+}
+
+/// Decision name for output, e.g. `escalate`
+pub fn decision_name(decision: Decision) -> String {
+    serde_json::to_value(decision)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_else(|| format!("{decision:?}"))
+}
+
+/// JSONL line for a pipeline
+pub fn pipeline_output(pipeline: &Pipeline) -> Result<PipelineOutput, OutputLineError> {
+    Ok(PipelineOutput::new(
+        pipeline.id.0.clone(),
+        pipeline.spec_path.clone(),
+        state_name(pipeline.state),
+        pipeline.iteration,
+        pipeline.max_iterations,
+    )?
+    .with_session(pipeline.session.clone())
+    .with_last_error(pipeline.last_error.clone())
+    .with_timestamps(pipeline.created_at, pipeline.updated_at))
+}
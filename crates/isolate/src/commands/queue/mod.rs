@@ -0,0 +1,111 @@
+//! Queue commands - serialize landing of finished sessions through a merge queue
+//!
+//! Entries live in the `merge_queue` table of `state.db` and are edited only
+//! through the pure use cases in `isolate_core::use_cases`, applied atomically
+//! by `QueueStore::modify`.
+//!
+//! # Subcommands
+//!
+//! - `add` - Enqueue a session for merging
+//! - `list` - Show the queue in processing order
+//! - `remove` - Take a session out of the queue
+//! - `reprioritize` - Change a pending session's priority
+//! - `status` - Show queue counts, or one session's entry
+//! - `process` - Rebase, gate and land queued sessions (see [`processor`])
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+
+pub mod processor;
+pub mod types;
+
+#[cfg(test)]
+mod tests;
+
+use anyhow::Result;
+use isolate_core::{
+    coordination::QueueStore, dequeue_session, enqueue_session, reprioritize_session,
+};
+
+use self::types::{
+    QueueAddArgs, QueueCounts, QueueEntryOutput, QueueListOutput, QueueRemoveOutput,
+    QueueReprioritizeArgs, QueueStatusOutput,
+};
+use crate::commands::lock::types::SessionExists;
+
+/// Enqueue an existing session for merging.
+pub async fn run_add(
+    args: &QueueAddArgs,
+    store: &QueueStore,
+    sessions: &dyn SessionExists,
+) -> Result<QueueEntryOutput> {
+    if !sessions.session_exists(&args.session).await? {
+        anyhow::bail!(
+            "SESSION_NOT_FOUND: Session '{}' does not exist",
+            args.session
+        );
+    }
+
+    let session = args.session.clone();
+    let priority = args.priority;
+    let queue = store
+        .modify(move |queue| enqueue_session(queue, session, priority))
+        .await?;
+
+    QueueEntryOutput::find_in(&queue, &args.session)
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' missing after enqueue", args.session))
+}
+
+/// List every entry in processing order.
+pub async fn run_list(store: &QueueStore) -> Result<QueueListOutput> {
+    let queue = store.load().await?;
+    Ok(QueueListOutput::from(&queue))
+}
+
+/// Remove a session's entry from the queue.
+pub async fn run_remove(session: &str, store: &QueueStore) -> Result<QueueRemoveOutput> {
+    let queue = store
+        .modify(|queue| dequeue_session(queue, session))
+        .await?;
+
+    Ok(QueueRemoveOutput {
+        session: session.to_string(),
+        removed: true,
+        remaining: queue.len(),
+    })
+}
+
+/// Change a pending session's priority and report its new position.
+pub async fn run_reprioritize(
+    args: &QueueReprioritizeArgs,
+    store: &QueueStore,
+) -> Result<QueueEntryOutput> {
+    let priority = args.priority;
+    let queue = store
+        .modify(|queue| reprioritize_session(queue, &args.session, priority))
+        .await?;
+
+    QueueEntryOutput::find_in(&queue, &args.session)
+        .ok_or_else(|| anyhow::anyhow!("Session '{}' missing after reprioritize", args.session))
+}
+
+/// Summarize the queue, optionally focusing on one session.
+pub async fn run_status(session: Option<&str>, store: &QueueStore) -> Result<QueueStatusOutput> {
+    let queue = store.load().await?;
+
+    let entry = match session {
+        Some(name) => Some(
+            QueueEntryOutput::find_in(&queue, name)
+                .ok_or_else(|| anyhow::anyhow!("Session '{name}' is not in the merge queue"))?,
+        ),
+        None => None,
+    };
+
+    Ok(QueueStatusOutput {
+        counts: QueueCounts::from(&queue),
+        entry,
+    })
+}
@@ -0,0 +1,692 @@
+//! Merge queue processor - lands queued sessions in speculative batches
+//!
+//! The processor claims up to `batch_size` pending entries at once and walks
+//! each through the `QueueStatus` state machine:
+//!
+//! 1. `pending -> claimed` - take the next entries in priority order
+//! 2. `claimed -> rebasing -> testing` - rebase each session onto the latest main
+//! 3. gate the whole batch stacked on main in one run of the quality gates
+//! 4. `testing -> ready_to_merge -> merging -> merged` - move the main bookmark
+//!
+//! When the batch is red, the processor bisects over prefixes of the batch to
+//! find the first entry that breaks it. Entries ahead of the culprit still
+//! land, the culprit moves to `failed_retryable` with the gate summary, and
+//! entries behind it go back to `pending` for the next batch. An entry that
+//! fails to land likewise sends the entries behind it back to `pending`.
+//!
+//! Every transition is persisted before the next step starts and emitted as a
+//! `queue_entry_status_changed` event. Entries found in flight on startup were
+//! interrupted by a crash and are resumed as one batch from their last
+//! persisted step.
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{Context, Result};
+use fs4::fs_std::FileExt;
+use isolate_core::{
+    config::GatesConfig,
+    coordination::{QueueStore, QueueTransition},
+    format_failure_message,
+    session_sync::{
+        validate_sync_preconditions, SessionSyncResult, SyncError, WorkspaceCleanStatus,
+    },
+    vcs::VcsResult,
+    BranchName, Event, EventType, GateError, GatesOutcome, JjBackend, QueueEntryId, QueueStatus,
+    VcsBackend, VcsError, WorkspaceState,
+};
+
+use super::types::{ProcessedEntry, QueueProcessOutput};
+use crate::{
+    commands::{
+        done::executor::BoxFuture,
+        events::append_event,
+        gates::{run_pipeline, TreeCache},
+        isolate_data_dir,
+    },
+    db::SessionDb,
+    session::{SessionStatus, SessionUpdate},
+};
+
+/// Repository operations the processor performs for each entry
+pub trait LandingOps: Send + Sync {
+    /// Rebase the session onto the latest main
+    fn sync<'a>(&'a self, session: &'a str) -> BoxFuture<'a, Result<SessionSyncResult, SyncError>>;
+
+    /// Run the quality gates against main with `sessions` stacked on top, in order
+    fn run_gates<'a>(
+        &'a self,
+        sessions: &'a [String],
+    ) -> BoxFuture<'a, Result<GatesOutcome, GateError>>;
+
+    /// Move main to the session's tip
+    fn land<'a>(&'a self, session: &'a str) -> BoxFuture<'a, Result<(), String>>;
+}
+
+/// Destination for queue status events
+pub trait EventSink: Send + Sync {
+    fn emit<'a>(&'a self, event: &'a Event) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Appends events to `.isolate/events.jsonl`, where `isolate events` reads them
+#[derive(Debug, Default)]
+pub struct EventLogSink;
+
+impl EventSink for EventLogSink {
+    fn emit<'a>(&'a self, event: &'a Event) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { append_event(event).await })
+    }
+}
+
+/// One entry of the batch being processed
+struct BatchMember {
+    id: QueueEntryId,
+    session: String,
+    status: QueueStatus,
+    detail: Option<String>,
+    transitions: Vec<QueueTransition>,
+}
+
+impl BatchMember {
+    fn into_processed(self, batch_id: Option<String>) -> ProcessedEntry {
+        ProcessedEntry {
+            id: self.id.to_string(),
+            session: self.session,
+            status: self.status,
+            batch_id,
+            detail: self.detail,
+            transitions: self.transitions,
+        }
+    }
+}
+
+/// Drives queue entries through the state machine, a batch at a time
+pub struct QueueProcessor<'a> {
+    store: &'a QueueStore,
+    ops: &'a dyn LandingOps,
+    events: &'a dyn EventSink,
+    batch_size: usize,
+}
+
+impl<'a> QueueProcessor<'a> {
+    pub fn new(store: &'a QueueStore, ops: &'a dyn LandingOps, events: &'a dyn EventSink) -> Self {
+        Self {
+            store,
+            ops,
+            events,
+            batch_size: 1,
+        }
+    }
+
+    /// Gate up to `size` entries together. A size of 1 lands entries one at a time.
+    #[must_use]
+    pub fn with_batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// Process batches until none are pending, or `limit` entries were handled.
+    pub async fn run(&self, limit: Option<usize>) -> Result<QueueProcessOutput> {
+        let mut processed = Vec::new();
+        let mut batches = 0;
+
+        loop {
+            let size = limit.map_or(self.batch_size, |max| {
+                self.batch_size.min(max.saturating_sub(processed.len()))
+            });
+            if size == 0 {
+                break;
+            }
+            let batch = self.process_batch_of(size).await?;
+            if batch.is_empty() {
+                break;
+            }
+            batches += 1;
+            processed.extend(batch);
+        }
+
+        let remaining = self
+            .store
+            .load()
+            .await?
+            .entries()
+            .iter()
+            .filter(|e| e.status == QueueStatus::Pending)
+            .count();
+        let merged = processed.iter().filter(|e| e.landed()).count();
+        let requeued = processed.iter().filter(|e| e.requeued()).count();
+
+        Ok(QueueProcessOutput {
+            failed: processed.len() - merged - requeued,
+            merged,
+            requeued,
+            batches,
+            remaining,
+            processed,
+        })
+    }
+
+    /// Resume an interrupted batch, or claim and process the next one.
+    ///
+    /// Returns the entries of the batch in queue order; empty when there is
+    /// nothing to do.
+    pub async fn process_batch(&self) -> Result<Vec<ProcessedEntry>> {
+        self.process_batch_of(self.batch_size).await
+    }
+
+    async fn process_batch_of(&self, size: usize) -> Result<Vec<ProcessedEntry>> {
+        let queue = self.store.load().await?;
+        let interrupted: Vec<_> = queue
+            .entries()
+            .iter()
+            .filter(|e| e.status.is_in_flight())
+            .cloned()
+            .collect();
+
+        let (batch_id, mut members) = if interrupted.is_empty() {
+            let batch_id = new_batch_id();
+            let claimed = self.store.claim_batch(size, &batch_id).await?;
+            let mut members = Vec::with_capacity(claimed.len());
+            for transition in claimed {
+                self.emit(&transition).await;
+                members.push(BatchMember {
+                    id: QueueEntryId::new(transition.entry_id.clone())?,
+                    session: transition.session.clone(),
+                    status: transition.to,
+                    detail: None,
+                    transitions: vec![transition],
+                });
+            }
+            (Some(batch_id), members)
+        } else {
+            let batch_id = interrupted.iter().find_map(|e| e.batch_id.clone());
+            let members = interrupted
+                .into_iter()
+                .map(|entry| BatchMember {
+                    id: entry.id,
+                    session: entry.session.to_string(),
+                    status: entry.status,
+                    detail: None,
+                    transitions: Vec::new(),
+                })
+                .collect();
+            (batch_id, members)
+        };
+
+        // Entries that passed the gates before a crash land first
+        for member in &mut members {
+            if matches!(
+                member.status,
+                QueueStatus::ReadyToMerge | QueueStatus::Merging
+            ) {
+                self.land(member).await?;
+            }
+        }
+
+        for member in &mut members {
+            self.rebase(member).await?;
+        }
+
+        self.gate(&mut members).await?;
+
+        Ok(members
+            .into_iter()
+            .map(|m| m.into_processed(batch_id.clone()))
+            .collect())
+    }
+
+    /// Take a claimed entry through the rebase, stopping at `testing` or a failure.
+    async fn rebase(&self, member: &mut BatchMember) -> Result<()> {
+        loop {
+            let (next, detail) = match member.status {
+                QueueStatus::Claimed => (QueueStatus::Rebasing, None),
+                QueueStatus::Rebasing => match self.ops.sync(&member.session).await {
+                    Ok(result) if !result.had_conflicts => (QueueStatus::Testing, None),
+                    Ok(_) => (
+                        QueueStatus::FailedRetryable,
+                        Some("Rebase onto main produced conflicts".to_string()),
+                    ),
+                    Err(e) => (QueueStatus::FailedRetryable, Some(e.to_string())),
+                },
+                _ => return Ok(()),
+            };
+            self.advance(member, next, detail).await?;
+        }
+    }
+
+    /// Gate every entry in `testing` as one stack, bisecting to find the
+    /// culprit if the stack is red, and settle each entry accordingly.
+    async fn gate(&self, members: &mut [BatchMember]) -> Result<()> {
+        let mut testing: Vec<&mut BatchMember> = members
+            .iter_mut()
+            .filter(|m| m.status == QueueStatus::Testing)
+            .collect();
+        if testing.is_empty() {
+            return Ok(());
+        }
+        let sessions: Vec<String> = testing.iter().map(|m| m.session.clone()).collect();
+
+        // Smallest red prefix: `lo - 1` entries are known green, `hi` known red
+        let culprit = match self.gate_failure(&sessions).await {
+            None => None,
+            Some(failure) => {
+                let (mut lo, mut hi, mut failure) = (1, sessions.len(), failure);
+                while lo < hi {
+                    let mid = lo + (hi - lo) / 2;
+                    match self.gate_failure(&sessions[..mid]).await {
+                        Some(reason) => {
+                            hi = mid;
+                            failure = reason;
+                        }
+                        None => lo = mid + 1,
+                    }
+                }
+                Some((hi - 1, failure))
+            }
+        };
+
+        // Entries behind a failure were only gated together with it, so they
+        // go back to pending rather than land on an untested combination
+        let mut requeue: Option<String> = None;
+        for (index, member) in testing.iter_mut().enumerate() {
+            if let Some(detail) = &requeue {
+                self.advance(member, QueueStatus::Pending, Some(detail.clone()))
+                    .await?;
+                continue;
+            }
+            match culprit {
+                Some((at, ref failure)) if index == at => {
+                    self.advance(member, QueueStatus::FailedRetryable, Some(failure.clone()))
+                        .await?;
+                    requeue = Some(format!(
+                        "Requeued behind failed batch member '{}'",
+                        member.session
+                    ));
+                }
+                _ => {
+                    self.advance(member, QueueStatus::ReadyToMerge, None)
+                        .await?;
+                    self.land(member).await?;
+                    if member.status != QueueStatus::Merged {
+                        requeue = Some(format!(
+                            "Requeued behind batch member '{}', which failed to land",
+                            member.session
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the gates over a stack of sessions; `None` when they pass.
+    async fn gate_failure(&self, sessions: &[String]) -> Option<String> {
+        match self.ops.run_gates(sessions).await {
+            Ok(outcome) if outcome.status.is_success() => None,
+            Ok(outcome) => Some(format_failure_message(&outcome)),
+            Err(e) => Some(e.to_string()),
+        }
+    }
+
+    /// Move a gated entry onto main.
+    async fn land(&self, member: &mut BatchMember) -> Result<()> {
+        if member.status == QueueStatus::ReadyToMerge {
+            self.advance(member, QueueStatus::Merging, None).await?;
+        }
+        if member.status == QueueStatus::Merging {
+            let (next, detail) = match self.ops.land(&member.session).await {
+                Ok(()) => (QueueStatus::Merged, None),
+                Err(reason) => (QueueStatus::FailedRetryable, Some(reason)),
+            };
+            self.advance(member, next, detail).await?;
+        }
+        Ok(())
+    }
+
+    /// Persist one step of an entry and publish it.
+    async fn advance(
+        &self,
+        member: &mut BatchMember,
+        next: QueueStatus,
+        detail: Option<String>,
+    ) -> Result<()> {
+        let transition = self
+            .store
+            .transition(&member.id, next, detail.as_deref())
+            .await?;
+        self.emit(&transition).await;
+        member.transitions.push(transition);
+        member.status = next;
+        member.detail = detail.or_else(|| member.detail.take());
+        Ok(())
+    }
+
+    /// Publish a transition. The transition is already persisted, so a failure
+    /// to record the event is reported but does not stop processing.
+    async fn emit(&self, transition: &QueueTransition) {
+        let event = Event::new(
+            EventType::QueueEntryStatusChanged,
+            format!(
+                "{}: {} -> {}",
+                transition.session, transition.from, transition.to
+            ),
+        )
+        .with_session(transition.session.clone())
+        .with_data(serde_json::to_value(transition).unwrap_or(serde_json::Value::Null));
+
+        if let Err(e) = self.events.emit(&event).await {
+            eprintln!("Warning: failed to record queue event: {e}");
+        }
+    }
+}
+
+/// Identifier for a freshly claimed batch
+fn new_batch_id() -> String {
+    let suffix: u16 = rand::Rng::gen(&mut rand::thread_rng());
+    format!("b-{}-{suffix:04x}", chrono::Utc::now().timestamp_millis())
+}
+
+/// Hold the processor lock so only one processor lands entries at a time.
+///
+/// The lock is released when the returned file is dropped.
+pub async fn acquire_processor_lock() -> Result<std::fs::File> {
+    let lock_path = isolate_data_dir().await?.join("queue-processor.lock");
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .context("Failed to open queue processor lock file")?;
+
+    file.try_lock_exclusive().map_err(|_| {
+        anyhow::anyhow!("QUEUE_PROCESSOR_BUSY: Another merge queue processor is already running")
+    })?;
+    Ok(file)
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// REAL OPERATIONS
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Workspace in `.isolate/` where multi-entry batches are stacked and gated
+const SPECULATIVE_WORKSPACE: &str = "isolate-queue-speculative";
+
+/// `LandingOps` backed by jj workspaces and the configured quality gates
+///
+/// Repository access goes through a [`VcsBackend`], run on the blocking
+/// thread pool since the backend shells out to `jj`.
+pub struct JjLandingOps {
+    db: SessionDb,
+    main_branch: String,
+    gates: GatesConfig,
+    backend: Arc<dyn VcsBackend>,
+}
+
+impl JjLandingOps {
+    /// `main_branch` is the bookmark entries are rebased onto and landed on;
+    /// `gates` is the pipeline every batch must pass first.
+    pub fn new(db: SessionDb, main_branch: String, gates: GatesConfig) -> Self {
+        Self {
+            db,
+            main_branch,
+            gates,
+            backend: Arc::new(JjBackend::new()),
+        }
+    }
+
+    /// Run one backend operation off the async runtime.
+    async fn vcs<T, F>(&self, op: F) -> VcsResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn VcsBackend) -> VcsResult<T> + Send + 'static,
+    {
+        let backend = Arc::clone(&self.backend);
+        tokio::task::spawn_blocking(move || op(backend.as_ref()))
+            .await
+            .map_err(|e| VcsError::OperationFailed(format!("VCS task failed: {e}")))?
+    }
+
+    /// Rebase the session's changes onto main in its workspace. A rebase that
+    /// conflicts is undone and reported as [`VcsError::Conflict`].
+    async fn rebase_onto_main(&self, path: String) -> VcsResult<()> {
+        let main = BranchName::new(self.main_branch.clone())?;
+        let working_copy = BranchName::new("@")?;
+        self.vcs(move |vcs| vcs.rebase(&path, &working_copy, &main))
+            .await
+    }
+
+    async fn workspace_path(&self, session: &str) -> Result<(PathBuf, SessionStatus), String> {
+        self.db
+            .get(session)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|s| (PathBuf::from(s.workspace_path), s.status))
+            .ok_or_else(|| format!("Session '{session}' not found"))
+    }
+
+    async fn sync_session(&self, session: &str) -> Result<SessionSyncResult, SyncError> {
+        let (path, status) = self
+            .workspace_path(session)
+            .await
+            .map_err(|_| SyncError::SessionNotFound(session.to_string()))?;
+        let workspace = path.display().to_string();
+        let vcs_err = |e: VcsError| SyncError::JjCommandError(e.to_string());
+
+        // Queued work must be committed; `@` is the empty working-copy commit
+        let status_path = workspace.clone();
+        let repo_status = self
+            .vcs(move |vcs| vcs.status(&status_path))
+            .await
+            .map_err(vcs_err)?;
+        let clean = if repo_status.clean {
+            WorkspaceCleanStatus::Clean
+        } else {
+            WorkspaceCleanStatus::Dirty
+        };
+        validate_sync_preconditions(true, Some(to_core_status(status)), clean, false).map_err(
+            |e| match e {
+                SyncError::DirtyWorkspace(_) => SyncError::DirtyWorkspace(workspace.clone()),
+                other => other,
+            },
+        )?;
+
+        let _lock = crate::commands::sync::acquire_sync_lock()
+            .await
+            .map_err(|e| SyncError::IoError(e.to_string()))?;
+
+        match self.rebase_onto_main(workspace.clone()).await {
+            Ok(()) => {}
+            Err(VcsError::Conflict(what)) => {
+                return Err(SyncError::Conflict {
+                    conflicted_files: conflicted_paths(&what),
+                    workspace,
+                })
+            }
+            Err(e) => {
+                return Err(SyncError::RebaseFailure {
+                    workspace,
+                    reason: e.to_string(),
+                })
+            }
+        }
+
+        let tip_path = workspace.clone();
+        let tip = self
+            .vcs(move |vcs| vcs.resolve(&tip_path, "@-"))
+            .await
+            .map_err(vcs_err)?;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.db
+            .update(
+                session,
+                SessionUpdate {
+                    last_synced: Some(now),
+                    ..SessionUpdate::default()
+                },
+            )
+            .await
+            .map_err(|e| SyncError::IoError(e.to_string()))?;
+
+        Ok(SessionSyncResult::new(
+            session.to_string(),
+            tip.to_string(),
+            false,
+        ))
+    }
+
+    async fn gates(&self, sessions: &[String]) -> Result<GatesOutcome, GateError> {
+        let path = match sessions {
+            [session] => self.session_gate_dir(session).await?,
+            _ => self.speculative_gate_dir(sessions).await?,
+        };
+
+        let cache = TreeCache::for_workspace(self.db.pool().clone(), &path).await;
+        run_pipeline(&self.gates, &path, cache.as_ref()).await
+    }
+
+    /// A single entry is gated in its own workspace, exactly as `done` would.
+    async fn session_gate_dir(&self, session: &str) -> Result<PathBuf, GateError> {
+        let (path, _) = self
+            .workspace_path(session)
+            .await
+            .map_err(GateError::WorkingDirectoryNotFound)?;
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            return Err(GateError::WorkingDirectoryNotFound(
+                path.display().to_string(),
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Check out the merge of every session's tip in the dedicated speculative
+    /// workspace. Each tip is already rebased onto main, so the merge is main
+    /// with the whole stack applied.
+    async fn speculative_gate_dir(&self, sessions: &[String]) -> Result<PathBuf, GateError> {
+        let setup_err = |reason: String| GateError::ExecutionFailed {
+            gate: SPECULATIVE_WORKSPACE.to_string(),
+            reason,
+        };
+
+        let mut tips = Vec::with_capacity(sessions.len());
+        for session in sessions {
+            let path = self.session_gate_dir(session).await?.display().to_string();
+            let tip = self
+                .vcs(move |vcs| vcs.resolve(&path, "@-"))
+                .await
+                .map_err(|e| setup_err(format!("Failed to resolve tip of '{session}': {e}")))?;
+            tips.push(tip.to_string());
+        }
+
+        let data_dir = isolate_data_dir()
+            .await
+            .map_err(|e| GateError::WorkingDirectoryNotFound(e.to_string()))?;
+        let path = data_dir.join(SPECULATIVE_WORKSPACE);
+        let workspace = path.display().to_string();
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            let (repo, target) = (data_dir.display().to_string(), workspace.clone());
+            self.vcs(move |vcs| vcs.add_workspace(&repo, SPECULATIVE_WORKSPACE, &target, None))
+                .await
+                .map_err(|e| setup_err(e.to_string()))?;
+        }
+
+        let target = workspace.clone();
+        let stacked = self
+            .vcs(move |vcs| {
+                let parents: Vec<&str> = tips.iter().map(String::as_str).collect();
+                vcs.checkout_merge(&target, &parents)
+            })
+            .await;
+        match stacked {
+            Ok(()) => Ok(path),
+            Err(VcsError::Conflict(_)) => Err(setup_err(format!(
+                "Batch {} conflicts when stacked on {}",
+                sessions.join(" + "),
+                self.main_branch
+            ))),
+            Err(e) => Err(setup_err(format!("Failed to stack batch on main: {e}"))),
+        }
+    }
+
+    async fn land_session(&self, session: &str) -> Result<(), String> {
+        let (path, _) = self.workspace_path(session).await?;
+        let workspace = path.display().to_string();
+
+        // Entries ahead in the same batch may have moved main since this one
+        // was rebased; the gates already covered the combined result, and the
+        // processor stops landing a batch at its first failure
+        self.rebase_onto_main(workspace.clone())
+            .await
+            .map_err(|e| format!("Failed to rebase {session} onto {}: {e}", self.main_branch))?;
+
+        // JJ refuses to move a bookmark backwards or sideways, so this only
+        // succeeds if main has not moved since the rebase
+        let main = BranchName::new(self.main_branch.clone()).map_err(|e| e.to_string())?;
+        self.vcs(move |vcs| vcs.move_branch(&workspace, &main, "@-"))
+            .await
+            .map_err(|e| format!("Failed to move {} to {session}: {e}", self.main_branch))?;
+
+        let current = self
+            .db
+            .get(session)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|s| s.state);
+        let state = current
+            .filter(|s| s.can_transition_to(WorkspaceState::Merged))
+            .map(|_| WorkspaceState::Merged);
+        self.db
+            .update(
+                session,
+                SessionUpdate {
+                    status: Some(SessionStatus::Completed),
+                    state,
+                    ..SessionUpdate::default()
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl LandingOps for JjLandingOps {
+    fn sync<'a>(&'a self, session: &'a str) -> BoxFuture<'a, Result<SessionSyncResult, SyncError>> {
+        Box::pin(self.sync_session(session))
+    }
+
+    fn run_gates<'a>(
+        &'a self,
+        sessions: &'a [String],
+    ) -> BoxFuture<'a, Result<GatesOutcome, GateError>> {
+        Box::pin(self.gates(sessions))
+    }
+
+    fn land<'a>(&'a self, session: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        Box::pin(self.land_session(session))
+    }
+}
+
+/// Paths listed in a [`VcsError::Conflict`] message, `what: a, b`
+fn conflicted_paths(what: &str) -> Vec<String> {
+    what.split_once(": ").map_or_else(Vec::new, |(_, paths)| {
+        paths.split(", ").map(String::from).collect()
+    })
+}
+
+const fn to_core_status(status: SessionStatus) -> isolate_core::types::SessionStatus {
+    match status {
+        SessionStatus::Creating => isolate_core::types::SessionStatus::Creating,
+        SessionStatus::Active => isolate_core::types::SessionStatus::Active,
+        SessionStatus::Paused => isolate_core::types::SessionStatus::Paused,
+        SessionStatus::Completed => isolate_core::types::SessionStatus::Completed,
+        SessionStatus::Failed => isolate_core::types::SessionStatus::Failed,
+    }
+}
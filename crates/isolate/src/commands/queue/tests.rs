@@ -0,0 +1,453 @@
+//! Tests for queue commands
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+
+use std::{future::Future, pin::Pin, sync::Mutex};
+
+use isolate_core::{
+    combine_results,
+    coordination::QueueStore,
+    session_sync::{SessionSyncResult, SyncError},
+    Event, GateError, GateResult, GatesOutcome, MoonGate, QueueEntryId, QueueStatus,
+};
+
+use super::{
+    processor::{EventSink, LandingOps, QueueProcessor},
+    run_add, run_list, run_remove, run_reprioritize, run_status,
+    types::{QueueAddArgs, QueueReprioritizeArgs},
+};
+use crate::commands::{done::executor::BoxFuture, lock::types::SessionExists};
+
+/// Validator that accepts a fixed set of session names.
+struct KnownSessions(&'static [&'static str]);
+
+impl SessionExists for KnownSessions {
+    fn session_exists(
+        &self,
+        session_name: &str,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<bool>> + Send + 'static>> {
+        let exists = self.0.contains(&session_name);
+        Box::pin(async move { Ok(exists) })
+    }
+}
+
+const SESSIONS: KnownSessions = KnownSessions(&["alpha", "beta", "gamma"]);
+
+async fn setup_store() -> anyhow::Result<QueueStore> {
+    use sqlx::sqlite::SqlitePoolOptions;
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await?;
+    let store = QueueStore::new(pool);
+    store.init().await?;
+    Ok(store)
+}
+
+fn add(session: &str, priority: u32) -> QueueAddArgs {
+    QueueAddArgs {
+        session: session.to_string(),
+        priority,
+    }
+}
+
+#[tokio::test]
+async fn test_add_reports_position_in_priority_order() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+
+    let first = run_add(&add("alpha", 50), &store, &SESSIONS).await?;
+    assert_eq!(first.position, 0);
+    assert_eq!(first.status, "pending");
+
+    let urgent = run_add(&add("beta", 10), &store, &SESSIONS).await?;
+    assert_eq!(urgent.position, 0);
+
+    let list = run_list(&store).await?;
+    let order: Vec<&str> = list.entries.iter().map(|e| e.session.as_str()).collect();
+    assert_eq!(order, vec!["beta", "alpha"]);
+    assert_eq!(list.total, 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_add_rejects_unknown_and_duplicate_sessions() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+
+    let unknown = run_add(&add("nope", 50), &store, &SESSIONS).await;
+    assert!(unknown.is_err_and(|e| e.to_string().contains("SESSION_NOT_FOUND")));
+
+    run_add(&add("alpha", 50), &store, &SESSIONS).await?;
+    let duplicate = run_add(&add("alpha", 10), &store, &SESSIONS).await;
+    assert!(duplicate.is_err());
+    assert_eq!(run_list(&store).await?.total, 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_reprioritize_moves_session_forward() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+    run_add(&add("alpha", 20), &store, &SESSIONS).await?;
+    run_add(&add("beta", 30), &store, &SESSIONS).await?;
+    run_add(&add("gamma", 40), &store, &SESSIONS).await?;
+
+    let moved = run_reprioritize(
+        &QueueReprioritizeArgs {
+            session: "gamma".to_string(),
+            priority: 0,
+        },
+        &store,
+    )
+    .await?;
+
+    assert_eq!(moved.position, 0);
+    assert_eq!(moved.priority, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_remove_and_status_counts() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+    run_add(&add("alpha", 20), &store, &SESSIONS).await?;
+    run_add(&add("beta", 30), &store, &SESSIONS).await?;
+
+    let removed = run_remove("alpha", &store).await?;
+    assert!(removed.removed);
+    assert_eq!(removed.remaining, 1);
+
+    let status = run_status(None, &store).await?;
+    assert_eq!(status.counts.total, 1);
+    assert_eq!(status.counts.pending, 1);
+    assert!(status.entry.is_none());
+
+    let single = run_status(Some("beta"), &store).await?;
+    assert_eq!(single.entry.map(|e| e.session), Some("beta".to_string()));
+
+    assert!(run_status(Some("alpha"), &store).await.is_err());
+    Ok(())
+}
+
+// ============================================================================
+// Queue processor
+// ============================================================================
+
+/// Landing operations with scripted outcomes per session.
+#[derive(Default)]
+struct ScriptedOps {
+    conflicts: &'static [&'static str],
+    failing_gates: &'static [&'static str],
+    failing_lands: &'static [&'static str],
+    calls: Mutex<Vec<String>>,
+}
+
+impl ScriptedOps {
+    fn record(&self, call: &str, session: &str) {
+        if let Ok(mut calls) = self.calls.lock() {
+            calls.push(format!("{call}:{session}"));
+        }
+    }
+
+    fn calls(&self) -> Vec<String> {
+        self.calls.lock().map_or_else(|_| Vec::new(), |c| c.clone())
+    }
+}
+
+impl LandingOps for ScriptedOps {
+    fn sync<'a>(&'a self, session: &'a str) -> BoxFuture<'a, Result<SessionSyncResult, SyncError>> {
+        self.record("sync", session);
+        let conflicted = self.conflicts.contains(&session);
+        Box::pin(async move {
+            if conflicted {
+                Err(SyncError::Conflict {
+                    workspace: session.to_string(),
+                    conflicted_files: vec!["src/lib.rs".to_string()],
+                })
+            } else {
+                Ok(SessionSyncResult::new(
+                    session.to_string(),
+                    "abc123".to_string(),
+                    false,
+                ))
+            }
+        })
+    }
+
+    fn run_gates<'a>(
+        &'a self,
+        sessions: &'a [String],
+    ) -> BoxFuture<'a, Result<GatesOutcome, GateError>> {
+        self.record("gates", &sessions.join("+"));
+        let quick = GateResult::passed(MoonGate::Quick, String::new(), String::new());
+        let red = sessions
+            .iter()
+            .any(|s| self.failing_gates.contains(&s.as_str()));
+        let test = if red {
+            GateResult::failed(MoonGate::Test, 1, String::new(), "test failed".to_string())
+        } else {
+            GateResult::passed(MoonGate::Test, String::new(), String::new())
+        };
+        Box::pin(async move { Ok(combine_results(quick, Some(test))) })
+    }
+
+    fn land<'a>(&'a self, session: &'a str) -> BoxFuture<'a, Result<(), String>> {
+        self.record("land", session);
+        let failed = self.failing_lands.contains(&session);
+        Box::pin(async move {
+            if failed {
+                Err(format!("Failed to move main to {session}"))
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
+
+#[derive(Default)]
+struct RecordingSink(Mutex<Vec<Event>>);
+
+impl EventSink for RecordingSink {
+    fn emit<'a>(&'a self, event: &'a Event) -> BoxFuture<'a, anyhow::Result<()>> {
+        if let Ok(mut events) = self.0.lock() {
+            events.push(event.clone());
+        }
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[tokio::test]
+async fn test_processor_lands_entries_in_priority_order() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+    run_add(&add("alpha", 50), &store, &SESSIONS).await?;
+    run_add(&add("beta", 10), &store, &SESSIONS).await?;
+    let ops = ScriptedOps::default();
+    let sink = RecordingSink::default();
+
+    let output = QueueProcessor::new(&store, &ops, &sink).run(None).await?;
+
+    assert_eq!(output.merged, 2);
+    assert_eq!(output.failed, 0);
+    assert_eq!(output.remaining, 0);
+    assert_eq!(
+        ops.calls(),
+        vec![
+            "sync:beta",
+            "gates:beta",
+            "land:beta",
+            "sync:alpha",
+            "gates:alpha",
+            "land:alpha"
+        ]
+    );
+
+    let beta = store.history("beta").await?;
+    let path: Vec<QueueStatus> = beta.iter().map(|t| t.to).collect();
+    assert_eq!(
+        path,
+        vec![
+            QueueStatus::Claimed,
+            QueueStatus::Rebasing,
+            QueueStatus::Testing,
+            QueueStatus::ReadyToMerge,
+            QueueStatus::Merging,
+            QueueStatus::Merged,
+        ]
+    );
+
+    let events = sink.0.lock().map_or(0, |e| e.len());
+    assert_eq!(events, 12);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_processor_fails_conflicted_and_red_entries() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+    run_add(&add("alpha", 10), &store, &SESSIONS).await?;
+    run_add(&add("beta", 20), &store, &SESSIONS).await?;
+    run_add(&add("gamma", 30), &store, &SESSIONS).await?;
+    let ops = ScriptedOps {
+        conflicts: &["alpha"],
+        failing_gates: &["beta"],
+        ..ScriptedOps::default()
+    };
+    let sink = RecordingSink::default();
+
+    let output = QueueProcessor::new(&store, &ops, &sink).run(None).await?;
+
+    assert_eq!(output.merged, 1);
+    assert_eq!(output.failed, 2);
+    let alpha = &output.processed[0];
+    assert_eq!(alpha.status, QueueStatus::FailedRetryable);
+    assert!(alpha
+        .detail
+        .as_deref()
+        .is_some_and(|d| d.contains("conflicts")));
+    let beta = &output.processed[1];
+    assert!(beta
+        .detail
+        .as_deref()
+        .is_some_and(|d| d.starts_with("Test gate failed")));
+    assert!(!ops.calls().contains(&"land:alpha".to_string()));
+    assert!(!ops.calls().contains(&"land:beta".to_string()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_processor_resumes_interrupted_entry() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+    run_add(&add("alpha", 10), &store, &SESSIONS).await?;
+    run_add(&add("beta", 20), &store, &SESSIONS).await?;
+
+    // Simulate a processor that crashed after rebasing alpha
+    let claimed = store
+        .claim_next()
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("nothing claimed"))?;
+    let id = QueueEntryId::new(claimed.entry_id)?;
+    store.transition(&id, QueueStatus::Rebasing, None).await?;
+    store.transition(&id, QueueStatus::Testing, None).await?;
+
+    let ops = ScriptedOps::default();
+    let sink = RecordingSink::default();
+    let processor = QueueProcessor::new(&store, &ops, &sink);
+
+    let resumed = processor.process_batch().await?;
+    assert_eq!(resumed.len(), 1);
+    assert_eq!(resumed[0].session, "alpha");
+    assert!(resumed[0].landed());
+    assert_eq!(ops.calls(), vec!["gates:alpha", "land:alpha"]);
+
+    let output = processor.run(Some(1)).await?;
+    assert_eq!(output.processed.len(), 1);
+    assert_eq!(output.processed[0].session, "beta");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_processor_lands_green_batch_with_one_gate_run() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+    run_add(&add("alpha", 10), &store, &SESSIONS).await?;
+    run_add(&add("beta", 20), &store, &SESSIONS).await?;
+    run_add(&add("gamma", 30), &store, &SESSIONS).await?;
+    let ops = ScriptedOps::default();
+    let sink = RecordingSink::default();
+
+    let output = QueueProcessor::new(&store, &ops, &sink)
+        .with_batch_size(3)
+        .run(None)
+        .await?;
+
+    assert_eq!(output.merged, 3);
+    assert_eq!(output.batches, 1);
+    let gate_runs: Vec<String> = ops
+        .calls()
+        .into_iter()
+        .filter(|c| c.starts_with("gates:"))
+        .collect();
+    assert_eq!(gate_runs, vec!["gates:alpha+beta+gamma"]);
+
+    let batch = output.processed[0].batch_id.clone();
+    assert!(batch.is_some());
+    assert!(output.processed.iter().all(|e| e.batch_id == batch));
+    let history = store.history("gamma").await?;
+    assert!(history.iter().all(|t| t.batch_id == batch));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_processor_bisects_red_batch_to_culprit() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+    run_add(&add("alpha", 10), &store, &SESSIONS).await?;
+    run_add(&add("beta", 20), &store, &SESSIONS).await?;
+    run_add(&add("gamma", 30), &store, &SESSIONS).await?;
+    let ops = ScriptedOps {
+        failing_gates: &["beta"],
+        ..ScriptedOps::default()
+    };
+    let sink = RecordingSink::default();
+    let processor = QueueProcessor::new(&store, &ops, &sink).with_batch_size(3);
+
+    let batch = processor.process_batch().await?;
+
+    let outcome: Vec<(&str, QueueStatus)> = batch
+        .iter()
+        .map(|e| (e.session.as_str(), e.status))
+        .collect();
+    assert_eq!(
+        outcome,
+        vec![
+            ("alpha", QueueStatus::Merged),
+            ("beta", QueueStatus::FailedRetryable),
+            ("gamma", QueueStatus::Pending),
+        ]
+    );
+    assert!(batch[1]
+        .detail
+        .as_deref()
+        .is_some_and(|d| d.starts_with("Test gate failed")));
+
+    let gate_runs: Vec<String> = ops
+        .calls()
+        .into_iter()
+        .filter(|c| c.starts_with("gates:"))
+        .collect();
+    assert_eq!(
+        gate_runs,
+        vec!["gates:alpha+beta+gamma", "gates:alpha+beta", "gates:alpha"]
+    );
+
+    // The requeued entry lost its batch and is first in line for the next one
+    let gamma = store.find_by_session("gamma").await?;
+    assert_eq!(gamma.as_ref().map(|e| e.status), Some(QueueStatus::Pending));
+    assert_eq!(gamma.and_then(|e| e.batch_id), None);
+
+    let next = processor.process_batch().await?;
+    assert_eq!(next.len(), 1);
+    assert!(next[0].landed());
+    assert_ne!(next[0].batch_id, batch[0].batch_id);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_processor_requeues_batch_behind_failed_land() -> anyhow::Result<()> {
+    let store = setup_store().await?;
+    run_add(&add("alpha", 10), &store, &SESSIONS).await?;
+    run_add(&add("beta", 20), &store, &SESSIONS).await?;
+    let ops = ScriptedOps {
+        failing_lands: &["alpha"],
+        ..ScriptedOps::default()
+    };
+    let sink = RecordingSink::default();
+    let processor = QueueProcessor::new(&store, &ops, &sink).with_batch_size(2);
+
+    let batch = processor.process_batch().await?;
+
+    let outcome: Vec<(&str, QueueStatus)> = batch
+        .iter()
+        .map(|e| (e.session.as_str(), e.status))
+        .collect();
+    assert_eq!(
+        outcome,
+        vec![
+            ("alpha", QueueStatus::FailedRetryable),
+            ("beta", QueueStatus::Pending),
+        ]
+    );
+    assert_eq!(
+        batch[1].detail.as_deref(),
+        Some("Requeued behind batch member 'alpha', which failed to land")
+    );
+    assert_eq!(
+        ops.calls(),
+        vec!["sync:alpha", "sync:beta", "gates:alpha+beta", "land:alpha"]
+    );
+
+    // Beta is gated again on its own before it lands
+    let next = processor.process_batch().await?;
+    assert_eq!(next.len(), 1);
+    assert!(next[0].landed());
+    assert!(ops.calls().contains(&"gates:beta".to_string()));
+    Ok(())
+}
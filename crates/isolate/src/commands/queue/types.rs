@@ -0,0 +1,190 @@
+//! Types for the queue command family
+
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+#![cfg_attr(not(test), deny(clippy::expect_used))]
+#![cfg_attr(not(test), deny(clippy::panic))]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+
+use chrono::{DateTime, Utc};
+use isolate_core::{coordination::QueueTransition, Queue, QueueEntry, QueueStatus};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+pub struct QueueAddArgs {
+    pub session: String,
+    pub priority: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct QueueReprioritizeArgs {
+    pub session: String,
+    pub priority: u32,
+}
+
+/// A queue entry as shown to users, including its place in line
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueEntryOutput {
+    pub id: String,
+    pub session: String,
+    pub priority: u32,
+    pub status: String,
+    /// Zero-based position in processing order
+    pub position: usize,
+    pub enqueued_at: DateTime<Utc>,
+    /// Speculative batch the entry is being landed in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+}
+
+impl QueueEntryOutput {
+    #[must_use]
+    pub fn from_entry(entry: &QueueEntry, position: usize) -> Self {
+        Self {
+            id: entry.id.to_string(),
+            session: entry.session.to_string(),
+            priority: entry.priority,
+            status: entry.status.to_string(),
+            position,
+            enqueued_at: entry.enqueued_at,
+            batch_id: entry.batch_id.clone(),
+        }
+    }
+
+    /// Find a session in a queue and describe it
+    #[must_use]
+    pub fn find_in(queue: &Queue, session: &str) -> Option<Self> {
+        queue
+            .entries()
+            .iter()
+            .enumerate()
+            .find(|(_, entry)| entry.session.as_str() == session)
+            .map(|(position, entry)| Self::from_entry(entry, position))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueListOutput {
+    pub entries: Vec<QueueEntryOutput>,
+    pub total: usize,
+}
+
+impl From<&Queue> for QueueListOutput {
+    fn from(queue: &Queue) -> Self {
+        let entries: Vec<QueueEntryOutput> = queue
+            .entries()
+            .iter()
+            .enumerate()
+            .map(|(position, entry)| QueueEntryOutput::from_entry(entry, position))
+            .collect();
+        Self {
+            total: entries.len(),
+            entries,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueRemoveOutput {
+    pub session: String,
+    pub removed: bool,
+    pub remaining: usize,
+}
+
+/// Per-status counts for the whole queue
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueCounts {
+    pub total: usize,
+    pub pending: usize,
+    pub in_flight: usize,
+    pub merged: usize,
+    pub failed: usize,
+    pub cancelled: usize,
+}
+
+impl From<&Queue> for QueueCounts {
+    fn from(queue: &Queue) -> Self {
+        queue
+            .entries()
+            .iter()
+            .fold(Self::default(), |counts, entry| {
+                let counts = Self {
+                    total: counts.total + 1,
+                    ..counts
+                };
+                match entry.status {
+                    QueueStatus::Pending => Self {
+                        pending: counts.pending + 1,
+                        ..counts
+                    },
+                    QueueStatus::Merged => Self {
+                        merged: counts.merged + 1,
+                        ..counts
+                    },
+                    QueueStatus::Cancelled => Self {
+                        cancelled: counts.cancelled + 1,
+                        ..counts
+                    },
+                    status if status.is_failed() => Self {
+                        failed: counts.failed + 1,
+                        ..counts
+                    },
+                    _ => Self {
+                        in_flight: counts.in_flight + 1,
+                        ..counts
+                    },
+                }
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueStatusOutput {
+    pub counts: QueueCounts,
+    /// Set when status was requested for a single session
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entry: Option<QueueEntryOutput>,
+}
+
+/// What the processor did with one entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessedEntry {
+    pub id: String,
+    pub session: String,
+    /// Status the entry was left in
+    pub status: QueueStatus,
+    /// Speculative batch the entry was processed in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<String>,
+    /// Failure reason when the entry did not land
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// Transitions made during this run, oldest first
+    pub transitions: Vec<QueueTransition>,
+}
+
+impl ProcessedEntry {
+    #[must_use]
+    pub fn landed(&self) -> bool {
+        self.status == QueueStatus::Merged
+    }
+
+    /// Put back in line because an entry ahead of it broke the batch
+    #[must_use]
+    pub fn requeued(&self) -> bool {
+        self.status == QueueStatus::Pending
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueueProcessOutput {
+    pub processed: Vec<ProcessedEntry>,
+    pub merged: usize,
+    pub failed: usize,
+    /// Entries sent back to pending behind a failed batch member
+    pub requeued: usize,
+    /// Number of speculative batches gated
+    pub batches: usize,
+    /// Entries still pending when the processor stopped
+    pub remaining: usize,
+}
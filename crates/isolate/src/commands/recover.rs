@@ -9,6 +9,8 @@
 //! - `isolate retry` - Retry last failed command
 //! - `isolate rollback <session> --to <checkpoint>` - Restore to checkpoint
 
+use std::path::PathBuf;
+
 use anyhow::{Context, Result};
 use futures::StreamExt;
 use isolate_core::{json::SchemaEnvelope, OutputFormat};
@@ -17,6 +19,7 @@ use tokio::process::Command;
 
 use super::{
     context::{detect_location, Location},
+    done::executor::{JjExecutor, RealJjExecutor, WorkspaceExecutor},
     get_session_db,
 };
 use crate::cli::is_command_available;
@@ -670,6 +673,8 @@ fn emit_rollback_output(
 
 /// Run operation log recovery or listing
 pub async fn run_op_recover(options: &OpRecoverOptions) -> Result<()> {
+    crate::commands::require_jj("Operation log recovery")?;
+
     // Determine workspace path from session or current location
     let workspace_path = if let Some(session_name) = &options.session {
         // Get session from database
@@ -702,22 +707,17 @@ pub async fn run_op_recover(options: &OpRecoverOptions) -> Result<()> {
         }
     };
 
+    let jj = RealJjExecutor::new();
+    let executor = WorkspaceExecutor::new(&jj, PathBuf::from(&workspace_path));
+
     // If listing or no operation specified, show operation log
     if options.list_only || options.operation.is_none() && !options.last {
-        return show_operation_log(&workspace_path, options.session.as_ref(), options.format).await;
+        return show_operation_log(&executor, options.session.as_ref(), options.format).await;
     }
 
     // Restore to specific operation
     let operation_id = if options.last {
-        // Get previous operation
-        let operations = get_operation_log(&workspace_path).await?;
-        if operations.len() < 2 {
-            anyhow::bail!("No previous operation to restore to");
-        }
-        operations
-            .get(1)
-            .map(|op| op.id.clone())
-            .ok_or_else(|| anyhow::anyhow!("Could not find previous operation"))?
+        previous_operation_id(&executor).await?
     } else {
         options
             .operation
@@ -725,35 +725,33 @@ pub async fn run_op_recover(options: &OpRecoverOptions) -> Result<()> {
             .ok_or_else(|| anyhow::anyhow!("Operation ID required"))?
     };
 
-    restore_to_operation(&workspace_path, &operation_id, options.format).await
+    let result = restore_to_operation(&executor, &operation_id).await;
+    emit_restore_output(&result, options.format)
 }
 
+/// Template for one `id|operation|time|user` line per operation
+const OP_LOG_TEMPLATE: &str =
+    r#"id ++ "|" ++ description.first_line() ++ "|" ++ time.start() ++ "|" ++ user ++ "\n""#;
+
 /// Get operation log from workspace
-async fn get_operation_log(workspace_path: &str) -> Result<Vec<OperationEntry>> {
-    let output = Command::new("jj")
-        .current_dir(workspace_path)
-        .args([
+async fn get_operation_log(executor: &dyn JjExecutor) -> Result<Vec<OperationEntry>> {
+    let output = executor
+        .run(&[
             "op",
             "log",
             "--no-graph",
             "-T",
-            r"id | operation | time | user | description",
+            OP_LOG_TEMPLATE,
             "--limit",
             "50",
         ])
-        .output()
         .await
-        .context("Failed to run jj op log")?;
+        .map_err(|e| anyhow::anyhow!("jj op log failed: {e}"))?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("jj op log failed: {stderr}");
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let current_op_id = get_current_operation_id(workspace_path).await.ok();
+    let current_op_id = get_current_operation_id(executor).await.ok();
 
-    let operations = stdout
+    let operations = output
+        .as_str()
         .lines()
         .filter_map(|line| {
             let parts: Vec<&str> = line.split('|').collect();
@@ -793,31 +791,35 @@ async fn get_operation_log(workspace_path: &str) -> Result<Vec<OperationEntry>>
 }
 
 /// Get current operation ID
-async fn get_current_operation_id(workspace_path: &str) -> Result<String> {
-    let output = Command::new("jj")
-        .current_dir(workspace_path)
-        .args(["op", "log", "--no-graph", "-T", "id", "--limit", "1"])
-        .output()
+async fn get_current_operation_id(executor: &dyn JjExecutor) -> Result<String> {
+    let output = executor
+        .run(&["op", "log", "--no-graph", "-T", "id", "--limit", "1"])
         .await
-        .context("Failed to get current operation")?;
+        .map_err(|e| anyhow::anyhow!("Failed to get current operation: {e}"))?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Failed to get current operation: {stderr}");
-    }
+    Ok(output.as_str().trim().to_string())
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(stdout.trim().to_string())
+/// ID of the operation before the current one
+async fn previous_operation_id(executor: &dyn JjExecutor) -> Result<String> {
+    let operations = get_operation_log(executor).await?;
+    if operations.len() < 2 {
+        anyhow::bail!("No previous operation to restore to");
+    }
+    operations
+        .get(1)
+        .map(|op| op.id.clone())
+        .ok_or_else(|| anyhow::anyhow!("Could not find previous operation"))
 }
 
 /// Show operation log
 async fn show_operation_log(
-    workspace_path: &str,
+    executor: &dyn JjExecutor,
     session_name: Option<&String>,
     format: OutputFormat,
 ) -> Result<()> {
-    let operations = get_operation_log(workspace_path).await?;
-    let current_op_id = get_current_operation_id(workspace_path).await.ok();
+    let operations = get_operation_log(executor).await?;
+    let current_op_id = get_current_operation_id(executor).await.ok();
 
     let session = session_name
         .map(std::string::String::as_str)
@@ -860,38 +862,35 @@ async fn show_operation_log(
 
 /// Restore to specific operation
 async fn restore_to_operation(
-    workspace_path: &str,
+    executor: &dyn JjExecutor,
     operation_id: &str,
-    format: OutputFormat,
-) -> Result<()> {
-    let output = Command::new("jj")
-        .current_dir(workspace_path)
-        .args(["op", "restore", "--operation", operation_id])
-        .output()
-        .await
-        .context("Failed to run jj op restore")?;
+) -> OperationRestoreOutput {
+    let result = executor
+        .run(&["op", "restore", "--operation", operation_id])
+        .await;
 
     let session = "workspace"; // Could be enhanced to get actual session name
 
-    let result = if output.status.success() {
-        OperationRestoreOutput {
+    match result {
+        Ok(_) => OperationRestoreOutput {
             session: session.to_string(),
             operation_id: operation_id.to_string(),
             success: true,
             message: format!("Restored to operation {operation_id}"),
-        }
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        OperationRestoreOutput {
+        },
+        Err(e) => OperationRestoreOutput {
             session: session.to_string(),
             operation_id: operation_id.to_string(),
             success: false,
-            message: format!("Failed to restore: {stderr}"),
-        }
-    };
+            message: format!("Failed to restore: {e}"),
+        },
+    }
+}
 
+/// Print the outcome of a restore, failing if it did not succeed
+fn emit_restore_output(result: &OperationRestoreOutput, format: OutputFormat) -> Result<()> {
     if format.is_json() {
-        let envelope = SchemaEnvelope::new("op-restore-response", "single", &result);
+        let envelope = SchemaEnvelope::new("op-restore-response", "single", result);
         let json_str = serde_json::to_string_pretty(&envelope)
             .context("Failed to serialize restore output")?;
         println!("{json_str}");
@@ -1348,4 +1347,87 @@ mod tests {
             assert!(!options.diagnose_only, "Should attempt fixes");
         }
     }
+
+    mod operation_log_behavior {
+        use isolate_core::vcs::FakeBackend;
+
+        use super::*;
+        use crate::commands::done::fake_executor::FakeJjExecutor;
+
+        const WORKSPACE: &str = "feature";
+        const WORKSPACE_PATH: &str = "/repo/.isolate/workspaces/feature";
+
+        fn repo_with_commit() -> FakeBackend {
+            let repo = FakeBackend::new("/repo");
+            repo.add_workspace_at("default", WORKSPACE, WORKSPACE_PATH, Some("main"))
+                .expect("add workspace");
+            repo.write_file(WORKSPACE, "feature.rs", "fn feature() {}\n")
+                .expect("write");
+            repo.commit_workspace(WORKSPACE, "Add feature")
+                .expect("commit");
+            repo
+        }
+
+        fn executor(repo: &FakeBackend) -> FakeJjExecutor {
+            FakeJjExecutor::in_workspace(repo.clone(), WORKSPACE)
+        }
+
+        /// GIVEN: A workspace with a few operations
+        /// WHEN: The operation log is read
+        /// THEN: Operations are listed newest first with the current one marked
+        #[tokio::test]
+        async fn lists_operations_newest_first() -> anyhow::Result<()> {
+            let repo = repo_with_commit();
+
+            let operations = get_operation_log(&executor(&repo)).await?;
+
+            let expected: Vec<String> = repo.operations().into_iter().map(|op| op.id).collect();
+            let ids: Vec<String> = operations.iter().map(|op| op.id.clone()).collect();
+            assert_eq!(ids, expected);
+            assert!(operations.first().is_some_and(|op| op.current));
+            assert_eq!(operations.iter().filter(|op| op.current).count(), 1);
+            assert!(operations
+                .first()
+                .is_some_and(|op| op.operation.starts_with("commit")));
+            Ok(())
+        }
+
+        /// GIVEN: A commit was just made
+        /// WHEN: Restoring the previous operation
+        /// THEN: The commit is undone and the working copy holds the edit again
+        #[tokio::test]
+        async fn restoring_previous_operation_undoes_last_change() -> anyhow::Result<()> {
+            let repo = repo_with_commit();
+            let executor = executor(&repo);
+
+            let previous = previous_operation_id(&executor).await?;
+            let result = restore_to_operation(&executor, &previous).await;
+
+            assert!(result.success, "{}", result.message);
+            let working_copy = repo.working_copy(WORKSPACE)?;
+            assert_eq!(working_copy.description, "");
+            assert!(working_copy.tree.contains_key("feature.rs"));
+            Ok(())
+        }
+
+        /// GIVEN: An operation id that does not exist
+        /// WHEN: Restoring to it
+        /// THEN: The restore fails with jj's error and nothing changes
+        #[tokio::test]
+        async fn unknown_operation_fails_cleanly() -> anyhow::Result<()> {
+            let repo = repo_with_commit();
+            let before = repo.working_copy(WORKSPACE)?;
+
+            let result = restore_to_operation(&executor(&repo), "ffffffffffff").await;
+
+            assert!(!result.success);
+            assert!(
+                result.message.contains("No operation ID matching"),
+                "{}",
+                result.message
+            );
+            assert_eq!(repo.working_copy(WORKSPACE)?, before);
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,376 @@
+//! Restack sessions built on other sessions - JSONL output for AI-first control plane
+//!
+//! `isolate add <name> --on <parent>` starts a session from another session's
+//! working copy. Once the parent moves on (new commits, a sync, or landing on
+//! main) its descendants still sit on the old version of its changes.
+//! Restacking rebases every descendant onto its parent, parents before
+//! children, or onto main once the parent is gone.
+//!
+//! `isolate restack <name>` restacks the sessions built on `<name>`; without a
+//! name every stacked session is restacked. `sync` and `done` restack the
+//! descendants of the session they moved automatically.
+//!
+//! Conflicts do not stop a restack: jj records them in the rebased commits
+//! and every restacked session gets a `conflict_analysis` line listing them.
+//! Git worktrees cannot hold conflicts, so a conflicting rebase there is
+//! aborted and reported as an error.
+
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![forbid(unsafe_code)]
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use isolate_core::{
+    coordination::StackStore,
+    output::{
+        emit_stdout, Action, ActionStatus, ActionTarget, ActionVerb, Message, OutputLine,
+        ResultKind, ResultOutput,
+    },
+    BackendType, BranchDag, BranchId, OutputFormat,
+};
+
+use crate::{
+    commands::{
+        determine_main_branch,
+        done::{
+            conflict::{existing_conflicts, ConflictDetectionResult},
+            executor::{JjExecutor, RealJjExecutor, WorkspaceExecutor},
+        },
+        get_session_db,
+        sync::{acquire_sync_lock, rebase_worktree},
+    },
+    db::SessionDb,
+    session::{Session, SessionStatus},
+};
+
+/// Options for the restack command
+#[derive(Debug, Clone)]
+pub struct RestackOptions {
+    /// Restack only the sessions built on this one
+    pub name: Option<String>,
+    /// Show what would be restacked without rebasing
+    pub dry_run: bool,
+    /// Output format
+    pub format: OutputFormat,
+}
+
+/// A session rebased onto its parent by a restack
+#[derive(Debug, Clone)]
+pub struct Restacked {
+    /// The restacked session
+    pub session: String,
+    /// Revision it was rebased onto
+    pub onto: String,
+    /// Conflicts the rebase left in its working copy
+    pub conflicts: ConflictDetectionResult,
+}
+
+/// Run the restack command with options
+pub async fn run_with_options(options: &RestackOptions) -> Result<()> {
+    let db = get_session_db().await?;
+
+    if let Some(name) = &options.name {
+        if db.get(name).await?.is_none() {
+            return Err(anyhow::Error::new(isolate_core::Error::NotFound(format!(
+                "Session '{name}' not found"
+            ))));
+        }
+    }
+
+    let stacks = StackStore::new(db.pool().clone());
+    let order = restack_order(&stacks.dag().await?, options.name.as_deref())?;
+
+    if options.dry_run {
+        let mut plan = Vec::with_capacity(order.len());
+        for name in &order {
+            if let Some(session) = live_session(&db, name).await? {
+                plan.push((name.clone(), destination(&db, &session).await?));
+            }
+        }
+        return output_plan(&plan, options.format);
+    }
+
+    let restacked = if order.is_empty() {
+        Vec::new()
+    } else {
+        let _lock = acquire_sync_lock().await?;
+        restack_sessions(&db, &RealJjExecutor::new(), &order).await?
+    };
+
+    emit_restacked(&restacked, options.format)?;
+    output_summary(&restacked, options.format)
+}
+
+/// Restack everything built on `name` after it moved
+///
+/// With `landed` the session has reached main: it leaves the stack first, so
+/// its children restack onto its own parent, or main. Returns nothing without
+/// touching the repository when no session is built on `name`.
+pub async fn restack_descendants(
+    db: &SessionDb,
+    executor: &dyn JjExecutor,
+    name: &str,
+    landed: bool,
+) -> Result<Vec<Restacked>> {
+    let stacks = StackStore::new(db.pool().clone());
+    let order = restack_order(&stacks.dag().await?, Some(name))?;
+    if landed {
+        stacks.remove(name).await?;
+    }
+    if order.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let _lock = acquire_sync_lock().await?;
+    restack_sessions(db, executor, &order).await
+}
+
+/// Restack every session built on another one, parents first
+pub async fn restack_all(db: &SessionDb, executor: &dyn JjExecutor) -> Result<Vec<Restacked>> {
+    let stacks = StackStore::new(db.pool().clone());
+    let order = restack_order(&stacks.dag().await?, None)?;
+    if order.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let _lock = acquire_sync_lock().await?;
+    restack_sessions(db, executor, &order).await
+}
+
+/// Sessions to restack, parents before children
+///
+/// The descendants of `from`, or every session stacked on another session
+/// when `from` is `None`. Sessions on main are not restacked; syncing moves them.
+pub fn restack_order(dag: &BranchDag, from: Option<&str>) -> Result<Vec<String>> {
+    let sorted = dag.topological_sort()?;
+    let wanted: HashSet<BranchId> = match from {
+        Some(name) => dag
+            .get_descendants(&BranchId::new(name)?)
+            .into_iter()
+            .collect(),
+        None => sorted
+            .iter()
+            .filter(|branch| dag.get_parent(branch).is_some())
+            .cloned()
+            .collect(),
+    };
+
+    Ok(sorted
+        .into_iter()
+        .filter(|branch| wanted.contains(branch))
+        .map(|branch| branch.as_str().to_string())
+        .collect())
+}
+
+/// Revision `session` builds on: its parent's working copy (its branch in a
+/// git worktree) while the parent is a live session, main otherwise
+pub async fn destination(db: &SessionDb, session: &Session) -> Result<String> {
+    let workspace_path = Path::new(&session.workspace_path);
+    let git = isolate_core::discover_backend(workspace_path) == Some(BackendType::Git);
+
+    match live_parent(db, &session.name).await? {
+        Some(parent) => Ok(parent_revision(&parent, git)),
+        None => Ok(determine_main_branch(workspace_path).await),
+    }
+}
+
+/// Session `name` is stacked on, if that session is still live
+pub async fn live_parent(db: &SessionDb, name: &str) -> Result<Option<String>> {
+    let stacks = StackStore::new(db.pool().clone());
+    match stacks.parent_of(name).await? {
+        Some(parent) => Ok(live_session(db, &parent).await?.map(|session| session.name)),
+        None => Ok(None),
+    }
+}
+
+/// Revision a child of session `parent` rebases onto: the parent's working
+/// copy in JJ, the branch named after it in a git worktree
+pub fn parent_revision(parent: &str, git: bool) -> String {
+    if git {
+        parent.to_string()
+    } else {
+        format!("{parent}@")
+    }
+}
+
+/// Rebase each of `order` onto its destination, in order
+///
+/// Sessions that are gone, completed or failed are skipped.
+async fn restack_sessions(
+    db: &SessionDb,
+    executor: &dyn JjExecutor,
+    order: &[String],
+) -> Result<Vec<Restacked>> {
+    let mut restacked = Vec::with_capacity(order.len());
+
+    for name in order {
+        let Some(session) = live_session(db, name).await? else {
+            continue;
+        };
+        let onto = destination(db, &session).await?;
+        let conflicts = rebase_session(executor, &session, &onto)
+            .await
+            .with_context(|| format!("Failed to restack '{name}' onto '{onto}'"))?;
+
+        restacked.push(Restacked {
+            session: name.clone(),
+            conflicts: restack_result(conflicts, &onto),
+            onto,
+        });
+    }
+
+    Ok(restacked)
+}
+
+/// Rebase one session's changes onto `onto`, returning the conflicted files
+async fn rebase_session(
+    executor: &dyn JjExecutor,
+    session: &Session,
+    onto: &str,
+) -> Result<Vec<String>> {
+    let workspace_path = &session.workspace_path;
+    if isolate_core::discover_backend(Path::new(workspace_path)) == Some(BackendType::Git) {
+        rebase_worktree(workspace_path, onto).await?;
+        return Ok(Vec::new());
+    }
+
+    let workspace = WorkspaceExecutor::new(executor, PathBuf::from(workspace_path));
+    workspace
+        .run(&["rebase", "-b", "@", "-d", onto])
+        .await
+        .map_err(anyhow::Error::new)?;
+    existing_conflicts(&workspace)
+        .await
+        .map_err(anyhow::Error::new)
+}
+
+async fn live_session(db: &SessionDb, name: &str) -> Result<Option<Session>> {
+    Ok(db.get(name).await?.filter(|session| {
+        !matches!(
+            session.status,
+            SessionStatus::Completed | SessionStatus::Failed
+        )
+    }))
+}
+
+fn restack_result(conflicts: Vec<String>, onto: &str) -> ConflictDetectionResult {
+    let summary = if conflicts.is_empty() {
+        format!("Restacked onto {onto} without conflicts")
+    } else {
+        format!(
+            "Restacking onto {onto} left conflicts in {} files: {}",
+            conflicts.len(),
+            conflicts.join(", ")
+        )
+    };
+
+    ConflictDetectionResult {
+        has_existing_conflicts: !conflicts.is_empty(),
+        merge_likely_safe: conflicts.is_empty(),
+        existing_conflicts: conflicts,
+        summary,
+        ..ConflictDetectionResult::default()
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+// OUTPUT
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Report each restacked session: an action and a `conflict_analysis` line
+/// in JSON mode, a line per session (and its conflicts) otherwise
+pub fn emit_restacked(restacked: &[Restacked], format: OutputFormat) -> Result<()> {
+    for entry in restacked {
+        let message = format!("Restacked '{}' onto {}", entry.session, entry.onto);
+        if format.is_json() {
+            let action = Action::new(
+                ActionVerb::new("restack").map_err(|e| anyhow::anyhow!("{e}"))?,
+                ActionTarget::new(&entry.session).map_err(|e| anyhow::anyhow!("{e}"))?,
+                ActionStatus::Completed,
+            )
+            .with_result(message);
+            emit_stdout(&OutputLine::Action(action)).map_err(|e| anyhow::anyhow!("{e}"))?;
+            entry
+                .conflicts
+                .emit_jsonl(&entry.session)
+                .map_err(anyhow::Error::new)?;
+        } else {
+            println!("{message}");
+            if entry.conflicts.has_conflicts() {
+                println!(
+                    "  Conflicts: {}",
+                    entry.conflicts.existing_conflicts.join(", ")
+                );
+            }
+        }
+    }
+
+    if !format.is_json() && restacked.iter().any(|r| r.conflicts.has_conflicts()) {
+        println!("Resolve with 'jj resolve' in each conflicted session");
+    }
+    Ok(())
+}
+
+fn output_plan(plan: &[(String, String)], format: OutputFormat) -> Result<()> {
+    if format.is_json() {
+        for (session, onto) in plan {
+            let action = Action::new(
+                ActionVerb::new("restack").map_err(|e| anyhow::anyhow!("{e}"))?,
+                ActionTarget::new(session).map_err(|e| anyhow::anyhow!("{e}"))?,
+                ActionStatus::Pending,
+            )
+            .with_result(format!("[DRY RUN] Would restack '{session}' onto {onto}"));
+            emit_stdout(&OutputLine::Action(action)).map_err(|e| anyhow::anyhow!("{e}"))?;
+        }
+        return emit_result(&format!(
+            "[DRY RUN] Would restack {} session(s)",
+            plan.len()
+        ));
+    }
+
+    if plan.is_empty() {
+        println!("[DRY RUN] Nothing to restack");
+    }
+    for (session, onto) in plan {
+        println!("[DRY RUN] Would restack '{session}' onto {onto}");
+    }
+    Ok(())
+}
+
+fn output_summary(restacked: &[Restacked], format: OutputFormat) -> Result<()> {
+    let conflicted = restacked
+        .iter()
+        .filter(|r| r.conflicts.has_conflicts())
+        .count();
+    let message = match (restacked.len(), conflicted) {
+        (0, _) => "Nothing to restack".to_string(),
+        (count, 0) => format!("Restacked {count} session(s)"),
+        (count, conflicted) => {
+            format!("Restacked {count} session(s), {conflicted} with conflicts")
+        }
+    };
+
+    if format.is_json() {
+        emit_result(&message)
+    } else {
+        println!("{message}");
+        Ok(())
+    }
+}
+
+fn emit_result(message: &str) -> Result<()> {
+    let result = ResultOutput::success(
+        ResultKind::Command,
+        Message::new(message).map_err(|e| anyhow::anyhow!("Invalid message: {e}"))?,
+    )
+    .map_err(|e| anyhow::anyhow!("{e}"))?;
+    emit_stdout(&OutputLine::Result(result)).map_err(|e| anyhow::anyhow!("{e}"))
+}
+
+#[cfg(test)]
+mod tests;
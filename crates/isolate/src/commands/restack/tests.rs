@@ -0,0 +1,137 @@
+//! Restacking against the in-memory repository
+
+use isolate_core::{
+    coordination::StackStore,
+    vcs::{FakeBackend, FakeFailure, FakeOp},
+    BranchDag, BranchId,
+};
+use tempfile::TempDir;
+
+use super::{destination, restack_order, restack_sessions};
+use crate::{
+    commands::done::fake_executor::FakeJjExecutor,
+    db::SessionDb,
+    session::{SessionStatus, SessionUpdate},
+};
+
+fn path(name: &str) -> String {
+    format!("/repo/.isolate/workspaces/{name}")
+}
+
+/// `api` has committed `schema.rs` and kept working after `ui` was stacked on it
+fn stacked_repo() -> anyhow::Result<FakeBackend> {
+    let repo = FakeBackend::new("/repo");
+    repo.commit_on("main", "Initial commit", &[("lib.rs", "fn lib() {}\n")])?;
+    repo.add_workspace_at("default", "api", &path("api"), Some("main"))?;
+    repo.write_file("api", "schema.rs", "struct Schema;\n")?;
+    repo.add_workspace_at("default", "ui", &path("ui"), Some("api@"))?;
+    repo.write_file("ui", "ui.rs", "fn ui() {}\n")?;
+    repo.commit_workspace("api", "Add schema")?;
+    repo.write_file("api", "api.rs", "fn api() {}\n")?;
+    Ok(repo)
+}
+
+async fn stacked_db() -> anyhow::Result<(SessionDb, StackStore, TempDir)> {
+    let dir = TempDir::new()?;
+    let db = SessionDb::create_or_open(&dir.path().join("state.db")).await?;
+    db.create("api", &path("api")).await?;
+    db.create("ui", &path("ui")).await?;
+    let stacks = StackStore::new(db.pool().clone());
+    stacks.add_child("ui", "api").await?;
+    Ok((db, stacks, dir))
+}
+
+fn id(name: &str) -> BranchId {
+    BranchId::new(name).expect("valid branch id")
+}
+
+#[test]
+fn test_restack_order_puts_parents_before_children() -> anyhow::Result<()> {
+    let mut dag = BranchDag::new();
+    dag.add_branch(id("schema"), None)?;
+    dag.add_branch(id("api"), Some(id("schema")))?;
+    dag.add_branch(id("ui"), Some(id("api")))?;
+    dag.add_branch(id("docs"), None)?;
+
+    assert_eq!(restack_order(&dag, Some("schema"))?, vec!["api", "ui"]);
+    assert_eq!(restack_order(&dag, Some("api"))?, vec!["ui"]);
+    assert_eq!(restack_order(&dag, None)?, vec!["api", "ui"]);
+    assert_eq!(restack_order(&dag, Some("docs"))?, Vec::<String>::new());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_child_is_rebased_onto_parent_working_copy() -> anyhow::Result<()> {
+    let repo = stacked_repo()?;
+    let (db, _stacks, _dir) = stacked_db().await?;
+    assert_eq!(repo.file("ui", "api.rs"), None);
+
+    let restacked =
+        restack_sessions(&db, &FakeJjExecutor::new(repo.clone()), &["ui".to_string()]).await?;
+
+    assert_eq!(restacked.len(), 1);
+    assert_eq!(restacked[0].onto, "api@");
+    assert!(restacked[0].conflicts.merge_likely_safe);
+    assert_eq!(repo.file("ui", "api.rs").as_deref(), Some("fn api() {}\n"));
+    assert_eq!(repo.file("ui", "ui.rs").as_deref(), Some("fn ui() {}\n"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_conflicts_are_reported_per_child() -> anyhow::Result<()> {
+    let repo = stacked_repo()?;
+    repo.write_file("ui", "lib.rs", "fn lib() { ui() }\n")?;
+    repo.write_file("api", "lib.rs", "fn lib() { api() }\n")?;
+    let (db, _stacks, _dir) = stacked_db().await?;
+
+    let restacked =
+        restack_sessions(&db, &FakeJjExecutor::new(repo.clone()), &["ui".to_string()]).await?;
+
+    let analysis = restacked[0].conflicts.to_conflict_analysis("ui");
+    assert_eq!(analysis.session, "ui");
+    assert!(!analysis.merge_safe);
+    assert_eq!(restacked[0].conflicts.existing_conflicts, vec!["lib.rs"]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_child_of_landed_parent_restacks_onto_main() -> anyhow::Result<()> {
+    let (db, stacks, _dir) = stacked_db().await?;
+    let ui = db.get("ui").await?.expect("ui session");
+    assert_eq!(destination(&db, &ui).await?, "api@");
+
+    db.update(
+        "api",
+        SessionUpdate {
+            status: Some(SessionStatus::Completed),
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    assert_eq!(destination(&db, &ui).await?, "main");
+    stacks.remove("api").await?;
+    assert_eq!(stacks.parent_of("ui").await?, None);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_failed_rebase_names_the_session() -> anyhow::Result<()> {
+    let repo = stacked_repo()?;
+    repo.fail_next(
+        FakeOp::Rebase,
+        FakeFailure::Error("concurrent modification".to_string()),
+    );
+    let (db, _stacks, _dir) = stacked_db().await?;
+
+    let result =
+        restack_sessions(&db, &FakeJjExecutor::new(repo.clone()), &["ui".to_string()]).await;
+
+    let error = format!("{:#}", result.expect_err("rebase should fail"));
+    assert!(
+        error.contains("Failed to restack 'ui' onto 'api@'"),
+        "{error}"
+    );
+    assert!(error.contains("concurrent modification"), "{error}");
+    Ok(())
+}
@@ -50,6 +50,10 @@ pub async fn run_with_options(options: &RevertOptions) -> Result<RevertExitCode,
 
 /// Core revert logic using Railway-Oriented Programming
 async fn execute_revert(options: &RevertOptions) -> Result<RevertOutput, RevertError> {
+    crate::commands::require_jj("Revert").map_err(|e| RevertError::InvalidState {
+        reason: e.to_string(),
+    })?;
+
     let root = jj_root()
         .await
         .map_err(|e: anyhow::Error| RevertError::JjCommandFailed {
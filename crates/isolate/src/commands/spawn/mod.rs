@@ -12,10 +12,12 @@
 
 pub mod heartbeat;
 pub mod rollback;
+pub mod session_agent;
 pub mod types;
 
 pub use heartbeat::{write_heartbeat_instructions, HeartbeatMonitor};
 pub use rollback::{SignalHandler, TransactionTracker};
+pub use session_agent::SessionAgent;
 pub use types::{SpawnArgs, SpawnError, SpawnOptions, SpawnOutput};
 
 /// AI instructions placed in spawned workspace
@@ -61,6 +63,7 @@ use types::SpawnStatus;
 use crate::{
     beads::{BeadRepository, BeadStatus},
     cli::jj_root,
+    commands::{in_own_process_group, kill_process_group, vcs_backend},
 };
 
 /// Run the spawn command with options
@@ -220,28 +223,26 @@ async fn spawn_and_monitor_agent(
     workspace_path: &Path,
     tracker: &TransactionTracker,
 ) -> Result<(Option<u32>, Option<i32>), SpawnError> {
-    // Apply timeout to the spawn operation
+    // A foreground agent is killed when it outlives the timeout
     let spawn_result = if options.background {
         tokio::time::timeout(
             Duration::from_secs(options.timeout_secs),
             spawn_agent_background(workspace_path, options),
         )
         .await
+        .unwrap_or(Err(SpawnError::Timeout {
+            timeout_secs: options.timeout_secs,
+        }))
     } else {
-        tokio::time::timeout(
-            Duration::from_secs(options.timeout_secs),
-            spawn_agent_foreground(workspace_path, options),
-        )
-        .await
+        spawn_agent_foreground(workspace_path, options).await
     };
 
-    let (pid, exit_code) = if let Ok(result) = spawn_result {
-        result?
-    } else {
-        let _ = tracker.rollback().await;
-        return Err(SpawnError::Timeout {
-            timeout_secs: options.timeout_secs,
-        });
+    let (pid, exit_code) = match spawn_result {
+        Err(e @ SpawnError::Timeout { .. }) => {
+            let _ = tracker.rollback().await;
+            return Err(e);
+        }
+        result => result?,
     };
 
     if let Some(pid) = pid {
@@ -354,19 +355,44 @@ async fn create_workspace_discoverability(workspace_path: &Path) -> Result<(), S
 async fn spawn_agent_foreground(
     workspace_path: &Path,
     options: &SpawnOptions,
+) -> Result<(Option<u32>, Option<i32>), SpawnError> {
+    run_agent(
+        workspace_path,
+        &options.agent_command,
+        &options.agent_args,
+        &[("Isolate_BEAD_ID", options.bead_id.clone())],
+        Duration::from_secs(options.timeout_secs),
+    )
+    .await
+}
+
+/// Run an agent in `workspace_path` under a heartbeat and wait for it
+///
+/// `env` is set on top of `Isolate_WORKSPACE` and `Isolate_ACTIVE`. The agent
+/// runs in its own process group; an agent still running after `timeout` is
+/// killed along with everything it started, and one whose wait is dropped is
+/// killed itself.
+pub async fn run_agent(
+    workspace_path: &Path,
+    agent_command: &str,
+    agent_args: &[String],
+    env: &[(&str, String)],
+    timeout: Duration,
 ) -> Result<(Option<u32>, Option<i32>), SpawnError> {
     let heartbeat = HeartbeatMonitor::with_defaults(workspace_path);
     heartbeat.initialize().await?;
 
-    let mut cmd = tokio::process::Command::new(&options.agent_command);
-    cmd.args(&options.agent_args)
+    let mut cmd = tokio::process::Command::new(agent_command);
+    cmd.args(agent_args)
         .current_dir(workspace_path)
-        .env("Isolate_BEAD_ID", &options.bead_id)
+        .envs(env.iter().map(|(key, value)| (key, value)))
         .env(
             "Isolate_WORKSPACE",
             workspace_path.to_string_lossy().as_ref(),
         )
-        .env("Isolate_ACTIVE", "1"); // Required by git pre-commit hook
+        .env("Isolate_ACTIVE", "1") // Required by git pre-commit hook
+        .kill_on_drop(true);
+    in_own_process_group(&mut cmd);
 
     let mut child = cmd.spawn().map_err(|e| SpawnError::AgentSpawnFailed {
         reason: format!("Failed to spawn agent: {e}"),
@@ -375,18 +401,27 @@ async fn spawn_agent_foreground(
     let pid = child.id();
 
     // Wait for completion asynchronously
-    let status = child
-        .wait()
-        .await
+    let waited = tokio::time::timeout(timeout, child.wait()).await;
+    if waited.is_err() {
+        // The agent's own subprocesses would keep writing into the workspace
+        if let Some(pid) = pid {
+            kill_process_group(pid).await;
+        }
+        // Killing also reaps the agent; it may have exited in the meantime
+        let _ = child.kill().await;
+    }
+
+    heartbeat.cleanup().await?;
+
+    let status = waited
+        .map_err(|_| SpawnError::Timeout {
+            timeout_secs: timeout.as_secs(),
+        })?
         .map_err(|e| SpawnError::AgentSpawnFailed {
             reason: format!("Failed to wait for agent: {e}"),
         })?;
 
-    let exit_code = status.code();
-
-    heartbeat.cleanup().await?;
-
-    Ok((pid, exit_code))
+    Ok((pid, status.code()))
 }
 
 /// Spawn agent in background (don't wait)
@@ -429,7 +464,13 @@ async fn handle_success(
     let merged = if options.no_auto_merge || options.no_auto_cleanup {
         false
     } else {
-        merge_to_main(root, bead_id).await?
+        // The backend runs jj synchronously, so keep it off the runtime threads
+        let (root, workspace_name) = (root.to_string(), bead_id.to_string());
+        tokio::task::spawn_blocking(move || merge_to_main(&root, &workspace_name))
+            .await
+            .map_err(|e| SpawnError::JjCommandFailed {
+                reason: format!("jj workspace forget task failed: {e}"),
+            })??
     };
 
     let cleaned = if options.no_auto_cleanup {
@@ -497,64 +538,29 @@ async fn handle_failure(
 /// # Errors
 /// * `JjCommandFailed` - If the jj command execution fails
 /// * `MergeFailed` - If the workspace doesn't exist or forget fails
-async fn merge_to_main(root: &str, workspace_name: &str) -> Result<bool, SpawnError> {
+fn merge_to_main(root: &str, workspace_name: &str) -> Result<bool, SpawnError> {
+    let backend = vcs_backend();
+
     // First, check if the workspace exists before attempting to forget
-    let list_output = tokio::process::Command::new("jj")
-        .args(["workspace", "list"])
-        .current_dir(root)
-        .output()
-        .await
+    let workspaces = backend
+        .list_workspaces(root)
         .map_err(|e| SpawnError::JjCommandFailed {
-            reason: format!("Failed to execute jj workspace list: {e}"),
+            reason: format!("jj workspace list failed: {e}"),
         })?;
 
-    if !list_output.status.success() {
-        return Err(SpawnError::JjCommandFailed {
-            reason: format!(
-                "jj workspace list failed: {}",
-                String::from_utf8_lossy(&list_output.stderr)
-            ),
-        });
-    }
-
-    // Check if our workspace exists in the list
-    let workspace_list = String::from_utf8_lossy(&list_output.stdout);
-    let workspace_exists = workspace_list
-        .lines()
-        .any(|line| line.contains(workspace_name));
-
-    if !workspace_exists {
+    if !workspaces.iter().any(|name| name == workspace_name) {
         return Err(SpawnError::MergeFailed {
             reason: format!("Workspace '{workspace_name}' does not exist"),
         });
     }
 
     // Abandon the workspace to merge changes back to main
-    let forget_output = tokio::process::Command::new("jj")
-        .args(["workspace", "forget", workspace_name])
-        .current_dir(root)
-        .output()
-        .await
+    backend
+        .forget_workspace(root, workspace_name)
         .map_err(|e| SpawnError::JjCommandFailed {
-            reason: format!("Failed to execute jj workspace forget: {e}"),
+            reason: format!("jj workspace forget failed: {e}"),
         })?;
 
-    if !forget_output.status.success() {
-        let stderr = String::from_utf8_lossy(&forget_output.stderr);
-        let stdout = String::from_utf8_lossy(&forget_output.stdout);
-
-        // Check for conflict indicators in the output
-        let error_output = if stderr.is_empty() {
-            stdout.to_string()
-        } else {
-            stderr.to_string()
-        };
-
-        return Err(SpawnError::JjCommandFailed {
-            reason: format!("jj workspace forget failed: {error_output}"),
-        });
-    }
-
     Ok(true)
 }
 
@@ -0,0 +1,353 @@
+//! Agent driver for orchestrator pipelines
+//!
+//! Each pipeline develops in its own session, `pipeline-<id>`, created on the
+//! first iteration and reused on retries. Before the agent starts, the
+//! request is written to `.isolate/pipeline.json` in the workspace, any
+//! validation feedback to `.isolate/feedback.md` and any guidance from the
+//! human who resumed the pipeline to `.isolate/guidance.md`, so the agent can
+//! pick up where the last iteration left off.
+
+use std::{future::Future, path::Path, time::Duration};
+
+use orchestrator::{AgentDriver, AgentError, AgentOutcome, AgentRequest};
+use tokio::runtime::RuntimeFlavor;
+
+use super::run_agent;
+use crate::commands::{
+    add::{self, AddOptions},
+    get_session_db,
+};
+
+/// Request file, relative to the workspace
+const REQUEST_FILE: &str = ".isolate/pipeline.json";
+
+/// Feedback file, relative to the workspace
+const FEEDBACK_FILE: &str = ".isolate/feedback.md";
+
+/// Human guidance file, relative to the workspace
+const GUIDANCE_FILE: &str = ".isolate/guidance.md";
+
+/// Runs a pipeline's agent in an isolate session
+#[derive(Debug, Clone)]
+pub struct SessionAgent {
+    pub agent_command: String,
+    pub agent_args: Vec<String>,
+    pub timeout_secs: u64,
+}
+
+impl SessionAgent {
+    pub const fn new(agent_command: String, agent_args: Vec<String>, timeout_secs: u64) -> Self {
+        Self {
+            agent_command,
+            agent_args,
+            timeout_secs,
+        }
+    }
+
+    async fn run(&self, request: &AgentRequest) -> Result<AgentOutcome, AgentError> {
+        let session = request
+            .session
+            .clone()
+            .unwrap_or_else(|| session_name(&request.pipeline_id));
+        let workspace = ensure_session(&session)
+            .await
+            .map_err(|e| AgentError::Session {
+                session: session.clone(),
+                reason: format!("{e:#}"),
+            })?;
+
+        let env = prepare_workspace(Path::new(&workspace), request)
+            .await
+            .map_err(|e| AgentError::Session {
+                session: session.clone(),
+                reason: e.to_string(),
+            })?;
+
+        let (_pid, exit_code) = run_agent(
+            Path::new(&workspace),
+            &self.agent_command,
+            &self.agent_args,
+            &env,
+            Duration::from_secs(self.timeout_secs),
+        )
+        .await
+        .map_err(|e| AgentError::Spawn(e.to_string()))?;
+
+        Ok(AgentOutcome { session, exit_code })
+    }
+}
+
+impl AgentDriver for SessionAgent {
+    fn develop(&mut self, request: &AgentRequest) -> Result<AgentOutcome, AgentError> {
+        block_on(self.run(request))?
+    }
+}
+
+/// Session a pipeline develops in
+fn session_name(pipeline_id: &str) -> String {
+    let short: String = pipeline_id
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .take(8)
+        .collect();
+    format!("pipeline-{short}")
+}
+
+/// Create `session` unless it exists, returning its workspace path
+async fn ensure_session(session: &str) -> anyhow::Result<String> {
+    let db = get_session_db().await?;
+    if let Some(existing) = db.get(session).await? {
+        return Ok(existing.workspace_path);
+    }
+
+    add::run_internal(&AddOptions {
+        no_open: true,
+        idempotent: true,
+        ..AddOptions::new(session.to_string())
+    })
+    .await?;
+
+    db.get(session)
+        .await?
+        .map(|created| created.workspace_path)
+        .ok_or_else(|| anyhow::anyhow!("Session '{session}' was not recorded"))
+}
+
+/// Write the request, feedback and guidance files, returning the agent's
+/// environment
+async fn prepare_workspace(
+    workspace: &Path,
+    request: &AgentRequest,
+) -> std::io::Result<Vec<(&'static str, String)>> {
+    tokio::fs::create_dir_all(workspace.join(".isolate")).await?;
+
+    let request_path = workspace.join(REQUEST_FILE);
+    let json = serde_json::to_string_pretty(request).map_err(std::io::Error::other)?;
+    tokio::fs::write(&request_path, json).await?;
+
+    let mut env = vec![
+        ("Isolate_PIPELINE_ID", request.pipeline_id.clone()),
+        ("Isolate_PIPELINE_ITERATION", request.iteration.to_string()),
+        ("Isolate_PIPELINE_SPEC", request.spec_path.clone()),
+        (
+            "Isolate_PIPELINE_REQUEST",
+            request_path.to_string_lossy().into_owned(),
+        ),
+    ];
+
+    let optional = [
+        (
+            "Isolate_PIPELINE_FEEDBACK",
+            FEEDBACK_FILE,
+            &request.feedback,
+        ),
+        (
+            "Isolate_PIPELINE_GUIDANCE",
+            GUIDANCE_FILE,
+            &request.guidance,
+        ),
+    ];
+    for (key, file, contents) in optional {
+        let path = workspace.join(file);
+        match contents {
+            Some(contents) => {
+                tokio::fs::write(&path, contents).await?;
+                env.push((key, path.to_string_lossy().into_owned()));
+            }
+            None => {
+                if tokio::fs::try_exists(&path).await? {
+                    tokio::fs::remove_file(&path).await?;
+                }
+            }
+        }
+    }
+
+    Ok(env)
+}
+
+/// Drive `future` from the synchronous executor
+///
+/// Inside the CLI's multi-threaded runtime this blocks the current worker
+/// in place. A current-thread runtime cannot give up its only thread, so
+/// there the future runs on a runtime of its own on a dedicated thread;
+/// outside any runtime one is started for the call.
+fn block_on<F>(future: F) -> Result<F::Output, AgentError>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            Ok(tokio::task::block_in_place(|| handle.block_on(future)))
+        }
+        Ok(_) => std::thread::scope(|scope| {
+            scope
+                .spawn(|| block_on_new_runtime(future))
+                .join()
+                .unwrap_or_else(|_| Err(AgentError::Spawn("Agent thread panicked".to_string())))
+        }),
+        Err(_) => block_on_new_runtime(future),
+    }
+}
+
+fn block_on_new_runtime<F: Future>(future: F) -> Result<F::Output, AgentError> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map(|runtime| runtime.block_on(future))
+        .map_err(|e| AgentError::Spawn(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::commands::spawn::types::SpawnError;
+
+    fn request(feedback: Option<&str>) -> AgentRequest {
+        AgentRequest {
+            pipeline_id: "3f2a9c1e-77b4-4d5e-9a0b-1c2d3e4f5a6b".to_string(),
+            spec_path: "specs/login.yaml".to_string(),
+            iteration: 2,
+            session: None,
+            feedback: feedback.map(str::to_string),
+            guidance: None,
+            twins: vec![],
+        }
+    }
+
+    #[test]
+    fn test_session_name_is_a_valid_session() {
+        let name = session_name("3f2a9c1e-77b4-4d5e-9a0b-1c2d3e4f5a6b");
+
+        assert_eq!(name, "pipeline-3f2a9c1e");
+        assert!(crate::session::validate_session_name(&name).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_agent_sees_feedback_from_last_iteration() {
+        let temp = TempDir::new().unwrap();
+        let env = prepare_workspace(temp.path(), &request(Some("Scenario 1: FAIL")))
+            .await
+            .unwrap();
+
+        let script = r#"test "$(cat "$Isolate_PIPELINE_FEEDBACK")" = "Scenario 1: FAIL" && test "$Isolate_PIPELINE_ITERATION" = 2"#;
+        let (_pid, exit_code) = run_agent(
+            temp.path(),
+            "sh",
+            &["-c".to_string(), script.to_string()],
+            &env,
+            Duration::from_secs(30),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(exit_code, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_agent_sees_human_guidance() {
+        let temp = TempDir::new().unwrap();
+        let resumed = AgentRequest {
+            guidance: Some("Hash passwords with argon2".to_string()),
+            ..request(None)
+        };
+
+        let env = prepare_workspace(temp.path(), &resumed).await.unwrap();
+
+        let guidance = env
+            .iter()
+            .find(|(key, _)| *key == "Isolate_PIPELINE_GUIDANCE")
+            .map(|(_, path)| std::fs::read_to_string(path).unwrap());
+        assert_eq!(guidance.as_deref(), Some("Hash passwords with argon2"));
+    }
+
+    #[tokio::test]
+    async fn test_stale_feedback_is_removed() {
+        let temp = TempDir::new().unwrap();
+        prepare_workspace(temp.path(), &request(Some("Scenario 1: FAIL")))
+            .await
+            .unwrap();
+
+        let env = prepare_workspace(temp.path(), &request(None))
+            .await
+            .unwrap();
+
+        assert!(!temp.path().join(FEEDBACK_FILE).exists());
+        assert!(env
+            .iter()
+            .all(|(key, _)| *key != "Isolate_PIPELINE_FEEDBACK"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_agent_fails_to_spawn() {
+        let temp = TempDir::new().unwrap();
+        let env = prepare_workspace(temp.path(), &request(None))
+            .await
+            .unwrap();
+
+        let result = run_agent(
+            temp.path(),
+            "isolate-no-such-agent",
+            &[],
+            &env,
+            Duration::from_secs(30),
+        )
+        .await;
+
+        assert!(matches!(result, Err(SpawnError::AgentSpawnFailed { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_agent_is_killed() {
+        let temp = TempDir::new().unwrap();
+        let env = prepare_workspace(temp.path(), &request(None))
+            .await
+            .unwrap();
+        let pid_file = temp.path().join("agent.pid");
+        let child_file = temp.path().join("child.pid");
+
+        let script = format!(
+            "echo $$ > {}; sleep 30 & echo $! > {}; wait",
+            pid_file.display(),
+            child_file.display()
+        );
+        let result = run_agent(
+            temp.path(),
+            "sh",
+            &["-c".to_string(), script],
+            &env,
+            Duration::from_secs(1),
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(SpawnError::Timeout { timeout_secs: 1 })
+        ));
+        for file in [&pid_file, &child_file] {
+            let pid = std::fs::read_to_string(file).unwrap();
+            assert!(
+                crate::commands::process_exited(pid.trim()).await,
+                "process {} still running",
+                pid.trim()
+            );
+        }
+        assert!(!temp.path().join(".isolate/heartbeat").exists());
+    }
+
+    #[tokio::test]
+    async fn test_block_on_inside_current_thread_runtime() {
+        let result = block_on(async { tokio::task::yield_now().await });
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_block_on_inside_multi_thread_runtime() {
+        let result = block_on(async { tokio::task::yield_now().await });
+
+        assert!(result.is_ok());
+    }
+}
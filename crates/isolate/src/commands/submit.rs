@@ -5,13 +5,15 @@
 //!
 //! ## Implementation (bd-1kj, bd-3am, bd-1sh)
 //!
-//! 1. Detects dirty workspace state before submission
-//! 2. Fails with exit code 3 if dirty and --auto-commit not set
-//! 3. Commits automatically if --auto-commit is set
-//! 4. Pushes bookmarks to remote
-//! 5. Extracts stable commit identities (`head_sha`, `change_id`)
-//! 6. Computes `logical_change_id` for deduplication
-//! 7. Returns structured JSON with schema envelope (bd-3am)
+//! 1. Runs the quality gates configured under `[gates]`, failing with exit code 3 (`GATES_FAILED`)
+//!    if any gate fails
+//! 2. Detects dirty workspace state before submission
+//! 3. Fails with exit code 3 if dirty and --auto-commit not set
+//! 4. Commits automatically if --auto-commit is set
+//! 5. Pushes bookmarks to remote
+//! 6. Extracts stable commit identities (`head_sha`, `change_id`)
+//! 7. Computes `logical_change_id` for deduplication
+//! 8. Returns structured JSON with schema envelope (bd-3am)
 
 #![warn(clippy::pedantic)]
 #![warn(clippy::nursery)]
@@ -20,12 +22,14 @@
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
-use isolate_core::{config, jj, json::schemas, OutputFormat};
+use isolate_core::{
+    config, format_failure_message, json::schemas, BranchName, Change, GateResult, OutputFormat,
+    VcsBackend,
+};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use tokio::process::Command;
 
-use crate::commands::{check_in_jj_repo, workspace_utils};
+use crate::commands::{check_in_jj_repo, gates, run_vcs, workspace_utils};
 
 /// Submit-specific errors
 #[derive(Debug, Error, Clone, PartialEq, Eq)]
@@ -107,6 +111,9 @@ pub struct SubmitSuccessData {
     pub dedupe_key: String,
     /// Whether this was a dry run
     pub dry_run: bool,
+    /// Quality gates that passed before the push
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gates: Vec<GateResult>,
 }
 
 /// Error data for submit response (bd-3am contract)
@@ -171,7 +178,7 @@ struct WorkspaceIdentity {
 /// - 0: Success
 /// - 1: General error
 /// - 2: Invalid arguments
-/// - 3: Precondition failed (no bookmark, dirty state)
+/// - 3: Precondition failed (no bookmark, dirty state, failing gates)
 /// - 5: Remote/network errors
 pub async fn run_with_options(options: &SubmitOptions) -> Result<i32> {
     // Check prerequisites
@@ -192,85 +199,161 @@ pub async fn run_with_options(options: &SubmitOptions) -> Result<i32> {
         resolve_workspace_context(&root).await?
     };
 
-    // Extract initial identity information
-    let identity = match extract_workspace_identity(&workspace_info.path).await {
-        Ok(id) => id,
-        Err(e) => {
-            return output_error(
-                options.format.is_json(),
-                "PRECONDITION_FAILED",
-                e.to_string(),
-                3,
-            );
+    let is_json = options.format.is_json();
+
+    // Nothing is pushed unless the configured gates pass
+    let gates = if options.dry_run {
+        Vec::new()
+    } else {
+        match run_quality_gates(&workspace_info.path).await {
+            Ok(gates) => gates,
+            Err(failure) => {
+                return output_error(is_json, failure.code, failure.message, failure.exit_code)
+            }
         }
     };
 
-    // For dry run, output what would happen and exit
+    let path = workspace_info.path.clone();
+    let submit_options = options.clone();
+    let submitted = run_vcs(move |vcs| Ok(submit_workspace(vcs, &path, &submit_options)))
+        .await
+        .unwrap_or_else(|e| Err(SubmitFailure::precondition(e.to_string())));
+
+    match submitted {
+        Ok(SubmitOutcome::DryRun {
+            identity,
+            dedupe_key,
+            dirty,
+        }) => {
+            if !is_json && dirty {
+                println!("Note: Workspace has uncommitted changes.");
+                println!("      These changes WOULD be committed automatically.");
+                println!();
+            }
+            output_dry_run(is_json, &identity, &dedupe_key)
+        }
+        Ok(SubmitOutcome::Submitted {
+            identity,
+            dedupe_key,
+        }) => output_success(is_json, &identity, &dedupe_key, gates),
+        Err(failure) => output_error(is_json, failure.code, failure.message, failure.exit_code),
+    }
+}
+
+/// What a submission that went through did
+#[derive(Debug, Clone)]
+enum SubmitOutcome {
+    /// Nothing was changed; `dirty` work would be auto-committed
+    DryRun {
+        identity: WorkspaceIdentity,
+        dedupe_key: String,
+        dirty: bool,
+    },
+    /// The bookmark was pushed
+    Submitted {
+        identity: WorkspaceIdentity,
+        dedupe_key: String,
+    },
+}
+
+/// A refused or failed submission, as reported to the user
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SubmitFailure {
+    code: &'static str,
+    message: String,
+    exit_code: i32,
+}
+
+impl SubmitFailure {
+    fn precondition(message: impl Into<String>) -> Self {
+        Self {
+            code: "PRECONDITION_FAILED",
+            message: message.into(),
+            exit_code: 3,
+        }
+    }
+
+    fn dirty_workspace(message: &str) -> Self {
+        Self {
+            code: "DIRTY_WORKSPACE",
+            message: message.to_string(),
+            exit_code: 3,
+        }
+    }
+
+    fn gates_failed(summary: &str) -> Self {
+        Self {
+            code: "GATES_FAILED",
+            message: format!("Quality gates failed, refusing to submit: {summary}"),
+            exit_code: 3,
+        }
+    }
+}
+
+/// Run the configured quality gates in the workspace at `workspace_path`
+async fn run_quality_gates(workspace_path: &Path) -> Result<Vec<GateResult>, SubmitFailure> {
+    let outcome = gates::run_configured(workspace_path)
+        .await
+        .map_err(|e| SubmitFailure::gates_failed(&format!("{e:#}")))?;
+
+    if outcome.status.is_failure() {
+        return Err(SubmitFailure::gates_failed(&format_failure_message(
+            &outcome,
+        )));
+    }
+    Ok(outcome.results)
+}
+
+/// Validate, commit if asked and push the workspace at `workspace_path`
+fn submit_workspace(
+    backend: &dyn VcsBackend,
+    workspace_path: &Path,
+    options: &SubmitOptions,
+) -> Result<SubmitOutcome, SubmitFailure> {
+    // Extract initial identity information
+    let identity = extract_workspace_identity(backend, workspace_path)
+        .map_err(|e| SubmitFailure::precondition(e.to_string()))?;
+
+    // For dry run, report what would happen and exit
     if options.dry_run {
-        let is_dirty = is_workspace_dirty(&workspace_info.path)
-            .await
-            .unwrap_or(false);
+        let dirty = is_workspace_dirty(backend, workspace_path).unwrap_or(false);
         let dedupe_key = compute_dedupe_key(&identity.change_id, &identity.workspace_name);
 
         // If it's dirty and we won't auto-commit, the real command would fail.
         // Dry-run should reflect this precondition failure (bd-34k fix).
-        if is_dirty && !options.auto_commit {
-            return output_error(
-                options.format.is_json(),
-                "DIRTY_WORKSPACE",
-                "Working copy has uncommitted changes.\nUse --auto-commit to commit automatically, or run 'jj commit' first. (Dry run validation failure)".to_string(),
-                3,
-            );
-        }
-
-        if !options.format.is_json() && is_dirty {
-            println!("Note: Workspace has uncommitted changes.");
-            println!("      These changes WOULD be committed automatically.");
-            println!();
+        if dirty && !options.auto_commit {
+            return Err(SubmitFailure::dirty_workspace(
+                "Working copy has uncommitted changes.\nUse --auto-commit to commit automatically, or run 'jj commit' first. (Dry run validation failure)",
+            ));
         }
 
-        return output_dry_run(options.format.is_json(), &identity, &dedupe_key);
+        return Ok(SubmitOutcome::DryRun {
+            identity,
+            dedupe_key,
+            dirty,
+        });
     }
 
     // Check dirty workspace state (bd-1sh) - only for real submission
-    match check_and_handle_dirty_state(&workspace_info.path, options).await {
+    match check_and_handle_dirty_state(backend, workspace_path, options) {
         Ok(()) => {}
         Err(SubmitError::DirtyWorkspace) => {
-            return output_error(
-                options.format.is_json(),
-                "DIRTY_WORKSPACE",
-                "Working copy has uncommitted changes.\nUse --auto-commit to commit automatically, or run 'jj commit' first.".to_string(),
-                3,
-            );
-        }
-        Err(e) => {
-            return output_error(
-                options.format.is_json(),
-                "PRECONDITION_FAILED",
-                e.to_string(),
-                3,
-            );
+            return Err(SubmitFailure::dirty_workspace(
+                "Working copy has uncommitted changes.\nUse --auto-commit to commit automatically, or run 'jj commit' first.",
+            ));
         }
+        Err(e) => return Err(SubmitFailure::precondition(e.to_string())),
     }
 
     // Re-extract identity after potential auto-commit to get the new HEAD SHA
-    let identity = match extract_workspace_identity(&workspace_info.path).await {
-        Ok(id) => id,
-        Err(e) => {
-            return output_error(
-                options.format.is_json(),
-                "PRECONDITION_FAILED",
-                e.to_string(),
-                3,
-            );
-        }
-    };
+    let identity = extract_workspace_identity(backend, workspace_path)
+        .map_err(|e| SubmitFailure::precondition(e.to_string()))?;
 
     // Compute final dedupe_key
     let dedupe_key = compute_dedupe_key(&identity.change_id, &identity.workspace_name);
 
     // Push bookmark to remote
-    if let Err(e) = push_bookmark(&identity.bookmark_name, &workspace_info.path).await {
+    if let Err(e) = push_bookmark(backend, &identity.bookmark_name, workspace_path) {
         let error_msg = e.to_string();
         // Check if it's a remote/network error
         let is_remote_error = error_msg.contains("remote")
@@ -285,10 +368,17 @@ pub async fn run_with_options(options: &SubmitOptions) -> Result<i32> {
             ("PRECONDITION_FAILED", 3)
         };
 
-        return output_error(options.format.is_json(), code, error_msg, exit_code);
+        return Err(SubmitFailure {
+            code,
+            message: error_msg,
+            exit_code,
+        });
     }
 
-    output_success(options.format.is_json(), &identity, &dedupe_key)
+    Ok(SubmitOutcome::Submitted {
+        identity,
+        dedupe_key,
+    })
 }
 
 /// Workspace context information
@@ -371,17 +461,21 @@ async fn resolve_workspace_context(root: &Path) -> Result<WorkspaceInfo> {
 /// - `head_sha`: The current commit hash
 /// - `bookmark_name`: The current bookmark name
 /// - `workspace_name`: The workspace name
-async fn extract_workspace_identity(
-    workspace_path: &PathBuf,
+fn extract_workspace_identity(
+    backend: &dyn VcsBackend,
+    workspace_path: &Path,
 ) -> Result<WorkspaceIdentity, SubmitError> {
-    // Get change_id (stable across rebases)
-    let change_id = get_change_id(workspace_path).await?;
-
-    // Get head_sha (current commit hash)
-    let head_sha = get_head_sha(workspace_path).await?;
-
-    // Get bookmark name
-    let bookmark_name = get_current_bookmark(workspace_path).await?;
+    let path = workspace_path.to_string_lossy();
+
+    // The first change in the log is the working-copy commit
+    let head = backend
+        .log(&path, 1)
+        .map_err(|e| SubmitError::IdentityExtractionFailed(format!("jj log failed: {e}")))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            SubmitError::IdentityExtractionFailed("empty change_id returned".to_string())
+        })?;
 
     // Get workspace name from path
     let workspace_name = workspace_path
@@ -390,104 +484,47 @@ async fn extract_workspace_identity(
         .ok_or_else(|| SubmitError::IdentityExtractionFailed("invalid workspace path".to_string()))?
         .to_string();
 
+    let bookmark_name = get_current_bookmark(backend, &path, &head, &workspace_name)?;
+
     Ok(WorkspaceIdentity {
-        change_id,
-        head_sha,
+        change_id: head.id.to_string(),
+        head_sha: head.commit_id.to_string(),
         bookmark_name,
         workspace_name,
     })
 }
 
-/// Get the `change_id` from jj log (stable across rebases)
-async fn get_change_id(workspace_path: &PathBuf) -> Result<String, SubmitError> {
-    let output = Command::new("jj")
-        .args(["log", "-r", "@", "--no-graph", "-T", "change_id"])
-        .current_dir(workspace_path)
-        .output()
-        .await
-        .map_err(|e| SubmitError::IdentityExtractionFailed(format!("failed to run jj log: {e}")))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SubmitError::IdentityExtractionFailed(format!(
-            "jj log failed: {stderr}"
-        )));
-    }
-
-    let change_id = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-    if change_id.is_empty() {
-        return Err(SubmitError::IdentityExtractionFailed(
-            "empty change_id returned".to_string(),
-        ));
-    }
-
-    Ok(change_id)
-}
-
-/// Get the current commit hash (`head_sha`)
-async fn get_head_sha(workspace_path: &PathBuf) -> Result<String, SubmitError> {
-    let output = Command::new("jj")
-        .args(["log", "-r", "@", "--no-graph", "-T", "commit_id"])
-        .current_dir(workspace_path)
-        .output()
-        .await
-        .map_err(|e| SubmitError::IdentityExtractionFailed(format!("failed to run jj log: {e}")))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SubmitError::IdentityExtractionFailed(format!(
-            "jj log failed: {stderr}"
-        )));
-    }
-
-    let head_sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
-
-    if head_sha.is_empty() {
-        return Err(SubmitError::IdentityExtractionFailed(
-            "empty commit_id returned".to_string(),
-        ));
-    }
-
-    Ok(head_sha)
-}
-
-/// Get the current bookmark name
-async fn get_current_bookmark(workspace_path: &PathBuf) -> Result<String, SubmitError> {
-    // Use jj log to get bookmarks pointing to the current revision
-    let output = Command::new("jj")
-        .args(["log", "-r", "@", "--no-graph", "-T", "bookmarks"])
-        .current_dir(workspace_path)
-        .output()
-        .await
-        .map_err(|e| {
-            SubmitError::IdentityExtractionFailed(format!("failed to get current bookmarks: {e}"))
-        })?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SubmitError::IdentityExtractionFailed(format!(
-            "jj log failed: {stderr}"
-        )));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let bookmarks: Vec<&str> = stdout.split_whitespace().collect();
-
-    // Prefer a bookmark that matches the workspace name if possible,
-    // otherwise just take the first one.
-    let workspace_name = workspace_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("");
-
-    let bookmark_name = bookmarks
+/// Get the bookmark pointing at the working-copy commit
+///
+/// Prefers a bookmark that matches the workspace name if possible,
+/// otherwise just takes the first one.
+fn get_current_bookmark(
+    backend: &dyn VcsBackend,
+    path: &str,
+    head: &Change,
+    workspace_name: &str,
+) -> Result<String, SubmitError> {
+    let branches = backend.list_branches(path, false).map_err(|e| {
+        SubmitError::IdentityExtractionFailed(format!("failed to get current bookmarks: {e}"))
+    })?;
+
+    // Bookmark listings show abbreviated commit ids
+    let at_head: Vec<&str> = branches
+        .iter()
+        .filter(|b| {
+            b.commit_id
+                .as_ref()
+                .is_some_and(|id| head.commit_id.as_str().starts_with(id.as_str()))
+        })
+        .map(|b| b.name.as_str())
+        .collect();
+
+    at_head
         .iter()
         .find(|&&b| b == workspace_name)
-        .or_else(|| bookmarks.first())
-        .ok_or(SubmitError::NoBookmark)?;
-
-    Ok((*bookmark_name).to_string())
+        .or_else(|| at_head.first())
+        .map(|b| (*b).to_string())
+        .ok_or(SubmitError::NoBookmark)
 }
 
 /// Compute `dedupe_key` for deduplication
@@ -507,17 +544,16 @@ fn compute_dedupe_key(change_id: &str, workspace_name: &str) -> String {
 /// Returns an error if:
 /// - The jj git push command fails
 /// - Remote is unreachable
-async fn push_bookmark(bookmark_name: &str, workspace_path: &PathBuf) -> Result<(), SubmitError> {
-    let output = Command::new("jj")
-        .args(["git", "push", "--bookmark", bookmark_name])
-        .current_dir(workspace_path)
-        .output()
-        .await
-        .map_err(|e| SubmitError::PushFailed(format!("failed to execute jj git push: {e}")))?;
+fn push_bookmark(
+    backend: &dyn VcsBackend,
+    bookmark_name: &str,
+    workspace_path: &Path,
+) -> Result<(), SubmitError> {
+    let name =
+        BranchName::new(bookmark_name).map_err(|e| SubmitError::PushFailed(e.to_string()))?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let error_msg = stderr.to_string();
+    if let Err(e) = backend.push_branch(&workspace_path.to_string_lossy(), &name) {
+        let error_msg = e.to_string();
 
         // Check for remote/network errors
         if error_msg.contains("could not resolve host")
@@ -546,12 +582,15 @@ async fn push_bookmark(bookmark_name: &str, workspace_path: &PathBuf) -> Result<
 /// Returns an error if:
 /// - JJ status command fails
 /// - Unable to parse status output
-async fn is_workspace_dirty(workspace_path: &Path) -> Result<bool, SubmitError> {
-    let status = jj::workspace_status(workspace_path)
-        .await
+fn is_workspace_dirty(
+    backend: &dyn VcsBackend,
+    workspace_path: &Path,
+) -> Result<bool, SubmitError> {
+    let status = backend
+        .status(&workspace_path.to_string_lossy())
         .map_err(|e| SubmitError::StatusCheckFailed(e.to_string()))?;
 
-    Ok(!status.is_clean())
+    Ok(!status.clean)
 }
 
 /// Check dirty state and handle based on --auto-commit flag (bd-1sh)
@@ -567,11 +606,12 @@ async fn is_workspace_dirty(workspace_path: &Path) -> Result<bool, SubmitError>
 /// Returns an error if:
 /// - Status check fails
 /// - Auto-commit is enabled but commit fails
-async fn check_and_handle_dirty_state(
-    workspace_path: &PathBuf,
+fn check_and_handle_dirty_state(
+    backend: &dyn VcsBackend,
+    workspace_path: &Path,
     options: &SubmitOptions,
 ) -> Result<(), SubmitError> {
-    let is_dirty = is_workspace_dirty(workspace_path).await?;
+    let is_dirty = is_workspace_dirty(backend, workspace_path)?;
 
     if !is_dirty {
         // Clean workspace - proceed with submission
@@ -581,7 +621,7 @@ async fn check_and_handle_dirty_state(
     // Workspace is dirty
     if options.auto_commit {
         // Auto-commit enabled - commit changes and proceed
-        auto_commit_changes(workspace_path, options.message.as_deref()).await
+        auto_commit_changes(backend, workspace_path, options.message.as_deref())
     } else {
         // No auto-commit - fail with explicit error
         Err(SubmitError::DirtyWorkspace)
@@ -596,8 +636,9 @@ async fn check_and_handle_dirty_state(
 ///
 /// Returns an error if:
 /// - JJ commit command fails
-async fn auto_commit_changes(
-    workspace_path: &PathBuf,
+fn auto_commit_changes(
+    backend: &dyn VcsBackend,
+    workspace_path: &Path,
     message: Option<&str>,
 ) -> Result<(), SubmitError> {
     let commit_message = message.map_or_else(
@@ -605,21 +646,10 @@ async fn auto_commit_changes(
         std::string::ToString::to_string,
     );
 
-    let output = Command::new("jj")
-        .args(["commit", "-m", &commit_message])
-        .current_dir(workspace_path)
-        .output()
-        .await
-        .map_err(|e| SubmitError::AutoCommitFailed(format!("failed to execute jj commit: {e}")))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(SubmitError::AutoCommitFailed(format!(
-            "jj commit failed: {stderr}"
-        )));
-    }
-
-    Ok(())
+    backend
+        .commit(&workspace_path.to_string_lossy(), &commit_message)
+        .map(|_| ())
+        .map_err(|e| SubmitError::AutoCommitFailed(format!("jj commit failed: {e}")))
 }
 
 /// Output for dry run (bd-3am contract)
@@ -632,6 +662,7 @@ fn output_dry_run(is_json: bool, identity: &WorkspaceIdentity, dedupe_key: &str)
             head_sha: identity.head_sha.clone(),
             dedupe_key: dedupe_key.to_string(),
             dry_run: true,
+            gates: Vec::new(),
         };
 
         let response = SubmitResponse::success(data);
@@ -654,7 +685,12 @@ fn output_dry_run(is_json: bool, identity: &WorkspaceIdentity, dedupe_key: &str)
 }
 
 /// Output for successful submission (bd-3am contract)
-fn output_success(is_json: bool, identity: &WorkspaceIdentity, dedupe_key: &str) -> Result<i32> {
+fn output_success(
+    is_json: bool,
+    identity: &WorkspaceIdentity,
+    dedupe_key: &str,
+    gates: Vec<GateResult>,
+) -> Result<i32> {
     if is_json {
         let data = SubmitSuccessData {
             workspace: identity.workspace_name.clone(),
@@ -663,6 +699,7 @@ fn output_success(is_json: bool, identity: &WorkspaceIdentity, dedupe_key: &str)
             head_sha: identity.head_sha.clone(),
             dedupe_key: dedupe_key.to_string(),
             dry_run: false,
+            gates,
         };
 
         let response = SubmitResponse::success(data);
@@ -676,6 +713,9 @@ fn output_success(is_json: bool, identity: &WorkspaceIdentity, dedupe_key: &str)
         println!("  Change ID: {}", identity.change_id);
         println!("  HEAD SHA: {}", identity.head_sha);
         println!("  Dedupe Key: {dedupe_key}");
+        if !gates.is_empty() {
+            println!("  Gates passed: {}", gates::describe_passed(&gates));
+        }
     }
 
     Ok(0)
@@ -766,6 +806,7 @@ mod tests {
             head_sha: "abc123def456".to_string(),
             dedupe_key: "feature-xyz:kxyz123".to_string(),
             dry_run: false,
+            gates: Vec::new(),
         };
 
         let response = SubmitResponse::success(data);
@@ -777,6 +818,25 @@ mod tests {
         assert!(response.error.is_none());
     }
 
+    #[test]
+    fn test_gates_failure_reports_summary() {
+        let outcome = isolate_core::collect_results(vec![GateResult::failed(
+            "lint",
+            2,
+            String::new(),
+            "error: unused import".to_string(),
+        )]);
+
+        let failure = SubmitFailure::gates_failed(&format_failure_message(&outcome));
+
+        assert_eq!(failure.code, "GATES_FAILED");
+        assert_eq!(failure.exit_code, 3);
+        assert_eq!(
+            failure.message,
+            "Quality gates failed, refusing to submit: Lint gate failed (exit code 2): error: unused import"
+        );
+    }
+
     #[test]
     fn test_submit_response_error_schema() {
         let response = SubmitResponse::error("PUSH_FAILED", "Failed to push to remote");
@@ -805,6 +865,7 @@ mod tests {
             head_sha: "abc123def456".to_string(),
             dedupe_key: "feature-xyz:kxyz123".to_string(),
             dry_run: true,
+            gates: Vec::new(),
         };
 
         let response = SubmitResponse::success(data);
@@ -826,6 +887,7 @@ mod tests {
             head_sha: "abc123".to_string(),
             dedupe_key: "feature-xyz:kxyz123".to_string(),
             dry_run: false,
+            gates: Vec::new(),
         };
 
         let response = SubmitResponse::success(data);
@@ -910,6 +972,7 @@ mod tests {
             head_sha: "h".to_string(),
             dedupe_key: "ws:c".to_string(),
             dry_run: true,
+            gates: Vec::new(),
         };
 
         let response = SubmitResponse::success(data);
@@ -1013,4 +1076,152 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.ok(), Some(3));
     }
+
+    // ── Submission against the in-memory repository ─────────────────────
+
+    mod fake_repo {
+        use isolate_core::vcs::{FakeBackend, FakeFailure, FakeOp};
+
+        use super::*;
+
+        const WORKSPACE: &str = "feature";
+        const WORKSPACE_PATH: &str = "/repo/.isolate/workspaces/feature";
+
+        fn options(auto_commit: bool, dry_run: bool) -> SubmitOptions {
+            SubmitOptions {
+                name: Some(WORKSPACE.to_string()),
+                format: OutputFormat::Json,
+                dry_run,
+                auto_commit,
+                message: None,
+            }
+        }
+
+        /// A session workspace with its bookmark on the working-copy commit
+        fn repo_with_bookmark() -> FakeBackend {
+            let repo = FakeBackend::new("/repo");
+            repo.add_workspace_at("default", WORKSPACE, WORKSPACE_PATH, Some("main"))
+                .expect("add workspace");
+            repo.create_bookmark(WORKSPACE, WORKSPACE, "@")
+                .expect("create bookmark");
+            repo
+        }
+
+        fn submit(
+            repo: &FakeBackend,
+            options: &SubmitOptions,
+        ) -> Result<SubmitOutcome, SubmitFailure> {
+            submit_workspace(repo, Path::new(WORKSPACE_PATH), options)
+        }
+
+        #[test]
+        fn test_clean_workspace_pushes_bookmark() {
+            let repo = repo_with_bookmark();
+
+            let outcome = submit(&repo, &options(false, false));
+
+            let Ok(SubmitOutcome::Submitted {
+                identity,
+                dedupe_key,
+            }) = outcome
+            else {
+                panic!("expected submission, got {outcome:?}");
+            };
+            assert_eq!(identity.bookmark_name, WORKSPACE);
+            assert_eq!(dedupe_key, format!("{WORKSPACE}:{}", identity.change_id));
+            assert_eq!(repo.remote_bookmark(WORKSPACE), Some(identity.head_sha));
+        }
+
+        #[test]
+        fn test_dirty_workspace_is_refused_without_auto_commit() {
+            let repo = repo_with_bookmark();
+            repo.write_file(WORKSPACE, "feature.rs", "fn feature() {}\n")
+                .expect("write");
+
+            let failure = submit(&repo, &options(false, false)).expect_err("dirty submit");
+
+            assert_eq!(failure.code, "DIRTY_WORKSPACE");
+            assert_eq!(failure.exit_code, 3);
+            assert_eq!(repo.remote_bookmark(WORKSPACE), None);
+        }
+
+        #[test]
+        fn test_dry_run_reports_dirty_workspace_without_committing() {
+            let repo = repo_with_bookmark();
+            repo.write_file(WORKSPACE, "feature.rs", "fn feature() {}\n")
+                .expect("write");
+            let head = repo.working_copy(WORKSPACE).expect("working copy");
+
+            let outcome = submit(&repo, &options(true, true));
+
+            assert!(matches!(
+                outcome,
+                Ok(SubmitOutcome::DryRun { dirty: true, .. })
+            ));
+            assert_eq!(repo.working_copy(WORKSPACE).expect("working copy"), head);
+            assert_eq!(repo.remote_bookmark(WORKSPACE), None);
+        }
+
+        #[test]
+        fn test_auto_commit_commits_pending_changes() {
+            let repo = repo_with_bookmark();
+            repo.write_file(WORKSPACE, "feature.rs", "fn feature() {}\n")
+                .expect("write");
+
+            check_and_handle_dirty_state(&repo, Path::new(WORKSPACE_PATH), &options(true, false))
+                .expect("auto-commit");
+
+            let committed = repo.resolve(WORKSPACE, "@-").expect("committed change");
+            assert_eq!(committed.description, "wip: auto-commit before submit");
+            assert!(committed.tree.contains_key("feature.rs"));
+            assert!(!is_workspace_dirty(&repo, Path::new(WORKSPACE_PATH)).expect("status"));
+        }
+
+        #[test]
+        fn test_rejected_push_is_a_precondition_failure() {
+            let repo = repo_with_bookmark();
+            repo.fail_next(
+                FakeOp::Push,
+                FakeFailure::PushRejected("bookmark moved unexpectedly".to_string()),
+            );
+
+            let failure = submit(&repo, &options(false, false)).expect_err("rejected push");
+
+            assert_eq!(failure.code, "PRECONDITION_FAILED");
+            assert_eq!(failure.exit_code, 3);
+            assert!(
+                failure.message.contains("push rejected"),
+                "{}",
+                failure.message
+            );
+        }
+
+        #[test]
+        fn test_unreachable_remote_is_a_remote_error() {
+            let repo = repo_with_bookmark();
+            repo.fail_next(
+                FakeOp::Push,
+                FakeFailure::Error("connection refused".to_string()),
+            );
+
+            let failure = submit(&repo, &options(false, false)).expect_err("unreachable remote");
+
+            assert_eq!(failure.code, "REMOTE_ERROR");
+            assert_eq!(failure.exit_code, 5);
+        }
+
+        #[test]
+        fn test_missing_bookmark_is_a_precondition_failure() {
+            let repo = FakeBackend::new("/repo");
+            repo.add_workspace_at("default", WORKSPACE, WORKSPACE_PATH, Some("main"))
+                .expect("add workspace");
+
+            let failure = submit(&repo, &options(false, false)).expect_err("no bookmark");
+
+            assert_eq!(
+                failure,
+                SubmitFailure::precondition(SubmitError::NoBookmark.to_string())
+            );
+        }
+    }
 }
@@ -51,13 +51,16 @@ use isolate_core::{
         IssueSeverity, IssueTitle, Message, OutputLine, ResultKind, ResultOutput, Summary,
         SummaryType,
     },
-    OutputFormat,
+    BackendType, BranchName, GitBackend, OutputFormat, VcsBackend,
 };
 use tokio::process::Command;
 
 use crate::{
-    cli::run_command,
-    commands::{determine_main_branch, get_session_db},
+    commands::{
+        determine_main_branch,
+        done::executor::{JjExecutor, RealJjExecutor},
+        get_session_db, restack,
+    },
     session::SessionUpdate,
 };
 
@@ -140,6 +143,10 @@ fn emit_result(success: bool, kind: ResultKind, message: &str) -> Result<()> {
 /// Returns `Ok(None)` if in main repo (not a workspace)
 /// Returns `Err` if not in a JJ repo at all
 async fn detect_workspace_context() -> Result<Option<String>> {
+    if crate::commands::in_git_only_repo() {
+        return tokio::task::spawn_blocking(detect_worktree_context).await?;
+    }
+
     // Try to get workspace root - this works from both main repo and workspace
     let output = Command::new("jj")
         .args(["workspace", "root"])
@@ -192,6 +199,19 @@ async fn detect_workspace_context() -> Result<Option<String>> {
     Ok(None)
 }
 
+/// Git counterpart of [`detect_workspace_context`]
+///
+/// A linked worktree reports the root of the main worktree it belongs to.
+fn detect_worktree_context() -> Result<Option<String>> {
+    let cwd = std::env::current_dir().context("Failed to get current directory")?;
+    let cwd = cwd.to_string_lossy();
+    let git = GitBackend::new();
+    let workspace_root = git.workspace_root(&cwd).map_err(anyhow::Error::new)?;
+    let main_root = git.main_worktree_root(&cwd).map_err(anyhow::Error::new)?;
+
+    Ok((Path::new(&workspace_root) != Path::new(&main_root)).then_some(main_root))
+}
+
 /// Get session database, handling both main repo and workspace contexts
 ///
 /// This function detects if we're in a workspace and routes to the main repo database.
@@ -369,19 +389,27 @@ async fn sync_current_workspace(options: SyncOptions) -> Result<()> {
 /// Returns `Ok(Some(name))` if in a workspace, `Ok(None)` if in main repo
 async fn detect_current_workspace_name() -> Result<Option<String>> {
     // 1. Get workspace root from jj workspace root
-    let output = Command::new("jj")
-        .args(["workspace", "root"])
-        .output()
-        .await
-        .context("Failed to run 'jj workspace root'")?;
-
-    if !output.status.success() {
-        // If command failed, likely not in a repo.
-        // If we return Ok(None), sync_all will run and fail with "Not in a JJ repo".
-        return Ok(None);
-    }
+    let workspace_root = if crate::commands::in_git_only_repo() {
+        let cwd = std::env::current_dir().context("Failed to get current directory")?;
+        match GitBackend::new().workspace_root(&cwd.to_string_lossy()) {
+            Ok(root) => root,
+            Err(_) => return Ok(None),
+        }
+    } else {
+        let output = Command::new("jj")
+            .args(["workspace", "root"])
+            .output()
+            .await
+            .context("Failed to run 'jj workspace root'")?;
+
+        if !output.status.success() {
+            // If command failed, likely not in a repo.
+            // If we return Ok(None), sync_all will run and fail with "Not in a JJ repo".
+            return Ok(None);
+        }
 
-    let workspace_root = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
     // Normalize path for comparison
     let workspace_path = std::fs::canonicalize(&workspace_root)
         .unwrap_or_else(|_| std::path::PathBuf::from(&workspace_root));
@@ -423,6 +451,13 @@ async fn sync_session_with_options(name: &str, options: SyncOptions) -> Result<(
     // Use internal sync function
     sync_session_internal(&db, &session.name, &session.workspace_path, options.dry_run).await?;
 
+    // Sessions stacked on this one follow it
+    let restacked = if options.dry_run {
+        Vec::new()
+    } else {
+        restack::restack_descendants(&db, &RealJjExecutor::new(), name, false).await?
+    };
+
     if options.format.is_json() {
         // Emit Action for the sync operation
         emit_action(
@@ -431,6 +466,7 @@ async fn sync_session_with_options(name: &str, options: SyncOptions) -> Result<(
             ActionStatus::Completed,
             Some(&format!("Synced session '{name}' with main")),
         )?;
+        restack::emit_restacked(&restacked, options.format)?;
         // Emit Result for command completion
         emit_result(
             true,
@@ -439,6 +475,7 @@ async fn sync_session_with_options(name: &str, options: SyncOptions) -> Result<(
         )?;
     } else if !options.dry_run {
         println!("Synced session '{name}' with main");
+        restack::emit_restacked(&restacked, options.format)?;
         println!();
         println!("NEXT: Continue working, or if done:");
         println!("  isolate done          # Merge to main + cleanup");
@@ -565,6 +602,11 @@ async fn sync_all_jsonl(
     let synced_count = synced.len();
     let failed_count = failed.len();
 
+    if !options.dry_run {
+        let restacked = restack::restack_all(db, &RealJjExecutor::new()).await?;
+        restack::emit_restacked(&restacked, options.format)?;
+    }
+
     // Emit Summary with counts
     emit_summary(
         SummaryType::Count,
@@ -638,6 +680,11 @@ async fn sync_all_text(
         )
         .await;
 
+    if !options.dry_run {
+        let restacked = restack::restack_all(db, &RealJjExecutor::new()).await?;
+        restack::emit_restacked(&restacked, options.format)?;
+    }
+
     println!();
     println!("Summary: {success_count} succeeded, {failure_count} failed");
 
@@ -652,28 +699,16 @@ async fn sync_all_text(
     Ok(())
 }
 
-/// Internal function to sync a session's workspace
-async fn sync_session_internal(
-    db: &crate::db::SessionDb,
-    name: &str,
-    workspace_path: &str,
-    dry_run: bool,
-) -> Result<()> {
-    let main_branch = determine_main_branch(Path::new(workspace_path)).await;
-
-    if dry_run {
-        println!("Would sync workspace '{workspace_path}' with main branch '{main_branch}'");
-        return Ok(());
-    }
-
-    // Acquire global sync lock to prevent concurrent JJ operations
-    // This serializes syncs across all isolate processes for this repo
+/// Acquire the repository-wide sync lock
+///
+/// This serializes syncs across all isolate processes for this repo, including
+/// the merge queue processor. The lock is held until the returned file is dropped.
+pub async fn acquire_sync_lock() -> Result<std::fs::File> {
     let data_dir = crate::commands::isolate_data_dir().await?;
     let lock_path = data_dir.join("sync.lock");
 
     // Use blocking lock in a separate task to avoid blocking the runtime
-    // The file handle (_lock) keeps the lock held until it is dropped
-    let _lock = tokio::task::spawn_blocking(move || -> Result<std::fs::File> {
+    tokio::task::spawn_blocking(move || -> Result<std::fs::File> {
         let file = std::fs::OpenOptions::new()
             .read(true)
             .write(true)
@@ -687,19 +722,68 @@ async fn sync_session_internal(
         Ok(file)
     })
     .await
-    .context("Failed to join locking task")??;
+    .context("Failed to join locking task")?
+}
+
+/// Internal function to sync a session's workspace
+///
+/// A session stacked on another live session syncs onto its parent instead of main.
+async fn sync_session_internal(
+    db: &crate::db::SessionDb,
+    name: &str,
+    workspace_path: &str,
+    dry_run: bool,
+) -> Result<()> {
+    let main_branch = determine_main_branch(Path::new(workspace_path)).await;
+    let git = isolate_core::discover_backend(Path::new(workspace_path)) == Some(BackendType::Git);
+    let parent = restack::live_parent(db, name).await?;
+
+    if dry_run {
+        match &parent {
+            Some(parent) => {
+                println!("Would sync workspace '{workspace_path}' with parent session '{parent}'");
+            }
+            None => {
+                println!(
+                    "Would sync workspace '{workspace_path}' with main branch '{main_branch}'"
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    let onto = parent.map_or(main_branch, |parent| restack::parent_revision(&parent, git));
+
+    // Acquire global sync lock to prevent concurrent JJ operations
+    // The file handle (_lock) keeps the lock held until it is dropped
+    let _lock = acquire_sync_lock().await?;
 
-    // Run rebase in the session's workspace
+    if git {
+        rebase_worktree(workspace_path, &onto).await?;
+        return record_synced(db, name).await;
+    }
+
+    rebase_jj_workspace(&RealJjExecutor::new(), workspace_path, &onto).await?;
+
+    record_synced(db, name).await
+}
+
+/// Rebase a jj workspace onto `main_branch`, retrying failed attempts
+///
+/// Conflicts do not fail the rebase: jj records them in the rebased commits.
+async fn rebase_jj_workspace(
+    executor: &dyn JjExecutor,
+    workspace_path: &str,
+    main_branch: &str,
+) -> Result<()> {
     let mut attempt = 0;
     let max_attempts = 3;
     let mut last_error = None;
 
     while attempt < max_attempts {
-        let result = run_command(
-            "jj",
-            &["--repository", workspace_path, "rebase", "-d", &main_branch],
-        )
-        .await;
+        let result = executor
+            .run(&["--repository", workspace_path, "rebase", "-d", main_branch])
+            .await;
 
         match result {
             Ok(_) => {
@@ -724,6 +808,29 @@ async fn sync_session_internal(
         return Err(e).context("Failed to sync workspace with main after retries");
     }
 
+    Ok(())
+}
+
+/// Rebase the branch checked out in a git worktree onto `onto`
+///
+/// A conflicting rebase is aborted, leaving the worktree untouched.
+pub(super) async fn rebase_worktree(workspace_path: &str, onto: &str) -> Result<()> {
+    let workspace_path = workspace_path.to_string();
+    let onto = BranchName::new(onto).map_err(anyhow::Error::new)?;
+    tokio::task::spawn_blocking(move || {
+        let git = GitBackend::new();
+        let branch = git
+            .current_branch(&workspace_path)
+            .map_err(anyhow::Error::new)?;
+        git.rebase(&workspace_path, &branch, &onto)
+            .map_err(anyhow::Error::new)
+            .with_context(|| format!("Failed to rebase workspace onto {onto}"))
+    })
+    .await?
+}
+
+/// Record a successful sync on the session
+async fn record_synced(db: &crate::db::SessionDb, name: &str) -> Result<()> {
     // Update last_synced timestamp
     let now = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
@@ -1381,4 +1488,104 @@ mod tests {
         assert_eq!(SyncBehavior::NamedSession, SyncBehavior::NamedSession);
         assert_ne!(SyncBehavior::NamedSession, SyncBehavior::AllSessions);
     }
+
+    // ============================================================================
+    // jj rebase against the in-memory repository
+    // ============================================================================
+
+    mod jj_rebase {
+        use isolate_core::vcs::{FakeBackend, FakeFailure, FakeOp};
+
+        use super::super::rebase_jj_workspace;
+        use crate::commands::done::fake_executor::FakeJjExecutor;
+
+        const WORKSPACE: &str = "feature";
+        const WORKSPACE_PATH: &str = "/repo/.isolate/workspaces/feature";
+
+        /// A session with one commit of its own, while main moved on
+        fn diverged_repo(main_file: (&str, &str)) -> anyhow::Result<FakeBackend> {
+            let repo = FakeBackend::new("/repo");
+            repo.commit_on("main", "Initial commit", &[("lib.rs", "fn lib() {}\n")])?;
+            repo.add_workspace_at("default", WORKSPACE, WORKSPACE_PATH, Some("main"))?;
+            repo.write_file(WORKSPACE, "lib.rs", "fn lib() { feature() }\n")?;
+            repo.commit_workspace(WORKSPACE, "Change lib")?;
+            repo.commit_on("main", "Concurrent change", &[main_file])?;
+            Ok(repo)
+        }
+
+        #[tokio::test]
+        async fn test_rebases_session_onto_main() -> anyhow::Result<()> {
+            let repo = diverged_repo(("other.rs", "fn other() {}\n"))?;
+            let executor = FakeJjExecutor::new(repo.clone());
+
+            rebase_jj_workspace(&executor, WORKSPACE_PATH, "main").await?;
+
+            let main = repo.bookmark("main").unwrap_or_default();
+            assert!(repo.is_ancestor(&main, &repo.working_copy(WORKSPACE)?.commit_id));
+            assert_eq!(
+                repo.file(WORKSPACE, "other.rs").as_deref(),
+                Some("fn other() {}\n")
+            );
+            assert_eq!(
+                repo.file(WORKSPACE, "lib.rs").as_deref(),
+                Some("fn lib() { feature() }\n")
+            );
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn test_retries_transient_failures() -> anyhow::Result<()> {
+            let repo = diverged_repo(("other.rs", "fn other() {}\n"))?;
+            repo.fail_times(
+                FakeOp::Rebase,
+                2,
+                FakeFailure::Error("concurrent operation".to_string()),
+            );
+            let executor = FakeJjExecutor::new(repo.clone());
+
+            rebase_jj_workspace(&executor, WORKSPACE_PATH, "main").await?;
+
+            assert_eq!(
+                repo.file(WORKSPACE, "other.rs").as_deref(),
+                Some("fn other() {}\n")
+            );
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn test_stale_working_copy_fails_after_retries() -> anyhow::Result<()> {
+            let repo = diverged_repo(("other.rs", "fn other() {}\n"))?;
+            repo.mark_stale(WORKSPACE);
+            let executor = FakeJjExecutor::new(repo.clone());
+
+            let result = rebase_jj_workspace(&executor, WORKSPACE_PATH, "main").await;
+
+            let message = format!(
+                "{:#}",
+                result
+                    .err()
+                    .ok_or_else(|| anyhow::anyhow!("sync succeeded"))?
+            );
+            assert!(
+                message.contains("after retries"),
+                "unexpected error: {message}"
+            );
+            assert!(message.contains("stale"), "unexpected error: {message}");
+            assert_eq!(repo.file(WORKSPACE, "other.rs"), None);
+            Ok(())
+        }
+
+        #[tokio::test]
+        async fn test_conflicts_are_recorded_not_fatal() -> anyhow::Result<()> {
+            let repo = diverged_repo(("lib.rs", "fn lib2() {}\n"))?;
+            let executor = FakeJjExecutor::new(repo.clone());
+
+            rebase_jj_workspace(&executor, WORKSPACE_PATH, "main").await?;
+
+            let conflicted = repo.log_revset(WORKSPACE, "conflicts()")?;
+            assert_ne!(conflicted.len(), 0);
+            assert!(conflicted.iter().all(|c| c.conflicts.contains("lib.rs")));
+            Ok(())
+        }
+    }
 }
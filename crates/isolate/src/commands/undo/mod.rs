@@ -39,6 +39,14 @@ const WORKSPACE_RETENTION_HOURS: u64 = 24;
 
 /// Run the undo command with options
 pub async fn run_with_options(options: &UndoOptions) -> Result<UndoExitCode, UndoError> {
+    if let Err(e) = crate::commands::require_jj("Undo") {
+        let error = UndoError::InvalidState {
+            reason: e.to_string(),
+        };
+        output_error(&error, options.format);
+        return Err(error);
+    }
+
     // Handle list mode
     if options.list {
         return run_list(options).await;
@@ -4,11 +4,12 @@
 //! - `isolate wait session-exists <name>` - Wait for session to exist
 //! - `isolate wait session-unlocked <name>` - Wait for session to be unlocked
 //! - `isolate wait healthy` - Wait for system to be healthy
+//! - `isolate wait queue-landed <name>` - Wait for a queued session to land on main
 
 use std::time::{Duration, Instant};
 
 use anyhow::{Context, Result};
-use isolate_core::{json::SchemaEnvelope, OutputFormat};
+use isolate_core::{coordination::QueueStore, json::SchemaEnvelope, OutputFormat, QueueStatus};
 use serde::Serialize;
 
 use super::get_session_db;
@@ -33,6 +34,8 @@ pub enum WaitCondition {
     Healthy,
     /// Wait for session to reach a specific status
     SessionStatus { name: String, status: String },
+    /// Wait for a session's merge queue entry to land
+    QueueLanded(String),
 }
 
 /// Wait result output
@@ -87,6 +90,14 @@ pub async fn run(options: &WaitOptions) -> Result<i32> {
             );
         }
 
+        // Stop early if the condition can no longer be met
+        if cannot_be_met(&options.condition, state.as_deref()) {
+            return output_result(
+                &make_output(false, &options.condition, start, false, state),
+                options.format,
+            );
+        }
+
         // Check timeout
         if start.elapsed() >= options.timeout {
             return output_result(
@@ -167,6 +178,32 @@ async fn check_condition(condition: &WaitCondition) -> Result<(bool, Option<Stri
             },
             Err(_) => Ok((false, Some("db_unavailable".to_string()))),
         },
+
+        WaitCondition::QueueLanded(name) => match get_session_db().await {
+            Ok(db) => match QueueStore::new(db.pool().clone()).find_by_session(name).await {
+                Ok(Some(entry)) => Ok((
+                    entry.status == QueueStatus::Merged,
+                    Some(format!("queue:{}", entry.status)),
+                )),
+                Ok(None) => Ok((false, Some("not_queued".to_string()))),
+                Err(_) => Ok((false, Some("error".to_string()))),
+            },
+            Err(_) => Ok((false, Some("db_unavailable".to_string()))),
+        },
+    }
+}
+
+/// Whether the observed state rules the condition out for good
+///
+/// A queue entry that failed or was cancelled will not land without being
+/// queued again, so waiting longer cannot help.
+fn cannot_be_met(condition: &WaitCondition, state: Option<&str>) -> bool {
+    match condition {
+        WaitCondition::QueueLanded(_) => state
+            .and_then(|s| s.strip_prefix("queue:"))
+            .and_then(|status| status.parse::<QueueStatus>().ok())
+            .is_some_and(|status| status.is_failed() || status == QueueStatus::Cancelled),
+        _ => false,
     }
 }
 
@@ -179,6 +216,7 @@ fn format_condition(condition: &WaitCondition) -> String {
         WaitCondition::SessionStatus { name, status } => {
             format!("session-status:{name}={status}")
         }
+        WaitCondition::QueueLanded(name) => format!("queue-landed:{name}"),
     }
 }
 
@@ -228,6 +266,22 @@ mod tests {
         assert_eq!(format_condition(&cond), "healthy");
     }
 
+    #[test]
+    fn test_format_condition_queue_landed() {
+        let cond = WaitCondition::QueueLanded("feature".to_string());
+        assert_eq!(format_condition(&cond), "queue-landed:feature");
+    }
+
+    #[test]
+    fn test_failed_queue_entry_stops_waiting() {
+        let cond = WaitCondition::QueueLanded("feature".to_string());
+        assert!(cannot_be_met(&cond, Some("queue:failed_retryable")));
+        assert!(cannot_be_met(&cond, Some("queue:cancelled")));
+        assert!(!cannot_be_met(&cond, Some("queue:testing")));
+        assert!(!cannot_be_met(&cond, Some("not_queued")));
+        assert!(!cannot_be_met(&WaitCondition::Healthy, Some("queue:cancelled")));
+    }
+
     #[test]
     fn test_wait_output_serializes() {
         let output = WaitOutput {
@@ -265,9 +265,7 @@ impl SessionDb {
         match init_schema(&pool).await {
             Ok(()) => {
                 check_schema_version(&pool).await?;
-                // Initialize lock manager tables (CRIT-001 fix)
-                let lock_mgr = isolate_core::coordination::locks::LockManager::new(pool.clone());
-                lock_mgr.init().await?;
+                init_coordination_schema(&pool).await?;
                 Ok(Self { pool })
             }
             Err(e) => {
@@ -278,10 +276,7 @@ impl SessionDb {
                         let new_pool = create_connection_pool(&db_url).await?;
                         init_schema(&new_pool).await?;
                         check_schema_version(&new_pool).await?;
-                        // Initialize lock manager tables (CRIT-001 fix)
-                        let lock_mgr =
-                            isolate_core::coordination::locks::LockManager::new(new_pool.clone());
-                        lock_mgr.init().await?;
+                        init_coordination_schema(&new_pool).await?;
                         Ok(Self { pool: new_pool })
                     }
                     Err(recovery_err) => Err(Error::DatabaseError(format!(
@@ -619,8 +614,16 @@ impl SessionDb {
     }
 
     /// Delete a session by name
+    ///
+    /// Sessions stacked on it are restacked onto its parent.
     pub async fn delete(&self, name: &str) -> Result<bool> {
-        delete_session(&self.pool, name).await
+        let deleted = delete_session(&self.pool, name).await?;
+        if deleted {
+            isolate_core::coordination::StackStore::new(self.pool.clone())
+                .remove(name)
+                .await?;
+        }
+        Ok(deleted)
     }
 
     /// Rename a session
@@ -659,6 +662,10 @@ impl SessionDb {
         })
         .await?;
 
+        isolate_core::coordination::StackStore::new(self.pool.clone())
+            .rename(old_name, new_name)
+            .await?;
+
         self.get(new_name)
             .await?
             .ok_or_else(|| Error::DatabaseError("Session not found after rename".to_string()))
@@ -1327,6 +1334,23 @@ async fn enable_wal_mode(pool: &SqlitePool) -> Result<()> {
     Ok(())
 }
 
+/// Initialize the coordination tables that share `state.db` with sessions
+async fn init_coordination_schema(pool: &SqlitePool) -> Result<()> {
+    // Initialize lock manager tables (CRIT-001 fix)
+    isolate_core::coordination::locks::LockManager::new(pool.clone())
+        .init()
+        .await?;
+    isolate_core::coordination::QueueStore::new(pool.clone())
+        .init()
+        .await?;
+    isolate_core::coordination::StackStore::new(pool.clone())
+        .init()
+        .await?;
+    isolate_core::coordination::GateCache::new(pool.clone())
+        .init()
+        .await
+}
+
 async fn init_schema(pool: &SqlitePool) -> Result<()> {
     sqlx::query(SCHEMA)
         .execute(pool)
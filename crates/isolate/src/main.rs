@@ -96,8 +96,9 @@ async fn main() {
     // HARD REQUIREMENT: JJ must be installed
 
     // AI agents that don't have JJ cannot use isolate - period.
+    // The one exception is a plain git repository, served by git worktrees.
 
-    if !cli::is_jj_installed().await {
+    if !cli::is_jj_installed().await && !commands::in_git_only_repo() {
         #[allow(clippy::print_stderr)]
         {
             eprintln!();
@@ -128,7 +129,7 @@ async fn main() {
 
             eprintln!("Isolate is built on top of JJ for workspace isolation.");
 
-            eprintln!("There is NO workaround - JJ is required.");
+            eprintln!("Only plain git repositories work without it, through git worktrees.");
 
             eprintln!();
         }
@@ -0,0 +1,82 @@
+//! Agent driver for the agent development phase
+//!
+//! The orchestrator does not know how sessions are created or agents run;
+//! that lives in the `isolate` CLI. [`AgentDriver`] is the seam: the
+//! executor hands it one [`AgentRequest`] per iteration and records the
+//! session it reports back on the pipeline.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::state::{Pipeline, TwinInstance};
+
+/// Everything an agent needs for one development iteration
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentRequest {
+    pub pipeline_id: String,
+    pub spec_path: String,
+    /// 1-based iteration about to run
+    pub iteration: u32,
+    /// Session from an earlier iteration, to keep working in
+    pub session: Option<String>,
+    /// Sanitized scenario feedback from the last validation, if it failed
+    pub feedback: Option<String>,
+    /// Guidance from the human who resumed the pipeline, if any
+    #[serde(default)]
+    pub guidance: Option<String>,
+    /// The universe the agent's work is validated against
+    pub twins: Vec<TwinInstance>,
+}
+
+impl AgentRequest {
+    /// Request for the next iteration of `pipeline`
+    #[must_use]
+    pub fn for_pipeline(pipeline: &Pipeline) -> Self {
+        Self {
+            pipeline_id: pipeline.id.0.clone(),
+            spec_path: pipeline.spec_path.clone(),
+            iteration: pipeline.iteration + 1,
+            session: pipeline.session.clone(),
+            feedback: pipeline.feedback.clone(),
+            guidance: pipeline.guidance.clone(),
+            twins: pipeline.twins.clone(),
+        }
+    }
+}
+
+/// What an agent run produced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AgentOutcome {
+    /// Session the agent worked in
+    pub session: String,
+    /// Agent exit code; `None` if it was killed by a signal
+    pub exit_code: Option<i32>,
+}
+
+impl AgentOutcome {
+    /// Whether the agent exited cleanly
+    #[must_use]
+    pub const fn succeeded(&self) -> bool {
+        matches!(self.exit_code, Some(0))
+    }
+}
+
+/// Errors driving an agent
+#[derive(Debug, Error)]
+pub enum AgentError {
+    #[error("Failed to prepare session {session}: {reason}")]
+    Session { session: String, reason: String },
+
+    #[error("Failed to run agent: {0}")]
+    Spawn(String),
+}
+
+/// Creates sessions and runs the agent in them
+pub trait AgentDriver: Send {
+    /// Run one development iteration and wait for the agent to finish
+    ///
+    /// # Errors
+    /// Returns an error if the session cannot be prepared or the agent
+    /// cannot be started.
+    fn develop(&mut self, request: &AgentRequest) -> Result<AgentOutcome, AgentError>;
+}
@@ -0,0 +1,307 @@
+//! Human escalation inbox
+//!
+//! Pipelines the orchestrator cannot settle on its own end up
+//! [`PipelineState::Escalated`]. [`inbox`] lists them with why they were
+//! escalated and the last sanitized feedback the agent saw. A human then
+//! settles each one: [`resume`] sends it back to agent development with
+//! guidance and more iterations, [`accept`] and [`fail`] end it. Every such
+//! call is recorded on the pipeline as a [`HumanDecision`].
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    persistence::{StateStore, StoreError},
+    state::{Pipeline, PipelineId, PipelineState, TransitionError},
+};
+
+/// Errors settling an escalation
+#[derive(Debug, thiserror::Error)]
+pub enum EscalationError {
+    #[error("Pipeline {id} is {state}, not escalated")]
+    NotEscalated { id: String, state: &'static str },
+
+    #[error("A decision needs an author")]
+    MissingAuthor,
+
+    #[error("Resuming needs guidance for the agent")]
+    MissingGuidance,
+
+    #[error(transparent)]
+    Store(#[from] StoreError),
+
+    #[error(transparent)]
+    Transition(#[from] TransitionError),
+}
+
+/// An escalated pipeline waiting on a human
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Escalation {
+    pub pipeline_id: PipelineId,
+    pub spec_path: String,
+    /// Why it was escalated
+    pub reason: Option<String>,
+    /// Sanitized feedback from the last failed validation
+    pub feedback: Option<String>,
+    /// Session holding the agent's work so far
+    pub session: Option<String>,
+    pub iteration: u32,
+    pub max_iterations: u32,
+    pub escalated_at: DateTime<Utc>,
+}
+
+impl Escalation {
+    fn from_pipeline(pipeline: &Pipeline) -> Self {
+        Self {
+            pipeline_id: pipeline.id.clone(),
+            spec_path: pipeline.spec_path.clone(),
+            reason: pipeline.last_error.clone(),
+            feedback: pipeline.feedback.clone(),
+            session: pipeline.session.clone(),
+            iteration: pipeline.iteration,
+            max_iterations: pipeline.max_iterations,
+            escalated_at: pipeline.updated_at,
+        }
+    }
+}
+
+/// What a human decided about an escalated pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HumanAction {
+    /// Sent back to agent development with guidance
+    Resume,
+    /// Accepted as it stands
+    Accept,
+    /// Failed for good
+    Fail,
+}
+
+/// A human decision on an escalated pipeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HumanDecision {
+    pub action: HumanAction,
+    /// Who decided
+    pub author: String,
+    /// Guidance for the agent when resuming, otherwise why
+    pub note: Option<String>,
+    /// Iterations granted on top of those already run, when resuming
+    pub iterations: Option<u32>,
+    pub at: DateTime<Utc>,
+}
+
+impl HumanDecision {
+    fn new(action: HumanAction, author: &str, note: Option<&str>) -> Self {
+        Self {
+            action,
+            author: author.trim().to_string(),
+            note: note.map(str::to_string),
+            iterations: None,
+            at: Utc::now(),
+        }
+    }
+}
+
+/// Escalated pipelines, longest waiting first
+#[must_use]
+pub fn inbox(store: &StateStore) -> Vec<Escalation> {
+    let mut escalations: Vec<Escalation> = store
+        .list_by_state(PipelineState::Escalated)
+        .into_iter()
+        .map(Escalation::from_pipeline)
+        .collect();
+    escalations.sort_by(|a, b| {
+        a.escalated_at
+            .cmp(&b.escalated_at)
+            .then_with(|| a.pipeline_id.0.cmp(&b.pipeline_id.0))
+    });
+    escalations
+}
+
+/// Send an escalated pipeline back to agent development
+///
+/// The agent gets `guidance` alongside the last feedback on its next
+/// iteration, and `iterations` more iterations to use it in.
+///
+/// # Errors
+/// Returns an error if the pipeline is not escalated, `author` or
+/// `guidance` is blank, or the store cannot be updated.
+pub fn resume(
+    store: &mut StateStore,
+    id: &PipelineId,
+    author: &str,
+    guidance: &str,
+    iterations: u32,
+) -> Result<Pipeline, EscalationError> {
+    if guidance.trim().is_empty() {
+        return Err(EscalationError::MissingGuidance);
+    }
+    let decision = HumanDecision {
+        iterations: Some(iterations),
+        ..HumanDecision::new(HumanAction::Resume, author, Some(guidance))
+    };
+    decide(store, id, decision, |p| {
+        p.transition_to(PipelineState::AgentDevelopment)?;
+        p.max_iterations = p.max_iterations.max(p.iteration.saturating_add(iterations));
+        p.guidance = Some(guidance.to_string());
+        p.clear_error();
+        Ok(())
+    })
+}
+
+/// Accept an escalated pipeline's work as it stands
+///
+/// # Errors
+/// Returns an error if the pipeline is not escalated, `author` is blank, or
+/// the store cannot be updated.
+pub fn accept(
+    store: &mut StateStore,
+    id: &PipelineId,
+    author: &str,
+    note: Option<&str>,
+) -> Result<Pipeline, EscalationError> {
+    let decision = HumanDecision::new(HumanAction::Accept, author, note);
+    decide(store, id, decision, |p| {
+        p.transition_to(PipelineState::Accepted)?;
+        p.clear_error();
+        Ok(())
+    })
+}
+
+/// Fail an escalated pipeline for good
+///
+/// # Errors
+/// Returns an error if the pipeline is not escalated, `author` is blank, or
+/// the store cannot be updated.
+pub fn fail(
+    store: &mut StateStore,
+    id: &PipelineId,
+    author: &str,
+    note: Option<&str>,
+) -> Result<Pipeline, EscalationError> {
+    let decision = HumanDecision::new(HumanAction::Fail, author, note);
+    decide(store, id, decision, |p| {
+        p.transition_to(PipelineState::Failed)?;
+        if let Some(note) = note {
+            p.set_error(note.to_string());
+        }
+        Ok(())
+    })
+}
+
+/// Apply `decision` to an escalated pipeline through `apply` and record it
+fn decide(
+    store: &mut StateStore,
+    id: &PipelineId,
+    decision: HumanDecision,
+    apply: impl FnOnce(&mut Pipeline) -> Result<(), TransitionError>,
+) -> Result<Pipeline, EscalationError> {
+    if decision.author.is_empty() {
+        return Err(EscalationError::MissingAuthor);
+    }
+
+    let mut pipeline = store.get(id)?.clone();
+    if pipeline.state != PipelineState::Escalated {
+        return Err(EscalationError::NotEscalated {
+            id: id.0.clone(),
+            state: pipeline.state.as_str(),
+        });
+    }
+
+    apply(&mut pipeline)?;
+    pipeline.reviews.push(decision);
+    store.update(pipeline.clone())?;
+    Ok(pipeline)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// A store holding one pipeline escalated after `iteration` iterations
+    fn escalated(iteration: u32) -> (StateStore, PipelineId, TempDir) {
+        let temp = TempDir::new().unwrap();
+        let mut store = StateStore::new(temp.path().to_path_buf()).unwrap();
+        let mut pipeline = Pipeline::new("specs/login.yaml".to_string());
+        pipeline.state = PipelineState::Validation;
+        pipeline.iteration = iteration;
+        pipeline.max_iterations = iteration;
+        pipeline.feedback = Some("Scenario 1: FAIL".to_string());
+        pipeline.transition_to(PipelineState::Escalated).unwrap();
+        pipeline.set_error("Max iterations reached".to_string());
+        let pipeline = store.create(pipeline).unwrap();
+        (store, pipeline.id, temp)
+    }
+
+    #[test]
+    fn test_inbox_lists_reason_and_feedback() {
+        let (mut store, id, _temp) = escalated(3);
+        store
+            .create(Pipeline::new("specs/other.yaml".to_string()))
+            .unwrap();
+
+        let inbox = inbox(&store);
+
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(inbox[0].pipeline_id, id);
+        assert_eq!(inbox[0].reason.as_deref(), Some("Max iterations reached"));
+        assert_eq!(inbox[0].feedback.as_deref(), Some("Scenario 1: FAIL"));
+    }
+
+    #[test]
+    fn test_resume_grants_iterations_and_records_guidance() {
+        let (mut store, id, _temp) = escalated(3);
+
+        let pipeline = resume(&mut store, &id, "alice", "Hash with argon2", 2).unwrap();
+
+        assert_eq!(pipeline.state, PipelineState::AgentDevelopment);
+        assert_eq!(pipeline.max_iterations, 5);
+        assert!(pipeline.can_iterate());
+        assert_eq!(pipeline.guidance.as_deref(), Some("Hash with argon2"));
+        assert_eq!(pipeline.last_error, None);
+        assert_eq!(pipeline.reviews.len(), 1);
+        assert_eq!(pipeline.reviews[0].action, HumanAction::Resume);
+        assert_eq!(pipeline.reviews[0].author, "alice");
+        assert_eq!(pipeline.reviews[0].iterations, Some(2));
+        assert!(inbox(&store).is_empty());
+    }
+
+    #[test]
+    fn test_accept_and_fail_settle_the_pipeline() {
+        let (mut store, id, _temp) = escalated(1);
+        let accepted = accept(&mut store, &id, "alice", Some("Good enough")).unwrap();
+        let (mut store, id, _temp) = escalated(1);
+        let failed = fail(&mut store, &id, "bob", Some("Spec is wrong")).unwrap();
+
+        assert_eq!(accepted.state, PipelineState::Accepted);
+        assert_eq!(accepted.reviews[0].note.as_deref(), Some("Good enough"));
+        assert_eq!(failed.state, PipelineState::Failed);
+        assert_eq!(failed.last_error.as_deref(), Some("Spec is wrong"));
+        assert_eq!(failed.reviews[0].author, "bob");
+    }
+
+    #[test]
+    fn test_only_escalated_pipelines_can_be_settled() {
+        let (mut store, id, _temp) = escalated(1);
+        accept(&mut store, &id, "alice", None).unwrap();
+
+        let again = fail(&mut store, &id, "bob", None);
+        let blank = resume(&mut store, &id, "alice", "  ", 1);
+
+        assert!(matches!(again, Err(EscalationError::NotEscalated { .. })));
+        assert!(matches!(blank, Err(EscalationError::MissingGuidance)));
+        assert_eq!(store.get(&id).unwrap().reviews.len(), 1);
+    }
+
+    #[test]
+    fn test_decisions_need_an_author() {
+        let (mut store, id, _temp) = escalated(1);
+
+        let result = accept(&mut store, &id, " ", None);
+
+        assert!(matches!(result, Err(EscalationError::MissingAuthor)));
+        assert_eq!(store.get(&id).unwrap().state, PipelineState::Escalated);
+    }
+}
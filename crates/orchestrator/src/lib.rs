@@ -2,21 +2,41 @@
 //!
 //! This crate provides the pipeline orchestration logic including:
 //! - State machine for pipeline phases
-//! - State persistence for crash recovery
-//! - Phase execution
-//! - Metrics collection
+//! - State persistence in SQLite, with transition history, for crash recovery
+//! - Spec linting
+//! - Phase execution, with configurable retry and escalation policies
+//! - An inbox for humans to resume, accept or fail escalated pipelines
+//! - Concurrent scheduling on a bounded worker pool
+//! - Agent sessions for development
+//! - Twin universes per pipeline
+//! - Scenario validation
+//! - Metrics collection, persisted and exported as Prometheus text or CSV
 
 #![deny(clippy::unwrap_used)]
 #![deny(clippy::expect_used)]
 #![deny(clippy::panic)]
 #![forbid(unsafe_code)]
 
+pub mod agent;
+pub mod escalation;
+pub mod linter;
 pub mod metrics;
 pub mod persistence;
 pub mod phases;
+pub mod policy;
+pub mod scheduler;
 pub mod state;
+pub mod universe;
+pub mod validation;
 
-pub use metrics::{Metrics, PhaseMetrics, ScenarioResult};
-pub use persistence::StateStore;
-pub use phases::PipelineExecutor;
-pub use state::{Pipeline, PipelineId, PipelineState};
+pub use agent::{AgentDriver, AgentError, AgentOutcome, AgentRequest};
+pub use escalation::{Escalation, EscalationError, HumanAction, HumanDecision};
+pub use linter::{LintReport, SpecLinter};
+pub use metrics::{
+    AggregatedMetrics, Metrics, MetricsWindow, PhaseMetrics, ScenarioResult, SharedMetrics,
+};
+pub use persistence::{SharedStore, StateStore, Transition};
+pub use phases::{Decision, PipelineExecutor};
+pub use policy::{DecisionRecord, ErrorClass, PolicyConfig, RetryPolicy};
+pub use scheduler::{AgentFactory, PipelineScheduler, ScheduledRun, SchedulerConfig};
+pub use state::{Pipeline, PipelineConfig, PipelineId, PipelineState, TwinInstance};
@@ -0,0 +1,482 @@
+//! Rule-based spec quality scoring
+//!
+//! Rules come from `specs/linter/rules.yaml`, grouped by category. Each rule
+//! carries a weight; a category scores the share of its weight whose rules
+//! passed (0-100), and the total is the category scores weighted by
+//! `scoring.category_weights`. A failing rule deducts its weight once, except
+//! that prohibited patterns only flagged as warnings deduct nothing.
+
+use std::collections::BTreeMap;
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::LintError;
+
+/// Category whose error-level failures reject a spec whatever its score
+pub const SECURITY: &str = "security";
+
+/// How serious a rule failure is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A rule a spec broke
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleFailure {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub category: String,
+    pub message: String,
+    pub severity: Severity,
+    /// Prohibited pattern that matched, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+}
+
+/// Score of one rule category
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryScore {
+    /// 0-100
+    pub score: u32,
+    pub failures: Vec<RuleFailure>,
+}
+
+/// The rules of `rules.yaml`, ready to score specs
+#[derive(Debug, Clone)]
+pub struct Rules {
+    categories: BTreeMap<String, Vec<Rule>>,
+    category_weights: BTreeMap<String, u32>,
+    threshold: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    id: String,
+    name: String,
+    weight: u32,
+    severity: Severity,
+    error_message: Option<String>,
+    check: Check,
+}
+
+/// What a rule checks, from the keys it sets
+#[derive(Debug, Clone)]
+enum Check {
+    /// Every dotted path is present and not blank
+    RequiredFields(Vec<String>),
+    /// The list at `path` has at least `min` items
+    MinItems { path: String, min: usize },
+    /// No pattern matches anywhere in the spec
+    ProhibitedPatterns(Vec<(String, Regex)>),
+    /// Every item of the list at `path` has a description of `min` chars
+    MinDescriptionLength { path: String, min: usize },
+    /// Every acceptance test sets these fields
+    RequiredTestFields(Vec<String>),
+    /// Every acceptance test name has at least `min` chars
+    MinTestNameLength(usize),
+    /// Every item of the list at `path` has an id matching `pattern`
+    RequiredIdPattern { path: String, pattern: Regex },
+    /// The value at `path` is present
+    Present(String),
+    /// Needs human judgement (`check_undefined`, `check_measurable`)
+    Manual,
+}
+
+#[derive(Debug, Deserialize)]
+struct RulesFile {
+    rules: BTreeMap<String, Vec<RawRule>>,
+    #[serde(default)]
+    scoring: Scoring,
+}
+
+#[derive(Debug, Deserialize)]
+struct Scoring {
+    #[serde(default = "default_threshold")]
+    threshold: u32,
+    #[serde(default = "default_category_weights")]
+    category_weights: BTreeMap<String, u32>,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            threshold: default_threshold(),
+            category_weights: default_category_weights(),
+        }
+    }
+}
+
+const fn default_threshold() -> u32 {
+    80
+}
+
+fn default_category_weights() -> BTreeMap<String, u32> {
+    [
+        ("completeness", 30),
+        ("clarity", 15),
+        ("testability", 30),
+        ("security", 15),
+        ("specificity", 10),
+    ]
+    .into_iter()
+    .map(|(category, weight)| (category.to_string(), weight))
+    .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRule {
+    id: String,
+    name: String,
+    #[serde(default)]
+    weight: u32,
+    severity: Option<Severity>,
+    error_message: Option<String>,
+    path: Option<String>,
+    required_fields: Option<Vec<String>>,
+    min_items: Option<usize>,
+    prohibited_patterns: Option<Vec<String>>,
+    min_description_length: Option<usize>,
+    required_test_fields: Option<Vec<String>>,
+    min_test_name_length: Option<usize>,
+    required_id_pattern: Option<String>,
+    #[serde(default)]
+    check_undefined: bool,
+    #[serde(default)]
+    check_measurable: bool,
+}
+
+impl RawRule {
+    fn compile(self) -> Result<Rule, LintError> {
+        let path = self.path.clone().unwrap_or_default();
+        let check = if let Some(fields) = self.required_fields {
+            Check::RequiredFields(fields)
+        } else if let Some(min) = self.min_items {
+            Check::MinItems { path, min }
+        } else if let Some(patterns) = self.prohibited_patterns {
+            Check::ProhibitedPatterns(
+                patterns
+                    .into_iter()
+                    .map(|pattern| {
+                        let regex = compile(&self.id, &pattern)?;
+                        Ok((pattern, regex))
+                    })
+                    .collect::<Result<_, LintError>>()?,
+            )
+        } else if let Some(min) = self.min_description_length {
+            Check::MinDescriptionLength { path, min }
+        } else if let Some(fields) = self.required_test_fields {
+            Check::RequiredTestFields(fields)
+        } else if let Some(min) = self.min_test_name_length {
+            Check::MinTestNameLength(min)
+        } else if let Some(pattern) = self.required_id_pattern {
+            Check::RequiredIdPattern {
+                path,
+                pattern: compile(&self.id, &pattern)?,
+            }
+        } else if self.check_undefined || self.check_measurable {
+            Check::Manual
+        } else if let Some(path) = self.path {
+            Check::Present(path)
+        } else {
+            Check::Manual
+        };
+
+        Ok(Rule {
+            id: self.id,
+            name: self.name,
+            weight: self.weight,
+            severity: self.severity.unwrap_or(Severity::Error),
+            error_message: self.error_message,
+            check,
+        })
+    }
+}
+
+fn compile(rule_id: &str, pattern: &str) -> Result<Regex, LintError> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| LintError::InvalidRules(format!("{rule_id}: pattern {pattern}: {e}")))
+}
+
+impl Rules {
+    /// Parse rules from `rules.yaml` contents
+    ///
+    /// # Errors
+    /// Returns an error if the YAML is invalid or a pattern does not compile.
+    pub fn from_yaml(yaml: &str) -> Result<Self, LintError> {
+        let file: RulesFile =
+            serde_yaml::from_str(yaml).map_err(|e| LintError::InvalidRules(e.to_string()))?;
+
+        let categories = file
+            .rules
+            .into_iter()
+            .map(|(category, rules)| {
+                let rules = rules
+                    .into_iter()
+                    .map(RawRule::compile)
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((category, rules))
+            })
+            .collect::<Result<_, LintError>>()?;
+
+        Ok(Self {
+            categories,
+            category_weights: file.scoring.category_weights,
+            threshold: file.scoring.threshold,
+        })
+    }
+
+    /// Minimum total score `rules.yaml` asks for
+    #[must_use]
+    pub const fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    /// Score every category of `spec`
+    #[must_use]
+    pub fn score(&self, spec: &Value) -> BTreeMap<String, CategoryScore> {
+        let text = serde_json::to_string(spec).unwrap_or_default();
+
+        self.categories
+            .iter()
+            .map(|(category, rules)| {
+                let total: u32 = rules.iter().map(|rule| rule.weight).sum();
+                let mut lost = 0;
+                let mut failures = Vec::new();
+
+                for rule in rules {
+                    if let Some(failure) = rule.check(spec, &text, category) {
+                        if rule.deducts(&failure) {
+                            lost += rule.weight;
+                        }
+                        failures.push(failure);
+                    }
+                }
+
+                let score = (total.saturating_sub(lost) * 100)
+                    .checked_div(total)
+                    .unwrap_or(100);
+                (category.clone(), CategoryScore { score, failures })
+            })
+            .collect()
+    }
+
+    /// Category scores weighted by `scoring.category_weights`
+    ///
+    /// Categories without rules score 100.
+    #[must_use]
+    pub fn total(&self, categories: &BTreeMap<String, CategoryScore>) -> u32 {
+        let weighted: u32 = self
+            .category_weights
+            .iter()
+            .map(|(category, weight)| categories.get(category).map_or(100, |c| c.score) * weight)
+            .sum();
+        weighted / 100
+    }
+}
+
+impl Rule {
+    fn check(&self, spec: &Value, text: &str, category: &str) -> Option<RuleFailure> {
+        let failure = |message: String, pattern: Option<String>| RuleFailure {
+            rule_id: self.id.clone(),
+            rule_name: self.name.clone(),
+            category: category.to_string(),
+            message: self.error_message.clone().unwrap_or(message),
+            severity: self.severity,
+            pattern,
+        };
+
+        match &self.check {
+            Check::RequiredFields(fields) => fields
+                .iter()
+                .find(|field| is_blank(lookup(spec, field)))
+                .map(|field| failure(format!("Missing required field: {field}"), None)),
+            Check::MinItems { path, min } => {
+                let count = lookup(spec, path)
+                    .and_then(Value::as_array)
+                    .map_or(0, Vec::len);
+                (count < *min).then(|| failure(format!("Minimum items not met for: {path}"), None))
+            }
+            Check::ProhibitedPatterns(patterns) => patterns
+                .iter()
+                .find(|(_, regex)| regex.is_match(text))
+                .map(|(pattern, _)| {
+                    failure(
+                        format!("Found prohibited pattern: {pattern}"),
+                        Some(pattern.clone()),
+                    )
+                }),
+            Check::MinDescriptionLength { path, min } => items(spec, path)
+                .iter()
+                .any(|item| description(item).chars().count() < *min)
+                .then(|| failure("Description too short".to_string(), None)),
+            Check::RequiredTestFields(fields) => acceptance_tests(spec)
+                .into_iter()
+                .find_map(|test| {
+                    fields
+                        .iter()
+                        .find(|field| is_blank(test.get(field.as_str())))
+                })
+                .map(|field| failure(format!("Test missing required field: {field}"), None)),
+            Check::MinTestNameLength(min) => acceptance_tests(spec)
+                .into_iter()
+                .map(|test| test.get("test_name").and_then(Value::as_str).unwrap_or(""))
+                .find(|name| name.chars().count() < *min)
+                .map(|name| failure(format!("Test name too short: {name}"), None)),
+            Check::RequiredIdPattern { path, pattern } => items(spec, path)
+                .iter()
+                .map(|item| item.get("id").and_then(Value::as_str).unwrap_or(""))
+                .find(|id| !pattern.is_match(id))
+                .map(|id| failure(format!("Invalid ID format: {id}"), None)),
+            Check::Present(path) => lookup(spec, path)
+                .is_none()
+                .then(|| failure(format!("Missing {path}"), None)),
+            Check::Manual => None,
+        }
+    }
+
+    /// Whether `failure` costs this rule's weight
+    fn deducts(&self, failure: &RuleFailure) -> bool {
+        !matches!(self.check, Check::ProhibitedPatterns(_)) || failure.severity == Severity::Error
+    }
+}
+
+/// Value at a dotted path such as `identity.id`
+fn lookup<'a>(spec: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .try_fold(spec, |value, key| value.get(key))
+        .filter(|value| !value.is_null())
+}
+
+fn is_blank(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => true,
+        Some(Value::String(text)) => text.trim().is_empty(),
+        Some(_) => false,
+    }
+}
+
+fn items<'a>(spec: &'a Value, path: &str) -> &'a [Value] {
+    lookup(spec, path)
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+fn description(item: &Value) -> &str {
+    item.get("description")
+        .and_then(Value::as_str)
+        .unwrap_or("")
+}
+
+/// Every happy and error path test
+fn acceptance_tests(spec: &Value) -> Vec<&Value> {
+    [
+        "acceptance_tests.happy_paths",
+        "acceptance_tests.error_paths",
+    ]
+    .iter()
+    .flat_map(|path| items(spec, path))
+    .filter(|test| test.is_object())
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const RULES: &str = r#"
+rules:
+  completeness:
+    - id: COMP-001
+      name: Has identity
+      weight: 5
+      required_fields: [identity.id]
+      error_message: "Missing identity.id"
+    - id: COMP-002
+      name: Has requirements
+      weight: 15
+      min_items: 1
+      path: requirements
+  clarity:
+    - id: CLAR-001
+      name: No ambiguous language
+      weight: 10
+      prohibited_patterns: ["TBD"]
+      severity: warning
+  security:
+    - id: SEC-001
+      name: No secrets
+      weight: 20
+      prohibited_patterns: ["api_key\"?\\s*[:=]\\s*\"?[a-z0-9]{20,}"]
+      severity: error
+scoring:
+  threshold: 80
+  category_weights:
+    completeness: 50
+    clarity: 20
+    security: 30
+"#;
+
+    #[test]
+    fn test_category_scores_share_of_passed_weight() {
+        let rules = Rules::from_yaml(RULES).unwrap();
+        let spec = json!({"identity": {"id": "spec-x"}});
+
+        let categories = rules.score(&spec);
+
+        assert_eq!(categories["completeness"].score, 25);
+        assert_eq!(categories["completeness"].failures[0].rule_id, "COMP-002");
+        assert_eq!(
+            rules.total(&categories),
+            (25 * 50 + 100 * 20 + 100 * 30) / 100
+        );
+    }
+
+    #[test]
+    fn test_warning_patterns_are_reported_without_deduction() {
+        let rules = Rules::from_yaml(RULES).unwrap();
+        let spec = json!({"identity": {"id": "spec-x"}, "requirements": [{"description": "tbd"}]});
+
+        let categories = rules.score(&spec);
+
+        assert_eq!(categories["clarity"].score, 100);
+        assert_eq!(
+            categories["clarity"].failures[0].severity,
+            Severity::Warning
+        );
+        assert_eq!(
+            categories["clarity"].failures[0].pattern.as_deref(),
+            Some("TBD")
+        );
+    }
+
+    #[test]
+    fn test_secret_fails_security_with_custom_message() {
+        let rules = Rules::from_yaml(RULES).unwrap();
+        let spec = json!({"identity": {}, "notes": {"api_key": "abcdefghij0123456789xyz"}});
+
+        let categories = rules.score(&spec);
+
+        assert_eq!(categories["security"].score, 0);
+        assert_eq!(
+            categories["completeness"].failures[0].message,
+            "Missing identity.id"
+        );
+    }
+
+    #[test]
+    fn test_bundled_rules_load() {
+        let rules = Rules::from_yaml(super::super::BUNDLED_RULES).unwrap();
+
+        assert_eq!(rules.threshold(), 80);
+        assert_eq!(rules.categories.len(), 5);
+    }
+}
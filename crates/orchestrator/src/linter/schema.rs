@@ -0,0 +1,343 @@
+//! JSON schema validation for specs
+//!
+//! Supports the draft-07 keywords `specs/schema/spec.schema.json` uses:
+//! `type`, `enum`, `required`, `properties`, `additionalProperties`, `items`,
+//! `minItems`, `minLength`, `maxLength` and `pattern`. Behavior ids must also
+//! be unique, which the schema documents but cannot express.
+
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::LintError;
+
+/// A place where a spec does not match the schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaViolation {
+    /// Where in the spec, e.g. `requirements[0].id`
+    pub path: String,
+    pub message: String,
+}
+
+/// A parsed spec schema with its patterns compiled
+#[derive(Debug, Clone)]
+pub struct Schema {
+    root: Value,
+    patterns: HashMap<String, Regex>,
+}
+
+impl Schema {
+    /// Parse a schema from JSON
+    ///
+    /// # Errors
+    /// Returns an error if the schema is not valid JSON or has an invalid pattern.
+    pub fn from_json(json: &str) -> Result<Self, LintError> {
+        let root: Value = serde_json::from_str(json)
+            .map_err(|e| LintError::InvalidSchema(format!("not valid JSON: {e}")))?;
+
+        let mut patterns = HashMap::new();
+        collect_patterns(&root, &mut patterns)?;
+
+        Ok(Self { root, patterns })
+    }
+
+    /// Every place `spec` breaks the schema, in document order
+    #[must_use]
+    pub fn validate(&self, spec: &Value) -> Vec<SchemaViolation> {
+        let mut violations = Vec::new();
+        self.check(&self.root, spec, "spec", &mut violations);
+        violations.extend(duplicate_behaviors(spec));
+        violations
+    }
+
+    fn check(&self, schema: &Value, value: &Value, path: &str, out: &mut Vec<SchemaViolation>) {
+        let violation = |message: String| SchemaViolation {
+            path: path.to_string(),
+            message,
+        };
+
+        if let Some(expected) = schema.get("type") {
+            if !type_matches(expected, value) {
+                out.push(violation(format!(
+                    "expected {}, found {}",
+                    type_names(expected),
+                    type_of(value)
+                )));
+                return;
+            }
+        }
+
+        if let Some(Value::Array(allowed)) = schema.get("enum") {
+            if !allowed.contains(value) {
+                out.push(violation(format!(
+                    "{value} is not one of {}",
+                    Value::Array(allowed.clone())
+                )));
+            }
+        }
+
+        match value {
+            Value::Object(object) => self.check_object(schema, object, path, out),
+            Value::Array(items) => self.check_array(schema, items, path, out),
+            Value::String(text) => self.check_string(schema, text, path, out),
+            _ => {}
+        }
+    }
+
+    fn check_object(
+        &self,
+        schema: &Value,
+        object: &serde_json::Map<String, Value>,
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for field in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(field) {
+                    out.push(SchemaViolation {
+                        path: path.to_string(),
+                        message: format!("missing required property '{field}'"),
+                    });
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        for (key, value) in object {
+            let child = format!("{path}.{key}");
+            match (
+                properties.and_then(|p| p.get(key)),
+                schema.get("additionalProperties"),
+            ) {
+                (Some(property), _) => self.check(property, value, &child, out),
+                (None, Some(Value::Bool(false))) => out.push(SchemaViolation {
+                    path: path.to_string(),
+                    message: format!("unexpected property '{key}'"),
+                }),
+                (None, Some(additional @ Value::Object(_))) => {
+                    self.check(additional, value, &child, out);
+                }
+                (None, _) => {}
+            }
+        }
+    }
+
+    fn check_array(
+        &self,
+        schema: &Value,
+        items: &[Value],
+        path: &str,
+        out: &mut Vec<SchemaViolation>,
+    ) {
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                out.push(SchemaViolation {
+                    path: path.to_string(),
+                    message: format!("expected at least {min} items, found {}", items.len()),
+                });
+            }
+        }
+
+        if let Some(item_schema) = schema.get("items") {
+            for (index, item) in items.iter().enumerate() {
+                self.check(item_schema, item, &format!("{path}[{index}]"), out);
+            }
+        }
+    }
+
+    fn check_string(&self, schema: &Value, text: &str, path: &str, out: &mut Vec<SchemaViolation>) {
+        let length = text.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                out.push(SchemaViolation {
+                    path: path.to_string(),
+                    message: format!("expected at least {min} characters, found {length}"),
+                });
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                out.push(SchemaViolation {
+                    path: path.to_string(),
+                    message: format!("expected at most {max} characters, found {length}"),
+                });
+            }
+        }
+
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            if self
+                .patterns
+                .get(pattern)
+                .is_some_and(|re| !re.is_match(text))
+            {
+                out.push(SchemaViolation {
+                    path: path.to_string(),
+                    message: format!("'{text}' does not match {pattern}"),
+                });
+            }
+        }
+    }
+}
+
+/// Compile every `pattern` in the schema up front so a bad one fails loading
+fn collect_patterns(
+    schema: &Value,
+    patterns: &mut HashMap<String, Regex>,
+) -> Result<(), LintError> {
+    match schema {
+        Value::Object(object) => {
+            if let Some(Value::String(pattern)) = object.get("pattern") {
+                let regex = Regex::new(pattern)
+                    .map_err(|e| LintError::InvalidSchema(format!("pattern {pattern}: {e}")))?;
+                patterns.insert(pattern.clone(), regex);
+            }
+            object
+                .values()
+                .try_for_each(|child| collect_patterns(child, patterns))
+        }
+        Value::Array(items) => items
+            .iter()
+            .try_for_each(|child| collect_patterns(child, patterns)),
+        _ => Ok(()),
+    }
+}
+
+fn duplicate_behaviors(spec: &Value) -> Vec<SchemaViolation> {
+    let Some(behaviors) = spec.get("behaviors").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    let mut seen = HashSet::new();
+    behaviors
+        .iter()
+        .enumerate()
+        .filter_map(|(index, behavior)| {
+            let id = behavior.get("id")?.as_str()?;
+            (!seen.insert(id)).then(|| SchemaViolation {
+                path: format!("spec.behaviors[{index}].id"),
+                message: format!("duplicate behavior id '{id}'"),
+            })
+        })
+        .collect()
+}
+
+fn type_matches(expected: &Value, value: &Value) -> bool {
+    match expected {
+        Value::String(name) => is_type(name, value),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .any(|name| is_type(name, value)),
+        _ => true,
+    }
+}
+
+fn is_type(name: &str, value: &Value) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        _ => true,
+    }
+}
+
+fn type_names(expected: &Value) -> String {
+    match expected {
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.as_str().unwrap_or("any").to_string(),
+    }
+}
+
+const fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Bool(_) => "boolean",
+        Value::Null => "null",
+        Value::Number(_) => "number",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::from_json(
+            r#"{
+                "type": "object",
+                "required": ["id", "tags"],
+                "properties": {
+                    "id": {"type": "string", "pattern": "^spec-[a-z]+$"},
+                    "kind": {"type": "string", "enum": ["feature", "bug"]},
+                    "tags": {"type": "array", "items": {"type": "string", "minLength": 2}}
+                },
+                "additionalProperties": false
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_valid_document_has_no_violations() {
+        let spec = json!({"id": "spec-auth", "kind": "bug", "tags": ["api"]});
+
+        assert_eq!(schema().validate(&spec), vec![]);
+    }
+
+    #[test]
+    fn test_violations_name_their_path() {
+        let spec = json!({"id": "auth", "kind": "epic", "tags": ["a", 3], "extra": true});
+
+        let violations = schema().validate(&spec);
+        let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                "spec",
+                "spec.id",
+                "spec.kind",
+                "spec.tags[0]",
+                "spec.tags[1]"
+            ]
+        );
+        assert!(violations[0]
+            .message
+            .contains("unexpected property 'extra'"));
+        assert!(violations[4]
+            .message
+            .contains("expected string, found number"));
+    }
+
+    #[test]
+    fn test_duplicate_behavior_ids_are_rejected() {
+        let schema = Schema::from_json("{}").unwrap();
+        let spec = json!({"behaviors": [{"id": "Login"}, {"id": "Logout"}, {"id": "Login"}]});
+
+        let violations = schema.validate(&spec);
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "spec.behaviors[2].id");
+    }
+
+    #[test]
+    fn test_invalid_pattern_fails_loading() {
+        let result = Schema::from_json(r#"{"properties": {"id": {"pattern": "(unclosed"}}}"#);
+
+        assert!(matches!(result, Err(LintError::InvalidSchema(_))));
+    }
+}
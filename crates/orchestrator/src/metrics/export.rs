@@ -0,0 +1,273 @@
+//! Export aggregated metrics as Prometheus text or CSV
+//!
+//! [`prometheus`] renders one aggregate as gauges in the Prometheus text
+//! exposition format, for a scrape endpoint or the node exporter's textfile
+//! collector. [`csv`] renders a series of windows as one row each, for
+//! tracking trends across weeks in a spreadsheet.
+
+use std::{collections::BTreeSet, fmt::Write};
+
+use chrono::SecondsFormat;
+
+use super::{AggregatedMetrics, MetricsWindow};
+
+/// Prefix of every exported metric name
+const NAMESPACE: &str = "orchestrator";
+
+/// Columns before the per-phase ones in [`csv`] output
+const CSV_COLUMNS: [&str; 9] = [
+    "window_start",
+    "window_end",
+    "total_pipelines",
+    "successful_pipelines",
+    "failed_pipelines",
+    "escalated_pipelines",
+    "success_rate",
+    "average_iterations",
+    "average_duration_secs",
+];
+
+/// Render `metrics` in the Prometheus text exposition format
+#[must_use]
+pub fn prometheus(metrics: &AggregatedMetrics) -> String {
+    let mut out = String::new();
+
+    gauge(
+        &mut out,
+        "pipelines_total",
+        "Pipelines in the aggregation window",
+        &[(None, f64::from(metrics.total_pipelines))],
+    );
+    gauge(
+        &mut out,
+        "pipelines",
+        "Pipelines by final outcome",
+        &[
+            (
+                Some(("outcome", "accepted")),
+                f64::from(metrics.successful_pipelines),
+            ),
+            (
+                Some(("outcome", "failed")),
+                f64::from(metrics.failed_pipelines),
+            ),
+            (
+                Some(("outcome", "escalated")),
+                f64::from(metrics.escalated_pipelines),
+            ),
+        ],
+    );
+    gauge(
+        &mut out,
+        "pipeline_success_ratio",
+        "Fraction of pipelines accepted",
+        &[(None, success_ratio(metrics))],
+    );
+    gauge(
+        &mut out,
+        "pipeline_iterations_avg",
+        "Average agent iterations per pipeline",
+        &[(None, metrics.average_iterations)],
+    );
+    gauge(
+        &mut out,
+        "pipeline_duration_seconds_avg",
+        "Average time spent in phases per pipeline",
+        &[(None, metrics.average_duration_secs)],
+    );
+
+    let mut samples: Vec<_> = metrics
+        .phase_durations
+        .iter()
+        .map(|(phase, secs)| (Some(("phase", phase.as_str())), *secs))
+        .collect();
+    samples.sort_by(|a, b| a.0.cmp(&b.0));
+    gauge(
+        &mut out,
+        "phase_duration_seconds_avg",
+        "Average duration of each phase",
+        &samples,
+    );
+
+    out
+}
+
+/// Render `windows` as CSV, one row per window
+///
+/// Every phase seen in any window gets an `<phase>_avg_secs` column, left
+/// empty for windows where that phase never ran.
+#[must_use]
+pub fn csv(windows: &[MetricsWindow]) -> String {
+    let phases: BTreeSet<&str> = windows
+        .iter()
+        .flat_map(|w| w.metrics.phase_durations.keys())
+        .map(String::as_str)
+        .collect();
+
+    let mut header: Vec<String> = CSV_COLUMNS.iter().map(ToString::to_string).collect();
+    header.extend(phases.iter().map(|phase| format!("{phase}_avg_secs")));
+    let mut out = csv_row(&header);
+
+    for window in windows {
+        let metrics = &window.metrics;
+        let mut row = vec![
+            window.start.to_rfc3339_opts(SecondsFormat::Secs, true),
+            window.end.to_rfc3339_opts(SecondsFormat::Secs, true),
+            metrics.total_pipelines.to_string(),
+            metrics.successful_pipelines.to_string(),
+            metrics.failed_pipelines.to_string(),
+            metrics.escalated_pipelines.to_string(),
+            success_ratio(metrics).to_string(),
+            metrics.average_iterations.to_string(),
+            metrics.average_duration_secs.to_string(),
+        ];
+        row.extend(phases.iter().map(|phase| {
+            metrics
+                .phase_durations
+                .get(*phase)
+                .map(ToString::to_string)
+                .unwrap_or_default()
+        }));
+        out.push_str(&csv_row(&row));
+    }
+
+    out
+}
+
+/// Accepted pipelines as a fraction of all of them, 0 when there are none
+fn success_ratio(metrics: &AggregatedMetrics) -> f64 {
+    if metrics.total_pipelines == 0 {
+        return 0.0;
+    }
+    f64::from(metrics.successful_pipelines) / f64::from(metrics.total_pipelines)
+}
+
+/// Append a gauge family: its HELP and TYPE lines, then one line per sample
+fn gauge(out: &mut String, name: &str, help: &str, samples: &[(Option<(&str, &str)>, f64)]) {
+    if samples.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "# HELP {NAMESPACE}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {NAMESPACE}_{name} gauge");
+    for (label, value) in samples {
+        match label {
+            Some((key, label_value)) => {
+                let _ = writeln!(
+                    out,
+                    "{NAMESPACE}_{name}{{{key}=\"{}\"}} {value}",
+                    escape_label(label_value)
+                );
+            }
+            None => {
+                let _ = writeln!(out, "{NAMESPACE}_{name} {value}");
+            }
+        }
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn csv_row(fields: &[String]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.clone()
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::*;
+
+    fn sample() -> AggregatedMetrics {
+        AggregatedMetrics {
+            total_pipelines: 4,
+            successful_pipelines: 3,
+            failed_pipelines: 1,
+            escalated_pipelines: 0,
+            average_duration_secs: 12.5,
+            average_iterations: 2.0,
+            phase_durations: [
+                ("validation".to_string(), 4.0),
+                ("agent_development".to_string(), 8.5),
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    #[test]
+    fn test_prometheus_gauges() {
+        let text = prometheus(&sample());
+
+        assert!(text.contains("# TYPE orchestrator_pipelines_total gauge\n"));
+        assert!(text.contains("orchestrator_pipelines_total 4\n"));
+        assert!(text.contains("orchestrator_pipelines{outcome=\"accepted\"} 3\n"));
+        assert!(text.contains("orchestrator_pipeline_success_ratio 0.75\n"));
+        let agent = text
+            .find("orchestrator_phase_duration_seconds_avg{phase=\"agent_development\"} 8.5\n")
+            .unwrap();
+        let validation = text
+            .find("orchestrator_phase_duration_seconds_avg{phase=\"validation\"} 4\n")
+            .unwrap();
+        assert!(agent < validation);
+    }
+
+    #[test]
+    fn test_prometheus_skips_phases_when_none_ran() {
+        let text = prometheus(&AggregatedMetrics::default());
+
+        assert!(text.contains("orchestrator_pipeline_success_ratio 0\n"));
+        assert!(!text.contains("phase_duration"));
+    }
+
+    #[test]
+    fn test_csv_rows_per_window() {
+        let start = Utc.with_ymd_and_hms(2026, 1, 5, 0, 0, 0).unwrap();
+        let windows = vec![
+            MetricsWindow {
+                start,
+                end: start + Duration::days(7),
+                metrics: sample(),
+            },
+            MetricsWindow {
+                start: start + Duration::days(7),
+                end: start + Duration::days(14),
+                metrics: AggregatedMetrics::default(),
+            },
+        ];
+
+        let csv = csv(&windows);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0]
+            .ends_with("average_duration_secs,agent_development_avg_secs,validation_avg_secs"));
+        assert_eq!(
+            lines[1],
+            "2026-01-05T00:00:00Z,2026-01-12T00:00:00Z,4,3,1,0,0.75,2,12.5,8.5,4"
+        );
+        assert!(lines[2].ends_with(",0,0,0,,"));
+    }
+
+    #[test]
+    fn test_csv_quotes_fields() {
+        assert_eq!(
+            csv_row(&["a,b".to_string(), "say \"hi\"".to_string()]),
+            "\"a,b\",\"say \"\"hi\"\"\"\n"
+        );
+    }
+}
@@ -1,11 +1,23 @@
 //! Metrics collection for pipeline execution
+//!
+//! [`Metrics`] collects phase timings as pipelines run; executors also
+//! persist each one to the state store, which rebuilds [`Metrics`] for any
+//! time window from its history (see [`crate::StateStore::aggregate_windows`]).
+//! [`export`] writes aggregates as Prometheus text or CSV.
 
-use std::collections::HashMap;
+pub mod export;
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use chrono::{DateTime, Utc};
 use im::Vector;
 use serde::{Deserialize, Serialize};
 
+use crate::state::Pipeline;
+
 /// A single scenario test result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScenarioResult {
@@ -48,6 +60,17 @@ pub struct AggregatedMetrics {
     pub phase_durations: HashMap<String, f64>,
 }
 
+/// Aggregated metrics for pipelines created in `[start, end)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub metrics: AggregatedMetrics,
+}
+
+/// Metrics collected by executors running pipelines concurrently
+pub type SharedMetrics = Arc<Mutex<Metrics>>;
+
 /// Metrics collector
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Metrics {
@@ -67,21 +90,47 @@ impl Metrics {
         }
     }
 
-    /// Record phase metrics
-    pub fn record_phase(&mut self, metrics: PhaseMetrics) {
-        // Update pipeline-level metrics
-        let pipeline_id = metrics.pipeline_id.clone();
-        let entry = self
-            .pipeline_metrics
-            .entry(pipeline_id.clone())
+    /// Rebuild metrics for `pipelines` from their recorded phases
+    ///
+    /// Each pipeline's state and iteration count stand in for its final
+    /// state and iterations; phases of other pipelines are ignored.
+    #[must_use]
+    pub fn from_history(
+        pipelines: &[Pipeline],
+        phases: impl IntoIterator<Item = PhaseMetrics>,
+    ) -> Self {
+        let mut metrics = Self::new();
+        for pipeline in pipelines {
+            let entry = metrics.entry(&pipeline.id.0);
+            entry.iteration_count = pipeline.iteration;
+            entry.final_state = pipeline.state.as_str().to_string();
+        }
+        for phase in phases {
+            if metrics.pipeline_metrics.contains_key(&phase.pipeline_id) {
+                metrics.record_phase(phase);
+            }
+        }
+        metrics
+    }
+
+    /// Pipeline-level metrics for `pipeline_id`, created on first use
+    fn entry(&mut self, pipeline_id: &str) -> &mut PipelineMetrics {
+        self.pipeline_metrics
+            .entry(pipeline_id.to_string())
             .or_insert_with(|| PipelineMetrics {
-                pipeline_id,
+                pipeline_id: pipeline_id.to_string(),
                 total_duration_secs: 0.0,
                 phase_metrics: vec![],
                 iteration_count: 0,
                 scenario_results: vec![],
                 final_state: "unknown".to_string(),
-            });
+            })
+    }
+
+    /// Record phase metrics
+    pub fn record_phase(&mut self, metrics: PhaseMetrics) {
+        // Update pipeline-level metrics
+        let entry = self.entry(&metrics.pipeline_id);
 
         entry.total_duration_secs += metrics.duration_secs;
         entry.phase_metrics.push(metrics.clone());
@@ -325,6 +374,32 @@ mod tests {
         assert!((rate - 66.666666).abs() < 0.1);
     }
 
+    #[test]
+    fn test_from_history_uses_pipeline_state_and_iterations() {
+        let mut accepted = Pipeline::new("specs/a.yaml".to_string());
+        accepted.state = crate::state::PipelineState::Accepted;
+        accepted.iteration = 3;
+        let pending = Pipeline::new("specs/b.yaml".to_string());
+        let phase = |pipeline_id: &str| PhaseMetrics {
+            pipeline_id: pipeline_id.to_string(),
+            phase: "validation".to_string(),
+            started_at: Utc::now(),
+            duration_secs: 4.0,
+            success: true,
+        };
+
+        let metrics = Metrics::from_history(
+            &[accepted.clone(), pending],
+            [phase(&accepted.id.0), phase("elsewhere")],
+        );
+        let agg = metrics.aggregated();
+
+        assert_eq!(agg.total_pipelines, 2);
+        assert_eq!(agg.successful_pipelines, 1);
+        assert!((agg.average_iterations - 1.5).abs() < f64::EPSILON);
+        assert_eq!(agg.phase_durations.get("validation"), Some(&4.0));
+    }
+
     #[test]
     fn test_slowest_phases() {
         let mut metrics = Metrics::new();
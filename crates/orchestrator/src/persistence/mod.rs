@@ -0,0 +1,689 @@
+//! State persistence for pipeline recovery
+//!
+//! Pipelines are kept in `state.db` under the state directory (see
+//! [`sqlite`] for the schema), with every state change appended to a
+//! transition history as part of the same write. Phase timings are stored
+//! next to them, so [`StateStore::aggregate_windows`] can rebuild metrics for
+//! any stretch of time. The store caches every
+//! pipeline in memory for reads; writes go straight to the database.
+//!
+//! Pipelines saved as one JSON file each, as earlier versions did, are moved
+//! into the database the first time the directory is opened.
+
+mod sqlite;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, error, info};
+
+use self::sqlite::Database;
+use crate::{
+    metrics::{Metrics, MetricsWindow, PhaseMetrics},
+    state::{Pipeline, PipelineId, PipelineState},
+};
+
+/// File the store keeps pipelines in, under its state directory
+pub const DATABASE_FILE: &str = "state.db";
+
+/// Error types for state store operations
+#[derive(Debug, thiserror::Error)]
+pub enum StoreError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Database error: {0}")]
+    Database(String),
+    #[error("Pipeline not found: {0}")]
+    NotFound(String),
+    #[error("Invalid state file: {0}")]
+    InvalidState(String),
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+}
+
+impl From<sqlx::Error> for StoreError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Database(e.to_string())
+    }
+}
+
+/// One recorded change of a pipeline's state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transition {
+    pub pipeline_id: PipelineId,
+    /// State before, or `None` when the pipeline was created
+    pub from: Option<PipelineState>,
+    pub to: PipelineState,
+    /// Agent iterations completed at the time
+    pub iteration: u32,
+    pub at: DateTime<Utc>,
+}
+
+/// A state store shared by executors running pipelines concurrently
+pub type SharedStore = Arc<Mutex<StateStore>>;
+
+/// State store for persisting pipeline state
+pub struct StateStore {
+    /// Directory holding the database
+    state_dir: PathBuf,
+    db: Database,
+    /// In-memory cache of pipelines
+    cache: HashMap<String, Pipeline>,
+    /// Last state written for each pipeline, to tell when one transitions
+    persisted: HashMap<String, PipelineState>,
+    /// Pipelines changed through `get_mut` and not yet written
+    dirty: HashSet<String>,
+}
+
+impl StateStore {
+    /// Create a new state store
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be created or the database
+    /// cannot be opened or loaded.
+    pub fn new(state_dir: PathBuf) -> Result<Self, StoreError> {
+        // Ensure directory exists
+        fs::create_dir_all(&state_dir)?;
+        let db = Database::open(&state_dir.join(DATABASE_FILE))?;
+
+        let mut store = Self {
+            state_dir,
+            db,
+            cache: HashMap::new(),
+            persisted: HashMap::new(),
+            dirty: HashSet::new(),
+        };
+
+        // Load existing state
+        store.load_all()?;
+        store.import_legacy_files()?;
+
+        Ok(store)
+    }
+
+    /// Load all pipelines from the database
+    fn load_all(&mut self) -> Result<(), StoreError> {
+        for pipeline in self.db.load_all()? {
+            debug!("Loaded pipeline: {}", pipeline.id);
+            self.persisted.insert(pipeline.id.0.clone(), pipeline.state);
+            self.cache.insert(pipeline.id.0.clone(), pipeline);
+        }
+
+        info!("Loaded {} pipelines from state store", self.cache.len());
+        Ok(())
+    }
+
+    /// Move pipelines saved as JSON files into the database
+    ///
+    /// Imported files are renamed to `*.json.imported` rather than deleted.
+    fn import_legacy_files(&mut self) -> Result<(), StoreError> {
+        for entry in fs::read_dir(&self.state_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+
+            match Self::load_single(&path) {
+                Ok(pipeline) => {
+                    if !self.cache.contains_key(&pipeline.id.0) {
+                        info!("Importing pipeline {} from {:?}", pipeline.id, path);
+                        self.write(&pipeline)?;
+                        self.cache.insert(pipeline.id.0.clone(), pipeline);
+                    }
+                    fs::rename(&path, path.with_extension("json.imported"))?;
+                }
+                Err(e) => {
+                    error!("Failed to load pipeline from {:?}: {e}", path);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Load a single pipeline from a file
+    fn load_single(path: &Path) -> Result<Pipeline, StoreError> {
+        let content = fs::read_to_string(path)?;
+        let pipeline: Pipeline =
+            serde_json::from_str(&content).map_err(|e| StoreError::InvalidState(e.to_string()))?;
+        Ok(pipeline)
+    }
+
+    /// The transition writing `pipeline` would record, if its state changed
+    fn transition_for(&self, pipeline: &Pipeline) -> Option<Transition> {
+        let from = self.persisted.get(&pipeline.id.0).copied();
+        (from != Some(pipeline.state)).then(|| Transition {
+            pipeline_id: pipeline.id.clone(),
+            from,
+            to: pipeline.state,
+            iteration: pipeline.iteration,
+            at: pipeline.updated_at,
+        })
+    }
+
+    /// Write pipelines and their transitions in one transaction
+    fn write_all(&mut self, pipelines: &[&Pipeline]) -> Result<(), StoreError> {
+        let transitions: Vec<Option<Transition>> = pipelines
+            .iter()
+            .map(|pipeline| self.transition_for(pipeline))
+            .collect();
+        let writes: Vec<sqlite::Write<'_>> = pipelines
+            .iter()
+            .zip(&transitions)
+            .map(|(pipeline, transition)| (*pipeline, transition.as_ref()))
+            .collect();
+        self.db.save(&writes)?;
+
+        for pipeline in pipelines {
+            self.persisted.insert(pipeline.id.0.clone(), pipeline.state);
+            self.dirty.remove(&pipeline.id.0);
+            debug!("Saved pipeline {}", pipeline.id);
+        }
+        Ok(())
+    }
+
+    /// Save a pipeline to the database
+    fn write(&mut self, pipeline: &Pipeline) -> Result<(), StoreError> {
+        self.write_all(&[pipeline])
+    }
+
+    /// Create a new pipeline
+    ///
+    /// # Errors
+    /// Returns an error if the pipeline cannot be saved.
+    pub fn create(&mut self, pipeline: Pipeline) -> Result<Pipeline, StoreError> {
+        let id = pipeline.id.0.clone();
+        self.write(&pipeline)?;
+        self.cache.insert(id, pipeline.clone());
+        Ok(pipeline)
+    }
+
+    /// Get a pipeline by ID
+    ///
+    /// # Errors
+    /// Returns an error if the pipeline is not found.
+    pub fn get(&self, id: &PipelineId) -> Result<&Pipeline, StoreError> {
+        self.cache
+            .get(&id.0)
+            .ok_or_else(|| StoreError::NotFound(id.0.clone()))
+    }
+
+    /// Get a mutable pipeline by ID
+    ///
+    /// Changes are written by the next [`Self::update`] of the pipeline or
+    /// [`Self::sync`].
+    ///
+    /// # Errors
+    /// Returns an error if the pipeline is not found.
+    pub fn get_mut(&mut self, id: &PipelineId) -> Result<&mut Pipeline, StoreError> {
+        let pipeline = self
+            .cache
+            .get_mut(&id.0)
+            .ok_or_else(|| StoreError::NotFound(id.0.clone()))?;
+        self.dirty.insert(id.0.clone());
+        Ok(pipeline)
+    }
+
+    /// Update a pipeline
+    ///
+    /// The row and, if its state changed, the transition are written
+    /// atomically.
+    ///
+    /// # Errors
+    /// Returns an error if the pipeline cannot be saved.
+    pub fn update(&mut self, pipeline: Pipeline) -> Result<(), StoreError> {
+        self.write(&pipeline)?;
+        self.cache.insert(pipeline.id.0.clone(), pipeline);
+        Ok(())
+    }
+
+    /// Delete a pipeline
+    ///
+    /// Its transitions are kept.
+    ///
+    /// # Errors
+    /// Returns an error if the pipeline is not found or cannot be deleted.
+    pub fn delete(&mut self, id: &PipelineId) -> Result<(), StoreError> {
+        if !self.exists(id) {
+            return Err(StoreError::NotFound(id.0.clone()));
+        }
+        self.db.delete(id)?;
+        self.cache.remove(&id.0);
+        self.persisted.remove(&id.0);
+        self.dirty.remove(&id.0);
+        Ok(())
+    }
+
+    /// List all pipelines
+    #[must_use]
+    pub fn list(&self) -> Vec<&Pipeline> {
+        self.cache.values().collect()
+    }
+
+    /// List pipelines by state
+    #[must_use]
+    pub fn list_by_state(&self, state: crate::state::PipelineState) -> Vec<&Pipeline> {
+        self.cache.values().filter(|p| p.state == state).collect()
+    }
+
+    /// Get pending pipelines that need recovery
+    #[must_use]
+    pub fn get_pending_recovery(&self) -> Vec<&Pipeline> {
+        self.cache
+            .values()
+            .filter(|p| !p.state.is_terminal())
+            .collect()
+    }
+
+    /// Pipelines created in `[start, end)`, oldest first, read from the
+    /// database
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub fn created_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Pipeline>, StoreError> {
+        self.db.created_between(start, end)
+    }
+
+    /// Every state a pipeline has moved through, oldest first
+    ///
+    /// # Errors
+    /// Returns an error if the query fails.
+    pub fn transitions(&self, id: &PipelineId) -> Result<Vec<Transition>, StoreError> {
+        self.db.transitions(id)
+    }
+
+    /// Persist the timing of one phase
+    ///
+    /// # Errors
+    /// Returns an error if the write fails.
+    pub fn record_phase(&mut self, phase: &PhaseMetrics) -> Result<(), StoreError> {
+        self.db.record_phase(phase)
+    }
+
+    /// Metrics for pipelines created in `[start, end)`, rebuilt from their
+    /// stored state and phase timings
+    ///
+    /// # Errors
+    /// Returns an error if a query fails.
+    pub fn metrics_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Metrics, StoreError> {
+        let pipelines = self.db.created_between(start, end)?;
+        let phases = self.db.phases_created_between(start, end)?;
+        Ok(Metrics::from_history(&pipelines, phases))
+    }
+
+    /// Aggregated metrics for consecutive windows of `width` from `start`,
+    /// the last one cut short at `end`
+    ///
+    /// # Errors
+    /// Returns an error if `width` is not positive or a query fails.
+    pub fn aggregate_windows(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        width: chrono::Duration,
+    ) -> Result<Vec<MetricsWindow>, StoreError> {
+        if width <= chrono::Duration::zero() {
+            return Err(StoreError::InvalidQuery(format!(
+                "Window width must be positive, got {width}"
+            )));
+        }
+
+        let mut windows = Vec::new();
+        let mut window_start = start;
+        while window_start < end {
+            let window_end = (window_start + width).min(end);
+            windows.push(MetricsWindow {
+                start: window_start,
+                end: window_end,
+                metrics: self.metrics_between(window_start, window_end)?.aggregated(),
+            });
+            window_start = window_end;
+        }
+        Ok(windows)
+    }
+
+    /// Check if a pipeline exists
+    #[must_use]
+    pub fn exists(&self, id: &PipelineId) -> bool {
+        self.cache.contains_key(&id.0)
+    }
+
+    /// Write pipelines changed through [`Self::get_mut`]
+    ///
+    /// # Errors
+    /// Returns an error if sync fails.
+    pub fn sync(&mut self) -> Result<(), StoreError> {
+        if self.dirty.is_empty() {
+            return Ok(());
+        }
+        let pipelines: Vec<Pipeline> = self
+            .dirty
+            .iter()
+            .filter_map(|id| self.cache.get(id).cloned())
+            .collect();
+        self.write_all(&pipelines.iter().collect::<Vec<_>>())?;
+        self.dirty.clear();
+        info!("Synced {} pipelines to disk", pipelines.len());
+        Ok(())
+    }
+
+    /// Export all state to a single JSON file
+    ///
+    /// # Errors
+    /// Returns an error if export fails.
+    pub fn export_all(&self, path: &Path) -> Result<(), StoreError> {
+        let pipelines: Vec<&Pipeline> = self.cache.values().collect();
+        let content = serde_json::to_string_pretty(&pipelines)?;
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Import state from a JSON file
+    ///
+    /// # Errors
+    /// Returns an error if import fails.
+    pub fn import_from(&mut self, path: &Path) -> Result<usize, StoreError> {
+        let content = fs::read_to_string(path)?;
+        let pipelines: Vec<Pipeline> = serde_json::from_str(&content)?;
+
+        let count = pipelines.len();
+        self.write_all(&pipelines.iter().collect::<Vec<_>>())?;
+        for pipeline in pipelines {
+            self.cache.insert(pipeline.id.0.clone(), pipeline);
+        }
+
+        info!("Imported {} pipelines from {:?}", count, path);
+        Ok(count)
+    }
+
+    /// Clear all state (for testing)
+    #[cfg(test)]
+    pub fn clear(&mut self) -> Result<(), StoreError> {
+        self.db.delete_all()?;
+        self.cache.clear();
+        self.persisted.clear();
+        self.dirty.clear();
+        Ok(())
+    }
+}
+
+impl Drop for StateStore {
+    fn drop(&mut self) {
+        if let Err(e) = self.sync() {
+            error!("Failed to sync state on drop: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::PipelineState;
+
+    fn create_temp_store() -> (StateStore, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let store = StateStore::new(temp_dir.path().to_path_buf()).unwrap();
+        (store, temp_dir)
+    }
+
+    #[test]
+    fn test_create_and_get() {
+        let (mut store, _temp) = create_temp_store();
+
+        let pipeline = Pipeline::new("specs/test.yaml".to_string());
+        let id = pipeline.id.clone();
+
+        store.create(pipeline).unwrap();
+
+        let retrieved = store.get(&id).unwrap();
+        assert_eq!(retrieved.spec_path, "specs/test.yaml");
+    }
+
+    #[test]
+    fn test_update() {
+        let (mut store, _temp) = create_temp_store();
+
+        let pipeline = Pipeline::new("specs/test.yaml".to_string());
+        let id = pipeline.id.clone();
+
+        store.create(pipeline).unwrap();
+
+        let pipeline = store.get_mut(&id).unwrap();
+        pipeline.transition_to(PipelineState::SpecReview).unwrap();
+        let _ = pipeline;
+
+        let retrieved = store.get(&id).unwrap();
+        assert_eq!(retrieved.state, PipelineState::SpecReview);
+    }
+
+    #[test]
+    fn test_delete() {
+        let (mut store, _temp) = create_temp_store();
+
+        let pipeline = Pipeline::new("specs/test.yaml".to_string());
+        let id = pipeline.id.clone();
+
+        store.create(pipeline).unwrap();
+        store.delete(&id).unwrap();
+
+        assert!(store.get(&id).is_err());
+    }
+
+    #[test]
+    fn test_list_by_state() {
+        let (mut store, _temp) = create_temp_store();
+
+        let p1 = Pipeline::new("specs/test1.yaml".to_string());
+        let p2 = Pipeline::new("specs/test2.yaml".to_string());
+
+        store.create(p1).unwrap();
+        store.create(p2.clone()).unwrap();
+
+        let p2_id = PipelineId(p2.id.0.clone());
+        let pipeline = store.get_mut(&p2_id).unwrap();
+        pipeline.transition_to(PipelineState::SpecReview).unwrap();
+
+        let pending = store.list_by_state(PipelineState::Pending);
+        assert_eq!(pending.len(), 1);
+    }
+
+    #[test]
+    fn test_export_import() {
+        let (mut store, _temp) = create_temp_store();
+
+        let pipeline = Pipeline::new("specs/test.yaml".to_string());
+        store.create(pipeline).unwrap();
+
+        let export_path = _temp.path().join("export.json");
+        store.export_all(&export_path).unwrap();
+
+        let (mut store2, _temp2) = create_temp_store();
+        store2.import_from(&export_path).unwrap();
+
+        assert_eq!(store2.cache.len(), 1);
+    }
+
+    #[test]
+    fn test_state_survives_reopen() {
+        let (mut store, temp) = create_temp_store();
+        let mut pipeline = store
+            .create(Pipeline::new("specs/test.yaml".to_string()))
+            .unwrap();
+        pipeline.transition_to(PipelineState::SpecReview).unwrap();
+        store.update(pipeline.clone()).unwrap();
+        drop(store);
+
+        let store = StateStore::new(temp.path().to_path_buf()).unwrap();
+
+        assert_eq!(
+            store.get(&pipeline.id).unwrap().state,
+            PipelineState::SpecReview
+        );
+    }
+
+    #[test]
+    fn test_transitions_record_each_state_change() {
+        let (mut store, _temp) = create_temp_store();
+        let mut pipeline = store
+            .create(Pipeline::new("specs/test.yaml".to_string()))
+            .unwrap();
+        pipeline.transition_to(PipelineState::SpecReview).unwrap();
+        store.update(pipeline.clone()).unwrap();
+        store.update(pipeline.clone()).unwrap();
+        store
+            .get_mut(&pipeline.id)
+            .unwrap()
+            .transition_to(PipelineState::Failed)
+            .unwrap();
+        store.sync().unwrap();
+
+        let states: Vec<_> = store
+            .transitions(&pipeline.id)
+            .unwrap()
+            .into_iter()
+            .map(|t| (t.from, t.to))
+            .collect();
+
+        assert_eq!(
+            states,
+            vec![
+                (None, PipelineState::Pending),
+                (Some(PipelineState::Pending), PipelineState::SpecReview),
+                (Some(PipelineState::SpecReview), PipelineState::Failed),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delete_keeps_transitions() {
+        let (mut store, _temp) = create_temp_store();
+        let pipeline = store
+            .create(Pipeline::new("specs/test.yaml".to_string()))
+            .unwrap();
+
+        store.delete(&pipeline.id).unwrap();
+
+        assert_eq!(store.transitions(&pipeline.id).unwrap().len(), 1);
+        assert!(store.delete(&pipeline.id).is_err());
+    }
+
+    #[test]
+    fn test_created_between() {
+        let (mut store, _temp) = create_temp_store();
+        let mut old = Pipeline::new("specs/old.yaml".to_string());
+        old.created_at = Utc::now() - chrono::Duration::days(2);
+        store.create(old).unwrap();
+        let recent = store
+            .create(Pipeline::new("specs/new.yaml".to_string()))
+            .unwrap();
+
+        let found = store
+            .created_between(Utc::now() - chrono::Duration::days(1), Utc::now())
+            .unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, recent.id);
+    }
+
+    #[test]
+    fn test_aggregate_windows_splits_by_creation_time() {
+        let (mut store, _temp) = create_temp_store();
+        let now = Utc::now();
+        let mut old = Pipeline::new("specs/old.yaml".to_string());
+        old.created_at = now - chrono::Duration::days(10);
+        old.state = PipelineState::Failed;
+        let old = store.create(old).unwrap();
+        let mut recent = Pipeline::new("specs/new.yaml".to_string());
+        recent.state = PipelineState::Accepted;
+        recent.iteration = 2;
+        let recent = store.create(recent).unwrap();
+        for (id, secs) in [(&old.id, 1.0), (&recent.id, 3.0), (&recent.id, 5.0)] {
+            store
+                .record_phase(&PhaseMetrics {
+                    pipeline_id: id.0.clone(),
+                    phase: "validation".to_string(),
+                    started_at: now,
+                    duration_secs: secs,
+                    success: true,
+                })
+                .unwrap();
+        }
+
+        let windows = store
+            .aggregate_windows(
+                now - chrono::Duration::days(13),
+                now + chrono::Duration::seconds(1),
+                chrono::Duration::days(7),
+            )
+            .unwrap();
+
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].metrics.total_pipelines, 1);
+        assert_eq!(windows[0].metrics.failed_pipelines, 1);
+        assert_eq!(
+            windows[0].metrics.phase_durations.get("validation"),
+            Some(&1.0)
+        );
+        assert_eq!(windows[1].end, now + chrono::Duration::seconds(1));
+        assert_eq!(windows[1].metrics.successful_pipelines, 1);
+        assert_eq!(
+            windows[1].metrics.phase_durations.get("validation"),
+            Some(&4.0)
+        );
+        assert!((windows[1].metrics.average_iterations - 2.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_aggregate_windows_rejects_empty_width() {
+        let (store, _temp) = create_temp_store();
+        let now = Utc::now();
+
+        let result = store.aggregate_windows(now, now, chrono::Duration::zero());
+
+        assert!(matches!(result, Err(StoreError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn test_imports_legacy_json_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let pipeline = Pipeline::new("specs/test.yaml".to_string());
+        let file = temp_dir.path().join(format!("{}.json", pipeline.id));
+        fs::write(&file, serde_json::to_string(&pipeline).unwrap()).unwrap();
+
+        let store = StateStore::new(temp_dir.path().to_path_buf()).unwrap();
+
+        assert!(store.exists(&pipeline.id));
+        assert!(!file.exists());
+        assert!(file.with_extension("json.imported").exists());
+    }
+
+    #[tokio::test]
+    async fn test_store_works_inside_runtime() {
+        let (mut store, _temp) = create_temp_store();
+
+        let pipeline = store
+            .create(Pipeline::new("specs/test.yaml".to_string()))
+            .unwrap();
+
+        assert_eq!(store.transitions(&pipeline.id).unwrap().len(), 1);
+        drop(store);
+    }
+}
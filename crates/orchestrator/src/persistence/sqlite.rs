@@ -0,0 +1,408 @@
+//! `SQLite` storage behind [`super::StateStore`]
+//!
+//! Pipelines live in the `pipelines` table of `state.db`, one row each: the
+//! full pipeline as JSON alongside the columns queries filter on. Every state
+//! change is appended to `pipeline_transitions` in the same transaction as
+//! the row it changes, and triggers reject edits to that table, so the
+//! history can only grow. Phase timings are appended to `phase_metrics` as
+//! executors record them, so metrics can be rebuilt for any time window.
+//!
+//! sqlx is async and the store is not. Queries run on the database's own
+//! current-thread runtime, driven from a scoped thread so the store works
+//! whether or not the caller is already inside a Tokio runtime.
+
+use std::{future::Future, path::Path, thread, time::Duration};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous},
+    Row, SqlitePool,
+};
+use tokio::runtime::Runtime;
+use tracing::error;
+
+use super::{StoreError, Transition};
+use crate::{
+    metrics::PhaseMetrics,
+    state::{Pipeline, PipelineId, PipelineState},
+};
+
+/// How long a write waits for another process holding the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: [&str; 8] = [
+    "CREATE TABLE IF NOT EXISTS pipelines (
+        id TEXT PRIMARY KEY,
+        spec_path TEXT NOT NULL,
+        state TEXT NOT NULL,
+        iteration INTEGER NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        data TEXT NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_pipelines_state ON pipelines(state)",
+    "CREATE INDEX IF NOT EXISTS idx_pipelines_created_at ON pipelines(created_at)",
+    "CREATE TABLE IF NOT EXISTS pipeline_transitions (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        pipeline_id TEXT NOT NULL,
+        from_state TEXT,
+        to_state TEXT NOT NULL,
+        iteration INTEGER NOT NULL,
+        at TEXT NOT NULL
+    )",
+    "CREATE TRIGGER IF NOT EXISTS pipeline_transitions_no_update
+     BEFORE UPDATE ON pipeline_transitions
+     BEGIN SELECT RAISE(ABORT, 'pipeline transitions are append-only'); END",
+    "CREATE TRIGGER IF NOT EXISTS pipeline_transitions_no_delete
+     BEFORE DELETE ON pipeline_transitions
+     BEGIN SELECT RAISE(ABORT, 'pipeline transitions are append-only'); END",
+    "CREATE TABLE IF NOT EXISTS phase_metrics (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        pipeline_id TEXT NOT NULL,
+        phase TEXT NOT NULL,
+        started_at TEXT NOT NULL,
+        duration_secs REAL NOT NULL,
+        success INTEGER NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_phase_metrics_pipeline ON phase_metrics(pipeline_id)",
+];
+
+/// A pipeline row to write, and the transition that got it there, if any
+pub(super) type Write<'a> = (&'a Pipeline, Option<&'a Transition>);
+
+/// Connection to `state.db`
+pub(super) struct Database {
+    /// Taken on drop, to shut down off any caller's runtime
+    runtime: Option<Runtime>,
+    pool: SqlitePool,
+}
+
+impl Database {
+    /// Open or create the database at `path`
+    pub(super) fn open(path: &Path) -> Result<Self, StoreError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT);
+
+        let pool = drive(&runtime, async {
+            let pool = SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect_with(options)
+                .await?;
+            for statement in SCHEMA {
+                sqlx::query(statement).execute(&pool).await?;
+            }
+            Ok(pool)
+        })?;
+
+        Ok(Self {
+            runtime: Some(runtime),
+            pool,
+        })
+    }
+
+    fn run<T, F>(&self, future: F) -> Result<T, StoreError>
+    where
+        T: Send,
+        F: Future<Output = Result<T, sqlx::Error>> + Send,
+    {
+        let runtime = self
+            .runtime
+            .as_ref()
+            .ok_or_else(|| StoreError::Database("Database is closed".to_string()))?;
+        drive(runtime, future)
+    }
+
+    /// Every stored pipeline; rows that no longer parse are logged and skipped
+    pub(super) fn load_all(&self) -> Result<Vec<Pipeline>, StoreError> {
+        let rows = self.run(
+            sqlx::query("SELECT id, data FROM pipelines ORDER BY created_at").fetch_all(&self.pool),
+        )?;
+        Ok(rows.iter().filter_map(parse_pipeline).collect())
+    }
+
+    /// Write every pipeline and its transition in one transaction
+    pub(super) fn save(&self, writes: &[Write<'_>]) -> Result<(), StoreError> {
+        let rows = writes
+            .iter()
+            .map(|(pipeline, transition)| {
+                Ok((*pipeline, serde_json::to_string(pipeline)?, *transition))
+            })
+            .collect::<Result<Vec<_>, StoreError>>()?;
+
+        self.run(async {
+            let mut tx = self.pool.begin().await?;
+            for (pipeline, data, transition) in &rows {
+                sqlx::query(
+                    "INSERT INTO pipelines
+                     (id, spec_path, state, iteration, created_at, updated_at, data)
+                     VALUES (?, ?, ?, ?, ?, ?, ?)
+                     ON CONFLICT(id) DO UPDATE SET
+                        spec_path = excluded.spec_path,
+                        state = excluded.state,
+                        iteration = excluded.iteration,
+                        updated_at = excluded.updated_at,
+                        data = excluded.data",
+                )
+                .bind(&pipeline.id.0)
+                .bind(&pipeline.spec_path)
+                .bind(pipeline.state.as_str())
+                .bind(i64::from(pipeline.iteration))
+                .bind(timestamp(pipeline.created_at))
+                .bind(timestamp(pipeline.updated_at))
+                .bind(data)
+                .execute(&mut *tx)
+                .await?;
+
+                if let Some(transition) = transition {
+                    sqlx::query(
+                        "INSERT INTO pipeline_transitions
+                         (pipeline_id, from_state, to_state, iteration, at)
+                         VALUES (?, ?, ?, ?, ?)",
+                    )
+                    .bind(&transition.pipeline_id.0)
+                    .bind(transition.from.as_ref().map(PipelineState::as_str))
+                    .bind(transition.to.as_str())
+                    .bind(i64::from(transition.iteration))
+                    .bind(timestamp(transition.at))
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+            tx.commit().await
+        })
+    }
+
+    /// Remove a pipeline's row; its transitions stay
+    pub(super) fn delete(&self, id: &PipelineId) -> Result<(), StoreError> {
+        self.run(
+            sqlx::query("DELETE FROM pipelines WHERE id = ?")
+                .bind(&id.0)
+                .execute(&self.pool),
+        )?;
+        Ok(())
+    }
+
+    /// Remove every pipeline row
+    #[cfg(test)]
+    pub(super) fn delete_all(&self) -> Result<(), StoreError> {
+        self.run(sqlx::query("DELETE FROM pipelines").execute(&self.pool))?;
+        Ok(())
+    }
+
+    /// A pipeline's transitions, oldest first
+    pub(super) fn transitions(&self, id: &PipelineId) -> Result<Vec<Transition>, StoreError> {
+        let rows = self.run(
+            sqlx::query(
+                "SELECT from_state, to_state, iteration, at FROM pipeline_transitions
+                 WHERE pipeline_id = ? ORDER BY seq",
+            )
+            .bind(&id.0)
+            .fetch_all(&self.pool),
+        )?;
+
+        rows.iter()
+            .map(|row| {
+                let from: Option<String> = row.try_get("from_state")?;
+                let to: String = row.try_get("to_state")?;
+                let iteration: i64 = row.try_get("iteration")?;
+                let at: String = row.try_get("at")?;
+                Ok(Transition {
+                    pipeline_id: id.clone(),
+                    from: from.as_deref().map(parse_state).transpose()?,
+                    to: parse_state(&to)?,
+                    iteration: u32::try_from(iteration)
+                        .map_err(|e| StoreError::InvalidState(e.to_string()))?,
+                    at: parse_timestamp(&at)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Pipelines created in `[start, end)`, oldest first
+    pub(super) fn created_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<Pipeline>, StoreError> {
+        let rows = self.run(
+            sqlx::query(
+                "SELECT id, data FROM pipelines
+                 WHERE created_at >= ? AND created_at < ? ORDER BY created_at",
+            )
+            .bind(timestamp(start))
+            .bind(timestamp(end))
+            .fetch_all(&self.pool),
+        )?;
+        Ok(rows.iter().filter_map(parse_pipeline).collect())
+    }
+
+    /// Append one phase's timing
+    pub(super) fn record_phase(&self, phase: &PhaseMetrics) -> Result<(), StoreError> {
+        self.run(
+            sqlx::query(
+                "INSERT INTO phase_metrics
+                 (pipeline_id, phase, started_at, duration_secs, success)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(&phase.pipeline_id)
+            .bind(&phase.phase)
+            .bind(timestamp(phase.started_at))
+            .bind(phase.duration_secs)
+            .bind(phase.success)
+            .execute(&self.pool),
+        )?;
+        Ok(())
+    }
+
+    /// Phases of pipelines created in `[start, end)`, in recording order
+    pub(super) fn phases_created_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<PhaseMetrics>, StoreError> {
+        let rows = self.run(
+            sqlx::query(
+                "SELECT pipeline_id, phase, started_at, duration_secs, success
+                 FROM phase_metrics WHERE pipeline_id IN (
+                     SELECT id FROM pipelines WHERE created_at >= ? AND created_at < ?
+                 ) ORDER BY seq",
+            )
+            .bind(timestamp(start))
+            .bind(timestamp(end))
+            .fetch_all(&self.pool),
+        )?;
+
+        rows.iter()
+            .map(|row| {
+                let started_at: String = row.try_get("started_at")?;
+                Ok(PhaseMetrics {
+                    pipeline_id: row.try_get("pipeline_id")?,
+                    phase: row.try_get("phase")?,
+                    started_at: parse_timestamp(&started_at)?,
+                    duration_secs: row.try_get("duration_secs")?,
+                    success: row.try_get("success")?,
+                })
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub(super) fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    #[cfg(test)]
+    pub(super) fn block_on<T, F>(&self, future: F) -> Result<T, StoreError>
+    where
+        T: Send,
+        F: Future<Output = Result<T, sqlx::Error>> + Send,
+    {
+        self.run(future)
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        let Some(runtime) = self.runtime.take() else {
+            return;
+        };
+        // A runtime can't be dropped from inside another one, so close the
+        // pool and drop the runtime on a thread of their own
+        let pool = self.pool.clone();
+        let closed =
+            thread::scope(|scope| scope.spawn(move || runtime.block_on(pool.close())).join());
+        if closed.is_err() {
+            error!("Failed to close pipeline database");
+        }
+    }
+}
+
+/// Drive `future` to completion on `runtime` from a scoped thread
+fn drive<T, F>(runtime: &Runtime, future: F) -> Result<T, StoreError>
+where
+    T: Send,
+    F: Future<Output = Result<T, sqlx::Error>> + Send,
+{
+    thread::scope(|scope| scope.spawn(|| runtime.block_on(future)).join())
+        .map_err(|_| StoreError::Database("Database thread panicked".to_string()))?
+        .map_err(StoreError::from)
+}
+
+fn parse_pipeline(row: &sqlx::sqlite::SqliteRow) -> Option<Pipeline> {
+    let id: String = row.try_get("id").ok()?;
+    let parsed = row
+        .try_get::<String, _>("data")
+        .map_err(StoreError::from)
+        .and_then(|data| serde_json::from_str(&data).map_err(StoreError::from));
+    match parsed {
+        Ok(pipeline) => Some(pipeline),
+        Err(e) => {
+            error!("Failed to load pipeline {id}: {e}");
+            None
+        }
+    }
+}
+
+fn parse_state(key: &str) -> Result<PipelineState, StoreError> {
+    serde_json::from_value(serde_json::Value::String(key.to_string()))
+        .map_err(|_| StoreError::InvalidState(format!("Unknown pipeline state '{key}'")))
+}
+
+/// Fixed-width UTC timestamp, so text order is time order
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+fn parse_timestamp(text: &str) -> Result<DateTime<Utc>, StoreError> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|at| at.with_timezone(&Utc))
+        .map_err(|e| StoreError::InvalidState(format!("Invalid timestamp '{text}': {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    #[test]
+    fn test_transitions_are_append_only() {
+        let temp = TempDir::new().unwrap();
+        let db = Database::open(&temp.path().join("state.db")).unwrap();
+        let pipeline = Pipeline::new("specs/test.yaml".to_string());
+        let transition = Transition {
+            pipeline_id: pipeline.id.clone(),
+            from: None,
+            to: pipeline.state,
+            iteration: 0,
+            at: pipeline.created_at,
+        };
+        db.save(&[(&pipeline, Some(&transition))]).unwrap();
+
+        let update = db.block_on(
+            sqlx::query("UPDATE pipeline_transitions SET to_state = 'accepted'").execute(db.pool()),
+        );
+        let delete =
+            db.block_on(sqlx::query("DELETE FROM pipeline_transitions").execute(db.pool()));
+
+        assert!(update.is_err_and(|e| e.to_string().contains("append-only")));
+        assert!(delete.is_err_and(|e| e.to_string().contains("append-only")));
+        assert_eq!(db.transitions(&pipeline.id).unwrap(), vec![transition]);
+    }
+
+    #[test]
+    fn test_timestamps_sort_as_text() {
+        let early = parse_timestamp("2026-01-02T03:04:05Z").unwrap();
+        let late = parse_timestamp("2026-01-02T03:04:05.5Z").unwrap();
+
+        assert!(timestamp(early) < timestamp(late));
+        assert_eq!(parse_timestamp(&timestamp(late)).unwrap(), late);
+    }
+}
@@ -1,18 +1,33 @@
 //! Pipeline phase executor
 
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    thread,
+};
 
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use scenarios::{FeedbackLevel, RunnerConfig, TwinRoute};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, warn};
 
 use crate::{
-    metrics::{Metrics, PhaseMetrics, ScenarioResult},
-    persistence::StateStore,
-    state::{Pipeline, PipelineState},
+    agent::{AgentDriver, AgentRequest},
+    linter::SpecLinter,
+    metrics::{Metrics, PhaseMetrics, ScenarioResult, SharedMetrics},
+    persistence::{SharedStore, StateStore},
+    policy::{self, DecisionRecord, ErrorClass, PolicyConfig, RetryPolicy},
+    scheduler::lock,
+    state::{Pipeline, PipelineConfig, PipelineState, TransitionError},
+    universe::{self, Universe, UniverseError},
+    validation::{self, ScenarioRun},
 };
 
+/// How much scenario detail agents get back on a retry
+const AGENT_FEEDBACK_LEVEL: FeedbackLevel = FeedbackLevel::Level2;
+
 /// Result of a phase execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhaseResult {
@@ -20,6 +35,9 @@ pub struct PhaseResult {
     pub message: String,
     pub quality_score: Option<u32>,
     pub scenario_results: Vec<ScenarioResult>,
+    /// What went wrong, when the phase failed
+    #[serde(default)]
+    pub error_class: Option<ErrorClass>,
 }
 
 /// Decision made after validation
@@ -32,45 +50,125 @@ pub enum Decision {
     Fail,
 }
 
+/// Move `pipeline` into the state a phase runs in
+///
+/// A recovered pipeline may already be there, having crashed mid-phase.
+fn enter(pipeline: &mut Pipeline, state: PipelineState) -> Result<(), TransitionError> {
+    if pipeline.state == state {
+        return Ok(());
+    }
+    pipeline.transition_to(state)
+}
+
+/// Seconds elapsed since `start`, to the sub-second, or zero if the clock
+/// went backwards
+fn secs_since(start: DateTime<Utc>) -> f64 {
+    Utc::now()
+        .signed_duration_since(start)
+        .to_std()
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
 /// Pipeline executor for running phases
-#[allow(dead_code)]
+///
+/// The state store and metrics may be shared with other executors, each
+/// running its own pipelines; see [`crate::scheduler::PipelineScheduler`].
 pub struct PipelineExecutor {
-    store: StateStore,
-    metrics: Metrics,
+    store: SharedStore,
+    metrics: SharedMetrics,
     scenarios_path: PathBuf,
-    linter_path: Option<PathBuf>,
+    linter: SpecLinter,
+    /// Running twin universes by pipeline id
+    universes: HashMap<String, Universe>,
+    /// Runs the agent during development; skipped when unset
+    agent: Option<Box<dyn AgentDriver>>,
+    /// When to retry, escalate or fail
+    policies: PolicyConfig,
 }
 
 impl PipelineExecutor {
     /// Create a new pipeline executor
     ///
+    /// `linter_path` is a specs directory holding `linter/rules.yaml` and
+    /// `schema/spec.schema.json`; without one the bundled copies are used.
+    ///
     /// # Errors
-    /// Returns an error if the state store cannot be initialized.
+    /// Returns an error if the state store or spec linter cannot be initialized.
     pub fn new(
         state_dir: PathBuf,
         scenarios_path: PathBuf,
         linter_path: Option<PathBuf>,
     ) -> Result<Self> {
         let store = StateStore::new(state_dir).context("Failed to initialize state store")?;
+        let linter = linter_path
+            .as_deref()
+            .map_or_else(SpecLinter::bundled, SpecLinter::from_dir)
+            .context("Failed to load spec linter")?;
+
+        Ok(Self::with_shared(
+            Arc::new(Mutex::new(store)),
+            Arc::new(Mutex::new(Metrics::new())),
+            scenarios_path,
+            linter,
+        ))
+    }
 
-        Ok(Self {
+    /// Create an executor over a store and metrics other executors also use
+    #[must_use]
+    pub fn with_shared(
+        store: SharedStore,
+        metrics: SharedMetrics,
+        scenarios_path: PathBuf,
+        linter: SpecLinter,
+    ) -> Self {
+        Self {
             store,
-            metrics: Metrics::new(),
+            metrics,
             scenarios_path,
-            linter_path,
-        })
+            linter,
+            universes: HashMap::new(),
+            agent: None,
+            policies: PolicyConfig::default(),
+        }
     }
 
-    /// Get the state store
+    /// Run development iterations through `agent`
     #[must_use]
-    pub fn store(&self) -> &StateStore {
-        &self.store
+    pub fn with_agent(mut self, agent: Box<dyn AgentDriver>) -> Self {
+        self.agent = Some(agent);
+        self
     }
 
-    /// Get metrics
+    /// Decide on pipelines under `policies` rather than the default policy
     #[must_use]
-    pub fn metrics(&self) -> &Metrics {
-        &self.metrics
+    pub fn with_policies(mut self, policies: PolicyConfig) -> Self {
+        self.policies = policies;
+        self
+    }
+
+    /// Get the state store
+    ///
+    /// The store is locked until the guard is dropped, so don't hold it
+    /// across a pipeline run.
+    pub fn store(&self) -> MutexGuard<'_, StateStore> {
+        lock(&self.store)
+    }
+
+    /// Get metrics
+    pub fn metrics(&self) -> MutexGuard<'_, Metrics> {
+        lock(&self.metrics)
+    }
+
+    /// Record a phase's timing in memory and persist it with the pipeline
+    fn record_phase(&self, phase: PhaseMetrics) {
+        if let Err(e) = self.store().record_phase(&phase) {
+            warn!(
+                "Failed to persist {} metrics for pipeline {}: {e}",
+                phase.phase, phase.pipeline_id
+            );
+        }
+        self.metrics().record_phase(phase);
     }
 
     /// Create a new pipeline
@@ -78,8 +176,21 @@ impl PipelineExecutor {
     /// # Errors
     /// Returns an error if the pipeline cannot be created.
     pub fn create_pipeline(&mut self, spec_path: String) -> Result<Pipeline> {
-        let pipeline = Pipeline::new(spec_path);
-        let pipeline = self.store.create(pipeline)?;
+        self.create_pipeline_with_config(spec_path, &PipelineConfig::default())
+    }
+
+    /// Create a new pipeline with its iteration limit and quality threshold
+    /// taken from `config`
+    ///
+    /// # Errors
+    /// Returns an error if the pipeline cannot be created.
+    pub fn create_pipeline_with_config(
+        &mut self,
+        spec_path: String,
+        config: &PipelineConfig,
+    ) -> Result<Pipeline> {
+        let pipeline = Pipeline::with_config(spec_path, config);
+        let pipeline = self.store().create(pipeline)?;
         info!("Created pipeline: {}", pipeline.id);
         Ok(pipeline)
     }
@@ -91,7 +202,16 @@ impl PipelineExecutor {
     pub fn run_pipeline(&mut self, pipeline_id: &crate::state::PipelineId) -> Result<Decision> {
         info!("Starting pipeline: {}", pipeline_id.0);
 
-        let mut pipeline = self.store.get(pipeline_id)?.clone();
+        let mut pipeline = self.store().get(pipeline_id)?.clone();
+        let policy = self.policy_for(&pipeline);
+        // The limit is fixed as the pipeline starts, so iterations a human
+        // grants when resuming it aren't taken back
+        if let Some(max_iterations) = policy
+            .max_iterations
+            .filter(|_| pipeline.state == PipelineState::Pending)
+        {
+            pipeline.max_iterations = max_iterations;
+        }
 
         // Recovery check: find where we left off
         if !pipeline.state.is_terminal() {
@@ -101,17 +221,37 @@ impl PipelineExecutor {
         // Phase 1: Spec Review
         if pipeline.state == PipelineState::Pending || pipeline.state == PipelineState::SpecReview {
             let result = self.spec_review(&mut pipeline)?;
-            if !result.success {
-                return Ok(self.handle_spec_failure(pipeline_id, result.message));
+            if let Some(class) = result.error_class {
+                return self.conclude_failure(&mut pipeline, &policy, class, &result.message);
             }
+            self.store().update(pipeline.clone())?;
         }
 
         // Phase 2: Universe Setup
         if pipeline.state == PipelineState::UniverseSetup {
             let result = self.universe_setup(&mut pipeline)?;
-            if !result.success {
-                return Ok(self.handle_setup_failure(pipeline_id, result.message));
+            if let Some(class) = result.error_class {
+                return self.conclude_failure(&mut pipeline, &policy, class, &result.message);
+            }
+            self.store().update(pipeline.clone())?;
+        }
+
+        // A recovered pipeline lost its twins with the process that booted them
+        if matches!(
+            pipeline.state,
+            PipelineState::AgentDevelopment | PipelineState::Validation
+        ) && !self.universes.contains_key(&pipeline.id.0)
+        {
+            if let Err(e) = self.boot_universe(&mut pipeline) {
+                let message = format!("Universe setup failed: {e}");
+                return self.conclude_failure(
+                    &mut pipeline,
+                    &policy,
+                    ErrorClass::UniverseSetup,
+                    &message,
+                );
             }
+            self.store().update(pipeline.clone())?;
         }
 
         // Phase 3: Agent Development (loop)
@@ -120,41 +260,33 @@ impl PipelineExecutor {
         {
             if pipeline.state == PipelineState::AgentDevelopment {
                 let result = self.agent_development(&mut pipeline)?;
-                if !result.success {
-                    return Ok(self.handle_dev_failure(pipeline_id, result.message));
+                if let Some(class) = result.error_class {
+                    match self.conclude_failure(&mut pipeline, &policy, class, &result.message)? {
+                        Decision::Retry => {
+                            Self::back_off(&policy, &pipeline);
+                            continue;
+                        }
+                        decision => return Ok(decision),
+                    }
                 }
+                self.store().update(pipeline.clone())?;
             }
 
             // Phase 4: Validation
             if pipeline.state == PipelineState::Validation {
-                let (decision, _result) = self.validation(&mut pipeline)?;
+                let (record, _result) = self.validation(&mut pipeline)?;
 
-                match decision {
-                    Decision::Accept => {
-                        self.finalize_acceptance(pipeline_id)?;
-                        return Ok(Decision::Accept);
-                    }
-                    Decision::Retry if pipeline.can_iterate() => {
-                        pipeline.iteration += 1;
-                        self.store.update(pipeline.clone())?;
+                match self.conclude(&mut pipeline, record)? {
+                    Decision::Retry => {
+                        pipeline.transition_to(PipelineState::AgentDevelopment)?;
+                        self.store().update(pipeline.clone())?;
                         info!(
                             "Retrying agent development, iteration {}",
-                            pipeline.iteration
+                            pipeline.iteration + 1
                         );
+                        Self::back_off(&policy, &pipeline);
                     }
-                    Decision::Retry => {
-                        warn!("Max iterations reached, escalating");
-                        self.escalate(pipeline_id, "Max iterations reached")?;
-                        return Ok(Decision::Escalate);
-                    }
-                    Decision::Escalate => {
-                        self.escalate(pipeline_id, "Validation escalated")?;
-                        return Ok(Decision::Escalate);
-                    }
-                    Decision::Fail => {
-                        self.fail(pipeline_id, "Validation failed")?;
-                        return Ok(Decision::Fail);
-                    }
+                    decision => return Ok(decision),
                 }
             }
         }
@@ -176,53 +308,63 @@ impl PipelineExecutor {
         let start = Utc::now();
         info!("Running spec review for: {}", pipeline.spec_path);
 
-        pipeline.transition_to(PipelineState::SpecReview)?;
-
-        // In a real implementation, this would run the linter
-        // For now, simulate a linter check
-        let quality_score = self.run_linter(&pipeline.spec_path);
+        enter(pipeline, PipelineState::SpecReview)?;
+
+        let report = match self.linter.lint_file(Path::new(&pipeline.spec_path)) {
+            Ok(report) => report,
+            Err(e) => {
+                self.record_spec_review(pipeline, start, false);
+                return Ok(PhaseResult {
+                    success: false,
+                    message: format!("Spec could not be linted: {e}"),
+                    quality_score: None,
+                    scenario_results: vec![],
+                    error_class: Some(ErrorClass::SpecRejected),
+                });
+            }
+        };
 
-        let duration = Utc::now().signed_duration_since(start);
-        self.metrics.record_phase(PhaseMetrics {
-            pipeline_id: pipeline.id.0.clone(),
-            phase: "spec_review".to_string(),
-            started_at: start,
-            duration_secs: duration.num_seconds() as f64,
-            success: quality_score >= pipeline.quality_threshold,
-        });
+        let quality_score = report.quality_score;
+        let passed = report.passes(pipeline.quality_threshold);
+        debug!(
+            "Spec {} linted: {}",
+            pipeline.spec_path,
+            report.summary(pipeline.quality_threshold)
+        );
+        self.record_spec_review(pipeline, start, passed);
 
-        if quality_score >= pipeline.quality_threshold {
+        if passed {
             pipeline.transition_to(PipelineState::UniverseSetup)?;
             Ok(PhaseResult {
                 success: true,
                 message: format!("Spec passed with score {quality_score}"),
                 quality_score: Some(quality_score),
                 scenario_results: vec![],
+                error_class: None,
             })
         } else {
-            pipeline.transition_to(PipelineState::Failed)?;
             Ok(PhaseResult {
                 success: false,
                 message: format!(
-                    "Spec quality {quality_score} below threshold {}",
-                    pipeline.quality_threshold
+                    "Spec review failed: {}",
+                    report.summary(pipeline.quality_threshold)
                 ),
                 quality_score: Some(quality_score),
                 scenario_results: vec![],
+                error_class: Some(ErrorClass::SpecRejected),
             })
         }
     }
 
-    /// Run the linter (simulated)
-    #[must_use]
-    fn run_linter(&self, _spec_path: &str) -> u32 {
-        // In a real implementation, this would:
-        // 1. Run the linter binary
-        // 2. Parse the output
-        // 3. Return a quality score
-        // For now, simulate a passing score
-        debug!("Running linter on spec");
-        85
+    /// Record spec review metrics
+    fn record_spec_review(&mut self, pipeline: &Pipeline, start: DateTime<Utc>, success: bool) {
+        self.record_phase(PhaseMetrics {
+            pipeline_id: pipeline.id.0.clone(),
+            phase: "spec_review".to_string(),
+            started_at: start,
+            duration_secs: secs_since(start),
+            success,
+        });
     }
 
     /// Phase 2: Universe setup
@@ -230,29 +372,66 @@ impl PipelineExecutor {
         let start = Utc::now();
         info!("Setting up universe for pipeline: {}", pipeline.id);
 
-        pipeline.transition_to(PipelineState::UniverseSetup)?;
+        enter(pipeline, PipelineState::UniverseSetup)?;
+
+        let booted = self.boot_universe(pipeline);
 
-        // Simulate universe setup
-        // In real implementation, this would deploy the twin
-        let duration = Utc::now().signed_duration_since(start);
-        self.metrics.record_phase(PhaseMetrics {
+        self.record_phase(PhaseMetrics {
             pipeline_id: pipeline.id.0.clone(),
             phase: "universe_setup".to_string(),
             started_at: start,
-            duration_secs: duration.num_seconds() as f64,
-            success: true,
+            duration_secs: secs_since(start),
+            success: booted.is_ok(),
         });
 
+        if let Err(e) = booted {
+            return Ok(PhaseResult {
+                success: false,
+                message: format!("Universe setup failed: {e}"),
+                quality_score: None,
+                scenario_results: vec![],
+                error_class: Some(ErrorClass::UniverseSetup),
+            });
+        }
+
         pipeline.transition_to(PipelineState::AgentDevelopment)?;
 
         Ok(PhaseResult {
             success: true,
-            message: "Universe setup complete".to_string(),
+            message: format!(
+                "Universe setup complete with {} twin(s)",
+                pipeline.twins.len()
+            ),
             quality_score: None,
             scenario_results: vec![],
+            error_class: None,
         })
     }
 
+    /// Boot the twins the pipeline's spec lists and record their URLs
+    fn boot_universe(&mut self, pipeline: &mut Pipeline) -> Result<(), UniverseError> {
+        self.teardown_universe(&pipeline.id);
+
+        let paths = universe::twin_paths(Path::new(&pipeline.spec_path))?;
+        let definitions = universe::load_definitions(&paths)?;
+        let booted = Universe::boot(definitions, universe::READY_TIMEOUT)?;
+
+        pipeline.twins = booted.twins().to_vec();
+        for twin in &pipeline.twins {
+            info!("Twin {} ready at {}", twin.name, twin.base_url);
+        }
+        self.universes.insert(pipeline.id.0.clone(), booted);
+        Ok(())
+    }
+
+    /// Stop the pipeline's twins, if any are running
+    fn teardown_universe(&mut self, id: &crate::state::PipelineId) {
+        if let Some(universe) = self.universes.remove(&id.0) {
+            debug!("Tearing down universe for pipeline {}", id.0);
+            universe.shutdown();
+        }
+    }
+
     /// Phase 3: Agent development
     fn agent_development(&mut self, pipeline: &mut Pipeline) -> Result<PhaseResult> {
         let start = Utc::now();
@@ -262,23 +441,31 @@ impl PipelineExecutor {
             pipeline.id
         );
 
-        pipeline.transition_to(PipelineState::AgentDevelopment)?;
+        enter(pipeline, PipelineState::AgentDevelopment)?;
 
-        // Simulate agent work
-        // In real implementation, this would invoke the agent
+        let failure = self.develop(pipeline)?;
 
-        let duration = Utc::now().signed_duration_since(start);
-        self.metrics.record_phase(PhaseMetrics {
+        self.record_phase(PhaseMetrics {
             pipeline_id: pipeline.id.0.clone(),
             phase: "agent_development".to_string(),
             started_at: start,
-            duration_secs: duration.num_seconds() as f64,
-            success: true,
+            duration_secs: secs_since(start),
+            success: failure.is_none(),
         });
 
-        // Increment iteration count
+        // A failed attempt uses up its iteration too
         pipeline.increment_iteration()?;
 
+        if let Some((class, message)) = failure {
+            return Ok(PhaseResult {
+                success: false,
+                message,
+                quality_score: None,
+                scenario_results: vec![],
+                error_class: Some(class),
+            });
+        }
+
         pipeline.transition_to(PipelineState::Validation)?;
 
         Ok(PhaseResult {
@@ -289,180 +476,187 @@ impl PipelineExecutor {
             ),
             quality_score: None,
             scenario_results: vec![],
+            error_class: None,
         })
     }
 
+    /// Run the agent for the next iteration, returning how and why it failed
+    ///
+    /// The session it ran in is persisted as soon as it is known, so a crash
+    /// or escalation still leaves the pipeline pointing at its work.
+    fn develop(&mut self, pipeline: &mut Pipeline) -> Result<Option<(ErrorClass, String)>> {
+        let Some(agent) = self.agent.as_mut() else {
+            debug!("No agent configured for pipeline {}", pipeline.id);
+            return Ok(None);
+        };
+
+        let request = AgentRequest::for_pipeline(pipeline);
+        let outcome = match agent.develop(&request) {
+            Ok(outcome) => outcome,
+            Err(e) => return Ok(Some((ErrorClass::AgentFailed, e.to_string()))),
+        };
+
+        pipeline.session = Some(outcome.session.clone());
+        self.store().update(pipeline.clone())?;
+
+        if outcome.succeeded() {
+            Ok(None)
+        } else {
+            Ok(Some(match outcome.exit_code {
+                Some(code) => (
+                    ErrorClass::AgentFailed,
+                    format!("Agent exited with code {code} in {}", outcome.session),
+                ),
+                None => (
+                    ErrorClass::AgentKilled,
+                    format!("Agent was killed in {}", outcome.session),
+                ),
+            }))
+        }
+    }
+
     /// Phase 4: Validation
-    fn validation(&mut self, pipeline: &mut Pipeline) -> Result<(Decision, PhaseResult)> {
+    fn validation(&mut self, pipeline: &mut Pipeline) -> Result<(DecisionRecord, PhaseResult)> {
         let start = Utc::now();
         info!("Running validation for pipeline: {}", pipeline.id);
 
-        pipeline.transition_to(PipelineState::Validation)?;
+        enter(pipeline, PipelineState::Validation)?;
 
         // Run scenarios
-        let scenario_results = self.run_scenarios(pipeline);
+        let ScenarioRun {
+            results: scenario_results,
+            feedback,
+        } = self.run_scenarios(pipeline);
 
-        let duration = Utc::now().signed_duration_since(start);
-        self.metrics.record_phase(PhaseMetrics {
+        self.record_phase(PhaseMetrics {
             pipeline_id: pipeline.id.0.clone(),
             phase: "validation".to_string(),
             started_at: start,
-            duration_secs: duration.num_seconds() as f64,
+            duration_secs: secs_since(start),
             success: !scenario_results.is_empty(),
         });
 
         // Make decision based on scenario results
-        let decision = self.make_decision(&scenario_results, pipeline);
+        let record = self.make_decision(&scenario_results, pipeline);
+        let decision = record.decision;
+        pipeline.feedback = (decision != Decision::Accept).then_some(feedback);
 
         let result = PhaseResult {
             success: decision != Decision::Fail,
             message: format!("Validation complete, decision: {decision:?}"),
             quality_score: None,
             scenario_results,
+            error_class: None,
         };
 
-        Ok((decision, result))
+        Ok((record, result))
     }
 
-    /// Run scenarios
+    /// Run every scenario under `scenarios_path` against the twin universe
+    ///
+    /// Relative scenario URLs resolve against the spec's first twin; absolute
+    /// ones reach any twin by its name or declared port.
     #[must_use]
-    fn run_scenarios(&self, _pipeline: &Pipeline) -> Vec<ScenarioResult> {
-        // In real implementation, this would run the scenario runner
-        // For now, simulate some scenarios
-        debug!("Running scenarios");
-
-        vec![
-            ScenarioResult {
-                name: "happy_path".to_string(),
-                passed: true,
-                duration_secs: 1.5,
-                error: None,
-            },
-            ScenarioResult {
-                name: "edge_case".to_string(),
-                passed: true,
-                duration_secs: 0.8,
-                error: None,
-            },
-        ]
+    fn run_scenarios(&self, pipeline: &Pipeline) -> ScenarioRun {
+        debug!(
+            "Running scenarios from {} for pipeline {}",
+            self.scenarios_path.display(),
+            pipeline.id
+        );
+        let mut config = RunnerConfig::default();
+        if let Some(twin) = pipeline.twins.first() {
+            config.twin_url.clone_from(&twin.base_url);
+        }
+        config.twins = pipeline
+            .twins
+            .iter()
+            .map(|twin| TwinRoute {
+                name: twin.name.clone(),
+                port: twin.port,
+                base_url: twin.base_url.clone(),
+            })
+            .collect();
+        validation::run_scenarios(&self.scenarios_path, config, AGENT_FEEDBACK_LEVEL)
     }
 
-    /// Make accept/retry/escalate/fail decision
+    /// Make accept/retry/escalate/fail decision under the spec's policy
     #[must_use]
-    fn make_decision(&self, results: &[ScenarioResult], pipeline: &Pipeline) -> Decision {
-        let passed_count = results.iter().filter(|r| r.passed).count();
-        let total = results.len();
-
-        if total == 0 {
-            warn!("No scenarios ran, defaulting to retry");
-            return Decision::Retry;
+    fn make_decision(&self, results: &[ScenarioResult], pipeline: &Pipeline) -> DecisionRecord {
+        if results.is_empty() {
+            warn!("No scenarios ran for pipeline {}", pipeline.id);
         }
+        let record = self.policy_for(pipeline).evaluate(results, pipeline);
+        debug!("Decided {:?}: {}", record.decision, record.reason);
+        record
+    }
 
-        let pass_rate = (passed_count * 100) / total;
-
-        if pass_rate >= 100 {
-            debug!("All {total} scenarios passed");
-            Decision::Accept
-        } else if pass_rate >= 50 {
-            debug!("{pass_rate}% scenarios passed, allowing retry");
-            if pipeline.can_iterate() {
-                Decision::Retry
-            } else {
-                Decision::Escalate
+    /// Record `record` on the pipeline and act on it
+    ///
+    /// Anything but a retry ends the pipeline and tears down its universe.
+    fn conclude(&mut self, pipeline: &mut Pipeline, record: DecisionRecord) -> Result<Decision> {
+        let decision = record.decision;
+        let reason = record.reason.clone();
+        pipeline.decisions.push(record);
+
+        match decision {
+            Decision::Accept => {
+                self.teardown_universe(&pipeline.id);
+                pipeline.transition_to(PipelineState::Accepted)?;
+                info!("Pipeline {} accepted: {reason}", pipeline.id.0);
+            }
+            Decision::Retry => info!("Retrying pipeline {}: {reason}", pipeline.id.0),
+            Decision::Escalate => {
+                self.teardown_universe(&pipeline.id);
+                pipeline.transition_to(PipelineState::Escalated)?;
+                warn!("Pipeline {} escalated: {reason}", pipeline.id.0);
+                pipeline.set_error(reason);
+            }
+            Decision::Fail => {
+                self.teardown_universe(&pipeline.id);
+                pipeline.transition_to(PipelineState::Failed)?;
+                error!("Pipeline {} failed: {reason}", pipeline.id.0);
+                pipeline.set_error(reason);
             }
-        } else {
-            debug!("Only {pass_rate}% scenarios passed, failing");
-            Decision::Fail
         }
-    }
 
-    /// Handle spec review failure
-    fn handle_spec_failure(&mut self, id: &crate::state::PipelineId, message: String) -> Decision {
-        error!("Spec review failed: {message}");
-        let pipeline_opt = self.store.get_mut(id).ok().map(|p| {
-            let _ = p.transition_to(PipelineState::Failed);
-            p.set_error(message);
-            p.clone()
-        });
-        if let Some(pipeline) = pipeline_opt {
-            let _ = self.store.update(pipeline);
-        }
-        Decision::Fail
+        self.store().update(pipeline.clone())?;
+        Ok(decision)
     }
 
-    /// Handle universe setup failure
-    fn handle_setup_failure(&mut self, id: &crate::state::PipelineId, message: String) -> Decision {
-        error!("Universe setup failed: {message}");
-        let pipeline_opt = self.store.get_mut(id).ok().map(|p| {
-            let _ = p.transition_to(PipelineState::Escalated);
-            p.set_error(message);
-            p.clone()
-        });
-        if let Some(pipeline) = pipeline_opt {
-            let _ = self.store.update(pipeline);
-        }
-        Decision::Escalate
+    /// Decide on a phase that failed with `class` and act on it
+    fn conclude_failure(
+        &mut self,
+        pipeline: &mut Pipeline,
+        policy: &RetryPolicy,
+        class: ErrorClass,
+        message: &str,
+    ) -> Result<Decision> {
+        let record = policy.on_error(class, message, pipeline);
+        self.conclude(pipeline, record)
     }
 
-    /// Handle agent development failure
-    fn handle_dev_failure(&mut self, id: &crate::state::PipelineId, message: String) -> Decision {
-        error!("Agent development failed: {message}");
-        let pipeline_opt = self.store.get_mut(id).ok().map(|p| {
-            let _ = p.transition_to(PipelineState::Escalated);
-            p.set_error(message);
-            p.clone()
-        });
-        if let Some(pipeline) = pipeline_opt {
-            let _ = self.store.update(pipeline);
-        }
-        Decision::Escalate
+    /// Policy for `pipeline`'s spec, by the spec's id
+    fn policy_for(&self, pipeline: &Pipeline) -> RetryPolicy {
+        let spec_id = policy::spec_id(Path::new(&pipeline.spec_path));
+        self.policies.policy_for(spec_id.as_deref()).clone()
     }
 
-    /// Finalize acceptance
-    fn finalize_acceptance(&mut self, id: &crate::state::PipelineId) -> Result<()> {
-        let pipeline_opt = self.store.get_mut(id).ok().map(|p| {
-            let _ = p.transition_to(PipelineState::Accepted);
-            p.clone()
-        });
-        if let Some(pipeline) = pipeline_opt {
-            self.store.update(pipeline)?;
-            info!("Pipeline {} accepted", id.0);
+    /// Wait out the policy's backoff before the pipeline's next iteration
+    fn back_off(policy: &RetryPolicy, pipeline: &Pipeline) {
+        let delay = policy.backoff.delay(pipeline.iteration);
+        if !delay.is_zero() {
+            info!(
+                "Backing off {delay:?} before retrying pipeline {}",
+                pipeline.id.0
+            );
+            thread::sleep(delay);
         }
-        Ok(())
-    }
-
-    /// Escalate pipeline
-    fn escalate(&mut self, id: &crate::state::PipelineId, reason: &str) -> Result<()> {
-        let pipeline_opt = self.store.get_mut(id).ok().map(|p| {
-            let _ = p.transition_to(PipelineState::Escalated);
-            p.set_error(reason.to_string());
-            p.clone()
-        });
-        if let Some(pipeline) = pipeline_opt {
-            self.store.update(pipeline)?;
-            warn!("Pipeline {} escalated: {reason}", id.0);
-        }
-        Ok(())
-    }
-
-    /// Fail pipeline
-    fn fail(&mut self, id: &crate::state::PipelineId, reason: &str) -> Result<()> {
-        let pipeline_opt = self.store.get_mut(id).ok().map(|p| {
-            let _ = p.transition_to(PipelineState::Failed);
-            p.set_error(reason.to_string());
-            p.clone()
-        });
-        if let Some(pipeline) = pipeline_opt {
-            self.store.update(pipeline)?;
-            error!("Pipeline {} failed: {reason}", id.0);
-        }
-        Ok(())
     }
 
     /// Get pending pipelines for recovery
     #[must_use]
     pub fn get_pending_pipelines(&self) -> Vec<Pipeline> {
-        self.store
+        self.store()
             .get_pending_recovery()
             .into_iter()
             .cloned()
@@ -474,11 +668,11 @@ impl PipelineExecutor {
     /// # Errors
     /// Returns an error if recovery fails.
     pub fn recover_pipeline(&mut self, pipeline_id: &crate::state::PipelineId) -> Result<Decision> {
-        let pipeline = self.store.get(pipeline_id)?;
+        let state = self.store().get(pipeline_id)?.state;
 
-        if pipeline.state.is_terminal() {
+        if state.is_terminal() {
             info!("Pipeline {} already in terminal state", pipeline_id.0);
-            return match pipeline.state {
+            return match state {
                 PipelineState::Accepted => Ok(Decision::Accept),
                 PipelineState::Escalated => Ok(Decision::Escalate),
                 PipelineState::Failed => Ok(Decision::Fail),
@@ -491,10 +685,12 @@ impl PipelineExecutor {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use tempfile::TempDir;
 
     use super::*;
+    pub(crate) use crate::linter::tests::GOOD_SPEC as SPEC;
+    use crate::validation::tests::assert_scenario;
 
     fn create_executor() -> (PipelineExecutor, TempDir) {
         let temp_dir = TempDir::new().unwrap();
@@ -516,6 +712,367 @@ mod tests {
         assert_eq!(pipeline.state, PipelineState::Pending);
     }
 
+    #[test]
+    fn test_phase_durations_keep_fractional_seconds() {
+        let elapsed = secs_since(Utc::now() - chrono::Duration::milliseconds(1500));
+
+        assert!((1.5..2.5).contains(&elapsed), "elapsed {elapsed}");
+        assert!(secs_since(Utc::now() + chrono::Duration::seconds(5)).abs() < f64::EPSILON);
+    }
+
+    pub(crate) fn write_spec(temp: &TempDir, contents: &str) -> String {
+        let path = temp.path().join("spec.yaml");
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_spec_review_passes_good_spec() {
+        let (mut executor, temp) = create_executor();
+        let mut pipeline = executor.create_pipeline(write_spec(&temp, SPEC)).unwrap();
+
+        let result = executor.spec_review(&mut pipeline).unwrap();
+
+        assert!(result.success, "{}", result.message);
+        assert_eq!(result.quality_score, Some(100));
+        assert_eq!(pipeline.state, PipelineState::UniverseSetup);
+    }
+
+    #[test]
+    fn test_spec_review_gates_pipeline_on_low_score() {
+        let (mut executor, temp) = create_executor();
+        let spec = write_spec(&temp, "identity:\n  id: spec-login\n  title: Login\n");
+        let pipeline = executor.create_pipeline(spec).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Fail);
+        let stored = executor.store().get(&pipeline.id).unwrap().clone();
+        assert_eq!(stored.state, PipelineState::Failed);
+        assert!(stored
+            .last_error
+            .as_deref()
+            .is_some_and(|e| e.contains("COMP-002")));
+    }
+
+    #[test]
+    fn test_spec_review_fails_unreadable_spec() {
+        let (mut executor, temp) = create_executor();
+        let mut pipeline = executor
+            .create_pipeline(temp.path().join("missing.yaml").display().to_string())
+            .unwrap();
+
+        let result = executor.spec_review(&mut pipeline).unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.quality_score, None);
+        assert!(result.message.contains("could not be linted"));
+    }
+
+    pub(crate) fn write_scenario(temp: &TempDir, name: &str, actual: &str) {
+        let dir = temp.path().join("scenarios");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join(format!("{name}.yaml")),
+            assert_scenario(name, actual),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_pipeline_accepted_when_scenarios_pass() {
+        let (mut executor, temp) = create_executor();
+        write_scenario(&temp, "happy_path", "ok");
+        let pipeline = executor.create_pipeline(write_spec(&temp, SPEC)).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Accept);
+        let stored = executor.store().get(&pipeline.id).unwrap().clone();
+        assert_eq!(stored.state, PipelineState::Accepted);
+    }
+
+    #[test]
+    fn test_pipeline_failed_when_scenarios_fail() {
+        let (mut executor, temp) = create_executor();
+        write_scenario(&temp, "happy_path", "broken");
+        let pipeline = executor.create_pipeline(write_spec(&temp, SPEC)).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Fail);
+    }
+
+    #[test]
+    fn test_scenarios_run_against_the_pipeline_universe() {
+        let (mut executor, temp) = create_executor();
+        std::fs::write(
+            temp.path().join("twin.yaml"),
+            "name: health\nport: 3001\nendpoints:\n  - path: /api/health\n    method: GET\n    response:\n      status: 200\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(temp.path().join("scenarios")).unwrap();
+        std::fs::write(
+            temp.path().join("scenarios/health.yaml"),
+            "name: health\ndescription: test\nsteps:\n  - type: http\n    url: /api/health\n",
+        )
+        .unwrap();
+        let spec = write_spec(&temp, &format!("{SPEC}twins:\n  - twin.yaml\n"));
+        let pipeline = executor.create_pipeline(spec).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Accept);
+        let stored = executor.store().get(&pipeline.id).unwrap().clone();
+        assert_eq!(stored.twins.len(), 1);
+        assert!(stored.twins[0].base_url.starts_with("http://127.0.0.1:"));
+        assert_eq!(executor.universes.len(), 0);
+    }
+
+    #[test]
+    fn test_scenarios_reach_every_twin_by_name_or_port() {
+        let (mut executor, temp) = create_executor();
+        std::fs::write(
+            temp.path().join("mail.yaml"),
+            "name: mail\nport: 3001\nendpoints:\n  - path: /v3/mail/send\n    method: GET\n    response:\n      status: 200\n",
+        )
+        .unwrap();
+        std::fs::write(
+            temp.path().join("payments.yaml"),
+            "name: payments\nport: 3002\nendpoints:\n  - path: /v1/charges\n    method: GET\n    response:\n      status: 200\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(temp.path().join("scenarios")).unwrap();
+        std::fs::write(
+            temp.path().join("scenarios/both.yaml"),
+            "name: both\ndescription: test\nsteps:\n  - type: http\n    url: http://localhost:3002/v1/charges\n  - type: http\n    url: http://mail/v3/mail/send\n",
+        )
+        .unwrap();
+        let spec = write_spec(
+            &temp,
+            &format!("{SPEC}twins:\n  - mail.yaml\n  - payments.yaml\n"),
+        );
+        let pipeline = executor.create_pipeline(spec).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        let stored = executor.store().get(&pipeline.id).unwrap().clone();
+        assert_eq!(decision, Decision::Accept, "{:?}", stored.last_error);
+        assert_eq!(
+            stored
+                .twins
+                .iter()
+                .map(|twin| (twin.name.as_str(), twin.port))
+                .collect::<Vec<_>>(),
+            vec![("mail", Some(3001)), ("payments", Some(3002))]
+        );
+    }
+
+    #[test]
+    fn test_missing_twin_escalates_setup() {
+        let (mut executor, temp) = create_executor();
+        let spec = write_spec(&temp, &format!("{SPEC}twins:\n  - missing.yaml\n"));
+        let pipeline = executor.create_pipeline(spec).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Escalate);
+        let stored = executor.store().get(&pipeline.id).unwrap().clone();
+        assert!(stored
+            .last_error
+            .as_deref()
+            .is_some_and(|e| e.contains("missing.yaml")));
+    }
+
+    /// Records every request and exits with the scripted codes in turn
+    struct FakeAgent {
+        requests: std::sync::Arc<std::sync::Mutex<Vec<AgentRequest>>>,
+        exit_codes: Vec<i32>,
+    }
+
+    impl AgentDriver for FakeAgent {
+        fn develop(
+            &mut self,
+            request: &AgentRequest,
+        ) -> Result<crate::agent::AgentOutcome, crate::agent::AgentError> {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(request.clone());
+            let exit_code = self
+                .exit_codes
+                .get(requests.len() - 1)
+                .copied()
+                .unwrap_or(0);
+            Ok(crate::agent::AgentOutcome {
+                session: format!("pipeline-{}", request.iteration),
+                exit_code: Some(exit_code),
+            })
+        }
+    }
+
+    fn with_fake_agent(
+        executor: PipelineExecutor,
+        exit_codes: Vec<i32>,
+    ) -> (
+        PipelineExecutor,
+        std::sync::Arc<std::sync::Mutex<Vec<AgentRequest>>>,
+    ) {
+        let requests = std::sync::Arc::default();
+        let agent = FakeAgent {
+            requests: std::sync::Arc::clone(&requests),
+            exit_codes,
+        };
+        (executor.with_agent(Box::new(agent)), requests)
+    }
+
+    #[test]
+    fn test_retry_feeds_sanitized_feedback_to_agent() {
+        let (executor, temp) = create_executor();
+        let (mut executor, requests) = with_fake_agent(executor, vec![]);
+        write_scenario(&temp, "login_works", "ok");
+        write_scenario(&temp, "secret_rule", "broken");
+        let pipeline = executor.create_pipeline(write_spec(&temp, SPEC)).unwrap();
+        let mut stored = executor.store().get(&pipeline.id).unwrap().clone();
+        stored.max_iterations = 2;
+        executor.store().update(stored).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Escalate);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].feedback, None);
+        assert_eq!(requests[1].iteration, 2);
+        assert_eq!(requests[1].session.as_deref(), Some("pipeline-1"));
+        let feedback = requests[1].feedback.as_deref().unwrap();
+        assert_eq!(
+            feedback,
+            "Scenario 1: PASS\nScenario 2: FAIL: assertion failed"
+        );
+        assert!(!feedback.contains("secret_rule"));
+    }
+
+    #[test]
+    fn test_resumed_escalation_passes_guidance_to_agent() {
+        let (executor, temp) = create_executor();
+        let (mut executor, requests) = with_fake_agent(executor, vec![]);
+        write_scenario(&temp, "login_works", "broken");
+        write_scenario(&temp, "logout_works", "ok");
+        let config = PipelineConfig {
+            max_iterations: 1,
+            ..PipelineConfig::default()
+        };
+        let pipeline = executor
+            .create_pipeline_with_config(write_spec(&temp, SPEC), &config)
+            .unwrap();
+        assert_eq!(
+            executor.run_pipeline(&pipeline.id).unwrap(),
+            Decision::Escalate
+        );
+
+        write_scenario(&temp, "login_works", "ok");
+        crate::escalation::resume(
+            &mut executor.store(),
+            &pipeline.id,
+            "alice",
+            "Compare hashes",
+            1,
+        )
+        .unwrap();
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Accept);
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].iteration, 2);
+        assert_eq!(requests[1].guidance.as_deref(), Some("Compare hashes"));
+        assert!(requests[1].feedback.is_some());
+    }
+
+    #[test]
+    fn test_agent_session_is_persisted() {
+        let (executor, temp) = create_executor();
+        let (mut executor, _requests) = with_fake_agent(executor, vec![]);
+        write_scenario(&temp, "happy_path", "ok");
+        let pipeline = executor.create_pipeline(write_spec(&temp, SPEC)).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Accept);
+        let stored = executor.store().get(&pipeline.id).unwrap().clone();
+        assert_eq!(stored.session.as_deref(), Some("pipeline-1"));
+        assert_eq!(stored.feedback, None);
+    }
+
+    #[test]
+    fn test_agent_failure_escalates_with_session() {
+        let (executor, temp) = create_executor();
+        let (mut executor, _requests) = with_fake_agent(executor, vec![3]);
+        let pipeline = executor.create_pipeline(write_spec(&temp, SPEC)).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Escalate);
+        let stored = executor.store().get(&pipeline.id).unwrap().clone();
+        assert_eq!(stored.state, PipelineState::Escalated);
+        assert_eq!(stored.session.as_deref(), Some("pipeline-1"));
+        assert!(stored
+            .last_error
+            .as_deref()
+            .is_some_and(|e| e.contains("code 3")));
+    }
+
+    #[test]
+    fn test_agent_failure_retries_when_policy_allows() {
+        let (executor, temp) = create_executor();
+        let policies = PolicyConfig::from_yaml("escalate_on: []\n").unwrap();
+        let (executor, requests) = with_fake_agent(executor, vec![3]);
+        let mut executor = executor.with_policies(policies);
+        write_scenario(&temp, "happy_path", "ok");
+        let pipeline = executor.create_pipeline(write_spec(&temp, SPEC)).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Accept);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        let stored = executor.store().get(&pipeline.id).unwrap().clone();
+        assert_eq!(stored.iteration, 2);
+        let decisions: Vec<_> = stored
+            .decisions
+            .iter()
+            .map(|d| (d.decision, d.error_class))
+            .collect();
+        assert_eq!(
+            decisions,
+            vec![
+                (Decision::Retry, Some(ErrorClass::AgentFailed)),
+                (Decision::Accept, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spec_policy_is_recorded_on_escalation() {
+        let (executor, temp) = create_executor();
+        let policies =
+            PolicyConfig::from_yaml("specs:\n  spec-user-login:\n    max_iterations: 1\n").unwrap();
+        let mut executor = executor.with_policies(policies);
+        write_scenario(&temp, "login_works", "ok");
+        write_scenario(&temp, "logout_works", "broken");
+        let pipeline = executor.create_pipeline(write_spec(&temp, SPEC)).unwrap();
+
+        let decision = executor.run_pipeline(&pipeline.id).unwrap();
+
+        assert_eq!(decision, Decision::Escalate);
+        let stored = executor.store().get(&pipeline.id).unwrap().clone();
+        let record = stored.decisions.last().unwrap();
+        assert_eq!(record.policy.max_iterations, Some(1));
+        assert_eq!(record.pass_ratio, Some(0.5));
+        assert_eq!(
+            stored.last_error.as_deref(),
+            Some("1 of 2 scenarios passed (50%); max iterations reached (1 of 1)")
+        );
+    }
+
     #[test]
     fn test_make_decision_some_pass() {
         let (executor, _temp) = create_executor();
@@ -536,7 +1093,7 @@ mod tests {
         // With 50% pass rate, if pipeline cannot iterate (e.g., not in AgentDevelopment state),
         // the decision should be Escalate
         let pipeline = Pipeline::new("spec.yaml".to_string());
-        let decision = executor.make_decision(&results, &pipeline);
+        let decision = executor.make_decision(&results, &pipeline).decision;
         assert_eq!(decision, Decision::Escalate);
     }
 
@@ -558,7 +1115,7 @@ mod tests {
             },
         ];
         let pipeline = Pipeline::new("spec.yaml".to_string());
-        let decision = executor.make_decision(&results, &pipeline);
+        let decision = executor.make_decision(&results, &pipeline).decision;
         assert_eq!(decision, Decision::Fail);
     }
 }
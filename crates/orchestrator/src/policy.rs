@@ -0,0 +1,563 @@
+//! Retry and escalation policy
+//!
+//! A [`RetryPolicy`] decides what happens after each attempt: accept the
+//! agent's work, retry with feedback, escalate to a human or fail outright.
+//! Policies are loaded from a YAML file whose top level is the global
+//! policy and whose `specs` section overrides it per spec id:
+//!
+//! ```yaml
+//! max_iterations: 5
+//! min_pass_ratio: 0.5
+//! scenarios:
+//!   checkout_succeeds: critical
+//!   legacy_report: optional
+//! backoff:
+//!   initial_secs: 30
+//!   multiplier: 2.0
+//!   max_secs: 600
+//! escalate_on: [universe_setup, agent_failed, agent_killed]
+//! specs:
+//!   spec-login:
+//!     min_pass_ratio: 0.8
+//! ```
+//!
+//! Keys a spec sets replace the global value whole; the rest are inherited.
+//! Every decision is recorded on the pipeline as a [`DecisionRecord`]
+//! carrying the reason and the policy that produced it.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use crate::{metrics::ScenarioResult, phases::Decision, state::Pipeline};
+
+/// Errors loading a policy file
+#[derive(Debug, thiserror::Error)]
+pub enum PolicyError {
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid policy: {0}")]
+    Invalid(String),
+}
+
+/// How much a scenario counts towards a decision
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Criticality {
+    /// Reported, but never blocks acceptance or counts towards the pass ratio
+    Optional,
+    /// Must pass to accept, and counts towards the pass ratio
+    #[default]
+    Normal,
+    /// Must pass to accept; while one fails the pipeline is retried and then
+    /// escalated, never failed on its pass ratio
+    Critical,
+}
+
+/// Kinds of failure a policy can escalate on instead of its usual handling
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// The spec could not be read or scored below its threshold; fails
+    /// otherwise
+    SpecRejected,
+    /// Twins did not boot; fails otherwise
+    UniverseSetup,
+    /// The agent could not start or exited non-zero; retried otherwise
+    AgentFailed,
+    /// The agent was killed, usually by its timeout; retried otherwise
+    AgentKilled,
+    /// Validation ran no scenarios; retried otherwise
+    NoScenarios,
+}
+
+impl ErrorClass {
+    /// Snake-case name, as written in policy files
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorClass::SpecRejected => "spec_rejected",
+            ErrorClass::UniverseSetup => "universe_setup",
+            ErrorClass::AgentFailed => "agent_failed",
+            ErrorClass::AgentKilled => "agent_killed",
+            ErrorClass::NoScenarios => "no_scenarios",
+        }
+    }
+}
+
+/// Delay before each retry, growing geometrically
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub initial_secs: u64,
+    /// Factor the delay grows by with each further retry
+    pub multiplier: f64,
+    /// Longest delay, however many retries came before
+    pub max_secs: u64,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_secs: 0,
+            multiplier: 2.0,
+            max_secs: 300,
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before retry number `retry`, counting from 1
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+        let secs = self.initial_secs as f64 * self.multiplier.powi(exponent);
+        // Caps near `u64::MAX` round up past the longest `Duration`
+        Duration::try_from_secs_f64(secs.min(self.max_secs as f64))
+            .unwrap_or(Duration::from_secs(self.max_secs))
+    }
+}
+
+/// When to accept, retry, escalate or fail a pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    /// Agent iterations before escalating; the pipeline's own limit if unset
+    pub max_iterations: Option<u32>,
+    /// Share of counted scenarios (0.0-1.0) that must pass to retry rather
+    /// than fail
+    pub min_pass_ratio: f64,
+    /// Criticality by scenario name; unlisted scenarios are normal
+    pub scenarios: BTreeMap<String, Criticality>,
+    /// Delay between iterations
+    pub backoff: Backoff,
+    /// Failures escalated to a human instead of their usual handling
+    pub escalate_on: BTreeSet<ErrorClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_iterations: None,
+            min_pass_ratio: 0.5,
+            scenarios: BTreeMap::new(),
+            backoff: Backoff::default(),
+            escalate_on: [
+                ErrorClass::UniverseSetup,
+                ErrorClass::AgentFailed,
+                ErrorClass::AgentKilled,
+            ]
+            .into_iter()
+            .collect(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn validate(&self, name: &str) -> Result<(), PolicyError> {
+        if !(0.0..=1.0).contains(&self.min_pass_ratio) {
+            return Err(PolicyError::Invalid(format!(
+                "{name}: min_pass_ratio must be between 0 and 1, got {}",
+                self.min_pass_ratio
+            )));
+        }
+        if self.max_iterations == Some(0) {
+            return Err(PolicyError::Invalid(format!(
+                "{name}: max_iterations must be at least 1"
+            )));
+        }
+        if !self.backoff.multiplier.is_finite() || self.backoff.multiplier < 1.0 {
+            return Err(PolicyError::Invalid(format!(
+                "{name}: backoff multiplier must be at least 1, got {}",
+                self.backoff.multiplier
+            )));
+        }
+        Ok(())
+    }
+
+    /// Criticality of the scenario named `name`
+    #[must_use]
+    pub fn criticality(&self, name: &str) -> Criticality {
+        self.scenarios.get(name).copied().unwrap_or_default()
+    }
+
+    /// Decide on `pipeline` from the scenarios its last iteration ran
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn evaluate(&self, results: &[ScenarioResult], pipeline: &Pipeline) -> DecisionRecord {
+        let counted: Vec<&ScenarioResult> = results
+            .iter()
+            .filter(|r| self.criticality(&r.name) != Criticality::Optional)
+            .collect();
+        if counted.is_empty() {
+            return self.on_error(ErrorClass::NoScenarios, "No scenarios ran", pipeline);
+        }
+
+        let passed = counted.iter().filter(|r| r.passed).count();
+        let ratio = passed as f64 / counted.len() as f64;
+        let record = |decision, reason| {
+            DecisionRecord::new(decision, reason, pipeline, self).with_pass_ratio(ratio)
+        };
+
+        if passed == counted.len() {
+            return record(
+                Decision::Accept,
+                format!("All {} counted scenarios passed", counted.len()),
+            );
+        }
+
+        let critical: Vec<&str> = counted
+            .iter()
+            .filter(|r| !r.passed && self.criticality(&r.name) == Criticality::Critical)
+            .map(|r| r.name.as_str())
+            .collect();
+        let summary = format!(
+            "{passed} of {} scenarios passed ({:.0}%)",
+            counted.len(),
+            ratio * 100.0
+        );
+
+        if !critical.is_empty() {
+            let reason = format!("Critical scenarios failed: {}", critical.join(", "));
+            return self.retry_or_escalate(reason, pipeline, |r| r.with_pass_ratio(ratio));
+        }
+        if ratio < self.min_pass_ratio {
+            return record(
+                Decision::Fail,
+                format!(
+                    "{summary}, below the {:.0}% minimum",
+                    self.min_pass_ratio * 100.0
+                ),
+            );
+        }
+        self.retry_or_escalate(summary, pipeline, |r| r.with_pass_ratio(ratio))
+    }
+
+    /// Decide on `pipeline` after a failure of kind `class`
+    #[must_use]
+    pub fn on_error(
+        &self,
+        class: ErrorClass,
+        message: &str,
+        pipeline: &Pipeline,
+    ) -> DecisionRecord {
+        let with_class = |record: DecisionRecord| record.with_error_class(class);
+        if self.escalate_on.contains(&class) {
+            return with_class(DecisionRecord::new(
+                Decision::Escalate,
+                message.to_string(),
+                pipeline,
+                self,
+            ));
+        }
+        match class {
+            ErrorClass::SpecRejected | ErrorClass::UniverseSetup => with_class(
+                DecisionRecord::new(Decision::Fail, message.to_string(), pipeline, self),
+            ),
+            ErrorClass::AgentFailed | ErrorClass::AgentKilled | ErrorClass::NoScenarios => {
+                self.retry_or_escalate(message.to_string(), pipeline, with_class)
+            }
+        }
+    }
+
+    fn retry_or_escalate(
+        &self,
+        reason: String,
+        pipeline: &Pipeline,
+        finish: impl Fn(DecisionRecord) -> DecisionRecord,
+    ) -> DecisionRecord {
+        let record = if pipeline.can_iterate() {
+            DecisionRecord::new(Decision::Retry, reason, pipeline, self)
+        } else {
+            let reason = format!(
+                "{reason}; max iterations reached ({} of {})",
+                pipeline.iteration, pipeline.max_iterations
+            );
+            DecisionRecord::new(Decision::Escalate, reason, pipeline, self)
+        };
+        finish(record)
+    }
+}
+
+/// The global retry policy and per-spec overrides
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PolicyConfig {
+    /// Policy for specs without an override
+    pub default: RetryPolicy,
+    /// Policies by spec id (`identity.id`)
+    pub specs: BTreeMap<String, RetryPolicy>,
+}
+
+impl PolicyConfig {
+    /// Load a policy file
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or is invalid.
+    pub fn from_file(path: &Path) -> Result<Self, PolicyError> {
+        let yaml = fs::read_to_string(path).map_err(|source| PolicyError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Self::from_yaml(&yaml)
+    }
+
+    /// Parse a policy file's contents
+    ///
+    /// # Errors
+    /// Returns an error if the YAML is invalid or a value is out of range.
+    pub fn from_yaml(yaml: &str) -> Result<Self, PolicyError> {
+        let mut global = match serde_yaml::from_str(yaml) {
+            Ok(Value::Mapping(mapping)) => mapping,
+            Ok(Value::Null) => Mapping::new(),
+            Ok(_) => return Err(PolicyError::Invalid("expected a mapping".to_string())),
+            Err(e) => return Err(PolicyError::Invalid(e.to_string())),
+        };
+        let overrides: BTreeMap<String, Mapping> = match global.remove("specs") {
+            Some(specs) => serde_yaml::from_value(specs)
+                .map_err(|e| PolicyError::Invalid(format!("specs: {e}")))?,
+            None => BTreeMap::new(),
+        };
+
+        let default = parse_policy("policy", global.clone())?;
+        let specs = overrides
+            .into_iter()
+            .map(|(spec, overrides)| {
+                let mut merged = global.clone();
+                merged.extend(overrides);
+                let policy = parse_policy(&format!("specs.{spec}"), merged)?;
+                Ok((spec, policy))
+            })
+            .collect::<Result<_, PolicyError>>()?;
+
+        Ok(Self { default, specs })
+    }
+
+    /// Policy for the spec with id `spec_id`
+    #[must_use]
+    pub fn policy_for(&self, spec_id: Option<&str>) -> &RetryPolicy {
+        spec_id
+            .and_then(|id| self.specs.get(id))
+            .unwrap_or(&self.default)
+    }
+}
+
+fn parse_policy(name: &str, mapping: Mapping) -> Result<RetryPolicy, PolicyError> {
+    let policy: RetryPolicy = serde_yaml::from_value(Value::Mapping(mapping))
+        .map_err(|e| PolicyError::Invalid(format!("{name}: {e}")))?;
+    policy.validate(name)?;
+    Ok(policy)
+}
+
+/// Id (`identity.id`) of the spec at `path`, if it can be read
+#[must_use]
+pub fn spec_id(path: &Path) -> Option<String> {
+    let spec: Value = serde_yaml::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    spec.get("identity")?
+        .get("id")?
+        .as_str()
+        .map(str::to_string)
+}
+
+/// A decision made on a pipeline, with why and under which policy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionRecord {
+    pub decision: Decision,
+    /// Human-readable reason, e.g. which scenarios failed
+    pub reason: String,
+    /// Failure that prompted the decision, if any
+    pub error_class: Option<ErrorClass>,
+    /// Agent iterations completed at the time
+    pub iteration: u32,
+    /// Share of counted scenarios that passed, after validation
+    pub pass_ratio: Option<f64>,
+    /// Policy the decision was made under
+    pub policy: RetryPolicy,
+    pub at: DateTime<Utc>,
+}
+
+impl DecisionRecord {
+    fn new(decision: Decision, reason: String, pipeline: &Pipeline, policy: &RetryPolicy) -> Self {
+        Self {
+            decision,
+            reason,
+            error_class: None,
+            iteration: pipeline.iteration,
+            pass_ratio: None,
+            policy: policy.clone(),
+            at: Utc::now(),
+        }
+    }
+
+    const fn with_pass_ratio(mut self, ratio: f64) -> Self {
+        self.pass_ratio = Some(ratio);
+        self
+    }
+
+    const fn with_error_class(mut self, class: ErrorClass) -> Self {
+        self.error_class = Some(class);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::PipelineState;
+
+    fn result(name: &str, passed: bool) -> ScenarioResult {
+        ScenarioResult {
+            name: name.to_string(),
+            passed,
+            duration_secs: 1.0,
+            error: None,
+        }
+    }
+
+    /// A pipeline mid-validation with `iteration` of `max` iterations done
+    fn validating(iteration: u32, max: u32) -> Pipeline {
+        let mut pipeline = Pipeline::new("spec.yaml".to_string());
+        pipeline.state = PipelineState::Validation;
+        pipeline.iteration = iteration;
+        pipeline.max_iterations = max;
+        pipeline
+    }
+
+    #[test]
+    fn test_pass_ratio_decides_retry_or_fail() {
+        let policy = RetryPolicy {
+            min_pass_ratio: 0.6,
+            ..RetryPolicy::default()
+        };
+        let half = [result("a", true), result("b", false)];
+        let most = [result("a", true), result("b", true), result("c", false)];
+
+        let failed = policy.evaluate(&half, &validating(1, 3));
+        let retried = policy.evaluate(&most, &validating(1, 3));
+        let escalated = policy.evaluate(&most, &validating(3, 3));
+
+        assert_eq!(failed.decision, Decision::Fail);
+        assert_eq!(
+            failed.reason,
+            "1 of 2 scenarios passed (50%), below the 60% minimum"
+        );
+        assert_eq!(failed.pass_ratio, Some(0.5));
+        assert_eq!(retried.decision, Decision::Retry);
+        assert_eq!(escalated.decision, Decision::Escalate);
+        assert!(escalated
+            .reason
+            .ends_with("max iterations reached (3 of 3)"));
+    }
+
+    #[test]
+    fn test_criticality() {
+        let policy =
+            PolicyConfig::from_yaml("scenarios:\n  checkout: critical\n  report: optional\n")
+                .unwrap()
+                .default;
+
+        let accepted = policy.evaluate(
+            &[result("login", true), result("report", false)],
+            &validating(1, 3),
+        );
+        let critical = policy.evaluate(
+            &[
+                result("login", false),
+                result("signup", false),
+                result("checkout", false),
+            ],
+            &validating(1, 3),
+        );
+
+        assert_eq!(accepted.decision, Decision::Accept);
+        assert_eq!(critical.decision, Decision::Retry);
+        assert_eq!(critical.reason, "Critical scenarios failed: checkout");
+    }
+
+    #[test]
+    fn test_error_classes_escalate_when_listed() {
+        let policy = PolicyConfig::from_yaml("escalate_on: [spec_rejected]\n")
+            .unwrap()
+            .default;
+        let pipeline = validating(1, 3);
+
+        let spec = policy.on_error(ErrorClass::SpecRejected, "bad spec", &pipeline);
+        let agent = policy.on_error(ErrorClass::AgentKilled, "timed out", &pipeline);
+        let setup = policy.on_error(ErrorClass::UniverseSetup, "no twin", &pipeline);
+
+        assert_eq!(spec.decision, Decision::Escalate);
+        assert_eq!(spec.error_class, Some(ErrorClass::SpecRejected));
+        assert_eq!(agent.decision, Decision::Retry);
+        assert_eq!(setup.decision, Decision::Fail);
+    }
+
+    #[test]
+    fn test_spec_overrides_inherit_global_values() {
+        let config = PolicyConfig::from_yaml(
+            "max_iterations: 4\nmin_pass_ratio: 0.7\nspecs:\n  spec-login:\n    min_pass_ratio: 0.9\n",
+        )
+        .unwrap();
+
+        let login = config.policy_for(Some("spec-login"));
+        let other = config.policy_for(Some("spec-other"));
+
+        assert_eq!(login.max_iterations, Some(4));
+        assert!((login.min_pass_ratio - 0.9).abs() < f64::EPSILON);
+        assert!((other.min_pass_ratio - 0.7).abs() < f64::EPSILON);
+        assert_eq!(config.policy_for(None), &config.default);
+    }
+
+    #[test]
+    fn test_rejects_invalid_policies() {
+        for yaml in [
+            "min_pass_ratio: 1.5\n",
+            "max_iterations: 0\n",
+            "backoff:\n  multiplier: 0.5\n",
+            "retries: 3\n",
+            "specs:\n  spec-x:\n    escalate_on: [tuesdays]\n",
+        ] {
+            assert!(
+                matches!(PolicyConfig::from_yaml(yaml), Err(PolicyError::Invalid(_))),
+                "{yaml}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_to_its_cap() {
+        let backoff = Backoff {
+            initial_secs: 10,
+            multiplier: 3.0,
+            max_secs: 60,
+        };
+
+        assert_eq!(backoff.delay(1), Duration::from_secs(10));
+        assert_eq!(backoff.delay(2), Duration::from_secs(30));
+        assert_eq!(backoff.delay(5), Duration::from_secs(60));
+        assert_eq!(Backoff::default().delay(3), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_backoff_cap_at_u64_max_does_not_overflow() {
+        let backoff = Backoff {
+            initial_secs: u64::MAX,
+            multiplier: 2.0,
+            max_secs: u64::MAX,
+        };
+
+        assert_eq!(backoff.delay(1), Duration::from_secs(u64::MAX));
+        assert_eq!(backoff.delay(40), Duration::from_secs(u64::MAX));
+    }
+}
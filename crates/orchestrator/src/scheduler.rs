@@ -0,0 +1,610 @@
+//! Concurrent pipeline scheduling
+//!
+//! [`PipelineScheduler`] runs pipelines on a fixed pool of worker threads, so
+//! at most `concurrency` are in flight at once. Each worker has its own
+//! [`PipelineExecutor`], and with it its own twin universes and agent, over a
+//! state store and metrics collector shared by the whole pool.
+//!
+//! On start the scheduler queues every pipeline a previous process left
+//! unfinished, oldest first, ahead of anything submitted afterwards, unless
+//! resuming is turned off with [`SchedulerConfig::with_resume`].
+
+use std::{
+    collections::{HashSet, VecDeque},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{Context, Result};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    agent::AgentDriver,
+    linter::SpecLinter,
+    metrics::{Metrics, SharedMetrics},
+    persistence::{SharedStore, StateStore},
+    phases::{Decision, PipelineExecutor},
+    policy::PolicyConfig,
+    state::{Pipeline, PipelineConfig, PipelineId},
+};
+
+/// Pipelines in flight at once unless configured otherwise
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Builds the agent each worker develops with
+pub type AgentFactory = Arc<dyn Fn() -> Box<dyn AgentDriver> + Send + Sync>;
+
+/// Lock `mutex`, even if a thread panicked while holding it
+///
+/// The store and metrics are only ever replaced whole, so a panicking
+/// pipeline can't leave them half-written for the others.
+pub(crate) fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// How a scheduler runs pipelines
+#[derive(Clone)]
+pub struct SchedulerConfig {
+    /// Directory pipeline state is kept in
+    pub state_dir: PathBuf,
+    /// Directory of scenario YAML files
+    pub scenarios_path: PathBuf,
+    /// Specs directory with linter rules and schema (bundled if unset)
+    pub linter_path: Option<PathBuf>,
+    /// Maximum pipelines running at once
+    pub concurrency: usize,
+    /// Builds each worker's agent; development is skipped without one
+    pub agents: Option<AgentFactory>,
+    /// Queue unfinished pipelines on start
+    pub resume: bool,
+    /// When to retry, escalate or fail
+    pub policies: PolicyConfig,
+}
+
+impl SchedulerConfig {
+    /// Config running [`DEFAULT_CONCURRENCY`] pipelines without an agent
+    #[must_use]
+    pub fn new(state_dir: PathBuf, scenarios_path: PathBuf) -> Self {
+        Self {
+            state_dir,
+            scenarios_path,
+            linter_path: None,
+            concurrency: DEFAULT_CONCURRENCY,
+            agents: None,
+            resume: true,
+            policies: PolicyConfig::default(),
+        }
+    }
+
+    /// Lint specs with the rules and schema under `path`
+    #[must_use]
+    pub fn with_linter_path(mut self, path: PathBuf) -> Self {
+        self.linter_path = Some(path);
+        self
+    }
+
+    /// Run at most `concurrency` pipelines at once (at least one)
+    #[must_use]
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Whether to queue unfinished pipelines on start
+    #[must_use]
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Decide on pipelines under `policies`
+    #[must_use]
+    pub fn with_policies(mut self, policies: PolicyConfig) -> Self {
+        self.policies = policies;
+        self
+    }
+
+    /// Develop with an agent from `factory`, one per worker
+    #[must_use]
+    pub fn with_agents<F>(mut self, factory: F) -> Self
+    where
+        F: Fn() -> Box<dyn AgentDriver> + Send + Sync + 'static,
+    {
+        self.agents = Some(Arc::new(factory));
+        self
+    }
+}
+
+/// A pipeline the scheduler ran to a decision, or the error that stopped it
+#[derive(Debug)]
+pub struct ScheduledRun {
+    pub pipeline_id: PipelineId,
+    pub result: Result<Decision>,
+}
+
+/// Pipelines waiting for and held by workers
+#[derive(Default)]
+struct Queue {
+    waiting: VecDeque<PipelineId>,
+    running: HashSet<PipelineId>,
+    finished: Vec<ScheduledRun>,
+    closed: bool,
+}
+
+impl Queue {
+    fn contains(&self, id: &PipelineId) -> bool {
+        self.running.contains(id) || self.waiting.contains(id)
+    }
+
+    fn is_idle(&self) -> bool {
+        self.waiting.is_empty() && self.running.is_empty()
+    }
+}
+
+/// State every worker shares
+struct Shared {
+    store: SharedStore,
+    metrics: SharedMetrics,
+    scenarios_path: PathBuf,
+    linter: SpecLinter,
+    agents: Option<AgentFactory>,
+    policies: PolicyConfig,
+    queue: Mutex<Queue>,
+    /// Signalled whenever the queue changes
+    changed: Condvar,
+}
+
+impl Shared {
+    fn executor(&self) -> PipelineExecutor {
+        let executor = PipelineExecutor::with_shared(
+            Arc::clone(&self.store),
+            Arc::clone(&self.metrics),
+            self.scenarios_path.clone(),
+            self.linter.clone(),
+        )
+        .with_policies(self.policies.clone());
+        match &self.agents {
+            Some(agents) => executor.with_agent(agents()),
+            None => executor,
+        }
+    }
+
+    fn wait<'a>(&self, queue: MutexGuard<'a, Queue>) -> MutexGuard<'a, Queue> {
+        self.changed
+            .wait(queue)
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Take the next waiting pipeline, blocking until there is one or the
+    /// scheduler stops
+    fn next(&self) -> Option<PipelineId> {
+        let mut queue = lock(&self.queue);
+        loop {
+            if let Some(id) = queue.waiting.pop_front() {
+                queue.running.insert(id.clone());
+                return Some(id);
+            }
+            if queue.closed {
+                return None;
+            }
+            queue = self.wait(queue);
+        }
+    }
+
+    fn finish(&self, pipeline_id: PipelineId, result: Result<Decision>) {
+        let mut queue = lock(&self.queue);
+        queue.running.remove(&pipeline_id);
+        queue.finished.push(ScheduledRun {
+            pipeline_id,
+            result,
+        });
+        drop(queue);
+        self.changed.notify_all();
+    }
+}
+
+/// Body of a worker thread: run pipelines until the scheduler stops
+fn work(shared: &Shared) {
+    let mut executor = shared.executor();
+    while let Some(id) = shared.next() {
+        debug!("Worker picked up pipeline {}", id.0);
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| executor.run_pipeline(&id)));
+        let result = outcome.unwrap_or_else(|_| {
+            error!("Pipeline {} panicked", id.0);
+            // Its twins may still be up; dropping the executor stops them
+            executor = shared.executor();
+            Err(anyhow::anyhow!("Pipeline {} panicked", id.0))
+        });
+        match &result {
+            Ok(decision) => info!("Pipeline {} finished: {decision:?}", id.0),
+            Err(e) => error!("Pipeline {} stopped: {e:#}", id.0),
+        }
+        shared.finish(id, result);
+    }
+}
+
+/// Runs pipelines concurrently on a bounded pool of workers
+///
+/// Dropping the scheduler waits for running pipelines but leaves queued ones
+/// pending in the store, to be resumed by the next scheduler started on it.
+pub struct PipelineScheduler {
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl PipelineScheduler {
+    /// Open the state store and start the workers, queueing every
+    /// unfinished pipeline if the config resumes them
+    ///
+    /// # Errors
+    /// Returns an error if the state store or spec linter cannot be
+    /// initialized, or a worker thread cannot be started.
+    pub fn start(config: SchedulerConfig) -> Result<Self> {
+        let store =
+            StateStore::new(config.state_dir).context("Failed to initialize state store")?;
+        let linter = config
+            .linter_path
+            .as_deref()
+            .map_or_else(SpecLinter::bundled, SpecLinter::from_dir)
+            .context("Failed to load spec linter")?;
+
+        let mut pending: Vec<&Pipeline> = if config.resume {
+            store.get_pending_recovery()
+        } else {
+            Vec::new()
+        };
+        pending.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.id.0.cmp(&b.id.0))
+        });
+        let waiting: VecDeque<PipelineId> = pending.into_iter().map(|p| p.id.clone()).collect();
+        if !waiting.is_empty() {
+            info!("Resuming {} unfinished pipeline(s)", waiting.len());
+        }
+
+        let shared = Arc::new(Shared {
+            store: Arc::new(Mutex::new(store)),
+            metrics: Arc::new(Mutex::new(Metrics::new())),
+            scenarios_path: config.scenarios_path,
+            linter,
+            agents: config.agents,
+            policies: config.policies,
+            queue: Mutex::new(Queue {
+                waiting,
+                ..Queue::default()
+            }),
+            changed: Condvar::new(),
+        });
+
+        let mut scheduler = Self {
+            shared,
+            workers: Vec::new(),
+        };
+        for n in 0..config.concurrency.max(1) {
+            let shared = Arc::clone(&scheduler.shared);
+            let worker = thread::Builder::new()
+                .name(format!("pipeline-worker-{n}"))
+                .spawn(move || work(&shared))
+                .context("Failed to start pipeline worker")?;
+            scheduler.workers.push(worker);
+        }
+        Ok(scheduler)
+    }
+
+    /// Number of pipelines that can run at once
+    #[must_use]
+    pub fn concurrency(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Get the state store
+    ///
+    /// Workers wait on the store while the guard is held, so drop it
+    /// promptly.
+    pub fn store(&self) -> MutexGuard<'_, StateStore> {
+        lock(&self.shared.store)
+    }
+
+    /// Get metrics
+    pub fn metrics(&self) -> MutexGuard<'_, Metrics> {
+        lock(&self.shared.metrics)
+    }
+
+    /// Create a pipeline and queue it
+    ///
+    /// # Errors
+    /// Returns an error if the pipeline cannot be saved.
+    pub fn create_pipeline(&self, spec_path: String, config: &PipelineConfig) -> Result<Pipeline> {
+        let pipeline = self
+            .store()
+            .create(Pipeline::with_config(spec_path, config))?;
+        info!("Created pipeline: {}", pipeline.id);
+        self.submit(&pipeline.id)?;
+        Ok(pipeline)
+    }
+
+    /// Queue a stored pipeline to run
+    ///
+    /// Returns `false` if it is already queued or running.
+    ///
+    /// # Errors
+    /// Returns an error if the pipeline does not exist.
+    pub fn submit(&self, id: &PipelineId) -> Result<bool> {
+        self.store().get(id)?;
+
+        let mut queue = lock(&self.shared.queue);
+        if queue.contains(id) {
+            debug!("Pipeline {} is already scheduled", id.0);
+            return Ok(false);
+        }
+        queue.waiting.push_back(id.clone());
+        drop(queue);
+        self.shared.changed.notify_all();
+        Ok(true)
+    }
+
+    /// Block until nothing is queued or running, returning the runs that
+    /// finished since the last call
+    pub fn wait_idle(&self) -> Vec<ScheduledRun> {
+        let mut queue = lock(&self.shared.queue);
+        while !queue.is_idle() {
+            queue = self.shared.wait(queue);
+        }
+        std::mem::take(&mut queue.finished)
+    }
+
+    /// Run everything queued, then stop the workers
+    ///
+    /// Returns the runs that finished since the last [`Self::wait_idle`].
+    pub fn shutdown(mut self) -> Vec<ScheduledRun> {
+        let runs = self.wait_idle();
+        self.stop();
+        runs
+    }
+
+    /// Stop the workers once their current pipelines finish
+    fn stop(&mut self) {
+        let mut queue = lock(&self.shared.queue);
+        queue.closed = true;
+        if !queue.waiting.is_empty() {
+            debug!(
+                "Leaving {} queued pipeline(s) for recovery",
+                queue.waiting.len()
+            );
+            queue.waiting.clear();
+        }
+        drop(queue);
+        self.shared.changed.notify_all();
+
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                warn!("Pipeline worker thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for PipelineScheduler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, Sender},
+    };
+
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::{
+        agent::{AgentError, AgentOutcome, AgentRequest},
+        phases::tests::{write_scenario, write_spec, SPEC},
+        state::PipelineState,
+    };
+
+    /// Reports when it starts, then succeeds once its gate is released,
+    /// tracking the most agents running at once
+    struct GatedAgent {
+        started: Sender<()>,
+        gate: Arc<Mutex<Receiver<()>>>,
+        active: Arc<AtomicUsize>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    impl AgentDriver for GatedAgent {
+        fn develop(&mut self, request: &AgentRequest) -> Result<AgentOutcome, AgentError> {
+            let now = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            let _ = self.started.send(());
+            // Dropping the gate's sender releases every agent
+            let _ = lock(&self.gate).recv();
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(AgentOutcome {
+                session: format!("pipeline-{}", request.iteration),
+                exit_code: Some(0),
+            })
+        }
+    }
+
+    /// Handles on the agents of a [`gated_config`]
+    struct Gate {
+        /// Receives once per agent run as it starts
+        started: Receiver<()>,
+        /// Drop to let every agent finish
+        release: Sender<()>,
+        peak: Arc<AtomicUsize>,
+    }
+
+    fn config(temp: &TempDir) -> SchedulerConfig {
+        SchedulerConfig::new(temp.path().join("state"), temp.path().join("scenarios"))
+    }
+
+    /// Config whose agents block until the gate is released
+    fn gated_config(temp: &TempDir) -> (SchedulerConfig, Gate) {
+        let (started, started_rx) = mpsc::channel();
+        let (release, gate) = mpsc::channel();
+        let gate = Arc::new(Mutex::new(gate));
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let tracked = Arc::clone(&peak);
+        let config = config(temp).with_agents(move || {
+            Box::new(GatedAgent {
+                started: started.clone(),
+                gate: Arc::clone(&gate),
+                active: Arc::clone(&active),
+                peak: Arc::clone(&tracked),
+            })
+        });
+        let gate = Gate {
+            started: started_rx,
+            release,
+            peak,
+        };
+        (config, gate)
+    }
+
+    /// Release `release` once `scheduler` has stopped taking pipelines
+    fn release_on_stop(scheduler: &PipelineScheduler, release: Sender<()>) {
+        let shared = Arc::clone(&scheduler.shared);
+        thread::spawn(move || {
+            let mut queue = lock(&shared.queue);
+            while !queue.closed {
+                queue = shared.wait(queue);
+            }
+            drop(release);
+        });
+    }
+
+    #[test]
+    fn test_runs_pipelines_up_to_concurrency_limit() {
+        let temp = TempDir::new().unwrap();
+        write_scenario(&temp, "happy_path", "ok");
+        let spec = write_spec(&temp, SPEC);
+        let (config, gate) = gated_config(&temp);
+        let scheduler = PipelineScheduler::start(config.with_concurrency(2)).unwrap();
+
+        let ids: Vec<PipelineId> = (0..5)
+            .map(|_| {
+                scheduler
+                    .create_pipeline(spec.clone(), &PipelineConfig::default())
+                    .unwrap()
+                    .id
+            })
+            .collect();
+        // Both workers are held in their agents at once
+        gate.started.recv().unwrap();
+        gate.started.recv().unwrap();
+        drop(gate.release);
+        let runs = scheduler.shutdown();
+
+        assert_eq!(runs.len(), 5);
+        assert!(runs
+            .iter()
+            .all(|run| matches!(run.result, Ok(Decision::Accept))));
+        assert!(ids
+            .iter()
+            .all(|id| runs.iter().any(|run| &run.pipeline_id == id)));
+        assert_eq!(gate.peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_resumes_unfinished_pipelines_on_start() {
+        let temp = TempDir::new().unwrap();
+        write_scenario(&temp, "happy_path", "ok");
+        let spec = write_spec(&temp, SPEC);
+        let config = config(&temp).with_concurrency(1);
+        let (first, second) = {
+            let mut store = StateStore::new(config.state_dir.clone()).unwrap();
+            let first = store.create(Pipeline::new(spec.clone())).unwrap();
+            let second = store.create(Pipeline::new(spec.clone())).unwrap();
+            let mut done = store.create(Pipeline::new(spec)).unwrap();
+            done.state = PipelineState::Failed;
+            store.update(done).unwrap();
+            (first.id, second.id)
+        };
+
+        let scheduler = PipelineScheduler::start(config).unwrap();
+        let runs = scheduler.wait_idle();
+
+        let ids: Vec<&PipelineId> = runs.iter().map(|run| &run.pipeline_id).collect();
+        assert_eq!(ids, vec![&first, &second]);
+        assert_eq!(
+            scheduler.store().get(&first).unwrap().state,
+            PipelineState::Accepted
+        );
+        assert!(scheduler
+            .metrics()
+            .get_pipeline_metrics(&second.0)
+            .is_some());
+    }
+
+    #[test]
+    fn test_start_without_resume_leaves_pipelines_pending() {
+        let temp = TempDir::new().unwrap();
+        let config = config(&temp).with_resume(false);
+        let pending = {
+            let mut store = StateStore::new(config.state_dir.clone()).unwrap();
+            store
+                .create(Pipeline::new(write_spec(&temp, SPEC)))
+                .unwrap()
+        };
+
+        let scheduler = PipelineScheduler::start(config).unwrap();
+
+        assert_eq!(scheduler.wait_idle().len(), 0);
+        assert_eq!(
+            scheduler.store().get(&pending.id).unwrap().state,
+            PipelineState::Pending
+        );
+    }
+
+    #[test]
+    fn test_submit_skips_scheduled_and_unknown_pipelines() {
+        let temp = TempDir::new().unwrap();
+        let spec = write_spec(&temp, SPEC);
+        let (config, gate) = gated_config(&temp);
+        let scheduler = PipelineScheduler::start(config.with_concurrency(1)).unwrap();
+
+        let pipeline = scheduler
+            .create_pipeline(spec, &PipelineConfig::default())
+            .unwrap();
+        gate.started.recv().unwrap();
+
+        assert!(!scheduler.submit(&pipeline.id).unwrap());
+        assert!(scheduler
+            .submit(&PipelineId("missing".to_string()))
+            .is_err());
+        drop(gate.release);
+    }
+
+    #[test]
+    fn test_drop_leaves_queued_pipelines_pending() {
+        let temp = TempDir::new().unwrap();
+        write_scenario(&temp, "happy_path", "ok");
+        let spec = write_spec(&temp, SPEC);
+        let (config, gate) = gated_config(&temp);
+        let state_dir = config.state_dir.clone();
+        {
+            let scheduler = PipelineScheduler::start(config.with_concurrency(1)).unwrap();
+            for _ in 0..3 {
+                scheduler
+                    .create_pipeline(spec.clone(), &PipelineConfig::default())
+                    .unwrap();
+            }
+            // The first is running and the rest are queued when it drops
+            gate.started.recv().unwrap();
+            release_on_stop(&scheduler, gate.release);
+        }
+
+        let store = StateStore::new(state_dir).unwrap();
+        assert_eq!(store.list_by_state(PipelineState::Accepted).len(), 1);
+        assert_eq!(store.get_pending_recovery().len(), 2);
+    }
+}
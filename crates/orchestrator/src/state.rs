@@ -3,6 +3,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::{escalation::HumanDecision, policy::DecisionRecord};
+
 /// Unique identifier for a pipeline
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PipelineId(pub String);
@@ -59,9 +61,31 @@ impl PipelineState {
     }
 
     /// Returns true if this state allows iteration
+    ///
+    /// Validation counts: a failed validation is retried by sending the
+    /// pipeline back to agent development from there.
     #[must_use]
     pub fn allows_iteration(&self) -> bool {
-        matches!(self, PipelineState::AgentDevelopment)
+        matches!(
+            self,
+            PipelineState::AgentDevelopment | PipelineState::Validation
+        )
+    }
+
+    /// Snake-case name the state is stored and reported under,
+    /// e.g. `agent_development`
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PipelineState::Pending => "pending",
+            PipelineState::SpecReview => "spec_review",
+            PipelineState::UniverseSetup => "universe_setup",
+            PipelineState::AgentDevelopment => "agent_development",
+            PipelineState::Validation => "validation",
+            PipelineState::Accepted => "accepted",
+            PipelineState::Escalated => "escalated",
+            PipelineState::Failed => "failed",
+        }
     }
 
     /// Get a human-readable description of the state
@@ -95,7 +119,8 @@ pub struct PipelineConfig {
     pub quality_threshold: u32,
     /// Path to scenarios directory
     pub scenarios_path: String,
-    /// Path to linter binary
+    /// Specs directory with `linter/rules.yaml` and `schema/spec.schema.json`;
+    /// the bundled rules and schema are used when unset
     pub linter_path: Option<String>,
 }
 
@@ -131,6 +156,37 @@ pub struct Pipeline {
     pub updated_at: DateTime<Utc>,
     /// Last error message if any
     pub last_error: Option<String>,
+    /// Twins booted for this pipeline's universe
+    #[serde(default)]
+    pub twins: Vec<TwinInstance>,
+    /// Isolate session the agent develops in
+    #[serde(default)]
+    pub session: Option<String>,
+    /// Sanitized feedback from the last failed validation
+    #[serde(default)]
+    pub feedback: Option<String>,
+    /// Every decision made on the pipeline, oldest first
+    #[serde(default)]
+    pub decisions: Vec<DecisionRecord>,
+    /// Guidance from the human who last resumed the pipeline
+    #[serde(default)]
+    pub guidance: Option<String>,
+    /// Human decisions on the pipeline's escalations, oldest first
+    #[serde(default)]
+    pub reviews: Vec<HumanDecision>,
+}
+
+/// A twin server running for a pipeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TwinInstance {
+    /// Name from the twin definition
+    pub name: String,
+    /// Where the twin serves, e.g. `http://127.0.0.1:41234`
+    pub base_url: String,
+    /// Port the twin definition declares; scenarios may address the twin as
+    /// `http://localhost:<port>`
+    #[serde(default)]
+    pub port: Option<u16>,
 }
 
 impl Pipeline {
@@ -148,6 +204,12 @@ impl Pipeline {
             created_at: now,
             updated_at: now,
             last_error: None,
+            twins: Vec::new(),
+            session: None,
+            feedback: None,
+            decisions: Vec::new(),
+            guidance: None,
+            reviews: Vec::new(),
         }
     }
 
@@ -165,6 +227,12 @@ impl Pipeline {
             created_at: now,
             updated_at: now,
             last_error: None,
+            twins: Vec::new(),
+            session: None,
+            feedback: None,
+            decisions: Vec::new(),
+            guidance: None,
+            reviews: Vec::new(),
         }
     }
 
@@ -187,6 +255,10 @@ impl Pipeline {
             (PipelineState::AgentDevelopment, PipelineState::Validation) => {}
             (PipelineState::AgentDevelopment, PipelineState::AgentDevelopment) => {}
             (PipelineState::AgentDevelopment, PipelineState::Escalated) => {}
+            // From Escalated: a human resumes, accepts or fails it
+            (PipelineState::Escalated, PipelineState::AgentDevelopment) => {}
+            (PipelineState::Escalated, PipelineState::Accepted) => {}
+            (PipelineState::Escalated, PipelineState::Failed) => {}
             // From Validation: can go to Accepted, AgentDevelopment (retry), or Failed
             (PipelineState::Validation, PipelineState::Accepted) => {}
             (PipelineState::Validation, PipelineState::AgentDevelopment) => {}
@@ -228,6 +300,8 @@ impl Pipeline {
     }
 
     /// Check if can proceed to next iteration
+    ///
+    /// True in agent development and validation while iterations remain.
     #[must_use]
     pub fn can_iterate(&self) -> bool {
         self.iteration < self.max_iterations && self.state.allows_iteration()
@@ -338,6 +412,21 @@ mod tests {
         assert!(pipeline.increment_iteration().is_err());
     }
 
+    #[test]
+    fn test_failed_validation_can_iterate() {
+        let mut pipeline = Pipeline::new("specs/test.yaml".to_string());
+        pipeline.transition_to(PipelineState::SpecReview).ok();
+        pipeline.transition_to(PipelineState::UniverseSetup).ok();
+        assert!(!pipeline.can_iterate());
+
+        pipeline.transition_to(PipelineState::AgentDevelopment).ok();
+        pipeline.transition_to(PipelineState::Validation).ok();
+        assert!(pipeline.can_iterate());
+
+        pipeline.iteration = pipeline.max_iterations;
+        assert!(!pipeline.can_iterate());
+    }
+
     #[test]
     fn test_terminal_state_no_transition() {
         let mut pipeline = Pipeline::new("specs/test.yaml".to_string());
@@ -351,4 +440,40 @@ mod tests {
         let result = pipeline.transition_to(PipelineState::Failed);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_escalated_pipeline_can_be_settled_by_a_human() {
+        for settled in [
+            PipelineState::AgentDevelopment,
+            PipelineState::Accepted,
+            PipelineState::Failed,
+        ] {
+            let mut pipeline = Pipeline::new("specs/test.yaml".to_string());
+            pipeline.transition_to(PipelineState::Escalated).unwrap();
+            assert!(pipeline.transition_to(settled).is_ok());
+        }
+
+        let mut pipeline = Pipeline::new("specs/test.yaml".to_string());
+        pipeline.transition_to(PipelineState::Escalated).unwrap();
+        assert!(pipeline.transition_to(PipelineState::Validation).is_err());
+    }
+
+    #[test]
+    fn test_state_names_match_serde() {
+        for state in [
+            PipelineState::Pending,
+            PipelineState::SpecReview,
+            PipelineState::UniverseSetup,
+            PipelineState::AgentDevelopment,
+            PipelineState::Validation,
+            PipelineState::Accepted,
+            PipelineState::Escalated,
+            PipelineState::Failed,
+        ] {
+            assert_eq!(
+                serde_json::to_value(state).unwrap(),
+                serde_json::Value::from(state.as_str())
+            );
+        }
+    }
 }
@@ -0,0 +1,349 @@
+//! Twin universes for pipelines
+//!
+//! A spec lists the twin definitions its scenarios run against under
+//! `twins:`, relative to the spec file. [`Universe::boot`] starts one
+//! `twins` server per definition on a free local port and waits until each
+//! answers before handing back their base URLs.
+//!
+//! The servers run on a dedicated thread with its own Tokio runtime, so a
+//! universe can be booted and torn down from synchronous code whether or not
+//! a runtime is already running. Dropping a [`Universe`] stops its servers.
+
+use std::{
+    fs,
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream},
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use serde_json::Value;
+use thiserror::Error;
+use tokio::sync::oneshot;
+use tracing::{debug, warn};
+use twins::TwinDefinition;
+
+use crate::state::TwinInstance;
+
+/// How long to wait for every twin to answer after boot
+pub const READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Errors setting up a universe
+#[derive(Debug, Error)]
+pub enum UniverseError {
+    #[error("Failed to read {path}: {message}")]
+    Read { path: PathBuf, message: String },
+
+    #[error("Invalid twin definition {path}: {message}")]
+    Definition { path: PathBuf, message: String },
+
+    #[error("Failed to start twins: {0}")]
+    Startup(String),
+
+    #[error("Twin {name} at {base_url} did not become ready")]
+    NotReady { name: String, base_url: String },
+}
+
+/// Twin definition files a spec lists under `twins:`, resolved against the
+/// spec's directory
+///
+/// # Errors
+/// Returns an error if the spec cannot be read or parsed.
+pub fn twin_paths(spec_path: &Path) -> Result<Vec<PathBuf>, UniverseError> {
+    let text = fs::read_to_string(spec_path).map_err(|e| UniverseError::Read {
+        path: spec_path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+    let spec: Value = serde_yaml::from_str(&text).map_err(|e| UniverseError::Read {
+        path: spec_path.to_path_buf(),
+        message: e.to_string(),
+    })?;
+
+    let base = spec_path.parent().unwrap_or_else(|| Path::new("."));
+    Ok(spec
+        .get("twins")
+        .and_then(Value::as_array)
+        .map(|twins| {
+            twins
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|twin| base.join(twin))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Load the twin definitions at `paths`
+///
+/// # Errors
+/// Returns an error naming the first file that cannot be read or parsed.
+pub fn load_definitions(paths: &[PathBuf]) -> Result<Vec<TwinDefinition>, UniverseError> {
+    paths
+        .iter()
+        .map(|path| {
+            let bytes = fs::read(path).map_err(|e| UniverseError::Read {
+                path: path.clone(),
+                message: e.to_string(),
+            })?;
+            TwinDefinition::from_yaml_bytes(&bytes).map_err(|e| UniverseError::Definition {
+                path: path.clone(),
+                message: e.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Running twin servers for one pipeline
+#[derive(Debug)]
+pub struct Universe {
+    twins: Vec<TwinInstance>,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Universe {
+    /// Start every twin on a free port and wait until all answer
+    ///
+    /// # Errors
+    /// Returns an error if a twin cannot bind or does not answer within
+    /// `ready_timeout`; twins already started are stopped.
+    pub fn boot(
+        definitions: Vec<TwinDefinition>,
+        ready_timeout: Duration,
+    ) -> Result<Self, UniverseError> {
+        let names: Vec<(String, u16)> = definitions
+            .iter()
+            .map(|d| (d.name.clone(), d.port))
+            .collect();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+
+        let thread = thread::Builder::new()
+            .name("twin-universe".to_string())
+            .spawn(move || run(definitions, &ready_tx, shutdown_rx))
+            .map_err(|e| UniverseError::Startup(e.to_string()))?;
+
+        let mut universe = Self {
+            twins: Vec::new(),
+            shutdown: Some(shutdown_tx),
+            thread: Some(thread),
+        };
+
+        let addrs = ready_rx
+            .recv_timeout(ready_timeout)
+            .map_err(|e| UniverseError::Startup(e.to_string()))?
+            .map_err(UniverseError::Startup)?;
+
+        universe.twins = names
+            .into_iter()
+            .zip(addrs)
+            .map(|((name, port), addr)| TwinInstance {
+                name,
+                base_url: format!("http://{addr}"),
+                port: Some(port),
+            })
+            .collect();
+
+        let deadline = Instant::now() + ready_timeout;
+        for twin in &universe.twins {
+            if !wait_ready(&twin.base_url, deadline) {
+                return Err(UniverseError::NotReady {
+                    name: twin.name.clone(),
+                    base_url: twin.base_url.clone(),
+                });
+            }
+        }
+
+        Ok(universe)
+    }
+
+    /// The running twins, in definition order
+    #[must_use]
+    pub fn twins(&self) -> &[TwinInstance] {
+        &self.twins
+    }
+
+    /// Stop every twin and wait for the servers to exit
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                warn!("Twin universe thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for Universe {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Body of the universe thread: bind, report addresses, serve until shutdown
+fn run(
+    definitions: Vec<TwinDefinition>,
+    ready: &mpsc::Sender<Result<Vec<SocketAddr>, String>>,
+    shutdown: oneshot::Receiver<()>,
+) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            let _ = ready.send(Err(e.to_string()));
+            return;
+        }
+    };
+
+    runtime.block_on(async move {
+        let mut listeners = Vec::with_capacity(definitions.len());
+        for definition in &definitions {
+            match tokio::net::TcpListener::bind(("127.0.0.1", 0)).await {
+                Ok(listener) => listeners.push(listener),
+                Err(e) => {
+                    let _ = ready.send(Err(format!("{}: {e}", definition.name)));
+                    return;
+                }
+            }
+        }
+
+        let addrs = listeners
+            .iter()
+            .map(tokio::net::TcpListener::local_addr)
+            .collect();
+        let addrs = match addrs {
+            Ok(addrs) => addrs,
+            Err(e) => {
+                let _ = ready.send(Err(e.to_string()));
+                return;
+            }
+        };
+
+        for (listener, definition) in listeners.into_iter().zip(definitions) {
+            tokio::spawn(async move {
+                let name = definition.name.clone();
+                if let Err(e) = twins::server::serve(listener, definition).await {
+                    warn!("Twin {name} stopped: {e}");
+                }
+            });
+        }
+
+        let _ = ready.send(Ok(addrs));
+        let _ = shutdown.await;
+    });
+}
+
+/// Poll the twin's inspection endpoint until it answers 200
+fn wait_ready(base_url: &str, deadline: Instant) -> bool {
+    let Some(addr) = base_url
+        .strip_prefix("http://")
+        .and_then(|addr| addr.parse::<SocketAddr>().ok())
+    else {
+        return false;
+    };
+
+    loop {
+        if probe(addr) {
+            debug!("Twin at {base_url} is ready");
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn probe(addr: SocketAddr) -> bool {
+    let Ok(mut stream) = TcpStream::connect_timeout(&addr, Duration::from_secs(1)) else {
+        return false;
+    };
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(1)));
+
+    let request =
+        format!("GET /_inspect/state HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n");
+    if stream.write_all(request.as_bytes()).is_err() {
+        return false;
+    }
+
+    let mut status = [0; 12];
+    stream.read_exact(&mut status).is_ok() && status.ends_with(b" 200")
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    const TWIN: &str = r"
+name: sendgrid
+port: 3001
+endpoints:
+  - path: /api/health
+    method: GET
+    response:
+      status: 200
+      body:
+        status: healthy
+";
+
+    #[test]
+    fn test_twin_paths_are_relative_to_spec() {
+        let temp = TempDir::new().unwrap();
+        let spec = temp.path().join("spec.yaml");
+        fs::write(&spec, "twins:\n  - twins/sendgrid.yaml\n").unwrap();
+
+        let paths = twin_paths(&spec).unwrap();
+
+        assert_eq!(paths, vec![temp.path().join("twins/sendgrid.yaml")]);
+    }
+
+    #[test]
+    fn test_spec_without_twins_has_empty_universe() {
+        let temp = TempDir::new().unwrap();
+        let spec = temp.path().join("spec.yaml");
+        fs::write(&spec, "identity:\n  id: spec-x\n").unwrap();
+
+        assert_eq!(twin_paths(&spec).unwrap(), Vec::<PathBuf>::new());
+    }
+
+    #[test]
+    fn test_invalid_definition_names_its_file() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("broken.yaml");
+        fs::write(&path, "name: broken\n").unwrap();
+
+        let result = load_definitions(&[path]);
+
+        assert!(matches!(result, Err(UniverseError::Definition { .. })));
+    }
+
+    #[test]
+    fn test_boot_serves_on_free_ports_until_shutdown() {
+        let definition = TwinDefinition::from_yaml(TWIN).unwrap();
+
+        let universe = Universe::boot(vec![definition.clone(), definition], READY_TIMEOUT).unwrap();
+        let twins = universe.twins().to_vec();
+
+        assert_eq!(twins.len(), 2);
+        assert_ne!(twins[0].base_url, twins[1].base_url);
+        assert!(!twins[0].base_url.ends_with(":3001"));
+        let addr: SocketAddr = twins[0].base_url["http://".len()..].parse().unwrap();
+        assert!(probe(addr));
+
+        universe.shutdown();
+
+        assert!(!probe(addr));
+    }
+}
@@ -0,0 +1,299 @@
+//! Scenario execution for the validation phase
+//!
+//! Loads every scenario YAML under a directory and runs it through
+//! [`scenarios::ScenarioRunner`], converting each outcome into a
+//! [`ScenarioResult`] with its real duration.
+//!
+//! Agents never see scenarios. What they get back is [`ScenarioRun::feedback`],
+//! passed through the [`Sanitizer`] at the requested level and naming
+//! scenarios only by position.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use scenarios::{FeedbackLevel, RunnerConfig, Sanitizer, Scenario, ScenarioRunner};
+use tracing::warn;
+
+use crate::metrics::ScenarioResult;
+
+/// A scenario file and its parsed contents
+#[derive(Debug, Clone)]
+pub struct ScenarioFile {
+    pub path: PathBuf,
+    /// The scenario, or why it could not be loaded
+    pub scenario: Result<Scenario, String>,
+}
+
+impl ScenarioFile {
+    /// Scenario name, falling back to the file stem when it did not parse
+    #[must_use]
+    pub fn name(&self) -> String {
+        self.scenario.as_ref().map_or_else(
+            |_| {
+                self.path
+                    .file_stem()
+                    .map_or_else(String::new, |s| s.to_string_lossy().into_owned())
+            },
+            |scenario| scenario.name.clone(),
+        )
+    }
+}
+
+/// Outcome of running a scenario directory
+#[derive(Debug, Clone, Default)]
+pub struct ScenarioRun {
+    pub results: Vec<ScenarioResult>,
+    /// Sanitized feedback, safe to hand to the agent
+    pub feedback: String,
+}
+
+/// Every `.yaml`/`.yml` scenario under `dir`, sorted by path
+///
+/// A missing directory holds no scenarios.
+#[must_use]
+pub fn load_scenarios(dir: &Path) -> Vec<ScenarioFile> {
+    let mut paths = Vec::new();
+    collect_yaml(dir, &mut paths);
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let scenario = fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| Scenario::from_yaml_bytes(&bytes).map_err(|e| e.to_string()));
+            ScenarioFile { path, scenario }
+        })
+        .collect()
+}
+
+fn collect_yaml(dir: &Path, paths: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for path in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+        if path.is_dir() {
+            collect_yaml(&path, paths);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        {
+            paths.push(path);
+        }
+    }
+}
+
+/// Run every scenario under `dir` against the twin at `config.twin_url`
+///
+/// Scenarios that fail to load count as failed. The runner is async; it is
+/// driven on a dedicated thread so this works whether or not the caller is
+/// already inside a Tokio runtime.
+#[must_use]
+pub fn run_scenarios(dir: &Path, config: RunnerConfig, level: FeedbackLevel) -> ScenarioRun {
+    let files = load_scenarios(dir);
+    if files.is_empty() {
+        return ScenarioRun::default();
+    }
+
+    let outcomes = std::thread::scope(|scope| {
+        scope
+            .spawn(|| run_files(&files, config))
+            .join()
+            .unwrap_or_else(|_| {
+                warn!("Scenario runner thread panicked");
+                files
+                    .iter()
+                    .map(|file| Err(failed(file, "Scenario runner panicked")))
+                    .collect()
+            })
+    });
+
+    let sanitizer = Sanitizer::new(level);
+    let feedback = outcomes
+        .iter()
+        .enumerate()
+        .map(|(index, outcome)| {
+            let verdict = match outcome {
+                Ok((raw, _)) => sanitizer.sanitize_result(raw),
+                Err(_) => "FAIL".to_string(),
+            };
+            format!("Scenario {}: {verdict}", index + 1)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    ScenarioRun {
+        results: outcomes
+            .into_iter()
+            .map(|outcome| outcome.map_or_else(|failed| failed, |(_, result)| result))
+            .collect(),
+        feedback,
+    }
+}
+
+/// The runner's raw result alongside its conversion, or the result of a
+/// scenario that never ran
+type Outcome = Result<(scenarios::ScenarioResult, ScenarioResult), ScenarioResult>;
+
+fn run_files(files: &[ScenarioFile], config: RunnerConfig) -> Vec<Outcome> {
+    let setup = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())
+        .and_then(|runtime| {
+            ScenarioRunner::new(config)
+                .map(|runner| (runtime, runner))
+                .map_err(|e| e.to_string())
+        });
+
+    let (runtime, runner) = match setup {
+        Ok(setup) => setup,
+        Err(e) => {
+            warn!("Could not start scenario runner: {e}");
+            return files.iter().map(|file| Err(failed(file, &e))).collect();
+        }
+    };
+
+    files
+        .iter()
+        .map(|file| match &file.scenario {
+            Ok(scenario) => {
+                let start = Instant::now();
+                let raw = runtime.block_on(runner.run(scenario));
+                let result = convert(&raw, start.elapsed().as_secs_f64());
+                Ok((raw, result))
+            }
+            Err(e) => Err(failed(file, &format!("Invalid scenario: {e}"))),
+        })
+        .collect()
+}
+
+/// Convert a runner result, keeping the first failing step's error
+fn convert(result: &scenarios::ScenarioResult, duration_secs: f64) -> ScenarioResult {
+    let error = result
+        .step_results
+        .iter()
+        .find(|step| !step.passed)
+        .map(|step| {
+            format!(
+                "{} step failed: {}",
+                step.step_type,
+                step.error.as_deref().unwrap_or("unknown error")
+            )
+        });
+
+    ScenarioResult {
+        name: result.scenario_name.clone(),
+        passed: result.passed,
+        duration_secs,
+        error,
+    }
+}
+
+fn failed(file: &ScenarioFile, error: &str) -> ScenarioResult {
+    ScenarioResult {
+        name: file.name(),
+        passed: false,
+        duration_secs: 0.0,
+        error: Some(error.to_string()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// A one-step scenario that passes when `actual` is `"ok"`.
+    pub(crate) fn assert_scenario(name: &str, actual: &str) -> String {
+        format!(
+            "name: {name}\ndescription: test\nsteps:\n  - type: assert\n    assertion: equals\n    equals: \"{actual}\"\n    expected: \"ok\"\n"
+        )
+    }
+
+    #[test]
+    fn test_missing_directory_has_no_scenarios() {
+        let temp = TempDir::new().unwrap();
+
+        let run = run_scenarios(
+            &temp.path().join("missing"),
+            RunnerConfig::default(),
+            FeedbackLevel::Level2,
+        );
+
+        assert_eq!(run.results.len(), 0);
+    }
+
+    #[test]
+    fn test_runs_every_scenario_in_path_order() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir(temp.path().join("nested")).unwrap();
+        fs::write(temp.path().join("a.yaml"), assert_scenario("passes", "ok")).unwrap();
+        fs::write(
+            temp.path().join("nested/b.yml"),
+            assert_scenario("fails", "no"),
+        )
+        .unwrap();
+        fs::write(temp.path().join("c.yaml"), "not: [a scenario").unwrap();
+        fs::write(temp.path().join("notes.txt"), "ignored").unwrap();
+
+        let run = run_scenarios(temp.path(), RunnerConfig::default(), FeedbackLevel::Level2);
+        let results = &run.results;
+
+        let outcomes: Vec<(&str, bool)> = results
+            .iter()
+            .map(|r| (r.name.as_str(), r.passed))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![("passes", true), ("c", false), ("fails", false)]
+        );
+        assert_eq!(results[0].error, None);
+        assert!(results[1]
+            .error
+            .as_deref()
+            .is_some_and(|e| e.starts_with("Invalid scenario")));
+        assert_eq!(
+            results[2].error.as_deref(),
+            Some("assert step failed: Assertion failed")
+        );
+    }
+
+    #[test]
+    fn test_feedback_hides_scenario_details() {
+        let temp = TempDir::new().unwrap();
+        fs::write(
+            temp.path().join("a.yaml"),
+            assert_scenario("secret-name", "ok"),
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("b.yaml"),
+            assert_scenario("hidden", "leak"),
+        )
+        .unwrap();
+        fs::write(temp.path().join("c.yaml"), "not: [a scenario").unwrap();
+
+        let run = run_scenarios(temp.path(), RunnerConfig::default(), FeedbackLevel::Level2);
+
+        assert_eq!(
+            run.feedback,
+            "Scenario 1: PASS\nScenario 2: FAIL: assertion failed\nScenario 3: FAIL"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_runs_inside_an_async_caller() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("a.yaml"), assert_scenario("passes", "ok")).unwrap();
+
+        let run = run_scenarios(temp.path(), RunnerConfig::default(), FeedbackLevel::Level2);
+
+        assert!(run.results[0].passed);
+    }
+}
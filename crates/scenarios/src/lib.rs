@@ -50,6 +50,6 @@ pub mod runner;
 pub mod sanitizer;
 pub mod scenario;
 
-pub use runner::{RunnerConfig, ScenarioResult, ScenarioRunner, StepResult};
+pub use runner::{RunnerConfig, ScenarioResult, ScenarioRunner, StepResult, TwinRoute};
 pub use sanitizer::{FeedbackLevel, Sanitizer};
 pub use scenario::{AssertStep, AssertionType, ExtractStep, HttpMethod, HttpStep, Scenario, Step};
@@ -11,7 +11,7 @@
 
 use std::collections::HashMap;
 
-use reqwest::Client;
+use reqwest::{Client, Url};
 use serde_json::Value;
 
 use crate::{
@@ -53,11 +53,24 @@ pub struct StepResult {
     pub error: Option<String>,
 }
 
+/// Where a named twin actually serves
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TwinRoute {
+    /// Name from the twin definition
+    pub name: String,
+    /// Port the twin definition declares
+    pub port: Option<u16>,
+    /// Base URL the twin is reachable at
+    pub base_url: String,
+}
+
 /// Scenario runner configuration
 #[derive(Debug, Clone)]
 pub struct RunnerConfig {
     /// Base URL for the twin instance
     pub twin_url: String,
+    /// Twins that absolute step URLs may address by name or declared port
+    pub twins: Vec<TwinRoute>,
     /// Timeout for HTTP requests in seconds
     pub timeout_secs: u64,
     /// Whether to follow redirects
@@ -68,6 +81,7 @@ impl Default for RunnerConfig {
     fn default() -> Self {
         Self {
             twin_url: String::from("http://localhost:3001"),
+            twins: Vec::new(),
             timeout_secs: 30,
             follow_redirects: true,
         }
@@ -78,7 +92,6 @@ impl Default for RunnerConfig {
 #[derive(Debug)]
 pub struct ScenarioRunner {
     client: Client,
-    #[allow(dead_code)]
     config: RunnerConfig,
     sanitizer: Sanitizer,
 }
@@ -156,12 +169,13 @@ impl ScenarioRunner {
 
     /// Execute an HTTP step
     async fn execute_http(&self, step: &HttpStep, context: &mut RunContext) -> StepResult {
+        let url = self.resolve_url(&step.url);
         let mut request = match step.method {
-            HttpMethod::Get => self.client.get(&step.url),
-            HttpMethod::Post => self.client.post(&step.url),
-            HttpMethod::Put => self.client.put(&step.url),
-            HttpMethod::Patch => self.client.patch(&step.url),
-            HttpMethod::Delete => self.client.delete(&step.url),
+            HttpMethod::Get => self.client.get(&url),
+            HttpMethod::Post => self.client.post(&url),
+            HttpMethod::Put => self.client.put(&url),
+            HttpMethod::Patch => self.client.patch(&url),
+            HttpMethod::Delete => self.client.delete(&url),
         };
 
         // Add headers
@@ -219,6 +233,48 @@ impl ScenarioRunner {
         }
     }
 
+    /// Resolve a step URL against the twin
+    ///
+    /// Paths such as `/v3/mail/send` are relative to `twin_url`, so the same
+    /// scenario runs against whichever universe the runner points at. An
+    /// absolute URL whose host is a twin's name, such as `http://sendgrid/`,
+    /// or a local host on a twin's declared port, such as
+    /// `http://localhost:3001/`, is moved onto that twin's base URL.
+    fn resolve_url(&self, url: &str) -> String {
+        if url.starts_with('/') {
+            return format!("{}{url}", self.config.twin_url.trim_end_matches('/'));
+        }
+
+        let Ok(parsed) = Url::parse(url) else {
+            return url.to_string();
+        };
+        let Some(host) = parsed.host_str() else {
+            return url.to_string();
+        };
+        let local = matches!(host, "localhost" | "127.0.0.1" | "[::1]");
+        let port = parsed.port_or_known_default();
+
+        self.config
+            .twins
+            .iter()
+            .find(|twin| {
+                twin.name.eq_ignore_ascii_case(host)
+                    || (local && twin.port.is_some() && twin.port == port)
+            })
+            .map_or_else(
+                || url.to_string(),
+                |twin| {
+                    let mut resolved =
+                        format!("{}{}", twin.base_url.trim_end_matches('/'), parsed.path());
+                    if let Some(query) = parsed.query() {
+                        resolved.push('?');
+                        resolved.push_str(query);
+                    }
+                    resolved
+                },
+            )
+    }
+
     /// Execute an extract step
     fn execute_extract(step: &ExtractStep, index: usize, context: &mut RunContext) -> StepResult {
         let Some(response) = &context.last_response else {
@@ -451,6 +507,62 @@ mod tests {
         assert_eq!(result, Some(serde_json::json!("value")));
     }
 
+    #[test]
+    fn test_relative_urls_resolve_against_twin() {
+        let runner = ScenarioRunner::new(RunnerConfig {
+            twin_url: "http://127.0.0.1:4100/".to_string(),
+            ..RunnerConfig::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            runner.resolve_url("/v3/mail/send"),
+            "http://127.0.0.1:4100/v3/mail/send"
+        );
+        assert_eq!(
+            runner.resolve_url("http://localhost:3001/health"),
+            "http://localhost:3001/health"
+        );
+    }
+
+    #[test]
+    fn test_absolute_urls_route_to_named_twins() {
+        let runner = ScenarioRunner::new(RunnerConfig {
+            twin_url: "http://127.0.0.1:4100".to_string(),
+            twins: vec![
+                TwinRoute {
+                    name: "sendgrid".to_string(),
+                    port: Some(3001),
+                    base_url: "http://127.0.0.1:4100".to_string(),
+                },
+                TwinRoute {
+                    name: "stripe".to_string(),
+                    port: Some(3002),
+                    base_url: "http://127.0.0.1:4200".to_string(),
+                },
+            ],
+            ..RunnerConfig::default()
+        })
+        .unwrap();
+
+        assert_eq!(
+            runner.resolve_url("http://localhost:3002/v1/charges?limit=1"),
+            "http://127.0.0.1:4200/v1/charges?limit=1"
+        );
+        assert_eq!(
+            runner.resolve_url("http://sendgrid/v3/mail/send"),
+            "http://127.0.0.1:4100/v3/mail/send"
+        );
+        assert_eq!(
+            runner.resolve_url("http://localhost:9999/health"),
+            "http://localhost:9999/health"
+        );
+        assert_eq!(
+            runner.resolve_url("https://example.com:3001/health"),
+            "https://example.com:3001/health"
+        );
+    }
+
     #[tokio::test]
     async fn test_runner_default_config() {
         let runner = ScenarioRunner::with_default_config();
@@ -41,7 +41,8 @@ pub enum Step {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HttpStep {
-    /// Target URL
+    /// Target URL; a path such as `/v3/mail/send` is relative to the twin,
+    /// and an absolute URL may name a twin by its name or declared port
     pub url: String,
     /// HTTP method
     #[serde(default)]
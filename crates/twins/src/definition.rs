@@ -9,11 +9,13 @@
 //!
 //! Parses twin definition YAML files into structured types.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::{matcher::CompiledMatcher, route::PathTemplate};
+
 /// Errors that can occur during twin definition parsing
 #[derive(Debug, Error)]
 pub enum DefinitionError {
@@ -25,6 +27,12 @@ pub enum DefinitionError {
     InvalidEndpoint(String),
     #[error("Invalid HTTP method: {0}")]
     InvalidMethod(String),
+    #[error("Invalid resource: {0}")]
+    InvalidResource(String),
+    #[error("Invalid matcher: {0}")]
+    InvalidMatcher(String),
+    #[error("Invalid fault config: {0}")]
+    InvalidFault(String),
 }
 
 /// HTTP method for an endpoint
@@ -84,15 +92,235 @@ pub struct EndpointResponse {
     pub headers: HashMap<String, String>,
 }
 
+/// How a matcher tests one request value
+///
+/// Written either as the value to equal, or as `{ regex: ... }` to match a
+/// pattern against the value as text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ValueMatcher {
+    /// Matches values whose text matches the pattern
+    Regex { regex: String },
+    /// Matches values equal to this one
+    Equals(serde_json::Value),
+}
+
+/// Conditions a request must all meet to select a response variant
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequestMatcher {
+    /// Request headers by name, any case
+    #[serde(default)]
+    pub headers: BTreeMap<String, ValueMatcher>,
+    /// Query parameters by name
+    #[serde(default)]
+    pub query: BTreeMap<String, ValueMatcher>,
+    /// JSON body fields by dotted path, e.g. `user.email` or `items.0.id`
+    #[serde(default)]
+    pub body: BTreeMap<String, ValueMatcher>,
+}
+
+impl RequestMatcher {
+    /// Whether the matcher has no conditions at all
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty() && self.query.is_empty() && self.body.is_empty()
+    }
+
+    /// The matcher with header names lowercased, as they are compared
+    fn normalized(&self) -> Self {
+        Self {
+            headers: self
+                .headers
+                .iter()
+                .map(|(k, v)| (k.to_ascii_lowercase(), v.clone()))
+                .collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Whether every condition of `other` is also one of these
+    fn includes_all(&self, other: &Self) -> bool {
+        fn includes(
+            conditions: &BTreeMap<String, ValueMatcher>,
+            required: &BTreeMap<String, ValueMatcher>,
+        ) -> bool {
+            required.iter().all(|(k, v)| conditions.get(k) == Some(v))
+        }
+        includes(&self.headers, &other.headers)
+            && includes(&self.query, &other.query)
+            && includes(&self.body, &other.body)
+    }
+}
+
+/// A response an endpoint returns when its matcher holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Variant {
+    /// Conditions selecting this response
+    pub when: RequestMatcher,
+    /// Response configuration
+    pub response: EndpointResponse,
+}
+
+/// Delay before a twin responds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum Latency {
+    /// Always this many milliseconds
+    Fixed { fixed_ms: u64 },
+    /// Uniformly between `min_ms` and `max_ms`, inclusive
+    Random { min_ms: u64, max_ms: u64 },
+}
+
+/// Faults a twin injects to simulate a flaky upstream
+///
+/// Rates are fractions of requests from 0.0 to 1.0. One roll per request
+/// picks at most one of dropping the connection, answering with
+/// `error_status`, or sending a malformed body, so the rates must not add
+/// up to more than 1.0.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FaultConfig {
+    /// Seed for reproducible faults; only allowed at the twin level
+    pub seed: Option<u64>,
+    /// Delay before responding
+    pub latency: Option<Latency>,
+    /// Fraction of requests answered with `error_status`
+    pub error_rate: f64,
+    /// Status of injected errors
+    pub error_status: u16,
+    /// Fraction of requests whose connection is dropped without a response
+    pub drop_rate: f64,
+    /// Fraction of requests answered with a body that is not valid JSON
+    pub malformed_rate: f64,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            latency: None,
+            error_rate: 0.0,
+            error_status: 500,
+            drop_rate: 0.0,
+            malformed_rate: 0.0,
+        }
+    }
+}
+
+/// How far fault rates may add up past 1.0, for rates written to add up to
+/// exactly 1.0 that sum a few ulps over as floats
+const RATE_SUM_TOLERANCE: f64 = 4.0 * f64::EPSILON;
+
+impl FaultConfig {
+    /// Check rates are fractions that fit in one roll and the rest is usable
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let rates = [
+            ("error_rate", self.error_rate),
+            ("drop_rate", self.drop_rate),
+            ("malformed_rate", self.malformed_rate),
+        ];
+        for (name, rate) in rates {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(format!("{name} must be between 0.0 and 1.0, got {rate}"));
+            }
+        }
+        if self.error_rate + self.drop_rate + self.malformed_rate > 1.0 + RATE_SUM_TOLERANCE {
+            return Err(
+                "error_rate, drop_rate and malformed_rate add up to more than 1.0".to_string(),
+            );
+        }
+        if !(100..=599).contains(&self.error_status) {
+            return Err(format!(
+                "error_status {} is not an HTTP status",
+                self.error_status
+            ));
+        }
+        if let Some(Latency::Random { min_ms, max_ms }) = self.latency {
+            if min_ms > max_ms {
+                return Err(format!("latency min_ms {min_ms} is above max_ms {max_ms}"));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Endpoint definition within a twin
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Endpoint {
-    /// URL path for the endpoint
+    /// URL path template, e.g. `/users/{id}` or `/v1/*`
     pub path: String,
     /// HTTP method
     pub method: HttpMethod,
-    /// Response configuration
+    /// Responses selected by request matchers, tried in order
+    #[serde(default)]
+    pub variants: Vec<Variant>,
+    /// Response configuration, the fallback when no variant matches
     pub response: EndpointResponse,
+    /// Faults for this endpoint, in place of the twin's
+    #[serde(default)]
+    pub faults: Option<FaultConfig>,
+}
+
+impl Endpoint {
+    /// Check every variant has a valid matcher no earlier variant shadows
+    fn validate_variants(&self) -> Result<(), String> {
+        for (j, variant) in self.variants.iter().enumerate() {
+            if variant.when.is_empty() {
+                return Err(format!(
+                    "variant {j} has no conditions; use the endpoint response as the fallback"
+                ));
+            }
+            CompiledMatcher::compile(&variant.when).map_err(|e| format!("variant {j}: {e}"))?;
+            let when = variant.when.normalized();
+            if let Some(k) = self.variants[..j]
+                .iter()
+                .position(|other| when.includes_all(&other.when.normalized()))
+            {
+                return Err(format!(
+                    "variant {j} has every condition of variant {k}, so it can never match"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How a resource mints ids for the items created in it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdStrategy {
+    /// Random UUID v4 strings
+    #[default]
+    Uuid,
+    /// Increasing integers starting at 1
+    Sequence,
+}
+
+/// A stateful CRUD collection within a twin
+///
+/// A resource at `/users` serves `GET /users` (list), `POST /users`
+/// (create), and `GET`, `PUT`, `PATCH` and `DELETE` on `/users/{id}`.
+/// Endpoints matching the same requests take precedence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Resource {
+    /// Name of the collection
+    pub name: String,
+    /// Collection path; items live at `<path>/{id}`
+    pub path: String,
+    /// Id generation for created items
+    #[serde(default)]
+    pub id: IdStrategy,
+    /// Field of each item holding its id
+    #[serde(default = "default_id_field")]
+    pub id_field: String,
+    /// Items the collection starts with
+    #[serde(default)]
+    pub seed: Vec<serde_json::Value>,
+}
+
+fn default_id_field() -> String {
+    "id".to_string()
 }
 
 /// Twin definition loaded from YAML
@@ -103,7 +331,14 @@ pub struct TwinDefinition {
     /// Port to run the twin server on
     pub port: u16,
     /// List of endpoint definitions
+    #[serde(default)]
     pub endpoints: Vec<Endpoint>,
+    /// Stateful CRUD collections
+    #[serde(default)]
+    pub resources: Vec<Resource>,
+    /// Faults for every endpoint and resource without their own
+    #[serde(default)]
+    pub faults: Option<FaultConfig>,
 }
 
 impl TwinDefinition {
@@ -135,20 +370,127 @@ impl TwinDefinition {
         if self.port == 0 {
             return Err(DefinitionError::MissingField("port".to_string()));
         }
-        if self.endpoints.is_empty() {
+        if self.endpoints.is_empty() && self.resources.is_empty() {
             return Err(DefinitionError::MissingField("endpoints".to_string()));
         }
+        let mut routes: Vec<(HttpMethod, PathTemplate)> = Vec::new();
         for (i, endpoint) in self.endpoints.iter().enumerate() {
             if !endpoint.path.starts_with('/') {
                 return Err(DefinitionError::InvalidEndpoint(format!(
                     "Endpoint {i}: path must start with /"
                 )));
             }
+            let template = PathTemplate::parse(&endpoint.path)?;
+            endpoint.validate_variants().map_err(|e| {
+                DefinitionError::InvalidMatcher(format!(
+                    "Endpoint {i} ({} {}): {e}",
+                    endpoint.method, endpoint.path
+                ))
+            })?;
+            if let Some(j) = routes
+                .iter()
+                .position(|(method, other)| *method == endpoint.method && other.overlaps(&template))
+            {
+                return Err(DefinitionError::InvalidEndpoint(format!(
+                    "Endpoint {i}: {} {} matches the same requests as endpoint {j}",
+                    endpoint.method, endpoint.path
+                )));
+            }
+            routes.push((endpoint.method, template));
+            if let Some(faults) = &endpoint.faults {
+                faults
+                    .validate()
+                    .map_err(|e| DefinitionError::InvalidFault(format!("Endpoint {i}: {e}")))?;
+                if faults.seed.is_some() {
+                    return Err(DefinitionError::InvalidFault(format!(
+                        "Endpoint {i}: seed is only allowed in the twin's faults"
+                    )));
+                }
+            }
+        }
+        if let Some(faults) = &self.faults {
+            faults.validate().map_err(DefinitionError::InvalidFault)?;
+        }
+        for (i, resource) in self.resources.iter().enumerate() {
+            resource
+                .validate()
+                .map_err(|e| DefinitionError::InvalidResource(format!("Resource {i}: {e}")))?;
+            if self.resources[..i]
+                .iter()
+                .any(|other| other.name == resource.name || other.path == resource.path)
+            {
+                return Err(DefinitionError::InvalidResource(format!(
+                    "Resource {i}: name '{}' or path '{}' is already used",
+                    resource.name, resource.path
+                )));
+            }
+            // An item path of one resource must never be another's collection
+            if let Some(other) = self.resources[..i].iter().find(|other| {
+                nests_under(&resource.path, &other.path) || nests_under(&other.path, &resource.path)
+            }) {
+                return Err(DefinitionError::InvalidResource(format!(
+                    "Resource {i}: path '{}' overlaps resource path '{}'",
+                    resource.path, other.path
+                )));
+            }
         }
         Ok(())
     }
 }
 
+impl Resource {
+    /// Check the collection can be served and its seed items have ids
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("name is required".to_string());
+        }
+        if self.id_field.is_empty() {
+            return Err("id_field must not be empty".to_string());
+        }
+        let template = PathTemplate::parse(&self.path).map_err(|e| e.to_string())?;
+        if !template.is_literal() || self.path == "/" || self.path.ends_with('/') {
+            return Err(format!(
+                "path '{}' must be a plain path such as /users",
+                self.path
+            ));
+        }
+        let mut ids = Vec::new();
+        for (j, item) in self.seed.iter().enumerate() {
+            let id = item
+                .as_object()
+                .and_then(|item| item.get(&self.id_field))
+                .and_then(id_string)
+                .ok_or_else(|| {
+                    format!(
+                        "seed item {j} must be an object with a string or number '{}'",
+                        self.id_field
+                    )
+                })?;
+            if ids.contains(&id) {
+                return Err(format!("seed id '{id}' is repeated"));
+            }
+            ids.push(id);
+        }
+        Ok(())
+    }
+}
+
+/// Whether `path` lies below `parent`, e.g. `/users/admins` below `/users`
+fn nests_under(path: &str, parent: &str) -> bool {
+    path.strip_prefix(parent)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// An item id as it appears in paths, for string and number ids
+#[must_use]
+pub fn id_string(id: &serde_json::Value) -> Option<String> {
+    match id {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -185,6 +527,275 @@ endpoints: []
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_overlapping_templates_are_rejected() {
+        let yaml = r"
+name: test
+port: 3001
+endpoints:
+  - path: /users/{id}
+    method: GET
+    response:
+      status: 200
+  - path: /users/{user_id}
+    method: GET
+    response:
+      status: 404
+";
+        let result = TwinDefinition::from_yaml(yaml);
+        assert!(matches!(result, Err(DefinitionError::InvalidEndpoint(_))));
+    }
+
+    #[test]
+    fn test_parse_resources() {
+        let yaml = r"
+name: users
+port: 3001
+resources:
+  - name: users
+    path: /users
+    id: sequence
+    seed:
+      - id: 1
+        email: ada@example.com
+";
+        let def = TwinDefinition::from_yaml(yaml).expect("Should parse resources");
+        assert!(def.endpoints.is_empty());
+        assert_eq!(def.resources[0].id, IdStrategy::Sequence);
+        assert_eq!(def.resources[0].id_field, "id");
+        assert_eq!(def.resources[0].seed.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_resources() {
+        let templated = r"
+name: users
+port: 3001
+resources:
+  - name: users
+    path: /users/{id}
+";
+        let unidentified_seed = r"
+name: users
+port: 3001
+resources:
+  - name: users
+    path: /users
+    seed:
+      - email: ada@example.com
+";
+        let nested = r"
+name: users
+port: 3001
+resources:
+  - name: users
+    path: /users
+  - name: admins
+    path: /users/admins
+";
+        for yaml in [templated, unidentified_seed, nested] {
+            let result = TwinDefinition::from_yaml(yaml);
+            assert!(matches!(result, Err(DefinitionError::InvalidResource(_))));
+        }
+    }
+
+    #[test]
+    fn test_parse_variants() {
+        let yaml = r"
+name: stripe
+port: 3001
+endpoints:
+  - path: /v1/charges
+    method: POST
+    variants:
+      - when:
+          headers:
+            Authorization: Bearer sk_test_good
+          body:
+            amount: 100
+            email: { regex: '@example\.com$' }
+        response:
+          status: 200
+    response:
+      status: 401
+";
+        let def = TwinDefinition::from_yaml(yaml).expect("Should parse variants");
+        let when = &def.endpoints[0].variants[0].when;
+        assert_eq!(
+            when.body.get("amount"),
+            Some(&ValueMatcher::Equals(serde_json::json!(100)))
+        );
+        assert!(matches!(
+            when.body.get("email"),
+            Some(ValueMatcher::Regex { .. })
+        ));
+        assert_eq!(def.endpoints[0].response.status, 401);
+    }
+
+    #[test]
+    fn test_invalid_matchers() {
+        let bad_regex = r"
+name: stripe
+port: 3001
+endpoints:
+  - path: /v1/charges
+    method: POST
+    variants:
+      - when:
+          query:
+            id: { regex: '(unclosed' }
+        response:
+          status: 200
+    response:
+      status: 401
+";
+        let ambiguous = r"
+name: stripe
+port: 3001
+endpoints:
+  - path: /v1/charges
+    method: POST
+    variants:
+      - when:
+          headers:
+            authorization: Bearer a
+        response:
+          status: 200
+      - when:
+          headers:
+            authorization: Bearer a
+        response:
+          status: 402
+    response:
+      status: 401
+";
+        let unconditional = r"
+name: stripe
+port: 3001
+endpoints:
+  - path: /v1/charges
+    method: POST
+    variants:
+      - when: {}
+        response:
+          status: 200
+    response:
+      status: 401
+";
+        let shadowed = r"
+name: stripe
+port: 3001
+endpoints:
+  - path: /v1/charges
+    method: POST
+    variants:
+      - when:
+          headers:
+            Authorization: Bearer a
+        response:
+          status: 200
+      - when:
+          headers:
+            authorization: Bearer a
+          query:
+            expand: customer
+        response:
+          status: 402
+    response:
+      status: 401
+";
+        for yaml in [bad_regex, ambiguous, unconditional, shadowed] {
+            let result = TwinDefinition::from_yaml(yaml);
+            assert!(matches!(result, Err(DefinitionError::InvalidMatcher(_))));
+        }
+    }
+
+    #[test]
+    fn test_parse_faults() {
+        let yaml = r"
+name: flaky
+port: 3001
+faults:
+  seed: 7
+  latency: { min_ms: 10, max_ms: 50 }
+  error_rate: 0.2
+  error_status: 503
+endpoints:
+  - path: /health
+    method: GET
+    faults:
+      latency: { fixed_ms: 100 }
+      drop_rate: 0.5
+    response:
+      status: 200
+";
+        let def = TwinDefinition::from_yaml(yaml).expect("Should parse faults");
+        let faults = def.faults.expect("Should have twin faults");
+        assert_eq!(faults.seed, Some(7));
+        assert_eq!(
+            faults.latency,
+            Some(Latency::Random {
+                min_ms: 10,
+                max_ms: 50
+            })
+        );
+        assert_eq!(faults.error_status, 503);
+        let endpoint = def.endpoints[0].faults.as_ref().expect("Should override");
+        assert_eq!(endpoint.latency, Some(Latency::Fixed { fixed_ms: 100 }));
+        assert_eq!(endpoint.error_status, 500);
+    }
+
+    #[test]
+    fn test_fault_rates_may_add_up_to_one() {
+        let yaml = r"
+name: flaky
+port: 3001
+faults:
+  error_rate: 0.34
+  drop_rate: 0.56
+  malformed_rate: 0.1
+endpoints:
+  - path: /health
+    method: GET
+    response:
+      status: 200
+";
+        let def = TwinDefinition::from_yaml(yaml).expect("Should accept rates adding up to 1.0");
+        let faults = def.faults.expect("Should have twin faults");
+        assert!(faults.error_rate + faults.drop_rate + faults.malformed_rate > 1.0);
+    }
+
+    #[test]
+    fn test_invalid_faults() {
+        let over_one = r"
+name: flaky
+port: 3001
+faults:
+  error_rate: 0.6
+  drop_rate: 0.6
+endpoints:
+  - path: /health
+    method: GET
+    response:
+      status: 200
+";
+        let endpoint_seed = r"
+name: flaky
+port: 3001
+endpoints:
+  - path: /health
+    method: GET
+    faults:
+      seed: 1
+    response:
+      status: 200
+";
+        for yaml in [over_one, endpoint_seed] {
+            let result = TwinDefinition::from_yaml(yaml);
+            assert!(matches!(result, Err(DefinitionError::InvalidFault(_))));
+        }
+    }
+
     #[test]
     fn test_invalid_path() {
         let yaml = r"
@@ -0,0 +1,288 @@
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::panic)]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![forbid(unsafe_code)]
+
+//! Fault injection module
+//!
+//! Decides, per request, how long a twin waits before answering and
+//! whether it fails: dropping the connection, answering with an error
+//! status, or sending a malformed body. Decisions come from one random
+//! generator, seeded from the twin's faults when a seed is set, and every
+//! request draws the same amount from it whatever the outcome, so a seeded
+//! twin fails the same requests in the same way on every run.
+
+use std::time::Duration;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::definition::{DefinitionError, FaultConfig, Latency};
+
+/// How a request fails, if at all
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FaultOutcome {
+    /// Answer normally
+    #[default]
+    None,
+    /// Close the connection without a complete response
+    Drop,
+    /// Answer with this status instead
+    Error(u16),
+    /// Answer with a body that is not valid JSON
+    Malformed,
+}
+
+/// The faults chosen for one request
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Fault {
+    /// Delay before responding
+    pub latency: Duration,
+    pub outcome: FaultOutcome,
+}
+
+/// Runtime fault state, as reported and changed through `/_inspect/faults`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FaultStatus {
+    /// Whether faults are injected at all
+    pub enabled: bool,
+    /// Seed the generator was last seeded with
+    pub seed: Option<u64>,
+    /// Faults for endpoints and resources without their own
+    pub faults: Option<FaultConfig>,
+}
+
+/// A change to the runtime fault state; absent fields stay as they are
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FaultUpdate {
+    pub enabled: Option<bool>,
+    /// Reseed the generator, restarting the fault sequence
+    pub seed: Option<u64>,
+    /// Replace the twin-level faults
+    pub faults: Option<FaultConfig>,
+}
+
+/// Chooses the faults of each request
+#[derive(Debug)]
+pub struct FaultInjector {
+    enabled: bool,
+    seed: Option<u64>,
+    faults: Option<FaultConfig>,
+    rng: StdRng,
+}
+
+impl FaultInjector {
+    /// Injector for a twin's faults, seeded from them if they have a seed
+    #[must_use]
+    pub fn new(faults: Option<FaultConfig>) -> Self {
+        let seed = faults.as_ref().and_then(|f| f.seed);
+        Self {
+            enabled: true,
+            seed,
+            faults,
+            rng: rng(seed),
+        }
+    }
+
+    /// Choose the faults of a request, using `endpoint` faults over the
+    /// twin's when given
+    pub fn sample(&mut self, endpoint: Option<&FaultConfig>) -> Fault {
+        // Draw before checking anything so every request consumes the same
+        // amount of the sequence
+        let latency_roll: f64 = self.rng.gen();
+        let outcome_roll: f64 = self.rng.gen();
+
+        let Some(config) = endpoint.or(self.faults.as_ref()).filter(|_| self.enabled) else {
+            return Fault::default();
+        };
+
+        let latency = match config.latency {
+            None => Duration::ZERO,
+            Some(Latency::Fixed { fixed_ms }) => Duration::from_millis(fixed_ms),
+            Some(Latency::Random { min_ms, max_ms }) => {
+                let span = max_ms - min_ms;
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    clippy::cast_precision_loss,
+                    clippy::cast_sign_loss
+                )]
+                let extra = (latency_roll * span.saturating_add(1) as f64) as u64;
+                Duration::from_millis(min_ms + extra.min(span))
+            }
+        };
+
+        let outcome = if outcome_roll < config.drop_rate {
+            FaultOutcome::Drop
+        } else if outcome_roll < config.drop_rate + config.error_rate {
+            FaultOutcome::Error(config.error_status)
+        } else if outcome_roll < config.drop_rate + config.error_rate + config.malformed_rate {
+            FaultOutcome::Malformed
+        } else {
+            FaultOutcome::None
+        };
+
+        Fault { latency, outcome }
+    }
+
+    /// Current runtime fault state
+    #[must_use]
+    pub fn status(&self) -> FaultStatus {
+        FaultStatus {
+            enabled: self.enabled,
+            seed: self.seed,
+            faults: self.faults.clone(),
+        }
+    }
+
+    /// Apply `update`, reseeding when it sets a seed or new faults with one
+    ///
+    /// # Errors
+    /// Returns `DefinitionError::InvalidFault` if the new faults are
+    /// invalid, leaving the state unchanged.
+    pub fn update(&mut self, update: FaultUpdate) -> Result<(), DefinitionError> {
+        if let Some(faults) = &update.faults {
+            faults.validate().map_err(DefinitionError::InvalidFault)?;
+        }
+
+        if let Some(enabled) = update.enabled {
+            self.enabled = enabled;
+        }
+        let seed = update
+            .seed
+            .or_else(|| update.faults.as_ref().and_then(|f| f.seed));
+        if let Some(faults) = update.faults {
+            self.faults = Some(faults);
+        }
+        if seed.is_some() {
+            self.seed = seed;
+            self.rng = rng(seed);
+        }
+        Ok(())
+    }
+}
+
+fn rng(seed: Option<u64>) -> StdRng {
+    seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flaky() -> FaultConfig {
+        FaultConfig {
+            seed: Some(42),
+            latency: Some(Latency::Random {
+                min_ms: 10,
+                max_ms: 20,
+            }),
+            error_rate: 0.3,
+            error_status: 503,
+            drop_rate: 0.1,
+            malformed_rate: 0.1,
+        }
+    }
+
+    fn run(injector: &mut FaultInjector, n: usize) -> Vec<Fault> {
+        (0..n).map(|_| injector.sample(None)).collect()
+    }
+
+    #[test]
+    fn test_seeded_faults_are_reproducible() {
+        let first = run(&mut FaultInjector::new(Some(flaky())), 200);
+        let second = run(&mut FaultInjector::new(Some(flaky())), 200);
+
+        assert_eq!(first, second);
+        assert!(first
+            .iter()
+            .all(|f| (10..=20).contains(&f.latency.as_millis())));
+        for outcome in [
+            FaultOutcome::None,
+            FaultOutcome::Drop,
+            FaultOutcome::Error(503),
+            FaultOutcome::Malformed,
+        ] {
+            assert!(first.iter().any(|f| f.outcome == outcome));
+        }
+    }
+
+    #[test]
+    fn test_latency_spanning_every_millisecond() {
+        let mut injector = FaultInjector::new(Some(FaultConfig {
+            seed: Some(7),
+            latency: Some(Latency::Random {
+                min_ms: 0,
+                max_ms: u64::MAX,
+            }),
+            ..FaultConfig::default()
+        }));
+
+        let faults = run(&mut injector, 20);
+
+        assert!(faults.iter().any(|f| f.latency > Duration::ZERO));
+    }
+
+    #[test]
+    fn test_endpoint_faults_replace_the_twins() {
+        let mut injector = FaultInjector::new(Some(flaky()));
+        let steady = FaultConfig {
+            latency: Some(Latency::Fixed { fixed_ms: 5 }),
+            ..FaultConfig::default()
+        };
+
+        let fault = injector.sample(Some(&steady));
+
+        assert_eq!(
+            fault,
+            Fault {
+                latency: Duration::from_millis(5),
+                outcome: FaultOutcome::None
+            }
+        );
+    }
+
+    #[test]
+    fn test_disabling_and_reseeding() {
+        let mut injector = FaultInjector::new(Some(flaky()));
+        let expected = run(&mut FaultInjector::new(Some(flaky())), 20);
+
+        injector
+            .update(FaultUpdate {
+                enabled: Some(false),
+                ..FaultUpdate::default()
+            })
+            .expect("Should disable");
+        assert!(run(&mut injector, 20)
+            .iter()
+            .all(|f| *f == Fault::default()));
+
+        injector
+            .update(FaultUpdate {
+                enabled: Some(true),
+                seed: Some(42),
+                ..FaultUpdate::default()
+            })
+            .expect("Should reseed");
+        assert_eq!(run(&mut injector, 20), expected);
+    }
+
+    #[test]
+    fn test_invalid_update_is_rejected() {
+        let mut injector = FaultInjector::new(None);
+
+        let result = injector.update(FaultUpdate {
+            enabled: Some(false),
+            faults: Some(FaultConfig {
+                error_rate: 2.0,
+                ..FaultConfig::default()
+            }),
+            ..FaultUpdate::default()
+        });
+
+        assert!(result.is_err());
+        assert!(injector.status().enabled);
+    }
+}
@@ -13,12 +13,24 @@
 //! ## Architecture
 //!
 //! - **Definition**: Parse twin definitions from YAML
-//! - **State**: In-memory state management for request/response tracking
+//! - **Route**: Match request paths against endpoint path templates
+//! - **Matcher**: Select endpoint response variants by headers, query and body
+//! - **Fault**: Inject seedable latency, errors, drops and malformed bodies
+//! - **Template**: Render responses from request data
+//! - **State**: In-memory request/response tracking and CRUD resource items
 //! - **Server**: HTTP server using axum
 
 pub mod definition;
+pub mod fault;
+pub mod matcher;
+pub mod route;
 pub mod server;
 pub mod state;
+pub mod template;
 
-pub use definition::{Endpoint, EndpointResponse, TwinDefinition};
-pub use state::{InMemoryTwinState, RequestRecord, TwinState};
+pub use definition::{
+    Endpoint, EndpointResponse, FaultConfig, IdStrategy, Latency, RequestMatcher, Resource,
+    TwinDefinition, ValueMatcher, Variant,
+};
+pub use route::{PathParams, PathTemplate};
+pub use state::{InMemoryResourceState, InMemoryTwinState, InsertError, RequestRecord, TwinState};
@@ -0,0 +1,179 @@
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::panic)]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![forbid(unsafe_code)]
+
+//! Request matching module
+//!
+//! Compiles the [`RequestMatcher`] of each endpoint variant once, so
+//! choosing a response per request only evaluates conditions. Header names
+//! compare in any case; regexes match the value as text, so a number in the
+//! body can still be matched by pattern.
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{
+    definition::{RequestMatcher, ValueMatcher},
+    template::{body_field, RequestContext},
+};
+
+/// A compiled [`ValueMatcher`]
+#[derive(Debug, Clone)]
+enum Condition {
+    Regex(Regex),
+    Equals(Value),
+}
+
+impl Condition {
+    fn compile(matcher: &ValueMatcher) -> Result<Self, String> {
+        match matcher {
+            ValueMatcher::Regex { regex } => Regex::new(regex)
+                .map(Self::Regex)
+                .map_err(|e| format!("invalid regex '{regex}': {e}")),
+            ValueMatcher::Equals(value) => Ok(Self::Equals(value.clone())),
+        }
+    }
+
+    /// Whether a header or query value meets the condition
+    fn matches_text(&self, text: &str) -> bool {
+        match self {
+            Self::Regex(regex) => regex.is_match(text),
+            Self::Equals(Value::String(expected)) => expected == text,
+            Self::Equals(expected) => {
+                serde_json::from_str::<Value>(text).is_ok_and(|value| value == *expected)
+            }
+        }
+    }
+
+    /// Whether a JSON body value meets the condition
+    fn matches_value(&self, value: &Value) -> bool {
+        match (self, value) {
+            (Self::Equals(expected), _) => expected == value,
+            (Self::Regex(regex), Value::String(text)) => regex.is_match(text),
+            (Self::Regex(regex), Value::Number(_) | Value::Bool(_)) => {
+                regex.is_match(&value.to_string())
+            }
+            (Self::Regex(_), _) => false,
+        }
+    }
+}
+
+/// A [`RequestMatcher`] ready to test requests
+#[derive(Debug, Clone, Default)]
+pub struct CompiledMatcher {
+    headers: Vec<(String, Condition)>,
+    query: Vec<(String, Condition)>,
+    body: Vec<(String, Condition)>,
+}
+
+impl CompiledMatcher {
+    /// Compile `matcher`, checking its regexes and field names
+    ///
+    /// # Errors
+    /// Returns a description of the first invalid condition.
+    pub fn compile(matcher: &RequestMatcher) -> Result<Self, String> {
+        let mut compiled = Self::default();
+        for (name, value) in &matcher.headers {
+            let name = name.to_ascii_lowercase();
+            if compiled.headers.iter().any(|(other, _)| *other == name) {
+                return Err(format!("header '{name}' is matched more than once"));
+            }
+            compiled.headers.push((name, Condition::compile(value)?));
+        }
+        for (name, value) in &matcher.query {
+            compiled
+                .query
+                .push((name.clone(), Condition::compile(value)?));
+        }
+        for (path, value) in &matcher.body {
+            if path.split('.').any(str::is_empty) {
+                return Err(format!("body field '{path}' is not a dotted path"));
+            }
+            compiled
+                .body
+                .push((path.clone(), Condition::compile(value)?));
+        }
+        Ok(compiled)
+    }
+
+    /// Whether the request meets every condition
+    #[must_use]
+    pub fn matches(&self, ctx: &RequestContext) -> bool {
+        self.headers.iter().all(|(name, condition)| {
+            ctx.headers
+                .get(name)
+                .is_some_and(|value| condition.matches_text(value))
+        }) && self.query.iter().all(|(name, condition)| {
+            ctx.query
+                .get(name)
+                .is_some_and(|value| condition.matches_text(value))
+        }) && self.body.iter().all(|(path, condition)| {
+            body_field(&ctx.body, path).is_some_and(|value| condition.matches_value(value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::http::Uri;
+
+    use super::*;
+    use crate::route::PathParams;
+
+    fn request(authorization: &str, uri: &str, body: &str) -> RequestContext {
+        let headers: HashMap<String, String> =
+            [("authorization".to_string(), authorization.to_string())].into();
+        let uri: Uri = uri.parse().expect("Should parse uri");
+        RequestContext::new(PathParams::new(), &uri, &headers, Some(body))
+    }
+
+    fn matcher(yaml: &str) -> CompiledMatcher {
+        let matcher: RequestMatcher = serde_yaml::from_str(yaml).expect("Should parse matcher");
+        CompiledMatcher::compile(&matcher).expect("Should compile")
+    }
+
+    #[test]
+    fn test_all_conditions_must_hold() {
+        let matcher = matcher(
+            r"
+headers:
+  Authorization: { regex: '^Bearer sk_test_' }
+query:
+  page: 2
+body:
+  customer.email: ada@example.com
+",
+        );
+        let body = r#"{"customer":{"email":"ada@example.com"}}"#;
+
+        assert!(matcher.matches(&request("Bearer sk_test_1", "/c?page=2", body)));
+        assert!(!matcher.matches(&request("Bearer sk_live_1", "/c?page=2", body)));
+        assert!(!matcher.matches(&request("Bearer sk_test_1", "/c", body)));
+        assert!(!matcher.matches(&request("Bearer sk_test_1", "/c?page=2", "{}")));
+    }
+
+    #[test]
+    fn test_body_values_compare_as_json() {
+        let matcher = matcher("body:\n  amount: 100\n  currency: { regex: '^(usd|eur)$' }\n");
+
+        assert!(matcher.matches(&request("", "/", r#"{"amount":100,"currency":"eur"}"#)));
+        assert!(!matcher.matches(&request("", "/", r#"{"amount":"100","currency":"eur"}"#)));
+        assert!(!matcher.matches(&request("", "/", r#"{"amount":100,"currency":"gbp"}"#)));
+    }
+
+    #[test]
+    fn test_compile_rejects_invalid_conditions() {
+        let duplicate: RequestMatcher =
+            serde_yaml::from_str("headers:\n  X-Key: a\n  x-key: b\n").expect("Should parse");
+        let bad_path: RequestMatcher =
+            serde_yaml::from_str("body:\n  user..email: a\n").expect("Should parse");
+
+        assert!(CompiledMatcher::compile(&duplicate).is_err());
+        assert!(CompiledMatcher::compile(&bad_path).is_err());
+    }
+}
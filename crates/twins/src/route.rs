@@ -0,0 +1,241 @@
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::panic)]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![forbid(unsafe_code)]
+
+//! Path template matching module
+//!
+//! Endpoint paths are templates: a segment is a literal (`users`), a named
+//! parameter (`{id}`) matching any one segment, or a trailing wildcard (`*`
+//! or `{*rest}`) matching everything after it. When several templates match
+//! a request, the most specific wins: segment by segment from the left, a
+//! literal beats a parameter and a parameter beats a wildcard.
+
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use crate::definition::DefinitionError;
+
+/// Parameters captured from a request path, by name
+pub type PathParams = BTreeMap<String, String>;
+
+/// One segment of a path template
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Matches exactly this text
+    Literal(String),
+    /// Matches any one segment, captured under this name
+    Param(String),
+    /// Matches the rest of the path, captured under this name if any
+    Wildcard(Option<String>),
+}
+
+impl Segment {
+    /// Rank for specificity: higher is more specific
+    const fn rank(&self) -> u8 {
+        match self {
+            Self::Literal(_) => 2,
+            Self::Param(_) => 1,
+            Self::Wildcard(_) => 0,
+        }
+    }
+}
+
+/// A parsed endpoint path template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTemplate {
+    segments: Vec<Segment>,
+}
+
+impl PathTemplate {
+    /// Parse a path template such as `/users/{id}` or `/v1/*`
+    ///
+    /// # Errors
+    /// Returns `DefinitionError::InvalidEndpoint` if the path does not start
+    /// with `/`, a parameter is malformed or repeated, or a wildcard is not
+    /// the last segment.
+    pub fn parse(path: &str) -> Result<Self, DefinitionError> {
+        let Some(rest) = path.strip_prefix('/') else {
+            return Err(invalid(path, "path must start with /"));
+        };
+
+        let mut segments = Vec::new();
+        let mut names = Vec::new();
+        let raw: Vec<&str> = if rest.is_empty() {
+            Vec::new()
+        } else {
+            rest.split('/').collect()
+        };
+        for (i, part) in raw.iter().enumerate() {
+            let segment = parse_segment(path, part)?;
+            if matches!(segment, Segment::Wildcard(_)) && i + 1 != raw.len() {
+                return Err(invalid(path, "a wildcard must be the last segment"));
+            }
+            if let Segment::Param(name) | Segment::Wildcard(Some(name)) = &segment {
+                if names.contains(name) {
+                    return Err(invalid(path, &format!("parameter '{name}' is repeated")));
+                }
+                names.push(name.clone());
+            }
+            segments.push(segment);
+        }
+        Ok(Self { segments })
+    }
+
+    /// Match `path` against the template, capturing its parameters
+    #[must_use]
+    pub fn matches(&self, path: &str) -> Option<PathParams> {
+        let rest = path.strip_prefix('/')?;
+        let parts: Vec<&str> = if rest.is_empty() {
+            Vec::new()
+        } else {
+            rest.split('/').collect()
+        };
+
+        let mut params = PathParams::new();
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Wildcard(name) => {
+                    if let Some(name) = name {
+                        params.insert(name.clone(), parts.get(i..)?.join("/"));
+                    }
+                    return Some(params);
+                }
+                Segment::Literal(literal) => {
+                    if parts.get(i)? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    let part = parts.get(i).filter(|part| !part.is_empty())?;
+                    params.insert(name.clone(), (*part).to_string());
+                }
+            }
+        }
+        (parts.len() == self.segments.len()).then_some(params)
+    }
+
+    /// Whether the template has only literal segments
+    #[must_use]
+    pub fn is_literal(&self) -> bool {
+        self.segments
+            .iter()
+            .all(|segment| matches!(segment, Segment::Literal(_)))
+    }
+
+    /// Order templates by specificity, most specific greatest
+    ///
+    /// Segments compare from the left: literal over parameter over
+    /// wildcard. Where one template is a prefix of the other, the longer is
+    /// more specific.
+    #[must_use]
+    pub fn cmp_specificity(&self, other: &Self) -> Ordering {
+        self.segments
+            .iter()
+            .map(Segment::rank)
+            .cmp(other.segments.iter().map(Segment::rank))
+    }
+
+    /// Whether both templates match exactly the same paths
+    #[must_use]
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.segments.len() == other.segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(&other.segments)
+                .all(|pair| match pair {
+                    (Segment::Literal(a), Segment::Literal(b)) => a == b,
+                    (Segment::Param(_), Segment::Param(_))
+                    | (Segment::Wildcard(_), Segment::Wildcard(_)) => true,
+                    _ => false,
+                })
+    }
+}
+
+fn parse_segment(path: &str, part: &str) -> Result<Segment, DefinitionError> {
+    if part == "*" {
+        return Ok(Segment::Wildcard(None));
+    }
+    let Some(inner) = part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) else {
+        if part.contains(['{', '}', '*']) {
+            return Err(invalid(
+                path,
+                &format!("segment '{part}' mixes text with a parameter"),
+            ));
+        }
+        return Ok(Segment::Literal(part.to_string()));
+    };
+
+    let (wildcard, name) = inner
+        .strip_prefix('*')
+        .map_or((false, inner), |name| (true, name));
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(invalid(path, &format!("invalid parameter '{part}'")));
+    }
+    Ok(if wildcard {
+        Segment::Wildcard(Some(name.to_string()))
+    } else {
+        Segment::Param(name.to_string())
+    })
+}
+
+fn invalid(path: &str, reason: &str) -> DefinitionError {
+    DefinitionError::InvalidEndpoint(format!("{path}: {reason}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(path: &str) -> PathTemplate {
+        PathTemplate::parse(path).expect("Should parse")
+    }
+
+    #[test]
+    fn test_params_are_captured() {
+        let params = template("/users/{id}/posts/{post_id}")
+            .matches("/users/42/posts/7")
+            .expect("Should match");
+
+        assert_eq!(params.get("id").map(String::as_str), Some("42"));
+        assert_eq!(params.get("post_id").map(String::as_str), Some("7"));
+        assert!(template("/users/{id}").matches("/users/42/posts").is_none());
+        assert!(template("/users/{id}").matches("/users/").is_none());
+    }
+
+    #[test]
+    fn test_wildcards_match_the_rest() {
+        let params = template("/files/{*key}")
+            .matches("/files/a/b.txt")
+            .expect("Should match");
+
+        assert_eq!(params.get("key").map(String::as_str), Some("a/b.txt"));
+        assert!(template("/v1/*").matches("/v1/anything/at/all").is_some());
+        assert!(template("/v1/*").matches("/v2/users").is_none());
+    }
+
+    #[test]
+    fn test_literals_are_more_specific() {
+        let me = template("/users/me");
+        let param = template("/users/{id}");
+        let any = template("/users/*");
+
+        assert_eq!(me.cmp_specificity(&param), Ordering::Greater);
+        assert_eq!(param.cmp_specificity(&any), Ordering::Greater);
+        assert_eq!(
+            template("/v1/*").cmp_specificity(&template("/{version}/users")),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_invalid_templates_are_rejected() {
+        assert!(PathTemplate::parse("users").is_err());
+        assert!(PathTemplate::parse("/users/{}").is_err());
+        assert!(PathTemplate::parse("/users/user-{id}").is_err());
+        assert!(PathTemplate::parse("/users/{id}/{id}").is_err());
+        assert!(PathTemplate::parse("/*/users").is_err());
+    }
+}
@@ -13,19 +13,23 @@ use std::{collections::HashMap, sync::Arc};
 
 use axum::{
     body::Body,
-    extract::{Path, State},
+    extract::State,
     http::{header::HeaderName, HeaderMap, Method, Request, StatusCode},
     response::{IntoResponse, Response},
-    routing::{any, delete, get, head, options, patch, post, put},
+    routing::{any, get, post},
     Router,
 };
 use thiserror::Error;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tower_http::trace::TraceLayer;
 
 use crate::{
-    definition::{Endpoint, HttpMethod, TwinDefinition},
-    state::{InMemoryTwinState, RequestRecord, TwinState},
+    definition::{Endpoint, EndpointResponse, HttpMethod, Resource, TwinDefinition},
+    fault::{Fault, FaultInjector, FaultOutcome, FaultUpdate},
+    matcher::CompiledMatcher,
+    route::{PathParams, PathTemplate},
+    state::{InMemoryResourceState, InMemoryTwinState, InsertError, RequestRecord, TwinState},
+    template::{self, RequestContext},
 };
 
 /// Errors that can occur in the server
@@ -55,21 +59,90 @@ pub struct AppState {
     pub definition: TwinDefinition,
     /// Request/response state
     pub state: Arc<RwLock<InMemoryTwinState>>,
+    /// Items held by the definition's resources
+    pub resources: Arc<RwLock<InMemoryResourceState>>,
+    /// Fault injection, toggled through `/_inspect/faults`
+    pub faults: Arc<Mutex<FaultInjector>>,
+    /// Parsed path template and variant matchers of each endpoint, in
+    /// definition order
+    routes: Arc<[Option<Route>]>,
+}
+
+/// An endpoint's path template and compiled variant matchers
+#[derive(Debug)]
+struct Route {
+    template: PathTemplate,
+    variants: Vec<CompiledMatcher>,
+}
+
+impl Route {
+    fn compile(endpoint: &Endpoint) -> Option<Self> {
+        Some(Self {
+            template: PathTemplate::parse(&endpoint.path).ok()?,
+            variants: endpoint
+                .variants
+                .iter()
+                .map(|v| CompiledMatcher::compile(&v.when).ok())
+                .collect::<Option<_>>()?,
+        })
+    }
 }
 
 impl AppState {
     /// Create new application state
     #[must_use]
     pub fn new(definition: TwinDefinition) -> Self {
+        let routes = definition.endpoints.iter().map(Route::compile).collect();
+        let resources = InMemoryResourceState::seeded(&definition.resources);
+        let faults = FaultInjector::new(definition.faults.clone());
         Self {
             definition,
             state: Arc::new(RwLock::new(InMemoryTwinState::new())),
+            resources: Arc::new(RwLock::new(resources)),
+            faults: Arc::new(Mutex::new(faults)),
+            routes,
         }
     }
 
-    /// Find matching endpoint for request
+    /// Find the most specific endpoint matching the request, with the
+    /// parameters its path template captured
     #[must_use]
-    pub fn find_endpoint(&self, method: &Method, path: &str) -> Option<&Endpoint> {
+    pub fn find_endpoint(&self, method: &Method, path: &str) -> Option<(&Endpoint, PathParams)> {
+        let (index, params) = self.find_route(method, path)?;
+        Some((self.definition.endpoints.get(index)?, params))
+    }
+
+    /// The response of the first variant of endpoint `index` whose matcher
+    /// holds for the request, or the endpoint's fallback response
+    fn select_response(&self, index: usize, ctx: &RequestContext) -> Option<&EndpointResponse> {
+        let endpoint = self.definition.endpoints.get(index)?;
+        let route = self.routes.get(index)?.as_ref()?;
+        Some(
+            route
+                .variants
+                .iter()
+                .zip(&endpoint.variants)
+                .find(|(matcher, _)| matcher.matches(ctx))
+                .map_or(&endpoint.response, |(_, variant)| &variant.response),
+        )
+    }
+
+    /// Choose the faults of a request to endpoint `index`, or to a
+    /// resource without one, and wait out their latency
+    async fn inject_faults(&self, index: Option<usize>) -> Fault {
+        let endpoint_faults = index
+            .and_then(|i| self.definition.endpoints.get(i))
+            .and_then(|e| e.faults.as_ref());
+        let fault = self.faults.lock().await.sample(endpoint_faults);
+        if !fault.latency.is_zero() {
+            tokio::time::sleep(fault.latency).await;
+        }
+        fault
+    }
+
+    /// Index of the most specific endpoint matching the request, with the
+    /// parameters its path template captured
+    fn find_route(&self, method: &Method, path: &str) -> Option<(usize, PathParams)> {
         let http_method = match method.as_str() {
             "GET" => HttpMethod::GET,
             "POST" => HttpMethod::POST,
@@ -84,11 +157,141 @@ impl AppState {
         self.definition
             .endpoints
             .iter()
-            .find(|e| e.method == http_method && e.path == path)
+            .zip(self.routes.iter())
+            .enumerate()
+            .filter(|(_, (e, _))| e.method == http_method)
+            .filter_map(|(i, (_, route))| {
+                let template = &route.as_ref()?.template;
+                template.matches(path).map(|params| (i, template, params))
+            })
+            .max_by(|(_, a, _), (_, b, _)| a.cmp_specificity(b))
+            .map(|(i, _, params)| (i, params))
     }
+
+    /// Find the resource collection or item a request path addresses
+    #[must_use]
+    pub fn find_resource(&self, path: &str) -> Option<ResourceRoute<'_>> {
+        self.definition.resources.iter().find_map(|resource| {
+            let rest = path.strip_prefix(resource.path.as_str())?;
+            if rest.is_empty() {
+                return Some(ResourceRoute::Collection(resource));
+            }
+            let id = rest.strip_prefix('/')?;
+            (!id.is_empty() && !id.contains('/'))
+                .then(|| ResourceRoute::Item(resource, id.to_string()))
+        })
+    }
+
+    /// Apply a request to a resource, returning the status and JSON body
+    async fn serve_resource(
+        &self,
+        method: &Method,
+        route: &ResourceRoute<'_>,
+        body: Option<&str>,
+    ) -> (StatusCode, Option<serde_json::Value>) {
+        let mut resources = self.resources.write().await;
+        let (status, body) = match (route, method.as_str()) {
+            (ResourceRoute::Collection(resource), "GET") => (
+                StatusCode::OK,
+                Ok(resources.list(resource).into_iter().collect()),
+            ),
+            (ResourceRoute::Collection(resource), "POST") => match request_object(body) {
+                Ok(item) => match resources.insert(resource, item) {
+                    Ok((updated, item)) => {
+                        *resources = updated;
+                        (StatusCode::CREATED, Ok(item))
+                    }
+                    Err(InsertError::InvalidId) => (
+                        StatusCode::BAD_REQUEST,
+                        Err(format!(
+                            "{} must be a non-empty string or a number",
+                            resource.id_field
+                        )),
+                    ),
+                    Err(InsertError::Duplicate) => (
+                        StatusCode::CONFLICT,
+                        Err(format!(
+                            "{} already has an item with that id",
+                            resource.name
+                        )),
+                    ),
+                },
+                Err(e) => (StatusCode::BAD_REQUEST, Err(e)),
+            },
+            (ResourceRoute::Item(resource, id), "GET") => resources.get(resource, id).map_or_else(
+                || not_found(resource, id),
+                |item| (StatusCode::OK, Ok(item)),
+            ),
+            (ResourceRoute::Item(resource, id), "PUT" | "PATCH") => {
+                let item = request_object(body).map(|mut item| {
+                    if method == Method::PATCH {
+                        if let Some(serde_json::Value::Object(mut existing)) =
+                            resources.get(resource, id)
+                        {
+                            existing.append(&mut item);
+                            item = existing;
+                        }
+                    }
+                    item
+                });
+                match item {
+                    Ok(item) => match resources.replace(resource, id, item) {
+                        Some((updated, item)) => {
+                            *resources = updated;
+                            (StatusCode::OK, Ok(item))
+                        }
+                        None => not_found(resource, id),
+                    },
+                    Err(e) => (StatusCode::BAD_REQUEST, Err(e)),
+                }
+            }
+            (ResourceRoute::Item(resource, id), "DELETE") => match resources.remove(resource, id) {
+                Some(updated) => {
+                    *resources = updated;
+                    return (StatusCode::NO_CONTENT, None);
+                }
+                None => not_found(resource, id),
+            },
+            _ => (
+                StatusCode::METHOD_NOT_ALLOWED,
+                Err(format!("{method} is not supported here")),
+            ),
+        };
+        drop(resources);
+
+        let body = body.unwrap_or_else(|error| serde_json::json!({ "error": error }));
+        (status, Some(body))
+    }
+}
+
+/// A resource collection or one of its items, addressed by a request
+#[derive(Debug, Clone)]
+pub enum ResourceRoute<'a> {
+    /// The collection path itself
+    Collection(&'a Resource),
+    /// An item path, with the item id
+    Item(&'a Resource, String),
+}
+
+/// A request body parsed as a JSON object
+fn request_object(
+    body: Option<&str>,
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    match serde_json::from_str(body.unwrap_or_default()) {
+        Ok(serde_json::Value::Object(item)) => Ok(item),
+        Ok(_) => Err("Request body must be a JSON object".to_string()),
+        Err(e) => Err(format!("Invalid JSON body: {e}")),
+    }
+}
+
+fn not_found(resource: &Resource, id: &str) -> (StatusCode, Result<serde_json::Value, String>) {
+    (
+        StatusCode::NOT_FOUND,
+        Err(format!("{} has no item '{id}'", resource.name)),
+    )
 }
 
-/// Handler for twin endpoints
+/// Handler for twin endpoints and resources
 async fn twin_handler(
     State(state): State<AppState>,
     method: Method,
@@ -96,16 +299,30 @@ async fn twin_handler(
     request: Request<Body>,
 ) -> Response {
     // Get path from request URI
-    let path = request.uri().path().to_string();
+    let uri = request.uri().clone();
+    let path = uri.path().to_string();
 
-    // Find matching endpoint
-    let Some(endpoint) = state.find_endpoint(&method, &path) else {
+    // Find matching endpoint, falling back to resources
+    let endpoint = state.find_route(&method, &path);
+    let resource = if endpoint.is_none() {
+        state.find_resource(&path)
+    } else {
+        None
+    };
+    if endpoint.is_none() && resource.is_none() {
         return (
             StatusCode::NOT_FOUND,
             format!("No endpoint found for {method} {path}"),
         )
             .into_response();
-    };
+    }
+
+    let fault = state
+        .inject_faults(endpoint.as_ref().map(|(index, _)| *index))
+        .await;
+    if fault.outcome == FaultOutcome::Drop {
+        return dropped_connection();
+    }
 
     // Extract request body
     let body_bytes = match axum::body::to_bytes(request.into_body(), 1024 * 1024).await {
@@ -132,20 +349,49 @@ async fn twin_handler(
         .collect();
 
     // Build response
-    let response = &endpoint.response;
-    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::OK);
+    let (status, response_headers, response_body) =
+        if let FaultOutcome::Error(status) = fault.outcome {
+            let body = serde_json::json!({ "error": "Injected fault" });
+            (status, HashMap::new(), Some(body))
+        } else {
+            match (endpoint, resource) {
+                (Some((index, params)), _) => {
+                    let ctx = RequestContext::new(
+                        params,
+                        &uri,
+                        &request_headers,
+                        request_body_str.as_deref(),
+                    );
+                    let Some(response) = state.select_response(index, &ctx) else {
+                        return StatusCode::NOT_FOUND.into_response();
+                    };
+                    render_response(response, &ctx)
+                }
+                (None, Some(route)) => {
+                    let (status, body) = state
+                        .serve_resource(&method, &route, request_body_str.as_deref())
+                        .await;
+                    (status.as_u16(), HashMap::new(), body)
+                }
+                (None, None) => return StatusCode::NOT_FOUND.into_response(),
+            }
+        };
+    let mut response_body = response_body.and_then(|body| serde_json::to_string(&body).ok());
+    if fault.outcome == FaultOutcome::Malformed {
+        response_body = Some(malformed(response_body.as_deref().unwrap_or_default()));
+    }
 
-    let mut builder = Response::builder().status(status);
+    let mut builder =
+        Response::builder().status(StatusCode::from_u16(status).unwrap_or(StatusCode::OK));
 
     // Add response headers
-    for (key, value) in &response.headers {
+    for (key, value) in &response_headers {
         if let Ok(name) = HeaderName::from_bytes(key.as_bytes()) {
             builder = builder.header(&name, value.as_str());
         }
     }
 
     // Add response body
-    let response_body = serde_json::to_string(&response.body).ok();
     if response_body.is_some() {
         builder = builder.header("content-type", "application/json");
     }
@@ -156,8 +402,8 @@ async fn twin_handler(
         path,
         request_headers,
         request_body_str,
-        response.status,
-        response.headers.clone(),
+        status,
+        response_headers,
         response_body.clone(),
     );
 
@@ -173,12 +419,40 @@ async fn twin_handler(
         .unwrap_or_else(|_| (StatusCode::INTERNAL_SERVER_ERROR, Body::empty()).into_response())
 }
 
-/// Handler for 404 Not Found
-async fn not_found_handler(method: Method, Path(path): Path<String>) -> impl IntoResponse {
-    (
-        StatusCode::NOT_FOUND,
-        format!("No endpoint found for {method} {path}"),
-    )
+/// Render an endpoint response's templated headers and body for a request
+fn render_response(
+    response: &EndpointResponse,
+    ctx: &RequestContext,
+) -> (u16, HashMap<String, String>, Option<serde_json::Value>) {
+    let headers = response
+        .headers
+        .iter()
+        .map(|(k, v)| (k.clone(), template::render_str(v, ctx)))
+        .collect();
+    let body = template::render_value(&response.body, ctx);
+    (response.status, headers, Some(body))
+}
+
+/// A response whose body fails mid-stream, so the connection closes
+/// without a complete response
+fn dropped_connection() -> Response {
+    let body = futures_util::stream::once(async {
+        Err::<axum::body::Bytes, _>(std::io::Error::new(
+            std::io::ErrorKind::ConnectionReset,
+            "Injected connection drop",
+        ))
+    });
+    Response::new(Body::from_stream(body))
+}
+
+/// `body` cut short so it no longer parses as JSON
+fn malformed(body: &str) -> String {
+    let cut = body
+        .char_indices()
+        .map(|(i, _)| i)
+        .nth(body.chars().count() / 2)
+        .unwrap_or(0);
+    format!("{}{{\"", &body[..cut])
 }
 
 /// Handler for inspection endpoint - GET /_inspect/state
@@ -190,12 +464,27 @@ async fn inspect_state(State(state): State<AppState>) -> impl IntoResponse {
         records = state_guard.get_records();
         count = state_guard.record_count();
     }
+    let resources: serde_json::Map<String, serde_json::Value> = {
+        let resources_guard = state.resources.read().await;
+        state
+            .definition
+            .resources
+            .iter()
+            .map(|r| {
+                (
+                    r.name.clone(),
+                    resources_guard.list(r).into_iter().collect(),
+                )
+            })
+            .collect()
+    };
 
     let response = serde_json::json!({
         "twin": state.definition.name,
         "port": state.definition.port,
         "request_count": count,
-        "requests": records
+        "requests": records,
+        "resources": resources
     });
 
     (
@@ -224,44 +513,73 @@ async fn inspect_requests(State(state): State<AppState>) -> impl IntoResponse {
 }
 
 /// Handler for clearing state - POST /_inspect/clear
+///
+/// Resources go back to their seed items.
 async fn clear_state(State(state): State<AppState>) -> impl IntoResponse {
     let mut state_guard = state.state.write().await;
     *state_guard = InMemoryTwinState::new();
     drop(state_guard);
 
+    let mut resources_guard = state.resources.write().await;
+    *resources_guard = InMemoryResourceState::seeded(&state.definition.resources);
+    drop(resources_guard);
+
     (StatusCode::OK, r#"{"status":"cleared"}"#)
 }
 
+/// Handler for fault inspection - GET /_inspect/faults
+async fn inspect_faults(State(state): State<AppState>) -> impl IntoResponse {
+    let status = state.faults.lock().await.status();
+    (
+        StatusCode::OK,
+        serde_json::to_string(&status).unwrap_or_default(),
+    )
+}
+
+/// Handler for changing faults at runtime - POST /_inspect/faults
+///
+/// Takes a [`FaultUpdate`], e.g. `{"enabled": false}` to switch faults off
+/// or `{"seed": 7}` to restart a reproducible sequence, and returns the new
+/// fault state.
+async fn update_faults(State(state): State<AppState>, body: String) -> Response {
+    let update = match serde_json::from_str::<FaultUpdate>(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid fault update: {e}"),
+            )
+                .into_response();
+        }
+    };
+
+    let mut faults = state.faults.lock().await;
+    if let Err(e) = faults.update(update) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+    let status = faults.status();
+    drop(faults);
+
+    (
+        StatusCode::OK,
+        serde_json::to_string(&status).unwrap_or_default(),
+    )
+        .into_response()
+}
+
 /// Build the router for the twin server
 pub fn build_router(definition: TwinDefinition) -> Router {
     let app_state = AppState::new(definition);
 
-    // Build endpoint routes dynamically
-    let mut router = Router::new()
+    // Twin endpoints are path templates, so every request outside the
+    // inspection endpoints goes to the twin handler to be matched there
+    Router::new()
         // Inspection endpoints
         .route("/_inspect/state", get(inspect_state))
         .route("/_inspect/requests", get(inspect_requests))
-        .route("/_inspect/clear", post(clear_state));
-
-    // Add twin endpoints
-    for endpoint in &app_state.definition.endpoints {
-        let path = endpoint.path.clone();
-        let method = endpoint.method;
-
-        router = match method {
-            HttpMethod::GET => router.route(&path, get(twin_handler)),
-            HttpMethod::POST => router.route(&path, post(twin_handler)),
-            HttpMethod::PUT => router.route(&path, put(twin_handler)),
-            HttpMethod::DELETE => router.route(&path, delete(twin_handler)),
-            HttpMethod::PATCH => router.route(&path, patch(twin_handler)),
-            HttpMethod::OPTIONS => router.route(&path, options(twin_handler)),
-            HttpMethod::HEAD => router.route(&path, head(twin_handler)),
-        };
-    }
-
-    // Add fallback for 404
-    router
-        .fallback(any(not_found_handler))
+        .route("/_inspect/clear", post(clear_state))
+        .route("/_inspect/faults", get(inspect_faults).post(update_faults))
+        .fallback(any(twin_handler))
         .with_state(app_state)
         .layer(TraceLayer::new_for_http())
 }
@@ -271,15 +589,33 @@ pub fn build_router(definition: TwinDefinition) -> Router {
 /// # Errors
 /// Returns `ServerError` if the server fails to start.
 pub async fn start_server(definition: TwinDefinition) -> Result<(), ServerError> {
-    let port = definition.port;
-    let router = build_router(definition);
-
-    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], port));
+    let addr = std::net::SocketAddr::from(([127, 0, 0, 1], definition.port));
 
     let listener = tokio::net::TcpListener::bind(addr)
         .await
         .map_err(|e| ServerError::StartupError(e.to_string()))?;
 
+    serve(listener, definition).await
+}
+
+/// Serve a twin on an already bound listener
+///
+/// Lets callers bind port 0 and learn the free port before serving. The
+/// definition's port is replaced with the listener's so inspection reports
+/// where the twin actually runs.
+///
+/// # Errors
+/// Returns `ServerError` if the listener address is unavailable or serving fails.
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    mut definition: TwinDefinition,
+) -> Result<(), ServerError> {
+    let addr = listener
+        .local_addr()
+        .map_err(|e| ServerError::StartupError(e.to_string()))?;
+    definition.port = addr.port();
+    let router = build_router(definition);
+
     tracing::info!("Starting twin server on http://{addr}");
 
     axum::serve(listener, router)
@@ -330,4 +666,231 @@ endpoints:
         let endpoint = state.find_endpoint(&Method::GET, "/nonexistent");
         assert!(endpoint.is_none());
     }
+
+    #[tokio::test]
+    async fn test_resource_create_then_read() {
+        let yaml = r"
+name: users
+port: 3003
+endpoints:
+  - path: /users/me
+    method: GET
+    response:
+      status: 200
+resources:
+  - name: users
+    path: /users
+    id: sequence
+";
+        let definition = TwinDefinition::from_yaml(yaml).expect("Should parse");
+        let state = AppState::new(definition);
+        let users = state.find_resource("/users").expect("Should route");
+        assert!(state.find_resource("/users/1/posts").is_none());
+
+        let (status, created) = state
+            .serve_resource(
+                &Method::POST,
+                &users,
+                Some(r#"{"email":"ada@example.com"}"#),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            created,
+            Some(serde_json::json!({"id": 1, "email": "ada@example.com"}))
+        );
+
+        let item = state.find_resource("/users/1").expect("Should route");
+        let (status, read) = state.serve_resource(&Method::GET, &item, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(read, created);
+
+        let (status, _) = state
+            .serve_resource(&Method::PATCH, &item, Some(r#"{"name":"Ada"}"#))
+            .await;
+        let (_, list) = state.serve_resource(&Method::GET, &users, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            list,
+            Some(serde_json::json!([{"id": 1, "email": "ada@example.com", "name": "Ada"}]))
+        );
+
+        let (status, _) = state.serve_resource(&Method::DELETE, &item, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = state.serve_resource(&Method::GET, &item, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = state
+            .serve_resource(&Method::POST, &users, Some("[]"))
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_resource_create_rejects_bad_and_taken_ids() {
+        let yaml = r"
+name: users
+port: 3003
+resources:
+  - name: users
+    path: /users
+    seed:
+      - id: 1
+";
+        let definition = TwinDefinition::from_yaml(yaml).expect("Should parse");
+        let state = AppState::new(definition);
+        let users = state.find_resource("/users").expect("Should route");
+
+        for body in [r#"{"id":null}"#, r#"{"id":""}"#, r#"{"id":{"n":2}}"#] {
+            let (status, _) = state
+                .serve_resource(&Method::POST, &users, Some(body))
+                .await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+        }
+        let (status, _) = state
+            .serve_resource(&Method::POST, &users, Some(r#"{"id":1}"#))
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_faults_are_injected_and_toggled() {
+        let yaml = r"
+name: flaky
+port: 3003
+faults:
+  seed: 1
+  error_rate: 1.0
+  error_status: 503
+endpoints:
+  - path: /health
+    method: GET
+    response:
+      status: 200
+  - path: /status
+    method: GET
+    faults:
+      malformed_rate: 1.0
+    response:
+      status: 200
+      body:
+        ok: true
+";
+        let definition = TwinDefinition::from_yaml(yaml).expect("Should parse");
+        let state = AppState::new(definition);
+        let get = |uri: &str| {
+            let request = Request::builder()
+                .uri(uri)
+                .body(Body::empty())
+                .expect("Should build request");
+            twin_handler(State(state.clone()), Method::GET, HeaderMap::new(), request)
+        };
+        let body = |response: Response| async {
+            let bytes = axum::body::to_bytes(response.into_body(), 1024)
+                .await
+                .expect("Should read body");
+            String::from_utf8(bytes.to_vec()).expect("Should be UTF-8")
+        };
+
+        assert_eq!(
+            get("/health").await.status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        let garbled = get("/status").await;
+        assert_eq!(garbled.status(), StatusCode::OK);
+        assert!(serde_json::from_str::<serde_json::Value>(&body(garbled).await).is_err());
+
+        let toggled = update_faults(State(state.clone()), r#"{"enabled":false}"#.to_string()).await;
+        assert!(body(toggled).await.contains(r#""enabled":false"#));
+        assert_eq!(get("/health").await.status(), StatusCode::OK);
+
+        let rejected = update_faults(
+            State(state.clone()),
+            r#"{"faults":{"drop_rate":3}}"#.to_string(),
+        )
+        .await;
+        assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_variants_select_response_by_request() {
+        let yaml = r"
+name: stripe
+port: 3003
+endpoints:
+  - path: /v1/charges
+    method: POST
+    variants:
+      - when:
+          headers:
+            authorization: Bearer sk_test_good
+          body:
+            amount: { regex: '^[1-9][0-9]*$' }
+        response:
+          status: 200
+      - when:
+          headers:
+            authorization: Bearer sk_test_good
+        response:
+          status: 400
+    response:
+      status: 401
+";
+        let definition = TwinDefinition::from_yaml(yaml).expect("Should parse");
+        let state = AppState::new(definition);
+        let (index, _) = state
+            .find_route(&Method::POST, "/v1/charges")
+            .expect("Should route");
+        let status = |authorization: &str, body: &str| {
+            let headers: HashMap<String, String> =
+                [("authorization".to_string(), authorization.to_string())].into();
+            let uri = "/v1/charges".parse().expect("Should parse uri");
+            let ctx = RequestContext::new(PathParams::new(), &uri, &headers, Some(body));
+            state.select_response(index, &ctx).map(|r| r.status)
+        };
+
+        assert_eq!(
+            status("Bearer sk_test_good", r#"{"amount":500}"#),
+            Some(200)
+        );
+        assert_eq!(status("Bearer sk_test_good", r#"{"amount":0}"#), Some(400));
+        assert_eq!(status("Bearer sk_test_bad", r#"{"amount":500}"#), Some(401));
+    }
+
+    #[test]
+    fn test_find_endpoint_prefers_most_specific_route() {
+        let yaml = r"
+name: users
+port: 3003
+endpoints:
+  - path: /v1/*
+    method: GET
+    response:
+      status: 404
+  - path: /v1/users/{id}
+    method: GET
+    response:
+      status: 200
+      body:
+        id: '{{request.path.id}}'
+  - path: /v1/users/me
+    method: GET
+    response:
+      status: 200
+";
+        let definition = TwinDefinition::from_yaml(yaml).expect("Should parse");
+        let state = AppState::new(definition);
+        let find = |path| {
+            state
+                .find_endpoint(&Method::GET, path)
+                .map(|(e, params)| (e.path.as_str(), params.get("id").cloned()))
+        };
+
+        assert_eq!(find("/v1/users/me"), Some(("/v1/users/me", None)));
+        assert_eq!(
+            find("/v1/users/42"),
+            Some(("/v1/users/{id}", Some("42".to_string())))
+        );
+        assert_eq!(find("/v1/orders/7"), Some(("/v1/*", None)));
+        assert_eq!(find("/v2/users"), None);
+    }
 }
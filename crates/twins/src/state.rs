@@ -7,15 +7,18 @@
 
 //! State management module for twin runtime
 //!
-//! Provides in-memory state tracking for requests and responses.
+//! Provides in-memory state tracking for requests and responses, and the
+//! items held by a twin's CRUD resources.
 
 use std::collections::HashMap;
 
 use chrono::{DateTime, Utc};
-use im::Vector;
+use im::{OrdMap, Vector};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::definition::{id_string, IdStrategy, Resource};
+
 /// A recorded request/response pair
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RequestRecord {
@@ -126,6 +129,161 @@ impl TwinState for InMemoryTwinState {
     }
 }
 
+/// Items of one resource collection, in creation order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResourceCollection {
+    items: Vector<serde_json::Value>,
+    /// Next id a sequence resource hands out
+    next_id: u64,
+}
+
+/// Why an item could not be added to a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertError {
+    /// The item's id is neither a non-empty string nor a number
+    InvalidId,
+    /// An item with the same id already exists
+    Duplicate,
+}
+
+/// In-memory items of every resource, keyed by resource name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InMemoryResourceState {
+    collections: OrdMap<String, ResourceCollection>,
+}
+
+impl InMemoryResourceState {
+    /// State holding each resource's seed items
+    #[must_use]
+    pub fn seeded(resources: &[Resource]) -> Self {
+        let collections = resources
+            .iter()
+            .map(|resource| {
+                let next_id = resource
+                    .seed
+                    .iter()
+                    .filter_map(|item| item.get(&resource.id_field)?.as_u64())
+                    .max()
+                    .unwrap_or(0)
+                    + 1;
+                let collection = ResourceCollection {
+                    items: resource.seed.iter().cloned().collect(),
+                    next_id,
+                };
+                (resource.name.clone(), collection)
+            })
+            .collect();
+        Self { collections }
+    }
+
+    /// Every item of `resource`, oldest first
+    #[must_use]
+    pub fn list(&self, resource: &Resource) -> Vector<serde_json::Value> {
+        self.collections
+            .get(&resource.name)
+            .map(|c| c.items.clone())
+            .unwrap_or_default()
+    }
+
+    /// The item of `resource` with `id`
+    #[must_use]
+    pub fn get(&self, resource: &Resource, id: &str) -> Option<serde_json::Value> {
+        let collection = self.collections.get(&resource.name)?;
+        position(collection, resource, id).map(|i| collection.items[i].clone())
+    }
+
+    /// Add `item` to `resource`, minting an id if it has none
+    ///
+    /// Returns the new state and the stored item.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InsertError::InvalidId`] if the item brings an id that is
+    /// neither a non-empty string nor a number, and
+    /// [`InsertError::Duplicate`] if an item with the same id exists.
+    pub fn insert(
+        &self,
+        resource: &Resource,
+        mut item: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<(Self, serde_json::Value), InsertError> {
+        let mut collection = self
+            .collections
+            .get(&resource.name)
+            .cloned()
+            .unwrap_or_default();
+        if !item.contains_key(&resource.id_field) {
+            let id = match resource.id {
+                IdStrategy::Uuid => serde_json::Value::from(Uuid::new_v4().to_string()),
+                IdStrategy::Sequence => loop {
+                    let id = collection.next_id;
+                    collection.next_id += 1;
+                    if position(&collection, resource, &id.to_string()).is_none() {
+                        break serde_json::Value::from(id);
+                    }
+                },
+            };
+            item.insert(resource.id_field.clone(), id);
+        }
+        let id = item
+            .get(&resource.id_field)
+            .and_then(id_string)
+            .ok_or(InsertError::InvalidId)?;
+        if position(&collection, resource, &id).is_some() {
+            return Err(InsertError::Duplicate);
+        }
+
+        let item = serde_json::Value::Object(item);
+        collection.items.push_back(item.clone());
+        Ok((self.with_collection(resource, collection), item))
+    }
+
+    /// Replace the item of `resource` with `id` by `item`, keeping its id
+    ///
+    /// Returns `None` if there is no such item.
+    #[must_use]
+    pub fn replace(
+        &self,
+        resource: &Resource,
+        id: &str,
+        mut item: serde_json::Map<String, serde_json::Value>,
+    ) -> Option<(Self, serde_json::Value)> {
+        let mut collection = self.collections.get(&resource.name)?.clone();
+        let i = position(&collection, resource, id)?;
+        if let Some(existing) = collection.items[i].get(&resource.id_field) {
+            item.insert(resource.id_field.clone(), existing.clone());
+        }
+        let item = serde_json::Value::Object(item);
+        collection.items.set(i, item.clone());
+        Some((self.with_collection(resource, collection), item))
+    }
+
+    /// Remove the item of `resource` with `id`
+    ///
+    /// Returns `None` if there is no such item.
+    #[must_use]
+    pub fn remove(&self, resource: &Resource, id: &str) -> Option<Self> {
+        let mut collection = self.collections.get(&resource.name)?.clone();
+        let i = position(&collection, resource, id)?;
+        collection.items.remove(i);
+        Some(self.with_collection(resource, collection))
+    }
+
+    fn with_collection(&self, resource: &Resource, collection: ResourceCollection) -> Self {
+        Self {
+            collections: self.collections.update(resource.name.clone(), collection),
+        }
+    }
+}
+
+/// Index of the item with `id` in `collection`
+fn position(collection: &ResourceCollection, resource: &Resource, id: &str) -> Option<usize> {
+    collection.items.iter().position(|item| {
+        item.get(&resource.id_field)
+            .and_then(id_string)
+            .is_some_and(|item_id| item_id == id)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,4 +320,80 @@ mod tests {
         let cleared = state_with_record.clear();
         assert_eq!(cleared.record_count(), 0);
     }
+
+    fn users() -> Resource {
+        Resource {
+            name: "users".to_string(),
+            path: "/users".to_string(),
+            id: IdStrategy::Sequence,
+            id_field: "id".to_string(),
+            seed: vec![serde_json::json!({"id": 1, "email": "ada@example.com"})],
+        }
+    }
+
+    fn object(value: serde_json::Value) -> serde_json::Map<String, serde_json::Value> {
+        match value {
+            serde_json::Value::Object(map) => map,
+            _ => serde_json::Map::new(),
+        }
+    }
+
+    #[test]
+    fn test_resource_crud() {
+        let users = users();
+        let state = InMemoryResourceState::seeded(std::slice::from_ref(&users));
+
+        let (state, created) = state
+            .insert(
+                &users,
+                object(serde_json::json!({"email": "bob@example.com"})),
+            )
+            .expect("Should insert");
+        assert_eq!(created["id"], 2);
+        assert_eq!(state.get(&users, "2"), Some(created));
+
+        let (state, replaced) = state
+            .replace(
+                &users,
+                "1",
+                object(serde_json::json!({"email": "ada@new.com"})),
+            )
+            .expect("Should replace");
+        assert_eq!(
+            replaced,
+            serde_json::json!({"id": 1, "email": "ada@new.com"})
+        );
+
+        let state = state.remove(&users, "2").expect("Should remove");
+        assert_eq!(state.list(&users).len(), 1);
+        assert!(state.remove(&users, "2").is_none());
+    }
+
+    #[test]
+    fn test_resource_rejects_duplicate_ids() {
+        let users = users();
+        let state = InMemoryResourceState::seeded(std::slice::from_ref(&users));
+
+        let duplicate = state.insert(&users, object(serde_json::json!({"id": 1})));
+
+        assert_eq!(duplicate.err(), Some(InsertError::Duplicate));
+        assert_eq!(state.list(&users).len(), 1);
+    }
+
+    #[test]
+    fn test_resource_rejects_unusable_ids() {
+        let users = users();
+        let state = InMemoryResourceState::seeded(std::slice::from_ref(&users));
+
+        for id in [
+            serde_json::Value::Null,
+            serde_json::json!(""),
+            serde_json::json!({"nested": 1}),
+        ] {
+            let result = state.insert(&users, object(serde_json::json!({"id": id})));
+
+            assert_eq!(result.err(), Some(InsertError::InvalidId));
+        }
+        assert_eq!(state.list(&users).len(), 1);
+    }
 }
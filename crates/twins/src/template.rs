@@ -0,0 +1,242 @@
+#![deny(clippy::unwrap_used)]
+#![deny(clippy::expect_used)]
+#![deny(clippy::panic)]
+#![warn(clippy::pedantic)]
+#![warn(clippy::nursery)]
+#![forbid(unsafe_code)]
+
+//! Response template rendering module
+//!
+//! String values in a response body and header values may reference the
+//! request with `{{ ... }}` placeholders:
+//!
+//! - `{{request.path.id}}` - a parameter captured by the path template
+//! - `{{request.query.page}}` - a query parameter
+//! - `{{request.headers.x-api-key}}` - a request header, any case
+//! - `{{request.body.user.email}}` - a field of the JSON body, or `{{request.body}}` for all of it
+//! - `{{uuid}}` - a UUID generated once per request
+//! - `{{now}}` - the time of the request, RFC 3339
+//!
+//! A body string that is exactly one placeholder takes the referenced JSON
+//! value as is, so `{{request.body.count}}` stays a number. Placeholders
+//! that name nothing known are left as written.
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{extract::Query, http::Uri};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::route::PathParams;
+
+/// What a response template can reference about the request
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    /// Parameters captured from the request path
+    pub path: PathParams,
+    /// Query parameters
+    pub query: BTreeMap<String, String>,
+    /// Request headers, by lowercase name
+    pub headers: HashMap<String, String>,
+    /// Request body: parsed JSON, a string if it is not JSON, or null
+    pub body: Value,
+    /// UUID minted for this request
+    pub uuid: String,
+    /// When the request arrived
+    pub now: DateTime<Utc>,
+}
+
+impl RequestContext {
+    /// Context for a request arriving now
+    #[must_use]
+    pub fn new(
+        path: PathParams,
+        uri: &Uri,
+        headers: &HashMap<String, String>,
+        body: Option<&str>,
+    ) -> Self {
+        let query = Query::<BTreeMap<String, String>>::try_from_uri(uri)
+            .map(|Query(query)| query)
+            .unwrap_or_default();
+        let body = body.map_or(Value::Null, |body| {
+            serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.to_string()))
+        });
+        Self {
+            path,
+            query,
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_ascii_lowercase(), v.clone()))
+                .collect(),
+            body,
+            uuid: Uuid::new_v4().to_string(),
+            now: Utc::now(),
+        }
+    }
+
+    /// Resolve a placeholder expression such as `request.path.id`
+    fn resolve(&self, expr: &str) -> Option<Value> {
+        match expr {
+            "uuid" => return Some(Value::String(self.uuid.clone())),
+            "now" => {
+                return Some(Value::String(
+                    self.now.to_rfc3339_opts(SecondsFormat::Millis, true),
+                ))
+            }
+            "request.body" => return Some(self.body.clone()),
+            _ => {}
+        }
+
+        let (source, name) = expr.strip_prefix("request.")?.split_once('.')?;
+        match source {
+            "path" => self.path.get(name).cloned().map(Value::String),
+            "query" => self.query.get(name).cloned().map(Value::String),
+            "headers" => self
+                .headers
+                .get(&name.to_ascii_lowercase())
+                .cloned()
+                .map(Value::String),
+            "body" => body_field(&self.body, name).cloned(),
+            _ => None,
+        }
+    }
+}
+
+/// Render every placeholder in `template`
+#[must_use]
+pub fn render_str(template: &str, ctx: &RequestContext) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let placeholder = &rest[start..start + 2 + len + 2];
+        out.push_str(&rest[..start]);
+        match ctx.resolve(placeholder[2..placeholder.len() - 2].trim()) {
+            Some(Value::String(value)) => out.push_str(&value),
+            Some(value) => out.push_str(&value.to_string()),
+            None => out.push_str(placeholder),
+        }
+        rest = &rest[start + placeholder.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Render placeholders in every string within `value`
+#[must_use]
+pub fn render_value(value: &Value, ctx: &RequestContext) -> Value {
+    match value {
+        Value::String(s) => sole_placeholder(s)
+            .and_then(|expr| ctx.resolve(expr))
+            .unwrap_or_else(|| Value::String(render_str(s, ctx))),
+        Value::Array(items) => Value::Array(items.iter().map(|v| render_value(v, ctx)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), render_value(v, ctx)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// The field of `body` at a dotted path such as `user.email` or `items.0`
+pub(crate) fn body_field<'a>(body: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(body, |value, key| match value {
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => value.get(key),
+    })
+}
+
+/// The expression of a string that is exactly one placeholder
+fn sole_placeholder(s: &str) -> Option<&str> {
+    let expr = s.strip_prefix("{{")?.strip_suffix("}}")?;
+    (!expr.contains("{{") && !expr.contains("}}")).then(|| expr.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> RequestContext {
+        let mut path = PathParams::new();
+        path.insert("id".to_string(), "42".to_string());
+        let headers: HashMap<String, String> =
+            [("X-Api-Key".to_string(), "secret".to_string())].into();
+        let uri: Uri = "/users/42?page=2&q=a%20b"
+            .parse()
+            .expect("Should parse uri");
+        RequestContext::new(
+            path,
+            &uri,
+            &headers,
+            Some(r#"{"email":"ada@example.com","tags":["a","b"],"count":3}"#),
+        )
+    }
+
+    #[test]
+    fn test_path_params_are_substituted() {
+        let body = serde_json::json!({
+            "id": "{{request.path.id}}",
+            "links": ["/users/{{ request.path.id }}/posts"],
+            "count": 3
+        });
+
+        let rendered = render_value(&body, &ctx());
+
+        assert_eq!(
+            rendered,
+            serde_json::json!({"id": "42", "links": ["/users/42/posts"], "count": 3})
+        );
+    }
+
+    #[test]
+    fn test_request_data_is_substituted() {
+        let ctx = ctx();
+        let body = serde_json::json!({
+            "email": "{{request.body.email}}",
+            "tag": "{{request.body.tags.1}}",
+            "count": "{{request.body.count}}",
+            "summary": "page {{request.query.page}} of '{{request.query.q}}'",
+            "key": "{{request.headers.x-api-key}}",
+            "echo": "{{request.body}}"
+        });
+
+        let rendered = render_value(&body, &ctx);
+
+        assert_eq!(rendered["email"], "ada@example.com");
+        assert_eq!(rendered["tag"], "b");
+        assert_eq!(rendered["count"], 3);
+        assert_eq!(rendered["summary"], "page 2 of 'a b'");
+        assert_eq!(rendered["key"], "secret");
+        assert_eq!(rendered["echo"], ctx.body);
+    }
+
+    #[test]
+    fn test_uuid_and_now_are_fixed_per_request() {
+        let ctx = ctx();
+
+        let location = render_str("/users/{{uuid}}", &ctx);
+        let body = render_value(
+            &serde_json::json!({"id": "{{uuid}}", "at": "{{now}}"}),
+            &ctx,
+        );
+
+        assert_eq!(
+            location,
+            format!("/users/{}", body["id"].as_str().unwrap_or(""))
+        );
+        assert!(Uuid::parse_str(&ctx.uuid).is_ok());
+        assert!(DateTime::parse_from_rfc3339(body["at"].as_str().unwrap_or("")).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_placeholders_are_kept() {
+        assert_eq!(
+            render_str("{{request.path.name}} {{request.body.missing}} {{x", &ctx()),
+            "{{request.path.name}} {{request.body.missing}} {{x"
+        );
+    }
+}
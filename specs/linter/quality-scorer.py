@@ -618,8 +618,9 @@ def main() -> int:
     )
     parser.add_argument(
         "--rules",
-        default="rules.yaml",
-        help="Path to rules YAML file (default: rules.yaml)",
+        default="../../crates/orchestrator/specs/linter/rules.yaml",
+        help="Path to rules YAML file, relative to this script "
+        "(default: the orchestrator crate's rules.yaml)",
     )
     parser.add_argument(
         "--json",
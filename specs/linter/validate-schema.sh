@@ -18,8 +18,9 @@
 set -euo pipefail
 
 SCRIPT_DIR="$(cd "$(dirname "${BASH_SOURCE[0]}")" && pwd)"
-SCHEMA_DIR="$(dirname "$SCRIPT_DIR")/schema"
-SCHEMA_FILE="$SCHEMA_DIR/spec.schema.json"
+# The schema and rules live with the orchestrator, which bundles them
+DATA_DIR="$(dirname "$(dirname "$SCRIPT_DIR")")/crates/orchestrator/specs"
+SCHEMA_FILE="$DATA_DIR/schema/spec.schema.json"
 
 # Colors for output
 RED='\033[0;31m'
@@ -173,7 +174,7 @@ get_validator() {
 run_quality_score() {
 	local yaml_file="$1"
 	local scorer_script="$SCRIPT_DIR/quality-scorer.py"
-	local rules_file="$SCRIPT_DIR/rules.yaml"
+	local rules_file="$DATA_DIR/linter/rules.yaml"
 
 	if [[ ! -f "$scorer_script" ]]; then
 		warning "Quality scorer not found: $scorer_script"